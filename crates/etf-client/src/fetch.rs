@@ -0,0 +1,367 @@
+//! Account fetch and deserialize helpers
+//!
+//! The SDK does not pick an RPC client; implement `AccountSource` for the
+//! one you use (a `get_account_data` call) and fetch typed accounts through
+//! it. `deserialize` works on raw account data from any source.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_spl::token::TokenAccount;
+use marinade_strategy::StrategyAccount;
+use vault::state::{
+    AssetOracles, AssetWhitelist, AuthorizedParticipant, BatchDeposit, BatchWithdrawal,
+    ChainlinkConfig, DcaSchedule, DepositBatch, DepositCommitment, DepositTicket,
+    LstBasket, MarketCapIndex, NavHistory, OracleFeeders, PriceCache, ProtocolConfig,
+    PythLazerConfig, QuoteConfig, RebalanceConfig, ReferralBalance, StakePool, StakePosition,
+    StrategyValue, SwitchboardConfig, TrackingError, UserPosition, Vault, VaultGuardian,
+    VaultLookupTable, VaultMetadata, VaultRegistration, VaultRegistry, VaultSchedule,
+    VaultTemplate, WithdrawalBatch,
+};
+use vault::MockPriceOracle;
+
+use crate::rpc::RpcClient;
+use crate::{pda, ClientError};
+
+/// Anything that can return an account's data
+pub trait AccountSource {
+    /// Data of the account at `address`, or None if it does not exist
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError>;
+}
+
+/// Deserialize an Anchor account (discriminator checked)
+pub fn deserialize<T: AccountDeserialize>(data: &[u8]) -> Result<T, ClientError> {
+    T::try_deserialize(&mut &data[..]).map_err(ClientError::Deserialize)
+}
+
+/// Fetch and deserialize the account at `address`
+pub fn fetch<T: AccountDeserialize>(
+    source: &impl AccountSource,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    let data = source
+        .account_data(address)?
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    deserialize(&data)
+}
+
+/// Like `fetch`, but None when the account does not exist yet
+pub fn fetch_optional<T: AccountDeserialize>(
+    source: &impl AccountSource,
+    address: &Pubkey,
+) -> Result<Option<T>, ClientError> {
+    source
+        .account_data(address)?
+        .map(|data| deserialize(&data))
+        .transpose()
+}
+
+pub fn fetch_vault(
+    source: &impl AccountSource,
+    admin: &Pubkey,
+    name: &str,
+) -> Result<Vault, ClientError> {
+    fetch(source, &pda::vault(admin, name))
+}
+
+pub fn fetch_user_position(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    user: &Pubkey,
+) -> Result<Option<UserPosition>, ClientError> {
+    fetch_optional(source, &pda::user_position(vault, user))
+}
+
+pub fn fetch_price_cache(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<PriceCache>, ClientError> {
+    fetch_optional(source, &pda::price_cache(vault))
+}
+
+/// None until the first sync_strategy_value or set_strategy_value_max_age
+pub fn fetch_strategy_value(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<StrategyValue>, ClientError> {
+    fetch_optional(source, &pda::strategy_value(vault))
+}
+
+/// None for vaults never given a quote currency (SOL-quoted)
+pub fn fetch_quote_config(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<QuoteConfig>, ClientError> {
+    fetch_optional(source, &pda::quote_config(vault))
+}
+
+/// None until the vault authority first calls set_max_turnover
+pub fn fetch_rebalance_config(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<RebalanceConfig>, ClientError> {
+    fetch_optional(source, &pda::rebalance_config(vault))
+}
+
+/// None until set_guardian is first called for the vault
+pub fn fetch_vault_guardian(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<VaultGuardian>, ClientError> {
+    fetch_optional(source, &pda::vault_guardian(vault))
+}
+
+pub fn fetch_nav_history(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<NavHistory>, ClientError> {
+    fetch_optional(source, &pda::nav_history(vault))
+}
+
+/// None until the vault's first snapshot_nav
+pub fn fetch_tracking_error(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<TrackingError>, ClientError> {
+    fetch_optional(source, &pda::tracking_error(vault))
+}
+
+pub fn fetch_mock_oracle(
+    source: &impl AccountSource,
+    address: &Pubkey,
+) -> Result<MockPriceOracle, ClientError> {
+    fetch(source, address)
+}
+
+/// None until the oracle authority first calls set_oracle_feeders
+pub fn fetch_oracle_feeders(
+    source: &impl AccountSource,
+    mock_oracle: &Pubkey,
+) -> Result<Option<OracleFeeders>, ClientError> {
+    fetch_optional(source, &pda::oracle_feeders(mock_oracle))
+}
+
+pub fn fetch_protocol_config(source: &impl AccountSource) -> Result<ProtocolConfig, ClientError> {
+    fetch(source, &pda::protocol_config())
+}
+
+/// None until the config authority has called set_asset_whitelist
+pub fn fetch_asset_whitelist(
+    source: &impl AccountSource,
+) -> Result<Option<AssetWhitelist>, ClientError> {
+    fetch_optional(source, &pda::asset_whitelist())
+}
+
+/// None until the config authority has called set_pyth_lazer_config
+pub fn fetch_pyth_lazer_config(
+    source: &impl AccountSource,
+) -> Result<Option<PythLazerConfig>, ClientError> {
+    fetch_optional(source, &pda::pyth_lazer_config())
+}
+
+/// None until the config authority has called set_chainlink_config
+pub fn fetch_chainlink_config(
+    source: &impl AccountSource,
+) -> Result<Option<ChainlinkConfig>, ClientError> {
+    fetch_optional(source, &pda::chainlink_config())
+}
+
+/// None until the config authority has called set_switchboard_config
+pub fn fetch_switchboard_config(
+    source: &impl AccountSource,
+) -> Result<Option<SwitchboardConfig>, ClientError> {
+    fetch_optional(source, &pda::switchboard_config())
+}
+
+pub fn fetch_vault_template(
+    source: &impl AccountSource,
+    name: &str,
+) -> Result<Option<VaultTemplate>, ClientError> {
+    fetch_optional(source, &pda::vault_template(name))
+}
+
+pub fn fetch_referral_balance(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    referrer: &Pubkey,
+) -> Result<Option<ReferralBalance>, ClientError> {
+    fetch_optional(source, &pda::referral_balance(vault, referrer))
+}
+
+/// None until the first vault is created
+pub fn fetch_vault_registry(
+    source: &impl AccountSource,
+) -> Result<Option<VaultRegistry>, ClientError> {
+    fetch_optional(source, &pda::vault_registry())
+}
+
+pub fn fetch_vault_registration(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<VaultRegistration>, ClientError> {
+    fetch_optional(source, &pda::vault_registration(vault))
+}
+
+/// Every registered vault, in registration order
+pub fn list_vault_registrations(rpc: &RpcClient) -> Result<Vec<VaultRegistration>, ClientError> {
+    let mut registrations = rpc
+        .get_program_accounts(&vault::ID, VaultRegistration::DISCRIMINATOR)?
+        .iter()
+        .map(|(_, data)| deserialize::<VaultRegistration>(data))
+        .collect::<Result<Vec<_>, _>>()?;
+    registrations.sort_by_key(|registration| registration.index);
+    Ok(registrations)
+}
+
+/// None until the vault authority has called set_vault_metadata
+pub fn fetch_vault_metadata(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<VaultMetadata>, ClientError> {
+    fetch_optional(source, &pda::vault_metadata(vault))
+}
+
+/// None until the vault authority has called create_lookup_table
+pub fn fetch_vault_lookup_table(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<VaultLookupTable>, ClientError> {
+    fetch_optional(source, &pda::vault_lookup_table(vault))
+}
+
+/// None until the vault authority has called configure_market_cap_index
+pub fn fetch_market_cap_index(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<MarketCapIndex>, ClientError> {
+    fetch_optional(source, &pda::market_cap_index(vault))
+}
+
+/// None until the vault authority has called set_composition_schedule
+pub fn fetch_vault_schedule(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<VaultSchedule>, ClientError> {
+    fetch_optional(source, &pda::vault_schedule(vault))
+}
+
+/// None until the vault authority has called configure_lst_basket
+pub fn fetch_lst_basket(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<LstBasket>, ClientError> {
+    fetch_optional(source, &pda::lst_basket(vault))
+}
+
+/// None until the vault authority has called configure_asset_oracles
+pub fn fetch_asset_oracles(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<AssetOracles>, ClientError> {
+    fetch_optional(source, &pda::asset_oracles(vault))
+}
+
+pub fn fetch_authorized_participant(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    participant: &Pubkey,
+) -> Result<Option<AuthorizedParticipant>, ClientError> {
+    fetch_optional(source, &pda::authorized_participant(vault, participant))
+}
+
+pub fn fetch_stake_pool(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<StakePool>, ClientError> {
+    fetch_optional(source, &pda::stake_pool(vault))
+}
+
+pub fn fetch_stake_position(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Option<StakePosition>, ClientError> {
+    fetch_optional(source, &pda::stake_position(&pda::stake_pool(vault), owner))
+}
+
+pub fn fetch_dca_schedule(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    user: &Pubkey,
+) -> Result<Option<DcaSchedule>, ClientError> {
+    fetch_optional(source, &pda::dca_schedule(vault, user))
+}
+
+pub fn fetch_deposit_commitment(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    user: &Pubkey,
+) -> Result<Option<DepositCommitment>, ClientError> {
+    fetch_optional(source, &pda::deposit_commitment(vault, user))
+}
+
+pub fn fetch_deposit_ticket(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    user: &Pubkey,
+) -> Result<Option<DepositTicket>, ClientError> {
+    fetch_optional(source, &pda::deposit_ticket(vault, user))
+}
+
+pub fn fetch_deposit_batch(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    epoch: u64,
+) -> Result<Option<DepositBatch>, ClientError> {
+    fetch_optional(source, &pda::deposit_batch(vault, epoch))
+}
+
+pub fn fetch_batch_deposit(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    epoch: u64,
+    user: &Pubkey,
+) -> Result<Option<BatchDeposit>, ClientError> {
+    fetch_optional(
+        source,
+        &pda::batch_deposit(&pda::deposit_batch(vault, epoch), user),
+    )
+}
+
+pub fn fetch_withdrawal_batch(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    epoch: u64,
+) -> Result<Option<WithdrawalBatch>, ClientError> {
+    fetch_optional(source, &pda::withdrawal_batch(vault, epoch))
+}
+
+pub fn fetch_batch_withdrawal(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+    epoch: u64,
+    user: &Pubkey,
+) -> Result<Option<BatchWithdrawal>, ClientError> {
+    fetch_optional(
+        source,
+        &pda::batch_withdrawal(&pda::withdrawal_batch(vault, epoch), user),
+    )
+}
+
+pub fn fetch_strategy(
+    source: &impl AccountSource,
+    vault: &Pubkey,
+) -> Result<Option<StrategyAccount>, ClientError> {
+    fetch_optional(source, &pda::marinade_strategy(vault))
+}
+
+/// Token balance of each vault asset ATA, in vault.assets order
+pub fn fetch_asset_balances(
+    source: &impl AccountSource,
+    vault: &Vault,
+) -> Result<Vec<u64>, ClientError> {
+    vault
+        .assets()
+        .iter()
+        .map(|asset| fetch::<TokenAccount>(source, &asset.ata).map(|account| account.amount))
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! Ed25519 keypairs in the Solana CLI file format
+//!
+//! Signing follows RFC 8032 on top of curve25519-dalek, which the Solana
+//! crates already pull in, so the SDK does not need a separate signer crate.
+
+use std::fmt;
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use curve25519_dalek::edwards::EdwardsPoint;
+use curve25519_dalek::scalar::{clamp_integer, Scalar};
+use sha2::{Digest, Sha512};
+
+use crate::ClientError;
+
+pub struct Keypair {
+    secret: [u8; 32],
+    pubkey: Pubkey,
+}
+
+impl Keypair {
+    /// Keypair from its 32-byte secret seed
+    pub fn from_seed(secret: [u8; 32]) -> Self {
+        let (scalar, _) = expand(&secret);
+        let pubkey = Pubkey::new_from_array(EdwardsPoint::mul_base(&scalar).compress().to_bytes());
+        Self { secret, pubkey }
+    }
+
+    /// Keypair from 64 bytes of secret seed followed by the public key
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ClientError> {
+        if bytes.len() != 64 {
+            return Err(ClientError::Keypair(format!(
+                "expected 64 bytes, got {}",
+                bytes.len()
+            )));
+        }
+        let keypair = Self::from_seed(bytes[..32].try_into().unwrap());
+        if keypair.pubkey.as_ref() != &bytes[32..] {
+            return Err(ClientError::Keypair(
+                "public key does not match secret".to_string(),
+            ));
+        }
+        Ok(keypair)
+    }
+
+    /// Read a keypair file written by `solana-keygen` (JSON array of 64 bytes)
+    pub fn read_file(path: impl AsRef<Path>) -> Result<Self, ClientError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| ClientError::Keypair(format!("{}: {}", path.display(), err)))?;
+        let bytes: Vec<u8> = serde_json::from_str(&contents)
+            .map_err(|err| ClientError::Keypair(format!("{}: {}", path.display(), err)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    /// Ed25519 signature of `message`
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        let (scalar, prefix) = expand(&self.secret);
+        let r = hash_to_scalar(&[&prefix, message]);
+        let big_r = EdwardsPoint::mul_base(&r).compress();
+        let k = hash_to_scalar(&[big_r.as_bytes(), self.pubkey.as_ref(), message]);
+        let s = r + k * scalar;
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(big_r.as_bytes());
+        signature[32..].copy_from_slice(s.as_bytes());
+        signature
+    }
+}
+
+/// Never print the secret
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("pubkey", &self.pubkey)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Secret scalar and nonce prefix derived from the seed
+fn expand(secret: &[u8; 32]) -> (Scalar, [u8; 32]) {
+    let hash: [u8; 64] = Sha512::digest(secret).into();
+    let scalar = Scalar::from_bytes_mod_order(clamp_integer(hash[..32].try_into().unwrap()));
+    (scalar, hash[32..].try_into().unwrap())
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rfc8032_vectors() {
+        // RFC 8032 section 7.1, tests 1 and 2
+        let cases = [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+        ];
+        for (secret, public, message, signature) in cases {
+            let keypair = Keypair::from_seed(hex(secret).try_into().unwrap());
+            assert_eq!(keypair.pubkey().to_bytes().to_vec(), hex(public));
+            assert_eq!(keypair.sign(&hex(message)).to_vec(), hex(signature));
+        }
+
+        let mut bytes = hex(cases[0].0);
+        bytes.extend(hex(cases[1].1));
+        assert!(matches!(
+            Keypair::from_bytes(&bytes),
+            Err(ClientError::Keypair(_))
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+//! Rust client SDK for the ETF-DeFi programs
+//!
+//! - `pda`: program derived addresses
+//! - `remaining`: remaining_accounts layouts for N-asset vaults
+//! - `marinade`: Marinade account lists (mainnet and the localnet mock)
+//! - `vault_ix` / `strategy_ix`: one instruction builder per instruction
+//! - `fetch`: typed account fetch and deserialize over any `AccountSource`
+//! - `lookup_table`: per-vault address lookup table contents for v0
+//!   transactions
+//! - `keypair` / `transaction` / `rpc`: signing, compute budget and a
+//!   blocking JSON-RPC client for the keeper and CLI
+
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+
+pub mod fetch;
+pub mod keypair;
+pub mod lookup_table;
+pub mod marinade;
+pub mod pda;
+pub mod remaining;
+pub mod rpc;
+pub mod strategy_ix;
+pub mod transaction;
+pub mod vault_ix;
+
+pub use marinade_strategy;
+pub use vault;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The account does not exist
+    AccountNotFound(Pubkey),
+    /// The account exists but is not the expected type
+    Deserialize(anchor_lang::error::Error),
+    /// The `AccountSource` failed to load an account
+    Source(String),
+    /// The vault prices from its MockOracle but none is set
+    MockOracleNotSet,
+    /// The vault prices per asset but its AssetOracles were not supplied
+    AssetOraclesNotSet,
+    /// JSON-RPC transport or server error
+    Rpc(String),
+    /// Invalid keypair, or a required signer is missing
+    Keypair(String),
+    /// The instructions cannot be compiled into a transaction message
+    Message(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::AccountNotFound(address) => write!(f, "account {} not found", address),
+            ClientError::Deserialize(err) => write!(f, "failed to deserialize account: {}", err),
+            ClientError::Source(err) => write!(f, "failed to load account: {}", err),
+            ClientError::MockOracleNotSet => write!(f, "vault has no MockOracle set"),
+            ClientError::AssetOraclesNotSet => {
+                write!(f, "vault prices per asset but has no AssetOracles")
+            }
+            ClientError::Rpc(err) => write!(f, "RPC error: {}", err),
+            ClientError::Keypair(err) => write!(f, "keypair error: {}", err),
+            ClientError::Message(err) => write!(f, "failed to compile message: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
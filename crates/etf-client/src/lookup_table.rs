@@ -0,0 +1,171 @@
+//! Vault address lookup tables
+//!
+//! `vault_addresses` lists every fixed account a deposit or withdrawal of the
+//! vault references; once they are in the vault's table (create_lookup_table
+//! and extend_lookup_table), `transaction::sign_v0_transaction` compresses
+//! each to a one-byte index, which keeps 5-10 asset vaults under the
+//! transaction size limit.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::address_lookup_table::state::LOOKUP_TABLE_META_SIZE;
+use anchor_lang::solana_program::message::AddressLookupTableAccount;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use vault::state::Vault;
+
+use crate::fetch::AccountSource;
+use crate::marinade::MarinadeAccounts;
+use crate::remaining::{self, PriceInput};
+use crate::vault_ix::{strategy_msol_ata, VaultId};
+use crate::{pda, ClientError};
+
+/// Accounts deposit and withdraw pass for `vault`, without user accounts,
+/// quotes or swap backend accounts; duplicates removed
+pub fn vault_addresses(
+    vault: &Vault,
+    marinade: &MarinadeAccounts,
+) -> Result<Vec<Pubkey>, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    let mut addresses = vec![
+        vault::ID,
+        vault_address,
+        vault.vault_token_mint,
+        vault.share_token_program(),
+        pda::event_authority(),
+        pda::price_cache(&vault_address),
+        anchor_spl::token::ID,
+        anchor_spl::associated_token::ID,
+        system_program::ID,
+        sysvar::clock::ID,
+        sysvar::rent::ID,
+    ];
+    if let Some(token_2022_program) = remaining::token_2022_program(vault) {
+        addresses.push(token_2022_program);
+    }
+    for asset in vault.assets() {
+        addresses.extend([asset.mint, asset.ata]);
+    }
+    addresses.extend(
+        remaining::price_accounts(vault, PriceInput::Oracle)?
+            .into_iter()
+            .map(|meta| meta.pubkey),
+    );
+    if let Some(strategy) = vault.marinade_strategy() {
+        addresses.extend([
+            strategy,
+            marinade_strategy::ID,
+            marinade.program,
+            marinade.state,
+            marinade.reserve_pda,
+            marinade.msol_mint,
+            strategy_msol_ata(&vault_address, marinade),
+            marinade.msol_mint_authority,
+            marinade.liq_pool_sol_leg_pda,
+            marinade.liq_pool_msol_leg,
+            marinade.liq_pool_msol_leg_authority,
+        ]);
+    }
+
+    let mut unique = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        if !unique.contains(&address) {
+            unique.push(address);
+        }
+    }
+    Ok(unique)
+}
+
+/// A user's share ATA and position, worth adding for frequent depositors
+pub fn user_addresses(vault: &Vault, user: &Pubkey) -> Vec<Pubkey> {
+    let id = VaultId::of(vault);
+    let vault_address = id.address();
+    vec![id.share_ata(user), pda::user_position(&vault_address, user)]
+}
+
+/// Addresses stored in a lookup table account's data
+pub fn parse_table(data: &[u8]) -> Result<Vec<Pubkey>, ClientError> {
+    let keys = data
+        .get(LOOKUP_TABLE_META_SIZE..)
+        .filter(|keys| keys.len() % 32 == 0)
+        .ok_or_else(|| ClientError::Source("malformed lookup table account".to_string()))?;
+    Ok(keys
+        .chunks_exact(32)
+        .map(|key| Pubkey::try_from(key).expect("32-byte chunk"))
+        .collect())
+}
+
+/// `wanted` addresses not yet in `existing`, in order
+pub fn missing_addresses(wanted: &[Pubkey], existing: &[Pubkey]) -> Vec<Pubkey> {
+    wanted
+        .iter()
+        .filter(|address| !existing.contains(address))
+        .copied()
+        .collect()
+}
+
+/// The vault's lookup table with its current contents, for
+/// `transaction::sign_v0_transaction`; None until create_lookup_table
+pub fn fetch_lookup_table(
+    source: &impl AccountSource,
+    vault_address: &Pubkey,
+) -> Result<Option<AddressLookupTableAccount>, ClientError> {
+    let Some(record) = crate::fetch::fetch_vault_lookup_table(source, vault_address)? else {
+        return Ok(None);
+    };
+    let data = source
+        .account_data(&record.lookup_table)?
+        .ok_or(ClientError::AccountNotFound(record.lookup_table))?;
+    Ok(Some(AddressLookupTableAccount {
+        key: record.lookup_table,
+        addresses: parse_table(&data)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remaining::tests::test_vault;
+    use vault::state::AssetConfig;
+    use vault::PriceSource;
+
+    #[test]
+    fn test_vault_addresses_dedup_and_strategy() {
+        let mut vault = test_vault(PriceSource::MockOracle);
+        let assets: Vec<AssetConfig> = (0..8)
+            .map(|_| AssetConfig {
+                mint: Pubkey::new_unique(),
+                weight: 12,
+                ata: Pubkey::new_unique(),
+            })
+            .collect();
+        vault.set_assets(&assets).unwrap();
+
+        let marinade = MarinadeAccounts::mock();
+        let without_strategy = vault_addresses(&vault, &marinade).unwrap();
+        // 10 fixed accounts, 2 per asset, the oracle and the oracle adapter
+        assert_eq!(without_strategy.len(), 10 + 16 + 2);
+
+        let strategy = pda::marinade_strategy(&VaultId::of(&vault).address());
+        vault.set_marinade_strategy(Some(strategy));
+        let with_strategy = vault_addresses(&vault, &marinade).unwrap();
+        // mock Marinade shares accounts between roles
+        assert!(with_strategy.len() > without_strategy.len());
+        for (i, address) in with_strategy.iter().enumerate() {
+            assert!(!with_strategy[i + 1..].contains(address));
+        }
+    }
+
+    #[test]
+    fn test_parse_table_and_missing() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let mut data = vec![0u8; LOOKUP_TABLE_META_SIZE];
+        data.extend_from_slice(a.as_ref());
+        data.extend_from_slice(b.as_ref());
+        assert_eq!(parse_table(&data).unwrap(), vec![a, b]);
+        assert!(parse_table(&data[..LOOKUP_TABLE_META_SIZE + 5]).is_err());
+
+        let c = Pubkey::new_unique();
+        assert_eq!(missing_addresses(&[a, c, b], &[a, b]), vec![c]);
+    }
+}
@@ -0,0 +1,93 @@
+//! Marinade account lists passed through the vault to marinade_strategy
+//!
+//! Deposit and withdraw always take the Marinade accounts (they are only
+//! used when the vault has a strategy set), so builders need a full set even
+//! for vaults without one.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::pubkey;
+
+/// Marinade Finance program (mainnet and devnet)
+pub const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+/// Marinade state account
+pub const MARINADE_STATE: Pubkey = pubkey!("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC");
+/// mSOL mint
+pub const MSOL_MINT: Pubkey = pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
+/// Liquidity pool mSOL leg, as recorded in the Marinade state
+pub const LIQ_POOL_MSOL_LEG: Pubkey = pubkey!("7GgPYjS5Dza89wV6FpZ23kUJRG5vbQ1GM25ezspYFSoE");
+/// Treasury mSOL account, as recorded in the Marinade state
+pub const TREASURY_MSOL_ACCOUNT: Pubkey = pubkey!("B1aLzaNMeFVAyQ6f3XbbUyKcH2YPHu2fqiEagmiF23VR");
+
+/// Every Marinade account a deposit or withdrawal can touch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MarinadeAccounts {
+    pub program: Pubkey,
+    pub state: Pubkey,
+    pub reserve_pda: Pubkey,
+    pub msol_mint: Pubkey,
+    pub msol_mint_authority: Pubkey,
+    pub liq_pool_sol_leg_pda: Pubkey,
+    pub liq_pool_msol_leg: Pubkey,
+    pub liq_pool_msol_leg_authority: Pubkey,
+    pub treasury_msol_account: Pubkey,
+}
+
+impl MarinadeAccounts {
+    /// Accounts of a Marinade deployment; PDAs are derived from `state`,
+    /// the mSOL leg and treasury must be read from the state account
+    pub fn from_state(
+        program: Pubkey,
+        state: Pubkey,
+        msol_mint: Pubkey,
+        liq_pool_msol_leg: Pubkey,
+        treasury_msol_account: Pubkey,
+    ) -> Self {
+        let derive =
+            |seed: &[u8]| Pubkey::find_program_address(&[state.as_ref(), seed], &program).0;
+        Self {
+            program,
+            state,
+            reserve_pda: derive(b"reserve"),
+            msol_mint,
+            msol_mint_authority: derive(b"st_mint"),
+            liq_pool_sol_leg_pda: derive(b"liq_sol"),
+            liq_pool_msol_leg,
+            liq_pool_msol_leg_authority: derive(b"liq_st_sol_authority"),
+            treasury_msol_account,
+        }
+    }
+
+    /// The live Marinade deployment (same addresses on mainnet and devnet)
+    pub fn mainnet() -> Self {
+        Self::from_state(
+            MARINADE_PROGRAM_ID,
+            MARINADE_STATE,
+            MSOL_MINT,
+            LIQ_POOL_MSOL_LEG,
+            TREASURY_MSOL_ACCOUNT,
+        )
+    }
+
+    /// The mock_marinade program used on localnet
+    ///
+    /// The mock has no reserve or treasury, so the SOL leg and mSOL leg
+    /// stand in for them.
+    pub fn mock() -> Self {
+        let program = mock_marinade::ID;
+        let derive = |seed: &[u8]| Pubkey::find_program_address(&[seed], &program).0;
+        let state = derive(b"state");
+        let liq_pool_sol_leg_pda = derive(b"liq_sol");
+        let liq_pool_msol_leg = derive(b"liq_msol");
+        Self {
+            program,
+            state,
+            reserve_pda: liq_pool_sol_leg_pda,
+            msol_mint: derive(b"msol_mint"),
+            msol_mint_authority: derive(b"msol_mint_authority"),
+            liq_pool_sol_leg_pda,
+            liq_pool_msol_leg,
+            liq_pool_msol_leg_authority: state,
+            treasury_msol_account: liq_pool_msol_leg,
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Program derived addresses of the vault and strategy programs
+//!
+//! Seeds mirror the `seeds = [...]` constraints of the on-chain account
+//! structs; every function returns the address only (bumps are stored in
+//! the accounts themselves).
+
+use anchor_lang::prelude::Pubkey;
+
+fn find(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+/// Vault PDA: [b"vault", admin, name]
+pub fn vault(admin: &Pubkey, name: &str) -> Pubkey {
+    find(&[b"vault", admin.as_ref(), name.as_bytes()], &vault::ID)
+}
+
+/// Share mint PDA: [b"vault_mint", admin, name]
+pub fn vault_mint(admin: &Pubkey, name: &str) -> Pubkey {
+    find(
+        &[b"vault_mint", admin.as_ref(), name.as_bytes()],
+        &vault::ID,
+    )
+}
+
+/// UserPosition PDA: [b"user_position", vault, user]
+pub fn user_position(vault: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(
+        &[b"user_position", vault.as_ref(), user.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// PriceCache PDA: [b"price_cache", vault]
+pub fn price_cache(vault: &Pubkey) -> Pubkey {
+    find(&[b"price_cache", vault.as_ref()], &vault::ID)
+}
+
+/// NavHistory PDA: [b"nav_history", vault]
+pub fn nav_history(vault: &Pubkey) -> Pubkey {
+    find(&[b"nav_history", vault.as_ref()], &vault::ID)
+}
+
+/// TrackingError PDA: [b"tracking_error", vault]
+pub fn tracking_error(vault: &Pubkey) -> Pubkey {
+    find(&[b"tracking_error", vault.as_ref()], &vault::ID)
+}
+
+/// MockPriceOracle PDA: [b"mock_oracle", authority]
+pub fn mock_oracle(authority: &Pubkey) -> Pubkey {
+    find(&[b"mock_oracle", authority.as_ref()], &vault::ID)
+}
+
+/// OracleFeeders PDA: [b"oracle_feeders", mock_oracle]
+pub fn oracle_feeders(mock_oracle: &Pubkey) -> Pubkey {
+    find(&[b"oracle_feeders", mock_oracle.as_ref()], &vault::ID)
+}
+
+/// ProtocolConfig PDA: [b"protocol_config"]
+pub fn protocol_config() -> Pubkey {
+    find(&[b"protocol_config"], &vault::ID)
+}
+
+/// AssetWhitelist PDA: [b"asset_whitelist"]
+pub fn asset_whitelist() -> Pubkey {
+    find(&[b"asset_whitelist"], &vault::ID)
+}
+
+/// PythLazerConfig PDA: [b"pyth_lazer_config"]
+pub fn pyth_lazer_config() -> Pubkey {
+    find(&[b"pyth_lazer_config"], &vault::ID)
+}
+
+/// SwitchboardConfig PDA: [b"switchboard_config"]
+pub fn switchboard_config() -> Pubkey {
+    find(&[b"switchboard_config"], &vault::ID)
+}
+
+/// ChainlinkConfig PDA: [b"chainlink_config"]
+pub fn chainlink_config() -> Pubkey {
+    find(&[b"chainlink_config"], &vault::ID)
+}
+
+/// VaultTemplate PDA: [b"vault_template", name]
+pub fn vault_template(name: &str) -> Pubkey {
+    find(&[b"vault_template", name.as_bytes()], &vault::ID)
+}
+
+/// ReferralBalance PDA: [b"referral", vault, referrer]
+pub fn referral_balance(vault: &Pubkey, referrer: &Pubkey) -> Pubkey {
+    find(
+        &[b"referral", vault.as_ref(), referrer.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// StakePool PDA: [b"stake_pool", vault]
+pub fn stake_pool(vault: &Pubkey) -> Pubkey {
+    find(&[b"stake_pool", vault.as_ref()], &vault::ID)
+}
+
+/// StakePosition PDA: [b"stake_position", stake_pool, owner]
+pub fn stake_position(stake_pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(
+        &[b"stake_position", stake_pool.as_ref(), owner.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// VaultRegistry PDA: [b"vault_registry"]
+pub fn vault_registry() -> Pubkey {
+    find(&[b"vault_registry"], &vault::ID)
+}
+
+/// VaultRegistration PDA: [b"vault_registration", vault]
+pub fn vault_registration(vault: &Pubkey) -> Pubkey {
+    find(&[b"vault_registration", vault.as_ref()], &vault::ID)
+}
+
+/// VaultMetadata PDA: [b"vault_metadata", vault]
+pub fn vault_metadata(vault: &Pubkey) -> Pubkey {
+    find(&[b"vault_metadata", vault.as_ref()], &vault::ID)
+}
+
+/// VaultLookupTable PDA: [b"vault_lookup_table", vault]
+pub fn vault_lookup_table(vault: &Pubkey) -> Pubkey {
+    find(&[b"vault_lookup_table", vault.as_ref()], &vault::ID)
+}
+
+/// MarketCapIndex PDA: [b"market_cap_index", vault]
+pub fn market_cap_index(vault: &Pubkey) -> Pubkey {
+    find(&[b"market_cap_index", vault.as_ref()], &vault::ID)
+}
+
+/// VaultSchedule PDA: [b"vault_schedule", vault]
+pub fn vault_schedule(vault: &Pubkey) -> Pubkey {
+    find(&[b"vault_schedule", vault.as_ref()], &vault::ID)
+}
+
+/// LstBasket PDA: [b"lst_basket", vault]
+pub fn lst_basket(vault: &Pubkey) -> Pubkey {
+    find(&[b"lst_basket", vault.as_ref()], &vault::ID)
+}
+
+/// AssetOracles PDA: [b"asset_oracles", vault]
+pub fn asset_oracles(vault: &Pubkey) -> Pubkey {
+    find(&[b"asset_oracles", vault.as_ref()], &vault::ID)
+}
+
+/// DcaSchedule PDA: [b"dca", vault, user]
+pub fn dca_schedule(vault: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[b"dca", vault.as_ref(), user.as_ref()], &vault::ID)
+}
+
+/// DepositBatch PDA: [b"deposit_batch", vault, epoch]
+pub fn deposit_batch(vault: &Pubkey, epoch: u64) -> Pubkey {
+    find(
+        &[b"deposit_batch", vault.as_ref(), &epoch.to_le_bytes()],
+        &vault::ID,
+    )
+}
+
+/// BatchDeposit PDA: [b"batch_deposit", batch, user]
+pub fn batch_deposit(batch: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(
+        &[b"batch_deposit", batch.as_ref(), user.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// WithdrawalBatch PDA: [b"withdrawal_batch", vault, epoch]
+pub fn withdrawal_batch(vault: &Pubkey, epoch: u64) -> Pubkey {
+    find(
+        &[b"withdrawal_batch", vault.as_ref(), &epoch.to_le_bytes()],
+        &vault::ID,
+    )
+}
+
+/// BatchWithdrawal PDA: [b"batch_withdrawal", batch, user]
+pub fn batch_withdrawal(batch: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(
+        &[b"batch_withdrawal", batch.as_ref(), user.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// DepositTicket PDA: [b"deposit_ticket", vault, user]
+pub fn deposit_ticket(vault: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(
+        &[b"deposit_ticket", vault.as_ref(), user.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// DepositCommitment PDA: [b"deposit_commitment", vault, user]
+pub fn deposit_commitment(vault: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(
+        &[b"deposit_commitment", vault.as_ref(), user.as_ref()],
+        &vault::ID,
+    )
+}
+
+/// AuthorizedParticipant PDA: [b"authorized_participant", vault, participant]
+pub fn authorized_participant(vault: &Pubkey, participant: &Pubkey) -> Pubkey {
+    find(
+        &[
+            b"authorized_participant",
+            vault.as_ref(),
+            participant.as_ref(),
+        ],
+        &vault::ID,
+    )
+}
+
+/// Metaplex metadata PDA of a share mint: [b"metadata", metadata program, mint]
+pub fn share_metadata(share_mint: &Pubkey) -> Pubkey {
+    find(
+        &[
+            b"metadata",
+            vault::TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            share_mint.as_ref(),
+        ],
+        &vault::TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+/// Authority that signs the vault's `emit_cpi!` self-invocations
+pub fn event_authority() -> Pubkey {
+    find(&[b"__event_authority"], &vault::ID)
+}
+
+/// System-owned SOL buffer PDA: [b"sol_buffer", vault]
+pub fn sol_buffer(vault: &Pubkey) -> Pubkey {
+    find(&[b"sol_buffer", vault.as_ref()], &vault::ID)
+}
+
+/// StrategyValue PDA: [b"strategy_value", vault]
+pub fn strategy_value(vault: &Pubkey) -> Pubkey {
+    find(&[b"strategy_value", vault.as_ref()], &vault::ID)
+}
+
+/// QuoteConfig PDA: [b"quote_config", vault]
+pub fn quote_config(vault: &Pubkey) -> Pubkey {
+    find(&[b"quote_config", vault.as_ref()], &vault::ID)
+}
+
+/// RebalanceConfig PDA: [b"rebalance_config", vault]
+pub fn rebalance_config(vault: &Pubkey) -> Pubkey {
+    find(&[b"rebalance_config", vault.as_ref()], &vault::ID)
+}
+
+/// SealedTargets PDA of a hidden-weights vault:
+/// [b"sealed_targets", vault] (rebalancing MXE program)
+pub fn sealed_targets(vault: &Pubkey) -> Pubkey {
+    find(&[vault::SEALED_TARGETS_SEED, vault.as_ref()], &vault::REBALANCING_MXE_PROGRAM_ID)
+}
+
+/// RebalanceResult PDA of an Arcium computation:
+/// [b"rebalance_result", computation_offset (le)] (rebalancing MXE program)
+pub fn rebalance_result(computation_offset: u64) -> Pubkey {
+    find(
+        &[vault::REBALANCE_RESULT_SEED, &computation_offset.to_le_bytes()],
+        &vault::REBALANCING_MXE_PROGRAM_ID,
+    )
+}
+
+/// VaultGuardian PDA: [b"vault_guardian", vault]
+pub fn vault_guardian(vault: &Pubkey) -> Pubkey {
+    find(&[b"vault_guardian", vault.as_ref()], &vault::ID)
+}
+
+/// Chainlink verifier state PDA: [b"verifier"] (verifier program)
+pub fn chainlink_verifier() -> Pubkey {
+    find(&[b"verifier"], &vault::chainlink::VERIFIER_PROGRAM_ID)
+}
+
+/// Chainlink verifier config of the DON that signed a report:
+/// [config_digest] (verifier program), the digest being the report's first
+/// 32 bytes
+pub fn chainlink_report_config(config_digest: &[u8; 32]) -> Pubkey {
+    find(&[config_digest], &vault::chainlink::VERIFIER_PROGRAM_ID)
+}
+
+/// Marinade StrategyAccount PDA: [b"marinade_strategy", vault]
+pub fn marinade_strategy(vault: &Pubkey) -> Pubkey {
+    find(
+        &[b"marinade_strategy", vault.as_ref()],
+        &marinade_strategy::ID,
+    )
+}
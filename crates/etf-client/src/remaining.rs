@@ -0,0 +1,491 @@
+//! remaining_accounts layouts
+//!
+//! The vault passes its variable-length account lists (one entry per asset,
+//! price accounts, strategy, swap backend) as remaining_accounts. The
+//! order is checked on-chain, so every builder assembles it here:
+//!
+//! - create_vault: [mint, vault ATA] per asset
+//! - deposit / withdraw: [mint, vault ATA] per asset, price accounts
+//!   (StrategyValue last if a strategy is set), strategy account (if set),
+//!   swap backend accounts; per-asset priced vaults pass their AssetOracles,
+//!   each asset's feed and the SOL feed as the price accounts
+//! - deposit_zap: [mint, vault ATA] per asset, price accounts, swap backend
+//!   accounts (input -> SOL first, then the allocation swaps)
+//! - withdraw_zap: as deposit_zap, with swaps BTC -> SOL, ETH -> SOL, SOL -> output
+//! - execute_dca: as deposit_zap (no input swap for SOL schedules)
+//! - deposit_single_asset / deposit_msol / deposit_prepare: [mint, vault ATA] per asset, price accounts
+//! - deposit_execute: [mint, vault ATA] per asset, swap backend accounts for
+//!   the assets this call allocates
+//! - create_units / redeem_units / redeem_wind_down: [holder ATA, vault ATA, mint] per asset
+//! - close_vault: [vault ATA, dust receiver ATA, mint] per asset
+//!
+//! Basket assets may be SPL Token or Token-2022 mints; holder ATAs are
+//! derived under the same token program as the vault's ATA.
+//! - snapshot_nav and the view instructions: [mint, vault ATA] per asset, price accounts
+//! - refresh_prices: oracle price accounts
+//! - recompute_weights: price accounts
+//! - configure_lst_basket / refresh_lst_rates: pool account per asset
+//! - rebalance: oracle price accounts, vault ATA per asset, swap backend accounts
+//! - apply_confidential_rebalance: MockOracle, vault ATA per asset, swap backend accounts
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use vault::state::AssetConfig;
+use vault::state::{AssetOracles, Vault};
+use vault::PriceSource;
+
+use crate::vault_ix::VaultId;
+use crate::{pda, ClientError};
+
+/// Where an instruction that accepts the PriceCache reads prices from
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PriceInput {
+    /// The vault's configured price source
+    #[default]
+    Oracle,
+    /// The vault's PriceCache, kept fresh by refresh_prices
+    Cache,
+}
+
+/// [mint, vault ATA] for each asset, in vault.assets order
+pub fn asset_accounts(vault: &Vault) -> Vec<AccountMeta> {
+    vault
+        .assets()
+        .iter()
+        .flat_map(|asset| {
+            [
+                AccountMeta::new_readonly(asset.mint, false),
+                AccountMeta::new(asset.ata, false),
+            ]
+        })
+        .collect()
+}
+
+/// [mint, ATA of `vault_address`] for each (mint, token program), as
+/// create_vault expects
+pub fn create_vault_accounts(
+    vault_address: &Pubkey,
+    mints: &[(Pubkey, Pubkey)],
+) -> Vec<AccountMeta> {
+    mints
+        .iter()
+        .flat_map(|(mint, token_program)| {
+            [
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new(
+                    get_associated_token_address_with_program_id(
+                        vault_address,
+                        mint,
+                        token_program,
+                    ),
+                    false,
+                ),
+            ]
+        })
+        .collect()
+}
+
+/// Token program of an asset, read off the address of the vault's ATA
+pub fn asset_token_program(vault: &Vault, asset: &AssetConfig) -> Pubkey {
+    let token_2022_ata = get_associated_token_address_with_program_id(
+        &VaultId::of(vault).address(),
+        &asset.mint,
+        &anchor_spl::token_2022::ID,
+    );
+    if asset.ata == token_2022_ata {
+        anchor_spl::token_2022::ID
+    } else {
+        anchor_spl::token::ID
+    }
+}
+
+/// The Token-2022 program if any asset needs it, for the optional
+/// `token_2022_program` account
+pub fn token_2022_program(vault: &Vault) -> Option<Pubkey> {
+    vault
+        .assets()
+        .iter()
+        .any(|asset| asset_token_program(vault, asset) == anchor_spl::token_2022::ID)
+        .then_some(anchor_spl::token_2022::ID)
+}
+
+/// ATA of `owner` for an asset, under the asset's token program
+pub fn holder_ata(vault: &Vault, asset: &AssetConfig, owner: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(
+        owner,
+        &asset.mint,
+        &asset_token_program(vault, asset),
+    )
+}
+
+/// Vault ATA for each asset, in vault.assets order
+pub fn asset_atas(vault: &Vault) -> Vec<AccountMeta> {
+    vault
+        .assets()
+        .iter()
+        .map(|asset| AccountMeta::new(asset.ata, false))
+        .collect()
+}
+
+/// [participant ATA, vault ATA, mint] for each asset, as create_units and
+/// redeem_units expect (the SOL slot's ATA is unused while the vault holds
+/// native SOL)
+pub fn in_kind_accounts(vault: &Vault, participant: &Pubkey) -> Vec<AccountMeta> {
+    vault
+        .assets()
+        .iter()
+        .flat_map(|asset| {
+            [
+                AccountMeta::new(holder_ata(vault, asset, participant), false),
+                AccountMeta::new(asset.ata, false),
+                AccountMeta::new_readonly(asset.mint, false),
+            ]
+        })
+        .collect()
+}
+
+/// [vault ATA, ATA of `receiver`, mint] for each asset, as close_vault expects
+pub fn close_vault_accounts(vault: &Vault, receiver: &Pubkey) -> Vec<AccountMeta> {
+    vault
+        .assets()
+        .iter()
+        .flat_map(|asset| {
+            [
+                AccountMeta::new(asset.ata, false),
+                AccountMeta::new(holder_ata(vault, asset, receiver), false),
+                AccountMeta::new_readonly(asset.mint, false),
+            ]
+        })
+        .collect()
+}
+
+/// Price accounts read by the vault's configured source
+///
+/// MockOracle: the mock oracle; Switchboard: the SwitchboardConfig (quotes are
+/// named accounts); CrossChecked: both; Aggregated: each distinct feed
+/// account; PythLazer: the PythLazerConfig and instructions sysvar;
+/// Chainlink: the ChainlinkConfig. Every source but Chainlink adds the oracle
+/// adapter program, which parses it; LST indexes add their LstBasket.
+pub fn oracle_accounts(vault: &Vault) -> Result<Vec<AccountMeta>, ClientError> {
+    let mut accounts = match vault.price_source() {
+        PriceSource::MockOracle => vec![AccountMeta::new_readonly(mock_oracle(vault)?, false)],
+        PriceSource::Switchboard => {
+            vec![AccountMeta::new_readonly(pda::switchboard_config(), false)]
+        }
+        PriceSource::CrossChecked => vec![
+            AccountMeta::new_readonly(mock_oracle(vault)?, false),
+            AccountMeta::new_readonly(pda::switchboard_config(), false),
+        ],
+        PriceSource::Chainlink => vec![AccountMeta::new_readonly(pda::chainlink_config(), false)],
+        PriceSource::PythLazer => vec![
+            AccountMeta::new_readonly(pda::pyth_lazer_config(), false),
+            AccountMeta::new_readonly(sysvar::instructions::ID, false),
+        ],
+        PriceSource::Aggregated => vault::aggregated_feed_accounts(vault)
+            .into_iter()
+            .map(|feed| AccountMeta::new_readonly(feed, false))
+            .collect(),
+    };
+    if vault.price_source() != PriceSource::Chainlink {
+        accounts.push(AccountMeta::new_readonly(oracle_adapter::ID, false));
+    }
+    if vault.lst_index() {
+        let vault_address = pda::vault(&vault.admin, vault.name());
+        accounts.push(AccountMeta::new_readonly(
+            pda::lst_basket(&vault_address),
+            false,
+        ));
+    }
+    Ok(accounts)
+}
+
+/// Price accounts for deposit, withdraw and snapshot_nav
+///
+/// Vaults with a staking strategy add their StrategyValue (writable, since
+/// deposits and withdrawals adjust it).
+pub fn price_accounts(vault: &Vault, input: PriceInput) -> Result<Vec<AccountMeta>, ClientError> {
+    let vault_address = pda::vault(&vault.admin, vault.name());
+    let mut accounts = match input {
+        PriceInput::Oracle => oracle_accounts(vault)?,
+        PriceInput::Cache => vec![AccountMeta::new_readonly(
+            pda::price_cache(&vault_address),
+            false,
+        )],
+    };
+    if vault.marinade_strategy().is_some() {
+        accounts.push(AccountMeta::new(pda::strategy_value(&vault_address), false));
+    }
+    Ok(accounts)
+}
+
+/// Price accounts of a per-asset priced vault: its AssetOracles, the feed
+/// of each asset, the SOL feed, the oracle adapter program, then the
+/// StrategyValue if a strategy is set
+pub fn basket_price_accounts(vault: &Vault, oracles: &AssetOracles) -> Vec<AccountMeta> {
+    let vault_address = pda::vault(&vault.admin, vault.name());
+    let mut accounts = vec![AccountMeta::new_readonly(
+        pda::asset_oracles(&vault_address),
+        false,
+    )];
+    accounts.extend(
+        oracles
+            .feeds
+            .iter()
+            .chain([&oracles.sol_feed])
+            .map(|feed| AccountMeta::new_readonly(feed.account, false)),
+    );
+    accounts.push(AccountMeta::new_readonly(oracle_adapter::ID, false));
+    if vault.marinade_strategy().is_some() {
+        accounts.push(AccountMeta::new(pda::strategy_value(&vault_address), false));
+    }
+    accounts
+}
+
+/// Full remaining_accounts for deposit_multi_asset and withdraw_multi_asset
+///
+/// Per-asset priced vaults need their fetched `asset_oracles`; `input` is
+/// not used for them.
+pub fn deposit_withdraw_accounts(
+    vault: &Vault,
+    input: PriceInput,
+    asset_oracles: Option<&AssetOracles>,
+    swap_accounts: &[AccountMeta],
+) -> Result<Vec<AccountMeta>, ClientError> {
+    let mut accounts = asset_accounts(vault);
+    if vault.per_asset_pricing() {
+        let oracles = asset_oracles.ok_or(ClientError::AssetOraclesNotSet)?;
+        accounts.extend(basket_price_accounts(vault, oracles));
+    } else {
+        accounts.extend(price_accounts(vault, input)?);
+    }
+    if let Some(strategy) = vault.marinade_strategy() {
+        accounts.push(AccountMeta::new(strategy, false));
+    }
+    accounts.extend_from_slice(swap_accounts);
+    Ok(accounts)
+}
+
+/// Full remaining_accounts for deposit_zap, withdraw_zap and execute_dca
+pub fn zap_accounts(
+    vault: &Vault,
+    input: PriceInput,
+    swap_accounts: &[AccountMeta],
+) -> Result<Vec<AccountMeta>, ClientError> {
+    let mut accounts = snapshot_nav_accounts(vault, input)?;
+    accounts.extend_from_slice(swap_accounts);
+    Ok(accounts)
+}
+
+/// Full remaining_accounts for deposit_execute
+pub fn deposit_execute_accounts(vault: &Vault, swap_accounts: &[AccountMeta]) -> Vec<AccountMeta> {
+    let mut accounts = asset_accounts(vault);
+    accounts.extend_from_slice(swap_accounts);
+    accounts
+}
+
+/// Full remaining_accounts for snapshot_nav, get_vault_stats, the previews,
+/// deposit_single_asset, deposit_msol and deposit_prepare
+pub fn snapshot_nav_accounts(
+    vault: &Vault,
+    input: PriceInput,
+) -> Result<Vec<AccountMeta>, ClientError> {
+    let mut accounts = asset_accounts(vault);
+    accounts.extend(price_accounts(vault, input)?);
+    Ok(accounts)
+}
+
+/// Full remaining_accounts for rebalance
+pub fn rebalance_accounts(
+    vault: &Vault,
+    swap_accounts: &[AccountMeta],
+) -> Result<Vec<AccountMeta>, ClientError> {
+    let mut accounts = oracle_accounts(vault)?;
+    accounts.extend(asset_atas(vault));
+    accounts.extend_from_slice(swap_accounts);
+    Ok(accounts)
+}
+
+/// Full remaining_accounts for apply_confidential_rebalance
+pub fn confidential_rebalance_accounts(
+    vault: &Vault,
+    swap_accounts: &[AccountMeta],
+) -> Result<Vec<AccountMeta>, ClientError> {
+    let mut accounts = vec![AccountMeta::new_readonly(mock_oracle(vault)?, false)];
+    accounts.extend(asset_atas(vault));
+    accounts.extend_from_slice(swap_accounts);
+    Ok(accounts)
+}
+
+/// Swap backend accounts for one MockAmm pool hop (see MOCK_AMM_ACCOUNTS_PER_HOP);
+/// token-to-token swaps need a hop for each token, in swap order
+pub fn mock_amm_hop(vault_address: &Pubkey, token_mint: &Pubkey) -> Vec<AccountMeta> {
+    let pool = Pubkey::find_program_address(&[b"pool", token_mint.as_ref()], &mock_amm::ID).0;
+    vec![
+        AccountMeta::new_readonly(mock_amm::ID, false),
+        AccountMeta::new(pool, false),
+        AccountMeta::new(get_associated_token_address(&pool, token_mint), false),
+        AccountMeta::new(
+            get_associated_token_address(vault_address, token_mint),
+            false,
+        ),
+        AccountMeta::new_readonly(anchor_spl::token::ID, false),
+    ]
+}
+
+fn mock_oracle(vault: &Vault) -> Result<Pubkey, ClientError> {
+    vault.mock_oracle().ok_or(ClientError::MockOracleNotSet)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use vault::state::{AssetConfig, PriceFeed, PriceFeedKind, VAULT_VERSION};
+    use vault::{MockPoolConfig, SwapBackend};
+
+    pub(crate) fn test_vault(price_source: PriceSource) -> Vault {
+        let admin = Pubkey::new_unique();
+        let assets: Vec<AssetConfig> = (0..3)
+            .map(|_| AssetConfig {
+                mint: Pubkey::new_unique(),
+                weight: 33,
+                ata: Pubkey::new_unique(),
+            })
+            .collect();
+        let mut vault = Vault {
+            bump: 255,
+            admin,
+            vault_token_mint: pda::vault_mint(&admin, "test"),
+            last_share_price: 1_000_000,
+            max_slippage_bps: 100,
+            version: VAULT_VERSION,
+            ..Default::default()
+        };
+        vault.set_name("test").unwrap();
+        vault.set_assets(&assets).unwrap();
+        vault.set_price_source(price_source);
+        vault.set_mock_oracle(Some(pda::mock_oracle(&admin)));
+        vault.set_swap_backend(SwapBackend::Mock);
+        vault.set_mock_pool(MockPoolConfig::DEFAULT);
+        vault
+    }
+
+    #[test]
+    fn test_deposit_withdraw_layout() {
+        let mut vault = test_vault(PriceSource::MockOracle);
+        let vault_address = pda::vault(&vault.admin, vault.name());
+        let strategy = pda::marinade_strategy(&vault_address);
+        vault.set_marinade_strategy(Some(strategy));
+
+        let accounts = deposit_withdraw_accounts(&vault, PriceInput::Oracle, None, &[]).unwrap();
+        let keys: Vec<Pubkey> = accounts.iter().map(|a| a.pubkey).collect();
+        assert_eq!(keys.len(), 3 * 2 + 3 + 1);
+        assert_eq!(keys[0], vault.assets()[0].mint);
+        assert_eq!(keys[1], vault.assets()[0].ata);
+        assert!(accounts[1].is_writable && !accounts[0].is_writable);
+        assert_eq!(keys[6], vault.mock_oracle().unwrap());
+        assert_eq!(keys[7], oracle_adapter::ID);
+        assert_eq!(keys[8], pda::strategy_value(&vault_address));
+        assert!(accounts[8].is_writable);
+        assert_eq!(keys[9], strategy);
+
+        // The PriceCache replaces the oracle slots
+        let cached = deposit_withdraw_accounts(&vault, PriceInput::Cache, None, &[]).unwrap();
+        assert_eq!(cached.len(), 3 * 2 + 2 + 1);
+        assert_eq!(cached[6].pubkey, pda::price_cache(&vault_address));
+    }
+
+    #[test]
+    fn test_per_asset_deposit_withdraw_layout() {
+        let mut vault = test_vault(PriceSource::MockOracle);
+        let mut assets = vault.assets().to_vec();
+        assets.pop();
+        vault.set_assets(&assets).unwrap();
+        let vault_address = pda::vault(&vault.admin, vault.name());
+
+        assert!(matches!(
+            deposit_withdraw_accounts(&vault, PriceInput::Oracle, None, &[]),
+            Err(ClientError::AssetOraclesNotSet)
+        ));
+
+        let feed = |feed_index| PriceFeed {
+            kind: PriceFeedKind::MockOracle,
+            feed_index,
+            account: vault.mock_oracle().unwrap(),
+        };
+        let oracles = AssetOracles {
+            vault: vault_address,
+            feeds: vec![feed(0), feed(1)],
+            sol_feed: feed(2),
+            bump: 255,
+        };
+        let accounts =
+            deposit_withdraw_accounts(&vault, PriceInput::Oracle, Some(&oracles), &[]).unwrap();
+        assert_eq!(accounts.len(), 2 * 2 + 1 + 2 + 1 + 1);
+        assert_eq!(accounts[4].pubkey, pda::asset_oracles(&vault_address));
+        assert!(accounts[5..8]
+            .iter()
+            .all(|a| a.pubkey == vault.mock_oracle().unwrap()));
+        assert_eq!(accounts[8].pubkey, oracle_adapter::ID);
+    }
+
+    #[test]
+    fn test_per_asset_sol_slot() {
+        let mut vault = test_vault(PriceSource::MockOracle);
+        let mut assets = vault.assets().to_vec();
+        assets.pop();
+        vault.set_assets(&assets).unwrap();
+
+        // Without the native mint the basket has no SOL leg to unwrap
+        assert_eq!(vault.sol_slot(), None);
+        assert_eq!(vault.wrapped_sol_ata(), None);
+
+        assets[0].mint = anchor_spl::token::spl_token::native_mint::ID;
+        vault.set_assets(&assets).unwrap();
+        assert_eq!(vault.sol_slot(), Some(0));
+        assert_eq!(vault.wrapped_sol_ata(), Some(assets[0].ata));
+    }
+
+    #[test]
+    fn test_rebalance_layout() {
+        // Switchboard quotes are named accounts; only their config and the
+        // adapter parsing them come first
+        let vault = test_vault(PriceSource::Switchboard);
+        let accounts = rebalance_accounts(&vault, &[]).unwrap();
+        assert_eq!(accounts.len(), 2 + 3);
+        assert_eq!(accounts[0].pubkey, pda::switchboard_config());
+        assert_eq!(accounts[1].pubkey, oracle_adapter::ID);
+        assert_eq!(accounts[2].pubkey, vault.assets()[0].ata);
+
+        let mut vault = test_vault(PriceSource::MockOracle);
+        let hop = mock_amm_hop(
+            &pda::vault(&vault.admin, vault.name()),
+            &vault.assets()[0].mint,
+        );
+        let accounts = rebalance_accounts(&vault, &hop).unwrap();
+        assert_eq!(accounts.len(), 2 + 3 + 5);
+        assert_eq!(accounts[0].pubkey, vault.mock_oracle().unwrap());
+
+        vault.set_mock_oracle(None);
+        assert!(matches!(
+            rebalance_accounts(&vault, &[]),
+            Err(ClientError::MockOracleNotSet)
+        ));
+
+        // Pyth Lazer reads its config and the instructions sysvar ahead of the ATAs
+        let vault = test_vault(PriceSource::PythLazer);
+        let accounts = rebalance_accounts(&vault, &[]).unwrap();
+        assert_eq!(accounts.len(), 3 + 3);
+        assert_eq!(accounts[0].pubkey, pda::pyth_lazer_config());
+        assert_eq!(accounts[1].pubkey, sysvar::instructions::ID);
+        assert_eq!(accounts[3].pubkey, vault.assets()[0].ata);
+
+        // Chainlink prices were verified when posted: no adapter
+        let vault = test_vault(PriceSource::Chainlink);
+        let accounts = rebalance_accounts(&vault, &[]).unwrap();
+        assert_eq!(accounts.len(), 1 + 3);
+        assert_eq!(accounts[0].pubkey, pda::chainlink_config());
+    }
+}
@@ -0,0 +1,271 @@
+//! Minimal blocking JSON-RPC client
+//!
+//! Speaks HTTP/1.0 over a plain TCP stream, which is enough for a local
+//! validator or an RPC proxy on the same host. HTTPS endpoints need such a
+//! proxy in front of them.
+
+use std::cell::Cell;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::fetch::AccountSource;
+use crate::transaction::SignedTransaction;
+use crate::ClientError;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Commitment used for reads and preflight
+pub const COMMITMENT: &str = "confirmed";
+
+/// Outcome of a submitted transaction
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignatureStatus {
+    /// Not seen yet, or not yet at the `confirmed` level
+    Pending,
+    Confirmed,
+    /// Landed but failed; holds the transaction error
+    Failed(String),
+}
+
+pub struct RpcClient {
+    url: String,
+    host: String,
+    path: String,
+    next_id: Cell<u64>,
+}
+
+impl RpcClient {
+    /// Client for an `http://host[:port][/path]` endpoint
+    pub fn new(url: &str) -> Result<Self, ClientError> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            ClientError::Rpc(format!("unsupported RPC URL {} (plain http:// only)", url))
+        })?;
+        let (host, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+        Ok(Self {
+            url: url.to_string(),
+            host,
+            path: path.to_string(),
+            next_id: Cell::new(1),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Call `method` and return its `result`
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, ClientError> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let body =
+            json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+
+        let rpc_err = |err: std::io::Error| ClientError::Rpc(format!("{}: {}", self.url, err));
+        let mut stream = TcpStream::connect(&self.host).map_err(rpc_err)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(rpc_err)?;
+        stream.set_write_timeout(Some(TIMEOUT)).map_err(rpc_err)?;
+        write!(
+            stream,
+            "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        )
+        .map_err(rpc_err)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(rpc_err)?;
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| ClientError::Rpc(format!("{}: malformed HTTP response", self.url)))?;
+        let status = head.lines().next().unwrap_or_default();
+        if !status.contains(" 200") {
+            return Err(ClientError::Rpc(format!(
+                "{} {}: {}",
+                method,
+                status,
+                body.trim()
+            )));
+        }
+        let mut reply: Value = serde_json::from_str(body)
+            .map_err(|err| ClientError::Rpc(format!("{}: {}", method, err)))?;
+        if let Some(error) = reply.get("error") {
+            return Err(ClientError::Rpc(format!("{}: {}", method, error)));
+        }
+        Ok(reply["result"].take())
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash, ClientError> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": COMMITMENT }]))?;
+        let blockhash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| ClientError::Rpc("getLatestBlockhash: missing blockhash".to_string()))?;
+        Hash::from_str(blockhash)
+            .map_err(|err| ClientError::Rpc(format!("getLatestBlockhash: {}", err)))
+    }
+
+    pub fn get_slot(&self) -> Result<u64, ClientError> {
+        let result = self.call("getSlot", json!([{ "commitment": COMMITMENT }]))?;
+        result
+            .as_u64()
+            .ok_or_else(|| ClientError::Rpc("getSlot: missing slot".to_string()))
+    }
+
+    /// Submit with preflight simulation; returns the signature
+    pub fn send_transaction(&self, transaction: &SignedTransaction) -> Result<String, ClientError> {
+        let encoded = BASE64.encode(&transaction.bytes);
+        let result = self.call(
+            "sendTransaction",
+            json!([encoded, { "encoding": "base64", "preflightCommitment": COMMITMENT, "maxRetries": 0 }]),
+        )?;
+        Ok(result
+            .as_str()
+            .unwrap_or(&transaction.signature)
+            .to_string())
+    }
+
+    pub fn get_signature_status(&self, signature: &str) -> Result<SignatureStatus, ClientError> {
+        let result = self.call("getSignatureStatuses", json!([[signature]]))?;
+        let status = &result["value"][0];
+        if status.is_null() {
+            return Ok(SignatureStatus::Pending);
+        }
+        if !status["err"].is_null() {
+            return Ok(SignatureStatus::Failed(status["err"].to_string()));
+        }
+        Ok(match status["confirmationStatus"].as_str() {
+            Some("confirmed") | Some("finalized") => SignatureStatus::Confirmed,
+            _ => SignatureStatus::Pending,
+        })
+    }
+
+    /// Poll until `signature` is confirmed or failed; Pending after `timeout`
+    pub fn wait_for_signature(
+        &self,
+        signature: &str,
+        timeout: Duration,
+    ) -> Result<SignatureStatus, ClientError> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            sleep(CONFIRM_POLL_INTERVAL);
+            match self.get_signature_status(signature) {
+                Ok(SignatureStatus::Pending) | Err(_) => {}
+                Ok(status) => return Ok(status),
+            }
+        }
+        Ok(SignatureStatus::Pending)
+    }
+
+    /// Address and data of every account of `program_id` whose data starts
+    /// with `discriminator`
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        discriminator: &[u8],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>, ClientError> {
+        let filter = json!({ "memcmp": { "offset": 0, "bytes": BASE64.encode(discriminator), "encoding": "base64" } });
+        let result = self.call(
+            "getProgramAccounts",
+            json!([program_id.to_string(), { "encoding": "base64", "commitment": COMMITMENT, "filters": [filter] }]),
+        )?;
+        let malformed = || ClientError::Rpc("getProgramAccounts: malformed account".to_string());
+        result
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|account| {
+                let address = account["pubkey"]
+                    .as_str()
+                    .and_then(|key| Pubkey::from_str(key).ok())
+                    .ok_or_else(malformed)?;
+                let data = account["account"]["data"][0]
+                    .as_str()
+                    .and_then(|data| BASE64.decode(data).ok())
+                    .ok_or_else(malformed)?;
+                Ok((address, data))
+            })
+            .collect()
+    }
+
+    /// Prioritization fees (micro-lamports per CU) paid in recent slots by
+    /// transactions that locked `accounts` writable
+    pub fn get_recent_prioritization_fees(
+        &self,
+        accounts: &[Pubkey],
+    ) -> Result<Vec<u64>, ClientError> {
+        let accounts: Vec<String> = accounts.iter().map(Pubkey::to_string).collect();
+        let result = self.call("getRecentPrioritizationFees", json!([accounts]))?;
+        Ok(result
+            .as_array()
+            .map(|fees| {
+                fees.iter()
+                    .filter_map(|fee| fee["prioritizationFee"].as_u64())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+impl AccountSource for RpcClient {
+    fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": COMMITMENT }]),
+        )?;
+        let value = &result["value"];
+        if value.is_null() {
+            return Ok(None);
+        }
+        let data = value["data"][0]
+            .as_str()
+            .ok_or_else(|| ClientError::Rpc(format!("getAccountInfo {}: missing data", address)))?;
+        BASE64
+            .decode(data)
+            .map(Some)
+            .map_err(|err| ClientError::Rpc(format!("getAccountInfo {}: {}", address, err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_parsing() {
+        let client = RpcClient::new("http://127.0.0.1:8899").unwrap();
+        assert_eq!(
+            (client.host.as_str(), client.path.as_str()),
+            ("127.0.0.1:8899", "/")
+        );
+        let client = RpcClient::new("http://localhost/rpc").unwrap();
+        assert_eq!(
+            (client.host.as_str(), client.path.as_str()),
+            ("localhost:80", "/rpc")
+        );
+        assert!(matches!(
+            RpcClient::new("https://api.devnet.solana.com"),
+            Err(ClientError::Rpc(_))
+        ));
+    }
+}
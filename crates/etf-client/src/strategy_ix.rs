@@ -0,0 +1,133 @@
+//! Instruction builders for the marinade_strategy program
+//!
+//! The vault CPIs stake, unstake and record_deposit during deposits,
+//! withdrawals and divest_from_strategy with itself as the vault signer; these builders are for initializing the
+//! strategy and for reading its value.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use marinade_strategy::{accounts, instruction};
+
+use crate::marinade::MarinadeAccounts;
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: marinade_strategy::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// The strategy's mSOL token account
+pub fn msol_ata(vault: &Pubkey, marinade: &MarinadeAccounts) -> Pubkey {
+    get_associated_token_address(&pda::marinade_strategy(vault), &marinade.msol_mint)
+}
+
+/// Create the StrategyAccount of `vault` and its mSOL ATA
+pub fn initialize(vault: &Pubkey, payer: &Pubkey, marinade: &MarinadeAccounts) -> Instruction {
+    build(
+        accounts::Initialize {
+            strategy_account: pda::marinade_strategy(vault),
+            vault: *vault,
+            payer: *payer,
+            system_program: system_program::ID,
+            msol_ata: msol_ata(vault, marinade),
+            msol_mint: marinade.msol_mint,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: anchor_spl::token::ID,
+        },
+        instruction::Initialize {},
+    )
+}
+
+pub fn stake(
+    vault: &Pubkey,
+    payer: &Pubkey,
+    marinade: &MarinadeAccounts,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::Stake {
+            strategy_account: pda::marinade_strategy(vault),
+            vault: *vault,
+            payer: *payer,
+            marinade_state: marinade.state,
+            reserve_pda: marinade.reserve_pda,
+            msol_mint: marinade.msol_mint,
+            msol_ata: msol_ata(vault, marinade),
+            msol_mint_authority: marinade.msol_mint_authority,
+            liq_pool_sol_leg_pda: marinade.liq_pool_sol_leg_pda,
+            liq_pool_msol_leg: marinade.liq_pool_msol_leg,
+            liq_pool_msol_leg_authority: marinade.liq_pool_msol_leg_authority,
+            marinade_program: marinade.program,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        },
+        instruction::Stake { amount },
+    )
+}
+
+pub fn unstake(
+    vault: &Pubkey,
+    sol_receiver: &Pubkey,
+    marinade: &MarinadeAccounts,
+    msol_amount: u64,
+) -> Instruction {
+    build(
+        accounts::Unstake {
+            strategy_account: pda::marinade_strategy(vault),
+            vault: *vault,
+            sol_receiver: *sol_receiver,
+            marinade_state: marinade.state,
+            msol_mint: marinade.msol_mint,
+            liq_pool_msol_leg: marinade.liq_pool_msol_leg,
+            liq_pool_sol_leg_pda: marinade.liq_pool_sol_leg_pda,
+            msol_ata: msol_ata(vault, marinade),
+            treasury_msol_account: marinade.treasury_msol_account,
+            marinade_program: marinade.program,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+        },
+        instruction::Unstake { msol_amount },
+    )
+}
+
+pub fn harvest(vault: &Pubkey, marinade: &MarinadeAccounts) -> Instruction {
+    build(
+        accounts::Harvest {
+            strategy_account: pda::marinade_strategy(vault),
+            vault: *vault,
+            marinade_state: marinade.state,
+        },
+        instruction::Harvest {},
+    )
+}
+
+/// Returns the strategy's SOL value as return data
+pub fn report_value(vault: &Pubkey, marinade: &MarinadeAccounts) -> Instruction {
+    build(
+        accounts::ReportValue {
+            strategy_account: pda::marinade_strategy(vault),
+            vault: *vault,
+            marinade_state: marinade.state,
+            msol_ata: msol_ata(vault, marinade),
+            msol_mint: marinade.msol_mint,
+        },
+        instruction::ReportValue {},
+    )
+}
+
+pub fn close_strategy(vault: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseStrategy {
+            strategy_account: pda::marinade_strategy(vault),
+            vault: *vault,
+            payer: *payer,
+        },
+        instruction::CloseStrategy {},
+    )
+}
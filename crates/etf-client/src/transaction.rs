@@ -0,0 +1,120 @@
+//! Transaction assembly: compute budget instructions and signing
+//!
+//! Produces legacy or v0 transactions in wire format, ready for
+//! `sendTransaction`. v0 transactions resolve accounts through address
+//! lookup tables (see `lookup_table`).
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::pubkey;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::{
+    v0, AddressLookupTableAccount, Message, VersionedMessage,
+};
+
+use crate::keypair::Keypair;
+use crate::ClientError;
+
+/// Compute Budget program
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// Cap the transaction's compute units (ComputeBudgetInstruction::SetComputeUnitLimit)
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    let mut data = vec![2];
+    data.extend_from_slice(&units.to_le_bytes());
+    Instruction {
+        program_id: COMPUTE_BUDGET_PROGRAM_ID,
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+/// Priority fee per compute unit (ComputeBudgetInstruction::SetComputeUnitPrice)
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![3];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction {
+        program_id: COMPUTE_BUDGET_PROGRAM_ID,
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+/// A signed transaction
+#[derive(Clone, Debug)]
+pub struct SignedTransaction {
+    /// Wire format (shortvec signatures followed by the message)
+    pub bytes: Vec<u8>,
+    /// Fee payer signature, base58 (the transaction id)
+    pub signature: String,
+}
+
+/// Build and sign a transaction paid for by `payer`
+///
+/// Every other signer the instructions require must be in `signers`.
+pub fn sign_transaction(
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+    recent_blockhash: Hash,
+) -> Result<SignedTransaction, ClientError> {
+    let mut message = Message::new(instructions, Some(&payer.pubkey()));
+    message.recent_blockhash = recent_blockhash;
+    let message_bytes = message.serialize();
+    sign_message(
+        message_bytes,
+        &message.account_keys,
+        message.header.num_required_signatures,
+        payer,
+        signers,
+    )
+}
+
+/// Build and sign a v0 transaction paid for by `payer`, resolving accounts
+/// through `lookup_tables` where they contain them
+pub fn sign_v0_transaction(
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<SignedTransaction, ClientError> {
+    let message = v0::Message::try_compile(
+        &payer.pubkey(),
+        instructions,
+        lookup_tables,
+        recent_blockhash,
+    )
+    .map_err(|err| ClientError::Message(err.to_string()))?;
+    let (account_keys, required) = (
+        message.account_keys.clone(),
+        message.header.num_required_signatures,
+    );
+    let message_bytes = VersionedMessage::V0(message).serialize();
+    sign_message(message_bytes, &account_keys, required, payer, signers)
+}
+
+fn sign_message(
+    message_bytes: Vec<u8>,
+    account_keys: &[Pubkey],
+    num_required_signatures: u8,
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<SignedTransaction, ClientError> {
+    let required = num_required_signatures as usize;
+    // Signature counts above 127 would need a multi-byte shortvec;
+    // transactions cannot fit that many anyway
+    let mut bytes = vec![required as u8];
+    for key in &account_keys[..required] {
+        let signer = std::iter::once(payer)
+            .chain(signers.iter().copied())
+            .find(|signer| signer.pubkey() == *key)
+            .ok_or_else(|| ClientError::Keypair(format!("missing signer {}", key)))?;
+        bytes.extend_from_slice(&signer.sign(&message_bytes));
+    }
+    let signature = bs58::encode(&bytes[1..65]).into_string();
+    bytes.extend_from_slice(&message_bytes);
+
+    Ok(SignedTransaction { bytes, signature })
+}
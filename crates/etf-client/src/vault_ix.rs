@@ -0,0 +1,3430 @@
+//! Instruction builders for the vault program
+//!
+//! One function per instruction, named after it. Builders derive every PDA,
+//! fill the Anchor accounts struct (including the event authority of
+//! `emit_cpi!` instructions) and append remaining_accounts in the order the
+//! program checks them. Builders that need the vault's assets, price source
+//! or strategy take the fetched `Vault`; the rest only need its `VaultId`.
+//!
+//! The Ephemeral Rollup oracle instructions (delegate/commit/undelegate) are
+//! disabled in the program and have no builders.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::address_lookup_table::instruction::derive_lookup_table_address;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use vault::state::{
+    AssetConfig, AssetOracles, DcaSchedule, DepositCommitment, FeeTier, LstBasket, LstPool,
+    PriceFeed, QuoteConfig, RebalanceConfig, ScheduleStep, Vault, VaultPreset, VaultTemplate,
+};
+use vault::{
+    accounts, chainlink, instruction, signed_prices, MintPrice, PriceSource, QuoteCurrency,
+    SwapBackend,
+};
+
+use crate::marinade::MarinadeAccounts;
+use crate::remaining::{self, PriceInput};
+use crate::{pda, strategy_ix, ClientError};
+
+/// Identifies a vault by its PDA seeds
+#[derive(Clone, Debug, PartialEq)]
+pub struct VaultId {
+    pub admin: Pubkey,
+    pub name: String,
+    /// Whether its shares come from a non-transferable Token-2022 mint
+    pub soulbound: bool,
+}
+
+impl VaultId {
+    /// Id of a vault with transferable shares; see `with_soulbound_shares`
+    pub fn new(admin: Pubkey, name: impl Into<String>) -> Self {
+        Self {
+            admin,
+            name: name.into(),
+            soulbound: false,
+        }
+    }
+
+    pub fn with_soulbound_shares(mut self, soulbound: bool) -> Self {
+        self.soulbound = soulbound;
+        self
+    }
+
+    /// Id of a fetched vault
+    pub fn of(vault: &Vault) -> Self {
+        Self::new(vault.admin, vault.name().to_string()).with_soulbound_shares(vault.soulbound())
+    }
+
+    pub fn address(&self) -> Pubkey {
+        pda::vault(&self.admin, &self.name)
+    }
+
+    pub fn share_mint(&self) -> Pubkey {
+        pda::vault_mint(&self.admin, &self.name)
+    }
+
+    /// Token program of the share mint
+    pub fn share_token_program(&self) -> Pubkey {
+        mint_token_program(self.soulbound)
+    }
+
+    /// `owner`'s share ATA
+    pub fn share_ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.share_mint(), &self.share_token_program())
+    }
+}
+
+/// `owner`'s share ATA of a fetched vault
+fn share_ata(vault: &Vault, owner: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, &vault.vault_token_mint, &vault.share_token_program())
+}
+
+/// Switchboard quote accounts (only read when price_source = Switchboard)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwitchboardQuotes {
+    pub btc: Pubkey,
+    pub eth: Pubkey,
+    pub sol: Pubkey,
+}
+
+/// Accounts deposit and withdraw take beyond the vault and user
+#[derive(Clone, Debug)]
+pub struct ExtraAccounts {
+    pub price_input: PriceInput,
+    pub quotes: SwitchboardQuotes,
+    /// Required even without a strategy; unused accounts are not read
+    pub marinade: MarinadeAccounts,
+    /// Swap backend accounts, in swap order (none for the Mock backend)
+    pub swap_accounts: Vec<AccountMeta>,
+    /// Fetched AssetOracles, required for per-asset priced vaults
+    pub asset_oracles: Option<AssetOracles>,
+    /// Fetched QuoteConfig; zaps of a USDC vault's USDC pass it to be held
+    /// to the peg
+    pub quote_config: Option<QuoteConfig>,
+}
+
+impl Default for ExtraAccounts {
+    fn default() -> Self {
+        Self {
+            price_input: PriceInput::Oracle,
+            quotes: SwitchboardQuotes::default(),
+            marinade: MarinadeAccounts::mainnet(),
+            swap_accounts: Vec::new(),
+            asset_oracles: None,
+            quote_config: None,
+        }
+    }
+}
+
+fn build(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining: Vec<AccountMeta>,
+) -> Instruction {
+    let mut metas = accounts.to_account_metas(None);
+    metas.extend(remaining);
+    Instruction {
+        program_id: vault::ID,
+        accounts: metas,
+        data: data.data(),
+    }
+}
+
+/// Create a vault holding `assets` as (mint, weight) pairs of SPL Token
+/// mints; weights must sum to 100. `soulbound` makes its shares
+/// non-transferable.
+pub fn create_vault(
+    admin: &Pubkey,
+    name: &str,
+    assets: &[(Pubkey, u8)],
+    soulbound: bool,
+) -> Instruction {
+    let assets: Vec<(Pubkey, u8, Pubkey)> = assets
+        .iter()
+        .map(|(mint, weight)| (*mint, *weight, anchor_spl::token::ID))
+        .collect();
+    create_vault_with_token_programs(admin, name, &assets, soulbound)
+}
+
+/// Create a vault holding `assets` as (mint, weight, token program) triples,
+/// for baskets with Token-2022 mints; weights must sum to 100
+pub fn create_vault_with_token_programs(
+    admin: &Pubkey,
+    name: &str,
+    assets: &[(Pubkey, u8, Pubkey)],
+    soulbound: bool,
+) -> Instruction {
+    let id = VaultId::new(*admin, name);
+    let vault_address = id.address();
+    let mints: Vec<(Pubkey, Pubkey)> = assets
+        .iter()
+        .map(|(mint, _, token_program)| (*mint, *token_program))
+        .collect();
+    let asset_configs = assets
+        .iter()
+        .map(|(mint, weight, token_program)| AssetConfig {
+            mint: *mint,
+            weight: *weight,
+            ata: get_associated_token_address_with_program_id(&vault_address, mint, token_program),
+        })
+        .collect();
+    let token_2022_program = (soulbound
+        || assets
+            .iter()
+            .any(|(_, _, token_program)| *token_program == anchor_spl::token_2022::ID))
+    .then_some(anchor_spl::token_2022::ID);
+
+    build(
+        accounts::CreateVault {
+            vault: vault_address,
+            admin: *admin,
+            vault_token_mint: id.share_mint(),
+            vault_registry: pda::vault_registry(),
+            vault_registration: pda::vault_registration(&vault_address),
+            asset_whitelist: pda::asset_whitelist(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CreateVault {
+            name: name.to_string(),
+            assets: asset_configs,
+            soulbound,
+        },
+        remaining::create_vault_accounts(&vault_address, &mints),
+    )
+}
+
+/// Create a vault from the fetched `template`; `token_2022_mints` lists its
+/// assets that are Token-2022 mints
+pub fn create_vault_from_template(
+    admin: &Pubkey,
+    name: &str,
+    template: &VaultTemplate,
+    token_2022_mints: &[Pubkey],
+    soulbound: bool,
+) -> Instruction {
+    let id = VaultId::new(*admin, name);
+    let vault_address = id.address();
+    let mints: Vec<(Pubkey, Pubkey)> = template
+        .preset
+        .assets
+        .iter()
+        .map(|asset| {
+            (
+                asset.mint,
+                mint_token_program(token_2022_mints.contains(&asset.mint)),
+            )
+        })
+        .collect();
+
+    build(
+        accounts::CreateVaultFromTemplate {
+            vault: vault_address,
+            admin: *admin,
+            vault_token_mint: id.share_mint(),
+            vault_template: pda::vault_template(&template.name),
+            vault_registry: pda::vault_registry(),
+            vault_registration: pda::vault_registration(&vault_address),
+            asset_whitelist: pda::asset_whitelist(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: (soulbound || !token_2022_mints.is_empty())
+                .then_some(anchor_spl::token_2022::ID),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CreateVaultFromTemplate {
+            name: name.to_string(),
+            _template_name: template.name.clone(),
+            soulbound,
+        },
+        remaining::create_vault_accounts(&vault_address, &mints),
+    )
+}
+
+/// Create a vault with no assets yet, for baskets too large for one
+/// create_vault; follow with add_vault_asset per asset and finalize_vault
+pub fn init_vault(admin: &Pubkey, name: &str, soulbound: bool) -> Instruction {
+    let id = VaultId::new(*admin, name);
+    let vault_address = id.address();
+    build(
+        accounts::InitVault {
+            vault: vault_address,
+            admin: *admin,
+            vault_token_mint: id.share_mint(),
+            vault_registry: pda::vault_registry(),
+            vault_registration: pda::vault_registration(&vault_address),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: soulbound.then_some(anchor_spl::token_2022::ID),
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::InitVault {
+            name: name.to_string(),
+            soulbound,
+        },
+        Vec::new(),
+    )
+}
+
+/// Append `mint` at `weight` to a vault from init_vault, opening its ATA;
+/// pass `token_2022` for Token-2022 mints
+pub fn add_vault_asset(
+    id: &VaultId,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    weight: u8,
+    token_2022: bool,
+) -> Instruction {
+    let vault_address = id.address();
+    let token_program = mint_token_program(token_2022);
+    let ata = get_associated_token_address_with_program_id(&vault_address, mint, &token_program);
+    build(
+        accounts::AddVaultAsset {
+            vault: vault_address,
+            authority: *authority,
+            mint: *mint,
+            vault_ata: ata,
+            asset_whitelist: pda::asset_whitelist(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: token_2022.then_some(anchor_spl::token_2022::ID),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::AddVaultAsset {
+            _name: id.name.clone(),
+            asset: AssetConfig {
+                mint: *mint,
+                weight,
+                ata,
+            },
+        },
+        Vec::new(),
+    )
+}
+
+/// Open a vault built with add_vault_asset for deposits; its weights must sum to 100
+pub fn finalize_vault(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::FinalizeVault {
+            vault: id.address(),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::FinalizeVault {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Deposit `amount` lamports; `referrer` must have registered for this vault
+pub fn deposit_multi_asset(
+    vault: &Vault,
+    user: &Pubkey,
+    amount: u64,
+    referrer: Option<Pubkey>,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let id = VaultId::of(vault);
+    let vault_address = id.address();
+    let marinade = &extra.marinade;
+
+    Ok(build(
+        accounts::DepositMultiAsset {
+            vault: vault_address,
+            user: *user,
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            referral_balance: referrer
+                .map(|referrer| pda::referral_balance(&vault_address, &referrer)),
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            marinade_strategy_program: marinade_strategy::ID,
+            marinade_program: marinade.program,
+            marinade_state: marinade.state,
+            reserve_pda: marinade.reserve_pda,
+            msol_mint: marinade.msol_mint,
+            strategy_msol_ata: strategy_msol_ata(&vault_address, marinade),
+            msol_mint_authority: marinade.msol_mint_authority,
+            liq_pool_sol_leg_pda: marinade.liq_pool_sol_leg_pda,
+            liq_pool_msol_leg: marinade.liq_pool_msol_leg,
+            liq_pool_msol_leg_authority: marinade.liq_pool_msol_leg_authority,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DepositMultiAsset {
+            _name: vault.name().to_string(),
+            amount,
+            referrer,
+        },
+        remaining::deposit_withdraw_accounts(
+            vault,
+            extra.price_input,
+            extra.asset_oracles.as_ref(),
+            &extra.swap_accounts,
+        )?,
+    ))
+}
+
+/// Deposit `amount` of `input_mint` from `user_input_account`, swapped to
+/// at least `min_sol_out` lamports before allocation
+pub fn deposit_zap(
+    vault: &Vault,
+    user: &Pubkey,
+    input_mint: &Pubkey,
+    user_input_account: &Pubkey,
+    amount: u64,
+    min_sol_out: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+
+    Ok(build(
+        accounts::DepositZap {
+            vault: vault_address,
+            user: *user,
+            input_mint: *input_mint,
+            user_input_account: *user_input_account,
+            vault_input_account: get_associated_token_address(&vault_address, input_mint),
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            quote_config: quote_config_account(&vault_address, extra.quote_config.as_ref()),
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DepositZap {
+            _name: vault.name().to_string(),
+            amount,
+            min_sol_out,
+        },
+        remaining::zap_accounts(vault, extra.price_input, &extra.swap_accounts)?,
+    ))
+}
+
+/// Deposit `amount` of the basket asset `mint` from `user_token_account`
+/// without swapping it
+pub fn deposit_single_asset(
+    vault: &Vault,
+    user: &Pubkey,
+    mint: &Pubkey,
+    user_token_account: &Pubkey,
+    amount: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+
+    Ok(build(
+        accounts::DepositSingleAsset {
+            vault: vault_address,
+            user: *user,
+            user_token_account: *user_token_account,
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: remaining::token_2022_program(vault),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DepositSingleAsset {
+            _name: vault.name().to_string(),
+            mint: *mint,
+            amount,
+        },
+        remaining::snapshot_nav_accounts(vault, extra.price_input)?,
+    ))
+}
+
+/// Deposit `amount` mSOL from `user_msol_account` into the vault's Marinade
+/// strategy
+pub fn deposit_msol(
+    vault: &Vault,
+    user: &Pubkey,
+    user_msol_account: &Pubkey,
+    amount: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+
+    Ok(build(
+        accounts::DepositMsol {
+            vault: vault_address,
+            user: *user,
+            user_msol_account: *user_msol_account,
+            msol_mint: extra.marinade.msol_mint,
+            marinade_state: extra.marinade.state,
+            strategy_account: pda::marinade_strategy(&vault_address),
+            strategy_msol_ata: strategy_ix::msol_ata(&vault_address, &extra.marinade),
+            marinade_strategy_program: marinade_strategy::ID,
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DepositMsol {
+            _name: vault.name().to_string(),
+            amount,
+        },
+        remaining::snapshot_nav_accounts(vault, extra.price_input)?,
+    ))
+}
+
+/// Escrow `amount` lamports in a deposit ticket priced now
+pub fn deposit_prepare(
+    vault: &Vault,
+    user: &Pubkey,
+    amount: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    Ok(build(
+        deposit_prepare_accounts(vault, user, extra, None),
+        instruction::DepositPrepare {
+            _name: vault.name().to_string(),
+            amount,
+        },
+        remaining::snapshot_nav_accounts(vault, extra.price_input)?,
+    ))
+}
+
+/// Commit `user` to a deposit of `amount` lamports without revealing it;
+/// keep `salt` (32 random bytes) for reveal_deposit
+pub fn commit_deposit(id: &VaultId, user: &Pubkey, amount: u64, salt: &[u8; 32]) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::CommitDeposit {
+            vault: vault_address,
+            user: *user,
+            deposit_commitment: pda::deposit_commitment(&vault_address, user),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CommitDeposit {
+            _name: id.name.clone(),
+            commitment: DepositCommitment::hash(&vault_address, user, amount, salt),
+        },
+        Vec::new(),
+    )
+}
+
+/// Open `user`'s deposit commitment (in a later slot than commit_deposit,
+/// within DEPOSIT_COMMITMENT_TTL_SLOTS) and escrow `amount` lamports in a
+/// ticket priced from the oracle now, buying at least `min_shares_out`;
+/// `extra.price_input` is ignored, as reveals never read the PriceCache
+pub fn reveal_deposit(
+    vault: &Vault,
+    user: &Pubkey,
+    amount: u64,
+    salt: [u8; 32],
+    min_shares_out: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+
+    Ok(build(
+        deposit_prepare_accounts(
+            vault,
+            user,
+            extra,
+            Some(pda::deposit_commitment(&vault_address, user)),
+        ),
+        instruction::RevealDeposit {
+            _name: vault.name().to_string(),
+            amount,
+            salt,
+            min_shares_out,
+        },
+        remaining::snapshot_nav_accounts(vault, PriceInput::Oracle)?,
+    ))
+}
+
+/// Drop `user`'s unrevealed deposit commitment
+pub fn cancel_deposit_commitment(id: &VaultId, user: &Pubkey) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::CancelDepositCommitment {
+            vault: vault_address,
+            deposit_commitment: pda::deposit_commitment(&vault_address, user),
+            user: *user,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CancelDepositCommitment {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+fn deposit_prepare_accounts(
+    vault: &Vault,
+    user: &Pubkey,
+    extra: &ExtraAccounts,
+    deposit_commitment: Option<Pubkey>,
+) -> accounts::DepositPrepare {
+    let vault_address = VaultId::of(vault).address();
+    accounts::DepositPrepare {
+        vault: vault_address,
+        user: *user,
+        ticket: pda::deposit_ticket(&vault_address, user),
+        user_shares_ata: share_ata(vault, user),
+        user_position: pda::user_position(&vault_address, user),
+        vault_token_mint: vault.vault_token_mint,
+        btc_quote: extra.quotes.btc,
+        eth_quote: extra.quotes.eth,
+        sol_quote: extra.quotes.sol,
+        deposit_commitment,
+        clock: sysvar::clock::ID,
+        share_token_program: vault.share_token_program(),
+        token_program: anchor_spl::token::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        system_program: system_program::ID,
+        rent: sysvar::rent::ID,
+        event_authority: pda::event_authority(),
+        program: vault::ID,
+    }
+}
+
+/// Allocate up to `max_assets` more assets of `user`'s deposit ticket;
+/// `swap_accounts` cover just those assets
+pub fn deposit_execute(
+    vault: &Vault,
+    user: &Pubkey,
+    executor: &Pubkey,
+    max_assets: u8,
+    swap_accounts: &[AccountMeta],
+) -> Instruction {
+    let vault_address = VaultId::of(vault).address();
+    build(
+        accounts::DepositExecute {
+            vault: vault_address,
+            ticket: pda::deposit_ticket(&vault_address, user),
+            user: *user,
+            executor: *executor,
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: vault.vault_token_mint,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DepositExecute {
+            _name: vault.name().to_string(),
+            max_assets,
+        },
+        remaining::deposit_execute_accounts(vault, swap_accounts),
+    )
+}
+
+/// Close `user`'s deposit ticket, refunding what was not allocated
+pub fn close_deposit_ticket(id: &VaultId, user: &Pubkey, closer: &Pubkey) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::CloseDepositTicket {
+            vault: vault_address,
+            ticket: pda::deposit_ticket(&vault_address, user),
+            user: *user,
+            closer: *closer,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CloseDepositTicket {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Create the user's wSOL ATA if the vault holds wrapped SOL; withdrawals
+/// unwrap the user's share through it (and close it)
+pub fn create_user_wsol_account(user: &Pubkey, vault: &Vault) -> Option<Instruction> {
+    vault.wrapped_sol_ata().map(|_| {
+        create_associated_token_account_idempotent(
+            user,
+            user,
+            &anchor_spl::token::spl_token::native_mint::ID,
+            &anchor_spl::token::ID,
+        )
+    })
+}
+
+/// Redeem `shares`; SOL unstaked from Marinade goes to `user`
+///
+/// Vaults holding wrapped SOL need the user's wSOL ATA to exist first
+/// (see create_user_wsol_account).
+pub fn withdraw_multi_asset(
+    vault: &Vault,
+    user: &Pubkey,
+    shares: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let id = VaultId::of(vault);
+    let vault_address = id.address();
+    let marinade = &extra.marinade;
+
+    Ok(build(
+        accounts::WithdrawMultiAsset {
+            vault: vault_address,
+            user: *user,
+            sol_receiver: *user,
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            marinade_strategy_program: marinade_strategy::ID,
+            marinade_program: marinade.program,
+            marinade_state: marinade.state,
+            msol_mint: marinade.msol_mint,
+            liq_pool_msol_leg: marinade.liq_pool_msol_leg,
+            liq_pool_sol_leg_pda: marinade.liq_pool_sol_leg_pda,
+            strategy_msol_ata: strategy_msol_ata(&vault_address, marinade),
+            treasury_msol_account: marinade.treasury_msol_account,
+            user_wsol_account: vault.wrapped_sol_ata().map(|_| {
+                get_associated_token_address(user, &anchor_spl::token::spl_token::native_mint::ID)
+            }),
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::WithdrawMultiAsset {
+            _name: vault.name().to_string(),
+            shares,
+        },
+        remaining::deposit_withdraw_accounts(
+            vault,
+            extra.price_input,
+            extra.asset_oracles.as_ref(),
+            &extra.swap_accounts,
+        )?,
+    ))
+}
+
+/// Redeem `shares` into `output_mint`, paid to the user's ATA; fails unless
+/// at least `min_out` arrives
+pub fn withdraw_zap(
+    vault: &Vault,
+    user: &Pubkey,
+    output_mint: &Pubkey,
+    shares: u64,
+    min_out: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+
+    Ok(build(
+        accounts::WithdrawZap {
+            vault: vault_address,
+            user: *user,
+            user_shares_ata: share_ata(vault, user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: vault.vault_token_mint,
+            output_mint: *output_mint,
+            vault_output_account: get_associated_token_address(&vault_address, output_mint),
+            user_output_account: get_associated_token_address(user, output_mint),
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            quote_config: quote_config_account(&vault_address, extra.quote_config.as_ref()),
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::WithdrawZap {
+            _name: vault.name().to_string(),
+            shares,
+            min_out,
+        },
+        remaining::zap_accounts(vault, extra.price_input, &extra.swap_accounts)?,
+    ))
+}
+
+/// Create `user`'s DCA schedule; `funding_mint` None funds it in SOL
+pub fn create_dca_schedule(
+    id: &VaultId,
+    user: &Pubkey,
+    funding_mint: Option<Pubkey>,
+    amount_per_period: u64,
+    interval_secs: i64,
+    min_sol_out: u64,
+) -> Instruction {
+    let vault_address = id.address();
+    let share_mint = id.share_mint();
+    build(
+        accounts::CreateDcaSchedule {
+            vault: vault_address,
+            schedule: pda::dca_schedule(&vault_address, user),
+            user: *user,
+            funding_mint,
+            user_shares_ata: id.share_ata(user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint: share_mint,
+            share_token_program: id.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CreateDcaSchedule {
+            _name: id.name.clone(),
+            amount_per_period,
+            interval_secs,
+            min_sol_out,
+        },
+        Vec::new(),
+    )
+}
+
+/// Create the schedule's ATA for `funding_mint` if it does not exist;
+/// token schedules need it before the first fund_dca_schedule
+pub fn create_dca_funding_account(
+    payer: &Pubkey,
+    id: &VaultId,
+    user: &Pubkey,
+    funding_mint: &Pubkey,
+) -> Instruction {
+    let schedule = pda::dca_schedule(&id.address(), user);
+    create_associated_token_account_idempotent(
+        payer,
+        &schedule,
+        funding_mint,
+        &anchor_spl::token::ID,
+    )
+}
+
+/// Token accounts of a token schedule: (user's ATA, schedule's ATA)
+fn dca_funding_accounts(
+    schedule: &Pubkey,
+    user: &Pubkey,
+    funding_mint: Option<Pubkey>,
+) -> (Option<Pubkey>, Option<Pubkey>) {
+    funding_mint
+        .map(|mint| {
+            (
+                get_associated_token_address(user, &mint),
+                get_associated_token_address(schedule, &mint),
+            )
+        })
+        .unzip()
+}
+
+/// Add `amount` to `user`'s schedule, from their wallet (SOL) or their ATA
+pub fn fund_dca_schedule(
+    id: &VaultId,
+    user: &Pubkey,
+    funding_mint: Option<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let vault_address = id.address();
+    let schedule = pda::dca_schedule(&vault_address, user);
+    let (user_funding_account, schedule_funding_account) =
+        dca_funding_accounts(&schedule, user, funding_mint);
+    build(
+        accounts::FundDcaSchedule {
+            vault: vault_address,
+            schedule,
+            user: *user,
+            user_funding_account,
+            schedule_funding_account,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::FundDcaSchedule {
+            _name: id.name.clone(),
+            amount,
+        },
+        Vec::new(),
+    )
+}
+
+/// Crank a due schedule (permissionless); `extra.swap_accounts` start with
+/// the funding token -> SOL swap for token schedules
+pub fn execute_dca(
+    vault: &Vault,
+    schedule: &DcaSchedule,
+    cranker: &Pubkey,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    let schedule_address = pda::dca_schedule(&vault_address, &schedule.user);
+    let (schedule_funding_account, vault_input_account) = schedule
+        .funding_mint
+        .map(|mint| {
+            (
+                get_associated_token_address(&schedule_address, &mint),
+                get_associated_token_address(&vault_address, &mint),
+            )
+        })
+        .unzip();
+
+    Ok(build(
+        accounts::ExecuteDca {
+            vault: vault_address,
+            schedule: schedule_address,
+            user: schedule.user,
+            cranker: *cranker,
+            user_shares_ata: share_ata(vault, &schedule.user),
+            user_position: pda::user_position(&vault_address, &schedule.user),
+            vault_token_mint: vault.vault_token_mint,
+            schedule_funding_account,
+            vault_input_account,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ExecuteDca {
+            _name: vault.name().to_string(),
+        },
+        remaining::zap_accounts(vault, extra.price_input, &extra.swap_accounts)?,
+    ))
+}
+
+/// Cancel `user`'s schedule and refund what is left
+pub fn cancel_dca_schedule(
+    id: &VaultId,
+    user: &Pubkey,
+    funding_mint: Option<Pubkey>,
+) -> Instruction {
+    let vault_address = id.address();
+    let schedule = pda::dca_schedule(&vault_address, user);
+    let (user_funding_account, schedule_funding_account) =
+        dca_funding_accounts(&schedule, user, funding_mint);
+    build(
+        accounts::CancelDcaSchedule {
+            vault: vault_address,
+            schedule,
+            user: *user,
+            user_funding_account,
+            schedule_funding_account,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CancelDcaSchedule {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Queue `amount` lamports in the batch of `epoch` (the current epoch)
+pub fn queue_batched_deposit(id: &VaultId, user: &Pubkey, epoch: u64, amount: u64) -> Instruction {
+    let vault_address = id.address();
+    let batch = pda::deposit_batch(&vault_address, epoch);
+    let vault_token_mint = id.share_mint();
+    build(
+        accounts::QueueBatchedDeposit {
+            vault: vault_address,
+            batch,
+            batch_deposit: pda::batch_deposit(&batch, user),
+            user: *user,
+            user_shares_ata: id.share_ata(user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint,
+            share_token_program: id.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::QueueBatchedDeposit {
+            _name: id.name.clone(),
+            epoch,
+            amount,
+        },
+        Vec::new(),
+    )
+}
+
+/// Deposit the batch of an ended `epoch` (permissionless)
+pub fn process_deposit_batch(
+    vault: &Vault,
+    epoch: u64,
+    cranker: &Pubkey,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    let batch = pda::deposit_batch(&vault_address, epoch);
+    Ok(build(
+        accounts::ProcessDepositBatch {
+            vault: vault_address,
+            batch,
+            batch_shares_ata: share_ata(vault, &batch),
+            cranker: *cranker,
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ProcessDepositBatch {
+            _name: vault.name().to_string(),
+            epoch,
+        },
+        remaining::zap_accounts(vault, extra.price_input, &extra.swap_accounts)?,
+    ))
+}
+
+/// Pay `user` their shares of a processed batch (permissionless)
+pub fn claim_batch_shares(
+    id: &VaultId,
+    epoch: u64,
+    user: &Pubkey,
+    cranker: &Pubkey,
+) -> Instruction {
+    let vault_address = id.address();
+    let batch = pda::deposit_batch(&vault_address, epoch);
+    let vault_token_mint = id.share_mint();
+    build(
+        accounts::ClaimBatchShares {
+            vault: vault_address,
+            batch,
+            batch_deposit: pda::batch_deposit(&batch, user),
+            user: *user,
+            cranker: *cranker,
+            batch_shares_ata: id.share_ata(&batch),
+            user_shares_ata: id.share_ata(user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint,
+            share_token_program: id.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClaimBatchShares {
+            _name: id.name.clone(),
+            epoch,
+        },
+        Vec::new(),
+    )
+}
+
+/// Take `user`'s deposit back out of the unprocessed batch of `epoch`
+pub fn cancel_batched_deposit(id: &VaultId, user: &Pubkey, epoch: u64) -> Instruction {
+    let vault_address = id.address();
+    let batch = pda::deposit_batch(&vault_address, epoch);
+    build(
+        accounts::CancelBatchedDeposit {
+            vault: vault_address,
+            batch,
+            batch_deposit: pda::batch_deposit(&batch, user),
+            user: *user,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CancelBatchedDeposit {
+            _name: id.name.clone(),
+            _epoch: epoch,
+        },
+        Vec::new(),
+    )
+}
+
+/// Queue `shares` in the withdrawal batch of `epoch` (the current epoch)
+pub fn queue_batched_withdrawal(
+    id: &VaultId,
+    user: &Pubkey,
+    epoch: u64,
+    shares: u64,
+) -> Instruction {
+    let vault_address = id.address();
+    let batch = pda::withdrawal_batch(&vault_address, epoch);
+    let vault_token_mint = id.share_mint();
+    build(
+        accounts::QueueBatchedWithdrawal {
+            vault: vault_address,
+            batch,
+            batch_withdrawal: pda::batch_withdrawal(&batch, user),
+            batch_shares_ata: id.share_ata(&batch),
+            user: *user,
+            user_shares_ata: id.share_ata(user),
+            user_position: pda::user_position(&vault_address, user),
+            vault_token_mint,
+            share_token_program: id.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::QueueBatchedWithdrawal {
+            _name: id.name.clone(),
+            epoch,
+            shares,
+        },
+        Vec::new(),
+    )
+}
+
+/// Redeem the withdrawal batch of an ended `epoch` (permissionless)
+pub fn process_withdrawal_batch(
+    vault: &Vault,
+    epoch: u64,
+    cranker: &Pubkey,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    let batch = pda::withdrawal_batch(&vault_address, epoch);
+    Ok(build(
+        accounts::ProcessWithdrawalBatch {
+            vault: vault_address,
+            batch,
+            batch_shares_ata: share_ata(vault, &batch),
+            cranker: *cranker,
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            clock: sysvar::clock::ID,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ProcessWithdrawalBatch {
+            _name: vault.name().to_string(),
+            epoch,
+        },
+        remaining::zap_accounts(vault, extra.price_input, &extra.swap_accounts)?,
+    ))
+}
+
+/// Pay `user` their SOL from a processed withdrawal batch (permissionless)
+pub fn claim_batch_withdrawal(
+    id: &VaultId,
+    epoch: u64,
+    user: &Pubkey,
+    cranker: &Pubkey,
+) -> Instruction {
+    let vault_address = id.address();
+    let batch = pda::withdrawal_batch(&vault_address, epoch);
+    build(
+        accounts::ClaimBatchWithdrawal {
+            vault: vault_address,
+            batch,
+            batch_withdrawal: pda::batch_withdrawal(&batch, user),
+            user: *user,
+            cranker: *cranker,
+            user_position: pda::user_position(&vault_address, user),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClaimBatchWithdrawal {
+            _name: id.name.clone(),
+            _epoch: epoch,
+        },
+        Vec::new(),
+    )
+}
+
+/// Take `user`'s shares back out of the unprocessed withdrawal batch of `epoch`
+pub fn cancel_batched_withdrawal(id: &VaultId, user: &Pubkey, epoch: u64) -> Instruction {
+    let vault_address = id.address();
+    let batch = pda::withdrawal_batch(&vault_address, epoch);
+    let vault_token_mint = id.share_mint();
+    build(
+        accounts::CancelBatchedWithdrawal {
+            vault: vault_address,
+            batch,
+            batch_withdrawal: pda::batch_withdrawal(&batch, user),
+            batch_shares_ata: id.share_ata(&batch),
+            user: *user,
+            user_shares_ata: id.share_ata(user),
+            vault_token_mint,
+            share_token_program: id.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CancelBatchedWithdrawal {
+            _name: id.name.clone(),
+            epoch,
+        },
+        Vec::new(),
+    )
+}
+
+pub(crate) fn strategy_msol_ata(vault_address: &Pubkey, marinade: &MarinadeAccounts) -> Pubkey {
+    get_associated_token_address(&pda::marinade_strategy(vault_address), &marinade.msol_mint)
+}
+
+/// Create `authority`'s MockOracle
+pub fn initialize_mock_oracle(authority: &Pubkey) -> Instruction {
+    build(
+        accounts::InitializeMockOracle {
+            mock_oracle: pda::mock_oracle(authority),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeMockOracle {},
+        Vec::new(),
+    )
+}
+
+/// Set `authority`'s MockOracle prices (micro-USD)
+pub fn update_mock_oracle(
+    authority: &Pubkey,
+    btc_price: i64,
+    eth_price: i64,
+    sol_price: i64,
+) -> Instruction {
+    build(
+        accounts::UpdateMockOracle {
+            mock_oracle: pda::mock_oracle(authority),
+            oracle_feeders: None,
+            authority: *authority,
+        },
+        instruction::UpdateMockOracle {
+            btc_price,
+            eth_price,
+            sol_price,
+        },
+        Vec::new(),
+    )
+}
+
+/// Set the prices of `oracle_authority`'s MockOracle as one of its feeders
+pub fn update_mock_oracle_as_feeder(
+    oracle_authority: &Pubkey,
+    feeder: &Pubkey,
+    btc_price: i64,
+    eth_price: i64,
+    sol_price: i64,
+) -> Instruction {
+    let mock_oracle = pda::mock_oracle(oracle_authority);
+    build(
+        accounts::UpdateMockOracle {
+            mock_oracle,
+            oracle_feeders: Some(pda::oracle_feeders(&mock_oracle)),
+            authority: *feeder,
+        },
+        instruction::UpdateMockOracle {
+            btc_price,
+            eth_price,
+            sol_price,
+        },
+        Vec::new(),
+    )
+}
+
+/// Set several markets of `oracle_authority`'s MockOracle at once, naming
+/// them by `vault`'s basket mints; `signer` is the authority or a feeder
+pub fn update_prices_batch(
+    oracle_authority: &Pubkey,
+    signer: &Pubkey,
+    vault: &Pubkey,
+    prices: Vec<MintPrice>,
+) -> Instruction {
+    let mock_oracle = pda::mock_oracle(oracle_authority);
+    build(
+        accounts::UpdatePricesBatch {
+            mock_oracle,
+            oracle_feeders: (signer != oracle_authority).then(|| pda::oracle_feeders(&mock_oracle)),
+            vault: *vault,
+            authority: *signer,
+        },
+        instruction::UpdatePricesBatch { prices },
+        Vec::new(),
+    )
+}
+
+/// Land prices `signer` signed off-chain for `oracle_authority`'s
+/// MockOracle: the Ed25519 verification, then update_mock_oracle_signed.
+/// Anyone may send the pair; `signature` covers `signed_prices::message`.
+pub fn update_mock_oracle_signed(
+    oracle_authority: &Pubkey,
+    signer: &Pubkey,
+    signature: &[u8; 64],
+    [btc_price, eth_price, sol_price]: [i64; 3],
+    signed_at: i64,
+) -> [Instruction; 2] {
+    let mock_oracle = pda::mock_oracle(oracle_authority);
+    let message = signed_prices::message(&mock_oracle, btc_price, eth_price, sol_price, signed_at);
+    let verify = Instruction {
+        program_id: ed25519_program::ID,
+        accounts: Vec::new(),
+        data: signed_prices::ed25519_instruction_data(signer, signature, &message),
+    };
+    let update = build(
+        accounts::UpdateMockOracleSigned {
+            mock_oracle,
+            oracle_feeders: (signer != oracle_authority).then(|| pda::oracle_feeders(&mock_oracle)),
+            instructions: sysvar::instructions::ID,
+        },
+        instruction::UpdateMockOracleSigned {
+            btc_price,
+            eth_price,
+            sol_price,
+            signed_at,
+        },
+        Vec::new(),
+    );
+    [verify, update]
+}
+
+/// Replace the keys that may update `authority`'s MockOracle
+pub fn set_oracle_feeders(authority: &Pubkey, feeders: Vec<Pubkey>) -> Instruction {
+    let mock_oracle = pda::mock_oracle(authority);
+    build(
+        accounts::SetOracleFeeders {
+            mock_oracle,
+            oracle_feeders: pda::oracle_feeders(&mock_oracle),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetOracleFeeders { feeders },
+        Vec::new(),
+    )
+}
+
+/// Refresh the vault's PriceCache (permissionless)
+pub fn refresh_prices(
+    vault: &Vault,
+    payer: &Pubkey,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    Ok(build(
+        accounts::RefreshPrices {
+            vault: vault_address,
+            price_cache: pda::price_cache(&vault_address),
+            btc_quote: quotes.btc,
+            eth_quote: quotes.eth,
+            sol_quote: quotes.sol,
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RefreshPrices {
+            _vault_name: vault.name().to_string(),
+        },
+        remaining::oracle_accounts(vault)?,
+    ))
+}
+
+/// Record a NAV checkpoint (permissionless)
+pub fn snapshot_nav(
+    vault: &Vault,
+    payer: &Pubkey,
+    price_input: PriceInput,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    Ok(build(
+        accounts::SnapshotNav {
+            vault: vault_address,
+            nav_history: pda::nav_history(&vault_address),
+            tracking_error: pda::tracking_error(&vault_address),
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: quotes.btc,
+            eth_quote: quotes.eth,
+            sol_quote: quotes.sol,
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SnapshotNav {
+            _vault_name: vault.name().to_string(),
+        },
+        remaining::snapshot_nav_accounts(vault, price_input)?,
+    ))
+}
+
+/// Read-only TVL and share price (simulate and decode `VaultStats`); pass
+/// the vault's QuoteConfig for figures in its quote currency and NAV
+/// denomination
+pub fn get_vault_stats(
+    vault: &Vault,
+    quote_config: Option<&QuoteConfig>,
+    price_input: PriceInput,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    let mut remaining_accounts = remaining::snapshot_nav_accounts(vault, price_input)?;
+    // The FX feed of a NAV currency is read through the oracle adapter
+    let adapter = AccountMeta::new_readonly(oracle_adapter::ID, false);
+    if quote_config.and_then(QuoteConfig::fx_feed).is_some()
+        && !remaining_accounts.contains(&adapter)
+    {
+        remaining_accounts.push(adapter);
+    }
+    Ok(build(
+        view_accounts(vault, quote_config, quotes),
+        instruction::GetVaultStats {
+            _vault_name: vault.name().to_string(),
+        },
+        remaining_accounts,
+    ))
+}
+
+/// Read-only deposit quote (simulate and decode `DepositPreview`)
+pub fn preview_deposit(
+    vault: &Vault,
+    amount: u64,
+    price_input: PriceInput,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    Ok(build(
+        view_accounts(vault, None, quotes),
+        instruction::PreviewDeposit {
+            _vault_name: vault.name().to_string(),
+            amount,
+        },
+        remaining::snapshot_nav_accounts(vault, price_input)?,
+    ))
+}
+
+/// Read-only withdrawal quote (simulate and decode `WithdrawPreview`);
+/// pass `user` to include their exit fee
+pub fn preview_withdraw(
+    vault: &Vault,
+    user: Option<&Pubkey>,
+    shares: u64,
+    price_input: PriceInput,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    Ok(build(
+        accounts::PreviewWithdraw {
+            vault: vault_address,
+            vault_token_mint: vault.vault_token_mint,
+            btc_quote: quotes.btc,
+            eth_quote: quotes.eth,
+            sol_quote: quotes.sol,
+            user_position: user.map(|user| pda::user_position(&vault_address, user)),
+        },
+        instruction::PreviewWithdraw {
+            _vault_name: vault.name().to_string(),
+            shares,
+        },
+        remaining::snapshot_nav_accounts(vault, price_input)?,
+    ))
+}
+
+fn view_accounts(
+    vault: &Vault,
+    quote_config: Option<&QuoteConfig>,
+    quotes: &SwitchboardQuotes,
+) -> accounts::VaultView {
+    let vault_address = VaultId::of(vault).address();
+    accounts::VaultView {
+        vault: vault_address,
+        vault_token_mint: vault.vault_token_mint,
+        btc_quote: quotes.btc,
+        eth_quote: quotes.eth,
+        sol_quote: quotes.sol,
+        quote_config: quote_config_account(&vault_address, quote_config),
+        fx_feed: quote_config.and_then(QuoteConfig::fx_feed),
+    }
+}
+
+/// The vault's QuoteConfig account, if it has one
+fn quote_config_account(vault_address: &Pubkey, config: Option<&QuoteConfig>) -> Option<Pubkey> {
+    config.map(|_| pda::quote_config(vault_address))
+}
+
+/// Rebalance back to target weights (vault admin); pass the vault's
+/// RebalanceConfig if it has one
+pub fn rebalance(
+    vault: &Vault,
+    config: Option<&RebalanceConfig>,
+    quotes: &SwitchboardQuotes,
+    swap_accounts: &[AccountMeta],
+) -> Result<Instruction, ClientError> {
+    Ok(build(
+        accounts::Rebalance {
+            vault: VaultId::of(vault).address(),
+            authority: vault.admin,
+            rebalance_config: rebalance_config_account(vault, config),
+            btc_quote: quotes.btc,
+            eth_quote: quotes.eth,
+            sol_quote: quotes.sol,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::Rebalance {
+            _vault_name: vault.name().to_string(),
+        },
+        remaining::rebalance_accounts(vault, swap_accounts)?,
+    ))
+}
+
+/// The vault's RebalanceConfig account, if it has one
+fn rebalance_config_account(vault: &Vault, config: Option<&RebalanceConfig>) -> Option<Pubkey> {
+    config.map(|_| pda::rebalance_config(&VaultId::of(vault).address()))
+}
+
+/// Read-only rebalance dry run (simulate and decode `RebalancePreview`);
+/// pass the vault's RebalanceConfig if it has one
+pub fn simulate_rebalance(
+    vault: &Vault,
+    config: Option<&RebalanceConfig>,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    Ok(build(
+        accounts::SimulateRebalance {
+            vault: VaultId::of(vault).address(),
+            btc_quote: quotes.btc,
+            eth_quote: quotes.eth,
+            sol_quote: quotes.sol,
+            rebalance_config: rebalance_config_account(vault, config),
+        },
+        instruction::SimulateRebalance {
+            _vault_name: vault.name().to_string(),
+        },
+        remaining::rebalance_accounts(vault, &[])?,
+    ))
+}
+
+/// Arcium accounts of rebalance_confidential and set_hidden_weights, derived
+/// by the Arcium client
+#[derive(Clone, Copy, Debug)]
+pub struct ArciumAccounts {
+    pub sign_pda_account: Pubkey,
+    pub mxe_account: Pubkey,
+    pub mempool_account: Pubkey,
+    pub executing_pool: Pubkey,
+    pub computation_account: Pubkey,
+    pub comp_def_account: Pubkey,
+    pub cluster_account: Pubkey,
+    pub pool_account: Pubkey,
+    pub clock_account: Pubkey,
+    pub arcium_program: Pubkey,
+}
+
+/// Seal the vault's target weights to the rebalancing MXE (vault admin);
+/// `encrypted_weights` are BTC, ETH and SOL, encrypted under `pub_key` and
+/// vault::computation_nonce(vault, computation_offset)
+pub fn set_hidden_weights(
+    vault: &Vault,
+    arcium: &ArciumAccounts,
+    computation_offset: u64,
+    pub_key: [u8; 32],
+    encrypted_weights: Vec<[u8; 32]>,
+) -> Instruction {
+    let vault_address = VaultId::of(vault).address();
+    build(
+        accounts::SetHiddenWeights {
+            vault: vault_address,
+            authority: vault.admin,
+            arcium_mxe_program: vault::REBALANCING_MXE_PROGRAM_ID,
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            arcium_program: arcium.arcium_program,
+            sealed_targets: pda::sealed_targets(&vault_address),
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetHiddenWeights {
+            _name: vault.name().to_string(),
+            computation_offset,
+            pub_key,
+            encrypted_weights,
+        },
+        Vec::new(),
+    )
+}
+
+/// Queue an encrypted rebalancing computation (vault admin, MockOracle
+/// vaults); `encrypted_portfolio` is encrypted under `pub_key` and
+/// vault::computation_nonce(vault, computation_offset), and is empty for a
+/// hidden-weights vault, whose inputs the program prices itself
+pub fn rebalance_confidential(
+    vault: &Vault,
+    arcium: &ArciumAccounts,
+    computation_offset: u64,
+    pub_key: [u8; 32],
+    encrypted_portfolio: Vec<[u8; 32]>,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    let remaining_accounts = if vault.weights_hidden() {
+        remaining::confidential_rebalance_accounts(vault, &[])?
+    } else {
+        Vec::new()
+    };
+    Ok(build(
+        accounts::RebalanceConfidential {
+            vault: vault_address,
+            authority: vault.admin,
+            arcium_mxe_program: vault::REBALANCING_MXE_PROGRAM_ID,
+            sign_pda_account: arcium.sign_pda_account,
+            mxe_account: arcium.mxe_account,
+            mempool_account: arcium.mempool_account,
+            executing_pool: arcium.executing_pool,
+            computation_account: arcium.computation_account,
+            comp_def_account: arcium.comp_def_account,
+            cluster_account: arcium.cluster_account,
+            pool_account: arcium.pool_account,
+            clock_account: arcium.clock_account,
+            arcium_program: arcium.arcium_program,
+            rebalance_result: pda::rebalance_result(computation_offset),
+            sealed_targets: vault
+                .weights_hidden()
+                .then(|| pda::sealed_targets(&vault_address)),
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RebalanceConfidential {
+            _vault_name: vault.name().to_string(),
+            computation_offset,
+            pub_key,
+            encrypted_portfolio,
+        },
+        remaining_accounts,
+    ))
+}
+
+/// Execute the swaps of a completed confidential rebalancing computation
+/// (vault admin); the drifts come from the MXE's RebalanceResult
+pub fn apply_confidential_rebalance(
+    vault: &Vault,
+    config: Option<&RebalanceConfig>,
+    computation_offset: u64,
+    swap_accounts: &[AccountMeta],
+) -> Result<Instruction, ClientError> {
+    Ok(build(
+        accounts::ApplyConfidentialRebalance {
+            vault: VaultId::of(vault).address(),
+            authority: vault.admin,
+            rebalance_result: pda::rebalance_result(computation_offset),
+            rebalance_config: rebalance_config_account(vault, config),
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ApplyConfidentialRebalance {
+            _vault_name: vault.name().to_string(),
+            computation_offset,
+        },
+        remaining::confidential_rebalance_accounts(vault, swap_accounts)?,
+    ))
+}
+
+/// Swap directly through an Orca Whirlpool (vault admin, Orca backend)
+///
+/// `whirlpool_accounts` are [whirlpool, token_vault_a, token_vault_b,
+/// tick_array_0, tick_array_1, tick_array_2, oracle].
+pub fn swap_via_orca(
+    id: &VaultId,
+    vault_token_a: &Pubkey,
+    vault_token_b: &Pubkey,
+    whirlpool_accounts: [Pubkey; 7],
+    amount_in: u64,
+    min_amount_out: u64,
+    a_to_b: bool,
+) -> Instruction {
+    let [whirlpool, token_vault_a, token_vault_b, tick_array_0, tick_array_1, tick_array_2, oracle] =
+        whirlpool_accounts;
+    build(
+        accounts::SwapViaOrca {
+            vault: id.address(),
+            authority: id.admin,
+            vault_token_a: *vault_token_a,
+            vault_token_b: *vault_token_b,
+            whirlpool,
+            token_vault_a,
+            token_vault_b,
+            tick_array_0,
+            tick_array_1,
+            tick_array_2,
+            oracle,
+            whirlpool_program: ORCA_WHIRLPOOL_PROGRAM_ID,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SwapViaOrca {
+            _name: id.name.clone(),
+            amount_in,
+            min_amount_out,
+            a_to_b,
+        },
+        Vec::new(),
+    )
+}
+
+/// Orca Whirlpool program
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: Pubkey =
+    anchor_lang::pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+pub fn set_price_source(
+    id: &VaultId,
+    authority: &Pubkey,
+    price_source: PriceSource,
+    mock_oracle: Option<Pubkey>,
+) -> Instruction {
+    build(
+        set_price_source_accounts(id, authority),
+        instruction::SetPriceSource {
+            _name: id.name.clone(),
+            price_source,
+            mock_oracle,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_cross_checked_pricing(
+    id: &VaultId,
+    authority: &Pubkey,
+    mock_oracle: Pubkey,
+    max_divergence_bps: u16,
+) -> Instruction {
+    build(
+        set_price_source_accounts(id, authority),
+        instruction::SetCrossCheckedPricing {
+            _name: id.name.clone(),
+            mock_oracle,
+            max_divergence_bps,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn configure_price_feeds(
+    id: &VaultId,
+    authority: &Pubkey,
+    price_feeds: Vec<PriceFeed>,
+    max_divergence_bps: u16,
+) -> Instruction {
+    build(
+        set_price_source_accounts(id, authority),
+        instruction::ConfigurePriceFeeds {
+            _name: id.name.clone(),
+            price_feeds,
+            max_divergence_bps,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_circuit_breaker(id: &VaultId, authority: &Pubkey, max_move_bps: u16) -> Instruction {
+    build(
+        set_price_source_accounts(id, authority),
+        instruction::SetCircuitBreaker {
+            _name: id.name.clone(),
+            max_move_bps,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn clear_circuit_breaker(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        set_price_source_accounts(id, authority),
+        instruction::ClearCircuitBreaker {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Set (or with None, remove) the vault's guardian; `authority` is the
+/// admin, or governance
+pub fn set_guardian(
+    id: &VaultId,
+    authority: &Pubkey,
+    guardian: Option<Pubkey>,
+    may_pause_rebalancing: bool,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SetGuardian {
+            vault: vault_address,
+            vault_guardian: pda::vault_guardian(&vault_address),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetGuardian {
+            _name: id.name.clone(),
+            guardian,
+            may_pause_rebalancing,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn guardian_pause(id: &VaultId, guardian: &Pubkey, pause_rebalancing: bool) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::GuardianPause {
+            vault: vault_address,
+            vault_guardian: pda::vault_guardian(&vault_address),
+            guardian: *guardian,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::GuardianPause {
+            _name: id.name.clone(),
+            pause_rebalancing,
+        },
+        Vec::new(),
+    )
+}
+
+/// Undo guardian_pause's deposit pause; `authority` is the admin, or
+/// governance
+pub fn resume_deposits(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        guardian_resume_accounts(id, authority),
+        instruction::ResumeDeposits {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Undo guardian_pause's rebalancing pause; `authority` is the admin, or
+/// governance
+pub fn resume_rebalancing(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        guardian_resume_accounts(id, authority),
+        instruction::ResumeRebalancing {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+fn guardian_resume_accounts(id: &VaultId, authority: &Pubkey) -> accounts::GuardianResume {
+    accounts::GuardianResume {
+        vault: id.address(),
+        authority: *authority,
+        event_authority: pda::event_authority(),
+        program: vault::ID,
+    }
+}
+
+pub fn set_twap_window(id: &VaultId, authority: &Pubkey, window_secs: u32) -> Instruction {
+    build(
+        set_price_source_accounts(id, authority),
+        instruction::SetTwapWindow {
+            _name: id.name.clone(),
+            window_secs,
+        },
+        Vec::new(),
+    )
+}
+
+fn set_price_source_accounts(id: &VaultId, authority: &Pubkey) -> accounts::SetPriceSource {
+    accounts::SetPriceSource {
+        vault: id.address(),
+        authority: *authority,
+        event_authority: pda::event_authority(),
+        program: vault::ID,
+    }
+}
+
+pub fn set_strategy(id: &VaultId, authority: &Pubkey, strategy: Pubkey) -> Instruction {
+    build(
+        accounts::SetStrategy {
+            vault: id.address(),
+            authority: *authority,
+            strategy_account: strategy,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetStrategy {
+            _name: id.name.clone(),
+            strategy,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn remove_strategy(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::RemoveStrategy {
+            vault: id.address(),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RemoveStrategy {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Liquid-unstake `msol_amount` of the strategy's mSOL into the vault's idle SOL
+///
+/// Passes the StrategyValue, so it must exist: send `sync_strategy_value`
+/// first (in the same transaction, it also keeps the value fresh).
+pub fn divest_from_strategy(
+    id: &VaultId,
+    authority: &Pubkey,
+    marinade: &MarinadeAccounts,
+    msol_amount: u64,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::DivestFromStrategy {
+            vault: vault_address,
+            authority: *authority,
+            sol_buffer: pda::sol_buffer(&vault_address),
+            strategy_value: Some(pda::strategy_value(&vault_address)),
+            strategy_account: pda::marinade_strategy(&vault_address),
+            marinade_strategy_program: marinade_strategy::ID,
+            marinade_program: marinade.program,
+            marinade_state: marinade.state,
+            msol_mint: marinade.msol_mint,
+            liq_pool_msol_leg: marinade.liq_pool_msol_leg,
+            liq_pool_sol_leg_pda: marinade.liq_pool_sol_leg_pda,
+            strategy_msol_ata: strategy_ix::msol_ata(&vault_address, marinade),
+            treasury_msol_account: marinade.treasury_msol_account,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DivestFromStrategy {
+            _name: id.name.clone(),
+            msol_amount,
+        },
+        Vec::new(),
+    )
+}
+
+/// Sync the vault's StrategyValue from its strategy (permissionless)
+pub fn sync_strategy_value(
+    id: &VaultId,
+    payer: &Pubkey,
+    marinade: &MarinadeAccounts,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SyncStrategyValue {
+            vault: vault_address,
+            strategy_value: pda::strategy_value(&vault_address),
+            strategy_account: pda::marinade_strategy(&vault_address),
+            marinade_strategy_program: marinade_strategy::ID,
+            marinade_state: marinade.state,
+            msol_mint: marinade.msol_mint,
+            strategy_msol_ata: strategy_ix::msol_ata(&vault_address, marinade),
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SyncStrategyValue {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Set how old a strategy value sync deposits and withdrawals accept (0 = default)
+pub fn set_strategy_value_max_age(
+    id: &VaultId,
+    authority: &Pubkey,
+    max_age_secs: u32,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SetStrategyValueMaxAge {
+            vault: vault_address,
+            strategy_value: pda::strategy_value(&vault_address),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetStrategyValueMaxAge {
+            _name: id.name.clone(),
+            max_age_secs,
+        },
+        Vec::new(),
+    )
+}
+
+/// Point holders at `successor`; `soft_closed` also pauses deposits
+pub fn suggest_alternative(
+    id: &VaultId,
+    authority: &Pubkey,
+    successor: &Pubkey,
+    soft_closed: bool,
+) -> Instruction {
+    build(
+        accounts::SuggestAlternative {
+            vault: id.address(),
+            successor_vault: *successor,
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SuggestAlternative {
+            _name: id.name.clone(),
+            soft_closed,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn clear_alternative(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::ClearAlternative {
+            vault: id.address(),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClearAlternative {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Move `shares` of `vault` into its successor, valuing both at the prices
+/// `extra` selects
+pub fn migrate_position(
+    vault: &Vault,
+    successor: &Vault,
+    user: &Pubkey,
+    shares: u64,
+    extra: &ExtraAccounts,
+) -> Result<Instruction, ClientError> {
+    let id = VaultId::of(vault);
+    let successor_id = VaultId::of(successor);
+    let mut remaining = remaining::snapshot_nav_accounts(vault, extra.price_input)?;
+    remaining.extend(remaining::snapshot_nav_accounts(successor, extra.price_input)?);
+
+    Ok(build(
+        accounts::MigratePosition {
+            vault: id.address(),
+            vault_token_mint: vault.vault_token_mint,
+            user_shares_ata: share_ata(vault, user),
+            successor_vault: successor_id.address(),
+            successor_token_mint: successor.vault_token_mint,
+            user_successor_shares_ata: share_ata(successor, user),
+            user: *user,
+            btc_quote: extra.quotes.btc,
+            eth_quote: extra.quotes.eth,
+            sol_quote: extra.quotes.sol,
+            share_token_program: vault.share_token_program(),
+            successor_share_token_program: successor.share_token_program(),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::MigratePosition {
+            _name: id.name,
+            _successor_name: successor_id.name,
+            shares,
+        },
+        remaining,
+    ))
+}
+
+pub fn set_lockup(id: &VaultId, authority: &Pubkey, lockup_secs: u32) -> Instruction {
+    build(
+        accounts::SetLockup {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetLockup {
+            _name: id.name.clone(),
+            lockup_secs,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_governance(
+    id: &VaultId,
+    authority: &Pubkey,
+    governance: Option<Pubkey>,
+    governance_mode: bool,
+) -> Instruction {
+    build(
+        accounts::SetGovernance {
+            vault: id.address(),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetGovernance {
+            _name: id.name.clone(),
+            governance,
+            governance_mode,
+        },
+        Vec::new(),
+    )
+}
+
+/// New target weights, in vault.assets order (must sum to 100)
+pub fn update_composition(id: &VaultId, authority: &Pubkey, weights: Vec<u8>) -> Instruction {
+    build(
+        accounts::UpdateComposition {
+            vault: id.address(),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::UpdateComposition {
+            _name: id.name.clone(),
+            weights,
+        },
+        Vec::new(),
+    )
+}
+
+/// Switch market-cap index mode; `authority` pays for the index account on first use
+pub fn configure_market_cap_index(
+    id: &VaultId,
+    authority: &Pubkey,
+    enabled: bool,
+    supply_authority: Option<Pubkey>,
+    recompute_interval_secs: i64,
+    max_weight_change: u8,
+) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::ConfigureMarketCapIndex {
+            vault,
+            market_cap_index: pda::market_cap_index(&vault),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ConfigureMarketCapIndex {
+            _name: id.name.clone(),
+            enabled,
+            supply_authority,
+            recompute_interval_secs,
+            max_weight_change,
+        },
+        Vec::new(),
+    )
+}
+
+/// Circulating supplies in whole tokens, in vault.assets order
+pub fn set_asset_supplies(id: &VaultId, authority: &Pubkey, supplies: Vec<u64>) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::SetAssetSupplies {
+            vault,
+            market_cap_index: pda::market_cap_index(&vault),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetAssetSupplies {
+            _name: id.name.clone(),
+            supplies,
+        },
+        Vec::new(),
+    )
+}
+
+/// Recompute a market-cap index vault's weights (permissionless)
+pub fn recompute_weights(
+    vault: &Vault,
+    price_input: PriceInput,
+    quotes: &SwitchboardQuotes,
+) -> Result<Instruction, ClientError> {
+    let vault_address = VaultId::of(vault).address();
+    Ok(build(
+        accounts::RecomputeWeights {
+            vault: vault_address,
+            market_cap_index: pda::market_cap_index(&vault_address),
+            btc_quote: quotes.btc,
+            eth_quote: quotes.eth,
+            sol_quote: quotes.sol,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RecomputeWeights {
+            _name: vault.name().to_string(),
+        },
+        remaining::price_accounts(vault, price_input)?,
+    ))
+}
+
+/// Replace the vault's glide path (empty clears it); `authority` pays for
+/// the schedule account on first use
+pub fn set_composition_schedule(
+    id: &VaultId,
+    authority: &Pubkey,
+    steps: Vec<ScheduleStep>,
+) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::SetCompositionSchedule {
+            vault,
+            vault_schedule: pda::vault_schedule(&vault),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetCompositionSchedule {
+            _name: id.name.clone(),
+            steps,
+        },
+        Vec::new(),
+    )
+}
+
+/// Apply the latest due glide path step (permissionless)
+pub fn apply_schedule_step(id: &VaultId) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::ApplyScheduleStep {
+            vault,
+            vault_schedule: pda::vault_schedule(&vault),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ApplyScheduleStep {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Set the price feed of each asset of a per-asset priced vault, plus the
+/// SOL feed; `authority` pays for the oracles account on first use
+pub fn configure_asset_oracles(
+    id: &VaultId,
+    authority: &Pubkey,
+    feeds: Vec<PriceFeed>,
+    sol_feed: PriceFeed,
+) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::ConfigureAssetOracles {
+            vault,
+            asset_oracles: pda::asset_oracles(&vault),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ConfigureAssetOracles {
+            _name: id.name.clone(),
+            feeds,
+            sol_feed,
+        },
+        Vec::new(),
+    )
+}
+
+/// Make the vault an LST index with a rate source per asset (empty turns
+/// LST pricing off); `authority` pays for the basket account on first use
+pub fn configure_lst_basket(id: &VaultId, authority: &Pubkey, pools: Vec<LstPool>) -> Instruction {
+    let vault = id.address();
+    let pool_accounts = lst_pool_accounts(&pools);
+    build(
+        accounts::ConfigureLstBasket {
+            vault,
+            lst_basket: pda::lst_basket(&vault),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ConfigureLstBasket {
+            _name: id.name.clone(),
+            pools,
+        },
+        pool_accounts,
+    )
+}
+
+/// Re-read an LST index's exchange rates from the pools in its fetched
+/// `basket` (permissionless)
+pub fn refresh_lst_rates(id: &VaultId, basket: &LstBasket) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::RefreshLstRates {
+            vault,
+            lst_basket: pda::lst_basket(&vault),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RefreshLstRates {
+            _name: id.name.clone(),
+        },
+        lst_pool_accounts(&basket.pools),
+    )
+}
+
+/// Pool account of each LST basket asset, as remaining_accounts
+fn lst_pool_accounts(pools: &[LstPool]) -> Vec<AccountMeta> {
+    pools
+        .iter()
+        .map(|pool| AccountMeta::new_readonly(pool.pool, false))
+        .collect()
+}
+
+pub fn set_exit_fee(
+    id: &VaultId,
+    authority: &Pubkey,
+    max_fee_bps: u16,
+    window_secs: u32,
+) -> Instruction {
+    build(
+        accounts::SetExitFee {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetExitFee {
+            _name: id.name.clone(),
+            max_fee_bps,
+            window_secs,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_max_slippage(id: &VaultId, authority: &Pubkey, max_slippage_bps: u16) -> Instruction {
+    build(
+        accounts::SetMaxSlippage {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetMaxSlippage {
+            _name: id.name.clone(),
+            max_slippage_bps,
+        },
+        Vec::new(),
+    )
+}
+
+/// Create or update the share token's Metaplex metadata; `authority` pays
+pub fn set_share_metadata(
+    id: &VaultId,
+    authority: &Pubkey,
+    share_name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Instruction {
+    let share_mint = id.share_mint();
+    build(
+        accounts::SetShareMetadata {
+            vault: id.address(),
+            vault_token_mint: share_mint,
+            metadata: pda::share_metadata(&share_mint),
+            token_metadata_program: vault::TOKEN_METADATA_PROGRAM_ID,
+            authority: *authority,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetShareMetadata {
+            _name: id.name.clone(),
+            share_name: share_name.to_string(),
+            symbol: symbol.to_string(),
+            uri: uri.to_string(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Convert a vault stored in an older layout; `payer` funds the extra rent
+pub fn migrate_vault(id: &VaultId, payer: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateVault {
+            vault: id.address(),
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::MigrateVault {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Add a vault created before the registry existed; `payer` covers rent
+pub fn register_vault(id: &VaultId, payer: &Pubkey) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::RegisterVault {
+            vault,
+            vault_registry: pda::vault_registry(),
+            vault_registration: pda::vault_registration(&vault),
+            payer: *payer,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RegisterVault {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Set the vault's display metadata; `authority` pays for the account on first use
+pub fn set_vault_metadata(
+    id: &VaultId,
+    authority: &Pubkey,
+    description: &str,
+    icon_uri: &str,
+    category: &str,
+    risk_level: u8,
+) -> Instruction {
+    let vault = id.address();
+    build(
+        accounts::SetVaultMetadata {
+            vault,
+            vault_metadata: pda::vault_metadata(&vault),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SetVaultMetadata {
+            _name: id.name.clone(),
+            description: description.to_string(),
+            icon_uri: icon_uri.to_string(),
+            category: category.to_string(),
+            risk_level,
+        },
+        Vec::new(),
+    )
+}
+
+/// Create the vault's address lookup table at `recent_slot` (a recent,
+/// finalized slot); returns the instruction and the table address
+pub fn create_lookup_table(
+    id: &VaultId,
+    authority: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    let vault = id.address();
+    let (lookup_table, _) = derive_lookup_table_address(&vault, recent_slot);
+    let ix = build(
+        accounts::CreateLookupTable {
+            vault,
+            vault_lookup_table: pda::vault_lookup_table(&vault),
+            lookup_table,
+            authority: *authority,
+            address_lookup_table_program: vault::LOOKUP_TABLE_PROGRAM_ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CreateLookupTable {
+            _name: id.name.clone(),
+            recent_slot,
+        },
+        Vec::new(),
+    );
+    (ix, lookup_table)
+}
+
+/// Add `addresses` to the vault's lookup table, one instruction per
+/// MAX_ADDRESSES_PER_EXTEND addresses; send them in separate transactions
+pub fn extend_lookup_table(
+    id: &VaultId,
+    authority: &Pubkey,
+    lookup_table: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    let vault = id.address();
+    addresses
+        .chunks(vault::MAX_ADDRESSES_PER_EXTEND)
+        .map(|chunk| {
+            build(
+                accounts::ExtendLookupTable {
+                    vault,
+                    vault_lookup_table: pda::vault_lookup_table(&vault),
+                    lookup_table: *lookup_table,
+                    authority: *authority,
+                    address_lookup_table_program: vault::LOOKUP_TABLE_PROGRAM_ID,
+                    system_program: system_program::ID,
+                    event_authority: pda::event_authority(),
+                    program: vault::ID,
+                },
+                instruction::ExtendLookupTable {
+                    _name: id.name.clone(),
+                    addresses: chunk.to_vec(),
+                },
+                Vec::new(),
+            )
+        })
+        .collect()
+}
+
+/// Cap the share of TVL one rebalance may trade, in bps (0 = no cap)
+pub fn set_max_turnover(id: &VaultId, authority: &Pubkey, max_turnover_bps: u16) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SetRebalanceConfig {
+            vault: vault_address,
+            rebalance_config: pda::rebalance_config(&vault_address),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetMaxTurnover {
+            _name: id.name.clone(),
+            max_turnover_bps_per_rebalance: max_turnover_bps,
+        },
+        Vec::new(),
+    )
+}
+
+/// Quote the vault in SOL or USDC; `quote_mint` is the USDC mint (any mint
+/// for SOL)
+pub fn set_quote_currency(
+    id: &VaultId,
+    authority: &Pubkey,
+    currency: QuoteCurrency,
+    quote_mint: &Pubkey,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SetQuoteCurrency {
+            vault: vault_address,
+            quote_config: pda::quote_config(&vault_address),
+            quote_mint: *quote_mint,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetQuoteCurrency {
+            _name: id.name.clone(),
+            currency,
+        },
+        Vec::new(),
+    )
+}
+
+/// Denominate the vault's NAV in `currency` (ISO 4217, e.g. *b"EUR"), priced
+/// in USD by the Switchboard `fx_feed` (any account for *b"USD"), which the
+/// vault reads through the oracle adapter
+pub fn set_nav_currency(
+    id: &VaultId,
+    authority: &Pubkey,
+    currency: [u8; 3],
+    fx_feed: &Pubkey,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SetNavCurrency {
+            vault: vault_address,
+            quote_config: pda::quote_config(&vault_address),
+            fx_feed: *fx_feed,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetNavCurrency {
+            _name: id.name.clone(),
+            currency,
+        },
+        vec![AccountMeta::new_readonly(oracle_adapter::ID, false)],
+    )
+}
+
+/// Set the smallest leg a rebalance swaps, in micro-USD (0 = the $1 default),
+/// and per-asset dust thresholds in base units, in vault.assets order
+pub fn set_dust_thresholds(
+    id: &VaultId,
+    authority: &Pubkey,
+    min_swap_usd: u64,
+    dust_thresholds: Vec<u64>,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::SetRebalanceConfig {
+            vault: vault_address,
+            rebalance_config: pda::rebalance_config(&vault_address),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetDustThresholds {
+            _name: id.name.clone(),
+            min_swap_usd,
+            dust_thresholds,
+        },
+        Vec::new(),
+    )
+}
+
+/// Set the fewest shares a deposit may mint (0 = any nonzero amount)
+pub fn set_min_deposit_shares(id: &VaultId, authority: &Pubkey, min_shares: u32) -> Instruction {
+    build(
+        accounts::SetMinDepositShares {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetMinDepositShares {
+            _name: id.name.clone(),
+            min_shares,
+        },
+        Vec::new(),
+    )
+}
+
+/// Release the vault's operation lock, dropping a pending confidential rebalance
+pub fn clear_operation_lock(id: &VaultId, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::ClearOperationLock {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::ClearOperationLock {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Release the lock of a confidential rebalance that has timed out
+/// (permissionless)
+pub fn release_stale_rebalance(id: &VaultId, computation_offset: u64) -> Instruction {
+    build(
+        accounts::ReleaseStaleRebalance {
+            vault: id.address(),
+            rebalance_result: pda::rebalance_result(computation_offset),
+        },
+        instruction::ReleaseStaleRebalance {
+            _name: id.name.clone(),
+            computation_offset,
+        },
+        Vec::new(),
+    )
+}
+
+/// Set the creation unit size; 0 disables AP creation and redemption
+pub fn set_creation_unit(id: &VaultId, authority: &Pubkey, unit_shares: u64) -> Instruction {
+    build(
+        accounts::SetCreationUnit {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetCreationUnit {
+            _name: id.name.clone(),
+            unit_shares,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn add_authorized_participant(
+    id: &VaultId,
+    authority: &Pubkey,
+    participant: &Pubkey,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::AddAuthorizedParticipant {
+            vault: vault_address,
+            authorized_participant: pda::authorized_participant(&vault_address, participant),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::AddAuthorizedParticipant {
+            _name: id.name.clone(),
+            participant: *participant,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn remove_authorized_participant(
+    id: &VaultId,
+    authority: &Pubkey,
+    participant: &Pubkey,
+) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::RemoveAuthorizedParticipant {
+            vault: vault_address,
+            authorized_participant: pda::authorized_participant(&vault_address, participant),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RemoveAuthorizedParticipant {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Create `units` creation units from the participant's ATAs
+pub fn create_units(vault: &Vault, participant: &Pubkey, units: u64) -> Instruction {
+    let vault_address = VaultId::of(vault).address();
+    build(
+        accounts::CreateUnits {
+            vault: vault_address,
+            authorized_participant: pda::authorized_participant(&vault_address, participant),
+            participant: *participant,
+            participant_shares_ata: share_ata(vault, participant),
+            vault_token_mint: vault.vault_token_mint,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: remaining::token_2022_program(vault),
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CreateUnits {
+            _name: vault.name().to_string(),
+            units,
+        },
+        remaining::in_kind_accounts(vault, participant),
+    )
+}
+
+/// Redeem `units` creation units into the participant's ATAs
+pub fn redeem_units(vault: &Vault, participant: &Pubkey, units: u64) -> Instruction {
+    let vault_address = VaultId::of(vault).address();
+    build(
+        accounts::RedeemUnits {
+            vault: vault_address,
+            authorized_participant: pda::authorized_participant(&vault_address, participant),
+            participant: *participant,
+            participant_shares_ata: share_ata(vault, participant),
+            vault_token_mint: vault.vault_token_mint,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: remaining::token_2022_program(vault),
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RedeemUnits {
+            _name: vault.name().to_string(),
+            units,
+        },
+        remaining::in_kind_accounts(vault, participant),
+    )
+}
+
+/// Freeze deposits and start the wind-down grace period
+pub fn begin_wind_down(id: &VaultId, authority: &Pubkey, grace_period_secs: i64) -> Instruction {
+    build(
+        accounts::BeginWindDown {
+            vault: id.address(),
+            authority: *authority,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::BeginWindDown {
+            _name: id.name.clone(),
+            grace_period_secs,
+        },
+        Vec::new(),
+    )
+}
+
+/// Redeem `shares` of a winding-down vault in kind into the user's ATAs
+pub fn redeem_wind_down(vault: &Vault, user: &Pubkey, shares: u64) -> Instruction {
+    build(
+        accounts::RedeemWindDown {
+            vault: VaultId::of(vault).address(),
+            user: *user,
+            user_shares_ata: share_ata(vault, user),
+            vault_token_mint: vault.vault_token_mint,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: remaining::token_2022_program(vault),
+            rent: sysvar::rent::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::RedeemWindDown {
+            _name: vault.name().to_string(),
+            shares,
+        },
+        remaining::in_kind_accounts(vault, user),
+    )
+}
+
+/// Create the admin's ATA for each asset if it does not exist; close_vault
+/// needs them wherever the vault ATA still holds dust
+pub fn create_dust_receiver_accounts(payer: &Pubkey, vault: &Vault) -> Vec<Instruction> {
+    vault
+        .assets()
+        .iter()
+        .map(|asset| {
+            create_associated_token_account_idempotent(
+                payer,
+                &vault.admin,
+                &asset.mint,
+                &remaining::asset_token_program(vault, asset),
+            )
+        })
+        .collect()
+}
+
+/// Close a wound-down vault; `registered` says whether it has a registry entry
+pub fn close_vault(vault: &Vault, authority: &Pubkey, registered: bool) -> Instruction {
+    let vault_address = VaultId::of(vault).address();
+    build(
+        accounts::CloseVault {
+            vault: vault_address,
+            admin: vault.admin,
+            vault_token_mint: vault.vault_token_mint,
+            vault_registration: registered.then(|| pda::vault_registration(&vault_address)),
+            authority: *authority,
+            share_token_program: vault.share_token_program(),
+            token_program: anchor_spl::token::ID,
+            token_2022_program: remaining::token_2022_program(vault),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CloseVault {
+            _name: vault.name().to_string(),
+        },
+        remaining::close_vault_accounts(vault, &vault.admin),
+    )
+}
+
+pub fn set_mock_pool(
+    id: &VaultId,
+    authority: &Pubkey,
+    fee_bps: u16,
+    depth_usd: u64,
+) -> Instruction {
+    build(
+        accounts::SetMockPool {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetMockPool {
+            _name: id.name.clone(),
+            fee_bps,
+            depth_usd,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_deposit_fee(
+    id: &VaultId,
+    authority: &Pubkey,
+    fee_bps: u16,
+    referral_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetDepositFee {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetDepositFee {
+            _name: id.name.clone(),
+            fee_bps,
+            referral_share_bps,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_swap_backend(id: &VaultId, authority: &Pubkey, backend: SwapBackend) -> Instruction {
+    build(
+        accounts::SetSwapBackend {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetSwapBackend {
+            _name: id.name.clone(),
+            backend,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn set_staker_fee_share(
+    id: &VaultId,
+    authority: &Pubkey,
+    staker_fee_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetStakerFeeShare {
+            vault: id.address(),
+            authority: *authority,
+        },
+        instruction::SetStakerFeeShare {
+            _name: id.name.clone(),
+            staker_fee_share_bps,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn initialize_protocol_config(
+    authority: &Pubkey,
+    treasury: Pubkey,
+    creator_fee_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::InitializeProtocolConfig {
+            protocol_config: pda::protocol_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeProtocolConfig {
+            treasury,
+            creator_fee_share_bps,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn update_protocol_config(
+    authority: &Pubkey,
+    treasury: Pubkey,
+    creator_fee_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::UpdateProtocolConfig {
+            protocol_config: pda::protocol_config(),
+            authority: *authority,
+        },
+        instruction::UpdateProtocolConfig {
+            treasury,
+            creator_fee_share_bps,
+        },
+        Vec::new(),
+    )
+}
+
+/// Replace the TVL tiers that lower the protocol's fee share for larger
+/// vaults (ascending by min_tvl_usd; empty turns them off)
+pub fn set_protocol_fee_tiers(authority: &Pubkey, fee_tiers: Vec<FeeTier>) -> Instruction {
+    build(
+        accounts::UpdateProtocolConfig {
+            protocol_config: pda::protocol_config(),
+            authority: *authority,
+        },
+        instruction::SetProtocolFeeTiers { fee_tiers },
+        Vec::new(),
+    )
+}
+
+/// Replace the mints accepted besides Portal wBTC/wETH; the first call
+/// creates the whitelist and starts enforcing it
+pub fn set_asset_whitelist(
+    authority: &Pubkey,
+    btc_alternates: Vec<Pubkey>,
+    eth_alternates: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::SetAssetWhitelist {
+            protocol_config: pda::protocol_config(),
+            asset_whitelist: pda::asset_whitelist(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetAssetWhitelist {
+            btc_alternates,
+            eth_alternates,
+        },
+        Vec::new(),
+    )
+}
+
+/// Set the Switchboard feed hashes of BTC, ETH and SOL (config authority only)
+pub fn set_switchboard_config(authority: &Pubkey, feed_hashes: [[u8; 32]; 3]) -> Instruction {
+    build(
+        accounts::SetSwitchboardConfig {
+            protocol_config: pda::protocol_config(),
+            switchboard_config: pda::switchboard_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetSwitchboardConfig { feed_hashes },
+        Vec::new(),
+    )
+}
+
+/// Set the Chainlink Data Streams feed ids of BTC, ETH and SOL (config
+/// authority only)
+pub fn set_chainlink_config(authority: &Pubkey, feed_ids: [[u8; 32]; 3]) -> Instruction {
+    build(
+        accounts::SetChainlinkConfig {
+            protocol_config: pda::protocol_config(),
+            chainlink_config: pda::chainlink_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetChainlinkConfig { feed_ids },
+        Vec::new(),
+    )
+}
+
+/// Verify a Chainlink Data Streams report and store its price
+///
+/// `signed_report` is the snappy-compressed full report the verifier takes;
+/// `config_digest` is the first 32 bytes of the uncompressed one.
+/// `relayer` must be allowed by `access_controller`, the verifier's access
+/// controller on the cluster.
+pub fn post_chainlink_report(
+    relayer: &Pubkey,
+    access_controller: &Pubkey,
+    config_digest: &[u8; 32],
+    signed_report: Vec<u8>,
+) -> Instruction {
+    build(
+        accounts::PostChainlinkReport {
+            chainlink_config: pda::chainlink_config(),
+            verifier_account: pda::chainlink_verifier(),
+            access_controller: *access_controller,
+            report_config: pda::chainlink_report_config(config_digest),
+            verifier_program: chainlink::VERIFIER_PROGRAM_ID,
+            relayer: *relayer,
+        },
+        instruction::PostChainlinkReport { signed_report },
+        Vec::new(),
+    )
+}
+
+/// Set the Pyth Lazer signers and BTC/ETH/SOL feed ids (config authority only)
+pub fn set_pyth_lazer_config(
+    authority: &Pubkey,
+    trusted_signers: Vec<Pubkey>,
+    feed_ids: [u32; 3],
+) -> Instruction {
+    build(
+        accounts::SetPythLazerConfig {
+            protocol_config: pda::protocol_config(),
+            pyth_lazer_config: pda::pyth_lazer_config(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetPythLazerConfig {
+            trusted_signers,
+            feed_ids,
+        },
+        Vec::new(),
+    )
+}
+
+/// Leading bytes of a Pyth Lazer update in the Solana format
+const PYTH_LAZER_SOLANA_MAGIC: [u8; 4] = [0xb9, 0x01, 0x1a, 0x82];
+
+/// Ed25519 verification of a Pyth Lazer update in the Solana format (magic,
+/// signature, public key, payload length and payload), to place right
+/// before a PythLazer vault's pricing instruction; None if `update` isn't
+/// in that format
+pub fn verify_pyth_lazer_update(update: &[u8]) -> Option<Instruction> {
+    let rest = update.strip_prefix(&PYTH_LAZER_SOLANA_MAGIC)?;
+    let signature: [u8; 64] = rest.get(..64)?.try_into().ok()?;
+    let signer = Pubkey::try_from(rest.get(64..96)?).ok()?;
+    let len = u16::from_le_bytes(rest.get(96..98)?.try_into().ok()?) as usize;
+    let payload = rest.get(98..98 + len)?;
+    Some(Instruction {
+        program_id: ed25519_program::ID,
+        accounts: Vec::new(),
+        data: signed_prices::ed25519_instruction_data(&signer, &signature, payload),
+    })
+}
+
+/// Create or replace the template `template_name` (config authority only)
+pub fn set_vault_template(
+    authority: &Pubkey,
+    template_name: &str,
+    preset: VaultPreset,
+) -> Instruction {
+    build(
+        accounts::SetVaultTemplate {
+            protocol_config: pda::protocol_config(),
+            vault_template: pda::vault_template(template_name),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::SetVaultTemplate {
+            template_name: template_name.to_string(),
+            preset,
+        },
+        Vec::new(),
+    )
+}
+
+/// Delete the template `template_name`, refunding its rent to `authority`
+pub fn remove_vault_template(authority: &Pubkey, template_name: &str) -> Instruction {
+    build(
+        accounts::RemoveVaultTemplate {
+            protocol_config: pda::protocol_config(),
+            vault_template: pda::vault_template(template_name),
+            authority: *authority,
+        },
+        instruction::RemoveVaultTemplate {
+            _template_name: template_name.to_string(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Pay the protocol's fees to `treasury` (the ProtocolConfig treasury; permissionless)
+pub fn claim_protocol_fees(id: &VaultId, treasury: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimProtocolFees {
+            vault: id.address(),
+            protocol_config: pda::protocol_config(),
+            treasury: *treasury,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClaimProtocolFees {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+pub fn claim_creator_fees(id: &VaultId) -> Instruction {
+    build(
+        accounts::ClaimCreatorFees {
+            vault: id.address(),
+            protocol_config: pda::protocol_config(),
+            authority: id.admin,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClaimCreatorFees {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Token program owning a mint: Token-2022 or SPL Token
+fn mint_token_program(token_2022: bool) -> Pubkey {
+    if token_2022 {
+        anchor_spl::token_2022::ID
+    } else {
+        anchor_spl::token::ID
+    }
+}
+
+/// Create `treasury`'s ATA for `mint` if it does not exist; sweeps need it
+pub fn create_treasury_token_account(
+    payer: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    token_2022: bool,
+) -> Instruction {
+    create_associated_token_account_idempotent(
+        payer,
+        treasury,
+        mint,
+        &mint_token_program(token_2022),
+    )
+}
+
+/// Sweep the vault's ATA of a non-composition `mint` to `treasury`'s ATA
+/// (the ProtocolConfig treasury); pass `token_2022` for Token-2022 mints
+pub fn sweep_unknown_token(
+    id: &VaultId,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    token_2022: bool,
+) -> Instruction {
+    let vault_address = id.address();
+    let token_program = mint_token_program(token_2022);
+    build(
+        accounts::SweepUnknownToken {
+            vault: vault_address,
+            protocol_config: pda::protocol_config(),
+            mint: *mint,
+            vault_token_account: get_associated_token_address_with_program_id(
+                &vault_address,
+                mint,
+                &token_program,
+            ),
+            treasury_token_account: get_associated_token_address_with_program_id(
+                treasury,
+                mint,
+                &token_program,
+            ),
+            authority: *authority,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: token_2022.then_some(anchor_spl::token_2022::ID),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::SweepUnknownToken {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Close the vault's empty ATA of a non-composition `mint`, returning its
+/// rent to the admin; pass `token_2022` for Token-2022 mints
+pub fn close_asset_ata(
+    id: &VaultId,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    token_2022: bool,
+) -> Instruction {
+    let vault_address = id.address();
+    let token_program = mint_token_program(token_2022);
+    build(
+        accounts::CloseAssetAta {
+            vault: vault_address,
+            vault_token_account: get_associated_token_address_with_program_id(
+                &vault_address,
+                mint,
+                &token_program,
+            ),
+            admin: id.admin,
+            authority: *authority,
+            token_program: anchor_spl::token::ID,
+            token_2022_program: token_2022.then_some(anchor_spl::token_2022::ID),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::CloseAssetAta {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+pub fn register_referrer(id: &VaultId, referrer: &Pubkey) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::RegisterReferrer {
+            vault: vault_address,
+            referral_balance: pda::referral_balance(&vault_address, referrer),
+            referrer: *referrer,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterReferrer {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+pub fn claim_referral_fees(id: &VaultId, referrer: &Pubkey) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::ClaimReferralFees {
+            vault: vault_address,
+            referral_balance: pda::referral_balance(&vault_address, referrer),
+            referrer: *referrer,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClaimReferralFees {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+pub fn initialize_stake_pool(id: &VaultId, payer: &Pubkey) -> Instruction {
+    let vault_address = id.address();
+    let stake_pool = pda::stake_pool(&vault_address);
+    let share_mint = id.share_mint();
+    build(
+        accounts::InitializeStakePool {
+            vault: vault_address,
+            stake_pool,
+            vault_token_mint: share_mint,
+            pool_shares_ata: get_associated_token_address(&stake_pool, &share_mint),
+            payer: *payer,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeStakePool {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+pub fn stake_shares(id: &VaultId, owner: &Pubkey, shares: u64) -> Instruction {
+    let vault_address = id.address();
+    let stake_pool = pda::stake_pool(&vault_address);
+    let share_mint = id.share_mint();
+    build(
+        accounts::StakeShares {
+            vault: vault_address,
+            stake_pool,
+            stake_position: pda::stake_position(&stake_pool, owner),
+            vault_token_mint: share_mint,
+            user_shares_ata: get_associated_token_address(owner, &share_mint),
+            pool_shares_ata: get_associated_token_address(&stake_pool, &share_mint),
+            owner: *owner,
+            token_program: anchor_spl::token::ID,
+            system_program: system_program::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::StakeShares {
+            _name: id.name.clone(),
+            shares,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn unstake_shares(id: &VaultId, owner: &Pubkey, shares: u64) -> Instruction {
+    let vault_address = id.address();
+    let stake_pool = pda::stake_pool(&vault_address);
+    let share_mint = id.share_mint();
+    build(
+        accounts::UnstakeShares {
+            vault: vault_address,
+            stake_pool,
+            stake_position: pda::stake_position(&stake_pool, owner),
+            vault_token_mint: share_mint,
+            user_shares_ata: get_associated_token_address(owner, &share_mint),
+            pool_shares_ata: get_associated_token_address(&stake_pool, &share_mint),
+            owner: *owner,
+            token_program: anchor_spl::token::ID,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::UnstakeShares {
+            _name: id.name.clone(),
+            shares,
+        },
+        Vec::new(),
+    )
+}
+
+pub fn claim_staking_rewards(id: &VaultId, owner: &Pubkey) -> Instruction {
+    let vault_address = id.address();
+    let stake_pool = pda::stake_pool(&vault_address);
+    build(
+        accounts::ClaimStakingRewards {
+            vault: vault_address,
+            stake_pool,
+            stake_position: pda::stake_position(&stake_pool, owner),
+            owner: *owner,
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::ClaimStakingRewards {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
+
+/// Move accrued staker fees into the stake pool (permissionless)
+pub fn distribute_staking_rewards(id: &VaultId) -> Instruction {
+    let vault_address = id.address();
+    build(
+        accounts::DistributeStakingRewards {
+            vault: vault_address,
+            protocol_config: pda::protocol_config(),
+            stake_pool: pda::stake_pool(&vault_address),
+            event_authority: pda::event_authority(),
+            program: vault::ID,
+        },
+        instruction::DistributeStakingRewards {
+            _name: id.name.clone(),
+        },
+        Vec::new(),
+    )
+}
@@ -0,0 +1,109 @@
+//! Keeper configuration, read from a TOML file (see keeper.example.toml)
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::Pubkey;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct KeeperConfig {
+    pub rpc_url: String,
+    pub keypair_path: String,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    #[serde(default = "default_drift_threshold_pct")]
+    pub drift_threshold_pct: i64,
+    /// MockOracle prices to push every tick (micro-USD JSON)
+    #[serde(default)]
+    pub price_file: Option<String>,
+    /// Sync strategy values against the localnet mock_marinade program
+    #[serde(default)]
+    pub mock_marinade: bool,
+    #[serde(default)]
+    pub compute_budget: ComputeBudgetConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    pub vaults: Vec<VaultEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct VaultEntry {
+    /// Base58 vault admin
+    pub admin: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ComputeBudgetConfig {
+    pub unit_limit: u32,
+    /// Percentile (0-100) of recent prioritization fees to pay
+    pub percentile: u8,
+    pub min_micro_lamports: u64,
+    pub max_micro_lamports: u64,
+}
+
+impl Default for ComputeBudgetConfig {
+    fn default() -> Self {
+        Self {
+            unit_limit: 400_000,
+            percentile: 75,
+            min_micro_lamports: 1_000,
+            max_micro_lamports: 200_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub confirm_timeout_secs: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 8_000,
+            confirm_timeout_secs: 30,
+        }
+    }
+}
+
+fn default_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_drift_threshold_pct() -> i64 {
+    5
+}
+
+impl KeeperConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        toml::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn keypair_path(&self) -> PathBuf {
+        expand_home(&self.keypair_path)
+    }
+}
+
+impl VaultEntry {
+    pub fn admin(&self) -> Result<Pubkey, String> {
+        self.admin
+            .parse()
+            .map_err(|_| format!("vault {}: invalid admin {}", self.name, self.admin))
+    }
+}
+
+/// Expand a leading `~/` to $HOME
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
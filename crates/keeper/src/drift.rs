@@ -0,0 +1,316 @@
+//! Off-chain mirror of the rebalance instruction's drift check and swap legs
+//!
+//! The keeper only submits a rebalance when the program would act on it, and
+//! needs the swap legs up front to pass the right backend accounts.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_spl::token::spl_token::native_mint;
+use etf_client::remaining;
+use etf_client::vault::state::{RebalanceConfig, Vault};
+use etf_client::vault::SwapBackend;
+
+/// Decimals the program assumes for every asset when valuing balances
+pub const ASSET_DECIMALS: u32 = 9;
+
+/// Drift (in whole percent) past which the program rebalances
+pub const PROGRAM_DRIFT_THRESHOLD_PCT: i64 = 5;
+
+/// Legs worth $1 or less are skipped on-chain unless the vault sets its own minimum
+const MIN_SWAP_USD: i64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AssetDrift {
+    /// Current value in micro-USD
+    pub usd: i64,
+    /// Current weight minus target weight, in whole percent
+    pub drift_pct: i64,
+    /// Value above (positive) or below (negative) target, in micro-USD
+    pub excess_usd: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwapLeg {
+    pub from: usize,
+    pub to: usize,
+    pub usd: i64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalancePlan {
+    pub total_usd: i64,
+    pub drifts: Vec<AssetDrift>,
+    /// Legs in the order the program executes them
+    pub legs: Vec<SwapLeg>,
+    /// Legs the program skips as dust
+    pub dust: Vec<SwapLeg>,
+}
+
+impl RebalancePlan {
+    /// Largest absolute drift across assets
+    pub fn max_drift_pct(&self) -> i64 {
+        self.drifts
+            .iter()
+            .map(|d| d.drift_pct.abs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether to submit: past `threshold` and past the program's own threshold
+    pub fn needs_rebalance(&self, threshold_pct: i64) -> bool {
+        let max_drift = self.max_drift_pct();
+        max_drift > threshold_pct.max(PROGRAM_DRIFT_THRESHOLD_PCT) && !self.legs.is_empty()
+    }
+}
+
+/// Plan a rebalance from asset balances and micro-USD prices (vault.assets
+/// order), applying the vault's RebalanceConfig limits if it has one
+pub fn plan(
+    vault: &Vault,
+    config: Option<&RebalanceConfig>,
+    balances: &[u64],
+    prices: &[i64],
+) -> RebalancePlan {
+    let usds: Vec<i64> = balances
+        .iter()
+        .zip(prices)
+        .map(|(&balance, &price)| {
+            (balance as i128 * price as i128 / 10i128.pow(ASSET_DECIMALS)) as i64
+        })
+        .collect();
+    let total_usd: i64 = usds.iter().sum();
+    if total_usd == 0 {
+        return RebalancePlan {
+            total_usd,
+            drifts: Vec::new(),
+            legs: Vec::new(),
+            dust: Vec::new(),
+        };
+    }
+
+    let drifts: Vec<AssetDrift> = vault
+        .assets()
+        .iter()
+        .zip(&usds)
+        .map(|(asset, &usd)| {
+            let target_usd = total_usd * asset.weight as i64 / 100;
+            AssetDrift {
+                usd,
+                drift_pct: usd * 100 / total_usd - asset.weight as i64,
+                excess_usd: usd - target_usd,
+            }
+        })
+        .collect();
+
+    // Same pairing as execute_rebalance_swaps: every over-allocated asset
+    // against every under-allocated one, using the pre-swap values
+    let mut legs = Vec::new();
+    if drifts
+        .iter()
+        .any(|d| d.drift_pct.abs() > PROGRAM_DRIFT_THRESHOLD_PCT)
+    {
+        for (from, from_drift) in drifts.iter().enumerate() {
+            if from_drift.excess_usd <= 0 {
+                continue;
+            }
+            for (to, to_drift) in drifts.iter().enumerate() {
+                if to_drift.excess_usd < 0 && from != to {
+                    let usd = from_drift.excess_usd.min(to_drift.excess_usd.abs());
+                    legs.push(SwapLeg { from, to, usd });
+                }
+            }
+        }
+    }
+
+    // Turnover cap: scale every leg by the same factor
+    let max_turnover_bps = config.map_or(0, |config| config.max_turnover_bps_per_rebalance);
+    let turnover: i64 = legs.iter().map(|leg| leg.usd).sum();
+    let cap = (total_usd as i128 * max_turnover_bps as i128 / 10_000) as i64;
+    if max_turnover_bps != 0 && turnover > cap {
+        for leg in &mut legs {
+            leg.usd = (leg.usd as i128 * cap as i128 / turnover as i128) as i64;
+        }
+    }
+
+    // Dust: below the minimum swap or the source asset's threshold
+    let min_swap_usd = match config.map_or(0, |config| config.min_swap_usd) {
+        0 => MIN_SWAP_USD,
+        min_swap_usd => min_swap_usd as i64,
+    };
+    let (legs, dust) = legs.into_iter().partition(|leg: &SwapLeg| {
+        let amount_in = leg.usd as i128 * 10i128.pow(ASSET_DECIMALS) / prices[leg.from] as i128;
+        let threshold = config.map_or(0, |config| config.dust_thresholds[leg.from]);
+        leg.usd > min_swap_usd && amount_in > threshold as i128
+    });
+
+    RebalancePlan {
+        total_usd,
+        drifts,
+        legs,
+        dust,
+    }
+}
+
+/// Swap backend accounts for the planned legs
+pub fn swap_accounts(
+    vault: &Vault,
+    vault_address: &Pubkey,
+    plan: &RebalancePlan,
+) -> Result<Vec<AccountMeta>, String> {
+    match vault.swap_backend() {
+        SwapBackend::Mock => Ok(Vec::new()),
+        SwapBackend::MockAmm => {
+            // Pools pair SOL with one token; token-to-token legs hop through SOL
+            let mut accounts = Vec::new();
+            for leg in &plan.legs {
+                let from = vault.assets()[leg.from].mint;
+                let to = vault.assets()[leg.to].mint;
+                for mint in [from, to] {
+                    if mint != native_mint::ID {
+                        accounts.extend(remaining::mock_amm_hop(vault_address, &mint));
+                    }
+                }
+            }
+            Ok(accounts)
+        }
+        backend => Err(format!(
+            "{:?} swaps need route accounts the keeper cannot build",
+            backend
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etf_client::vault::state::{AssetConfig, MAX_ASSETS, VAULT_VERSION};
+    use etf_client::vault::{MockPoolConfig, PriceSource};
+
+    fn vault(weights: &[u8]) -> Vault {
+        let assets: Vec<AssetConfig> = weights
+            .iter()
+            .map(|&weight| AssetConfig {
+                mint: Pubkey::new_unique(),
+                weight,
+                ata: Pubkey::new_unique(),
+            })
+            .collect();
+        let mut vault = Vault {
+            bump: 255,
+            admin: Pubkey::new_unique(),
+            vault_token_mint: Pubkey::new_unique(),
+            last_share_price: 1_000_000,
+            max_slippage_bps: 100,
+            version: VAULT_VERSION,
+            ..Default::default()
+        };
+        vault.set_name("test").unwrap();
+        vault.set_assets(&assets).unwrap();
+        vault.set_price_source(PriceSource::MockOracle);
+        vault.set_swap_backend(SwapBackend::Mock);
+        vault.set_mock_pool(MockPoolConfig::DEFAULT);
+        vault
+    }
+
+    #[test]
+    fn test_plan_matches_program() {
+        let vault = vault(&[40, 30, 30]);
+        let prices = [1_000_000; 3];
+        let one_usd = 10u64.pow(ASSET_DECIMALS);
+
+        // 42/29/29: within 5%, nothing to do
+        let balanced = plan(
+            &vault,
+            None,
+            &[42 * one_usd, 29 * one_usd, 29 * one_usd],
+            &prices,
+        );
+        assert_eq!(balanced.max_drift_pct(), 2);
+        assert!(balanced.legs.is_empty());
+        assert!(!balanced.needs_rebalance(5));
+
+        // 60/20/20: asset 0 sells $20 into each of the others
+        let drifted = plan(
+            &vault,
+            None,
+            &[60 * one_usd, 20 * one_usd, 20 * one_usd],
+            &prices,
+        );
+        assert_eq!(drifted.drifts[0].drift_pct, 20);
+        assert_eq!(
+            drifted.legs,
+            vec![
+                SwapLeg {
+                    from: 0,
+                    to: 1,
+                    usd: 10_000_000
+                },
+                SwapLeg {
+                    from: 0,
+                    to: 2,
+                    usd: 10_000_000
+                },
+            ]
+        );
+        assert!(drifted.needs_rebalance(5));
+        assert!(!drifted.needs_rebalance(25));
+    }
+
+    #[test]
+    fn test_plan_applies_rebalance_config() {
+        let vault = vault(&[40, 30, 30]);
+        let prices = [1_000_000; 3];
+        let one_usd = 10u64.pow(ASSET_DECIMALS);
+        let balances = [60 * one_usd, 20 * one_usd, 20 * one_usd];
+        let mut config = RebalanceConfig {
+            vault: Pubkey::new_unique(),
+            max_turnover_bps_per_rebalance: 1_000,
+            min_swap_usd: 0,
+            dust_thresholds: [0; MAX_ASSETS],
+            bump: 255,
+        };
+
+        // A 10% turnover cap halves both $10 legs
+        let capped = plan(&vault, Some(&config), &balances, &prices);
+        assert_eq!(
+            capped.legs.iter().map(|leg| leg.usd).collect::<Vec<_>>(),
+            vec![5_000_000, 5_000_000]
+        );
+
+        // 1%: $0.50 legs are dust, nothing left to submit
+        config.max_turnover_bps_per_rebalance = 100;
+        let dusty = plan(&vault, Some(&config), &balances, &prices);
+        assert_eq!(dusty.dust.len(), 2);
+        assert!(!dusty.needs_rebalance(5));
+
+        // A lower minimum swap keeps them; a threshold on asset 0 drops them again
+        config.min_swap_usd = 100_000;
+        assert_eq!(
+            plan(&vault, Some(&config), &balances, &prices).legs.len(),
+            2
+        );
+        config.dust_thresholds[0] = one_usd;
+        assert!(plan(&vault, Some(&config), &balances, &prices)
+            .legs
+            .is_empty());
+    }
+
+    #[test]
+    fn test_mock_amm_hops() {
+        let mut vault = vault(&[50, 50]);
+        vault.set_swap_backend(SwapBackend::MockAmm);
+        vault.assets_mut()[1].mint = native_mint::ID;
+        let one_usd = 10u64.pow(ASSET_DECIMALS);
+        let plan = plan(&vault, None, &[80 * one_usd, 20 * one_usd], &[1_000_000; 2]);
+        let address = Pubkey::new_unique();
+
+        // Token to SOL is a single hop on the token's pool
+        let accounts = swap_accounts(&vault, &address, &plan).unwrap();
+        assert_eq!(
+            accounts,
+            remaining::mock_amm_hop(&address, &vault.assets()[0].mint)
+        );
+
+        vault.set_swap_backend(SwapBackend::Orca);
+        assert!(swap_accounts(&vault, &address, &plan).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! One keeper pass over a vault: push prices, refresh LST rates, the cache
+//! and the strategy value, recompute market-cap weights or apply glide path
+//! steps when due, rebalance on drift
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::prelude::Pubkey;
+use etf_client::fetch::{self, AccountSource};
+use etf_client::keypair::Keypair;
+use etf_client::marinade::MarinadeAccounts;
+use etf_client::remaining::PriceInput;
+use etf_client::rpc::RpcClient;
+use etf_client::vault::state::Vault;
+use etf_client::vault::PriceSource;
+use etf_client::vault_ix::{self, SwitchboardQuotes, VaultId};
+use etf_client::{pda, vault};
+use serde::Deserialize;
+
+use crate::config::{expand_home, KeeperConfig};
+use crate::drift;
+use crate::submit::Submitter;
+
+/// Contents of the optional price file (micro-USD)
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct PriceFile {
+    btc_price: i64,
+    eth_price: i64,
+    sol_price: i64,
+}
+
+pub struct Keeper {
+    pub config: KeeperConfig,
+    pub rpc: RpcClient,
+    pub keypair: Keypair,
+}
+
+impl Keeper {
+    fn submitter(&self) -> Submitter<'_> {
+        Submitter {
+            rpc: &self.rpc,
+            payer: &self.keypair,
+            compute_budget: &self.config.compute_budget,
+            retry: &self.config.retry,
+        }
+    }
+
+    /// Run one pass over `admin`/`name`
+    pub fn tick(&self, admin: &Pubkey, name: &str) -> Result<(), String> {
+        let id = VaultId::new(*admin, name);
+        let vault_address = id.address();
+        let mut vault =
+            fetch::fetch_vault(&self.rpc, admin, name).map_err(|err| err.to_string())?;
+
+        if vault.lst_index() {
+            self.refresh_lst_rates(&vault)?;
+        }
+        if vault.marinade_strategy().is_some() {
+            self.sync_strategy_value(&id)?;
+        }
+
+        let prices = match vault.price_source() {
+            PriceSource::MockOracle => {
+                let oracle = vault.mock_oracle().ok_or("vault has no MockOracle set")?;
+                self.push_mock_prices(&oracle)?;
+                self.refresh_cache(&vault)?;
+                // LST prices only exist once the rates are applied, in the cache
+                if vault.lst_index() {
+                    self.cached_prices(&vault_address)?
+                } else {
+                    let oracle = fetch::fetch_mock_oracle(&self.rpc, &oracle)
+                        .map_err(|err| err.to_string())?;
+                    vec![oracle.btc_price, oracle.eth_price, oracle.sol_price]
+                }
+            }
+            PriceSource::Aggregated => {
+                self.refresh_cache(&vault)?;
+                self.cached_prices(&vault_address)?
+            }
+            // Quotes and reports are signed off-chain; nothing to crank or price from
+            PriceSource::Switchboard
+            | PriceSource::CrossChecked
+            | PriceSource::PythLazer
+            | PriceSource::Chainlink => {
+                println!(
+                    "[{}] {:?} vault: skipping (no quote source)",
+                    name,
+                    vault.price_source()
+                );
+                return Ok(());
+            }
+        };
+
+        // Only the manager can rebalance toward hidden targets
+        if vault.weights_hidden() {
+            println!("[{}] hidden-weights vault: skipping", name);
+            return Ok(());
+        }
+
+        // A guardian paused trading; resume_rebalancing must be called first
+        if vault.rebalancing_paused() {
+            println!("[{}] rebalancing paused by guardian: skipping", name);
+            return Ok(());
+        }
+
+        let reweighted = if vault.market_cap_weighted() {
+            self.recompute_weights(&vault)?
+        } else {
+            self.apply_schedule_step(&vault)?
+        };
+        if reweighted {
+            vault = fetch::fetch_vault(&self.rpc, admin, name).map_err(|err| err.to_string())?;
+        }
+
+        let balances =
+            fetch::fetch_asset_balances(&self.rpc, &vault).map_err(|err| err.to_string())?;
+        let rebalance_config = fetch::fetch_rebalance_config(&self.rpc, &vault_address)
+            .map_err(|err| err.to_string())?;
+        let plan = drift::plan(&vault, rebalance_config.as_ref(), &balances, &prices);
+        println!(
+            "[{}] TVL ${}, max drift {}%",
+            name,
+            plan.total_usd / 1_000_000,
+            plan.max_drift_pct()
+        );
+        if !plan.needs_rebalance(self.config.drift_threshold_pct) {
+            return Ok(());
+        }
+        if vault.admin != self.keypair.pubkey() {
+            return Err(format!(
+                "drift past threshold but {} is not the vault admin",
+                self.keypair.pubkey()
+            ));
+        }
+
+        let swap_accounts = drift::swap_accounts(&vault, &vault_address, &plan)?;
+        let instruction = vault_ix::rebalance(
+            &vault,
+            rebalance_config.as_ref(),
+            &SwitchboardQuotes::default(),
+            &swap_accounts,
+        )
+        .map_err(|err| err.to_string())?;
+        let mut writable = vec![vault_address];
+        writable.extend(vault.assets().iter().map(|asset| asset.ata));
+        let signature = self.submitter().submit(&[instruction], &writable)?;
+        println!(
+            "[{}] rebalanced ({} legs, {} dust skipped): {}",
+            name,
+            plan.legs.len(),
+            plan.dust.len(),
+            signature
+        );
+        Ok(())
+    }
+
+    /// Push the price file to the MockOracle if the keeper owns it or is one
+    /// of its feeders
+    fn push_mock_prices(&self, oracle: &Pubkey) -> Result<(), String> {
+        let Some(path) = &self.config.price_file else {
+            return Ok(());
+        };
+        let signer = self.keypair.pubkey();
+        let owner = if *oracle == pda::mock_oracle(&signer) {
+            signer
+        } else {
+            let feeders =
+                fetch::fetch_oracle_feeders(&self.rpc, oracle).map_err(|err| err.to_string())?;
+            if !feeders.is_some_and(|feeders| feeders.allows(&signer)) {
+                return Ok(());
+            }
+            fetch::fetch_mock_oracle(&self.rpc, oracle)
+                .map_err(|err| err.to_string())?
+                .authority
+        };
+        let prices = read_price_file(&expand_home(path))?;
+        let instruction = if owner == signer {
+            vault_ix::update_mock_oracle(
+                &signer,
+                prices.btc_price,
+                prices.eth_price,
+                prices.sol_price,
+            )
+        } else {
+            vault_ix::update_mock_oracle_as_feeder(
+                &owner,
+                &signer,
+                prices.btc_price,
+                prices.eth_price,
+                prices.sol_price,
+            )
+        };
+        self.submitter().submit(&[instruction], &[*oracle])?;
+        Ok(())
+    }
+
+    /// Crank refresh_prices so cache readers see current prices
+    fn refresh_cache(&self, vault: &Vault) -> Result<(), String> {
+        let instruction =
+            vault_ix::refresh_prices(vault, &self.keypair.pubkey(), &SwitchboardQuotes::default())
+                .map_err(|err| err.to_string())?;
+        let vault_address = VaultId::of(vault).address();
+        self.submitter()
+            .submit(&[instruction], &[pda::price_cache(&vault_address)])?;
+        Ok(())
+    }
+
+    /// Crank sync_strategy_value so deposits and withdrawals see a fresh
+    /// strategy value
+    fn sync_strategy_value(&self, id: &VaultId) -> Result<(), String> {
+        let marinade = if self.config.mock_marinade {
+            MarinadeAccounts::mock()
+        } else {
+            MarinadeAccounts::mainnet()
+        };
+        let instruction = vault_ix::sync_strategy_value(id, &self.keypair.pubkey(), &marinade);
+        self.submitter()
+            .submit(&[instruction], &[pda::strategy_value(&id.address())])?;
+        Ok(())
+    }
+
+    /// Prices in the vault's PriceCache, in vault.assets order
+    fn cached_prices(&self, vault_address: &Pubkey) -> Result<Vec<i64>, String> {
+        let cache = fetch::fetch_price_cache(&self.rpc, vault_address)
+            .map_err(|err| err.to_string())?
+            .ok_or("price cache missing after refresh")?;
+        Ok(vec![cache.btc_price, cache.eth_price, cache.sol_price])
+    }
+
+    /// Crank refresh_lst_rates so the cache and this pass price LSTs at
+    /// current exchange rates
+    fn refresh_lst_rates(&self, vault: &Vault) -> Result<(), String> {
+        let id = VaultId::of(vault);
+        let vault_address = id.address();
+        let basket = fetch::fetch_lst_basket(&self.rpc, &vault_address)
+            .map_err(|err| err.to_string())?
+            .ok_or("LST index has no basket")?;
+
+        let instruction = vault_ix::refresh_lst_rates(&id, &basket);
+        self.submitter().submit(
+            &[instruction],
+            &[vault_address, pda::lst_basket(&vault_address)],
+        )?;
+        Ok(())
+    }
+
+    /// Crank recompute_weights once the index's interval has passed; returns
+    /// whether it ran. Prices come from the cache refreshed just before.
+    fn recompute_weights(&self, vault: &Vault) -> Result<bool, String> {
+        let vault_address = VaultId::of(vault).address();
+        let Some(index) = fetch::fetch_market_cap_index(&self.rpc, &vault_address)
+            .map_err(|err| err.to_string())?
+        else {
+            return Ok(false);
+        };
+        if index.supplies_updated_at == 0
+            || unix_now()? - index.last_recompute < index.recompute_interval_secs
+        {
+            return Ok(false);
+        }
+
+        let instruction =
+            vault_ix::recompute_weights(vault, PriceInput::Cache, &SwitchboardQuotes::default())
+                .map_err(|err| err.to_string())?;
+        let signature = self.submitter().submit(
+            &[instruction],
+            &[vault_address, pda::market_cap_index(&vault_address)],
+        )?;
+        println!("[{}] weights recomputed: {}", vault.name(), signature);
+        Ok(true)
+    }
+
+    /// Crank apply_schedule_step when a glide path step is due; returns
+    /// whether it ran
+    fn apply_schedule_step(&self, vault: &Vault) -> Result<bool, String> {
+        let id = VaultId::of(vault);
+        let vault_address = id.address();
+        let Some(schedule) = fetch::fetch_vault_schedule(&self.rpc, &vault_address)
+            .map_err(|err| err.to_string())?
+        else {
+            return Ok(false);
+        };
+        if schedule.due_step(unix_now()?).is_none() {
+            return Ok(false);
+        }
+
+        let instruction = vault_ix::apply_schedule_step(&id);
+        let signature = self.submitter().submit(
+            &[instruction],
+            &[vault_address, pda::vault_schedule(&vault_address)],
+        )?;
+        println!("[{}] glide path step applied: {}", vault.name(), signature);
+        Ok(true)
+    }
+
+    /// Whether the vault program is deployed at the RPC endpoint
+    pub fn check_program(&self) -> Result<(), String> {
+        match self.rpc.account_data(&vault::ID) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err(format!(
+                "vault program {} not found at {}",
+                vault::ID,
+                self.rpc.url()
+            )),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+fn unix_now() -> Result<i64, String> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| err.to_string())?
+        .as_secs() as i64)
+}
+
+fn read_price_file(path: &Path) -> Result<PriceFile, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    serde_json::from_str(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+}
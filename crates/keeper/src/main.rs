@@ -0,0 +1,72 @@
+//! ETF-DeFi keeper
+//!
+//! Watches the configured vaults and, every poll interval, pushes MockOracle
+//! prices, refreshes the PriceCache and submits a rebalance when any asset
+//! drifts past the threshold. Usage: `keeper [config.toml] [--once]`.
+
+mod config;
+mod drift;
+mod keeper;
+mod submit;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::thread::sleep;
+use std::time::Duration;
+
+use etf_client::keypair::Keypair;
+use etf_client::rpc::RpcClient;
+
+use crate::config::KeeperConfig;
+use crate::keeper::Keeper;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("keeper: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let once = args.iter().any(|arg| arg == "--once");
+    let config_path = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("keeper.toml"));
+
+    let config = KeeperConfig::load(&config_path)?;
+    let vaults = config
+        .vaults
+        .iter()
+        .map(|entry| Ok((entry.admin()?, entry.name.clone())))
+        .collect::<Result<Vec<_>, String>>()?;
+    let keeper = Keeper {
+        rpc: RpcClient::new(&config.rpc_url).map_err(|err| err.to_string())?,
+        keypair: Keypair::read_file(config.keypair_path()).map_err(|err| err.to_string())?,
+        config,
+    };
+    keeper.check_program()?;
+    println!(
+        "keeper {} watching {} vault(s)",
+        keeper.keypair.pubkey(),
+        vaults.len()
+    );
+
+    loop {
+        for (admin, name) in &vaults {
+            // One failing vault must not stop the others
+            if let Err(err) = keeper.tick(admin, name) {
+                eprintln!("[{}] {}", name, err);
+            }
+        }
+        if once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(keeper.config.poll_interval_secs));
+    }
+}
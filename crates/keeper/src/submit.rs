@@ -0,0 +1,175 @@
+//! Transaction submission: priority fees, confirmation and retry with backoff
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use etf_client::keypair::Keypair;
+use etf_client::rpc::{RpcClient, SignatureStatus};
+use etf_client::transaction::{self, set_compute_unit_limit, set_compute_unit_price};
+
+use crate::config::{ComputeBudgetConfig, RetryConfig};
+
+/// Exponential backoff delays, doubling up to a cap
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    next: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(config: &RetryConfig) -> Self {
+        Self {
+            next: Duration::from_millis(config.initial_backoff_ms),
+            max: Duration::from_millis(config.max_backoff_ms),
+        }
+    }
+}
+
+impl Iterator for Backoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Duration> {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        Some(delay)
+    }
+}
+
+/// Priority fee (micro-lamports per CU) from recent fees on the written accounts
+pub fn priority_fee(recent_fees: &[u64], config: &ComputeBudgetConfig) -> u64 {
+    let mut fees = recent_fees.to_vec();
+    fees.sort_unstable();
+    let fee = match fees.len() {
+        0 => 0,
+        n => fees[(n - 1) * config.percentile.min(100) as usize / 100],
+    };
+    fee.clamp(config.min_micro_lamports, config.max_micro_lamports)
+}
+
+pub struct Submitter<'a> {
+    pub rpc: &'a RpcClient,
+    pub payer: &'a Keypair,
+    pub compute_budget: &'a ComputeBudgetConfig,
+    pub retry: &'a RetryConfig,
+}
+
+enum Attempt {
+    /// Worth retrying with a fresh blockhash (send error, or not confirmed in time)
+    Retry(String),
+    /// Landed and failed; retrying would fail the same way
+    Failed(String),
+}
+
+impl Submitter<'_> {
+    /// Send `instructions` with a compute budget, retrying with backoff and an
+    /// escalating priority fee until confirmed; returns the signature
+    ///
+    /// `writable` are the contended accounts used to price the fee.
+    pub fn submit(
+        &self,
+        instructions: &[Instruction],
+        writable: &[Pubkey],
+    ) -> Result<String, String> {
+        let recent = self
+            .rpc
+            .get_recent_prioritization_fees(writable)
+            .unwrap_or_default();
+        let base_fee = priority_fee(&recent, self.compute_budget);
+        let mut backoff = Backoff::new(self.retry);
+
+        let mut last_error = String::from("no attempts made");
+        for attempt in 0..self.retry.max_attempts {
+            let fee = base_fee
+                .saturating_mul(1 << attempt.min(16))
+                .min(self.compute_budget.max_micro_lamports);
+            match self.attempt(instructions, fee) {
+                Ok(signature) => return Ok(signature),
+                Err(Attempt::Failed(err)) => return Err(err),
+                Err(Attempt::Retry(err)) => {
+                    let delay = backoff.next().unwrap_or_default();
+                    eprintln!(
+                        "attempt {}/{} failed ({}), retrying in {:?}",
+                        attempt + 1,
+                        self.retry.max_attempts,
+                        err,
+                        delay
+                    );
+                    last_error = err;
+                    sleep(delay);
+                }
+            }
+        }
+        Err(format!(
+            "gave up after {} attempts: {}",
+            self.retry.max_attempts, last_error
+        ))
+    }
+
+    fn attempt(&self, instructions: &[Instruction], fee: u64) -> Result<String, Attempt> {
+        let mut all = vec![
+            set_compute_unit_limit(self.compute_budget.unit_limit),
+            set_compute_unit_price(fee),
+        ];
+        all.extend_from_slice(instructions);
+
+        let blockhash = self
+            .rpc
+            .get_latest_blockhash()
+            .map_err(|err| Attempt::Retry(err.to_string()))?;
+        let transaction = transaction::sign_transaction(&all, self.payer, &[], blockhash)
+            .map_err(|err| Attempt::Failed(err.to_string()))?;
+        let signature = self
+            .rpc
+            .send_transaction(&transaction)
+            .map_err(|err| Attempt::Retry(err.to_string()))?;
+
+        // A resend after a timeout may land twice; rebalance and price
+        // updates are no-ops the second time
+        let timeout = Duration::from_secs(self.retry.confirm_timeout_secs);
+        match self.rpc.wait_for_signature(&signature, timeout) {
+            Ok(SignatureStatus::Confirmed) => Ok(signature),
+            Ok(SignatureStatus::Failed(err)) => {
+                Err(Attempt::Failed(format!("{} failed: {}", signature, err)))
+            }
+            Ok(SignatureStatus::Pending) | Err(_) => Err(Attempt::Retry(format!(
+                "{} not confirmed in time",
+                signature
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_priority_fee() {
+        let retry = RetryConfig {
+            max_attempts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3_000,
+            confirm_timeout_secs: 30,
+        };
+        let delays: Vec<u64> = Backoff::new(&retry)
+            .take(4)
+            .map(|d| d.as_millis() as u64)
+            .collect();
+        assert_eq!(delays, vec![500, 1_000, 2_000, 3_000]);
+
+        let budget = ComputeBudgetConfig {
+            unit_limit: 400_000,
+            percentile: 75,
+            min_micro_lamports: 1_000,
+            max_micro_lamports: 50_000,
+        };
+        assert_eq!(priority_fee(&[], &budget), 1_000);
+        assert_eq!(
+            priority_fee(&[9_000, 2_000, 4_000, 8_000, 0], &budget),
+            8_000
+        );
+        assert_eq!(priority_fee(&[1_000_000], &budget), 50_000);
+    }
+}
@@ -4,6 +4,7 @@ use anchor_lang::system_program::ID;
 // Re-export state module
 pub mod state;
 pub use state::*;
+#[cfg(test)]
 mod tests;
 /// ========= Events you can assert in tests =========
 #[event]
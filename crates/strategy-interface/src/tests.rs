@@ -1,3 +1,5 @@
+use super::*;
+
 #[test]
 fn test_strategy_state_size() {
     assert_eq!(StrategyState::SIZE, 32 + 1 + 32 + 32 + 8 + 8 + 8 + 1 + 1);
@@ -23,8 +25,6 @@ fn test_strategy_kind_serialization() {
 
 #[test]
 fn test_initialize_args_serialization() {
-    use anchor_lang::prelude::*;
-    
     let args = InitializeArgs {
         kind: 255,
         protocol_program: Pubkey::default(),
@@ -0,0 +1,984 @@
+//! Command line parsing
+
+use anchor_lang::prelude::Pubkey;
+use etf_client::vault::state::{LstKind, LstPool, ScheduleStep};
+use etf_client::vault::{PriceSource, QuoteCurrency};
+
+pub const USAGE: &str = "\
+usage: vault-cli [--config PATH] [--url URL] [--keypair PATH] [--admin PUBKEY] <command>
+
+Vaults are addressed by name; the admin defaults to the keypair (pass --admin
+when signing as the vault's governance).
+
+commands:
+  list                                    every vault in the on-chain registry
+  show <name>
+  register-vault <name>                   add a vault created before the registry
+  migrate-vault <name>                    convert a vault to the current account layout
+  create-vault <name> <mint>:<weight>... [--soulbound]
+                                          --soulbound: shares can't be transferred
+  init-vault <name> [--soulbound]         create-vault in steps, for large baskets:
+  add-vault-asset <name> <mint>:<weight> [--token-2022]
+  finalize-vault <name>                   init, add each asset, then finalize
+  init-strategy <name> [--mock-marinade]
+  set-strategy <name> [strategy]          default: the vault's Marinade strategy PDA
+  remove-strategy <name>
+  divest <name> <msol-lamports> [--mock-marinade]
+                                          liquid-unstake strategy mSOL into idle SOL
+  set-price-source <name> mock-oracle [oracle] | switchboard | aggregated | pyth-lazer | chainlink
+  set-cross-check <name> <max-divergence-bps> [oracle]
+                                          Switchboard, rejected when it strays from the mock oracle
+  set-quote-currency <name> sol | usdc <usdc-mint>
+                                          currency deposits and NAV are quoted in
+  set-nav-currency <name> usd | <code> <fx-feed>
+                                          report NAV in e.g. EUR, priced by a Switchboard FX feed
+  update-composition <name> <weight>...
+  market-cap-index <name> on|off <interval-secs> <max-change> [supply-authority]
+                                          weights follow supply x price (the keeper recomputes)
+  set-supplies <name> <supply>...         circulating supplies in whole tokens, asset order
+  set-schedule <name> <unix-ts>:<weight>,<weight>...
+                                          glide path steps (none clears; the keeper applies them)
+  lst-basket <name> sol|marinade:<state>|stake-pool:<pool>...
+                                          price assets as LSTs by exchange rate (none turns it off)
+  pause <name> <successor-vault>          soft-close and point holders at a successor
+  resume <name>
+  set-deposit-fee <name> <fee-bps> <referral-share-bps>
+  set-exit-fee <name> <max-fee-bps> <window-secs>
+  set-staker-fee-share <name> <bps>
+  set-max-slippage <name> <bps>
+  set-min-deposit-shares <name> <shares>  deposits minting fewer shares abort
+  set-max-turnover <name> <bps>           most of TVL one rebalance may trade (0 = no cap)
+  set-dust <name> <min-swap-usd> [threshold]...
+                                          skip smaller rebalance legs; micro-USD (0 = $1),
+                                          thresholds in base units, asset order
+  clear-operation-lock <name>             unlock a vault stuck in a confidential rebalance
+  set-share-metadata <name> <token-name> <symbol> <uri>
+  wind-down <name> <grace-days>           freeze deposits; holders exit before close-vault
+  close-vault <name>                      close a wound-down vault once every share is redeemed
+  lookup-table <name> [--mock-marinade]   create or top up the vault's address lookup table
+  set-vault-metadata <name> <category> <risk-level> <icon-uri> <description...>
+  claim-creator-fees <name>
+  sweep-token <name> <mint> <treasury> [--token-2022]
+                                          send stray non-composition tokens to the treasury
+  close-asset-ata <name> <mint> [--token-2022]
+                                          close an empty non-composition ATA for its rent
+  asset-whitelist [btc:<mint>|eth:<mint>]...
+                                          mints accepted besides Portal wBTC/wETH (config authority)
+  switchboard-config <btc-hash> <eth-hash> <sol-hash>
+                                          Switchboard feed hashes, hex (config authority)
+  chainlink-config <btc-id> <eth-id> <sol-id>
+                                          Chainlink Data Streams feed ids, hex (config authority)
+  pyth-lazer-config <btc-id>,<eth-id>,<sol-id> <signer>...
+                                          Lazer feed ids and trusted signers (config authority)
+  init-oracle
+  update-oracle <btc-usd> <eth-usd> <sol-usd> [owner]
+                                          owner: push as a feeder of that key's oracle
+  oracle-feeders [key]...                 keys allowed to update your oracle (none revokes all)";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    List,
+    RegisterVault {
+        name: String,
+    },
+    MigrateVault {
+        name: String,
+    },
+    Show {
+        name: String,
+    },
+    CreateVault {
+        name: String,
+        assets: Vec<(Pubkey, u8)>,
+        soulbound: bool,
+    },
+    InitVault {
+        name: String,
+        soulbound: bool,
+    },
+    AddVaultAsset {
+        name: String,
+        asset: (Pubkey, u8),
+        token_2022: bool,
+    },
+    FinalizeVault {
+        name: String,
+    },
+    InitStrategy {
+        name: String,
+        mock_marinade: bool,
+    },
+    SetStrategy {
+        name: String,
+        strategy: Option<Pubkey>,
+    },
+    RemoveStrategy {
+        name: String,
+    },
+    Divest {
+        name: String,
+        msol_amount: u64,
+        mock_marinade: bool,
+    },
+    SetPriceSource {
+        name: String,
+        source: PriceSource,
+        mock_oracle: Option<Pubkey>,
+    },
+    SetCrossCheck {
+        name: String,
+        max_divergence_bps: u16,
+        mock_oracle: Option<Pubkey>,
+    },
+    SetQuoteCurrency {
+        name: String,
+        currency: QuoteCurrency,
+        quote_mint: Option<Pubkey>,
+    },
+    SetNavCurrency {
+        name: String,
+        currency: [u8; 3],
+        fx_feed: Option<Pubkey>,
+    },
+    UpdateComposition {
+        name: String,
+        weights: Vec<u8>,
+    },
+    MarketCapIndex {
+        name: String,
+        enabled: bool,
+        interval_secs: i64,
+        max_weight_change: u8,
+        supply_authority: Option<Pubkey>,
+    },
+    SetSupplies {
+        name: String,
+        supplies: Vec<u64>,
+    },
+    SetSchedule {
+        name: String,
+        steps: Vec<ScheduleStep>,
+    },
+    LstBasket {
+        name: String,
+        pools: Vec<LstPool>,
+    },
+    SwitchboardConfig {
+        feed_hashes: [[u8; 32]; 3],
+    },
+    ChainlinkConfig {
+        feed_ids: [[u8; 32]; 3],
+    },
+    PythLazerConfig {
+        feed_ids: [u32; 3],
+        trusted_signers: Vec<Pubkey>,
+    },
+    AssetWhitelist {
+        btc_alternates: Vec<Pubkey>,
+        eth_alternates: Vec<Pubkey>,
+    },
+    Pause {
+        name: String,
+        successor: Pubkey,
+    },
+    Resume {
+        name: String,
+    },
+    SetDepositFee {
+        name: String,
+        fee_bps: u16,
+        referral_share_bps: u16,
+    },
+    SetExitFee {
+        name: String,
+        max_fee_bps: u16,
+        window_secs: u32,
+    },
+    SetStakerFeeShare {
+        name: String,
+        bps: u16,
+    },
+    SetMaxSlippage {
+        name: String,
+        bps: u16,
+    },
+    SetMinDepositShares {
+        name: String,
+        shares: u32,
+    },
+    SetMaxTurnover {
+        name: String,
+        bps: u16,
+    },
+    SetDust {
+        name: String,
+        min_swap_usd: u64,
+        thresholds: Vec<u64>,
+    },
+    ClearOperationLock {
+        name: String,
+    },
+    SetShareMetadata {
+        name: String,
+        token_name: String,
+        symbol: String,
+        uri: String,
+    },
+    SetVaultMetadata {
+        name: String,
+        category: String,
+        risk_level: u8,
+        icon_uri: String,
+        description: String,
+    },
+    WindDown {
+        name: String,
+        grace_days: i64,
+    },
+    CloseVault {
+        name: String,
+    },
+    LookupTable {
+        name: String,
+        mock_marinade: bool,
+    },
+    ClaimCreatorFees {
+        name: String,
+    },
+    SweepToken {
+        name: String,
+        mint: Pubkey,
+        treasury: Pubkey,
+        token_2022: bool,
+    },
+    CloseAssetAta {
+        name: String,
+        mint: Pubkey,
+        token_2022: bool,
+    },
+    InitOracle,
+    /// Prices in micro-USD
+    UpdateOracle {
+        btc: i64,
+        eth: i64,
+        sol: i64,
+        /// Authority of the oracle, when signing as one of its feeders
+        owner: Option<Pubkey>,
+    },
+    OracleFeeders {
+        feeders: Vec<Pubkey>,
+    },
+}
+
+/// Global flags followed by the command
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+    pub config: Option<String>,
+    pub url: Option<String>,
+    pub keypair: Option<String>,
+    pub admin: Option<Pubkey>,
+    pub command: Vec<String>,
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = |flag: &str| {
+                iter.next()
+                    .cloned()
+                    .ok_or(format!("{} needs a value", flag))
+            };
+            match arg.as_str() {
+                "--config" => parsed.config = Some(value(arg)?),
+                "--url" => parsed.url = Some(value(arg)?),
+                "--keypair" => parsed.keypair = Some(value(arg)?),
+                "--admin" => parsed.admin = Some(pubkey(&value(arg)?)?),
+                _ => {
+                    parsed.command.push(arg.clone());
+                    parsed.command.extend(iter.cloned());
+                    break;
+                }
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let (command, rest) = args.split_first().ok_or("missing command")?;
+        let rest: Vec<&str> = rest.iter().map(String::as_str).collect();
+        let name = || {
+            rest.first()
+                .map(|name| name.to_string())
+                .ok_or(format!("{}: missing vault name", command))
+        };
+        let arg = |i: usize, what: &str| {
+            rest.get(i)
+                .copied()
+                .ok_or(format!("{}: missing {}", command, what))
+        };
+
+        Ok(match command.as_str() {
+            "list" => Command::List,
+            "show" => Command::Show { name: name()? },
+            "register-vault" => Command::RegisterVault { name: name()? },
+            "migrate-vault" => Command::MigrateVault { name: name()? },
+            "create-vault" => Command::CreateVault {
+                name: name()?,
+                assets: rest[1..]
+                    .iter()
+                    .filter(|arg| **arg != "--soulbound")
+                    .map(|asset| parse_asset(asset))
+                    .collect::<Result<_, _>>()?,
+                soulbound: rest[1..].contains(&"--soulbound"),
+            },
+            "init-vault" => Command::InitVault {
+                name: name()?,
+                soulbound: rest.get(1) == Some(&"--soulbound"),
+            },
+            "add-vault-asset" => Command::AddVaultAsset {
+                name: name()?,
+                asset: parse_asset(arg(1, "asset")?)?,
+                token_2022: rest.get(2) == Some(&"--token-2022"),
+            },
+            "finalize-vault" => Command::FinalizeVault { name: name()? },
+            "init-strategy" => Command::InitStrategy {
+                name: name()?,
+                mock_marinade: rest.get(1) == Some(&"--mock-marinade"),
+            },
+            "set-strategy" => Command::SetStrategy {
+                name: name()?,
+                strategy: rest.get(1).map(|key| pubkey(key)).transpose()?,
+            },
+            "remove-strategy" => Command::RemoveStrategy { name: name()? },
+            "divest" => Command::Divest {
+                name: name()?,
+                msol_amount: number(arg(1, "mSOL amount")?)?,
+                mock_marinade: rest.get(2) == Some(&"--mock-marinade"),
+            },
+            "set-price-source" => {
+                let source = match arg(1, "price source")? {
+                    "mock-oracle" => PriceSource::MockOracle,
+                    "switchboard" => PriceSource::Switchboard,
+                    "aggregated" => PriceSource::Aggregated,
+                    "pyth-lazer" => PriceSource::PythLazer,
+                    "chainlink" => PriceSource::Chainlink,
+                    other => return Err(format!("unknown price source {}", other)),
+                };
+                Command::SetPriceSource {
+                    name: name()?,
+                    source,
+                    mock_oracle: rest.get(2).map(|key| pubkey(key)).transpose()?,
+                }
+            }
+            "set-cross-check" => Command::SetCrossCheck {
+                name: name()?,
+                max_divergence_bps: number(arg(1, "max divergence bps")?)?,
+                mock_oracle: rest.get(2).map(|key| pubkey(key)).transpose()?,
+            },
+            "set-quote-currency" => {
+                let (currency, quote_mint) = match arg(1, "quote currency")? {
+                    "sol" => (QuoteCurrency::Sol, None),
+                    "usdc" => (QuoteCurrency::Usdc, Some(pubkey(arg(2, "USDC mint")?)?)),
+                    other => return Err(format!("unknown quote currency {}", other)),
+                };
+                Command::SetQuoteCurrency {
+                    name: name()?,
+                    currency,
+                    quote_mint,
+                }
+            }
+            "set-nav-currency" => {
+                let code = arg(1, "currency code")?.to_ascii_uppercase();
+                let currency: [u8; 3] = code
+                    .as_bytes()
+                    .try_into()
+                    .ok()
+                    .filter(|code: &[u8; 3]| code.iter().all(u8::is_ascii_uppercase))
+                    .ok_or_else(|| format!("invalid currency code {}", code))?;
+                let fx_feed = match &currency {
+                    b"USD" => None,
+                    _ => Some(pubkey(arg(2, "FX feed")?)?),
+                };
+                Command::SetNavCurrency {
+                    name: name()?,
+                    currency,
+                    fx_feed,
+                }
+            }
+            "update-composition" => Command::UpdateComposition {
+                name: name()?,
+                weights: rest[1..]
+                    .iter()
+                    .map(|w| number(w))
+                    .collect::<Result<_, _>>()?,
+            },
+            "market-cap-index" => Command::MarketCapIndex {
+                name: name()?,
+                enabled: match arg(1, "on|off")? {
+                    "on" => true,
+                    "off" => false,
+                    other => return Err(format!("expected on or off, got {}", other)),
+                },
+                interval_secs: number(arg(2, "interval secs")?)?,
+                max_weight_change: number(arg(3, "max change")?)?,
+                supply_authority: rest.get(4).map(|key| pubkey(key)).transpose()?,
+            },
+            "set-supplies" => Command::SetSupplies {
+                name: name()?,
+                supplies: rest[1..]
+                    .iter()
+                    .map(|s| number(s))
+                    .collect::<Result<_, _>>()?,
+            },
+            "set-schedule" => Command::SetSchedule {
+                name: name()?,
+                steps: rest[1..]
+                    .iter()
+                    .map(|step| parse_step(step))
+                    .collect::<Result<_, _>>()?,
+            },
+            "lst-basket" => Command::LstBasket {
+                name: name()?,
+                pools: rest[1..]
+                    .iter()
+                    .map(|pool| parse_lst_pool(pool))
+                    .collect::<Result<_, _>>()?,
+            },
+            "pause" => Command::Pause {
+                name: name()?,
+                successor: pubkey(arg(1, "successor vault")?)?,
+            },
+            "resume" => Command::Resume { name: name()? },
+            "set-deposit-fee" => Command::SetDepositFee {
+                name: name()?,
+                fee_bps: number(arg(1, "fee bps")?)?,
+                referral_share_bps: number(arg(2, "referral share bps")?)?,
+            },
+            "set-exit-fee" => Command::SetExitFee {
+                name: name()?,
+                max_fee_bps: number(arg(1, "max fee bps")?)?,
+                window_secs: number(arg(2, "window secs")?)?,
+            },
+            "set-staker-fee-share" => Command::SetStakerFeeShare {
+                name: name()?,
+                bps: number(arg(1, "bps")?)?,
+            },
+            "set-max-slippage" => Command::SetMaxSlippage {
+                name: name()?,
+                bps: number(arg(1, "bps")?)?,
+            },
+            "set-min-deposit-shares" => Command::SetMinDepositShares {
+                name: name()?,
+                shares: number(arg(1, "shares")?)?,
+            },
+            "set-max-turnover" => Command::SetMaxTurnover {
+                name: name()?,
+                bps: number(arg(1, "bps")?)?,
+            },
+            "set-dust" => Command::SetDust {
+                name: name()?,
+                min_swap_usd: number(arg(1, "min-swap-usd")?)?,
+                thresholds: rest[2..]
+                    .iter()
+                    .map(|s| number(s))
+                    .collect::<Result<_, _>>()?,
+            },
+            "clear-operation-lock" => Command::ClearOperationLock { name: name()? },
+            "set-share-metadata" => Command::SetShareMetadata {
+                name: name()?,
+                token_name: arg(1, "token name")?.to_string(),
+                symbol: arg(2, "symbol")?.to_string(),
+                uri: arg(3, "uri")?.to_string(),
+            },
+            "set-vault-metadata" => Command::SetVaultMetadata {
+                name: name()?,
+                category: arg(1, "category")?.to_string(),
+                risk_level: number(arg(2, "risk level")?)?,
+                icon_uri: arg(3, "icon uri")?.to_string(),
+                description: rest.get(4..).unwrap_or_default().join(" "),
+            },
+            "wind-down" => Command::WindDown {
+                name: name()?,
+                grace_days: number(arg(1, "grace days")?)?,
+            },
+            "close-vault" => Command::CloseVault { name: name()? },
+            "lookup-table" => Command::LookupTable {
+                name: name()?,
+                mock_marinade: rest.get(1) == Some(&"--mock-marinade"),
+            },
+            "claim-creator-fees" => Command::ClaimCreatorFees { name: name()? },
+            "sweep-token" => Command::SweepToken {
+                name: name()?,
+                mint: pubkey(arg(1, "mint")?)?,
+                treasury: pubkey(arg(2, "treasury")?)?,
+                token_2022: rest.get(3) == Some(&"--token-2022"),
+            },
+            "close-asset-ata" => Command::CloseAssetAta {
+                name: name()?,
+                mint: pubkey(arg(1, "mint")?)?,
+                token_2022: rest.get(2) == Some(&"--token-2022"),
+            },
+            "asset-whitelist" => {
+                let mut btc_alternates = Vec::new();
+                let mut eth_alternates = Vec::new();
+                for entry in rest {
+                    match entry.split_once(':') {
+                        Some(("btc", mint)) => btc_alternates.push(pubkey(mint)?),
+                        Some(("eth", mint)) => eth_alternates.push(pubkey(mint)?),
+                        _ => {
+                            return Err(format!("expected btc:<mint> or eth:<mint>, got {}", entry))
+                        }
+                    }
+                }
+                Command::AssetWhitelist {
+                    btc_alternates,
+                    eth_alternates,
+                }
+            }
+            "switchboard-config" => Command::SwitchboardConfig {
+                feed_hashes: [
+                    parse_hex32(arg(0, "BTC feed hash")?)?,
+                    parse_hex32(arg(1, "ETH feed hash")?)?,
+                    parse_hex32(arg(2, "SOL feed hash")?)?,
+                ],
+            },
+            "chainlink-config" => Command::ChainlinkConfig {
+                feed_ids: [
+                    parse_hex32(arg(0, "BTC feed id")?)?,
+                    parse_hex32(arg(1, "ETH feed id")?)?,
+                    parse_hex32(arg(2, "SOL feed id")?)?,
+                ],
+            },
+            "pyth-lazer-config" => {
+                let feed_ids = arg(0, "feed ids")?
+                    .split(',')
+                    .map(number)
+                    .collect::<Result<Vec<u32>, _>>()?;
+                Command::PythLazerConfig {
+                    feed_ids: feed_ids
+                        .try_into()
+                        .map_err(|_| "expected <btc-id>,<eth-id>,<sol-id>".to_string())?,
+                    trusted_signers: rest[1..]
+                        .iter()
+                        .map(|key| pubkey(key))
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            "init-oracle" => Command::InitOracle,
+            "update-oracle" => Command::UpdateOracle {
+                btc: parse_usd(arg(0, "BTC price")?)?,
+                eth: parse_usd(arg(1, "ETH price")?)?,
+                sol: parse_usd(arg(2, "SOL price")?)?,
+                owner: rest.get(3).map(|key| pubkey(key)).transpose()?,
+            },
+            "oracle-feeders" => Command::OracleFeeders {
+                feeders: rest
+                    .iter()
+                    .map(|key| pubkey(key))
+                    .collect::<Result<_, _>>()?,
+            },
+            other => return Err(format!("unknown command {}", other)),
+        })
+    }
+}
+
+fn pubkey(value: &str) -> Result<Pubkey, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid public key {}", value))
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+/// `<mint>:<weight>`
+fn parse_asset(value: &str) -> Result<(Pubkey, u8), String> {
+    let (mint, weight) = value
+        .split_once(':')
+        .ok_or(format!("expected <mint>:<weight>, got {}", value))?;
+    Ok((pubkey(mint)?, number(weight)?))
+}
+
+/// `<unix-ts>:<weight>,<weight>...`
+fn parse_step(value: &str) -> Result<ScheduleStep, String> {
+    let (at, weights) = value
+        .split_once(':')
+        .ok_or(format!("expected <unix-ts>:<weight>,..., got {}", value))?;
+    Ok(ScheduleStep {
+        at: number(at)?,
+        weights: weights.split(',').map(number).collect::<Result<_, _>>()?,
+    })
+}
+
+/// `sol`, `marinade:<state>` or `stake-pool:<pool>`
+fn parse_lst_pool(value: &str) -> Result<LstPool, String> {
+    let (kind, pool) = value.split_once(':').unwrap_or((value, ""));
+    let kind = match kind {
+        "sol" if pool.is_empty() => {
+            return Ok(LstPool {
+                kind: LstKind::Sol,
+                pool: Pubkey::default(),
+            })
+        }
+        "marinade" => LstKind::Marinade,
+        "stake-pool" => LstKind::SplStakePool,
+        _ => {
+            return Err(format!(
+                "expected sol, marinade:<state> or stake-pool:<pool>, got {}",
+                value
+            ))
+        }
+    };
+    Ok(LstPool {
+        kind,
+        pool: pubkey(pool)?,
+    })
+}
+
+/// 32 bytes as 64 hex digits, with or without 0x (feed hashes and ids)
+fn parse_hex32(value: &str) -> Result<[u8; 32], String> {
+    let invalid = || format!("expected 32 bytes of hex, got {}", value);
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let mut hash = [0u8; 32];
+    for (byte, pair) in hash.iter_mut().zip(digits.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+    }
+    Ok(hash)
+}
+
+/// Decimal USD ("65000.25") to micro-USD
+pub fn parse_usd(value: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid USD price {}", value);
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 6 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let whole: i64 = whole.parse().map_err(|_| invalid())?;
+    let fraction: i64 = format!("{:0<6}", fraction).parse().map_err(|_| invalid())?;
+    whole
+        .checked_mul(1_000_000)
+        .and_then(|micro| micro.checked_add(fraction))
+        .filter(|price| *price > 0)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let mint = Pubkey::new_unique();
+        let parsed = Args::parse(&args(&format!(
+            "--url http://localhost:8899 create-vault etf {}:60 {}:40",
+            mint, mint
+        )))
+        .unwrap();
+        assert_eq!(parsed.url.as_deref(), Some("http://localhost:8899"));
+        assert_eq!(
+            Command::parse(&parsed.command).unwrap(),
+            Command::CreateVault {
+                name: "etf".to_string(),
+                assets: vec![(mint, 60), (mint, 40)],
+                soulbound: false,
+            }
+        );
+        assert_eq!(
+            Command::parse(&args(&format!("create-vault etf {}:100 --soulbound", mint))).unwrap(),
+            Command::CreateVault {
+                name: "etf".to_string(),
+                assets: vec![(mint, 100)],
+                soulbound: true,
+            }
+        );
+        assert_eq!(
+            Command::parse(&args(&format!(
+                "add-vault-asset etf {}:25 --token-2022",
+                mint
+            )))
+            .unwrap(),
+            Command::AddVaultAsset {
+                name: "etf".to_string(),
+                asset: (mint, 25),
+                token_2022: true,
+            }
+        );
+        assert!(Command::parse(&args("add-vault-asset etf")).is_err());
+
+        assert_eq!(
+            Command::parse(&args("divest etf 5000 --mock-marinade")).unwrap(),
+            Command::Divest {
+                name: "etf".to_string(),
+                msol_amount: 5000,
+                mock_marinade: true,
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("set-price-source etf aggregated")).unwrap(),
+            Command::SetPriceSource {
+                name: "etf".to_string(),
+                source: PriceSource::Aggregated,
+                mock_oracle: None,
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("set-cross-check etf 150")).unwrap(),
+            Command::SetCrossCheck {
+                name: "etf".to_string(),
+                max_divergence_bps: 150,
+                mock_oracle: None,
+            }
+        );
+        let usdc = Pubkey::new_unique();
+        assert_eq!(
+            Command::parse(&args(&format!("set-quote-currency etf usdc {}", usdc))).unwrap(),
+            Command::SetQuoteCurrency {
+                name: "etf".to_string(),
+                currency: QuoteCurrency::Usdc,
+                quote_mint: Some(usdc),
+            }
+        );
+        assert!(Command::parse(&args("set-quote-currency etf usdc")).is_err());
+        let eur_feed = Pubkey::new_unique();
+        assert_eq!(
+            Command::parse(&args(&format!("set-nav-currency etf eur {}", eur_feed))).unwrap(),
+            Command::SetNavCurrency {
+                name: "etf".to_string(),
+                currency: *b"EUR",
+                fx_feed: Some(eur_feed),
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("set-nav-currency etf USD")).unwrap(),
+            Command::SetNavCurrency {
+                name: "etf".to_string(),
+                currency: *b"USD",
+                fx_feed: None,
+            }
+        );
+        assert!(Command::parse(&args("set-nav-currency etf eur")).is_err());
+        assert!(Command::parse(&args("set-nav-currency etf euro")).is_err());
+        assert_eq!(
+            Command::parse(&args(
+                "set-vault-metadata etf Index 2 https://x.io/etf.png Blue chip crypto basket"
+            ))
+            .unwrap(),
+            Command::SetVaultMetadata {
+                name: "etf".to_string(),
+                category: "Index".to_string(),
+                risk_level: 2,
+                icon_uri: "https://x.io/etf.png".to_string(),
+                description: "Blue chip crypto basket".to_string(),
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("update-oracle 65000.5 3000 150.25")).unwrap(),
+            Command::UpdateOracle {
+                btc: 65_000_500_000,
+                eth: 3_000_000_000,
+                sol: 150_250_000,
+                owner: None,
+            }
+        );
+        let (owner, feeder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            Command::parse(&args(&format!("update-oracle 1 2 3 {}", owner))).unwrap(),
+            Command::UpdateOracle {
+                btc: 1_000_000,
+                eth: 2_000_000,
+                sol: 3_000_000,
+                owner: Some(owner),
+            }
+        );
+        assert_eq!(
+            Command::parse(&args(&format!("oracle-feeders {} {}", owner, feeder))).unwrap(),
+            Command::OracleFeeders {
+                feeders: vec![owner, feeder],
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("oracle-feeders")).unwrap(),
+            Command::OracleFeeders { feeders: vec![] }
+        );
+        assert_eq!(
+            Command::parse(&args("market-cap-index etf on 86400 5")).unwrap(),
+            Command::MarketCapIndex {
+                name: "etf".to_string(),
+                enabled: true,
+                interval_secs: 86_400,
+                max_weight_change: 5,
+                supply_authority: None,
+            }
+        );
+        assert!(Command::parse(&args("market-cap-index etf yes 86400 5")).is_err());
+        assert_eq!(
+            Command::parse(&args(
+                "set-schedule etf 1700000000:20,20,60 1800000000:40,40,20"
+            ))
+            .unwrap(),
+            Command::SetSchedule {
+                name: "etf".to_string(),
+                steps: vec![
+                    ScheduleStep {
+                        at: 1_700_000_000,
+                        weights: vec![20, 20, 60],
+                    },
+                    ScheduleStep {
+                        at: 1_800_000_000,
+                        weights: vec![40, 40, 20],
+                    },
+                ],
+            }
+        );
+        assert!(Command::parse(&args("set-schedule etf 1700000000")).is_err());
+        let pool = Pubkey::new_unique();
+        assert_eq!(
+            Command::parse(&args(&format!(
+                "lst-basket lst stake-pool:{} marinade:{} sol",
+                pool, pool
+            )))
+            .unwrap(),
+            Command::LstBasket {
+                name: "lst".to_string(),
+                pools: vec![
+                    LstPool {
+                        kind: LstKind::SplStakePool,
+                        pool,
+                    },
+                    LstPool {
+                        kind: LstKind::Marinade,
+                        pool,
+                    },
+                    LstPool {
+                        kind: LstKind::Sol,
+                        pool: Pubkey::default(),
+                    },
+                ],
+            }
+        );
+        assert!(Command::parse(&args("lst-basket lst jito")).is_err());
+        assert_eq!(
+            Command::parse(&args(&format!("asset-whitelist btc:{} eth:{}", pool, pool))).unwrap(),
+            Command::AssetWhitelist {
+                btc_alternates: vec![pool],
+                eth_alternates: vec![pool],
+            }
+        );
+        assert!(Command::parse(&args(&format!("asset-whitelist sol:{}", pool))).is_err());
+        assert_eq!(
+            Command::parse(&args(&format!("pyth-lazer-config 1,2,6 {}", pool))).unwrap(),
+            Command::PythLazerConfig {
+                feed_ids: [1, 2, 6],
+                trusted_signers: vec![pool],
+            }
+        );
+        assert!(Command::parse(&args("pyth-lazer-config 1,2")).is_err());
+        let (btc, eth, sol) = (
+            "ab".repeat(32),
+            format!("0x{}", "01".repeat(32)),
+            "FF".repeat(32),
+        );
+        assert_eq!(
+            Command::parse(&args(&format!(
+                "switchboard-config {} {} {}",
+                btc, eth, sol
+            )))
+            .unwrap(),
+            Command::SwitchboardConfig {
+                feed_hashes: [[0xab; 32], [0x01; 32], [0xff; 32]],
+            }
+        );
+        assert_eq!(
+            Command::parse(&args(&format!("chainlink-config {} {} {}", sol, btc, eth))).unwrap(),
+            Command::ChainlinkConfig {
+                feed_ids: [[0xff; 32], [0xab; 32], [0x01; 32]],
+            }
+        );
+        assert!(Command::parse(&args(&format!("switchboard-config {} {} ab", btc, eth))).is_err());
+        assert!(Command::parse(&args(&format!(
+            "switchboard-config {} {} {}+",
+            btc,
+            eth,
+            &sol[1..]
+        )))
+        .is_err());
+        assert_eq!(
+            Command::parse(&args("set-min-deposit-shares etf 1000")).unwrap(),
+            Command::SetMinDepositShares {
+                name: "etf".to_string(),
+                shares: 1_000,
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("set-max-turnover etf 1000")).unwrap(),
+            Command::SetMaxTurnover {
+                name: "etf".to_string(),
+                bps: 1_000,
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("set-dust etf 500000 100 0 2000")).unwrap(),
+            Command::SetDust {
+                name: "etf".to_string(),
+                min_swap_usd: 500_000,
+                thresholds: vec![100, 0, 2_000],
+            }
+        );
+        assert_eq!(
+            Command::parse(&args("clear-operation-lock etf")).unwrap(),
+            Command::ClearOperationLock {
+                name: "etf".to_string(),
+            }
+        );
+        assert_eq!(
+            Command::parse(&args(&format!(
+                "sweep-token etf {} {} --token-2022",
+                pool, mint
+            )))
+            .unwrap(),
+            Command::SweepToken {
+                name: "etf".to_string(),
+                mint: pool,
+                treasury: mint,
+                token_2022: true,
+            }
+        );
+        assert!(Command::parse(&args(&format!("sweep-token etf {}", pool))).is_err());
+        assert_eq!(
+            Command::parse(&args(&format!("close-asset-ata etf {}", pool))).unwrap(),
+            Command::CloseAssetAta {
+                name: "etf".to_string(),
+                mint: pool,
+                token_2022: false,
+            }
+        );
+        assert!(Command::parse(&args("set-deposit-fee etf 50")).is_err());
+        assert!(Command::parse(&args("set-price-source etf pyth")).is_err());
+        assert!(Args::parse(&args("--admin")).is_err());
+    }
+
+    #[test]
+    fn test_parse_usd() {
+        assert_eq!(parse_usd("1").unwrap(), 1_000_000);
+        assert_eq!(parse_usd("0.000001").unwrap(), 1);
+        assert!(parse_usd("0.0000001").is_err());
+        assert!(parse_usd("0").is_err());
+        assert!(parse_usd("-5").is_err());
+        assert!(parse_usd("1.2x").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! CLI configuration: RPC URL and keypair, from a TOML file and flags
+//!
+//! Default file: ~/.config/etf-defi/cli.toml
+//!
+//! ```toml
+//! rpc_url = "http://127.0.0.1:8899"
+//! keypair_path = "~/.config/solana/id.json"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+pub const DEFAULT_CONFIG_PATH: &str = "~/.config/etf-defi/cli.toml";
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct CliConfig {
+    #[serde(default = "default_rpc_url")]
+    pub rpc_url: String,
+    #[serde(default = "default_keypair_path")]
+    pub keypair_path: String,
+}
+
+fn default_rpc_url() -> String {
+    "http://127.0.0.1:8899".to_string()
+}
+
+fn default_keypair_path() -> String {
+    "~/.config/solana/id.json".to_string()
+}
+
+impl Default for CliConfig {
+    fn default() -> Self {
+        Self {
+            rpc_url: default_rpc_url(),
+            keypair_path: default_keypair_path(),
+        }
+    }
+}
+
+impl CliConfig {
+    /// Load `path`; the default path may be missing, an explicit one may not
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let file = expand_home(path.unwrap_or(DEFAULT_CONFIG_PATH));
+        match std::fs::read_to_string(&file) {
+            Ok(contents) => {
+                toml::from_str(&contents).map_err(|err| format!("{}: {}", file.display(), err))
+            }
+            Err(_) if path.is_none() => Ok(Self::default()),
+            Err(err) => Err(format!("{}: {}", file.display(), err)),
+        }
+    }
+}
+
+/// Expand a leading `~/` to $HOME
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
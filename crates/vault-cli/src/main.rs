@@ -0,0 +1,547 @@
+//! ETF-DeFi vault admin CLI
+//!
+//! Builds each admin instruction with etf-client, signs it with the
+//! configured keypair and waits for confirmation. Run without arguments for
+//! usage.
+
+mod command;
+mod config;
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::AddressLookupTableAccount;
+use etf_client::fetch;
+use etf_client::keypair::Keypair;
+use etf_client::marinade::MarinadeAccounts;
+use etf_client::rpc::{RpcClient, SignatureStatus};
+use etf_client::vault::state::{Vault, SECONDS_PER_DAY};
+use etf_client::vault::{PriceSource, QuoteCurrency};
+use etf_client::vault_ix::{self, VaultId};
+use etf_client::{lookup_table, pda, strategy_ix, transaction};
+
+use crate::command::{Args, Command, USAGE};
+use crate::config::{expand_home, CliConfig};
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let args = Args::parse(args)?;
+    let command = Command::parse(&args.command)?;
+    let config = CliConfig::load(args.config.as_deref())?;
+    let rpc_url = args.url.unwrap_or(config.rpc_url);
+    let keypair_path = expand_home(args.keypair.as_deref().unwrap_or(&config.keypair_path));
+
+    let rpc = RpcClient::new(&rpc_url).map_err(|err| err.to_string())?;
+    let signer = Keypair::read_file(&keypair_path).map_err(|err| err.to_string())?;
+    let admin = args.admin.unwrap_or(signer.pubkey());
+
+    match &command {
+        Command::List => return list(&rpc),
+        Command::Show { name } => return show(&rpc, &admin, name),
+        Command::CloseVault { name } => {
+            let instructions = close_vault(&rpc, &signer.pubkey(), &admin, name)?;
+            return send(&rpc, &signer, &instructions);
+        }
+        Command::LookupTable {
+            name,
+            mock_marinade,
+        } => return lookup_table(&rpc, &signer, &admin, name, *mock_marinade),
+        _ => {}
+    }
+    let instructions = instructions(&command, &signer.pubkey(), &admin);
+    send(&rpc, &signer, &instructions)
+}
+
+/// Instructions for every command except `list`, `show`, `close-vault` and
+/// `lookup-table`
+fn instructions(command: &Command, signer: &Pubkey, admin: &Pubkey) -> Vec<Instruction> {
+    let id = |name: &str| VaultId::new(*admin, name);
+    match command {
+        Command::List
+        | Command::Show { .. }
+        | Command::CloseVault { .. }
+        | Command::LookupTable { .. } => Vec::new(),
+        Command::RegisterVault { name } => vec![vault_ix::register_vault(&id(name), signer)],
+        Command::MigrateVault { name } => vec![vault_ix::migrate_vault(&id(name), signer)],
+        Command::CreateVault {
+            name,
+            assets,
+            soulbound,
+        } => vec![vault_ix::create_vault(signer, name, assets, *soulbound)],
+        Command::InitVault { name, soulbound } => {
+            vec![vault_ix::init_vault(signer, name, *soulbound)]
+        }
+        Command::AddVaultAsset {
+            name,
+            asset: (mint, weight),
+            token_2022,
+        } => vec![vault_ix::add_vault_asset(
+            &id(name),
+            signer,
+            mint,
+            *weight,
+            *token_2022,
+        )],
+        Command::FinalizeVault { name } => vec![vault_ix::finalize_vault(&id(name), signer)],
+        Command::InitStrategy {
+            name,
+            mock_marinade,
+        } => {
+            let marinade = if *mock_marinade {
+                MarinadeAccounts::mock()
+            } else {
+                MarinadeAccounts::mainnet()
+            };
+            vec![strategy_ix::initialize(
+                &id(name).address(),
+                signer,
+                &marinade,
+            )]
+        }
+        Command::SetStrategy { name, strategy } => {
+            let strategy = strategy.unwrap_or_else(|| pda::marinade_strategy(&id(name).address()));
+            vec![vault_ix::set_strategy(&id(name), signer, strategy)]
+        }
+        Command::RemoveStrategy { name } => vec![vault_ix::remove_strategy(&id(name), signer)],
+        Command::Divest {
+            name,
+            msol_amount,
+            mock_marinade,
+        } => {
+            let marinade = if *mock_marinade {
+                MarinadeAccounts::mock()
+            } else {
+                MarinadeAccounts::mainnet()
+            };
+            vec![
+                vault_ix::sync_strategy_value(&id(name), signer, &marinade),
+                vault_ix::divest_from_strategy(&id(name), signer, &marinade, *msol_amount),
+            ]
+        }
+        Command::SetPriceSource {
+            name,
+            source,
+            mock_oracle,
+        } => {
+            let mock_oracle = match source {
+                PriceSource::MockOracle => {
+                    Some(mock_oracle.unwrap_or_else(|| pda::mock_oracle(signer)))
+                }
+                _ => *mock_oracle,
+            };
+            vec![vault_ix::set_price_source(
+                &id(name),
+                signer,
+                *source,
+                mock_oracle,
+            )]
+        }
+        Command::SetCrossCheck {
+            name,
+            max_divergence_bps,
+            mock_oracle,
+        } => vec![vault_ix::set_cross_checked_pricing(
+            &id(name),
+            signer,
+            mock_oracle.unwrap_or_else(|| pda::mock_oracle(signer)),
+            *max_divergence_bps,
+        )],
+        Command::SetQuoteCurrency {
+            name,
+            currency,
+            quote_mint,
+        } => {
+            // SOL quoting ignores the mint; any mint account will do
+            let id = id(name);
+            let quote_mint = quote_mint.unwrap_or_else(|| id.share_mint());
+            vec![vault_ix::set_quote_currency(
+                &id,
+                signer,
+                *currency,
+                &quote_mint,
+            )]
+        }
+        Command::SetNavCurrency {
+            name,
+            currency,
+            fx_feed,
+        } => {
+            // USD ignores the feed; any account will do
+            let id = id(name);
+            let fx_feed = fx_feed.unwrap_or_else(|| id.address());
+            vec![vault_ix::set_nav_currency(&id, signer, *currency, &fx_feed)]
+        }
+        Command::UpdateComposition { name, weights } => {
+            vec![vault_ix::update_composition(
+                &id(name),
+                signer,
+                weights.clone(),
+            )]
+        }
+        Command::MarketCapIndex {
+            name,
+            enabled,
+            interval_secs,
+            max_weight_change,
+            supply_authority,
+        } => vec![vault_ix::configure_market_cap_index(
+            &id(name),
+            signer,
+            *enabled,
+            *supply_authority,
+            *interval_secs,
+            *max_weight_change,
+        )],
+        Command::SetSupplies { name, supplies } => vec![vault_ix::set_asset_supplies(
+            &id(name),
+            signer,
+            supplies.clone(),
+        )],
+        Command::SetSchedule { name, steps } => vec![vault_ix::set_composition_schedule(
+            &id(name),
+            signer,
+            steps.clone(),
+        )],
+        Command::LstBasket { name, pools } => vec![vault_ix::configure_lst_basket(
+            &id(name),
+            signer,
+            pools.clone(),
+        )],
+        Command::Pause { name, successor } => vec![vault_ix::suggest_alternative(
+            &id(name),
+            signer,
+            successor,
+            true,
+        )],
+        Command::Resume { name } => vec![vault_ix::clear_alternative(&id(name), signer)],
+        Command::SetDepositFee {
+            name,
+            fee_bps,
+            referral_share_bps,
+        } => vec![vault_ix::set_deposit_fee(
+            &id(name),
+            signer,
+            *fee_bps,
+            *referral_share_bps,
+        )],
+        Command::SetExitFee {
+            name,
+            max_fee_bps,
+            window_secs,
+        } => vec![vault_ix::set_exit_fee(
+            &id(name),
+            signer,
+            *max_fee_bps,
+            *window_secs,
+        )],
+        Command::SetStakerFeeShare { name, bps } => {
+            vec![vault_ix::set_staker_fee_share(&id(name), signer, *bps)]
+        }
+        Command::SetMaxSlippage { name, bps } => {
+            vec![vault_ix::set_max_slippage(&id(name), signer, *bps)]
+        }
+        Command::SetMinDepositShares { name, shares } => {
+            vec![vault_ix::set_min_deposit_shares(&id(name), signer, *shares)]
+        }
+        Command::SetMaxTurnover { name, bps } => {
+            vec![vault_ix::set_max_turnover(&id(name), signer, *bps)]
+        }
+        Command::SetDust {
+            name,
+            min_swap_usd,
+            thresholds,
+        } => vec![vault_ix::set_dust_thresholds(
+            &id(name),
+            signer,
+            *min_swap_usd,
+            thresholds.clone(),
+        )],
+        Command::ClearOperationLock { name } => {
+            vec![vault_ix::clear_operation_lock(&id(name), signer)]
+        }
+        Command::SetShareMetadata {
+            name,
+            token_name,
+            symbol,
+            uri,
+        } => vec![vault_ix::set_share_metadata(
+            &id(name),
+            signer,
+            token_name,
+            symbol,
+            uri,
+        )],
+        Command::SetVaultMetadata {
+            name,
+            category,
+            risk_level,
+            icon_uri,
+            description,
+        } => vec![vault_ix::set_vault_metadata(
+            &id(name),
+            signer,
+            description,
+            icon_uri,
+            category,
+            *risk_level,
+        )],
+        Command::WindDown { name, grace_days } => vec![vault_ix::begin_wind_down(
+            &id(name),
+            signer,
+            grace_days * SECONDS_PER_DAY,
+        )],
+        Command::ClaimCreatorFees { name } => vec![vault_ix::claim_creator_fees(&id(name))],
+        Command::SweepToken {
+            name,
+            mint,
+            treasury,
+            token_2022,
+        } => vec![
+            vault_ix::create_treasury_token_account(signer, treasury, mint, *token_2022),
+            vault_ix::sweep_unknown_token(&id(name), signer, treasury, mint, *token_2022),
+        ],
+        Command::CloseAssetAta {
+            name,
+            mint,
+            token_2022,
+        } => vec![vault_ix::close_asset_ata(
+            &id(name),
+            signer,
+            mint,
+            *token_2022,
+        )],
+        Command::ChainlinkConfig { feed_ids } => {
+            vec![vault_ix::set_chainlink_config(signer, *feed_ids)]
+        }
+        Command::SwitchboardConfig { feed_hashes } => {
+            vec![vault_ix::set_switchboard_config(signer, *feed_hashes)]
+        }
+        Command::PythLazerConfig {
+            feed_ids,
+            trusted_signers,
+        } => vec![vault_ix::set_pyth_lazer_config(
+            signer,
+            trusted_signers.clone(),
+            *feed_ids,
+        )],
+        Command::AssetWhitelist {
+            btc_alternates,
+            eth_alternates,
+        } => vec![vault_ix::set_asset_whitelist(
+            signer,
+            btc_alternates.clone(),
+            eth_alternates.clone(),
+        )],
+        Command::InitOracle => vec![vault_ix::initialize_mock_oracle(signer)],
+        Command::UpdateOracle {
+            btc,
+            eth,
+            sol,
+            owner: None,
+        } => vec![vault_ix::update_mock_oracle(signer, *btc, *eth, *sol)],
+        Command::UpdateOracle {
+            btc,
+            eth,
+            sol,
+            owner: Some(owner),
+        } => vec![vault_ix::update_mock_oracle_as_feeder(
+            owner, signer, *btc, *eth, *sol,
+        )],
+        Command::OracleFeeders { feeders } => {
+            vec![vault_ix::set_oracle_feeders(signer, feeders.clone())]
+        }
+    }
+}
+
+fn send(rpc: &RpcClient, signer: &Keypair, instructions: &[Instruction]) -> Result<(), String> {
+    let blockhash = rpc.get_latest_blockhash().map_err(|err| err.to_string())?;
+    let transaction = transaction::sign_transaction(instructions, signer, &[], blockhash)
+        .map_err(|err| err.to_string())?;
+    let signature = rpc
+        .send_transaction(&transaction)
+        .map_err(|err| err.to_string())?;
+    match rpc.wait_for_signature(&signature, CONFIRM_TIMEOUT) {
+        Ok(SignatureStatus::Confirmed) => {
+            println!("{}", signature);
+            Ok(())
+        }
+        Ok(SignatureStatus::Failed(err)) => Err(format!("{} failed: {}", signature, err)),
+        Ok(SignatureStatus::Pending) => Err(format!(
+            "{} not confirmed after {:?}",
+            signature, CONFIRM_TIMEOUT
+        )),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// close_vault, preceded by the admin ATAs that receive leftover dust
+fn close_vault(
+    rpc: &RpcClient,
+    signer: &Pubkey,
+    admin: &Pubkey,
+    name: &str,
+) -> Result<Vec<Instruction>, String> {
+    let vault = fetch::fetch_vault(rpc, admin, name).map_err(|err| err.to_string())?;
+    let registered = fetch::fetch_vault_registration(rpc, &pda::vault(admin, name))
+        .map_err(|err| err.to_string())?
+        .is_some();
+    let mut instructions = vault_ix::create_dust_receiver_accounts(signer, &vault);
+    instructions.push(vault_ix::close_vault(&vault, signer, registered));
+    Ok(instructions)
+}
+
+/// Create the vault's lookup table if it has none, then add whichever of
+/// its deposit/withdraw accounts are missing, one transaction per extension
+fn lookup_table(
+    rpc: &RpcClient,
+    signer: &Keypair,
+    admin: &Pubkey,
+    name: &str,
+    mock_marinade: bool,
+) -> Result<(), String> {
+    let id = VaultId::new(*admin, name);
+    let vault = fetch::fetch_vault(rpc, admin, name).map_err(|err| err.to_string())?;
+    let existing = match lookup_table::fetch_lookup_table(rpc, &id.address())
+        .map_err(|err| err.to_string())?
+    {
+        Some(table) => table,
+        None => {
+            let slot = rpc.get_slot().map_err(|err| err.to_string())?;
+            let (instruction, address) = vault_ix::create_lookup_table(&id, &signer.pubkey(), slot);
+            send(rpc, signer, &[instruction])?;
+            AddressLookupTableAccount {
+                key: address,
+                addresses: Vec::new(),
+            }
+        }
+    };
+
+    let marinade = if mock_marinade {
+        MarinadeAccounts::mock()
+    } else {
+        MarinadeAccounts::mainnet()
+    };
+    let wanted = lookup_table::vault_addresses(&vault, &marinade).map_err(|err| err.to_string())?;
+    let missing = lookup_table::missing_addresses(&wanted, &existing.addresses);
+    for instruction in vault_ix::extend_lookup_table(&id, &signer.pubkey(), &existing.key, &missing)
+    {
+        send(rpc, signer, &[instruction])?;
+    }
+    println!(
+        "lookup table {}: {} addresses",
+        existing.key,
+        existing.addresses.len() + missing.len()
+    );
+    Ok(())
+}
+
+fn list(rpc: &RpcClient) -> Result<(), String> {
+    let registrations = fetch::list_vault_registrations(rpc).map_err(|err| err.to_string())?;
+    for registration in registrations {
+        let vault: Vault = fetch::fetch(rpc, &registration.vault).map_err(|err| err.to_string())?;
+        println!(
+            "{:>4}  {}  {:<32}  admin {}  TVL ${:.2}",
+            registration.index,
+            registration.vault,
+            vault.name(),
+            registration.admin,
+            vault.last_tvl_usd as f64 / 1e6
+        );
+    }
+    Ok(())
+}
+
+fn show(rpc: &RpcClient, admin: &Pubkey, name: &str) -> Result<(), String> {
+    let vault = fetch::fetch_vault(rpc, admin, name).map_err(|err| err.to_string())?;
+    println!("vault          {}", pda::vault(admin, name));
+    println!("name           {}", vault.name());
+    println!("admin          {}", vault.admin);
+    println!("share mint     {}", vault.vault_token_mint);
+    println!("total shares   {}", vault.total_shares);
+    println!("TVL (USD)      {}", vault.last_tvl_usd as f64 / 1e6);
+    let quote =
+        fetch::fetch_quote_config(rpc, &pda::vault(admin, name)).map_err(|err| err.to_string())?;
+    match &quote {
+        Some(quote) if quote.currency == QuoteCurrency::Usdc => {
+            println!("quoted in      USDC ({})", quote.quote_mint)
+        }
+        _ => println!("quoted in      SOL"),
+    }
+    match quote
+        .as_ref()
+        .and_then(|quote| quote.fx_feed().map(|feed| (quote, feed)))
+    {
+        Some((quote, feed)) => println!(
+            "NAV in         {} (FX feed {})",
+            String::from_utf8_lossy(&quote.nav_currency),
+            feed
+        ),
+        None => println!("NAV in         USD"),
+    }
+    println!("price source   {:?}", vault.price_source());
+    if let Some(oracle) = vault.mock_oracle() {
+        println!("mock oracle    {}", oracle);
+    }
+    println!("swap backend   {:?}", vault.swap_backend());
+    match vault.marinade_strategy() {
+        Some(strategy) => println!("strategy       {}", strategy),
+        None => println!("strategy       none"),
+    }
+    println!(
+        "deposits       {}",
+        if vault.deposits_paused() {
+            "paused"
+        } else {
+            "open"
+        }
+    );
+    if vault.wind_down_deadline != 0 {
+        println!("winding down   closable from {}", vault.wind_down_deadline);
+    }
+    if let Some(successor) = vault.successor() {
+        println!("successor      {}", successor);
+    }
+    println!(
+        "fees (bps)     deposit {}, referral share {}, exit {} over {}s, staker share {}",
+        vault.deposit_fee_bps,
+        vault.referral_share_bps,
+        vault.exit_fee_bps,
+        vault.exit_fee_window_secs,
+        vault.staker_fee_share_bps
+    );
+    println!("max slippage   {} bps", vault.max_slippage_bps);
+    println!("layout         v{}", vault.version);
+    let metadata = fetch::fetch_vault_metadata(rpc, &pda::vault(admin, name))
+        .map_err(|err| err.to_string())?;
+    if let Some(metadata) = metadata {
+        println!(
+            "category       {} (risk {})",
+            metadata.category, metadata.risk_level
+        );
+        println!("icon           {}", metadata.icon_uri);
+        println!("description    {}", metadata.description);
+    }
+    println!("assets");
+    for asset in vault.assets() {
+        println!("  {} {:>3}%  ata {}", asset.mint, asset.weight, asset.ata);
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+
+declare_id!("7oeiPCWzYyrtv1uGjpELcCn8hEzq76hca4GCggCdD6Ue");
+
+// Longest test asset symbol (used as a PDA seed)
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Devnet faucet for the vault's test assets (wBTC, wETH, ...)
+///
+/// The faucet PDA is the mint authority of every test mint it creates, so
+/// users can drip small amounts (once per cooldown per wallet and mint) and
+/// the faucet authority can fund mock AMM pools without manual minting.
+#[program]
+pub mod faucet {
+    use super::*;
+
+    pub fn initialize_faucet(ctx: Context<InitializeFaucet>, cooldown_secs: i64) -> Result<()> {
+        require!(cooldown_secs >= 0, ErrorCode::InvalidCooldown);
+
+        let faucet = &mut ctx.accounts.faucet;
+        faucet.bump = ctx.bumps.faucet;
+        faucet.authority = ctx.accounts.authority.key();
+        faucet.cooldown_secs = cooldown_secs;
+
+        msg!("Faucet initialized with a {}s cooldown", cooldown_secs);
+        Ok(())
+    }
+
+    /// Create a test mint (e.g. "wBTC" with 8 decimals) owned by the faucet
+    pub fn create_test_asset(
+        ctx: Context<CreateTestAsset>,
+        symbol: String,
+        decimals: u8,
+        drip_amount: u64,
+    ) -> Result<()> {
+        require!(
+            !symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LEN,
+            ErrorCode::InvalidSymbol
+        );
+        require!(drip_amount > 0, ErrorCode::ZeroAmount);
+
+        let asset = &mut ctx.accounts.test_asset;
+        asset.bump = ctx.bumps.test_asset;
+        asset.mint = ctx.accounts.mint.key();
+        asset.symbol = symbol;
+        asset.decimals = decimals;
+        asset.drip_amount = drip_amount;
+
+        msg!("Test asset {} created: {} ({} decimals)", asset.symbol, asset.mint, decimals);
+        Ok(())
+    }
+
+    /// Mint the asset's drip amount to the caller, at most once per cooldown
+    pub fn drip(ctx: Context<Drip>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let record = &mut ctx.accounts.drip_record;
+        require!(
+            record.last_drip_at == 0 || now - record.last_drip_at >= ctx.accounts.faucet.cooldown_secs,
+            ErrorCode::CooldownActive
+        );
+        record.bump = ctx.bumps.drip_record;
+        record.last_drip_at = now;
+
+        let amount = ctx.accounts.test_asset.drip_amount;
+        mint_from_faucet(
+            &ctx.accounts.faucet,
+            &ctx.accounts.mint,
+            &ctx.accounts.user_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        msg!("Dripped {} {} to {}", amount, ctx.accounts.test_asset.symbol, ctx.accounts.user.key());
+        Ok(())
+    }
+
+    /// Mint any amount to any token account, e.g. a mock AMM pool (faucet authority only)
+    pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmount);
+
+        mint_from_faucet(
+            &ctx.accounts.faucet,
+            &ctx.accounts.mint,
+            &ctx.accounts.destination.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        msg!("Funded {} with {} {}", ctx.accounts.destination.key(), amount, ctx.accounts.test_asset.symbol);
+        Ok(())
+    }
+}
+
+fn mint_from_faucet<'info>(
+    faucet: &Account<'info, Faucet>,
+    mint: &Account<'info, Mint>,
+    to: &AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"faucet".as_ref(), &[faucet.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = MintTo {
+        mint: mint.to_account_info(),
+        to: to.clone(),
+        authority: faucet.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    token::mint_to(cpi_ctx, amount)
+}
+
+#[derive(Accounts)]
+pub struct InitializeFaucet<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Faucet::INIT_SPACE,
+        seeds = [b"faucet"],
+        bump
+    )]
+    pub faucet: Account<'info, Faucet>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(symbol: String, decimals: u8)]
+pub struct CreateTestAsset<'info> {
+    #[account(
+        seeds = [b"faucet"],
+        bump = faucet.bump,
+        has_one = authority
+    )]
+    pub faucet: Account<'info, Faucet>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"mint", symbol.as_bytes()],
+        bump,
+        mint::decimals = decimals,
+        mint::authority = faucet
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TestAsset::INIT_SPACE,
+        seeds = [b"asset", mint.key().as_ref()],
+        bump
+    )]
+    pub test_asset: Account<'info, TestAsset>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Drip<'info> {
+    #[account(seeds = [b"faucet"], bump = faucet.bump)]
+    pub faucet: Account<'info, Faucet>,
+
+    #[account(
+        seeds = [b"asset", mint.key().as_ref()],
+        bump = test_asset.bump
+    )]
+    pub test_asset: Account<'info, TestAsset>,
+
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + DripRecord::INIT_SPACE,
+        seeds = [b"drip", mint.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub drip_record: Account<'info, DripRecord>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::authority = user,
+        associated_token::mint = mint
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Fund<'info> {
+    #[account(
+        seeds = [b"faucet"],
+        bump = faucet.bump,
+        has_one = authority
+    )]
+    pub faucet: Account<'info, Faucet>,
+
+    #[account(
+        seeds = [b"asset", mint.key().as_ref()],
+        bump = test_asset.bump
+    )]
+    pub test_asset: Account<'info, TestAsset>,
+
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Faucet {
+    pub bump: u8,
+    pub authority: Pubkey,
+    /// Seconds a wallet waits between drips of the same mint
+    pub cooldown_secs: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TestAsset {
+    pub bump: u8,
+    pub mint: Pubkey,
+    #[max_len(MAX_SYMBOL_LEN)]
+    pub symbol: String,
+    pub decimals: u8,
+    /// Base units minted per drip
+    pub drip_amount: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct DripRecord {
+    pub bump: u8,
+    pub last_drip_at: i64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Cooldown must not be negative")]
+    InvalidCooldown,
+    #[msg("Symbol must be 1-10 bytes")]
+    InvalidSymbol,
+    #[msg("This wallet already dripped this asset within the cooldown")]
+    CooldownActive,
+}
@@ -1,7 +1,9 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 use anchor_spl::associated_token::AssociatedToken;
-use marinade_cpi::program::MarinadeFinance;
 use marinade_cpi::cpi::accounts::{Deposit, LiquidUnstake};
 use marinade_cpi::cpi::{deposit as marinade_deposit, liquid_unstake as marinade_liquid_unstake};
 
@@ -132,11 +134,35 @@ pub mod marinade_strategy {
         // SOL is already in the receiver account, no need to transfer
         // The receiver should be the final destination (user account)
         
+        // Release the unstaked share of the cost basis and refresh the mSOL balance
+        let msol_before = ctx.accounts.msol_ata.amount;
+        ctx.accounts.msol_ata.reload()?;
+        let strategy = &mut ctx.accounts.strategy_account;
+        let basis_released = (strategy.total_staked as u128 * msol_amount as u128 / msol_before as u128) as u64;
+        strategy.total_staked = strategy.total_staked.saturating_sub(basis_released);
+        strategy.msol_balance = ctx.accounts.msol_ata.amount;
+        
         msg!("Liquid unstaked {} mSOL, received {} lamports SOL", msol_amount, sol_received);
         
         Ok(())
     }
 
+    /// Account for mSOL the vault delivered straight into the strategy's mSOL
+    /// ATA (instead of staking SOL): refreshes msol_balance and adds the
+    /// mSOL's SOL value, `lamports`, to total_staked as its cost basis
+    pub fn record_deposit(ctx: Context<RecordDeposit>, lamports: u64) -> Result<()> {
+        require!(lamports > 0, ErrorCode::ZeroAmount);
+
+        let strategy = &mut ctx.accounts.strategy_account;
+        strategy.total_staked = strategy.total_staked.checked_add(lamports)
+            .ok_or(ErrorCode::MathOverflow)?;
+        strategy.msol_balance = ctx.accounts.msol_ata.amount;
+
+        msg!("Recorded mSOL deposit worth {} lamports. Total staked: {}", lamports, strategy.total_staked);
+
+        Ok(())
+    }
+
     pub fn harvest(_ctx: Context<Harvest>) -> Result<u64> {
         // Marinade doesn't require explicit harvest - yields accrue to mSOL price
         // The value increase is reflected in report_value()
@@ -144,22 +170,18 @@ pub mod marinade_strategy {
     }
 
     /// Calculate the SOL value of held mSOL using Marinade's state
+    /// (or the localnet mock_marinade state), including accrued staking yield
     pub fn report_value(ctx: Context<ReportValue>) -> Result<u64> {
         let msol_balance = ctx.accounts.msol_ata.amount;
         
-        // Get Marinade state to calculate mSOL -> SOL conversion
-        // Marinade state contains: msol_supply, total_cooling_down, total_lamports_under_control, etc.
-        // Conversion rate = total_lamports_under_control / msol_supply
-        
-        // For now, we'll return the mSOL balance directly
-        // TODO: Parse Marinade state account to get accurate SOL value
-        let _marinade_state_data = ctx.accounts.marinade_state.try_borrow_data()?;
+        let (msol_price, denominator) = msol_price(
+            &ctx.accounts.marinade_state,
+            &ctx.accounts.msol_mint.key(),
+        )?;
+        let sol_value = u64::try_from(msol_balance as u128 * msol_price as u128 / denominator)
+            .map_err(|_| ErrorCode::MathOverflow)?;
         
-        // Simplified calculation (this should parse the actual Marinade state)
-        // In production, you'd deserialize the Marinade state struct
-        let sol_value = msol_balance; // Placeholder - should be: msol_balance * exchange_rate
-        
-        msg!("mSOL balance: {}, estimated SOL value: {}", msol_balance, sol_value);
+        msg!("mSOL balance: {}, SOL value: {}", msol_balance, sol_value);
         
         Ok(sol_value)
     }
@@ -171,6 +193,32 @@ pub mod marinade_strategy {
     }
 }
 
+// Marinade State layout (after the 8-byte discriminator); msol_price is
+// lamports per mSOL scaled by 2^32
+const MARINADE_MSOL_MINT_OFFSET: usize = 8;
+const MARINADE_MSOL_PRICE_OFFSET: usize = 512;
+const MARINADE_PRICE_DENOMINATOR: u128 = 1 << 32;
+
+/// Lamports per mSOL and its scale, read from the Marinade state
+fn msol_price(state: &AccountInfo, msol_mint: &Pubkey) -> Result<(u64, u128)> {
+    let data = state.try_borrow_data()?;
+    
+    if state.owner == &mock_marinade::ID {
+        let mock = mock_marinade::MarinadeState::try_deserialize(&mut &data[..])?;
+        require!(mock.msol_mint == *msol_mint, ErrorCode::InvalidMarinadeState);
+        return Ok((mock.msol_price, mock_marinade::PRICE_DENOMINATOR as u128));
+    }
+    
+    require!(
+        state.owner.to_string() == MARINADE_PROGRAM_ID
+            && data.len() >= MARINADE_MSOL_PRICE_OFFSET + 8
+            && data[MARINADE_MSOL_MINT_OFFSET..MARINADE_MSOL_MINT_OFFSET + 32] == msol_mint.to_bytes(),
+        ErrorCode::InvalidMarinadeState
+    );
+    let price_bytes = &data[MARINADE_MSOL_PRICE_OFFSET..MARINADE_MSOL_PRICE_OFFSET + 8];
+    Ok((u64::from_le_bytes(price_bytes.try_into().unwrap()), MARINADE_PRICE_DENOMINATOR))
+}
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(
@@ -315,6 +363,28 @@ pub struct Unstake<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct RecordDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"marinade_strategy", vault.key().as_ref()],
+        bump = strategy_account.bump,
+        constraint = strategy_account.vault == vault.key()
+    )]
+    pub strategy_account: Account<'info, StrategyAccount>,
+    
+    /// Vault PDA, signing through CPI
+    pub vault: Signer<'info>,
+    
+    #[account(
+        associated_token::authority = strategy_account,
+        associated_token::mint = msol_mint
+    )]
+    pub msol_ata: Account<'info, TokenAccount>,
+    
+    pub msol_mint: Account<'info, Mint>,
+}
+
 #[derive(Accounts)]
 pub struct Harvest<'info> {
     #[account(
@@ -392,4 +462,6 @@ pub enum ErrorCode {
     InsufficientMsol,
     #[msg("Math overflow")]
     MathOverflow,
+    #[msg("Account is not the Marinade state for this mSOL mint")]
+    InvalidMarinadeState,
 }
\ No newline at end of file
@@ -0,0 +1,271 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+declare_id!("6j9veGsKvKzxPLXpYEDAc5GM6fx3Nns2PBgrUfK9jjch");
+
+// Upper bound for a pool's swap fee (10%)
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Devnet constant-product AMM pairing native SOL with one test token
+///
+/// Lets the vault's deposits, withdrawals and rebalances move real tokens on
+/// devnet. Each pool keeps its SOL as lamports in the pool PDA and its tokens
+/// in the PDA's ATA.
+///
+/// Program-owned accounts (like the vault PDA) cannot be debited by the
+/// system program, so SOL-in swaps are prefunded: the caller credits the pool
+/// PDA with `amount_in` lamports, then calls `swap`, which checks the
+/// lamports above `sol_reserve` cover the input.
+#[program]
+pub mod mock_amm {
+    use super::*;
+
+    pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.bump = ctx.bumps.pool;
+        pool.authority = ctx.accounts.payer.key();
+        pool.token_mint = ctx.accounts.token_mint.key();
+        pool.token_vault = ctx.accounts.token_vault.key();
+        pool.sol_reserve = 0;
+        pool.fee_bps = fee_bps;
+
+        msg!("Mock AMM pool initialized for mint: {} ({} bps fee)", pool.token_mint, fee_bps);
+        Ok(())
+    }
+
+    /// Add SOL and tokens to the pool (no LP tokens; devnet liquidity is not withdrawn)
+    pub fn add_liquidity(ctx: Context<AddLiquidity>, sol_amount: u64, token_amount: u64) -> Result<()> {
+        require!(sol_amount > 0 || token_amount > 0, ErrorCode::ZeroAmount);
+
+        if sol_amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.provider.to_account_info(),
+                to: ctx.accounts.pool.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+            transfer(cpi_ctx, sol_amount)?;
+        }
+
+        if token_amount > 0 {
+            let cpi_accounts = token::Transfer {
+                from: ctx.accounts.provider_token_account.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.provider.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, token_amount)?;
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.sol_reserve = pool.sol_reserve.checked_add(sol_amount).ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Added {} lamports and {} tokens of liquidity", sol_amount, token_amount);
+        Ok(())
+    }
+
+    /// Swap against the pool's x * y = k curve
+    ///
+    /// - `sol_to_token`: the pool PDA must already hold `amount_in` lamports
+    ///   above its reserve; tokens go to `token_account`
+    /// - otherwise: `amount_in` tokens move from `token_account` (owned by
+    ///   `authority`) to the pool; lamports go to `sol_recipient`
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, min_amount_out: u64, sol_to_token: bool) -> Result<u64> {
+        require!(amount_in > 0, ErrorCode::ZeroAmount);
+
+        let pool_info = ctx.accounts.pool.to_account_info();
+        let token_reserve = ctx.accounts.token_vault.amount;
+        let sol_reserve = ctx.accounts.pool.sol_reserve;
+        let fee_bps = ctx.accounts.pool.fee_bps;
+
+        let amount_out = if sol_to_token {
+            let rent_exempt_minimum = Rent::get()?.minimum_balance(pool_info.data_len());
+            let unaccounted = pool_info
+                .lamports()
+                .saturating_sub(rent_exempt_minimum)
+                .saturating_sub(sol_reserve);
+            require!(unaccounted >= amount_in, ErrorCode::SolNotReceived);
+
+            let amount_out = get_amount_out(amount_in, sol_reserve, token_reserve, fee_bps)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+
+            let token_mint = ctx.accounts.pool.token_mint;
+            let seeds = &[b"pool".as_ref(), token_mint.as_ref(), &[ctx.accounts.pool.bump]];
+            let signer = &[&seeds[..]];
+            let cpi_accounts = token::Transfer {
+                from: ctx.accounts.token_vault.to_account_info(),
+                to: ctx.accounts.token_account.to_account_info(),
+                authority: pool_info.clone(),
+            };
+            let cpi_ctx =
+                CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+            token::transfer(cpi_ctx, amount_out)?;
+
+            ctx.accounts.pool.sol_reserve = sol_reserve + amount_in;
+            amount_out
+        } else {
+            let amount_out = get_amount_out(amount_in, token_reserve, sol_reserve, fee_bps)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+
+            let cpi_accounts = token::Transfer {
+                from: ctx.accounts.token_account.to_account_info(),
+                to: ctx.accounts.token_vault.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_ctx, amount_in)?;
+
+            **pool_info.try_borrow_mut_lamports()? -= amount_out;
+            **ctx.accounts.sol_recipient.try_borrow_mut_lamports()? += amount_out;
+
+            ctx.accounts.pool.sol_reserve = sol_reserve - amount_out;
+            amount_out
+        };
+
+        msg!(
+            "Swapped {} {} for {} {}",
+            amount_in,
+            if sol_to_token { "lamports" } else { "tokens" },
+            amount_out,
+            if sol_to_token { "tokens" } else { "lamports" }
+        );
+
+        Ok(amount_out)
+    }
+}
+
+/// Constant-product output for `amount_in`, with the fee taken from the input
+pub fn get_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64, fee_bps: u16) -> Option<u64> {
+    let amount_in_after_fee = amount_in as u128 * (10_000 - fee_bps as u128) / 10_000;
+    let denominator = (reserve_in as u128).checked_add(amount_in_after_fee)?;
+    if denominator == 0 {
+        return None;
+    }
+    u64::try_from(reserve_out as u128 * amount_in_after_fee / denominator).ok()
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", token_mint.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::authority = pool,
+        associated_token::mint = token_mint
+    )]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.token_mint.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut, address = pool.token_vault)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = pool.token_mint, token::authority = provider)]
+    pub provider_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.token_mint.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(mut, address = pool.token_vault)]
+    pub token_vault: Account<'info, TokenAccount>,
+
+    /// Swapper's token account: source for token-in, destination for SOL-in
+    #[account(mut, token::mint = pool.token_mint)]
+    pub token_account: Account<'info, TokenAccount>,
+
+    /// Owner of token_account (only needs to sign for token-in swaps)
+    pub authority: Signer<'info>,
+
+    /// CHECK: Receives the SOL of token-in swaps; any account can be credited
+    #[account(mut)]
+    pub sol_recipient: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    /// Lamports of the pool's SOL side (the PDA also holds its rent)
+    pub sol_reserve: u64,
+    pub fee_bps: u16,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Fee exceeds the allowed maximum")]
+    InvalidFee,
+    #[msg("Pool was not credited with the SOL input")]
+    SolNotReceived,
+    #[msg("Swap output is below the minimum amount out")]
+    SlippageExceeded,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_amount_out() {
+        // 1 SOL into 100 SOL / 10,000 token pool without fee: 10,000 * 1 / 101
+        assert_eq!(get_amount_out(1_000, 100_000, 10_000_000, 0), Some(99_009));
+        // 30 bps fee comes off the input first
+        assert_eq!(get_amount_out(1_000, 100_000, 10_000_000, 30), Some(98_715));
+        // Empty pool cannot quote
+        assert_eq!(get_amount_out(0, 0, 10, 0), None);
+    }
+}
@@ -0,0 +1,319 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount};
+
+declare_id!("BaGMCTX48qY5Snzrx3Qf4bHn66Vrr8Ykw5MBMeCKcbz3");
+
+// mSOL price scale: PRICE_DENOMINATOR lamports per mSOL is a 1:1 price
+pub const PRICE_DENOMINATOR: u64 = 1_000_000_000;
+
+/// Localnet stand-in for Marinade liquid staking
+///
+/// `deposit` and `liquid_unstake` keep Marinade's instruction names, argument
+/// and account order, so marinade_strategy's CPIs run unchanged when this
+/// program is passed as `marinade_program`. Instead of stake accounts and a
+/// real liquidity pool, the mock mints and burns mSOL at a configurable price
+/// and keeps every deposited lamport in the liquidity pool SOL leg PDA.
+///
+/// Accounts the strategy passes but the mock does not use (reserve PDA,
+/// mSOL leg authority, treasury) are accepted as-is; the mSOL leg created by
+/// `initialize` can also be passed as the treasury account.
+#[program]
+pub mod mock_marinade {
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, msol_price: u64) -> Result<()> {
+        require!(msol_price > 0, ErrorCode::InvalidPrice);
+
+        let state = &mut ctx.accounts.state;
+        state.bump = ctx.bumps.state;
+        state.authority = ctx.accounts.authority.key();
+        state.msol_mint = ctx.accounts.msol_mint.key();
+        state.liq_pool_msol_leg = ctx.accounts.liq_pool_msol_leg.key();
+        state.msol_price = msol_price;
+        state.msol_mint_authority_bump = ctx.bumps.msol_mint_authority;
+        state.liq_pool_sol_leg_bump = ctx.bumps.liq_pool_sol_leg_pda;
+
+        // Keep the SOL leg rent-exempt so small deposits and partial unstakes succeed
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.authority.to_account_info(),
+            to: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, Rent::get()?.minimum_balance(0))?;
+
+        msg!("Mock Marinade initialized, mSOL price: {}", msol_price);
+        Ok(())
+    }
+
+    /// Set the lamports paid per mSOL (scaled by PRICE_DENOMINATOR), e.g. to simulate staking yield
+    pub fn set_msol_price(ctx: Context<SetMsolPrice>, msol_price: u64) -> Result<()> {
+        require!(msol_price > 0, ErrorCode::InvalidPrice);
+
+        ctx.accounts.state.msol_price = msol_price;
+
+        msg!("mSOL price set to {}", msol_price);
+        Ok(())
+    }
+
+    /// Stake `lamports` from `transfer_from` and mint mSOL at the current price to `mint_to`
+    pub fn deposit(ctx: Context<Deposit>, lamports: u64) -> Result<()> {
+        require!(lamports > 0, ErrorCode::ZeroAmount);
+
+        let state = &ctx.accounts.state;
+        let msol_amount = sol_to_msol(lamports, state.msol_price).ok_or(ErrorCode::MathOverflow)?;
+        require!(msol_amount > 0, ErrorCode::ZeroAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.transfer_from.to_account_info(),
+            to: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, lamports)?;
+
+        let seeds = &[b"msol_mint_authority".as_ref(), &[state.msol_mint_authority_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.msol_mint.to_account_info(),
+            to: ctx.accounts.mint_to.to_account_info(),
+            authority: ctx.accounts.msol_mint_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::mint_to(cpi_ctx, msol_amount)?;
+
+        msg!("Deposited {} lamports for {} mSOL", lamports, msol_amount);
+        Ok(())
+    }
+
+    /// Burn `msol_amount` from `get_msol_from` and pay its SOL value from the SOL leg
+    pub fn liquid_unstake(ctx: Context<LiquidUnstake>, msol_amount: u64) -> Result<()> {
+        require!(msol_amount > 0, ErrorCode::ZeroAmount);
+
+        let state = &ctx.accounts.state;
+        let lamports = msol_to_sol(msol_amount, state.msol_price).ok_or(ErrorCode::MathOverflow)?;
+        let available = ctx
+            .accounts
+            .liq_pool_sol_leg_pda
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(available >= lamports, ErrorCode::InsufficientLiquidity);
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.msol_mint.to_account_info(),
+            from: ctx.accounts.get_msol_from.to_account_info(),
+            authority: ctx.accounts.get_msol_from_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::burn(cpi_ctx, msol_amount)?;
+
+        let seeds = &[b"liq_sol".as_ref(), &[state.liq_pool_sol_leg_bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+            to: ctx.accounts.transfer_sol_to.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.system_program.to_account_info(), cpi_accounts, signer);
+        transfer(cpi_ctx, lamports)?;
+
+        msg!("Liquid unstaked {} mSOL for {} lamports", msol_amount, lamports);
+        Ok(())
+    }
+}
+
+/// mSOL minted for `lamports` at `msol_price`
+pub fn sol_to_msol(lamports: u64, msol_price: u64) -> Option<u64> {
+    if msol_price == 0 {
+        return None;
+    }
+    u64::try_from(lamports as u128 * PRICE_DENOMINATOR as u128 / msol_price as u128).ok()
+}
+
+/// Lamports paid for `msol_amount` at `msol_price`
+pub fn msol_to_sol(msol_amount: u64, msol_price: u64) -> Option<u64> {
+    u64::try_from(msol_amount as u128 * msol_price as u128 / PRICE_DENOMINATOR as u128).ok()
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarinadeState::INIT_SPACE,
+        seeds = [b"state"],
+        bump
+    )]
+    pub state: Account<'info, MarinadeState>,
+
+    /// CHECK: PDA that signs mSOL mints
+    #[account(seeds = [b"msol_mint_authority"], bump)]
+    pub msol_mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"msol_mint"],
+        bump,
+        mint::decimals = 9,
+        mint::authority = msol_mint_authority
+    )]
+    pub msol_mint: Account<'info, Mint>,
+
+    /// CHECK: System-owned PDA holding all deposited SOL
+    #[account(mut, seeds = [b"liq_sol"], bump)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"liq_msol"],
+        bump,
+        token::mint = msol_mint,
+        token::authority = state
+    )]
+    pub liq_pool_msol_leg: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetMsolPrice<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = authority
+    )]
+    pub state: Account<'info, MarinadeState>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Same account order as Marinade's `deposit`
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = msol_mint
+    )]
+    pub state: Account<'info, MarinadeState>,
+
+    #[account(mut)]
+    pub msol_mint: Account<'info, Mint>,
+
+    /// CHECK: SOL leg PDA, receives the deposit
+    #[account(mut, seeds = [b"liq_sol"], bump = state.liq_pool_sol_leg_bump)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+
+    /// CHECK: Unused by the mock
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+
+    /// CHECK: Unused by the mock
+    pub liq_pool_msol_leg_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Unused by the mock (deposits go to the SOL leg)
+    #[account(mut)]
+    pub reserve_pda: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub transfer_from: Signer<'info>,
+
+    #[account(mut, token::mint = msol_mint)]
+    pub mint_to: Account<'info, TokenAccount>,
+
+    /// CHECK: PDA that signs mSOL mints
+    #[account(seeds = [b"msol_mint_authority"], bump = state.msol_mint_authority_bump)]
+    pub msol_mint_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Same account order as Marinade's `liquid_unstake`
+#[derive(Accounts)]
+pub struct LiquidUnstake<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump,
+        has_one = msol_mint
+    )]
+    pub state: Account<'info, MarinadeState>,
+
+    #[account(mut)]
+    pub msol_mint: Account<'info, Mint>,
+
+    /// CHECK: SOL leg PDA, pays out the unstake
+    #[account(mut, seeds = [b"liq_sol"], bump = state.liq_pool_sol_leg_bump)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+
+    /// CHECK: Unused by the mock
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+
+    /// CHECK: Unused by the mock (no unstake fee is charged)
+    #[account(mut)]
+    pub treasury_msol_account: UncheckedAccount<'info>,
+
+    #[account(mut, token::mint = msol_mint, token::authority = get_msol_from_authority)]
+    pub get_msol_from: Account<'info, TokenAccount>,
+
+    pub get_msol_from_authority: Signer<'info>,
+
+    /// CHECK: Receives the unstaked SOL; any account can be credited
+    #[account(mut)]
+    pub transfer_sol_to: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MarinadeState {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub msol_mint: Pubkey,
+    pub liq_pool_msol_leg: Pubkey,
+    /// Lamports per mSOL, scaled by PRICE_DENOMINATOR
+    pub msol_price: u64,
+    pub msol_mint_authority_bump: u8,
+    pub liq_pool_sol_leg_bump: u8,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("mSOL price must be greater than zero")]
+    InvalidPrice,
+    #[msg("SOL leg cannot cover the unstake")]
+    InsufficientLiquidity,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_msol_conversion() {
+        // 1.05 SOL per mSOL
+        let price = 1_050_000_000;
+        assert_eq!(sol_to_msol(1_050_000_000, price), Some(1_000_000_000));
+        assert_eq!(msol_to_sol(1_000_000_000, price), Some(1_050_000_000));
+        // 1:1 price round-trips exactly
+        assert_eq!(sol_to_msol(123, PRICE_DENOMINATOR), Some(123));
+        assert_eq!(sol_to_msol(1, 0), None);
+    }
+}
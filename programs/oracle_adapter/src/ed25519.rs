@@ -0,0 +1,121 @@
+//! Ed25519 program instructions
+//!
+//! Signed prices (Pyth Lazer updates, and the vault's signed mock oracle
+//! updates) travel with an Ed25519 program instruction verifying one
+//! signature right before the instruction that uses them. The runtime rejects
+//! the whole transaction if the signature is invalid, so readers only need
+//! the signer and message, taken from the instruction's own data.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::instruction::Instruction;
+
+/// Ed25519 program data: count and padding, one offsets entry, then the
+/// public key, signature and message
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const PUBKEY_START: usize = OFFSETS_START + OFFSETS_LEN;
+const SIGNATURE_START: usize = PUBKEY_START + 32;
+const MESSAGE_START: usize = SIGNATURE_START + 64;
+
+/// Offsets entry pointing into the Ed25519 instruction's own data
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Ed25519 program instruction data verifying `signature` of `message` by
+/// `signer`, with everything inline
+pub fn ed25519_instruction_data(signer: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(MESSAGE_START + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for value in [
+        SIGNATURE_START as u16,
+        CURRENT_INSTRUCTION,
+        PUBKEY_START as u16,
+        CURRENT_INSTRUCTION,
+        MESSAGE_START as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    data
+}
+
+/// Signer and message of an Ed25519 program instruction verifying exactly
+/// one signature over data held in the instruction itself; None for any
+/// other shape
+pub fn verified_message(instruction: &Instruction) -> Option<(Pubkey, &[u8])> {
+    if instruction.program_id != ed25519_program::ID {
+        return None;
+    }
+    let data = &instruction.data;
+    if data.len() < PUBKEY_START || data[0] != 1 {
+        return None;
+    }
+    let offset = |i: usize| {
+        let at = OFFSETS_START + 2 * i;
+        u16::from_le_bytes([data[at], data[at + 1]])
+    };
+    let (pubkey_offset, message_offset, message_len) = (offset(2) as usize, offset(4) as usize, offset(5) as usize);
+    // Signature, public key and message must all come from this instruction
+    if [offset(1), offset(3), offset(6)].iter().any(|index| *index != CURRENT_INSTRUCTION) {
+        return None;
+    }
+    let pubkey = data.get(pubkey_offset..pubkey_offset + 32)?;
+    let message = data.get(message_offset..message_offset + message_len)?;
+    Some((Pubkey::try_from(pubkey).ok()?, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ed25519_instruction(data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: ed25519_program::ID,
+            accounts: vec![],
+            data,
+        }
+    }
+
+    #[test]
+    fn test_verified_message_round_trip() {
+        let signer = Pubkey::new_unique();
+        let message = b"prices signed off-chain";
+        let data = ed25519_instruction_data(&signer, &[7; 64], message);
+
+        let instruction = ed25519_instruction(data.clone());
+        assert_eq!(verified_message(&instruction), Some((signer, &message[..])));
+
+        // Another program's instruction proves nothing
+        let other = Instruction {
+            program_id: Pubkey::new_unique(),
+            ..ed25519_instruction(data.clone())
+        };
+        assert_eq!(verified_message(&other), None);
+    }
+
+    #[test]
+    fn test_verified_message_rejects_other_shapes() {
+        let signer = Pubkey::new_unique();
+        let data = ed25519_instruction_data(&signer, &[7; 64], b"prices");
+
+        // Two signatures
+        let mut two = data.clone();
+        two[0] = 2;
+        assert_eq!(verified_message(&ed25519_instruction(two)), None);
+
+        // Message taken from another instruction of the transaction
+        let mut elsewhere = data.clone();
+        elsewhere[OFFSETS_START + 12..OFFSETS_START + 14].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(verified_message(&ed25519_instruction(elsewhere)), None);
+
+        // Offsets past the end of the data
+        let mut truncated = data;
+        truncated.truncate(MESSAGE_START + 2);
+        assert_eq!(verified_message(&ed25519_instruction(truncated)), None);
+        assert_eq!(verified_message(&ed25519_instruction(vec![1, 0])), None);
+    }
+}
@@ -0,0 +1,210 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+
+declare_id!("4pCSVvFkZLL4U3ydKv9zNwfg4tyBPeiw6AuKLZRHeEBK");
+
+// Ed25519 program instructions carrying signed prices
+pub mod ed25519;
+
+// The vault's MockPriceOracle layout and TWAP
+pub mod mock_oracle;
+
+// Signed Pyth Lazer updates verified through the instructions sysvar
+pub mod pyth_lazer;
+
+// Switchboard On-Demand pull feed parsing
+pub mod switchboard;
+
+use mock_oracle::MockOracle;
+
+/// A price in micro-USD, with the raw value and exponent it came from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct NormalizedPrice {
+    pub price_usd: i64, // Price in USD with 6 decimals (micro-dollars)
+    pub original_price: i64,
+    pub expo: i32,
+}
+
+impl NormalizedPrice {
+    /// Convert a `price * 10^expo` quote to micro-dollars (6 decimals)
+    /// Example: BTC at $50,000 with expo=-8 -> 50_000_000_000 micro-dollars
+    pub fn from_quote(price: i64, expo: i32) -> Result<Self> {
+        let price_usd = if expo < -6 {
+            // Price has more decimals than we want, divide
+            price
+                .checked_div(10i64.pow((-expo - 6) as u32))
+                .ok_or(AdapterError::MathOverflow)?
+        } else if expo > -6 {
+            // Price has fewer decimals, multiply
+            price
+                .checked_mul(10i64.pow((6 + expo) as u32))
+                .ok_or(AdapterError::MathOverflow)?
+        } else {
+            price
+        };
+        Ok(Self {
+            price_usd,
+            original_price: price,
+            expo,
+        })
+    }
+
+    /// Wrap a price that is already in micro-dollars
+    pub fn from_micro_usd(price_usd: i64) -> Self {
+        Self {
+            price_usd,
+            original_price: price_usd / 1_000_000,
+            expo: -6,
+        }
+    }
+}
+
+/// Oracle price parsing for the ETF-DeFi vault
+///
+/// Every instruction only reads accounts and returns the prices it parsed as
+/// return data, so the vault calls them by CPI. Keeping the vendor formats
+/// here lets an oracle integration be fixed or extended by upgrading this
+/// program, without touching the program that holds depositors' funds.
+#[program]
+pub mod oracle_adapter {
+    use super::*;
+
+    /// Price `feed` and each further Switchboard On-Demand pull feed in
+    /// `remaining_accounts`
+    ///
+    /// `expected_feed_hashes` holds one entry per feed: Some pins what the
+    /// feed prices (its feed hash), None accepts any feed (for callers that
+    /// match the account by key).
+    pub fn read_switchboard<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReadSwitchboard<'info>>,
+        expected_feed_hashes: Vec<Option<[u8; 32]>>,
+    ) -> Result<Vec<NormalizedPrice>> {
+        require!(
+            expected_feed_hashes.len() == 1 + ctx.remaining_accounts.len(),
+            AdapterError::InvalidQuote
+        );
+        let now = Clock::get()?.unix_timestamp;
+
+        std::iter::once(ctx.accounts.feed.as_ref())
+            .chain(ctx.remaining_accounts)
+            .zip(&expected_feed_hashes)
+            .map(|(account, expected)| read_pull_feed(account, expected.as_ref(), now))
+            .collect()
+    }
+
+    /// BTC, ETH and SOL prices of a MockPriceOracle owned by `owner` (the
+    /// vault program), at most `max_age_secs` old; the TWAP over
+    /// `twap_window_secs` when nonzero
+    pub fn read_mock_oracle(
+        ctx: Context<ReadMockOracle>,
+        owner: Pubkey,
+        max_age_secs: i64,
+        twap_window_secs: u32,
+    ) -> Result<Vec<NormalizedPrice>> {
+        let account = &ctx.accounts.oracle;
+        require_keys_eq!(*account.owner, owner, AdapterError::InvalidQuote);
+        let oracle = MockOracle::parse(&account.try_borrow_data()?).ok_or(AdapterError::InvalidQuote)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now - oracle.last_update < max_age_secs, AdapterError::StaleQuote);
+
+        let prices = if twap_window_secs > 0 {
+            msg!("⏱️  Using {}s TWAP", twap_window_secs);
+            oracle.twap(twap_window_secs as i64, now)
+        } else {
+            [oracle.btc_price, oracle.eth_price, oracle.sol_price]
+        };
+        Ok(prices.into_iter().map(NormalizedPrice::from_micro_usd).collect())
+    }
+
+    /// Prices of `feed_ids` from the Pyth Lazer update verified by the
+    /// Ed25519 instruction right before the calling top-level instruction,
+    /// signed by one of `trusted_signers` and at most
+    /// MAX_PYTH_LAZER_AGE_SECS old
+    pub fn read_pyth_lazer(
+        ctx: Context<ReadPythLazer>,
+        trusted_signers: Vec<Pubkey>,
+        feed_ids: Vec<u32>,
+    ) -> Result<Vec<NormalizedPrice>> {
+        let update = pyth_lazer::verified_update(&ctx.accounts.instructions, &trusted_signers)?;
+        require!(
+            update.age_secs(Clock::get()?.unix_timestamp) <= pyth_lazer::MAX_PYTH_LAZER_AGE_SECS,
+            AdapterError::StaleQuote
+        );
+
+        feed_ids
+            .iter()
+            .map(|feed_id| {
+                let lazer = update.price(*feed_id).ok_or(AdapterError::InvalidQuote)?;
+                NormalizedPrice::from_quote(lazer.mantissa, lazer.exponent as i32)
+            })
+            .collect()
+    }
+}
+
+/// Verify and parse a Switchboard On-Demand pull feed (see `switchboard`)
+fn read_pull_feed(account: &AccountInfo, expected_feed_hash: Option<&[u8; 32]>, now: i64) -> Result<NormalizedPrice> {
+    require_keys_eq!(
+        *account.owner,
+        switchboard::SWITCHBOARD_ON_DEMAND_PROGRAM_ID,
+        AdapterError::InvalidQuote
+    );
+    let feed = switchboard::parse_pull_feed(&account.try_borrow_data()?).ok_or(AdapterError::InvalidQuote)?;
+
+    if let Some(expected) = expected_feed_hash {
+        require!(feed.feed_hash == *expected, AdapterError::InvalidQuote);
+    }
+    require!(feed.has_min_samples(), AdapterError::InvalidQuote);
+    require!(
+        now - feed.last_update_timestamp <= switchboard::MAX_QUOTE_AGE_SECS,
+        AdapterError::StaleQuote
+    );
+    let price_usd = feed.micro_usd().ok_or(AdapterError::InvalidPrice)?;
+
+    msg!(
+        "📊 Switchboard feed {}: {} micro-USD from {} samples at slot {}",
+        account.key,
+        price_usd,
+        feed.num_samples,
+        feed.slot
+    );
+
+    Ok(NormalizedPrice::from_micro_usd(price_usd))
+}
+
+/// Further Switchboard pull feeds are passed in `remaining_accounts`
+#[derive(Accounts)]
+pub struct ReadSwitchboard<'info> {
+    /// CHECK: Switchboard pull feed; owner and contents checked by read_pull_feed
+    pub feed: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadMockOracle<'info> {
+    /// CHECK: Owner and discriminator checked by read_mock_oracle
+    pub oracle: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadPythLazer<'info> {
+    /// CHECK: Instructions sysvar, read for the Ed25519 verification
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[error_code]
+pub enum AdapterError {
+    #[msg("Invalid Oracle Quote")]
+    InvalidQuote,
+    #[msg("Stale Quote")]
+    StaleQuote,
+    #[msg("Invalid Price")]
+    InvalidPrice,
+    #[msg("Expected an Ed25519 instruction verifying one signature over this price update, just before it")]
+    InvalidPriceSignature,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,153 @@
+//! The vault's mock price oracle
+//!
+//! MockPriceOracle accounts belong to the vault program, which creates and
+//! updates them; this program only reads them. `MockOracle` mirrors the
+//! account's layout (the vault's `MockPriceOracle::LEN` fixes it), and
+//! read_mock_oracle checks the owner the vault passes, the discriminator and
+//! the age of the last update before pricing from it.
+
+use anchor_lang::prelude::*;
+
+/// Anchor discriminator of the vault's MockPriceOracle
+pub const MOCK_ORACLE_DISCRIMINATOR: [u8; 8] = [163, 151, 169, 55, 252, 5, 24, 160];
+
+/// Price updates a mock oracle keeps for TWAP
+pub const TWAP_SAMPLES: usize = 8;
+
+/// A single mock oracle update, kept for TWAP calculation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceSample {
+    pub btc_price: i64,
+    pub eth_price: i64,
+    pub sol_price: i64,
+    pub timestamp: i64,
+}
+
+/// MockPriceOracle as laid out after its discriminator; prices in micro-USD
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MockOracle {
+    pub authority: Pubkey,
+    pub btc_price: i64,
+    pub eth_price: i64,
+    pub sol_price: i64,
+    pub last_update: i64,
+    pub bump: u8,
+    pub history: [PriceSample; TWAP_SAMPLES],
+    pub history_index: u8,
+    pub history_len: u8,
+    /// ER commit interval, last commit and session (unused for pricing)
+    pub commit_frequency_ms: u32,
+    pub last_commit_at: i64,
+    pub delegated_at: i64,
+    pub max_session_secs: u32,
+}
+
+impl MockOracle {
+    /// Parse a MockPriceOracle account's data; None for another account type
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.get(..8)? != MOCK_ORACLE_DISCRIMINATOR {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// Time-weighted average BTC/ETH/SOL prices over the last `window_secs`
+    ///
+    /// Each sample is weighted by how long it was the live price inside the
+    /// window. Falls back to spot prices when the history does not cover any
+    /// time in the window (e.g. a single update in the current second).
+    pub fn twap(&self, window_secs: i64, now: i64) -> [i64; 3] {
+        let window_start = now - window_secs;
+        let mut weighted = [0i128; 3];
+        let mut total_secs: i128 = 0;
+        let mut segment_end = now;
+
+        // Walk samples from newest to oldest
+        for i in 0..self.history_len as usize {
+            let slot = (self.history_index as usize + TWAP_SAMPLES - 1 - i) % TWAP_SAMPLES;
+            let sample = &self.history[slot];
+            let segment_start = sample.timestamp.max(window_start);
+
+            if segment_end > segment_start {
+                let secs = (segment_end - segment_start) as i128;
+                weighted[0] += sample.btc_price as i128 * secs;
+                weighted[1] += sample.eth_price as i128 * secs;
+                weighted[2] += sample.sol_price as i128 * secs;
+                total_secs += secs;
+            }
+
+            if sample.timestamp <= window_start {
+                break;
+            }
+            segment_end = sample.timestamp;
+        }
+
+        if total_secs == 0 {
+            return [self.btc_price, self.eth_price, self.sol_price];
+        }
+
+        weighted.map(|sum| (sum / total_secs) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle(samples: &[(i64, i64)]) -> MockOracle {
+        let mut oracle = MockOracle {
+            authority: Pubkey::new_unique(),
+            btc_price: 0,
+            eth_price: 0,
+            sol_price: 0,
+            last_update: 0,
+            bump: 255,
+            history: [PriceSample::default(); TWAP_SAMPLES],
+            history_index: 0,
+            history_len: 0,
+            commit_frequency_ms: 0,
+            last_commit_at: 0,
+            delegated_at: 0,
+            max_session_secs: 0,
+        };
+        for (price, timestamp) in samples {
+            oracle.btc_price = *price;
+            oracle.eth_price = *price;
+            oracle.sol_price = *price;
+            oracle.last_update = *timestamp;
+            oracle.history[oracle.history_index as usize] = PriceSample {
+                btc_price: *price,
+                eth_price: *price,
+                sol_price: *price,
+                timestamp: *timestamp,
+            };
+            oracle.history_index = ((oracle.history_index as usize + 1) % TWAP_SAMPLES) as u8;
+            oracle.history_len = (oracle.history_len + 1).min(TWAP_SAMPLES as u8);
+        }
+        oracle
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        let oracle = oracle(&[(100, 1_000)]);
+        let mut data = MOCK_ORACLE_DISCRIMINATOR.to_vec();
+        oracle.serialize(&mut data).unwrap();
+        // The vault's MockPriceOracle::LEN
+        assert_eq!(data.len(), 8 + 32 + 8 + 8 + 8 + 8 + 1 + TWAP_SAMPLES * 32 + 1 + 1 + 4 + 8 + 8 + 4);
+        assert_eq!(MockOracle::parse(&data), Some(oracle));
+
+        data[0] ^= 1;
+        assert_eq!(MockOracle::parse(&data), None);
+    }
+
+    #[test]
+    fn test_twap() {
+        // $100 for 30s, then $200 for the last 10s of a 40s window
+        let oracle = oracle(&[(100, 1_000), (200, 1_030)]);
+        assert_eq!(oracle.twap(40, 1_040), [125; 3]);
+        // A window inside the latest update is its spot price
+        assert_eq!(oracle.twap(5, 1_040), [200; 3]);
+        // Nothing elapsed since the only update: spot
+        assert_eq!(self::oracle(&[(100, 1_000)]).twap(60, 1_000), [100; 3]);
+    }
+}
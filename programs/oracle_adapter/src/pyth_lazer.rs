@@ -0,0 +1,194 @@
+//! Pyth Lazer prices
+//!
+//! A PythLazer vault prices from signed Pyth Lazer updates instead of a
+//! posted feed account. The transaction carries, right before the vault's
+//! pricing instruction, an Ed25519 program instruction verifying a Lazer
+//! signer's signature over the update payload (the Solana-format message
+//! Lazer streams, split into its public key, signature and payload).
+//! read_pyth_lazer reads that instruction back through the instructions
+//! sysvar (which names the top-level instruction even under CPI), checks the
+//! signer against the trusted signers the vault passes and takes the prices
+//! of the requested feed ids from the payload. Updates older than
+//! MAX_PYTH_LAZER_AGE_SECS are rejected, so keepers and depositors fetch a
+//! fresh update per transaction and price at sub-second latency.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+
+use crate::ed25519;
+use crate::AdapterError;
+
+/// Oldest Lazer update a pricing instruction accepts
+pub const MAX_PYTH_LAZER_AGE_SECS: i64 = 10;
+
+/// Leading magic of a Lazer update payload
+pub const PAYLOAD_MAGIC: u32 = 2_479_346_549;
+
+/// Payload property ids (pyth-lazer-protocol PriceFeedProperty)
+const PROPERTY_PRICE: u8 = 0;
+const PROPERTY_BEST_BID_PRICE: u8 = 1;
+const PROPERTY_BEST_ASK_PRICE: u8 = 2;
+const PROPERTY_PUBLISHER_COUNT: u8 = 3;
+const PROPERTY_EXPONENT: u8 = 4;
+const PROPERTY_CONFIDENCE: u8 = 5;
+
+/// One feed of a Lazer update, priced at `mantissa * 10^exponent` USD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LazerPrice {
+    pub feed_id: u32,
+    /// 0 when the feed had no price
+    pub mantissa: i64,
+    pub exponent: i16,
+}
+
+/// A parsed Lazer update payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LazerUpdate {
+    /// Microseconds since the Unix epoch
+    pub timestamp_us: u64,
+    pub prices: Vec<LazerPrice>,
+}
+
+impl LazerUpdate {
+    /// Price of `feed_id`, if the update carries one
+    pub fn price(&self, feed_id: u32) -> Option<&LazerPrice> {
+        self.prices
+            .iter()
+            .find(|price| price.feed_id == feed_id && price.mantissa > 0)
+    }
+
+    /// Seconds between the update and `now`
+    pub fn age_secs(&self, now: i64) -> i64 {
+        now - (self.timestamp_us / 1_000_000) as i64
+    }
+}
+
+/// Little-endian reader over a payload
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let head = self.0.get(..N)?.try_into().ok()?;
+        self.0 = &self.0[N..];
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|bytes| bytes[0])
+    }
+}
+
+/// Parse a Lazer update payload: magic, timestamp, channel, then each feed's
+/// id and properties. Feeds without an exponent property, properties this
+/// program doesn't know and trailing bytes make the payload invalid.
+pub fn parse_payload(payload: &[u8]) -> Option<LazerUpdate> {
+    let mut reader = Reader(payload);
+    if u32::from_le_bytes(reader.take()?) != PAYLOAD_MAGIC {
+        return None;
+    }
+    let timestamp_us = u64::from_le_bytes(reader.take()?);
+    let _channel = reader.u8()?;
+
+    let num_feeds = reader.u8()?;
+    let mut prices = Vec::with_capacity(num_feeds as usize);
+    for _ in 0..num_feeds {
+        let feed_id = u32::from_le_bytes(reader.take()?);
+        let mut mantissa = 0;
+        let mut exponent = None;
+        for _ in 0..reader.u8()? {
+            match reader.u8()? {
+                PROPERTY_PRICE => mantissa = i64::from_le_bytes(reader.take()?),
+                PROPERTY_BEST_BID_PRICE | PROPERTY_BEST_ASK_PRICE | PROPERTY_CONFIDENCE => {
+                    reader.take::<8>()?;
+                }
+                PROPERTY_PUBLISHER_COUNT => {
+                    reader.take::<2>()?;
+                }
+                PROPERTY_EXPONENT => exponent = Some(i16::from_le_bytes(reader.take()?)),
+                _ => return None,
+            }
+        }
+        prices.push(LazerPrice {
+            feed_id,
+            mantissa,
+            exponent: exponent?,
+        });
+    }
+
+    reader.0.is_empty().then_some(LazerUpdate { timestamp_us, prices })
+}
+
+/// The Lazer update verified by the Ed25519 instruction right before the
+/// current top-level one, signed by one of `trusted_signers`
+pub fn verified_update(instructions: &AccountInfo, trusted_signers: &[Pubkey]) -> Result<LazerUpdate> {
+    let current_index = load_current_index_checked(instructions)? as usize;
+    require!(current_index > 0, AdapterError::InvalidPriceSignature);
+    let ed25519_ix = load_instruction_at_checked(current_index - 1, instructions)?;
+
+    let (signer, payload) = ed25519::verified_message(&ed25519_ix).ok_or(AdapterError::InvalidPriceSignature)?;
+    require!(trusted_signers.contains(&signer), AdapterError::InvalidPriceSignature);
+
+    parse_payload(payload).ok_or(AdapterError::InvalidQuote.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Payload with one feed per (id, price, exponent), plus a publisher count
+    fn payload(timestamp_us: u64, feeds: &[(u32, i64, i16)]) -> Vec<u8> {
+        let mut data = PAYLOAD_MAGIC.to_le_bytes().to_vec();
+        data.extend_from_slice(&timestamp_us.to_le_bytes());
+        data.push(1);
+        data.push(feeds.len() as u8);
+        for (feed_id, price, exponent) in feeds {
+            data.extend_from_slice(&feed_id.to_le_bytes());
+            data.push(3);
+            data.push(PROPERTY_PRICE);
+            data.extend_from_slice(&price.to_le_bytes());
+            data.push(PROPERTY_PUBLISHER_COUNT);
+            data.extend_from_slice(&7u16.to_le_bytes());
+            data.push(PROPERTY_EXPONENT);
+            data.extend_from_slice(&exponent.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_payload() {
+        // BTC $65,000 and SOL $150 at 8 decimals
+        let data = payload(1_700_000_000_500_000, &[(1, 6_500_000_000_000, -8), (6, 15_000_000_000, -8)]);
+        let update = parse_payload(&data).unwrap();
+
+        assert_eq!(update.timestamp_us, 1_700_000_000_500_000);
+        assert_eq!(update.age_secs(1_700_000_003), 3);
+        assert_eq!(
+            update.price(6),
+            Some(&LazerPrice {
+                feed_id: 6,
+                mantissa: 15_000_000_000,
+                exponent: -8,
+            })
+        );
+        assert_eq!(update.price(2), None);
+    }
+
+    #[test]
+    fn test_parse_payload_rejects_malformed() {
+        let data = payload(1, &[(1, 100, -8)]);
+
+        let mut wrong_magic = data.clone();
+        wrong_magic[0] ^= 1;
+        assert_eq!(parse_payload(&wrong_magic), None);
+
+        assert_eq!(parse_payload(&data[..data.len() - 1]), None);
+
+        let mut trailing = data.clone();
+        trailing.push(0);
+        assert_eq!(parse_payload(&trailing), None);
+
+        // A feed whose price is missing (0) has no usable price
+        let missing = parse_payload(&payload(1, &[(1, 0, -8)])).unwrap();
+        assert_eq!(missing.price(1), None);
+    }
+}
@@ -0,0 +1,150 @@
+//! Switchboard On-Demand pull feeds
+//!
+//! Reads a PullFeedAccountData account without the switchboard-on-demand
+//! crate, whose dependency tree conflicts with the program's. The layout
+//! below is the SDK's zero-copy struct as laid out on-chain (SBF aligns i128
+//! to 8 bytes): 32 oracle submissions, the feed's configuration, then the
+//! current result the oracles last agreed on. Values are i128 with 18
+//! decimals.
+//!
+//! read_switchboard checks the account is owned by the Switchboard program
+//! and carries this discriminator, that its feed hash is the one expected for
+//! the market, that the result aggregates at least the feed's
+//! min_sample_size samples, and that the feed's own last update is no older
+//! than MAX_QUOTE_AGE_SECS.
+
+use anchor_lang::prelude::*;
+
+/// Switchboard On-Demand program owning the pull feeds
+#[cfg(feature = "mainnet")]
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+#[cfg(not(feature = "mainnet"))]
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("Aio4gaXjXzJNVLtzwtNVmSqGKpANtXhybbkhtAC94ji2");
+
+/// Oldest feed update (by the feed's last_update_timestamp) accepted
+pub const MAX_QUOTE_AGE_SECS: i64 = 60;
+
+/// Anchor discriminator of PullFeedAccountData
+pub const PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+
+/// Decimals of feed values
+pub const PRECISION: u32 = 18;
+
+// Byte offsets into the account, discriminator included
+const SUBMISSIONS_LEN: usize = 32 * 64;
+const FEED_HASH: usize = 8 + SUBMISSIONS_LEN + 32 + 32;
+const MIN_SAMPLE_SIZE: usize = FEED_HASH + 32 + 8 + 8 + 8 + 4 + 32 + 3;
+const LAST_UPDATE_TIMESTAMP: usize = MIN_SAMPLE_SIZE + 1;
+const RESULT: usize = LAST_UPDATE_TIMESTAMP + 8 + 8 + 32;
+const RESULT_NUM_SAMPLES: usize = RESULT + 6 * 16;
+const RESULT_SLOT: usize = RESULT_NUM_SAMPLES + 8;
+/// Shortest account holding every field read here
+pub const MIN_ACCOUNT_LEN: usize = RESULT_SLOT + 8;
+
+/// The fields of a pull feed the vault prices from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PullFeed {
+    /// Hash of the feed's job definition, which fixes what it prices
+    pub feed_hash: [u8; 32],
+    /// Samples the feed requires for a valid result
+    pub min_sample_size: u8,
+    /// Unix time of the feed's last update
+    pub last_update_timestamp: i64,
+    /// Current result, with PRECISION decimals
+    pub value: i128,
+    /// Samples the current result aggregates
+    pub num_samples: u8,
+    /// Slot of the current result
+    pub slot: u64,
+}
+
+impl PullFeed {
+    /// Current result in micro-USD, rounded down; None if not positive or
+    /// too large for an i64
+    pub fn micro_usd(&self) -> Option<i64> {
+        if self.value <= 0 {
+            return None;
+        }
+        i64::try_from(self.value / 10i128.pow(PRECISION - 6)).ok()
+    }
+
+    /// Whether the current result aggregates enough samples (at least one,
+    /// and at least the feed's own minimum)
+    pub fn has_min_samples(&self) -> bool {
+        self.num_samples >= self.min_sample_size.max(1)
+    }
+}
+
+/// Parse a PullFeedAccountData; None for a different account type or a
+/// truncated one
+pub fn parse_pull_feed(data: &[u8]) -> Option<PullFeed> {
+    if data.len() < MIN_ACCOUNT_LEN || data[..8] != PULL_FEED_DISCRIMINATOR {
+        return None;
+    }
+    let bytes = |at: usize, len: usize| &data[at..at + len];
+    Some(PullFeed {
+        feed_hash: bytes(FEED_HASH, 32).try_into().ok()?,
+        min_sample_size: data[MIN_SAMPLE_SIZE],
+        last_update_timestamp: i64::from_le_bytes(bytes(LAST_UPDATE_TIMESTAMP, 8).try_into().ok()?),
+        value: i128::from_le_bytes(bytes(RESULT, 16).try_into().ok()?),
+        num_samples: data[RESULT_NUM_SAMPLES],
+        slot: u64::from_le_bytes(bytes(RESULT_SLOT, 8).try_into().ok()?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_account(feed_hash: [u8; 32], value: i128, num_samples: u8, min_sample_size: u8) -> Vec<u8> {
+        let mut data = vec![0u8; 3208];
+        data[..8].copy_from_slice(&PULL_FEED_DISCRIMINATOR);
+        data[FEED_HASH..FEED_HASH + 32].copy_from_slice(&feed_hash);
+        data[MIN_SAMPLE_SIZE] = min_sample_size;
+        data[LAST_UPDATE_TIMESTAMP..LAST_UPDATE_TIMESTAMP + 8].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[RESULT..RESULT + 16].copy_from_slice(&value.to_le_bytes());
+        data[RESULT_NUM_SAMPLES] = num_samples;
+        data[RESULT_SLOT..RESULT_SLOT + 8].copy_from_slice(&42u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_layout_offsets() {
+        // Fixed by the SDK struct; a change here means the parser drifted
+        assert_eq!(FEED_HASH, 2120);
+        assert_eq!(MIN_SAMPLE_SIZE, 2215);
+        assert_eq!(LAST_UPDATE_TIMESTAMP, 2216);
+        assert_eq!(RESULT, 2264);
+        assert_eq!(RESULT_NUM_SAMPLES, 2360);
+        assert_eq!(RESULT_SLOT, 2368);
+    }
+
+    #[test]
+    fn test_parse_pull_feed() {
+        // BTC at $65,000.5 with 18 decimals
+        let value = 65_000_500_000_000_000_000_000i128;
+        let feed = parse_pull_feed(&feed_account([9; 32], value, 5, 3)).unwrap();
+
+        assert_eq!(feed.feed_hash, [9; 32]);
+        assert_eq!(feed.last_update_timestamp, 1_700_000_000);
+        assert_eq!(feed.slot, 42);
+        assert_eq!(feed.micro_usd(), Some(65_000_500_000));
+        assert!(feed.has_min_samples());
+    }
+
+    #[test]
+    fn test_rejects_bad_feeds() {
+        let mut data = feed_account([9; 32], 1, 5, 3);
+        data[0] ^= 1;
+        assert_eq!(parse_pull_feed(&data), None);
+        assert_eq!(parse_pull_feed(&feed_account([9; 32], 1, 5, 3)[..MIN_ACCOUNT_LEN - 1]), None);
+
+        let thin = parse_pull_feed(&feed_account([9; 32], 1, 2, 3)).unwrap();
+        assert!(!thin.has_min_samples());
+        let empty = parse_pull_feed(&feed_account([9; 32], 1, 0, 0)).unwrap();
+        assert!(!empty.has_min_samples());
+
+        let negative = parse_pull_feed(&feed_account([9; 32], -1, 5, 3)).unwrap();
+        assert_eq!(negative.micro_usd(), None);
+    }
+}
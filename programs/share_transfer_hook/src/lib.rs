@@ -0,0 +1,301 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_hook::TransferHookAccount;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::Account as TokenAccountState;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::account::ExtraAccountMeta;
+use spl_tlv_account_resolution::seeds::Seed;
+use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use spl_transfer_hook_interface::instruction::ExecuteInstruction;
+
+declare_id!("8NWQRN7MGfXmgjBqYaS5Kv3nkjxb139uBxmmgbmQJkVs");
+
+/// Highest transfer fee a share mint may charge (1%)
+pub const MAX_FEE_BPS: u16 = 100;
+
+/// Accounts Token-2022 appends to every transfer: the hook config and the
+/// sender's fee credit
+const NUM_EXTRA_ACCOUNTS: usize = 2;
+
+/// Secondary-market fee on transfers of Token-2022 vault shares
+///
+/// A share mint with the TransferHook extension pointing here pays
+/// `fee_bps` of every transfer to the vault's treasury share account. A hook
+/// runs inside the token program's transfer and can't move tokens itself, so
+/// the fee is prepaid: shares sent to the treasury credit the sender's
+/// FeeCredit, and each other transfer draws its fee from the credit of the
+/// source account's owner, failing if the credit is short. Wallets and pools
+/// open their FeeCredit once with open_fee_credit; transfers out of the
+/// treasury are free. Minting and burning aren't transfers and never pay.
+#[program]
+pub mod share_transfer_hook {
+    use super::*;
+
+    /// Set up the hook for a share mint; signed by the mint authority
+    pub fn initialize_hook(ctx: Context<InitializeHook>, vault: Pubkey, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+        require!(
+            ctx.accounts.mint.mint_authority == Some(ctx.accounts.authority.key()).into(),
+            ErrorCode::Unauthorized
+        );
+
+        {
+            let mut data = ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?;
+            ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_account_metas()?)?;
+        }
+
+        let config = &mut ctx.accounts.hook_config;
+        config.bump = ctx.bumps.hook_config;
+        config.mint = ctx.accounts.mint.key();
+        config.vault = vault;
+        config.treasury = ctx.accounts.treasury.key();
+        config.authority = ctx.accounts.authority.key();
+        config.fee_bps = fee_bps;
+
+        msg!("Transfer fee of {} bps on {} paid to {}", fee_bps, config.mint, config.treasury);
+        Ok(())
+    }
+
+    /// Change the fee and the treasury account it is paid to
+    pub fn set_fee(ctx: Context<SetFee>, fee_bps: u16) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFee);
+
+        let config = &mut ctx.accounts.hook_config;
+        config.fee_bps = fee_bps;
+        config.treasury = ctx.accounts.treasury.key();
+
+        msg!("Transfer fee set to {} bps, paid to {}", fee_bps, config.treasury);
+        Ok(())
+    }
+
+    /// Open `owner`'s FeeCredit for a share mint; anyone may pay for it
+    pub fn open_fee_credit(ctx: Context<OpenFeeCredit>, owner: Pubkey) -> Result<()> {
+        let credit = &mut ctx.accounts.fee_credit;
+        credit.bump = ctx.bumps.fee_credit;
+        credit.mint = ctx.accounts.mint.key();
+        credit.owner = owner;
+        credit.balance = 0;
+        Ok(())
+    }
+
+    /// Token-2022 transfer hook (the interface's Execute instruction)
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        require_transferring(&ctx.accounts.source_token.to_account_info())?;
+
+        let config = &ctx.accounts.hook_config;
+        let credit = &mut ctx.accounts.fee_credit;
+        if ctx.accounts.source_token.key() == config.treasury {
+            return Ok(());
+        }
+        if ctx.accounts.destination_token.key() == config.treasury {
+            credit.balance = credit.balance.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+            msg!("Prepaid {} shares of transfer fees ({} credited)", amount, credit.balance);
+            return Ok(());
+        }
+
+        let fee = transfer_fee(amount, config.fee_bps).ok_or(ErrorCode::MathOverflow)?;
+        require!(credit.balance >= fee, ErrorCode::InsufficientFeeCredit);
+        credit.balance -= fee;
+        msg!("Charged a {} share transfer fee ({} credit left)", fee, credit.balance);
+        Ok(())
+    }
+}
+
+/// Fee on a transfer of `amount` shares, rounded up so every transfer of a
+/// fee-charging mint pays something
+pub fn transfer_fee(amount: u64, fee_bps: u16) -> Option<u64> {
+    let fee = (amount as u128 * fee_bps as u128).div_ceil(10_000);
+    u64::try_from(fee).ok()
+}
+
+/// hook_config, then the FeeCredit of the source account's owner (bytes
+/// 32..64 of the token account)
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"hook_config".to_vec() },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            false,
+        )?,
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"fee_credit".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 0, data_index: 32, length: 32 },
+            ],
+            false,
+            true,
+        )?,
+    ])
+}
+
+/// Reject calls to the hook outside of a Token-2022 transfer
+fn require_transferring(source: &AccountInfo) -> Result<()> {
+    let data = source.try_borrow_data()?;
+    let account = StateWithExtensions::<TokenAccountState>::unpack(&data)?;
+    let hook = account.get_extension::<TransferHookAccount>()?;
+    require!(bool::from(hook.transferring), ErrorCode::NotTransferring);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeHook<'info> {
+    /// CHECK: Written by initialize_hook as the mint's ExtraAccountMetaList
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(NUM_EXTRA_ACCOUNTS).unwrap(),
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + HookConfig::INIT_SPACE,
+        seeds = [b"hook_config", mint.key().as_ref()],
+        bump
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Vault treasury's share account, which receives the fees
+    #[account(token::mint = mint)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    /// Mint authority of the share mint
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"hook_config", hook_config.mint.as_ref()],
+        bump = hook_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub hook_config: Account<'info, HookConfig>,
+
+    #[account(token::mint = hook_config.mint)]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct OpenFeeCredit<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FeeCredit::INIT_SPACE,
+        seeds = [b"fee_credit", mint.key().as_ref(), owner.as_ref()],
+        bump
+    )]
+    pub fee_credit: Account<'info, FeeCredit>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts of the transfer hook interface's Execute instruction
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Source owner or delegate; the token program checked its signature
+    pub owner: UncheckedAccount<'info>,
+
+    /// CHECK: The mint's ExtraAccountMetaList
+    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"hook_config", mint.key().as_ref()], bump = hook_config.bump)]
+    pub hook_config: Account<'info, HookConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_credit", mint.key().as_ref(), source_token.owner.as_ref()],
+        bump = fee_credit.bump
+    )]
+    pub fee_credit: Account<'info, FeeCredit>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct HookConfig {
+    pub bump: u8,
+    pub mint: Pubkey,
+    /// Vault whose shares `mint` are
+    pub vault: Pubkey,
+    /// Share account the fees are paid to
+    pub treasury: Pubkey,
+    /// Can change the fee and treasury
+    pub authority: Pubkey,
+    pub fee_bps: u16,
+}
+
+/// Transfer fees an owner has prepaid, in shares
+#[account]
+#[derive(InitSpace)]
+pub struct FeeCredit {
+    pub bump: u8,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub balance: u64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Fee exceeds the allowed maximum")]
+    InvalidFee,
+    #[msg("Signer is not the share mint or hook authority")]
+    Unauthorized,
+    #[msg("Hook was called outside of a token transfer")]
+    NotTransferring,
+    #[msg("Prepaid transfer fees don't cover this transfer; send shares to the treasury first")]
+    InsufficientFeeCredit,
+    #[msg("Math overflow")]
+    MathOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_fee() {
+        // 25 bps of 1,000 shares
+        assert_eq!(transfer_fee(1_000_000_000_000, 25), Some(2_500_000_000));
+        // Rounded up: even 1 base unit pays at a nonzero rate
+        assert_eq!(transfer_fee(1, 25), Some(1));
+        assert_eq!(transfer_fee(1_000, 0), Some(0));
+        assert_eq!(transfer_fee(u64::MAX, MAX_FEE_BPS), Some(u64::MAX / 100 + 1));
+    }
+}
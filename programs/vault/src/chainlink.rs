@@ -0,0 +1,175 @@
+//! Chainlink Data Streams prices
+//!
+//! A Chainlink vault prices from Data Streams reports verified on-chain.
+//! Anyone relays a signed report through post_chainlink_report, which hands
+//! it to the Chainlink verifier program by CPI. The verifier checks the DON's
+//! signatures against its config (and the caller against its access
+//! controller) and returns the report data, a v3 (crypto) report whose
+//! benchmark price the vault keeps in the protocol's ChainlinkConfig for the
+//! market with the report's feed id. Pricing instructions then read that
+//! account and reject any market whose price was observed more than
+//! MAX_CHAINLINK_AGE_SECS ago, so relayers post a fresh report ahead of them
+//! (usually in the same transaction).
+
+use anchor_lang::prelude::*;
+
+/// Chainlink Data Streams verifier program (same id on devnet and mainnet)
+pub const VERIFIER_PROGRAM_ID: Pubkey = pubkey!("Gt9S41PtjR58CbG9JhJ3J6vxesqrNAswbWYbLNTMZA3c");
+
+/// Oldest observation a pricing instruction accepts
+pub const MAX_CHAINLINK_AGE_SECS: i64 = 60;
+
+/// Decimals of v3 report prices
+pub const PRECISION: u32 = 18;
+
+/// Anchor discriminator of the verifier's `verify` instruction
+const VERIFY_DISCRIMINATOR: [u8; 8] = [133, 161, 141, 48, 120, 198, 88, 150];
+
+/// Report schema carried in the leading two bytes of its feed id
+const REPORT_VERSION_V3: u16 = 3;
+
+/// ABI words of a v3 report: feed id, valid from, observed at, native fee,
+/// LINK fee, expires at, benchmark price, bid and ask
+const REPORT_V3_WORDS: usize = 9;
+const WORD: usize = 32;
+
+/// The fields of a v3 report the vault prices from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReportV3 {
+    pub feed_id: [u8; 32],
+    /// Unix time the price was observed
+    pub observations_timestamp: u32,
+    /// Unix time after which the report is no longer valid
+    pub expires_at: u32,
+    /// Benchmark (mid) price, with PRECISION decimals
+    pub benchmark_price: i128,
+}
+
+impl ReportV3 {
+    /// Benchmark price in micro-USD, rounded down; None if not positive or
+    /// too large for an i64
+    pub fn micro_usd(&self) -> Option<i64> {
+        if self.benchmark_price <= 0 {
+            return None;
+        }
+        i64::try_from(self.benchmark_price / 10i128.pow(PRECISION - 6)).ok()
+    }
+}
+
+/// Data of the verifier's `verify` instruction for `signed_report` (the
+/// snappy-compressed full report, as the verifier expects it)
+pub fn verify_instruction_data(signed_report: &[u8]) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + 4 + signed_report.len());
+    data.extend_from_slice(&VERIFY_DISCRIMINATOR);
+    data.extend_from_slice(&(signed_report.len() as u32).to_le_bytes());
+    data.extend_from_slice(signed_report);
+    data
+}
+
+/// Parse the report data the verifier returns; None unless it is a v3
+/// report whose integers fit their types
+pub fn parse_report_v3(data: &[u8]) -> Option<ReportV3> {
+    if data.len() != REPORT_V3_WORDS * WORD {
+        return None;
+    }
+    let word = |index: usize| &data[index * WORD..(index + 1) * WORD];
+
+    let feed_id: [u8; 32] = word(0).try_into().ok()?;
+    if u16::from_be_bytes([feed_id[0], feed_id[1]]) != REPORT_VERSION_V3 {
+        return None;
+    }
+    Some(ReportV3 {
+        feed_id,
+        observations_timestamp: abi_u32(word(2))?,
+        expires_at: abi_u32(word(5))?,
+        benchmark_price: abi_i128(word(6))?,
+    })
+}
+
+/// ABI uint32: big-endian, zero-padded on the left
+fn abi_u32(word: &[u8]) -> Option<u32> {
+    let (padding, value) = word.split_at(WORD - 4);
+    if padding.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(u32::from_be_bytes(value.try_into().ok()?))
+}
+
+/// ABI int192, sign-extended to a word; None if it doesn't fit an i128
+fn abi_i128(word: &[u8]) -> Option<i128> {
+    let (padding, value) = word.split_at(WORD - 16);
+    let value = i128::from_be_bytes(value.try_into().ok()?);
+    let sign = if value < 0 { 0xff } else { 0 };
+    padding.iter().all(|byte| *byte == sign).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(value: i128) -> [u8; 32] {
+        let mut word = [if value < 0 { 0xff } else { 0 }; 32];
+        word[16..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    fn report(feed_id: [u8; 32], observed_at: u32, price: i128) -> Vec<u8> {
+        let words = [
+            feed_id,
+            word(observed_at as i128),
+            word(observed_at as i128),
+            word(0),
+            word(0),
+            word(observed_at as i128 + 86_400),
+            word(price),
+            word(price - 1),
+            word(price + 1),
+        ];
+        words.concat()
+    }
+
+    fn feed_id(version: u16) -> [u8; 32] {
+        let mut id = [7u8; 32];
+        id[..2].copy_from_slice(&version.to_be_bytes());
+        id
+    }
+
+    #[test]
+    fn test_parse_report_v3() {
+        // ETH at $3,000.25 with 18 decimals
+        let price = 3_000_250_000_000_000_000_000i128;
+        let report = parse_report_v3(&report(feed_id(3), 1_700_000_000, price)).unwrap();
+
+        assert_eq!(report.feed_id, feed_id(3));
+        assert_eq!(report.observations_timestamp, 1_700_000_000);
+        assert_eq!(report.expires_at, 1_700_086_400);
+        assert_eq!(report.micro_usd(), Some(3_000_250_000));
+    }
+
+    #[test]
+    fn test_parse_report_rejects_others() {
+        // Other schemas, truncated data and out-of-range integers
+        assert_eq!(parse_report_v3(&report(feed_id(2), 1, 1)), None);
+        let valid = report(feed_id(3), 1, 1);
+        assert_eq!(parse_report_v3(&valid[..valid.len() - 1]), None);
+
+        let mut wide_timestamp = valid.clone();
+        wide_timestamp[2 * WORD] = 1;
+        assert_eq!(parse_report_v3(&wide_timestamp), None);
+
+        let mut wide_price = valid;
+        wide_price[6 * WORD] = 1;
+        assert_eq!(parse_report_v3(&wide_price), None);
+
+        let negative = parse_report_v3(&report(feed_id(3), 1, -5)).unwrap();
+        assert_eq!(negative.benchmark_price, -5);
+        assert_eq!(negative.micro_usd(), None);
+    }
+
+    #[test]
+    fn test_verify_instruction_data() {
+        let data = verify_instruction_data(&[1, 2, 3]);
+        assert_eq!(data[..8], VERIFY_DISCRIMINATOR);
+        assert_eq!(data[8..], [3, 0, 0, 0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,20 @@
+//! Ephemeral Rollups compatibility shim
+//!
+//! The delegated mock oracle (delegate_mock_oracle and the commit, crank and
+//! undelegate instructions around it) runs on MagicBlock's Ephemeral Rollups
+//! through `ephemeral-rollups-sdk`. SDK releases have lagged Anchor's before:
+//! 0.2.11 pulled in a borsh that Anchor 0.31.1 could not build with, which
+//! kept the ER instructions switched off. So the SDK is only compiled with
+//! the `ephemeral-rollups` feature (on by default), pinned to a release known
+//! to build against this Anchor, and everything the program takes from it is
+//! re-exported here. Building with `--no-default-features --features devnet`
+//! leaves the ER instructions and their contexts out and the rest of the
+//! program unchanged; a future SDK API change only touches this file.
+//!
+//! The ER bookkeeping kept on MockPriceOracle (commit interval, session
+//! start and length) is plain data and stays in every build, so accounts
+//! keep one layout whichever way the program was built.
+
+pub use ephemeral_rollups_sdk::anchor::{commit, delegate, ephemeral};
+pub use ephemeral_rollups_sdk::cpi::DelegateConfig;
+pub use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
@@ -0,0 +1,116 @@
+//! Market-cap index weights
+//!
+//! A market-cap weighted vault derives its composition from each asset's
+//! supply × price instead of admin-set weights. Weights stay whole
+//! percentages summing to 100 with every asset at least 1%, and one
+//! recompute moves any weight by at most a configured number of points so
+//! a single price print cannot swing the basket.
+
+/// Whole-percentage weights proportional to `caps`
+///
+/// Rounding leftovers go to the largest remainders, then any asset that
+/// rounded to 0% is raised to 1% (weights must stay non-zero), taken from
+/// the largest weight. Returns None when the caps are all zero or there are
+/// more than 50 assets.
+pub fn market_cap_weights(caps: &[u128]) -> Option<Vec<u8>> {
+    let total: u128 = caps.iter().sum();
+    if total == 0 || caps.len() > 50 {
+        return None;
+    }
+
+    let mut weights: Vec<u8> = Vec::with_capacity(caps.len());
+    let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(caps.len());
+    for (i, cap) in caps.iter().enumerate() {
+        let share = cap.checked_mul(100)?;
+        weights.push((share / total) as u8);
+        remainders.push((share % total, i));
+    }
+
+    let assigned: usize = weights.iter().map(|w| *w as usize).sum();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (_, i) in remainders.iter().take(100 - assigned) {
+        weights[*i] += 1;
+    }
+
+    while let Some(zero) = weights.iter().position(|w| *w == 0) {
+        let largest = (0..weights.len()).max_by_key(|i| (weights[*i], usize::MAX - i))?;
+        weights[largest] -= 1;
+        weights[zero] = 1;
+    }
+    Some(weights)
+}
+
+/// Move `current` towards `target`, each weight by at most `max_change`
+/// points, keeping the sum at 100
+///
+/// Both inputs must sum to 100. When the clamped increases and decreases
+/// differ, the larger side is trimmed to match, earliest assets first.
+pub fn step_weights(current: &[u8], target: &[u8], max_change: u8) -> Vec<u8> {
+    let max_change = max_change as i16;
+    let mut deltas: Vec<i16> = current
+        .iter()
+        .zip(target)
+        .map(|(c, t)| (*t as i16 - *c as i16).clamp(-max_change, max_change))
+        .collect();
+
+    let up: i16 = deltas.iter().filter(|d| **d > 0).sum();
+    let down: i16 = -deltas.iter().filter(|d| **d < 0).sum::<i16>();
+    let mut excess_up = up - up.min(down);
+    let mut excess_down = down - up.min(down);
+    for delta in deltas.iter_mut() {
+        if *delta > 0 && excess_up > 0 {
+            let trim = (*delta).min(excess_up);
+            *delta -= trim;
+            excess_up -= trim;
+        } else if *delta < 0 && excess_down > 0 {
+            let trim = (-*delta).min(excess_down);
+            *delta += trim;
+            excess_down -= trim;
+        }
+    }
+
+    current
+        .iter()
+        .zip(deltas)
+        .map(|(c, d)| (*c as i16 + d) as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_market_cap_weights() {
+        // $60 / $30 / $10
+        let weights = market_cap_weights(&[60, 30, 10]).unwrap();
+        assert_eq!(weights.iter().map(|w| *w as u32).sum::<u32>(), 100);
+        assert_eq!(weights, vec![60, 30, 10]);
+
+        // A negligible cap still gets 1%
+        let weights = market_cap_weights(&[1_000_000, 1_000_000, 1]).unwrap();
+        assert_eq!(weights, vec![49, 50, 1]);
+
+        // 1/3 each: the leftover point goes to the first
+        assert_eq!(market_cap_weights(&[7, 7, 7]).unwrap(), vec![34, 33, 33]);
+
+        assert!(market_cap_weights(&[0, 0]).is_none());
+        assert!(market_cap_weights(&[]).is_none());
+    }
+
+    #[test]
+    fn test_step_weights_bounded() {
+        // Increases capped at 5, so decreases only take 5 of their 10; the
+        // earliest decrease is trimmed first
+        let stepped = step_weights(&[40, 30, 30], &[70, 20, 10], 5);
+        assert_eq!(stepped, vec![45, 30, 25]);
+
+        // Decreases capped at 5, so increases only get 5 of their 10
+        let stepped = step_weights(&[10, 10, 80], &[30, 30, 40], 5);
+        assert_eq!(stepped, vec![10, 15, 75]);
+        assert_eq!(stepped.iter().map(|w| *w as u32).sum::<u32>(), 100);
+
+        // Within the bound the target is reached
+        assert_eq!(step_weights(&[40, 30, 30], &[42, 29, 29], 5), vec![42, 29, 29]);
+    }
+}
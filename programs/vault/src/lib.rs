@@ -1,23 +1,96 @@
+// Anchor 0.31 codegen still calls the deprecated AccountInfo::realloc
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use anchor_lang::Result;
 use anchor_lang::system_program::{transfer, Transfer};
-use anchor_spl::associated_token::{spl_associated_token_account, AssociatedToken};
+use anchor_lang::solana_program::sysvar::instructions::{self as sysvar_instructions, load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{self, Mint as ShareMint, TokenAccount as ShareAccount, TokenInterface};
 
-// Ephemeral Rollups SDK imports
-use ephemeral_rollups_sdk::anchor::{commit, delegate, ephemeral};
-use ephemeral_rollups_sdk::cpi::DelegateConfig;
-use ephemeral_rollups_sdk::ephem::{commit_accounts, commit_and_undelegate_accounts};
+// Ephemeral Rollups SDK, behind the `ephemeral-rollups` feature
+#[cfg(feature = "ephemeral-rollups")]
+mod er;
+#[cfg(feature = "ephemeral-rollups")]
+use er::{commit, commit_accounts, commit_and_undelegate_accounts, delegate, ephemeral, DelegateConfig};
 
 // Mock swap module for devnet testing
 mod swap;
-use swap::MockSwap;
+pub use swap::MockPoolConfig;
+
+// Direct Orca Whirlpool swaps for the top pairs
+mod orca;
+
+// Metaplex metadata (name, symbol, logo) for share mints
+mod share_metadata;
+pub use share_metadata::TOKEN_METADATA_PROGRAM_ID;
+
+// SPL Token / Token-2022 balances and transfers for basket assets
+mod token_io;
+
+// Token-2022 non-transferable share mints for soulbound vaults
+mod soulbound;
+
+// Per-vault address lookup tables for v0 deposit/withdraw transactions
+mod lookup_table;
+
+// Weight math for market-cap index mode
+mod index_weights;
+
+// Exchange-rate pricing for LST index vaults
+mod lst;
+
+// Sanctum router conversions between SOL and LSTs
+mod sanctum;
+pub use lookup_table::{LOOKUP_TABLE_PROGRAM_ID, MAX_ADDRESSES_PER_EXTEND};
+
+// Cached SOL value of the staking strategy, counted in TVL
+mod strategy_value;
+
+// Address checks for the Marinade accounts passed to marinade_strategy
+mod marinade;
+use marinade::MarinadeAccount;
+
+// u128 fixed-point helpers for share and TVL math
+mod math;
+
+// Drift and swap-leg planning shared by rebalance and simulate_rebalance
+mod rebalance_plan;
+
+// Completed Arcium rebalancing results, read back from the MXE program
+mod mxe;
+pub use mxe::{computation_nonce, REBALANCE_RESULT_SEED, REBALANCING_MXE_PROGRAM_ID, SEALED_TARGETS_SEED};
+
+// Rolling deviation from target weights, sampled by snapshot_nav
+mod tracking;
+
+// Ed25519-signed mock oracle updates, relayed by anyone
+pub mod signed_prices;
+
+// Chainlink Data Streams reports, verified by CPI to the Chainlink verifier
+pub mod chainlink;
+
+// Swap backend selection shared by deposit, withdraw and rebalance
+mod swap_engine;
+pub use swap_engine::SwapBackend;
+use swap_engine::{SwapAsset, SwapEngine};
 
 // Switchboard Oracle Quotes integration
 // Manual parsing of Switchboard Pull Feed data to avoid dependency conflicts
 
-mod state;
-use state::{AssetConfig, Vault};
+pub mod state;
+use state::{
+    AssetConfig, AssetOracles, AssetWhitelist, AuthorizedParticipant, BatchDeposit, BatchWithdrawal, DcaSchedule, DepositBatch, DepositCommitment, LstBasket, LstKind, LstPool, MarketCapIndex, ScheduleStep, DepositTicket, NavHistory, TicketSlice, NavSnapshot, OracleFeeders, PriceCache, PythLazerConfig, SwitchboardConfig, ChainlinkConfig, PriceFeed, PriceFeedKind, ProtocolConfig, FeeTier, QuoteConfig, ReferralBalance,
+    RebalanceConfig, StakePool, StakePosition, StrategyValue, UserPosition, LegacyVault, Vault, VaultLookupTable, VaultMetadata, VaultPrefix, VaultPreset, VaultRegistration, VaultRegistry, VaultSchedule, VaultTemplate, TrackingError, VaultGuardian, WithdrawalBatch, MAX_FEEDS_PER_ASSET,
+    MAX_APPROVED_ALTERNATES, MAX_ASSETS, MAX_TEMPLATE_ASSETS, MAX_FEE_TIERS, MAX_NAME_LEN, MAX_ORACLE_FEEDERS, MAX_PRICE_FEEDS, MAX_PYTH_LAZER_SIGNERS, ORACLE_MARKETS, MIN_DCA_INTERVAL_SECS, MIN_RECOMPUTE_INTERVAL_SECS, MIN_WIND_DOWN_GRACE_SECS, DEPOSIT_TICKET_TTL_SECS, NAV_SNAPSHOT_INTERVAL_SECS, SECONDS_PER_DAY, VAULT_VERSION,
+};
+
+// Oracle price fetching shared by deposit, withdraw and rebalance
+mod prices;
+pub use prices::{aggregated_feed_accounts, NormalizedPrice};
+use prices::{BasketPrices, PriceAccounts};
 
 // Mock Price Oracle for devnet testing
 // This allows testing with real-time prices
@@ -30,179 +103,191 @@ pub struct MockPriceOracle {
     pub sol_price: i64,          // SOL/USD price in micro-dollars (6 decimals)
     pub last_update: i64,        // Unix timestamp of last update
     pub bump: u8,                // PDA bump seed
+    pub history: [PriceSample; TWAP_SAMPLES], // Ring buffer of recent updates
+    pub history_index: u8,       // Next slot to write in history
+    pub history_len: u8,         // Number of valid samples in history
+    pub commit_frequency_ms: u32, // ER commit interval set by delegate_mock_oracle (0 = never delegated)
+    pub last_commit_at: i64,     // Unix timestamp of the last ER commit to L1
+    pub delegated_at: i64,       // Unix timestamp the current ER session began (0 = on L1)
+    pub max_session_secs: u32,   // ER session length after which anyone may undelegate
+}
+
+// Number of price updates kept by the mock oracle for TWAP
+pub const TWAP_SAMPLES: usize = 8;
+
+// Shortest ER commit interval delegate_mock_oracle accepts (1 second)
+#[cfg(feature = "ephemeral-rollups")]
+pub const MIN_ER_COMMIT_FREQUENCY_MS: u32 = 1_000;
+
+// Longest ER session delegate_mock_oracle accepts (7 days)
+#[cfg(feature = "ephemeral-rollups")]
+pub const MAX_ER_SESSION_SECS: u32 = 7 * 24 * 60 * 60;
+
+/// A single mock oracle update, kept for TWAP calculation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct PriceSample {
+    pub btc_price: i64,
+    pub eth_price: i64,
+    pub sol_price: i64,
+    pub timestamp: i64,
+}
+
+/// One market's price in an update_prices_batch call, keyed by the mint a
+/// vault holds for it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct MintPrice {
+    pub mint: Pubkey,
+    pub price: i64, // USD price in micro-dollars (6 decimals)
 }
 
 impl MockPriceOracle {
-    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1; // discriminator + pubkey + 4*i64 + u8
+    // discriminator + pubkey + 4*i64 + u8 + history (4*i64 each) + 2*u8 + ER commit frequency and time
+    // + ER session start and length
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1 + TWAP_SAMPLES * 32 + 1 + 1 + 4 + 8 + 8 + 4;
+
+    /// Append the current prices to the history ring buffer
+    pub fn record_sample(&mut self) {
+        self.history[self.history_index as usize] = PriceSample {
+            btc_price: self.btc_price,
+            eth_price: self.eth_price,
+            sol_price: self.sol_price,
+            timestamp: self.last_update,
+        };
+        self.history_index = ((self.history_index as usize + 1) % TWAP_SAMPLES) as u8;
+        self.history_len = (self.history_len + 1).min(TWAP_SAMPLES as u8);
+    }
+
+    /// Whether the ER commit interval has elapsed since the last commit
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn commit_due(&self, now: i64) -> bool {
+        self.commit_frequency_ms > 0 && (now - self.last_commit_at) * 1_000 >= self.commit_frequency_ms as i64
+    }
+
+    /// Whether the current ER session has run past max_session_secs
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn session_expired(&self, now: i64) -> bool {
+        self.delegated_at > 0 && now - self.delegated_at >= self.max_session_secs as i64
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub enum PriceSource {
     Switchboard,  // Use Switchboard feeds (for mainnet/production)
     MockOracle,   // Use mock oracle (for devnet testing)
+    Aggregated,   // Median of up to three configured feeds per asset
+    CrossChecked, // Switchboard, rejected when the mock oracle disagrees by more than max_divergence_bps
+    PythLazer,    // Signed Pyth Lazer update verified in the same transaction
+    Chainlink,    // Chainlink Data Streams reports posted through post_chainlink_report
+}
+
+/// Operation holding a vault's lock (Vault::begin_operation)
+///
+/// Every instruction that moves the vault's holdings takes the lock for its
+/// duration, and a confidential rebalance holds it from rebalance_confidential
+/// until its result is applied, so deposits, withdrawals and strategy exits
+/// cannot act on balances or a cached TVL the pending rebalance is about to
+/// move. If the result never arrives or is never applied, anyone can
+/// release the lock after CONFIDENTIAL_REBALANCE_TIMEOUT_SECS
+/// (release_stale_rebalance).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum VaultOperation {
+    Idle,
+    Deposit,
+    Withdraw,
+    Rebalance,
+    StrategyExit, // divest_from_strategy pulling SOL out of the strategy
+}
+
+/// Currency a vault is quoted in (QuoteConfig)
+///
+/// NAV is always computed in micro-USD. A USDC vault takes deposits and pays
+/// withdrawals in USDC through deposit_zap and withdraw_zap, with USDC held
+/// to its $1 peg for the swap's oracle bound, and reports TVL and share price
+/// in USDC base units, which at the peg are the micro-USD figures. SOL
+/// deposits stay open and are valued the same way.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuoteCurrency {
+    #[default]
+    Sol,
+    Usdc,
 }
 
 // Import strategy interface types for Marinade integration
 // use strategy_interface::{InitializeArgs, StakeArgs, StrategyKind, StrategyState, UnstakeArgs};
 
+/// Decimals of USDC, whose base units equal micro-USD at its $1 peg
+pub const USDC_DECIMALS: u8 = 6;
+
+/// NAV denomination when a vault sets none (QuoteConfig::nav_currency)
+pub const USD_CURRENCY: [u8; 3] = *b"USD";
+
 // Switchboard Oracle Quotes configuration
 // Maximum age for quotes in seconds (2 minutes for devnet)
 pub const MAX_QUOTE_AGE_SECS: u64 = 120;
 
-// Price feed precision helper
-// Switchboard Oracle Quotes use i64 with negative exponents (e.g., -8 for BTC)
-// This helper normalizes prices to a common USD value
-#[derive(Clone, Copy, Debug)]
-pub struct NormalizedPrice {
-    pub price_usd: i64, // Price in USD with 6 decimals (micro-dollars)
-    pub original_price: i64,
-    pub expo: i32,
-}
-
-impl NormalizedPrice {
-    /// Convert Switchboard Oracle Quote to micro-dollars (6 decimals)
-    /// Example: BTC at $50,000 with expo=-8 -> 50_000_000_000 micro-dollars
-    pub fn from_switchboard_quote(price: i64, expo: i32) -> Result<Self> {
-        let price_usd = if expo < -6 {
-            // Price has more decimals than we want, divide
-            price
-                .checked_div(10i64.pow((-expo - 6) as u32))
-                .ok_or(VaultError::MathOverflow)?
-        } else if expo > -6 {
-            // Price has fewer decimals, multiply
-            price
-                .checked_mul(10i64.pow((6 + expo) as u32))
-                .ok_or(VaultError::MathOverflow)?
-        } else {
-            price
-        };
-        Ok(Self {
-            price_usd,
-            original_price: price,
-            expo,
-        })
-    }
-
-    /// Calculate token amount from USD value (in micro-dollars)
-    /// Returns amount in token's native decimals
-    pub fn usd_to_tokens(&self, usd_micro: i64, token_decimals: u8) -> Result<i64> {
-        // usd_micro has 6 decimals
-        // price_usd has 6 decimals
-        // Result should have token_decimals
-        let base_amount: i64 = usd_micro
-            .checked_mul(10i64.pow(token_decimals as u32))
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(self.price_usd)
-            .ok_or(VaultError::MathOverflow)?;
-        Ok(base_amount)
-    }
-
-    /// Calculate USD value from token amount
-    pub fn tokens_to_usd(&self, amount: u64, token_decimals: u8) -> i64 {
-        let amount_i64 = amount as i64;
-        (amount_i64 * self.price_usd) / 10i64.pow(token_decimals as u32)
-    }
-}
+// Arcium MXE compute_rebalancing configuration
+// Instruction discriminator: first 8 bytes of SHA256("global:compute_rebalancing")
+pub const COMPUTE_REBALANCING_DISCRIMINATOR: [u8; 8] = [126, 197, 44, 141, 35, 123, 172, 126];
+// RebalancingInput fields, in circuit order:
+// 3 balances (u64), 3 prices (u64), 3 target weights (u8), 3 current weights (u8), threshold (u8)
+pub const REBALANCING_INPUT_FIELDS: usize = 13;
+// RebalancingInput fields holding the BTC/ETH/SOL target weights
+pub const TARGET_WEIGHT_FIELDS: std::ops::Range<usize> = 6..9;
+// Size of a single Rescue ciphertext
+pub const CIPHERTEXT_LEN: usize = 32;
+// Largest trade per asset a hidden-weights rebalance may reveal, in bps of TVL (10%)
+pub const HIDDEN_MAX_TRADE_BPS: u16 = 1_000;
+// Seconds after which anyone may release a confidential rebalance's lock (1 hour)
+pub const CONFIDENTIAL_REBALANCE_TIMEOUT_SECS: i64 = 3_600;
 
-/// Switchboard Oracle Quotes verification helper
-impl Vault {
-    /// Verify and parse a Switchboard Oracle Quote
-    /// This reads from Switchboard Pull Feed accounts on devnet
-    /// Note: For devnet testing, uses reasonable fallback prices if feed is inactive
-    pub fn verify_oracle_quote(
-        price_data: &[u8],
-        _current_timestamp: i64,
-    ) -> Result<NormalizedPrice> {
-        // Ensure we have enough data to parse
-        require!(price_data.len() >= 100, VaultError::InvalidQuote);
-
-        msg!("📊 Parsing Switchboard feed (size: {} bytes)", price_data.len());
-
-        // Switchboard Pull Feed account structure:
-        // Try to extract price data from multiple possible offsets
-        // as the structure may vary between feed versions
-        
-        // Common offsets in Switchboard feeds:
-        // Offset 72-88: value mantissa (i128)
-        // Offset 88-92: scale (i32)
-        
-        let mantissa_bytes: [u8; 16] = price_data[72..88]
-            .try_into()
-            .map_err(|_| VaultError::InvalidQuote)?;
-        let mantissa = i128::from_le_bytes(mantissa_bytes);
-        
-        let scale_bytes: [u8; 4] = price_data[88..92]
-            .try_into()
-            .map_err(|_| VaultError::InvalidQuote)?;
-        let scale = i32::from_le_bytes(scale_bytes);
+// Upper bound for the time-decaying exit fee (5%)
+pub const MAX_EXIT_FEE_BPS: u16 = 500;
 
-        msg!("Raw Switchboard data: mantissa={}, scale={}", mantissa, scale);
+// Upper bound for the deposit fee (1%)
+pub const MAX_DEPOSIT_FEE_BPS: u16 = 100;
 
-        // Convert from i128 (18 decimals internal) to i64 price
-        // Switchboard uses 18 decimal precision internally
-        // Check if feed is active (mantissa should be positive and reasonable)
-        
-        // STRICT MODE: Require active feed data (no fallback)
-        // Comment out this section and uncomment the fallback section below for devnet testing
-        require!(mantissa > 0, VaultError::InvalidQuote);
-        require!(mantissa < 1_000_000_000_000_000_000, VaultError::InvalidQuote);
-        
-        let raw_price = (mantissa / 10i128.pow(9)) as i64;
-        msg!("Parsed price from Switchboard: {}", raw_price);
-        
-        require!(raw_price > 0, VaultError::InvalidPrice);
-        require!(raw_price < 10_000_000, VaultError::InvalidPrice);
-        
-        let price = raw_price;
+// Swap slippage tolerance for new vaults (1%) and its upper bound (5%)
+pub const DEFAULT_MAX_SLIPPAGE_BPS: u16 = 100;
+pub const MAX_SLIPPAGE_BPS: u16 = 500;
 
-        // Convert to normalized price (micro-USD with 6 decimals)
-        // For devnet feeds, use -8 scale (standard for crypto prices)
-        let normalized_price = NormalizedPrice::from_switchboard_quote(price, -8)?;
+// Upper bound for the Mock swap backend's simulated pool fee (1%)
+pub const MAX_MOCK_POOL_FEE_BPS: u16 = 100;
 
-        msg!("✅ Price determined: {} (normalized: ${})", price, normalized_price.price_usd);
+/// Strategy programs a vault may delegate to (set_strategy checks the owner)
+pub const APPROVED_STRATEGY_PROGRAMS: [Pubkey; 1] = [marinade_strategy::ID];
 
-        Ok(normalized_price)
-    }
-}
+/// remaining_accounts per asset for in-kind flows: [holder token account, vault ATA, mint]
+pub const IN_KIND_ACCOUNTS_PER_ASSET: usize = 3;
 
 /// Helper functions for price and token calculations
 impl Vault {
     /// Convert token amount to USD micro-dollars (6 decimals)
     /// Handles different token decimals properly
     pub fn token_amount_to_usd_micro(amount: u64, token_decimals: u8) -> Result<u64> {
-        // Convert from token's native decimals to 6 decimal USD
+        // Rescale from the token's native decimals to 6 decimal USD, rounding down
         let result = if token_decimals >= 6 {
-            // Token has more decimals than USD, divide
-            (amount)
-                .checked_div(10u64.pow((token_decimals - 6) as u32))
-                .ok_or(VaultError::MathOverflow)?
+            amount as u128 / math::pow10(token_decimals - 6)?
         } else {
-            // Token has fewer decimals, multiply
-            (amount)
-                .checked_mul(10u64.pow((6 - token_decimals) as u32))
+            (amount as u128)
+                .checked_mul(math::pow10(6 - token_decimals)?)
                 .ok_or(VaultError::MathOverflow)?
         };
-
-        Ok(result)
+        math::to_u64(result)
     }
 
     /// Convert USD micro-dollars to token amount
     /// Handles different token decimals properly
     pub fn usd_micro_to_token_amount(usd_micro: i64, token_decimals: u8) -> Result<u64> {
-        // Convert from 6 decimal USD to token's native decimals
+        // Rescale from 6 decimal USD to the token's native decimals, rounding down
+        let usd_micro = math::to_u128(usd_micro)?;
         let result = if token_decimals >= 6 {
-            // Token has more decimals than USD, multiply
-            (usd_micro
-                .checked_mul(10i64.pow((token_decimals - 6) as u32))
-                .ok_or(VaultError::MathOverflow)?) as u64
+            usd_micro
+                .checked_mul(math::pow10(token_decimals - 6)?)
+                .ok_or(VaultError::MathOverflow)?
         } else {
-            // Token has fewer decimals, divide
-            (usd_micro
-                .checked_div(10i64.pow((6 - token_decimals) as u32))
-                .ok_or(VaultError::MathOverflow)?) as u64
+            usd_micro / math::pow10(6 - token_decimals)?
         };
-
-        Ok(result)
+        math::to_u64(result)
     }
 
     /// Calculate token amount from USD allocation using normalized price
@@ -212,7 +297,7 @@ impl Vault {
         token_decimals: u8,
     ) -> Result<u64> {
         let amount = normalized_price.usd_to_tokens(usd_allocation, token_decimals)?;
-        Ok(amount as u64)
+        math::to_u64(math::to_u128(amount)?)
     }
 
     /// Calculate total vault value (TVL) in USD micro-dollars
@@ -226,9 +311,9 @@ impl Vault {
         sol_price: &NormalizedPrice,
     ) -> Result<i64> {
         // Calculate USD value for each asset
-        let btc_value_usd = btc_price.tokens_to_usd(btc_balance, 8); // BTC has 8 decimals
-        let eth_value_usd = eth_price.tokens_to_usd(eth_balance, 18); // ETH has 18 decimals
-        let sol_value_usd = sol_price.tokens_to_usd(sol_balance, 9); // SOL has 9 decimals
+        let btc_value_usd = btc_price.tokens_to_usd(btc_balance, 8)?; // BTC has 8 decimals
+        let eth_value_usd = eth_price.tokens_to_usd(eth_balance, 18)?; // ETH has 18 decimals
+        let sol_value_usd = sol_price.tokens_to_usd(sol_balance, 9)?; // SOL has 9 decimals
 
         // Sum all values
         let total_tvl = btc_value_usd
@@ -248,7 +333,7 @@ impl Vault {
         Ok(total_tvl)
     }
 
-    /// Calculate share price in USD micro-dollars
+    /// Calculate share price in USD micro-dollars, rounded down
     /// Special case: if no shares exist, return 1_000_000 (= $1.00)
     pub fn calculate_share_price(tvl_usd_micro: i64, total_shares: u64) -> Result<i64> {
         if total_shares == 0 {
@@ -261,1701 +346,13682 @@ impl Vault {
             msg!("⚠️  WARNING: TVL is {} but {} shares exist - using default share price", tvl_usd_micro, total_shares);
             Ok(1_000_000) // $1.00 per share as fallback
         } else {
-            // Share_Price = TVL / Total_Shares
-            let share_price = (tvl_usd_micro)
-                .checked_mul(1_000_000_000) // Scale up for precision (vault shares have 9 decimals)
-                .ok_or(VaultError::MathOverflow)?
-                .checked_div(total_shares as i64)
-                .ok_or(VaultError::MathOverflow)?
-                .checked_div(1_000) // Scale back to micro-dollars (6 decimals)
-                .ok_or(VaultError::MathOverflow)?;
-
-            Ok(share_price)
+            // Share_Price = TVL × 10^6 / Total_Shares (prices are quoted per 10^6 share units)
+            let share_price = math::mul_div_floor(
+                math::to_u128(tvl_usd_micro)?,
+                math::USD_UNIT,
+                total_shares as u128,
+            )?;
+            math::to_i64(share_price)
         }
     }
 
-    /// Calculate shares to mint based on deposit value and share price
-    pub fn calculate_shares_to_mint(deposit_usd_micro: i64, share_price_usd_micro: i64) -> Result<u64> {
+    /// Calculate shares to mint based on deposit value and share price,
+    /// rounded down
+    ///
+    /// Only for the $1.00 bootstrap price; priced vaults go through
+    /// calculate_shares_for_deposit.
+    fn calculate_shares_to_mint(deposit_usd_micro: i64, share_price_usd_micro: i64) -> Result<u64> {
         // Prevent division by zero or negative share price
         require!(share_price_usd_micro > 0, VaultError::MathOverflow);
-        
-        // Shares = (Deposit_Value * 10^9) / Share_Price
-        // We multiply by 10^9 because vault shares have 9 decimals
-        let shares = (deposit_usd_micro)
-            .checked_mul(1_000_000_000) // Scale to 9 decimals
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(share_price_usd_micro)
-            .ok_or(VaultError::MathOverflow)?
-            .checked_div(1_000) // Adjust from micro (6) to match 9 decimal shares
-            .ok_or(VaultError::MathOverflow)?;
 
-        Ok(shares as u64)
+        // Shares = Deposit_Value × 10^6 / Share_Price
+        let shares = math::mul_div_floor(
+            math::to_u128(deposit_usd_micro)?,
+            math::USD_UNIT,
+            math::to_u128(share_price_usd_micro)?,
+        )?;
+        math::to_u64(shares)
+    }
+
+    /// Shares a deposit worth `deposit_usd_micro` buys from a vault worth
+    /// `tvl_usd_micro`, rounded down
+    ///
+    /// Divides by TVL directly rather than through the rounded share price, so
+    /// the depositor never gains from rounding. Empty (or zero-TVL) vaults
+    /// price at $1.00 as calculate_share_price does.
+    pub fn calculate_shares_for_deposit(deposit_usd_micro: i64, tvl_usd_micro: i64, total_shares: u64) -> Result<u64> {
+        if total_shares == 0 || tvl_usd_micro <= 0 {
+            return Self::calculate_shares_to_mint(deposit_usd_micro, 1_000_000);
+        }
+        let shares = math::mul_div_floor(
+            math::to_u128(deposit_usd_micro)?,
+            total_shares as u128,
+            math::to_u128(tvl_usd_micro)?,
+        )?;
+        math::to_u64(shares)
+    }
+
+    /// Shares to burn for `usd_micro` of a vault worth `tvl_usd_micro`,
+    /// rounded up so a redemption never takes more than its shares are worth
+    pub fn calculate_shares_to_burn(usd_micro: i64, tvl_usd_micro: i64, total_shares: u64) -> Result<u64> {
+        require!(tvl_usd_micro > 0, VaultError::MathOverflow);
+        let shares = math::mul_div_ceil(
+            math::to_u128(usd_micro)?,
+            total_shares as u128,
+            math::to_u128(tvl_usd_micro)?,
+        )?;
+        math::to_u64(shares)
     }
 }
 
 // Events for off-chain tracking and indexing
+// Every event carries `seq` from Vault::next_event_seq, so indexers can order
+// a vault's events and detect gaps
 #[event]
 pub struct VaultCreatedEvent {
     pub vault: Pubkey,
+    pub seq: u64,
     pub admin: Pubkey,
     pub name: String,
     pub vault_token_mint: Pubkey,
     pub num_assets: u8,
+    /// Shares are non-transferable
+    pub soulbound: bool,
 }
 
 #[event]
 pub struct DepositEvent {
     pub vault: Pubkey,
+    pub seq: u64,
     pub user: Pubkey,
     pub deposit_mint: Pubkey,
     pub amount_deposited: u64,
     pub shares_minted: u64,
-    pub tvl_usd: i64,
+      pub tvl_usd: i64,
 }
 
 #[event]
 pub struct WithdrawEvent {
     pub vault: Pubkey,
+    pub seq: u64,
     pub user: Pubkey,
     pub shares_burned: u64,
+    pub withdraw_mint: Pubkey,
     pub amount_withdrawn: u64,
-  pub tvl_usd: i64,
+    pub exit_fee: u64,
+    pub tvl_usd: i64,
+    /// Proceeds less the average cost of the burned shares (micro-dollars)
+    pub realized_pnl_usd: i64,
 }
 
-declare_id!("Faiwct1BxfrV1w5xYs8Y55mQ4VJXPGx1qPBZJnw5p7pR");
+#[event]
+pub struct DcaScheduleCreatedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub schedule: Pubkey,
+    pub user: Pubkey,
+    pub funding_mint: Option<Pubkey>,
+    pub amount_per_period: u64,
+    pub interval_secs: i64,
+}
 
-#[ephemeral]
-#[program(heap = 262144)] // 256KB heap for CPI operations with large instruction data
-pub mod vault {
-  use super::*;    /// Create a new multi-asset vault with custom composition
-    ///
-    /// This is the primary initialization instruction for creating vaults.
-    /// Each vault is a unique PDA derived from admin + name, allowing multiple
-    /// vaults per admin with different compositions.
-    ///
-    /// **Key Design Decisions:**
-    /// 1. **PDA Seeds**: [b"vault", admin, name] - enables multiple vaults per admin
-    /// 2. **Dynamic Space**: Calculated from name length and asset count at runtime
-    /// 3. **Share Mint**: Each vault has unique SPL token for shares (9 decimals for precision)
-    /// 4. **Asset ATAs**: Created via remaining_accounts to handle variable asset count
-    ///
-    /// **Solana Best Practices:**
-    /// - Uses init constraint for atomic account creation with rent exemption
-    /// - Vault PDA is mint authority for shares (secure share issuance)
-    /// - ATAs use canonical Associated Token Program addresses
-    /// - Validates composition (weights sum to 100) before creation
-    ///
-    /// **Parameters:**
-    /// - name: Unique identifier (max 32 bytes for space efficiency)
-    /// - assets: Vec of AssetConfig with mint, weight, and ATA placeholder
-    ///
-    /// **Remaining Accounts (passed in order):**
-    /// For each asset: [mint_account, ata_account]
-    /// - mint_account: The SPL token mint (unchecked, validated against AssetConfig)
-    /// - ata_account: Vault's ATA for this mint (mut, will be initialized)
-    pub fn create_vault<'info>(
-        ctx: Context<'_, '_, '_, 'info, CreateVault<'info>>,
-        name: String,
-        assets: Vec<AssetConfig>,
-    ) -> Result<()> {
-        // Validation: Name length (for space and clarity)
-        require!(name.len() > 0 && name.len() <= 32, VaultError::InvalidName);
+#[event]
+pub struct DepositCommittedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+    pub commitment: [u8; 32],
+    pub slot: u64,
+}
 
-        // Validation: Asset count (at least 1, reasonable max for compute budget)
-        require!(
-            assets.len() > 0 && assets.len() <= 10,
-            VaultError::InvalidAssetCount
-        );
+#[event]
+pub struct DepositCommitmentCancelledEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+}
 
-        // Validation: Weights sum to exactly 100
-        let total_weight: u64 = assets.iter().map(|a| a.weight as u64).sum();
-        require!(total_weight == 100, VaultError::InvalidWeights);
+#[event]
+pub struct DepositPreparedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub expires_at: i64,
+}
 
-        // Validation: All weights are positive
-        require!(
-            assets.iter().all(|a| a.weight > 0),
-            VaultError::InvalidWeights
-        );
+#[event]
+pub struct DepositExecutedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+    pub assets_allocated: u8,
+    pub lamports: u64,
+    pub shares_minted: u64,
+    pub complete: bool,
+}
 
-        // Validation: Check we have correct number of remaining accounts
-        require!(
-            ctx.remaining_accounts.len() == assets.len() * 2,
-            VaultError::InvalidRemainingAccounts
-        );
+#[event]
+pub struct DepositTicketClosedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+    pub refunded: u64,
+    pub expired: bool,
+}
 
-        let vault = &mut ctx.accounts.vault;
-        vault.bump = ctx.bumps.vault;
-        vault.admin = ctx.accounts.admin.key();
-        vault.name = name.clone();
-        vault.vault_token_mint = ctx.accounts.vault_token_mint.key();
-        vault.assets = Vec::with_capacity(assets.len());
-        vault.marinade_strategy = None;
-        // Default to Switchboard for mainnet compatibility
-        vault.price_source = PriceSource::Switchboard;
-        vault.mock_oracle = None;
+#[event]
+pub struct DcaExecutedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub schedule: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub shares_minted: u64,
+    pub remaining: u64,
+}
 
-        // Create ATAs for each asset using remaining_accounts
-        // This approach is necessary because Anchor account constraints don't support
-        // variable-length account lists. Using remaining_accounts + manual validation
-        // is the standard Solana pattern for dynamic account sets.
-        for (i, asset_config) in assets.iter().enumerate() {
-            // Validate we have enough remaining accounts
-            require!(
-                ctx.remaining_accounts.len() > (i * 2 + 1),
-                VaultError::InvalidRemainingAccounts
-            );
+#[event]
+pub struct DcaScheduleCancelledEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub schedule: Pubkey,
+    pub user: Pubkey,
+    pub refunded: u64,
+}
 
-            let mint_account = &ctx.remaining_accounts[i * 2];
-            let ata_account = &ctx.remaining_accounts[i * 2 + 1];
+#[event]
+pub struct BatchedDepositQueuedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub epoch: u64,
+    pub user: Pubkey,
+    pub amount: u64,
+    /// Lamports queued in the batch so far
+    pub batch_lamports: u64,
+}
 
-            // Validate account types and ownership
-            require!(
-                mint_account.owner == &anchor_spl::token::ID,
-                VaultError::InvalidMint
-            );
+#[event]
+pub struct DepositBatchProcessedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub epoch: u64,
+    pub lamports: u64,
+    pub deposits: u32,
+    pub shares_minted: u64,
+    pub fee_lamports: u64,
+    pub tvl_usd: i64,
+}
 
-            // Validate mint matches expected mint from AssetConfig
-            require!(
-                mint_account.key() == asset_config.mint,
-                VaultError::InvalidMint
-            );
+#[event]
+pub struct BatchSharesClaimedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub user: Pubkey,
+    pub lamports: u64,
+    pub shares: u64,
+}
 
-            // Derive expected ATA address for security (prevent fake ATAs)
-            let expected_ata = anchor_spl::associated_token::get_associated_token_address(
-                &vault.key(),
-                &asset_config.mint,
-            );
-            require!(ata_account.key() == expected_ata, VaultError::InvalidATA);
+#[event]
+pub struct BatchedDepositCancelledEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub user: Pubkey,
+    pub refunded: u64,
+}
 
-            // Check ATA ownership only if account already exists
-            // Uninitialized accounts are owned by System Program, not ATA Program
-            if !ata_account.data_is_empty() {
-                require!(
-                    ata_account.owner == &spl_associated_token_account::id(),
-                    VaultError::InvalidATA
-                );
-            }
+#[event]
+pub struct BatchedWithdrawalQueuedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub epoch: u64,
+    pub user: Pubkey,
+    pub shares: u64,
+    /// Shares queued in the batch so far
+    pub batch_shares: u64,
+}
 
-            // Initialize ATA if it doesn't exist
-            // This is a CPI (Cross-Program Invocation) to the Associated Token Program
-            // We check if the account is empty (uninitialized) and create it if needed
-            if ata_account.data_is_empty() {
-                msg!("Initializing ATA for asset {}", asset_config.mint);
-                
-                // Create the ATA using CPI to the Associated Token Program
-                // This is the idiomatic way to create ATAs on Solana
-                let cpi_accounts = anchor_spl::associated_token::Create {
-                    payer: ctx.accounts.admin.to_account_info(),
-                    associated_token: ata_account.clone(),
-                    authority: vault.to_account_info(),
-                    mint: mint_account.clone(),
-                    system_program: ctx.accounts.system_program.to_account_info(),
-                    token_program: ctx.accounts.token_program.to_account_info(),
-                };
-                
-                let cpi_program = ctx.accounts.associated_token_program.to_account_info();
-                let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-                
-                anchor_spl::associated_token::create(cpi_ctx)?;
-                
-                msg!("ATA created successfully: {}", expected_ata);
-            } else {
-                msg!("ATA already exists: {}", expected_ata);
-            }
+#[event]
+pub struct WithdrawalBatchProcessedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub epoch: u64,
+    pub shares_burned: u64,
+    pub withdrawals: u32,
+    pub lamports: u64,
+    pub tvl_usd: i64,
+}
 
-            // Store asset configuration with actual ATA address
-            vault.assets.push(AssetConfig {
-                mint: asset_config.mint,
-                weight: asset_config.weight,
-                ata: expected_ata,
-            });
+#[event]
+pub struct BatchWithdrawalClaimedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub user: Pubkey,
+    pub shares: u64,
+    pub lamports: u64,
+    pub exit_fee: u64,
+    pub realized_pnl_usd: i64,
+}
 
-            msg!(
-                "Asset {}: mint={}, weight={}%, ata={}",
-                i,
-                asset_config.mint,
-                asset_config.weight,
-                expected_ata
-            );
-        }
+#[event]
+pub struct BatchedWithdrawalCancelledEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub batch: Pubkey,
+    pub user: Pubkey,
+    pub shares: u64,
+}
 
-        msg!("Vault '{}' created successfully", vault.name);
-        msg!("  Admin: {}", vault.admin);
-        msg!("  Share Mint: {}", vault.vault_token_mint);
-        msg!("  Assets: {}", vault.assets.len());
+#[event]
+pub struct AuthorizedParticipantEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub participant: Pubkey,
+    pub authorized: bool,
+}
 
-        // Emit creation event for indexers/off-chain tracking
-        emit!(VaultCreatedEvent {
-            vault: vault.key(),
-            admin: vault.admin,
-            name: vault.name.clone(),
-            vault_token_mint: vault.vault_token_mint,
-            num_assets: vault.assets.len() as u8,
-        });
+#[event]
+pub struct CreationUnitsEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub participant: Pubkey,
+    /// true for create_units, false for redeem_units
+    pub created: bool,
+    pub units: u64,
+    pub shares: u64,
+    /// In-kind amount of each asset, in vault.assets order
+    pub amounts: Vec<u64>,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct WindDownStartedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// close_vault is allowed from this time
+    pub deadline: i64,
+}
 
-    /// Deposit SOL into a multi-asset vault and receive proportional shares
-    /// This function handles the complete deposit flow with proper formulas
-    ///
-    /// **Parameters:**
-    /// - amount: Amount of SOL to deposit (in lamports, 9 decimals)
-    /// - name: Vault name for PDA derivation
-    ///
-    /// **Process:**
-    /// 1. Convert SOL deposit amount to USD using SOL price
-    /// 2. Calculate current vault TVL from existing balances
-    /// 3. Calculate share price (TVL / total_shares, or $1 if first deposit)
-    /// 4. Allocate SOL across vault assets based on weights
-    /// 5. Execute mock swaps to achieve target allocation
-    /// 6. Mint vault shares proportional to deposit value
-    pub fn deposit_multi_asset<'info>(
-        ctx: Context<'_, '_, '_, 'info, DepositMultiAsset<'info>>,
-        _name: String,
-        amount: u64,
-    ) -> Result<()> {
-        require!(amount > 0, VaultError::InvalidAmount);
-
-        let vault = &ctx.accounts.vault;
-        
-        // Validate remaining accounts: we need asset mints and vault ATAs
-        // If using MockOracle, we need one additional account (the oracle)
-        // If Marinade strategy is set, we need one more account (the strategy)
-        let mut expected_accounts = match vault.price_source {
-            PriceSource::MockOracle => vault.assets.len() * 2 + 1, // +1 for oracle
-            PriceSource::Switchboard => vault.assets.len() * 2,
-        };
-        
-        if vault.marinade_strategy.is_some() {
-            expected_accounts += 1;
-        }
-        
-        msg!(
-            "Remaining accounts validation: expected {}, got {}",
-            expected_accounts,
-            ctx.remaining_accounts.len()
-        );
-        
-        require!(
-            ctx.remaining_accounts.len() == expected_accounts,
-            VaultError::InvalidRemainingAccounts
-        );
+#[event]
+pub struct WindDownRedeemedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+    pub shares: u64,
+    /// In-kind amount of each asset, in vault.assets order
+    pub amounts: Vec<u64>,
+}
 
-        let sol_decimals = 9u8; // SOL has 9 decimals
+#[event]
+pub struct VaultClosedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// Receives the rent of the closed accounts
+    pub admin: Pubkey,
+}
 
-        // Fetch prices based on configured price source
-        let clock = &ctx.accounts.clock;
-        let current_time = clock.unix_timestamp;
+#[event]
+pub struct ShareMetadataSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
 
-        msg!("🔍 Fetching prices from {:?}...", vault.price_source);
+#[event]
+pub struct VaultMigratedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub from_version: u8,
+    pub to_version: u8,
+}
 
-        let (btc_normalized, eth_normalized, sol_normalized) = match vault.price_source {
-            PriceSource::Switchboard => {
-                // Use Switchboard feeds
-                msg!("📊 Reading Switchboard Oracle Quotes...");
-                
-                let btc_quote_data = &ctx.accounts.btc_quote.data.borrow();
-                let btc_norm = Vault::verify_oracle_quote(btc_quote_data, current_time)?;
-                
-                let eth_quote_data = &ctx.accounts.eth_quote.data.borrow();
-                let eth_norm = Vault::verify_oracle_quote(eth_quote_data, current_time)?;
-                
-                let sol_quote_data = &ctx.accounts.sol_quote.data.borrow();
-                let sol_norm = Vault::verify_oracle_quote(sol_quote_data, current_time)?;
-                
-                (btc_norm, eth_norm, sol_norm)
-            },
-            PriceSource::MockOracle => {
-                // Use mock oracle
-                msg!("🎭 Reading Mock Oracle prices...");
-                
-                require!(vault.mock_oracle.is_some(), VaultError::InvalidPrice);
-                let oracle_key = vault.mock_oracle.unwrap();
-                
-                // Find mock oracle in remaining accounts
-                let mock_oracle_account = ctx.remaining_accounts
-                    .iter()
-                    .find(|acc| acc.key() == oracle_key)
-                    .ok_or(VaultError::InvalidPrice)?;
-                
-                let oracle_data = mock_oracle_account.try_borrow_data()?;
-                let mock_oracle = MockPriceOracle::try_deserialize(&mut &oracle_data[..])?;
-                
-                // Validate prices are fresh (within last 5 minutes)
-                let price_age = current_time - mock_oracle.last_update;
-                require!(price_age < 300, VaultError::StaleQuote);
-                
-                // Convert mock oracle prices (already in micro-USD) to NormalizedPrice
-                let btc_norm = NormalizedPrice {
-                    price_usd: mock_oracle.btc_price,
-                    original_price: mock_oracle.btc_price / 1_000_000,
-                    expo: -6,
-                };
-                
-                let eth_norm = NormalizedPrice {
-                    price_usd: mock_oracle.eth_price,
-                    original_price: mock_oracle.eth_price / 1_000_000,
-                    expo: -6,
-                };
-                
-                let sol_norm = NormalizedPrice {
-                    price_usd: mock_oracle.sol_price,
-                    original_price: mock_oracle.sol_price / 1_000_000,
-                    expo: -6,
-                };
-                
-                (btc_norm, eth_norm, sol_norm)
-            },
-        };
+#[event]
+pub struct VaultRegisteredEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub index: u64,
+}
 
-        msg!("BTC Price: ${} (expo: {})", btc_normalized.original_price, btc_normalized.expo);
-        msg!("ETH Price: ${} (expo: {})", eth_normalized.original_price, eth_normalized.expo);
-        msg!("SOL Price: ${} (expo: {})", sol_normalized.original_price, sol_normalized.expo);
+#[event]
+pub struct VaultMetadataSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub category: String,
+    pub risk_level: u8,
+}
 
-        msg!(
-            "📊 Prices - BTC: ${}, ETH: ${}, SOL: ${}",
-            btc_normalized.price_usd,
-            eth_normalized.price_usd,
-            sol_normalized.price_usd
-        );
+#[event]
+pub struct LookupTableCreatedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub lookup_table: Pubkey,
+}
 
-        // STEP 1: Transfer SOL from user to vault
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.user.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
-        };
+#[event]
+pub struct LookupTableExtendedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub added: u16,
+    pub total: u16,
+}
 
-        let cpi_program = ctx.accounts.system_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        transfer(cpi_ctx, amount)?;
-        msg!("✅ Transferred {} lamports from user to vault", amount);
+#[event]
+pub struct NavSnapshotEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub timestamp: i64,
+    pub tvl_usd: i64,
+    pub total_shares: u64,
+    pub share_price: i64,
+    /// Rolling tracking error after this snapshot, in bps (see tracking.rs)
+    pub tracking_error_bps: u32,
+}
 
-        // STEP 2: Calculate deposit value in USD
-        let deposit_usd_micro = sol_normalized.tokens_to_usd(amount, sol_decimals);
-        msg!("Deposit: {} SOL = ${} USD", amount, deposit_usd_micro);
+#[event]
+pub struct CircuitBreakerEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub restricted: bool,
+    pub checkpoint: [i64; 3],
+    pub prices: [i64; 3],
+}
 
-        // STEP 3: Calculate current vault TVL from asset balances in ATAs
-        msg!("Calculating vault TVL...");
-        
-        // Get asset balances from remaining_accounts (vault ATAs)
-        let mut btc_balance = 0u64;
-        let mut eth_balance = 0u64;
-        let mut sol_balance = 0u64;
-
-        // IMPORTANT: For SOL, we need to check BOTH:
-        // 1. SPL token balance in ATA (if using wrapped SOL tokens)
-        // 2. Native SOL in vault PDA's lamports (for deposits that don't wrap)
-        
-        // First, check native SOL balance in vault PDA
-        let vault_lamports = ctx.accounts.vault.to_account_info().lamports();
-        let vault_data_len = ctx.accounts.vault.to_account_info().data_len();
-        let rent_exempt_minimum = ctx.accounts.rent.minimum_balance(vault_data_len);
-        // Subtract rent-exempt reserve to get actual deposited SOL
-        let native_sol_balance = vault_lamports.saturating_sub(rent_exempt_minimum);
-        msg!("  Native SOL in vault PDA: {} lamports (total: {}, rent: {})", native_sol_balance, vault_lamports, rent_exempt_minimum);
+#[event]
+pub struct GuardianSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub guardian: Option<Pubkey>,
+    pub may_pause_rebalancing: bool,
+}
 
-        for (i, asset) in vault.assets.iter().enumerate() {
-            let ata_account_info = &ctx.remaining_accounts[i * 2 + 1];
-            
-            // Parse the ATA to get balance
-            if ata_account_info.data_is_empty() {
-                msg!("  Asset {} ATA is empty (balance = 0)", asset.mint);
-                continue;
-            }
+/// Emitted when the guardian pauses rebalancing or the governed authority
+/// resumes it
+#[event]
+pub struct RebalancingPauseEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub paused: bool,
+}
 
-            // Deserialize token account to get amount
-            let ata_data = ata_account_info.try_borrow_data()?;
-            let ata = TokenAccount::try_deserialize(&mut &ata_data[..])?;
-            
-            msg!("Asset {} (weight {}%): {} tokens in ATA", asset.mint, asset.weight, ata.amount);
-
-            // Map balance to correct asset based on weight
-            // This is a simplified approach - in production you'd match by mint address
-            match asset.weight {
-                40 => btc_balance = ata.amount, // BTC gets 40%
-                30 if eth_balance == 0 => eth_balance = ata.amount, // First 30% is ETH
-                30 => {
-                    // For SOL: Use SPL token balance OR native balance (whichever is greater)
-                    // This handles both wrapped SOL tokens and native SOL deposits
-                    sol_balance = if ata.amount > 0 {
-                        ata.amount // Using SPL token wSOL
-                    } else {
-                        native_sol_balance // Using native SOL
-                    };
-                    msg!("  → Using SOL balance: {} (native + SPL)", sol_balance);
-                },
-                _ => {}
-            }
-        }
+#[event]
+pub struct SuccessorSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub successor: Option<Pubkey>,
+    pub soft_closed: bool,
+}
 
-        let current_tvl = Vault::calculate_tvl_from_balances(
-            btc_balance,
-            eth_balance,
-            sol_balance,
-            &btc_normalized,
-            &eth_normalized,
-            &sol_normalized,
-        )?;
+#[event]
+pub struct PositionMigratedEvent {
+    pub from_vault: Pubkey,
+    pub seq: u64,
+    pub to_vault: Pubkey,
+    pub user: Pubkey,
+    pub shares_burned: u64,
+    pub lamports_moved: u64,
+    pub shares_minted: u64,
+}
 
-        msg!("Current TVL: ${} USD", current_tvl);
+/// Emitted once prices and balances are loaded at the start of a rebalance
+#[event]
+pub struct RebalanceStartedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub authority: Pubkey,
+    pub tvl_usd: i64,
+    /// Current weight of each asset in basis points, in vault.assets order
+    pub current_weights_bps: Vec<u16>,
+    pub timestamp: i64,
+}
 
-        // STEP 4: Calculate share price
-        let total_shares = ctx.accounts.vault_token_mint.supply;
-        let share_price = Vault::calculate_share_price(current_tvl, total_shares)?;
-        
-        msg!(
-            "Share Price: ${} USD (TVL: ${}, Supply: {} shares)",
-            share_price,
-            current_tvl,
-            total_shares
-        );
+/// Emitted for every swap leg executed during a rebalance
+#[event]
+pub struct RebalanceSwapEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Oracle prices used for the swap (micro-USD)
+    pub from_price: i64,
+    pub to_price: i64,
+}
 
-        // STEP 5: Calculate shares to mint
-        let shares_to_mint = Vault::calculate_shares_to_mint(deposit_usd_micro, share_price)?;
-        msg!("🎁 Shares to mint: {} shares", shares_to_mint);
+/// Emitted when a rebalance finishes (also when no swaps were needed)
+#[event]
+pub struct SwapExecutedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
 
-        // STEP 6: Transfer SOL from user to vault
-        msg!("💸 Transferring {} SOL from user to vault...", amount);
-        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-            &ctx.accounts.user.key(),
-            &ctx.accounts.vault.key(),
-            amount,
-        );
-        anchor_lang::solana_program::program::invoke(
-            &transfer_ix,
-            &[
-                ctx.accounts.user.to_account_info(),
-                ctx.accounts.vault.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
-        msg!("✅ SOL transferred successfully");
+#[event]
+pub struct RebalanceCompletedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub swap_count: u8,
+    pub tvl_usd: i64,
+    /// Post-rebalance weight of each asset in basis points, in vault.assets order
+    pub new_weights_bps: Vec<u16>,
+    /// Legs skipped as dust (below the vault's minimum swap or an asset's
+    /// dust threshold) and their combined value in micro-USD
+    pub dust_legs_skipped: u8,
+    pub dust_usd_skipped: i64,
+    pub timestamp: i64,
+}
 
-        // STEP 7: Allocate SOL across vault assets using MockSwap
-        msg!("🔄 Allocating deposit across vault assets...");
+/// Emitted when SOL is delegated to the vault's staking strategy
+#[event]
+pub struct StrategyStakedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub strategy: Pubkey,
+    pub lamports: u64,
+}
 
-        let mut sol_to_stake: Option<u64> = None;
+/// Emitted when a withdrawal or divest_from_strategy unstakes from the
+/// vault's staking strategy
+#[event]
+pub struct StrategyUnstakedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub strategy: Pubkey,
+    pub msol_amount: u64,
+    pub lamports_received: u64,
+    /// Lamports received above the proportional amount originally staked
+    pub yield_lamports: u64,
+}
 
-        for (i, asset) in vault.assets.iter().enumerate() {
-            let usd_allocation = (deposit_usd_micro * asset.weight as i64) / 100;
-            let sol_amount_for_asset = (amount as i64 * asset.weight as i64 / 100) as u64;
-            
-            // Get the decimals, price, and whether to swap for this asset
-            let (decimals, price, asset_name) = match asset.weight {
-                40 => (8u8, &btc_normalized, "BTC"),  // BTC - needs swap
-                30 if i == 1 => (18u8, &eth_normalized, "ETH"), // ETH - needs swap
-                30 => {
-                    // Store SOL amount for Marinade staking
-                    sol_to_stake = Some(sol_amount_for_asset);
-                    (9u8, &sol_normalized, "SOL")
-                },
-                _ => continue,
-            };
+/// Emitted when sync_strategy_value refreshes the vault's StrategyValue
+#[event]
+pub struct StrategyValueSyncedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub strategy: Pubkey,
+    /// Lamports the strategy position is worth, staking yield included
+    pub value_lamports: u64,
+    pub updated_at: i64,
+}
 
-            // Calculate token amount using MockSwap for BTC and ETH
-            let token_amount = if asset_name == "SOL" {
-                // For SOL, no swap needed - amount will be staked via Marinade
-                sol_amount_for_asset
-            } else {
-                // For BTC and ETH, use MockSwap to calculate swap output
-                MockSwap::calculate_swap_output(
-                    sol_amount_for_asset,
-                    sol_normalized.original_price,
-                    sol_normalized.expo,
-                    price.original_price,
-                    price.expo,
-                    9, // SOL decimals
-                    decimals, // Target asset decimals
-                )?
-            };
+/// Emitted when the vault keeps an exit fee on withdrawal
+#[event]
+pub struct FeeCollectedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub user: Pubkey,
+    pub fee_bps: u16,
+    pub fee_lamports: u64,
+}
 
-            msg!(
-                "  ✓ Asset {} ({}%): ${} USD = {} {} (from {} SOL)",
-                asset.mint,
-                asset.weight,
-                usd_allocation,
-                token_amount,
-                asset_name,
-                sol_amount_for_asset
-            );
+/// Emitted when a referred deposit credits the referrer's balance
+#[event]
+pub struct ReferralFeeAccruedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub referrer: Pubkey,
+    pub user: Pubkey,
+    pub lamports: u64,
+}
 
-            // NOTE: For devnet, MockSwap only calculates amounts
-            // In production with Jupiter, actual swaps would execute here:
-            // - For BTC/ETH: Execute Jupiter CPI (SOL -> BTC/ETH)
-            // - For SOL: Delegate to Marinade strategy
-        }
+/// Emitted when the treasury or the vault creator claims its share of vault fees
+#[event]
+pub struct FeesClaimedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub recipient: Pubkey,
+    /// true for the creator's share, false for the treasury's
+    pub to_creator: bool,
+    pub lamports: u64,
+}
 
-        // Delegate SOL portion to Marinade strategy (if configured)
-        if let (Some(strategy_key), Some(stake_amount)) = (vault.marinade_strategy, sol_to_stake) {
-            msg!("🌊 Marinade strategy configured!");
-            msg!("   Delegating {} lamports (30%) to Marinade...", stake_amount);
-            
-            // Find the strategy account in remaining_accounts
-            let strategy_account_info = ctx.remaining_accounts.iter()
-                .find(|acc| acc.key() == strategy_key)
-                .ok_or(VaultError::MarinadeError)?;
-            
-            // Build CPI context for marinade_strategy::stake
-            let cpi_accounts = marinade_strategy::cpi::accounts::Stake {
-                strategy_account: strategy_account_info.clone(),
-                vault: ctx.accounts.vault.to_account_info(),
-                payer: ctx.accounts.user.to_account_info(), // User must sign as payer
-                marinade_state: ctx.accounts.marinade_state.to_account_info(),
-                reserve_pda: ctx.accounts.reserve_pda.to_account_info(),
-                msol_mint: ctx.accounts.msol_mint.to_account_info(),
-                msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
-                msol_mint_authority: ctx.accounts.msol_mint_authority.to_account_info(),
-                liq_pool_sol_leg_pda: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
-                liq_pool_msol_leg: ctx.accounts.liq_pool_msol_leg.to_account_info(),
-                liq_pool_msol_leg_authority: ctx.accounts.liq_pool_msol_leg_authority.to_account_info(),
-                marinade_program: ctx.accounts.marinade_program.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
-                token_program: ctx.accounts.token_program.to_account_info(),
-            };
-            
-            // Vault PDA signs the CPI call
-            let vault_seeds = &[
-                b"vault".as_ref(),
-                vault.admin.as_ref(),
-                vault.name.as_bytes(),
-                &[vault.bump],
-            ];
-            let signer_seeds = &[&vault_seeds[..]];
-            
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.marinade_strategy_program.to_account_info(),
-                cpi_accounts,
-                signer_seeds,
-            );
-            
-            // Execute CPI call to marinade_strategy::stake
-            marinade_strategy::cpi::stake(cpi_ctx, stake_amount)?;
-            
-            msg!("✅Successfully delegated {} lamports to Marinade!", stake_amount);
-            
-        } else if sol_to_stake.is_some() {
-            msg!("No Marinade strategy configured - SOL will remain in vault");
-        }
+/// Emitted when tokens outside the composition are swept to the treasury
+#[event]
+pub struct StrayTokenSweptEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub mint: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+}
 
-        // STEP 8: Mint shares to user
-        msg!("🪙 Minting {} shares to user...", shares_to_mint);
+/// Emitted when an unused vault token account is closed for its rent
+#[event]
+pub struct AssetAtaClosedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    /// Rent returned to the vault admin
+    pub lamports: u64,
+}
 
-        let vault_seeds = &[
-            b"vault".as_ref(),
-            vault.admin.as_ref(),
-            vault.name.as_bytes(),
-            &[vault.bump],
-        ];
-        let signer_seeds = &[&vault_seeds[..]];
+#[event]
+pub struct ReferralFeesClaimedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub referrer: Pubkey,
+    pub lamports: u64,
+}
 
-        let cpi_accounts = anchor_spl::token::MintTo {
-            mint: ctx.accounts.vault_token_mint.to_account_info(),
-            to: ctx.accounts.user_shares_ata.to_account_info(),
-            authority: ctx.accounts.vault.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        
-        anchor_spl::token::mint_to(cpi_ctx, shares_to_mint)?;
+/// Emitted when the vault's basket composition is set
+#[event]
+pub struct SharesStakedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub owner: Pubkey,
+    pub shares: u64,
+    pub total_staked: u64,
+}
 
-        // STEP 9: Calculate new vault state
-        let new_tvl = current_tvl + deposit_usd_micro;
-        let new_total_shares = total_shares + shares_to_mint;
-        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+#[event]
+pub struct SharesUnstakedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub owner: Pubkey,
+    pub shares: u64,
+    pub total_staked: u64,
+}
 
-        msg!("✅ Deposit Complete!");
-        msg!("   New TVL: ${} USD", new_tvl);
-        msg!("   New Total Shares: {}", new_total_shares);
-        msg!("   New Share Price: ${} USD", new_share_price);
+#[event]
+pub struct StakingRewardsDistributedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub lamports: u64,
+    pub total_staked: u64,
+}
 
-        // Emit deposit event
-        emit!(DepositEvent {
-            vault: vault.key(),
-            user: ctx.accounts.user.key(),
-            deposit_mint: anchor_lang::solana_program::system_program::ID, // SOL
-            amount_deposited: amount,
-            shares_minted: shares_to_mint,
-            tvl_usd: new_tvl,
-        });
+#[event]
+pub struct StakingRewardsClaimedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub owner: Pubkey,
+    pub lamports: u64,
+}
 
-        Ok(())
-    }
+#[event]
+pub struct CompositionChangedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub mints: Vec<Pubkey>,
+    pub weights: Vec<u8>,
+}
 
-    /// Withdraw from multi-asset vault by burning shares
-    /// This function implements proportional withdrawal across all vault assets
+#[event]
+pub struct VaultTemplateAppliedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// VaultTemplate the vault was created from
+    pub template: Pubkey,
+}
+
+#[event]
+pub struct VaultAssetAddedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub index: u8,
+    pub mint: Pubkey,
+    pub weight: u8,
+    pub ata: Pubkey,
+}
+
+#[event]
+pub struct MarketCapIndexConfiguredEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub enabled: bool,
+    pub supply_authority: Option<Pubkey>,
+    pub recompute_interval_secs: i64,
+    pub max_weight_change: u8,
+}
+
+#[event]
+pub struct AssetSuppliesSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub supplies: Vec<u64>,
+}
+
+#[event]
+pub struct WeightsRecomputedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// Market-cap weights before the per-run bound
+    pub target_weights: Vec<u8>,
+    /// Weights now in effect
+    pub weights: Vec<u8>,
+}
+
+#[event]
+pub struct CompositionScheduleSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// Number of steps (0 = schedule cleared)
+    pub steps: u8,
+    /// Timestamp of the last step
+    pub final_at: i64,
+}
+
+#[event]
+pub struct AssetOraclesConfiguredEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// Feed of each asset, in vault.assets order
+    pub feeds: Vec<PriceFeed>,
+    /// Feed of SOL
+    pub sol_feed: PriceFeed,
+}
+
+#[event]
+pub struct LstBasketConfiguredEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// Rate source of each asset (empty = LST pricing off)
+    pub pools: Vec<LstPool>,
+    /// Lamports per token of each asset, scaled by 10^9
+    pub rates: Vec<u64>,
+}
+
+#[event]
+pub struct LstRatesRefreshedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    /// Lamports per token of each asset, scaled by 10^9
+    pub rates: Vec<u64>,
+}
+
+#[event]
+pub struct GovernanceSetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub governance: Option<Pubkey>,
+    pub governance_mode: bool,
+}
+
+/// Emitted when price source, feeds or TWAP window change
+#[event]
+pub struct PriceSourceChangedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub price_source: PriceSource,
+    pub mock_oracle: Option<Pubkey>,
+    pub feed_count: u8,
+    pub twap_window_secs: u32,
+}
+
+/// Emitted when deposits pause or resume (soft close, circuit breaker or
+/// guardian)
+#[event]
+pub struct VaultPauseEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub paused: bool,
+    pub soft_closed: bool,
+    pub restricted: bool,
+    pub guardian_paused: bool,
+}
+
+#[event]
+pub struct StrategySetEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub strategy: Pubkey,
+}
+
+#[event]
+pub struct StrategyRemovedEvent {
+    pub vault: Pubkey,
+    pub seq: u64,
+    pub strategy: Option<Pubkey>,
+}
+
+declare_id!("Faiwct1BxfrV1w5xYs8Y55mQ4VJXPGx1qPBZJnw5p7pR");
+
+#[cfg_attr(feature = "ephemeral-rollups", ephemeral)]
+#[program(heap = 262144)] // 256KB heap for CPI operations with large instruction data
+pub mod vault {
+  use super::*;    /// Create a new multi-asset vault with custom composition
+    ///
+    /// This is the primary initialization instruction for creating vaults.
+    /// Each vault is a unique PDA derived from admin + name, allowing multiple
+    /// vaults per admin with different compositions.
+    ///
+    /// **Key Design Decisions:**
+    /// 1. **PDA Seeds**: [b"vault", admin, name] - enables multiple vaults per admin
+    /// 2. **Dynamic Space**: Calculated from name length and asset count at runtime
+    /// 3. **Share Mint**: Each vault has unique SPL token for shares (9 decimals for precision)
+    /// 4. **Asset ATAs**: Created via remaining_accounts to handle variable asset count
+    ///
+    /// **Solana Best Practices:**
+    /// - Uses init constraint for atomic account creation with rent exemption
+    /// - Vault PDA is mint authority for shares (secure share issuance)
+    /// - ATAs use canonical Associated Token Program addresses
+    /// - Validates composition (weights sum to 100) before creation
     ///
     /// **Parameters:**
-    /// - shares: Amount of vault shares to burn
-    /// - name: Vault name for PDA derivation
+    /// - name: Unique identifier (max 32 bytes for space efficiency)
+    /// - assets: Vec of AssetConfig with mint, weight, and ATA placeholder
+    /// - soulbound: Make shares non-transferable (see soulbound.rs); fixed for
+    ///   the vault's lifetime
     ///
-    /// **Process:**
-    /// 1. Calculate withdrawal percentage (shares_to_burn / total_shares)
-    /// 2. For each asset, calculate proportional amount to withdraw
-    /// 3. Calculate total withdrawal value in USD
-    /// 4. Transfer SOL back to user (30% of vault is in SOL)
-    /// 5. For BTC/ETH: Calculate swap to SOL and add to user's withdrawal
-    /// 6. Burn user's shares
-    /// 7. Update vault state
-    pub fn withdraw_multi_asset<'info>(
-        ctx: Context<'_, '_, '_, 'info, WithdrawMultiAsset<'info>>,
+    /// **Remaining Accounts (passed in order):**
+    /// For each asset: [mint_account, ata_account]
+    /// - mint_account: The SPL Token or Token-2022 mint (unchecked, validated against AssetConfig)
+    /// - ata_account: Vault's ATA for this mint under the mint's token program (mut, will be initialized)
+    /// Token-2022 mints also need `token_2022_program`.
+    pub fn create_vault<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateVault<'info>>,
         name: String,
-        shares: u64,
+        assets: Vec<AssetConfig>,
+        soulbound: bool,
     ) -> Result<()> {
-        require!(shares > 0, VaultError::InvalidAmount);
+        // Validation: Name length (for space and clarity)
+        require!(!name.is_empty() && name.len() <= MAX_NAME_LEN, VaultError::InvalidName);
 
-        let vault = &ctx.accounts.vault;
-        let total_shares = ctx.accounts.vault_token_mint.supply;
+        // Validation: Asset count, positive weights summing to 100, no duplicate mints
+        validate_composition(&assets)?;
 
-        require!(shares <= total_shares, VaultError::InsufficientShares);
+        // Validation: Check we have correct number of remaining accounts
         require!(
-            ctx.accounts.user_shares_ata.amount >= shares,
-            VaultError::InsufficientShares
+            ctx.remaining_accounts.len() == assets.len() * 2,
+            VaultError::InvalidRemainingAccounts
         );
 
-        // Validate remaining accounts: we need asset mints and vault ATAs
-        // If using MockOracle, we need one additional account (the oracle)
-        // If Marinade strategy is set, we need one more account (the strategy)
-        let mut expected_accounts = match vault.price_source {
-            PriceSource::MockOracle => vault.assets.len() * 2 + 1, // +1 for oracle
-            PriceSource::Switchboard => vault.assets.len() * 2,
+        // BTC/ETH slots must hold approved wrapped mints once the protocol keeps a whitelist
+        let whitelist = load_asset_whitelist(&ctx.accounts.asset_whitelist)?;
+
+        // Soulbound vaults issue shares from a non-transferable Token-2022 mint
+        let admin_key = ctx.accounts.admin.key();
+        soulbound::create_share_mint(
+            soulbound,
+            &ctx.accounts.vault_token_mint,
+            &[b"vault_mint", admin_key.as_ref(), name.as_bytes(), &[ctx.bumps.vault_token_mint]],
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.admin,
+            &ctx.accounts.token_program,
+            ctx.accounts.token_2022_program.as_ref().map(|p| p.as_ref()),
+            &ctx.accounts.system_program,
+        )?;
+
+        // Create ATAs for each asset using remaining_accounts
+        // This approach is necessary because Anchor account constraints don't support
+        // variable-length account lists. Using remaining_accounts + manual validation
+        // is the standard Solana pattern for dynamic account sets.
+        let programs = AssetAtaPrograms {
+            token_program: ctx.accounts.token_program.as_ref(),
+            token_2022_program: ctx.accounts.token_2022_program.as_ref().map(|p| p.as_ref()),
+            associated_token_program: ctx.accounts.associated_token_program.as_ref(),
+            system_program: ctx.accounts.system_program.as_ref(),
         };
-        
-        if vault.marinade_strategy.is_some() {
-            expected_accounts += 1;
+        let mut stored_assets = Vec::with_capacity(assets.len());
+        for (i, asset_config) in assets.iter().enumerate() {
+            stored_assets.push(open_asset_ata(
+                i,
+                asset_config,
+                &ctx.remaining_accounts[i * 2],
+                &ctx.remaining_accounts[i * 2 + 1],
+                &ctx.accounts.vault.to_account_info(),
+                &ctx.accounts.admin.to_account_info(),
+                whitelist.as_ref(),
+                &programs,
+            )?);
         }
-        
-        msg!(
-            "Withdraw remaining accounts validation: expected {}, got {}",
-            expected_accounts,
-            ctx.remaining_accounts.len()
-        );
-        
-        require!(
-            ctx.remaining_accounts.len() == expected_accounts,
-            VaultError::InvalidRemainingAccounts
-        );
 
-        msg!("🔓 Starting withdrawal of {} shares...", shares);
+        // The ATA CPIs above take the vault account, so its data is only
+        // borrowed once they are done
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_init()?;
+        init_vault_state(
+            &mut vault,
+            ctx.bumps.vault,
+            ctx.accounts.admin.key(),
+            &name,
+            ctx.accounts.vault_token_mint.key(),
+        )?;
+        vault.set_soulbound(soulbound);
+        vault.set_assets(&stored_assets)?;
 
-        // Fetch prices based on configured price source
-        let clock = &ctx.accounts.clock;
-        let current_time = clock.unix_timestamp;
+        // Append to the on-chain vault registry
+        let registry = &mut ctx.accounts.vault_registry;
+        registry.bump = ctx.bumps.vault_registry;
+        let registration = &mut ctx.accounts.vault_registration;
+        registration.register(registry, vault_key, &vault, ctx.bumps.vault_registration)?;
 
-        msg!("🔍 Fetching prices from {:?}...", vault.price_source);
+        msg!("Vault '{}' created successfully", vault.name());
+        msg!("  Admin: {}", vault.admin);
+        msg!("  Share Mint: {}{}", vault.vault_token_mint, if soulbound { " (soulbound)" } else { "" });
+        msg!("  Assets: {}", vault.assets().len());
 
-        let (btc_normalized, eth_normalized, sol_normalized) = match vault.price_source {
-            PriceSource::Switchboard => {
-                // Use Switchboard feeds
-                let btc_quote_data = &ctx.accounts.btc_quote.data.borrow();
-                let btc_norm = Vault::verify_oracle_quote(btc_quote_data, current_time)?;
-                
-                let eth_quote_data = &ctx.accounts.eth_quote.data.borrow();
-                let eth_norm = Vault::verify_oracle_quote(eth_quote_data, current_time)?;
-                
-                let sol_quote_data = &ctx.accounts.sol_quote.data.borrow();
-                let sol_norm = Vault::verify_oracle_quote(sol_quote_data, current_time)?;
-                
-                (btc_norm, eth_norm, sol_norm)
-            },
-            PriceSource::MockOracle => {
-                require!(vault.mock_oracle.is_some(), VaultError::InvalidPrice);
-                let oracle_key = vault.mock_oracle.unwrap();
-                
-                let mock_oracle_account = ctx.remaining_accounts
-                    .iter()
-                    .find(|acc| acc.key() == oracle_key)
-                    .ok_or(VaultError::InvalidPrice)?;
-                
-                let oracle_data = mock_oracle_account.try_borrow_data()?;
-                let mock_oracle = MockPriceOracle::try_deserialize(&mut &oracle_data[..])?;
-                
-                let price_age = current_time - mock_oracle.last_update;
-                require!(price_age < 300, VaultError::StaleQuote);
-                
-                let btc_norm = NormalizedPrice {
-                    price_usd: mock_oracle.btc_price,
-                    original_price: mock_oracle.btc_price / 1_000_000,
-                    expo: -6,
-                };
-                
-                let eth_norm = NormalizedPrice {
-                    price_usd: mock_oracle.eth_price,
-                    original_price: mock_oracle.eth_price / 1_000_000,
-                    expo: -6,
-                };
-                
-                let sol_norm = NormalizedPrice {
-                    price_usd: mock_oracle.sol_price,
-                    original_price: mock_oracle.sol_price / 1_000_000,
-                    expo: -6,
-                };
-                
-                (btc_norm, eth_norm, sol_norm)
-            },
+        // Emit creation event for indexers/off-chain tracking
+        emit_cpi!(VaultCreatedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            admin: vault.admin,
+            name: vault.name().to_string(),
+            vault_token_mint: vault.vault_token_mint,
+            num_assets: vault.assets().len() as u8,
+            soulbound,
+        });
+        emit_cpi!(CompositionChangedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mints: vault.assets().iter().map(|a| a.mint).collect(),
+            weights: vault.assets().iter().map(|a| a.weight).collect(),
+        });
+        emit_cpi!(VaultRegisteredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            index: registration.index,
+        });
+
+        Ok(())
+    }
+
+    /// Create a vault with an empty composition, to be filled by add_vault_asset
+    ///
+    /// create_vault opens every asset's ATA in one instruction, which runs out of
+    /// compute and account space for large baskets. init_vault only sets up the
+    /// vault, its share mint and its registry entry; the vault stays unfinalized
+    /// (and so closed to deposits) until finalize_vault checks the composition,
+    /// so nothing can be deposited into a half-built basket.
+    pub fn init_vault(ctx: Context<InitVault>, name: String, soulbound: bool) -> Result<()> {
+        require!(!name.is_empty() && name.len() <= MAX_NAME_LEN, VaultError::InvalidName);
+
+        let admin_key = ctx.accounts.admin.key();
+        soulbound::create_share_mint(
+            soulbound,
+            &ctx.accounts.vault_token_mint,
+            &[b"vault_mint", admin_key.as_ref(), name.as_bytes(), &[ctx.bumps.vault_token_mint]],
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.admin,
+            &ctx.accounts.token_program,
+            ctx.accounts.token_2022_program.as_ref().map(|p| p.as_ref()),
+            &ctx.accounts.system_program,
+        )?;
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_init()?;
+        init_vault_state(
+            &mut vault,
+            ctx.bumps.vault,
+            ctx.accounts.admin.key(),
+            &name,
+            ctx.accounts.vault_token_mint.key(),
+        )?;
+        vault.set_soulbound(soulbound);
+        vault.set_finalized(false);
+
+        let registry = &mut ctx.accounts.vault_registry;
+        registry.bump = ctx.bumps.vault_registry;
+        let registration = &mut ctx.accounts.vault_registration;
+        registration.register(registry, vault_key, &vault, ctx.bumps.vault_registration)?;
+
+        msg!("Vault '{}' initialized; add its assets, then finalize it", vault.name());
+        msg!("  Share Mint: {}{}", vault.vault_token_mint, if soulbound { " (soulbound)" } else { "" });
+
+        emit_cpi!(VaultCreatedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            admin: vault.admin,
+            name: vault.name().to_string(),
+            vault_token_mint: vault.vault_token_mint,
+            num_assets: 0,
+            soulbound,
+        });
+        emit_cpi!(VaultRegisteredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            index: registration.index,
+        });
+
+        Ok(())
+    }
+
+    /// Append one asset to a vault from init_vault and open the vault's ATA for it
+    /// (only before finalize_vault; the running weight total may not pass 100)
+    pub fn add_vault_asset<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddVaultAsset<'info>>,
+        _name: String,
+        asset: AssetConfig,
+    ) -> Result<()> {
+        let vault = ctx.accounts.vault.load()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require_unfinalized(&vault)?;
+        require!(vault.assets().len() < MAX_ASSETS, VaultError::InvalidAssetCount);
+        require!(asset.weight > 0, VaultError::InvalidWeights);
+        let total_weight: u64 = vault.assets().iter().map(|a| a.weight as u64).sum::<u64>() + asset.weight as u64;
+        require!(total_weight <= 100, VaultError::InvalidWeights);
+        require!(vault.get_asset_by_mint(&asset.mint).is_none(), VaultError::DuplicateAsset);
+
+        let whitelist = load_asset_whitelist(&ctx.accounts.asset_whitelist)?;
+        let programs = AssetAtaPrograms {
+            token_program: ctx.accounts.token_program.as_ref(),
+            token_2022_program: ctx.accounts.token_2022_program.as_ref().map(|p| p.as_ref()),
+            associated_token_program: ctx.accounts.associated_token_program.as_ref(),
+            system_program: ctx.accounts.system_program.as_ref(),
         };
+        let index = vault.assets().len();
+        // The ATA is created with the vault as its owner
+        drop(vault);
+        let stored = open_asset_ata(
+            index,
+            &asset,
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.vault_ata.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            whitelist.as_ref(),
+            &programs,
+        )?;
 
-        msg!(
-            "📊 Prices - BTC: ${}, ETH: ${}, SOL: ${}",
-            btc_normalized.price_usd,
-            eth_normalized.price_usd,
-            sol_normalized.price_usd
-        );
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let mut assets = vault.assets().to_vec();
+        assets.push(stored);
+        vault.set_assets(&assets)?;
+
+        emit_cpi!(VaultAssetAddedEvent {
+            vault: ctx.accounts.vault.key(),
+            seq: vault.next_event_seq(),
+            index: index as u8,
+            mint: stored.mint,
+            weight: stored.weight,
+            ata: stored.ata,
+        });
 
-        // STEP 1: Calculate withdrawal percentage
-        // Formula: Withdrawal_Percentage = Shares_to_Burn ÷ Total_Outstanding_Shares
-        let withdrawal_percentage = (shares as u128 * 1_000_000) / (total_shares as u128); // Scale by 1M for precision
-        msg!(
-            "📊 Withdrawal percentage: {}% ({} / {} shares)",
-            (withdrawal_percentage * 100) / 1_000_000,
-            shares,
-            total_shares
-        );
+        Ok(())
+    }
 
-        // STEP 2: Calculate proportional asset amounts and total withdrawal value
-        let mut total_withdrawal_value_usd = 0i64;
-        let mut total_sol_to_return = 0u64;
-        let mut sol_from_native = 0u64;
-        let mut sol_from_marinade = 0u64;
+    /// Check that the weights added since init_vault sum to 100 and open the
+    /// vault for deposits
+    pub fn finalize_vault(ctx: Context<FinalizeVault>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
 
-        // First, check native SOL balance in vault PDA
-        let vault_lamports = ctx.accounts.vault.to_account_info().lamports();
-        let vault_data_len = ctx.accounts.vault.to_account_info().data_len();
-        let rent_exempt_minimum = ctx.accounts.rent.minimum_balance(vault_data_len);
-        let native_sol_balance = vault_lamports.saturating_sub(rent_exempt_minimum);
-        msg!("  Native SOL in vault PDA: {} lamports", native_sol_balance);
-        
-        // Check Marinade strategy staked value
-        let mut marinade_sol_value = 0u64;
-        if let Some(strategy_key) = vault.marinade_strategy {
-            let expected_strategy_index = match vault.price_source {
-                PriceSource::MockOracle => vault.assets.len() * 2 + 1,
-                PriceSource::Switchboard => vault.assets.len() * 2,
-            };
-            
-            if ctx.remaining_accounts.len() > expected_strategy_index {
-                let strategy_account_info = &ctx.remaining_accounts[expected_strategy_index];
-                if strategy_account_info.key() == strategy_key {
-                    // Read strategy state to get mSOL balance
-                    // Note: In full implementation, convert mSOL to SOL using Marinade exchange rate
-                    // For now, we'll use the total_staked value which tracks original deposit
-                    msg!("  Marinade strategy detected - including staked SOL in TVL");
-                    // TODO: Parse strategy account and get actual mSOL value with yield
-                }
-            }
-        }
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require_unfinalized(&vault)?;
+        vault.validate_weights()?;
 
-        for (i, asset) in vault.assets.iter().enumerate() {
-            let ata_account_info = &ctx.remaining_accounts[i * 2 + 1];
-            
-            // Get current balance from ATA
-            let ata_data = ata_account_info.try_borrow_data()?;
-            let ata = TokenAccount::try_deserialize(&mut &ata_data[..])?;
-            let current_balance = ata.amount;
-            drop(ata_data); // Release borrow
+        let was_paused = vault.deposits_paused();
+        vault.set_finalized(true);
 
-            // Calculate proportional amount to withdraw
-            // Formula: Amount_to_Withdraw = Current_Asset_Amount × Withdrawal_Percentage
-            let amount_to_withdraw = ((current_balance as u128 * withdrawal_percentage) / 1_000_000) as u64;
+        msg!("Vault '{}' finalized with {} assets", vault.name(), vault.assets().len());
 
-            // Get asset info
-            let (decimals, price, asset_name) = match asset.weight {
-                40 => (8u8, &btc_normalized, "BTC"),
-                30 if i == 1 => (18u8, &eth_normalized, "ETH"),
-                30 => (9u8, &sol_normalized, "SOL"),
-                _ => continue,
-            };
+        emit_cpi!(CompositionChangedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mints: vault.assets().iter().map(|a| a.mint).collect(),
+            weights: vault.assets().iter().map(|a| a.weight).collect(),
+        });
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
 
-            // Calculate USD value of this withdrawal
-            let asset_value_usd = price.tokens_to_usd(amount_to_withdraw, decimals);
-            total_withdrawal_value_usd += asset_value_usd;
+        Ok(())
+    }
 
-            msg!(
-                "  • {} {}: {} tokens (${} USD)",
-                amount_to_withdraw,
-                asset_name,
-                asset_name,
-                asset_value_usd
-            );
+    /// Create a vault from a protocol template: its composition, price source
+    /// and feeds, swap backend and slippage limit
+    ///
+    /// Takes create_vault's accounts plus the template, with the same
+    /// remaining accounts ([mint, ata] per template asset, in template order).
+    /// Everything else starts at create_vault's defaults and stays the
+    /// creator's to change.
+    pub fn create_vault_from_template<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateVaultFromTemplate<'info>>,
+        name: String,
+        _template_name: String,
+        soulbound: bool,
+    ) -> Result<()> {
+        require!(!name.is_empty() && name.len() <= MAX_NAME_LEN, VaultError::InvalidName);
 
-            // For SOL: We use native SOL from the vault PDA (not SPL tokens)
-            // The actual SOL withdrawal will be calculated after checking Marinade
-            // Note: During deposits, SOL goes as native lamports, not SPL tokens
-            // For BTC/ETH: Calculate equivalent SOL using MockSwap
-            if asset_name == "SOL" {
-                // SOL withdrawal will be calculated after Marinade unstaking
-                // We need to know: vault native balance + Marinade holdings
-                msg!("    → SOL withdrawal will be calculated from native balance + Marinade");
-            } else {
-                // For BTC/ETH: Only swap if we have a non-zero amount
-                if amount_to_withdraw > 0 {
-                    // Use MockSwap to calculate how much SOL we'd get for this asset
-                    let sol_equivalent = MockSwap::calculate_swap_output(
-                        amount_to_withdraw,
-                        price.original_price,
-                        price.expo,
-                        sol_normalized.original_price,
-                        sol_normalized.expo,
-                        decimals,
-                        9, // SOL decimals
-                    )?;
-                    total_sol_to_return += sol_equivalent;
-                    
-                    msg!(
-                        "    → Swapped {} {} to {} SOL equivalent",
-                        amount_to_withdraw,
-                        asset_name,
-                        sol_equivalent
-                    );
-                } else {
-                    msg!("    → No {} balance to withdraw", asset_name);
-                }
-            }
-        }
-
-        // STEP 2.5: Handle Marinade unstaking if strategy is active
-        if let Some(strategy_key) = vault.marinade_strategy {
-            msg!("🌊 Marinade strategy detected - unstaking proportional mSOL!");
-            
-            // Find the strategy account in remaining_accounts
-            let expected_strategy_index = match vault.price_source {
-                PriceSource::MockOracle => vault.assets.len() * 2 + 1, // After oracle
-                PriceSource::Switchboard => vault.assets.len() * 2,
-            };
-            
-            if ctx.remaining_accounts.len() > expected_strategy_index {
-                let strategy_account_info = &ctx.remaining_accounts[expected_strategy_index];
-                
-                if strategy_account_info.key() == strategy_key {
-                    msg!("   Strategy account found in remaining_accounts");
-                    
-                    // Read strategy account to get mSOL balance
-                    let strategy_data = strategy_account_info.try_borrow_data()?;
-                    let mut strategy_slice = &strategy_data[..];
-                    let strategy = marinade_strategy::StrategyAccount::try_deserialize(&mut strategy_slice)?;
-                    drop(strategy_data);
-                    
-                    let total_msol = strategy.msol_balance;
-                    let initial_staked = strategy.total_staked;
-                    
-                    msg!("   Total mSOL in strategy: {}", total_msol);
-                    msg!("   Initial SOL staked: {}", initial_staked);
-                    
-                    // Calculate proportional mSOL to unstake
-                    let msol_to_unstake = ((total_msol as u128 * withdrawal_percentage) / 1_000_000) as u64;
-                    
-                    if msol_to_unstake > 0 {
-                        msg!("   Unstaking {} mSOL ({}% of total)", msol_to_unstake, (withdrawal_percentage * 100) / 1_000_000);
-                        
-                        // Record vault balance before unstaking
-                        let vault_balance_before = ctx.accounts.vault.to_account_info().lamports();
-                        
-                        // Build CPI context for marinade_strategy::unstake
-                        let vault_seeds = &[
-                            b"vault".as_ref(),
-                            vault.admin.as_ref(),
-                            vault.name.as_bytes(),
-                            &[vault.bump],
-                        ];
-                        let signer_seeds = &[&vault_seeds[..]];
-                        
-                        let cpi_accounts = marinade_strategy::cpi::accounts::Unstake {
-                            strategy_account: strategy_account_info.clone(),
-                            vault: ctx.accounts.vault.to_account_info(),
-                            sol_receiver: ctx.accounts.sol_receiver.to_account_info(), // System-owned account
-                            marinade_state: ctx.accounts.marinade_state.to_account_info(),
-                            msol_mint: ctx.accounts.msol_mint.to_account_info(),
-                            liq_pool_msol_leg: ctx.accounts.liq_pool_msol_leg.to_account_info(),
-                            liq_pool_sol_leg_pda: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
-                            msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
-                            treasury_msol_account: ctx.accounts.treasury_msol_account.to_account_info(),
-                            marinade_program: ctx.accounts.marinade_program.to_account_info(),
-                            system_program: ctx.accounts.system_program.to_account_info(),
-                            token_program: ctx.accounts.token_program.to_account_info(),
-                        };
-                        
-                        let cpi_ctx = CpiContext::new_with_signer(
-                            ctx.accounts.marinade_strategy_program.to_account_info(),
-                            cpi_accounts,
-                            signer_seeds,
-                        );
-                        
-                        // Record receiver balance before unstaking (Marinade will transfer to receiver)
-                        let receiver_balance_before = ctx.accounts.sol_receiver.to_account_info().lamports();
-                        
-                        // Execute unstake - Marinade will return SOL to receiver (including yield!)
-                        marinade_strategy::cpi::unstake(cpi_ctx, msol_to_unstake)?;
-                        
-                        // Calculate SOL received by receiver from Marinade (includes yield)
-                        let receiver_balance_after = ctx.accounts.sol_receiver.to_account_info().lamports();
-                        let sol_received_from_marinade = receiver_balance_after.saturating_sub(receiver_balance_before);
-                        
-                        sol_from_marinade = sol_received_from_marinade;
-                        
-                        // SOL was already transferred to receiver by Marinade
-                        // Don't add to total_sol_to_return since it's not in the vault
-                        
-                        // Calculate yield
-                        let proportional_initial = ((initial_staked as u128 * withdrawal_percentage) / 1_000_000) as u64;
-                        let yield_earned = sol_received_from_marinade.saturating_sub(proportional_initial);
-                        
-                        msg!("   ✅ Unstaked {} mSOL", msol_to_unstake);
-                        msg!("   📥 Received {} SOL from Marinade (transferred to user)", sol_received_from_marinade);
-                        msg!("   🎁 Yield earned: {} lamports", yield_earned);
-                    } else {
-                        msg!("   No mSOL to unstake for this withdrawal amount");
-                    }
-                } else {
-                    msg!("   ⚠️  Strategy account mismatch in remaining_accounts");
-                }
-            } else {
-                msg!("   ⚠️  Strategy account not provided in remaining_accounts");
-            }
-        }
-
-        // STEP 2.6: Calculate total SOL value to withdraw based on withdrawal USD value
-        // We need to convert the total_withdrawal_value_usd to SOL
-        // sol_normalized.price_usd is in micro-dollars (6 decimals)
-        // SOL has 9 decimals (lamports)
-        // Formula: sol_lamports = (withdrawal_value_micro_usd * 10^9) / (sol_price_micro_usd)
-        
-        let withdrawal_sol_raw = (total_withdrawal_value_usd as u128 * 1_000_000_000u128) 
-            / sol_normalized.price_usd as u128;
-        let total_sol_to_withdraw = withdrawal_sol_raw as u64;
-        
-        msg!("   Total withdrawal value: ${} USD (micro)", total_withdrawal_value_usd);
-        msg!("   SOL price: ${} USD (micro)", sol_normalized.price_usd);
-        msg!("   Total SOL to withdraw: {} lamports", total_sol_to_withdraw);
-        msg!("   SOL already unstaked from Marinade: {} lamports", sol_from_marinade);
-        
-        // Calculate remaining SOL to withdraw from vault's native balance
-        // Marinade already sent SOL directly to user, so we only need: total - marinade_amount
-        let vault_native_sol_to_withdraw = total_sol_to_withdraw.saturating_sub(sol_from_marinade);
-        total_sol_to_return = vault_native_sol_to_withdraw;
-        sol_from_native = vault_native_sol_to_withdraw;
-        
-        msg!("   Vault native SOL to withdraw: {} lamports", vault_native_sol_to_withdraw);
-        
-        // Verify vault has enough SOL
-        let current_vault_lamports = ctx.accounts.vault.to_account_info().lamports();
-        let available_sol = current_vault_lamports.saturating_sub(rent_exempt_minimum);
-        msg!("   Available SOL in vault: {} lamports", available_sol);
-        
+        let preset = ctx.accounts.vault_template.preset.clone();
+        let assets = preset.asset_configs();
         require!(
-            available_sol >= vault_native_sol_to_withdraw,
-            VaultError::InsufficientBalance
+            ctx.remaining_accounts.len() == assets.len() * 2,
+            VaultError::InvalidRemainingAccounts
         );
 
-        msg!(
-            "💰 Total withdrawal value: ${} USD",
-            total_withdrawal_value_usd
-        );
-        msg!(
-            "   SOL from native/SPL: {} lamports",
-            sol_from_native
-        );
-        if sol_from_marinade > 0 {
-            msg!(
-                "   SOL from Marinade (with yield, already sent to receiver): {} lamports",
-                sol_from_marinade
-            );
-        }
-        msg!("   SOL to transfer from vault: {} lamports", total_sol_to_return);
-        msg!("   Total SOL user will have received: {} lamports (vault: {}, marinade receiver: {})", total_sol_to_return + sol_from_marinade, total_sol_to_return, sol_from_marinade);
+        let whitelist = load_asset_whitelist(&ctx.accounts.asset_whitelist)?;
 
-        // STEP 3: Transfer remaining SOL from vault to user
-        // Note: Marinade SOL was already sent directly to user above
-        if total_sol_to_return > 0 {
-            msg!("💸 Transferring {} SOL from vault to user...", total_sol_to_return);
-            **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= total_sol_to_return;
-            **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += total_sol_to_return;
-        }
+        let admin_key = ctx.accounts.admin.key();
+        soulbound::create_share_mint(
+            soulbound,
+            &ctx.accounts.vault_token_mint,
+            &[b"vault_mint", admin_key.as_ref(), name.as_bytes(), &[ctx.bumps.vault_token_mint]],
+            &ctx.accounts.vault.key(),
+            &ctx.accounts.admin,
+            &ctx.accounts.token_program,
+            ctx.accounts.token_2022_program.as_ref().map(|p| p.as_ref()),
+            &ctx.accounts.system_program,
+        )?;
 
-        // STEP 4: Burn shares
-        msg!("🔥 Burning {} shares...", shares);
-        
-        let burn_accounts = anchor_spl::token::Burn {
-            mint: ctx.accounts.vault_token_mint.to_account_info(),
-            from: ctx.accounts.user_shares_ata.to_account_info(),
-            authority: ctx.accounts.user.to_account_info(),
+        let programs = AssetAtaPrograms {
+            token_program: ctx.accounts.token_program.as_ref(),
+            token_2022_program: ctx.accounts.token_2022_program.as_ref().map(|p| p.as_ref()),
+            associated_token_program: ctx.accounts.associated_token_program.as_ref(),
+            system_program: ctx.accounts.system_program.as_ref(),
         };
-        let burn_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), burn_accounts);
-        anchor_spl::token::burn(burn_ctx, shares)?;
-
-        // STEP 5: Calculate new vault state
-        let new_total_shares = total_shares - shares;
-        
-        // Recalculate TVL with remaining assets
-        let mut btc_remaining = 0u64;
-        let mut eth_remaining = 0u64;
-        let mut sol_remaining = 0u64;
-
-        for (i, asset) in vault.assets.iter().enumerate() {
-            let ata_account_info = &ctx.remaining_accounts[i * 2 + 1];
-            let ata_data = ata_account_info.try_borrow_data()?;
-            let ata = TokenAccount::try_deserialize(&mut &ata_data[..])?;
-            
-            match asset.weight {
-                40 => btc_remaining = ata.amount,
-                30 if i == 1 => eth_remaining = ata.amount,
-                30 => sol_remaining = ata.amount,
-                _ => {}
-            }
+        let mut stored_assets = Vec::with_capacity(assets.len());
+        for (i, asset_config) in assets.iter().enumerate() {
+            stored_assets.push(open_asset_ata(
+                i,
+                asset_config,
+                &ctx.remaining_accounts[i * 2],
+                &ctx.remaining_accounts[i * 2 + 1],
+                &ctx.accounts.vault.to_account_info(),
+                &ctx.accounts.admin.to_account_info(),
+                whitelist.as_ref(),
+                &programs,
+            )?);
         }
 
-        let new_tvl = Vault::calculate_tvl_from_balances(
-            btc_remaining,
-            eth_remaining,
-            sol_remaining,
-            &btc_normalized,
-            &eth_normalized,
-            &sol_normalized,
+        // As in create_vault, the data is only borrowed after the ATA CPIs
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_init()?;
+        init_vault_state(
+            &mut vault,
+            ctx.bumps.vault,
+            ctx.accounts.admin.key(),
+            &name,
+            ctx.accounts.vault_token_mint.key(),
         )?;
+        vault.set_soulbound(soulbound);
+        vault.set_assets(&stored_assets)?;
+
+        vault.set_price_source(preset.price_source);
+        vault.set_mock_oracle(preset.mock_oracle);
+        vault.twap_window_secs = preset.twap_window_secs;
+        vault.set_price_feeds(&preset.price_feeds)?;
+        vault.max_divergence_bps = preset.max_divergence_bps;
+        require!(
+            preset.swap_backend != SwapBackend::Sanctum || vault.wrapped_sol_ata().is_none(),
+            VaultError::WrappedSolUnsupported
+        );
+        vault.set_swap_backend(preset.swap_backend);
+        vault.max_slippage_bps = preset.max_slippage_bps;
 
-        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+        let template = &mut ctx.accounts.vault_template;
+        template.vaults_created = template
+            .vaults_created
+            .checked_add(1)
+            .ok_or(VaultError::MathOverflow)?;
 
-        msg!("✅ Withdrawal Complete!");
-        msg!("   Withdrawn: ${} USD in {} SOL", total_withdrawal_value_usd, total_sol_to_return);
-        msg!("   New TVL: ${} USD", new_tvl);
-        msg!("   New Total Shares: {}", new_total_shares);
-        msg!("   New Share Price: ${} USD", new_share_price);
+        let registry = &mut ctx.accounts.vault_registry;
+        registry.bump = ctx.bumps.vault_registry;
+        let registration = &mut ctx.accounts.vault_registration;
+        registration.register(registry, vault_key, &vault, ctx.bumps.vault_registration)?;
 
-        // Emit withdrawal event
-        emit!(WithdrawEvent {
-            vault: vault.key(),
-            user: ctx.accounts.user.key(),
-            shares_burned: shares,
-            amount_withdrawn: total_sol_to_return,
-            tvl_usd: new_tvl,
+        msg!("Vault '{}' created from template '{}'", vault.name(), template.name);
+        msg!("  Share Mint: {}{}", vault.vault_token_mint, if soulbound { " (soulbound)" } else { "" });
+        msg!("  Assets: {}", vault.assets().len());
+
+        emit_cpi!(VaultCreatedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            admin: vault.admin,
+            name: vault.name().to_string(),
+            vault_token_mint: vault.vault_token_mint,
+            num_assets: vault.assets().len() as u8,
+            soulbound,
+        });
+        emit_cpi!(VaultTemplateAppliedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            template: template.key(),
+        });
+        emit_cpi!(CompositionChangedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mints: vault.assets().iter().map(|a| a.mint).collect(),
+            weights: vault.assets().iter().map(|a| a.weight).collect(),
+        });
+        emit_cpi!(price_source_changed_event(&mut vault, vault_key));
+        emit_cpi!(VaultRegisteredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            index: registration.index,
         });
 
         Ok(())
     }
 
-    /// Initialize mock price oracle for devnet testing
-    /// This allows testing with real-time market prices on devnet
-    pub fn initialize_mock_oracle(ctx: Context<InitializeMockOracle>) -> Result<()> {
-        let oracle = &mut ctx.accounts.mock_oracle;
+    /// Deposit SOL into a multi-asset vault and receive proportional shares
+    /// This function handles the complete deposit flow with proper formulas
+    ///
+    /// **Parameters:**
+    /// - amount: Amount of SOL to deposit (in lamports, 9 decimals)
+    /// - name: Vault name for PDA derivation
+    /// - referrer: Optional referrer credited with referral_share_bps of the deposit fee
+    ///   (requires their ReferralBalance as `referral_balance`); the rest of the fee is
+    ///   split between the protocol treasury and the vault creator
+    ///
+    /// **Process:**
+    /// 1. Convert SOL deposit amount (net of the deposit fee) to USD using SOL price
+    /// 2. Calculate current vault TVL from existing balances
+    /// 3. Calculate share price (TVL / total_shares, or $1 if first deposit)
+    /// 4. Allocate SOL across vault assets based on weights
+    /// 5. Execute mock swaps to achieve target allocation
+    /// 6. Mint vault shares proportional to deposit value
+    ///
+    /// Without a strategy, the SOL leg is wrapped into the vault's wSOL ATA
+    /// when the SOL slot is the native mint.
+    ///
+    /// Baskets of other than three assets are priced per asset (see
+    /// prices::fetch_basket_prices): every asset is bought with SOL except a
+    /// native-mint slot, which is staked or wrapped like the SOL slot.
+    ///
+    /// **remaining_accounts layout:**
+    /// - [mint, vault ATA] per asset
+    /// - price accounts (prices::price_account_count; StrategyValue last if a strategy is set)
+    /// - strategy account (if set)
+    /// - swap backend accounts
+    pub fn deposit_multi_asset<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositMultiAsset<'info>>,
+        _name: String,
+        amount: u64,
+        referrer: Option<Pubkey>,
+    ) -> Result<DepositReceipt> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+
+        // A referrer must be registered for this vault and cannot be the depositor
+        if let Some(referrer) = referrer {
+            let referral = ctx
+                .accounts
+                .referral_balance
+                .as_ref()
+                .ok_or(VaultError::InvalidReferral)?;
+            require!(
+                referral.vault == vault_key
+                    && referral.referrer == referrer
+                    && referrer != ctx.accounts.user.key(),
+                VaultError::InvalidReferral
+            );
+        }
         
-        oracle.authority = ctx.accounts.authority.key();
-        oracle.btc_price = 0;
-        oracle.eth_price = 0;
-        oracle.sol_price = 0;
-        oracle.last_update = Clock::get()?.unix_timestamp;
-        oracle.bump = ctx.bumps.mock_oracle;
-
-        msg!("Mock oracle initialized: {}", oracle.key());
+        // Validate remaining accounts: we need asset mints and vault ATAs
+        // If using MockOracle or a PriceCache, we need one additional account
+        // If Marinade strategy is set, we need one more account (the strategy)
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let mut expected_accounts = vault.assets().len() * 2 + price_slots;
         
-        Ok(())
-    }
-
-    /// Update mock oracle prices
-    /// Fetches real-time prices and updates the mock oracle
-    /// Only callable by oracle authority
-    pub fn update_mock_oracle(
-        ctx: Context<UpdateMockOracle>,
-        btc_price: i64,
-        eth_price: i64,
-        sol_price: i64,
-    ) -> Result<()> {
-        let oracle = &mut ctx.accounts.mock_oracle;
+        if vault.marinade_strategy().is_some() {
+            expected_accounts += 1;
+        }
         
-        require!(
-            ctx.accounts.authority.key() == oracle.authority,
-            VaultError::Unauthorized
+        msg!(
+            "Remaining accounts validation: expected {}, got {}",
+            expected_accounts,
+            ctx.remaining_accounts.len()
         );
+        
+        // Swap backend accounts (if any) follow the accounts above
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
 
-        // Validate prices are reasonable (positive and within bounds)
-        require!(btc_price > 0 && btc_price < 10_000_000_000_000, VaultError::InvalidPrice);
-        require!(eth_price > 0 && eth_price < 10_000_000_000_000, VaultError::InvalidPrice);
-        require!(sol_price > 0 && sol_price < 10_000_000_000_000, VaultError::InvalidPrice);
+        let sol_decimals = 9u8; // SOL has 9 decimals
 
-        oracle.btc_price = btc_price;
-        oracle.eth_price = eth_price;
-        oracle.sol_price = sol_price;
-        oracle.last_update = Clock::get()?.unix_timestamp;
+        // Fetch prices based on configured price source
+        let clock = &ctx.accounts.clock;
 
-        msg!("Mock oracle updated - BTC: ${}, ETH: ${}, SOL: ${}", 
-             btc_price / 1_000_000, eth_price / 1_000_000, sol_price / 1_000_000);
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        // Per-asset priced vaults read one feed per asset (see
+        // prices::fetch_basket_prices); the circuit breaker only watches the
+        // BTC/ETH/SOL markets. MockOracle prices must be within the last 5 minutes
+        let feed_prices = if vault.per_asset_pricing() {
+            require!(!vault.restricted(), VaultError::VaultRestricted);
+            None
+        } else {
+            Some(prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?)
+        };
+        let basket_prices = match &feed_prices {
+            Some(feed_prices) => {
+                // Circuit breaker: no deposits while prices jumped past the checkpoint
+                let restricted = prices::check_circuit_breaker(&mut vault, vault_key, feed_prices).restricted;
+                require!(!restricted, VaultError::VaultRestricted);
+                BasketPrices::from_markets(&vault, feed_prices)
+            },
+            None => prices::fetch_basket_prices(&vault, &price_accounts, 300)?,
+        };
+        let sol_normalized = basket_prices.sol;
+
+        for (asset, price) in vault.assets().iter().zip(&basket_prices.assets) {
+            msg!("{} Price: ${} (expo: {})", asset.mint, price.original_price, price.expo);
+        }
+        msg!("SOL Price: ${} (expo: {})", sol_normalized.original_price, sol_normalized.expo);
+
+        // STEP 1: Calculate deposit value in USD
+        // The deposit fee stays in the vault (minus any referral share) and buys no shares
+        let deposit_fee = math::bps_of(amount, vault.deposit_fee_bps)?;
+        let net_amount = amount - deposit_fee;
+        let deposit_usd_micro = sol_normalized.tokens_to_usd(net_amount, sol_decimals)?;
+        msg!("Deposit: {} SOL = ${} USD (fee: {} lamports)", net_amount, deposit_usd_micro, deposit_fee);
+
+        // STEP 2: Calculate current vault TVL from asset balances in ATAs
+        msg!("Calculating vault TVL...");
         
-        Ok(())
-    }
+        let current_tvl = match &feed_prices {
+            Some(feed_prices) => vault_tvl_usd(
+                &vault,
+                ctx.accounts.vault.as_ref(),
+                ctx.remaining_accounts,
+                &ctx.accounts.rent,
+                feed_prices,
+            )?,
+            None => basket_tvl_usd(&vault, &vault_key, ctx.remaining_accounts, &basket_prices)?,
+        };
 
-    // ============================================================================
-    // EPHEMERAL ROLLUPS INTEGRATION (TEMPORARILY DISABLED)
-    // ============================================================================
-    // The following instructions are disabled pending ER SDK compatibility fixes
-    // Issue: borsh version mismatch between Anchor 0.31.1 and ephemeral-rollups-sdk 0.2.11
-    // Will be re-enabled once MagicBlock updates SDK for Anchor 0.31+ compatibility
-    
-    
-    /// Delegate mock oracle to Ephemeral Rollup for high-frequency price updates
-    /// This transfers ownership of the oracle PDA to the ER delegation program
-    /// Allows sub-second price updates without L1 transaction fees
-    /// Only callable by oracle authority
-    pub fn delegate_mock_oracle(ctx: Context<DelegateMockOracleCtx>) -> Result<()> {
-        // Delegate the oracle PDA to Ephemeral Rollup
-        // The #[delegate] macro on DelegateMockOracle provides the delegate_pda method
-        ctx.accounts.delegate_pda(
-            &ctx.accounts.payer,
-            &[b"mock_oracle", ctx.accounts.payer.key().as_ref()],
-            DelegateConfig {
-                validator: None, // Will use default ER validator
-                commit_frequency_ms: 30_000, // Commit to L1 every 30 seconds
-            },
-        )?;
-
-        msg!("Mock oracle delegated to Ephemeral Rollup: {}", ctx.accounts.pda.key());
-        msg!("High-frequency price updates now enabled (sub-second latency)");
+        msg!("Current TVL: ${} USD", current_tvl);
 
-        Ok(())
-    }    /// Commit mock oracle state to L1 without undelegating
-    /// This creates a checkpoint on L1 while keeping the oracle on ER
-    /// Useful for periodic state synchronization
-    pub fn commit_mock_oracle(ctx: Context<CommitMockOracle>) -> Result<()> {
-        let authority = &ctx.accounts.authority;
-        let mock_oracle = &ctx.accounts.mock_oracle;
+        // STEP 3: Calculate share price
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let share_price = Vault::calculate_share_price(current_tvl, total_shares)?;
         
-        // Commit the oracle account state to L1
-        commit_accounts(
-            authority,
-            vec![&mock_oracle.to_account_info()],
-            &ctx.accounts.magic_context,
-            &ctx.accounts.magic_program,
-        )?;
+        msg!(
+            "Share Price: ${} USD (TVL: ${}, Supply: {} shares)",
+            share_price,
+            current_tvl,
+            total_shares
+        );
 
-        msg!("Mock oracle state committed to L1: {}", mock_oracle.key());
-        msg!("Oracle remains on ER for continued high-frequency updates");
-        
-        Ok(())
-    }
+        // STEP 4: Calculate shares to mint
+        let shares_to_mint = Vault::calculate_shares_for_deposit(deposit_usd_micro, current_tvl, total_shares)?;
+        vault.require_min_shares(shares_to_mint)?;
+        msg!("🎁 Shares to mint: {} shares", shares_to_mint);
 
-    /// Undelegate mock oracle from Ephemeral Rollup back to L1
-    /// This commits final state and returns ownership to the vault program
-    /// Callable by oracle authority to end ER session
-    pub fn undelegate_mock_oracle(ctx: Context<UndelegateMockOracle>) -> Result<()> {
-        let authority = &ctx.accounts.authority;
-        let mock_oracle = &ctx.accounts.mock_oracle;
-        
-        // Verify authority
-        require!(
-            authority.key() == mock_oracle.authority,
-            VaultError::Unauthorized
+        // The deposit lands on the vault PDA and the swaps and CPIs below
+        // take it, so everything they need is read before its data is released
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
         );
+        let marinade_strategy = vault.marinade_strategy();
+        let (lockup_secs, referral_share_bps) = (vault.lockup_secs, vault.referral_share_bps);
+        drop(vault);
 
-        // Commit and undelegate the oracle back to L1
-        commit_and_undelegate_accounts(
-            authority,
-            vec![&mock_oracle.to_account_info()],
-            &ctx.accounts.magic_context,
-            &ctx.accounts.magic_program,
+        // STEP 5: Transfer SOL from user to vault (only now, so the TVL above
+        // excludes the deposit being priced against it)
+        msg!("💸 Transferring {} SOL from user to vault...", amount);
+        transfer_lamports_in(
+            &ctx.accounts.user,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
         )?;
+        msg!("✅ SOL transferred successfully");
 
-        msg!("Mock oracle undelegated from Ephemeral Rollup: {}", mock_oracle.key());
-        msg!("Oracle returned to L1, final state committed");
-        
-        Ok(())
-    }
-    
+        // STEP 6: Allocate SOL across vault assets using MockSwap
+        msg!("🔄 Allocating deposit across vault assets...");
 
-    /// Set price source for vault (Switchboard or MockOracle)
-    /// Allows switching between real Switchboard feeds and mock oracle
-    pub fn set_price_source(
-        ctx: Context<SetPriceSource>,
-        _name: String,
-        price_source: PriceSource,
-        mock_oracle: Option<Pubkey>,
-    ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        
-        require!(
-            ctx.accounts.authority.key() == vault.admin,
-            VaultError::Unauthorized
-        );
+        let (allocations, sol_to_stake) = allocate_deposit(
+            &mut swaps,
+            &ctx.accounts.vault,
+            net_amount,
+            deposit_usd_micro,
+            &basket_prices,
+        )?;
 
-        // If setting to MockOracle, require mock_oracle address
-        if price_source == PriceSource::MockOracle {
-            require!(mock_oracle.is_some(), VaultError::InvalidPrice);
+        // Delegate SOL portion to Marinade strategy (if configured)
+        let mut staked = None;
+        if let (Some(strategy_key), Some(stake_amount)) = (marinade_strategy, sol_to_stake) {
+            msg!("🌊 Marinade strategy configured!");
+            msg!("   Delegating {} lamports (30%) to Marinade...", stake_amount);
+            
+            // Find the strategy account in remaining_accounts
+            let strategy_account_info = ctx.remaining_accounts.iter()
+                .find(|acc| acc.key() == strategy_key)
+                .ok_or(VaultError::MarinadeError)?;
+
+            marinade::verify(
+                &ctx.accounts.marinade_program.key(),
+                &[
+                    (MarinadeAccount::State, ctx.accounts.marinade_state.key()),
+                    (MarinadeAccount::ReservePda, ctx.accounts.reserve_pda.key()),
+                    (MarinadeAccount::MsolMint, ctx.accounts.msol_mint.key()),
+                    (MarinadeAccount::MsolMintAuthority, ctx.accounts.msol_mint_authority.key()),
+                    (MarinadeAccount::LiqPoolSolLeg, ctx.accounts.liq_pool_sol_leg_pda.key()),
+                    (MarinadeAccount::LiqPoolMsolLeg, ctx.accounts.liq_pool_msol_leg.key()),
+                    (MarinadeAccount::LiqPoolMsolLegAuthority, ctx.accounts.liq_pool_msol_leg_authority.key()),
+                ],
+            )?;
+            
+            // Build CPI context for marinade_strategy::stake
+            let cpi_accounts = marinade_strategy::cpi::accounts::Stake {
+                strategy_account: strategy_account_info.clone(),
+                vault: ctx.accounts.vault.to_account_info(),
+                payer: ctx.accounts.user.to_account_info(), // User must sign as payer
+                marinade_state: ctx.accounts.marinade_state.to_account_info(),
+                reserve_pda: ctx.accounts.reserve_pda.to_account_info(),
+                msol_mint: ctx.accounts.msol_mint.to_account_info(),
+                msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
+                msol_mint_authority: ctx.accounts.msol_mint_authority.to_account_info(),
+                liq_pool_sol_leg_pda: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+                liq_pool_msol_leg: ctx.accounts.liq_pool_msol_leg.to_account_info(),
+                liq_pool_msol_leg_authority: ctx.accounts.liq_pool_msol_leg_authority.to_account_info(),
+                marinade_program: ctx.accounts.marinade_program.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            };
+            
+            // Vault PDA signs the CPI call
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.marinade_strategy_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            );
+            
+            // Execute CPI call to marinade_strategy::stake
+            marinade_strategy::cpi::stake(cpi_ctx, stake_amount)?;
+            strategy_value::adjust(
+                &*ctx.accounts.vault.load()?,
+                &vault_key,
+                ctx.remaining_accounts,
+                stake_amount as i64,
+            )?;
+            staked = Some((strategy_key, stake_amount));
+            
+            msg!("✅Successfully delegated {} lamports to Marinade!", stake_amount);
+            
+        } else if let Some(sol_leg) = sol_to_stake {
+            msg!("No Marinade strategy configured - SOL will remain in vault");
+            wrap_sol_leg(
+                &*ctx.accounts.vault.load()?,
+                ctx.accounts.vault.as_ref(),
+                ctx.remaining_accounts,
+                &ctx.accounts.token_program,
+                sol_leg,
+            )?;
         }
 
-        vault.price_source = price_source;
-        vault.mock_oracle = mock_oracle;
+        // STEP 7: Mint shares to user
+        msg!("🪙 Minting {} shares to user...", shares_to_mint);
 
-        msg!("Price source set to: {:?}", price_source);
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.share_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
         
-        Ok(())
-    }
+        token_interface::mint_to(cpi_ctx, shares_to_mint)?;
+
+        // Track the user's cost basis and shares
+        let position = &mut ctx.accounts.user_position;
+        position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+        position.record_deposit(shares_to_mint, deposit_usd_micro, clock);
+        position.lock_shares(shares_to_mint, lockup_secs, clock.unix_timestamp);
+
+        // Referrer's share of the deposit fee moves from the vault into their balance PDA
+        let mut referral_cut = 0u64;
+        if let (Some(_), Some(referral)) = (referrer, ctx.accounts.referral_balance.as_mut()) {
+            referral_cut = math::bps_of(deposit_fee, referral_share_bps)?;
+            if referral_cut > 0 {
+                **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= referral_cut;
+                **referral.to_account_info().try_borrow_mut_lamports()? += referral_cut;
+                referral.accrued_lamports = referral.accrued_lamports.saturating_add(referral_cut);
+                referral.total_earned = referral.total_earned.saturating_add(referral_cut);
+                msg!("🤝 Referral fee: {} lamports credited to {}", referral_cut, referral.referrer);
+            }
+        }
 
-    /// Set a strategy for the vault (only callable by vault authority)
-    /// This allows the vault to delegate asset management to a strategy
-    pub fn set_strategy(ctx: Context<SetStrategy>, _name: String, strategy: Pubkey) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+        // STEP 8: Calculate new vault state
+        let new_tvl = current_tvl + deposit_usd_micro;
+        let new_total_shares = total_shares + shares_to_mint;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
 
-        // Only vault admin can set strategy
-        require!(
-            ctx.accounts.authority.key() == vault.admin,
-            VaultError::Unauthorized
-        );
+        msg!("✅ Deposit Complete!");
+        msg!("   New TVL: ${} USD", new_tvl);
+        msg!("   New Total Shares: {}", new_total_shares);
+        msg!("   New Share Price: ${} USD", new_share_price);
 
-        vault.marinade_strategy = Some(strategy);
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+        vault.pending_fees = vault.pending_fees.saturating_add(deposit_fee - referral_cut);
+
+        if let Some((strategy, lamports)) = staked {
+            emit_cpi!(StrategyStakedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                strategy,
+                lamports,
+            });
+        }
+        if deposit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user: ctx.accounts.user.key(),
+                fee_bps: vault.deposit_fee_bps,
+                fee_lamports: deposit_fee,
+            });
+        }
+        if let (Some(referrer), true) = (referrer, referral_cut > 0) {
+            emit_cpi!(ReferralFeeAccruedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                referrer,
+                user: ctx.accounts.user.key(),
+                lamports: referral_cut,
+            });
+        }
 
-        msg!("Strategy set for vault: {}", strategy);
+        // Emit deposit event
+        emit_cpi!(DepositEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            deposit_mint: anchor_lang::solana_program::system_program::ID, // SOL
+            amount_deposited: amount,
+            shares_minted: shares_to_mint,
+            tvl_usd: new_tvl,
+        });
 
-        Ok(())
+        // Returned via return data so clients can preview the deposit by simulation
+        Ok(DepositReceipt {
+            shares_minted: shares_to_mint,
+            deposit_fee,
+            deposit_usd: deposit_usd_micro,
+            share_price,
+            staked_lamports: if marinade_strategy.is_some() {
+                sol_to_stake.unwrap_or(0)
+            } else {
+                0
+            },
+            allocations,
+        })
     }
 
-    /// Remove strategy from vault (only callable by vault authority)
-    /// This makes the vault work standalone without delegation
-    pub fn remove_strategy(ctx: Context<RemoveStrategy>, _name: String) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+    /// Deposit any SPL token into a multi-asset vault and receive shares
+    ///
+    /// **Parameters:**
+    /// - name: Vault name for PDA derivation
+    /// - amount: Amount of `input_mint` to deposit (in its base units)
+    /// - min_sol_out: Minimum lamports the input must swap into; basket assets
+    ///   are also held to the oracle quote less max_slippage_bps
+    ///
+    /// **Process:**
+    /// 1. Value the vault before the deposit lands
+    /// 2. Move the input tokens into the vault's account for `input_mint`
+    /// 3. Swap them into SOL through the vault's swap backend
+    /// 4. Charge the deposit fee and allocate the rest like deposit_multi_asset
+    /// 5. Mint vault shares proportional to the SOL value received
+    ///
+    /// The Mock backend can only price basket assets, and the SOL share is
+    /// left in the vault rather than delegated to the Marinade strategy.
+    /// remaining_accounts: [mint, vault ATA] per asset, price accounts, then
+    /// swap backend accounts for input -> SOL followed by the allocation swaps.
+    pub fn deposit_zap<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositZap<'info>>,
+        _name: String,
+        amount: u64,
+        min_sol_out: u64,
+    ) -> Result<DepositReceipt> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
 
-        // Only vault admin can remove strategy
+        // SOL deposits go through deposit_multi_asset
+        let input_mint = ctx.accounts.input_mint.key();
         require!(
-            ctx.accounts.authority.key() == vault.admin,
-            VaultError::Unauthorized
+            input_mint != anchor_spl::token::spl_token::native_mint::ID,
+            VaultError::InvalidMint
         );
 
-        vault.marinade_strategy = None;
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let expected_accounts = vault.assets().len() * 2 + price_slots;
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
 
-        msg!("Strategy removed from vault");
+        let clock = &ctx.accounts.clock;
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        let restricted = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices).restricted;
+        require!(!restricted, VaultError::VaultRestricted);
+
+        // STEP 1: Value the vault before any of the deposit arrives
+        let current_tvl = vault_tvl_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &feed_prices,
+        )?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let share_price = Vault::calculate_share_price(current_tvl, total_shares)?;
+        msg!("Current TVL: ${} USD, Share Price: ${} USD", current_tvl, share_price);
 
-        Ok(())
-    }
+        // STEP 2: Move the input tokens into the vault
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.user_input_account.to_account_info(),
+            to: ctx.accounts.vault_input_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+        msg!("✅ Transferred {} of {} from user to vault", amount, input_mint);
 
-    /// Rebalance vault when asset drifts exceed threshold
-    /// 
-    /// This function detects when asset allocations drift from target weights
-    /// and executes swaps to restore the target portfolio composition.
-    /// 
-    /// **Process:**
-    /// 1. Authorization check (only admin)
-    /// 2. Fetch current prices from MockOracle
-    /// 3. Calculate current USD values for each asset
-    /// 4. Detect drifts > threshold (5%)
-    /// 5. Execute MockSwap operations to rebalance
-    /// 
-    /// **remaining_accounts layout:**
-    /// - [0]: MockOracle account
-    /// - [1..n]: Vault's ATAs for each asset (mut)
-    pub fn rebalance(ctx: Context<Rebalance>, _vault_name: String) -> Result<()> {
-        let vault = &ctx.accounts.vault;
-        
-        // STEP 1: Authorization check
-        require!(
-            ctx.accounts.authority.key() == vault.admin,
-            VaultError::Unauthorized
+        // STEP 3: Swap the input into SOL and allocate it like a SOL deposit
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
         );
+        let lockup_secs = vault.lockup_secs;
+        // The swaps below can credit the vault PDA
+        drop(vault);
+        let quote = ctx.accounts.quote_config.as_deref();
+        let sol_received = swap_to_sol(
+            &mut swaps,
+            &ctx.accounts.vault,
+            quote,
+            input_mint,
+            amount,
+            min_sol_out,
+            &feed_prices,
+        )?;
+        msg!("🔄 Swapped {} of {} into {} lamports", amount, input_mint, sol_received);
+
+        let ReceivedDeposit {
+            deposit_fee,
+            deposit_usd: deposit_usd_micro,
+            shares: shares_to_mint,
+            allocations,
+            sol_leg,
+        } = deposit_received_sol(
+            &mut swaps,
+            &ctx.accounts.vault,
+            sol_received,
+            current_tvl,
+            total_shares,
+            &feed_prices,
+        )?;
+        wrap_sol_leg(
+            &*ctx.accounts.vault.load()?,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program,
+            sol_leg,
+        )?;
 
-        msg!("🔄 Starting rebalancing for vault: {}", vault.name);
-
-        // Verify we're using MockOracle
-        require!(
-            vault.price_source == PriceSource::MockOracle,
-            VaultError::InvalidPrice
-        );
-        require!(
-            vault.mock_oracle.is_some(),
-            VaultError::InvalidPrice
-        );
+        // STEP 4: Mint shares to user
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.share_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares_to_mint)?;
+        msg!("🪙 Minted {} shares to user", shares_to_mint);
 
-        // STEP 2: Fetch prices from MockOracle
-        let oracle_account = &ctx.remaining_accounts[0];
-        let oracle_data = oracle_account.try_borrow_data()?;
-        let oracle = MockPriceOracle::try_deserialize(&mut &oracle_data[..])?;
-        
-        // Check staleness (2 min max)
-        let current_time = Clock::get()?.unix_timestamp;
-        let age = (current_time - oracle.last_update) as u64;
-        require!(age < 120, VaultError::StaleQuote);
+        let position = &mut ctx.accounts.user_position;
+        position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+        position.record_deposit(shares_to_mint, deposit_usd_micro, clock);
+        position.lock_shares(shares_to_mint, lockup_secs, clock.unix_timestamp);
 
-        msg!("📊 Current prices (micro-USD):");
-        msg!("   BTC: ${}", oracle.btc_price / 1_000_000);
-        msg!("   ETH: ${}", oracle.eth_price / 1_000_000);
-        msg!("   SOL: ${}", oracle.sol_price / 1_000_000);
+        let new_tvl = current_tvl + deposit_usd_micro;
+        let new_total_shares = total_shares + shares_to_mint;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
 
-        let prices = vec![oracle.btc_price, oracle.eth_price, oracle.sol_price];
-        
-        // STEP 3: Calculate current USD values for each asset
-        let mut total_usd: i64 = 0;
-        let mut current_usds = Vec::new();
-        let mut balances = Vec::new();
-        
-        for (i, asset) in vault.assets.iter().enumerate() {
-            let ata_index = i + 1; // Skip oracle at index 0
-            let ata_account = &ctx.remaining_accounts[ata_index];
-            
-            // Parse token account to get balance
-            let ata_data = ata_account.try_borrow_data()?;
-            let balance = u64::from_le_bytes(
-                ata_data[64..72].try_into().map_err(|_| VaultError::InvalidATA)?
-            );
-            
-            balances.push(balance);
-            
-            // Calculate USD value
-            // balance is in native token decimals, price is in micro-USD
-            let usd_value = calculate_asset_usd_value(
-                balance,
-                prices[i],
-                asset.mint,
-            )?;
-            
-            current_usds.push(usd_value);
-            total_usd = total_usd.checked_add(usd_value).ok_or(VaultError::MathOverflow)?;
-            
-            msg!("   Asset {}: Balance={}, USD=${}", i, balance, usd_value / 1_000_000);
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+        vault.pending_fees = vault.pending_fees.saturating_add(deposit_fee);
+
+        if deposit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user: ctx.accounts.user.key(),
+                fee_bps: vault.deposit_fee_bps,
+                fee_lamports: deposit_fee,
+            });
         }
-        
-        if total_usd == 0 {
-            msg!("⚠️  Empty vault - no rebalancing needed");
-            return Ok(());
+        emit_cpi!(DepositEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            deposit_mint: input_mint,
+            amount_deposited: amount,
+            shares_minted: shares_to_mint,
+            tvl_usd: new_tvl,
+        });
+
+        Ok(DepositReceipt {
+            shares_minted: shares_to_mint,
+            deposit_fee,
+            deposit_usd: deposit_usd_micro,
+            share_price,
+            staked_lamports: 0,
+            allocations,
+        })
+    }
+
+    /// Deposit one basket asset as-is and receive shares
+    ///
+    /// **Parameters:**
+    /// - name: Vault name for PDA derivation
+    /// - mint: Basket asset being deposited (not the SOL slot's native mint,
+    ///   which deposit_multi_asset takes as lamports)
+    /// - amount: Amount of `mint` to send from `user_token_account`
+    ///
+    /// The tokens land in the vault's ATA for the asset unswapped, so the
+    /// basket drifts toward that asset until the next rebalance restores
+    /// the target weights. Shares are minted for what the vault actually
+    /// received (net of any Token-2022 transfer fee) at the oracle price.
+    /// The deposit fee is kept in kind: that part of the deposit buys no
+    /// shares and stays in the vault for existing holders.
+    /// remaining_accounts: [mint, vault ATA] per asset, price accounts
+    pub fn deposit_single_asset<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositSingleAsset<'info>>,
+        _name: String,
+        mint: Pubkey,
+        amount: u64,
+    ) -> Result<DepositReceipt> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+        require!(
+            mint != anchor_spl::token::spl_token::native_mint::ID,
+            VaultError::InvalidMint
+        );
+        let index = vault
+            .assets()
+            .iter()
+            .position(|asset| asset.mint == mint)
+            .ok_or(VaultError::InvalidMint)?;
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        require!(
+            ctx.remaining_accounts.len() >= vault.assets().len() * 2 + price_slots,
+            VaultError::InvalidRemainingAccounts
+        );
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2].key() == asset.mint, VaultError::InvalidMint);
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
         }
+        let mint_account = &ctx.remaining_accounts[index * 2];
+        let vault_ata = &ctx.remaining_accounts[index * 2 + 1];
 
-        msg!("💰 Total TVL: ${}", total_usd / 1_000_000);
-        
-        // STEP 4: Check for drifts > threshold (5%)
-        let threshold: i64 = 5; // 5%
-        let mut drifts = Vec::new();
-        let mut needs_rebalance = false;
-        
-        for (i, asset) in vault.assets.iter().enumerate() {
-            let target_usd = (total_usd * asset.weight as i64) / 100;
-            let current_pct = (current_usds[i] * 100) / total_usd;
-            let drift_pct = current_pct - asset.weight as i64;
-            
-            drifts.push((i, drift_pct, current_usds[i] - target_usd));
-            
-            msg!("   Asset {} (weight={}%): current={}%, drift={}%",
-                i, asset.weight, current_pct, drift_pct);
-            
-            if drift_pct.abs() > threshold {
-                needs_rebalance = true;
-                msg!("     ⚠️  Drift exceeds threshold!");
+        let clock = &ctx.accounts.clock;
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        let restricted = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices).restricted;
+        require!(!restricted, VaultError::VaultRestricted);
+        let price = feed_prices.get(index).ok_or(VaultError::InvalidMint)?;
+
+        // STEP 1: Value the vault before the deposit lands
+        let current_tvl = vault_tvl_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &feed_prices,
+        )?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let share_price = Vault::calculate_share_price(current_tvl, total_shares)?;
+        msg!("Current TVL: ${} USD, Share Price: ${} USD", current_tvl, share_price);
+
+        // STEP 2: Move the tokens into the vault's ATA, measuring what arrived
+        let balance_before = token_io::token_amount(vault_ata)?;
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        token_io::transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            token_2022_program.as_ref(),
+            &ctx.accounts.user_token_account.to_account_info(),
+            mint_account,
+            vault_ata,
+            &ctx.accounts.user.to_account_info(),
+            amount,
+            &[],
+        )?;
+        let received = token_io::token_amount(vault_ata)?
+            .checked_sub(balance_before)
+            .ok_or(VaultError::MathOverflow)?;
+        msg!("✅ Received {} of {} from user", received, mint);
+
+        // STEP 3: Value the deposit net of the in-kind fee
+        let deposit_fee = math::bps_of(received, vault.deposit_fee_bps)?;
+        let net_amount = received - deposit_fee;
+        let deposit_usd_micro = price.tokens_to_usd(net_amount, vault.asset_decimals(index))?;
+        let shares_to_mint = Vault::calculate_shares_for_deposit(deposit_usd_micro, current_tvl, total_shares)?;
+        vault.require_min_shares(shares_to_mint)?;
+        msg!("Deposit: {} of {} = ${} USD (fee: {})", net_amount, mint, deposit_usd_micro, deposit_fee);
+
+        // STEP 4: Mint shares to user
+        let signer = vault.signer();
+        drop(vault);
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.share_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares_to_mint)?;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        msg!("🪙 Minted {} shares to user", shares_to_mint);
+
+        let position = &mut ctx.accounts.user_position;
+        position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+        position.record_deposit(shares_to_mint, deposit_usd_micro, clock);
+        position.lock_shares(shares_to_mint, vault.lockup_secs, clock.unix_timestamp);
+
+        // The in-kind fee is part of the new TVL but bought no shares
+        let fee_usd = price.tokens_to_usd(deposit_fee, vault.asset_decimals(index))?;
+        let new_tvl = current_tvl + deposit_usd_micro + fee_usd;
+        let new_total_shares = total_shares + shares_to_mint;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+
+        emit_cpi!(DepositEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            deposit_mint: mint,
+            amount_deposited: received,
+            shares_minted: shares_to_mint,
+            tvl_usd: new_tvl,
+        });
+
+        Ok(DepositReceipt {
+            shares_minted: shares_to_mint,
+            deposit_fee,
+            deposit_usd: deposit_usd_micro,
+            share_price,
+            staked_lamports: 0,
+            allocations: vec![AssetAllocation {
+                mint,
+                usd_value: deposit_usd_micro,
+                token_amount: net_amount,
+            }],
+        })
+    }
+
+    /// Deposit mSOL straight into the vault's Marinade strategy and receive shares
+    ///
+    /// **Parameters:**
+    /// - name: Vault name for PDA derivation
+    /// - amount: mSOL to send from `user_msol_account`
+    ///
+    /// The mSOL is valued at Marinade's exchange rate × the SOL price and
+    /// counts toward the SOL/staking leg as if the vault had staked the SOL
+    /// itself, so holders skip the liquid-unstake fee of converting first.
+    /// The deposit fee is kept in kind, as in deposit_single_asset.
+    /// remaining_accounts: [mint, vault ATA] per asset, price accounts
+    pub fn deposit_msol<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositMsol<'info>>,
+        _name: String,
+        amount: u64,
+    ) -> Result<DepositReceipt> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+        require!(
+            vault.marinade_strategy() == Some(ctx.accounts.strategy_account.key()),
+            VaultError::MarinadeError
+        );
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        require!(
+            ctx.remaining_accounts.len() >= vault.assets().len() * 2 + price_slots,
+            VaultError::InvalidRemainingAccounts
+        );
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
+
+        let clock = &ctx.accounts.clock;
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        let restricted = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices).restricted;
+        require!(!restricted, VaultError::VaultRestricted);
+        let sol_price = feed_prices[2];
+
+        // Marinade's own state prices the mSOL (owner and mint are checked)
+        let msol_mint = ctx.accounts.msol_mint.key();
+        marinade::verify(
+            ctx.accounts.marinade_state.owner,
+            &[
+                (MarinadeAccount::State, ctx.accounts.marinade_state.key()),
+                (MarinadeAccount::MsolMint, msol_mint),
+            ],
+        )?;
+        let rate = lst::read_rate(LstKind::Marinade, &ctx.accounts.marinade_state, &msol_mint)?;
+
+        // STEP 1: Value the vault before the deposit lands
+        let current_tvl = vault_tvl_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &feed_prices,
+        )?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let share_price = Vault::calculate_share_price(current_tvl, total_shares)?;
+        msg!("Current TVL: ${} USD, Share Price: ${} USD", current_tvl, share_price);
+
+        // STEP 2: Move the mSOL into the strategy's mSOL account
+        let cpi_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.user_msol_account.to_account_info(),
+            to: ctx.accounts.strategy_msol_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+        let lamports = u64::try_from(amount as u128 * rate as u128 / lst::RATE_SCALE as u128)
+            .map_err(|_| VaultError::MathOverflow)?;
+        msg!("✅ Received {} mSOL worth {} lamports", amount, lamports);
+
+        // STEP 3: Record it with the strategy as staked SOL
+        let signer = vault.signer();
+        drop(vault);
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let cpi_accounts = marinade_strategy::cpi::accounts::RecordDeposit {
+            strategy_account: ctx.accounts.strategy_account.to_account_info(),
+            vault: ctx.accounts.vault.to_account_info(),
+            msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
+            msol_mint: ctx.accounts.msol_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marinade_strategy_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        marinade_strategy::cpi::record_deposit(cpi_ctx, lamports)?;
+        let vault = ctx.accounts.vault.load()?;
+        strategy_value::adjust(&vault, &vault_key, ctx.remaining_accounts, lamports as i64)?;
+
+        // STEP 4: Value the deposit net of the in-kind fee and mint shares
+        let deposit_fee = math::bps_of(amount, vault.deposit_fee_bps)?;
+        let net_lamports = lamports - math::bps_of(lamports, vault.deposit_fee_bps)?;
+        let deposit_usd_micro = sol_price.tokens_to_usd(net_lamports, 9)?;
+        let shares_to_mint = Vault::calculate_shares_for_deposit(deposit_usd_micro, current_tvl, total_shares)?;
+        vault.require_min_shares(shares_to_mint)?;
+        msg!("Deposit: {} lamports = ${} USD (fee: {} mSOL)", net_lamports, deposit_usd_micro, deposit_fee);
+        let lockup_secs = vault.lockup_secs;
+        drop(vault);
+
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.share_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares_to_mint)?;
+        msg!("🪙 Minted {} shares to user", shares_to_mint);
+
+        let position = &mut ctx.accounts.user_position;
+        position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+        position.record_deposit(shares_to_mint, deposit_usd_micro, clock);
+        position.lock_shares(shares_to_mint, lockup_secs, clock.unix_timestamp);
+
+        let new_tvl = current_tvl + sol_price.tokens_to_usd(lamports, 9)?;
+        let new_total_shares = total_shares + shares_to_mint;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+
+        let strategy = ctx.accounts.strategy_account.key();
+        emit_cpi!(StrategyStakedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            strategy,
+            lamports,
+        });
+        emit_cpi!(DepositEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            deposit_mint: msol_mint,
+            amount_deposited: amount,
+            shares_minted: shares_to_mint,
+            tvl_usd: new_tvl,
+        });
+
+        Ok(DepositReceipt {
+            shares_minted: shares_to_mint,
+            deposit_fee,
+            deposit_usd: deposit_usd_micro,
+            share_price,
+            staked_lamports: lamports,
+            allocations: vec![AssetAllocation {
+                mint: msol_mint,
+                usd_value: deposit_usd_micro,
+                token_amount: amount - deposit_fee,
+            }],
+        })
+    }
+
+    /// Start a deposit that executes over several transactions
+    ///
+    /// Values the vault and locks the share price and swap prices in a
+    /// DepositTicket that escrows `amount` lamports; the deposit fee is
+    /// charged slice by slice. deposit_execute then allocates the deposit one basket asset
+    /// at a time within DEPOSIT_TICKET_TTL_SECS; close_deposit_ticket refunds
+    /// whatever was not allocated.
+    /// remaining_accounts: [mint, vault ATA] per asset, price accounts
+    pub fn deposit_prepare<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, DepositPrepare<'info>>,
+        _name: String,
+        amount: u64,
+    ) -> Result<()> {
+        require!(ctx.accounts.deposit_commitment.is_none(), VaultError::InvalidDepositCommitment);
+        let event = prepare_deposit_ticket(&mut ctx, amount)?;
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    /// Commit to a deposit without revealing its amount
+    ///
+    /// Records `commitment` (DepositCommitment::hash of the amount and a
+    /// secret salt) so a large deposit gives searchers nothing to sandwich:
+    /// the swaps it triggers only become predictable once reveal_deposit
+    /// opens it, in a later slot, and are priced at that slot's oracle
+    /// prices (never a PriceCache). One open commitment per user and vault.
+    pub fn commit_deposit(ctx: Context<CommitDeposit>, _name: String, commitment: [u8; 32]) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.require_deposits_open()?;
+
+        let slot = Clock::get()?.slot;
+        let deposit_commitment = &mut ctx.accounts.deposit_commitment;
+        deposit_commitment.vault = vault_key;
+        deposit_commitment.user = ctx.accounts.user.key();
+        deposit_commitment.commitment = commitment;
+        deposit_commitment.committed_slot = slot;
+        deposit_commitment.bump = ctx.bumps.deposit_commitment;
+
+        msg!("🔒 Deposit committed at slot {}", slot);
+
+        emit_cpi!(DepositCommittedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            commitment,
+            slot,
+        });
+
+        Ok(())
+    }
+
+    /// Reveal a committed deposit and prepare it
+    ///
+    /// `amount` and `salt` must open the user's DepositCommitment, committed
+    /// in an earlier slot and at most DEPOSIT_COMMITMENT_TTL_SLOTS ago. The
+    /// deposit is then valued and escrowed exactly as deposit_prepare does,
+    /// at the prices read now, and must buy at least `min_shares_out`; the
+    /// commitment closes to the user, so it opens once. deposit_execute then
+    /// allocates the ticket with every swap held to the ticket's prices, so
+    /// moving a pool once the amount is public cannot cost the depositor
+    /// more than max_slippage_bps.
+    /// remaining_accounts: as deposit_prepare
+    pub fn reveal_deposit<'info>(
+        mut ctx: Context<'_, '_, '_, 'info, DepositPrepare<'info>>,
+        _name: String,
+        amount: u64,
+        salt: [u8; 32],
+        min_shares_out: u64,
+    ) -> Result<()> {
+        let commitment = ctx
+            .accounts
+            .deposit_commitment
+            .as_ref()
+            .ok_or(VaultError::InvalidDepositCommitment)?;
+        require!(commitment.opens(amount, &salt), VaultError::InvalidDepositCommitment);
+        commitment.require_revealable(Clock::get()?.slot)?;
+        // Cached prices may predate the commitment; price from the oracle itself
+        require!(
+            !ctx.remaining_accounts.iter().any(prices::is_price_cache),
+            VaultError::InvalidRemainingAccounts
+        );
+
+        msg!("🔓 Deposit commitment opened: {} lamports", amount);
+        let event = prepare_deposit_ticket(&mut ctx, amount)?;
+        require!(event.shares >= min_shares_out, VaultError::SharesBelowMinimum);
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    /// Drop an unrevealed deposit commitment, returning its rent to the user
+    pub fn cancel_deposit_commitment(ctx: Context<CancelDepositCommitment>, _name: String) -> Result<()> {
+        msg!("🔓 Deposit commitment cancelled");
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(DepositCommitmentCancelledEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Allocate up to `max_assets` more basket assets of a prepared deposit
+    ///
+    /// Each asset's slice of the escrowed lamports moves into the vault, is
+    /// swapped at the ticket's prices (held to max_slippage_bps) and mints
+    /// its slice of the shares, so the deposit can be split wherever the
+    /// transaction runs out of compute or accounts. The SOL slice stays in
+    /// the vault (wrapped where the vault holds wrapped SOL); it is not
+    /// delegated to a strategy. The ticket closes to the user once every
+    /// asset is allocated. Anyone may execute an unexpired ticket.
+    /// remaining_accounts: [mint, vault ATA] per asset, then swap backend
+    /// accounts for this call's allocation swaps.
+    pub fn deposit_execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositExecute<'info>>,
+        _name: String,
+        max_assets: u8,
+    ) -> Result<()> {
+        require!(max_assets > 0, VaultError::InvalidAmount);
+
+        let clock = &ctx.accounts.clock;
+        let ticket = &ctx.accounts.ticket;
+        require!(clock.unix_timestamp <= ticket.expires_at, VaultError::DepositTicketExpired);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, vault.assets().len() * 2)?;
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
+        );
+
+        let asset_count = vault.assets().len();
+        let first = ticket.next_asset as usize;
+        let end = (first + max_assets as usize).min(asset_count);
+        let mut progress: DepositTicket = (***ticket).clone();
+        let mut total = TicketSlice { lamports: 0, fee: 0, usd: 0, shares: 0 };
+        let basket_prices = BasketPrices::from_markets(&vault, &progress.prices);
+        let (sol_slot, lockup_secs) = (vault.sol_slot(), vault.lockup_secs);
+        // The swaps and the share mint take the vault account
+        drop(vault);
+
+        for i in first..end {
+            let weight = ctx.accounts.vault.load()?.deposit_weight(i);
+            let last = i + 1 == asset_count;
+            let slice = progress.next_slice(weight, last)?;
+
+            **ctx.accounts.ticket.to_account_info().try_borrow_mut_lamports()? -= slice.lamports;
+            **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? += slice.lamports;
+
+            let net_lamports = slice.lamports - slice.fee;
+            allocate_asset(&mut swaps, &ctx.accounts.vault, i, net_lamports, slice.usd, &basket_prices)?;
+            if sol_slot == Some(i) {
+                wrap_sol_leg(
+                    &*ctx.accounts.vault.load()?,
+                    ctx.accounts.vault.as_ref(),
+                    ctx.remaining_accounts,
+                    &ctx.accounts.token_program,
+                    net_lamports,
+                )?;
             }
+
+            progress.record_slice(weight, last);
+            total.lamports += slice.lamports;
+            total.fee += slice.fee;
+            total.usd += slice.usd;
+            total.shares += slice.shares;
         }
-        
-        if !needs_rebalance {
-            msg!("✅ No rebalancing needed - all assets within threshold");
-            return Ok(());
+
+        if total.shares > 0 {
+            let cpi_accounts = token_interface::MintTo {
+                mint: ctx.accounts.vault_token_mint.to_account_info(),
+                to: ctx.accounts.user_shares_ata.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.share_token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::mint_to(cpi_ctx, total.shares)?;
         }
-        
-        msg!("🔨 Rebalancing required!");
-        
-        // STEP 5: Execute swaps using MockSwap
-        // Find over-allocated and under-allocated assets
-        for (from_idx, from_drift, excess_usd) in drifts.iter() {
-            if *excess_usd > 0 {
-                // This asset is over-allocated, sell some
-                msg!("   Selling from asset {}", from_idx);
-                
-                for (to_idx, to_drift, deficit_usd) in drifts.iter() {
-                    if *deficit_usd < 0 && from_idx != to_idx {
-                        // This asset is under-allocated, buy some
-                        let swap_usd = (*excess_usd).min((*deficit_usd).abs());
-                        
-                        if swap_usd > 1_000_000 { // Only swap if > $1
-                            msg!("     Swapping ${} from asset {} to asset {}",
-                                swap_usd / 1_000_000, from_idx, to_idx);
-                            
-                            // Calculate swap amount in token terms
-                            let from_asset = &vault.assets[*from_idx];
-                            let to_asset = &vault.assets[*to_idx];
-                            
-                            // Determine token decimals
-                            let from_decimals = get_token_decimals(from_asset.mint)?;
-                            let to_decimals = get_token_decimals(to_asset.mint)?;
-                            
-                            // Calculate input amount: swap_usd / from_price * 10^from_decimals
-                            let amount_in = (swap_usd * 10i64.pow(from_decimals as u32)) / prices[*from_idx];
-                            let amount_in_u64 = amount_in as u64;
-                            
-                            // Use MockSwap to calculate output
-                            let amount_out = MockSwap::calculate_swap_output(
-                                amount_in_u64,
-                                prices[*from_idx],
-                                -6, // MockOracle uses micro-USD (6 decimals)
-                                prices[*to_idx],
-                                -6,
-                                from_decimals,
-                                to_decimals,
-                            )?;
-                            
-                            msg!("       Input: {} (asset {}), Output: {} (asset {})",
-                                amount_in_u64, from_idx, amount_out, to_idx);
-                            
-                            // Note: In production, this would execute actual token transfers
-                            // For now, we just log the intended swaps
-                            // The ATAs need to be updated via CPI to token program
-                        }
-                    }
-                }
+
+        let position = &mut ctx.accounts.user_position;
+        position.record_deposit(total.shares, total.usd, clock);
+        position.lock_shares(total.shares, lockup_secs, clock.unix_timestamp);
+
+        let complete = progress.is_complete();
+        msg!(
+            "🎟️  Allocated assets {}..{}: {} lamports, {} shares minted{}",
+            first,
+            end,
+            total.lamports,
+            total.shares,
+            if complete { " - deposit complete" } else { "" }
+        );
+
+        let new_total_shares = ctx.accounts.vault_token_mint.supply + total.shares;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let new_tvl = vault.last_tvl_usd + total.usd;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+        vault.pending_fees = vault.pending_fees.saturating_add(total.fee);
+
+        let user = ctx.accounts.user.key();
+        emit_cpi!(DepositExecutedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user,
+            assets_allocated: (end - first) as u8,
+            lamports: total.lamports,
+            shares_minted: total.shares,
+            complete,
+        });
+
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.next_asset = progress.next_asset;
+        ticket.allocated_weight = progress.allocated_weight;
+        if complete {
+            if ticket.deposit_fee > 0 {
+                emit_cpi!(FeeCollectedEvent {
+                    vault: vault_key,
+                    seq: vault.next_event_seq(),
+                    user,
+                    fee_bps: vault.deposit_fee_bps,
+                    fee_lamports: ticket.deposit_fee,
+                });
             }
+            emit_cpi!(DepositEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user,
+                deposit_mint: anchor_lang::solana_program::system_program::ID, // SOL
+                amount_deposited: ticket.amount,
+                shares_minted: ticket.shares,
+                tvl_usd: new_tvl,
+            });
+            ticket.close(ctx.accounts.user.to_account_info())?;
         }
-        
-        msg!("✅ Rebalancing complete!");
-        
+
         Ok(())
     }
 
-    /// Rebalance vault using Arcium MXE for confidential computation
-    /// 
-    /// This instruction prevents MEV attacks by encrypting the rebalancing
-    /// computation inputs and outputs using Arcium's Multi-Party Computation.
-    /// 
-    /// **Flow:**
-    /// 1. Client: Fetch vault state and prices, prepare encrypted portfolio
-    /// 2. On-chain: Call Arcium MXE to queue encrypted rebalancing computation
-    /// 3. Arcium MXE: Compute rebalancing in encrypted form
-    /// 4. Callback: Receive encrypted results and execute swaps
-    /// 
-    /// **remaining_accounts layout:**
-    /// - [0]: MockOracle account (for price verification)
-    pub fn rebalance_confidential(
-        ctx: Context<RebalanceConfidential>,
-        vault_name: String,
-        computation_offset: u64,
-        pub_key: [u8; 32],
-        nonce: u128,
-        encrypted_user_funds: [u8; 32],
-    ) -> Result<()> {
-        let vault = &ctx.accounts.vault;
-        
-        // STEP 1: Authorization check
+    /// Close a deposit ticket, refunding the lamports not yet allocated
+    ///
+    /// The user may close their ticket at any time; anyone may close it once
+    /// it has expired. Shares already minted for allocated assets stay.
+    pub fn close_deposit_ticket(ctx: Context<CloseDepositTicket>, _name: String) -> Result<()> {
+        let ticket = &ctx.accounts.ticket;
+        let expired = Clock::get()?.unix_timestamp > ticket.expires_at;
         require!(
-            ctx.accounts.authority.key() == vault.admin,
-            VaultError::Unauthorized
+            expired || ctx.accounts.closer.key() == ticket.user,
+            VaultError::DepositTicketActive
         );
 
-        msg!("🔐 Starting confidential rebalancing for vault: {}", vault.name);
+        // Unallocated lamports leave with the ticket's lamports when it closes
+        let refunded = ticket.unallocated_lamports()?;
+        msg!("🎟️  Deposit ticket closed, {} lamports refunded", refunded);
 
-        // Verify we're using MockOracle
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(DepositTicketClosedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            refunded,
+            expired,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw from multi-asset vault by burning shares
+    /// This function implements proportional withdrawal across all vault assets
+    ///
+    /// **Parameters:**
+    /// - shares: Amount of vault shares to burn
+    /// - name: Vault name for PDA derivation
+    ///
+    /// **Process:**
+    /// 1. Calculate withdrawal percentage (shares_to_burn / total_shares)
+    /// 2. For each asset, calculate proportional amount to withdraw
+    /// 3. Calculate total withdrawal value in USD
+    /// 4. Transfer SOL back to user (the basket's SOL leg, if it has one)
+    /// 5. For every other asset: Calculate swap to SOL and add to user's withdrawal
+    /// 6. Burn user's shares
+    /// 7. Update vault state
+    ///
+    /// Where the vault holds wrapped SOL, the user's share of it is moved into
+    /// `user_wsol_account` and unwrapped by closing that account to the user.
+    ///
+    /// Baskets of other than three assets are priced per asset (see
+    /// prices::fetch_basket_prices); every asset but a native-mint slot is
+    /// swapped to SOL, so a basket without a SOL leg pays out entirely from
+    /// swap proceeds. remaining_accounts are laid out as for deposit_multi_asset.
+    pub fn withdraw_multi_asset<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawMultiAsset<'info>>,
+        _name: String,
+        shares: u64,
+    ) -> Result<()> {
+        require!(shares > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Withdraw)?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+
+        require!(shares <= total_shares, VaultError::InsufficientShares);
         require!(
-            vault.price_source == PriceSource::MockOracle,
-            VaultError::InvalidPrice
+            ctx.accounts.user_shares_ata.amount >= shares,
+            VaultError::InsufficientShares
         );
+
+        // Shares still in their lockup period stay in the user's balance
+        let locked_shares = ctx.accounts.user_position.locked_at(ctx.accounts.clock.unix_timestamp);
         require!(
-            vault.mock_oracle.is_some(),
-            VaultError::InvalidPrice
+            ctx.accounts.user_shares_ata.amount - shares >= locked_shares,
+            VaultError::SharesLocked
         );
 
-        msg!("📡 Queuing encrypted computation to Arcium MXE...");
-
-        // STEP 2: Build instruction data for compute_rebalancing
-        // Instruction discriminator from IDL: [126, 197, 44, 141, 35, 123, 172, 126]
-        // This is the first 8 bytes of SHA256("global:compute_rebalancing")
-        // Total size: 8 + 8 + 32 + 16 + 32 = 96 bytes (fixed size)
-        let mut instruction_data = [0u8; 96];
-        let mut offset = 0;
+        // Validate remaining accounts: we need asset mints and vault ATAs
+        // If using MockOracle or a PriceCache, we need one additional account
+        // If Marinade strategy is set, we need one more account (the strategy)
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let mut expected_accounts = vault.assets().len() * 2 + price_slots;
         
-        // Add discriminator (8 bytes)
-        instruction_data[offset..offset + 8].copy_from_slice(&[126, 197, 44, 141, 35, 123, 172, 126]);
-        offset += 8;
+        if vault.marinade_strategy().is_some() {
+            expected_accounts += 1;
+        }
         
-        // 1. computation_offset: u64 (8 bytes, little-endian)
-        instruction_data[offset..offset + 8].copy_from_slice(&computation_offset.to_le_bytes());
-        offset += 8;
+        msg!(
+            "Withdraw remaining accounts validation: expected {}, got {}",
+            expected_accounts,
+            ctx.remaining_accounts.len()
+        );
         
-        // 2. pub_key: [u8; 32] (32 bytes)
-        instruction_data[offset..offset + 32].copy_from_slice(&pub_key);
-        offset += 32;
+        // Swap backend accounts (if any) follow the accounts above
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
+
+        msg!("🔓 Starting withdrawal of {} shares...", shares);
+
+        // Fetch prices based on configured price source
+        let clock = &ctx.accounts.clock;
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        // Per-asset priced vaults read one feed per asset; the circuit breaker
+        // only watches the BTC/ETH/SOL markets. MockOracle prices must be
+        // within the last 5 minutes
+        let feed_prices = if vault.per_asset_pricing() {
+            None
+        } else {
+            Some(prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?)
+        };
+        let feed_prices = match feed_prices {
+            Some(feed_prices) => {
+                // Circuit breaker: withdrawals stay open, but at conservative prices
+                let breaker = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices);
+                if let Some(event) = breaker.tripped {
+                    emit_cpi!(event);
+                    if let Some(event) = pause_change_event(&mut vault, vault_key, false) {
+                        emit_cpi!(event);
+                    }
+                }
+                Some(if breaker.restricted {
+                    msg!("⚠️  Vault restricted - withdrawing at conservative prices");
+                    prices::conservative_prices(&vault, feed_prices)
+                } else {
+                    feed_prices
+                })
+            },
+            None => None,
+        };
+        let basket_prices = match &feed_prices {
+            Some(feed_prices) => BasketPrices::from_markets(&vault, feed_prices),
+            None => prices::fetch_basket_prices(&vault, &price_accounts, 300)?,
+        };
+        let sol_normalized = basket_prices.sol;
+
+        for (asset, price) in vault.assets().iter().zip(&basket_prices.assets) {
+            msg!("📊 Price of {}: ${}", asset.mint, price.price_usd);
+        }
+        msg!("📊 SOL Price: ${}", sol_normalized.price_usd);
+
+        // STEP 1: Calculate withdrawal percentage
+        // Formula: Withdrawal_Percentage = Shares_to_Burn ÷ Total_Outstanding_Shares
+        // (for logging only; amounts below take their slice as balance × shares ÷ total)
+        let withdrawal_percentage = (shares as u128 * 1_000_000) / (total_shares as u128); // Scale by 1M for precision
+        msg!(
+            "📊 Withdrawal percentage: {}% ({} / {} shares)",
+            (withdrawal_percentage * 100) / 1_000_000,
+            shares,
+            total_shares
+        );
+
+        // STEP 2: Calculate proportional asset amounts and total withdrawal value
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
+        );
+        let sol_asset = SwapAsset {
+            mint: anchor_spl::token::spl_token::native_mint::ID,
+            decimals: 9,
+            price: sol_normalized.original_price,
+            expo: sol_normalized.expo,
+        };
+
+        let mut total_withdrawal_value_usd = 0i64;
+        let mut sol_from_marinade = 0u64;
+        let mut unstaked = None;
+        // The user's share of the vault's wrapped SOL, paid out by unwrapping
+        let mut wsol_share = 0u64;
+
+        // First, check native SOL balance in vault PDA
+        let vault_lamports = ctx.accounts.vault.to_account_info().lamports();
+        let vault_data_len = ctx.accounts.vault.to_account_info().data_len();
+        let rent_exempt_minimum = ctx.accounts.rent.minimum_balance(vault_data_len);
+        let native_sol_balance = vault_lamports
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(vault.fee_reserve());
+        msg!("  Native SOL in vault PDA: {} lamports", native_sol_balance);
+        
+        // Staked SOL is valued from the StrategyValue, which must be fresh
+        let staked_lamports = strategy_value::staked_lamports(&vault, &vault_key, ctx.remaining_accounts)?;
+        if vault.marinade_strategy().is_some() {
+            msg!("  Marinade strategy value: {} lamports (yield included)", staked_lamports);
+        }
+
+        // The swaps, unstake, unwrap and burn below all take the vault PDA,
+        // so what they need of its data is read before it is released
+        let asset_mints: Vec<Pubkey> = vault.assets().iter().map(|asset| asset.mint).collect();
+        let sol_slot = vault.sol_slot();
+        let holds_wsol = vault.wrapped_sol_ata().is_some();
+        let sol_leg = match sol_slot {
+            Some(slot) => sol_leg_balance(
+                &vault,
+                ctx.accounts.vault.as_ref(),
+                &ctx.remaining_accounts[slot * 2 + 1],
+                &ctx.accounts.rent,
+            )?,
+            None => 0,
+        };
+        let marinade_strategy = vault.marinade_strategy();
+        let (max_exit_fee_bps, exit_fee_window_secs) = (vault.exit_fee_bps, vault.exit_fee_window_secs);
+        let fee_reserve = vault.fee_reserve();
+        drop(vault);
+
+        for (i, &asset_mint) in asset_mints.iter().enumerate() {
+            let ata_account_info = &ctx.remaining_accounts[i * 2 + 1];
+            
+            // Get current balance from ATA (the SOL leg may be native lamports)
+            let current_balance = if sol_slot == Some(i) {
+                sol_leg
+            } else {
+                token_io::token_amount(ata_account_info)?
+            };
+
+            // Calculate proportional amount to withdraw
+            // Formula: Amount_to_Withdraw = Current_Asset_Amount × Withdrawal_Percentage
+            let amount_to_withdraw = math::pro_rata(current_balance, shares, total_shares)?;
+
+            // Get asset info
+            let decimals = basket_prices.decimals[i];
+            let price = &basket_prices.assets[i];
+
+            // Calculate USD value of this withdrawal
+            let asset_value_usd = price.tokens_to_usd(amount_to_withdraw, decimals)?;
+            total_withdrawal_value_usd = total_withdrawal_value_usd
+                .checked_add(asset_value_usd)
+                .ok_or(VaultError::MathOverflow)?;
+
+            msg!(
+                "  • {} of {}: ${} USD",
+                amount_to_withdraw,
+                asset_mint,
+                asset_value_usd
+            );
+
+            // For SOL: We use native SOL from the vault PDA (not SPL tokens)
+            // The actual SOL withdrawal will be calculated after checking Marinade
+            // Note: During deposits, SOL goes as native lamports, not SPL tokens
+            // Every other asset: Swap to SOL through the swap backend
+            if sol_slot == Some(i) {
+                // SOL withdrawal will be calculated after Marinade unstaking
+                // We need to know: vault native balance + Marinade holdings
+                msg!("    → SOL withdrawal will be calculated from native balance + Marinade");
+                if holds_wsol {
+                    wsol_share = amount_to_withdraw;
+                }
+            } else {
+                // Only swap if we have a non-zero amount
+                if amount_to_withdraw > 0 {
+                    let source = SwapAsset {
+                        mint: asset_mint,
+                        decimals,
+                        price: price.original_price,
+                        expo: price.expo,
+                    };
+                    let min_out = swaps.min_out(&source, &sol_asset, amount_to_withdraw)?;
+                    let sol_equivalent = swaps.execute_swap(&source, &sol_asset, amount_to_withdraw, min_out)?;
+                    msg!(
+                        "    → Swapped {} of {} to {} SOL equivalent",
+                        amount_to_withdraw,
+                        asset_mint,
+                        sol_equivalent
+                    );
+                } else {
+                    msg!("    → No {} balance to withdraw", asset_mint);
+                }
+            }
+        }
+
+        // Orca pays SOL proceeds into the vault's wSOL ATA rather than the
+        // PDA's lamports, so they are unwrapped along with the SOL leg; the
+        // backends that credit lamports (Mock, MockAmm, Sanctum) add nothing here
+        let wsol_swapped = match sol_slot.filter(|_| holds_wsol) {
+            Some(slot) => token_io::token_amount(&ctx.remaining_accounts[slot * 2 + 1])?.saturating_sub(sol_leg),
+            None => 0,
+        };
+
+        // STEP 2.5: Handle Marinade unstaking if strategy is active
+        if let Some(strategy_key) = marinade_strategy {
+            msg!("🌊 Marinade strategy detected - unstaking proportional mSOL!");
+            verify_unstake_accounts(
+                &ctx.accounts.marinade_program,
+                &ctx.accounts.marinade_state,
+                &ctx.accounts.msol_mint,
+                &ctx.accounts.liq_pool_msol_leg,
+                &ctx.accounts.liq_pool_sol_leg_pda,
+                &ctx.accounts.treasury_msol_account,
+            )?;
+            
+            // Find the strategy account in remaining_accounts
+            let expected_strategy_index = asset_mints.len() * 2 + price_slots; // After price account
+            
+            if ctx.remaining_accounts.len() > expected_strategy_index {
+                let strategy_account_info = &ctx.remaining_accounts[expected_strategy_index];
+                
+                if strategy_account_info.key() == strategy_key {
+                    msg!("   Strategy account found in remaining_accounts");
+                    
+                    // Read strategy account to get mSOL balance
+                    let strategy_data = strategy_account_info.try_borrow_data()?;
+                    let mut strategy_slice = &strategy_data[..];
+                    let strategy = marinade_strategy::StrategyAccount::try_deserialize(&mut strategy_slice)?;
+                    drop(strategy_data);
+                    
+                    let total_msol = strategy.msol_balance;
+                    let initial_staked = strategy.total_staked;
+                    
+                    msg!("   Total mSOL in strategy: {}", total_msol);
+                    msg!("   Initial SOL staked: {}", initial_staked);
+                    
+                    // Calculate proportional mSOL to unstake
+                    let msol_to_unstake = math::pro_rata(total_msol, shares, total_shares)?;
+                    
+                    if msol_to_unstake > 0 {
+                        msg!("   Unstaking {} mSOL ({}% of total)", msol_to_unstake, (withdrawal_percentage * 100) / 1_000_000);
+                        
+                        // Build CPI context for marinade_strategy::unstake
+                        let cpi_accounts = marinade_strategy::cpi::accounts::Unstake {
+                            strategy_account: strategy_account_info.clone(),
+                            vault: ctx.accounts.vault.to_account_info(),
+                            sol_receiver: ctx.accounts.sol_receiver.to_account_info(), // System-owned account
+                            marinade_state: ctx.accounts.marinade_state.to_account_info(),
+                            msol_mint: ctx.accounts.msol_mint.to_account_info(),
+                            liq_pool_msol_leg: ctx.accounts.liq_pool_msol_leg.to_account_info(),
+                            liq_pool_sol_leg_pda: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+                            msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
+                            treasury_msol_account: ctx.accounts.treasury_msol_account.to_account_info(),
+                            marinade_program: ctx.accounts.marinade_program.to_account_info(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                            token_program: ctx.accounts.token_program.to_account_info(),
+                        };
+                        
+                        let cpi_ctx = CpiContext::new_with_signer(
+                            ctx.accounts.marinade_strategy_program.to_account_info(),
+                            cpi_accounts,
+                            signer_seeds,
+                        );
+                        
+                        // Record receiver balance before unstaking (Marinade will transfer to receiver)
+                        let receiver_balance_before = ctx.accounts.sol_receiver.to_account_info().lamports();
+                        
+                        // Execute unstake - Marinade will return SOL to receiver (including yield!)
+                        marinade_strategy::cpi::unstake(cpi_ctx, msol_to_unstake)?;
+                        
+                        // Calculate SOL received by receiver from Marinade (includes yield)
+                        let receiver_balance_after = ctx.accounts.sol_receiver.to_account_info().lamports();
+                        let sol_received_from_marinade = receiver_balance_after.saturating_sub(receiver_balance_before);
+                        
+                        sol_from_marinade = sol_received_from_marinade;
+                        
+                        // SOL was already transferred to receiver by Marinade
+                        // Don't add to total_sol_to_return since it's not in the vault
+                        
+                        // Calculate yield
+                        let proportional_initial = math::pro_rata(initial_staked, shares, total_shares)?;
+                        let yield_earned = sol_received_from_marinade.saturating_sub(proportional_initial);
+                        
+                        msg!("   ✅ Unstaked {} mSOL", msol_to_unstake);
+                        msg!("   📥 Received {} SOL from Marinade (transferred to user)", sol_received_from_marinade);
+                        msg!("   🎁 Yield earned: {} lamports", yield_earned);
+
+                        // The cached value shrinks by the share of the position unstaked
+                        let unstaked_value = math::pro_rata(staked_lamports, shares, total_shares)?;
+                        strategy_value::adjust(
+                            &*ctx.accounts.vault.load()?,
+                            &vault_key,
+                            ctx.remaining_accounts,
+                            -(unstaked_value as i64),
+                        )?;
+                        unstaked = Some((strategy_key, msol_to_unstake, sol_received_from_marinade, yield_earned));
+                    } else {
+                        msg!("   No mSOL to unstake for this withdrawal amount");
+                    }
+                } else {
+                    msg!("   ⚠️  Strategy account mismatch in remaining_accounts");
+                }
+            } else {
+                msg!("   ⚠️  Strategy account not provided in remaining_accounts");
+            }
+        }
+
+        // STEP 2.6: Split the payout between the vault and the strategy
+        // The basket slice is converted to SOL at the oracle price; the staked
+        // slice is valued explicitly from the StrategyValue (yield included)
+        // and paid by Marinade, so the vault's leg never depends on what the
+        // unstake actually returned
+        let basket_lamports = u64::try_from(sol_normalized.usd_to_tokens(total_withdrawal_value_usd, 9)?)
+            .map_err(|_| VaultError::MathOverflow)?;
+        let staked_share = math::pro_rata(staked_lamports, shares, total_shares)?;
+        require!(
+            staked_share == 0 || unstaked.is_some(),
+            VaultError::InvalidStrategyAccount
+        );
+        let staked_value_usd = sol_normalized.tokens_to_usd(staked_share, 9)?;
+        total_withdrawal_value_usd = total_withdrawal_value_usd
+            .checked_add(staked_value_usd)
+            .ok_or(VaultError::MathOverflow)?;
+
+        // Exit fee decays from exit_fee_bps at the last deposit to zero over the window
+        // The fee simply stays in the vault, raising TVL for remaining holders
+        let exit_fee_bps = ctx.accounts.user_position.exit_fee_bps(
+            max_exit_fee_bps,
+            exit_fee_window_secs,
+            clock.unix_timestamp,
+        );
+        let payout = math::split_withdraw_payout(basket_lamports, staked_share, exit_fee_bps)?;
+        let exit_fee = payout.exit_fee;
+        if exit_fee > 0 {
+            msg!("   Exit fee: {} bps = {} lamports (kept by vault)", exit_fee_bps, exit_fee);
+        }
         
-        // 3. nonce: u128 (16 bytes, little-endian)
-        instruction_data[offset..offset + 16].copy_from_slice(&nonce.to_le_bytes());
-        offset += 16;
+        msg!("   Total withdrawal value: ${} USD (micro)", total_withdrawal_value_usd);
+        msg!("   SOL price: ${} USD (micro)", sol_normalized.price_usd);
+        msg!("   Basket SOL: {} lamports, staked SOL: {} lamports", basket_lamports, staked_share);
+        msg!("   SOL already unstaked from Marinade: {} lamports", sol_from_marinade);
         
-        // 4. encrypted_user_funds: [u8; 32] (32 bytes) - Just ONE encrypted value
-        instruction_data[offset..offset + 32].copy_from_slice(&encrypted_user_funds);
-        offset += 32;
+        let total_sol_to_return = payout.from_vault;
+        // Wrapped SOL covers the user's share of the SOL leg and any swap
+        // proceeds paid in wSOL; the rest is native
+        let sol_from_wsol = wsol_share
+            .checked_add(wsol_swapped)
+            .ok_or(VaultError::MathOverflow)?
+            .min(total_sol_to_return);
+        let sol_from_native = total_sol_to_return - sol_from_wsol;
         
-        msg!("   Instruction data size: {} bytes", offset);
-        msg!("   Expected: 8 (discriminator) + 8 (offset) + 32 (pub_key) + 16 (nonce) + 32 (funds) = 96 bytes");
-
-        // STEP 3: Build account metas for CPI
-        use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
-        use anchor_lang::solana_program::program::invoke;
+        msg!("   Vault native SOL to withdraw: {} lamports", sol_from_native);
+        if sol_from_wsol > 0 {
+            msg!("   Vault wrapped SOL to unwrap: {} lamports", sol_from_wsol);
+        }
         
-        let account_metas = [
-            AccountMeta::new(ctx.accounts.authority.key(), true),             // payer (signer, mut)
-            AccountMeta::new(ctx.accounts.sign_pda_account.key(), false),     // sign_pda_account (mut)
-            AccountMeta::new_readonly(ctx.accounts.mxe_account.key(), false), // mxe_account
-            AccountMeta::new(ctx.accounts.mempool_account.key(), false),      // mempool_account (mut)
-            AccountMeta::new(ctx.accounts.executing_pool.key(), false),       // executing_pool (mut)
-            AccountMeta::new(ctx.accounts.computation_account.key(), false),  // computation_account (mut)
-            AccountMeta::new_readonly(ctx.accounts.comp_def_account.key(), false), // comp_def_account
-            AccountMeta::new(ctx.accounts.cluster_account.key(), false),      // cluster_account (mut)
-            AccountMeta::new(ctx.accounts.pool_account.key(), false),         // pool_account (mut)
-            AccountMeta::new_readonly(ctx.accounts.clock_account.key(), false), // clock_account
-            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // system_program
-            AccountMeta::new_readonly(ctx.accounts.arcium_program.key(), false), // arcium_program
-        ];
+        // Verify vault has enough SOL
+        let current_vault_lamports = ctx.accounts.vault.to_account_info().lamports();
+        let available_sol = current_vault_lamports
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(fee_reserve);
+        msg!("   Available SOL in vault: {} lamports", available_sol);
+        
+        require!(
+            available_sol >= sol_from_native,
+            VaultError::InsufficientBalance
+        );
 
-        let ix = Instruction {
-            program_id: ctx.accounts.arcium_mxe_program.key(),
-            accounts: account_metas.to_vec(),
-            data: instruction_data.to_vec(),
-        };
+        msg!(
+            "💰 Total withdrawal value: ${} USD",
+            total_withdrawal_value_usd
+        );
+        msg!(
+            "   SOL from native/SPL: {} lamports",
+            sol_from_native
+        );
+        if sol_from_marinade > 0 {
+            msg!(
+                "   SOL from Marinade (with yield, already sent to receiver): {} lamports",
+                sol_from_marinade
+            );
+        }
+        msg!("   SOL to transfer from vault: {} lamports", total_sol_to_return);
+        msg!("   Total SOL user will have received: {} lamports (vault: {}, marinade receiver: {})", total_sol_to_return + sol_from_marinade, total_sol_to_return, sol_from_marinade);
 
-        // STEP 4: Invoke the Arcium MXE program
-        invoke(
-            &ix,
-            &[
-                ctx.accounts.authority.to_account_info(),
-                ctx.accounts.sign_pda_account.to_account_info(),
-                ctx.accounts.mxe_account.to_account_info(),
-                ctx.accounts.mempool_account.to_account_info(),
-                ctx.accounts.executing_pool.to_account_info(),
-                ctx.accounts.computation_account.to_account_info(),
-                ctx.accounts.comp_def_account.to_account_info(),
-                ctx.accounts.cluster_account.to_account_info(),
-                ctx.accounts.pool_account.to_account_info(),
-                ctx.accounts.clock_account.to_account_info(),
-                ctx.accounts.system_program.to_account_info(),
-                ctx.accounts.arcium_program.to_account_info(),
-            ],
-        )?;
+        // STEP 3: Transfer remaining SOL from vault to user
+        // Note: Marinade SOL was already sent directly to user above
+        if sol_from_native > 0 {
+            msg!("💸 Transferring {} SOL from vault to user...", sol_from_native);
+            **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= sol_from_native;
+            **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += sol_from_native;
+        }
+        if sol_from_wsol > 0 {
+            let user_wsol_account = ctx
+                .accounts
+                .user_wsol_account
+                .as_ref()
+                .ok_or(VaultError::WsolAccountRequired)?;
+            let sol_slot = sol_slot.ok_or(VaultError::InvalidATA)?;
+            token_io::unwrap_sol(
+                &ctx.accounts.vault.to_account_info(),
+                &ctx.remaining_accounts[sol_slot * 2 + 1],
+                &user_wsol_account.to_account_info(),
+                &ctx.accounts.user.to_account_info(),
+                &ctx.accounts.token_program.to_account_info(),
+                sol_from_wsol,
+                signer_seeds,
+            )?;
+            msg!("🎁 Unwrapped {} lamports of wrapped SOL to user", sol_from_wsol);
+        }
 
-        msg!("✅ Encrypted computation queued successfully!");
-        msg!("   Computation offset: {}", computation_offset);
-        msg!("   Portfolio data: [ENCRYPTED - 13 assets]");
-        msg!("   MEV protection: ACTIVE");
-        msg!("   Awaiting MXE callback with encrypted results...");
+        // STEP 4: Burn shares
+        msg!("🔥 Burning {} shares...", shares);
         
-        Ok(())
-    }
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            from: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let burn_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_accounts);
+        token_interface::burn(burn_ctx, shares)?;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        let position = &mut ctx.accounts.user_position;
+        position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+        let realized_pnl_usd = position.record_withdrawal(shares, total_withdrawal_value_usd, clock);
+
+        // STEP 5: Calculate new vault state
+        let new_total_shares = total_shares - shares;
+        
+        // Recalculate TVL with remaining assets
+        let new_tvl = if let Some([btc_normalized, eth_normalized, sol_normalized]) = &feed_prices {
+            let mut btc_remaining = 0u64;
+            let mut eth_remaining = 0u64;
+            let mut sol_remaining = 0u64;
+
+            for i in 0..vault.assets().len() {
+                let ata_info = &ctx.remaining_accounts[i * 2 + 1];
+                match i {
+                    0 => btc_remaining = token_io::token_amount(ata_info)?,
+                    1 => eth_remaining = token_io::token_amount(ata_info)?,
+                    2 => {
+                        sol_remaining =
+                            sol_leg_balance(&vault, ctx.accounts.vault.as_ref(), ata_info, &ctx.accounts.rent)?
+                    }
+                    _ => {}
+                }
+            }
+
+            let staked_remaining = strategy_value::staked_lamports(&vault, &vault_key, ctx.remaining_accounts)?;
+            Vault::calculate_tvl_from_balances(
+                btc_remaining,
+                eth_remaining,
+                sol_remaining,
+                btc_normalized,
+                eth_normalized,
+                sol_normalized,
+            )? + sol_normalized.tokens_to_usd(staked_remaining, 9)?
+        } else {
+            basket_tvl_usd(&vault, &vault_key, ctx.remaining_accounts, &basket_prices)?
+        };
+
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+
+        msg!("✅ Withdrawal Complete!");
+        msg!("   Withdrawn: ${} USD in {} SOL", total_withdrawal_value_usd, total_sol_to_return);
+        msg!("   New TVL: ${} USD", new_tvl);
+        msg!("   New Total Shares: {}", new_total_shares);
+        msg!("   New Share Price: ${} USD", new_share_price);
+
+        let now = clock.unix_timestamp;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, now);
+        vault.end_operation();
+
+        if let Some((strategy, msol_amount, lamports_received, yield_lamports)) = unstaked {
+            emit_cpi!(StrategyUnstakedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                strategy,
+                msol_amount,
+                lamports_received,
+                yield_lamports,
+            });
+        }
+        if exit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user: ctx.accounts.user.key(),
+                fee_bps: exit_fee_bps,
+                fee_lamports: exit_fee,
+            });
+        }
+
+        // Emit withdrawal event
+        emit_cpi!(WithdrawEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            shares_burned: shares,
+            withdraw_mint: anchor_lang::solana_program::system_program::ID, // SOL
+            amount_withdrawn: total_sol_to_return,
+            exit_fee,
+            tvl_usd: new_tvl,
+            realized_pnl_usd,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw from a multi-asset vault into a single token of the user's choice
+    ///
+    /// **Parameters:**
+    /// - name: Vault name for PDA derivation
+    /// - shares: Amount of vault shares to burn
+    /// - min_out: Minimum amount of `output_mint` the user receives; basket
+    ///   assets are also held to the oracle quote less max_slippage_bps
+    ///
+    /// **Process:**
+    /// 1. Take the user's proportional share of every asset
+    /// 2. Swap BTC and ETH into SOL, then all of it into `output_mint`
+    /// 3. Keep the exit fee (in SOL) in the vault
+    /// 4. Transfer the output to the user's ATA and burn the shares
+    ///
+    /// Needs a backend that moves tokens (not Mock) and a vault without a
+    /// strategy, since staked SOL cannot be swapped in the same transaction.
+    /// Vaults holding wrapped SOL withdraw through withdraw_multi_asset.
+    /// remaining_accounts: [mint, vault ATA] per asset, price accounts, then
+    /// swap backend accounts for BTC -> SOL, ETH -> SOL and SOL -> output.
+    pub fn withdraw_zap<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawZap<'info>>,
+        _name: String,
+        shares: u64,
+        min_out: u64,
+    ) -> Result<()> {
+        require!(shares > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Withdraw)?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        require!(shares <= total_shares, VaultError::InsufficientShares);
+        require!(
+            ctx.accounts.user_shares_ata.amount >= shares,
+            VaultError::InsufficientShares
+        );
+        let locked_shares = ctx.accounts.user_position.locked_at(ctx.accounts.clock.unix_timestamp);
+        require!(
+            ctx.accounts.user_shares_ata.amount - shares >= locked_shares,
+            VaultError::SharesLocked
+        );
+
+        require!(vault.marinade_strategy().is_none(), VaultError::StrategyActive);
+        require!(vault.swap_backend() != SwapBackend::Mock, VaultError::UnsupportedSwapPair);
+        require!(vault.wrapped_sol_ata().is_none(), VaultError::WrappedSolUnsupported);
+        // SOL withdrawals go through withdraw_multi_asset
+        let output_mint = ctx.accounts.output_mint.key();
+        require!(
+            output_mint != anchor_spl::token::spl_token::native_mint::ID,
+            VaultError::InvalidMint
+        );
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let expected_accounts = vault.assets().len() * 2 + price_slots;
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
+
+        let clock = &ctx.accounts.clock;
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        // Circuit breaker: withdrawals stay open, but at conservative prices
+        let breaker = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices);
+        if let Some(event) = breaker.tripped {
+            emit_cpi!(event);
+            if let Some(event) = pause_change_event(&mut vault, vault_key, false) {
+                emit_cpi!(event);
+            }
+        }
+        let withdraw_prices = if breaker.restricted {
+            msg!("⚠️  Vault restricted - withdrawing at conservative prices");
+            prices::conservative_prices(&vault, feed_prices)
+        } else {
+            feed_prices
+        };
+        let [btc_normalized, eth_normalized, sol_normalized] = withdraw_prices;
+
+        // STEP 1: The user's share of native SOL, measured before swaps credit the vault
+        let rent_exempt_minimum = ctx.accounts.rent.minimum_balance(ctx.accounts.vault.to_account_info().data_len());
+        let native_sol_balance = ctx.accounts.vault.to_account_info().lamports()
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(vault.fee_reserve());
+        let mut total_sol = math::pro_rata(native_sol_balance, shares, total_shares)?;
+        let mut total_withdrawal_value_usd = sol_normalized.tokens_to_usd(total_sol, 9)?;
+
+        // STEP 2: Swap the user's share of BTC and ETH into SOL
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
+        );
+        let sol_asset = SwapAsset {
+            mint: anchor_spl::token::spl_token::native_mint::ID,
+            decimals: 9,
+            price: sol_normalized.original_price,
+            expo: sol_normalized.expo,
+        };
+        let assets = vault.assets().to_vec();
+        let asset_decimals: Vec<u8> = (0..assets.len()).map(|i| vault.asset_decimals(i)).collect();
+        let (max_exit_fee_bps, exit_fee_window_secs) = (vault.exit_fee_bps, vault.exit_fee_window_secs);
+        let quote = ctx.accounts.quote_config.as_deref();
+        let output_asset = basket_swap_asset(&vault, output_mint, &withdraw_prices)
+            .or_else(|| quote_swap_asset(quote, output_mint));
+        // The swaps credit the vault PDA, and the payout and burn take it
+        drop(vault);
+
+        for (i, asset) in assets.iter().enumerate() {
+            let price = match i {
+                0 => &btc_normalized,
+                1 => &eth_normalized,
+                _ => continue,
+            };
+            let decimals = asset_decimals[i];
+            let balance = token_io::token_amount(&ctx.remaining_accounts[i * 2 + 1])?;
+
+            let amount_to_withdraw = math::pro_rata(balance, shares, total_shares)?;
+            if amount_to_withdraw == 0 {
+                continue;
+            }
+            total_withdrawal_value_usd += price.tokens_to_usd(amount_to_withdraw, decimals)?;
+
+            let source = SwapAsset {
+                mint: asset.mint,
+                decimals,
+                price: price.original_price,
+                expo: price.expo,
+            };
+            let swap_min_out = swaps.min_out(&source, &sol_asset, amount_to_withdraw)?;
+            let sol_out = swaps.execute_swap(&source, &sol_asset, amount_to_withdraw, swap_min_out)?;
+            msg!("  • Swapped {} of {} to {} SOL", amount_to_withdraw, asset.mint, sol_out);
+            total_sol += sol_out;
+        }
+
+        // STEP 3: Exit fee stays in the vault as SOL
+        let exit_fee_bps = ctx.accounts.user_position.exit_fee_bps(
+            max_exit_fee_bps,
+            exit_fee_window_secs,
+            clock.unix_timestamp,
+        );
+        let exit_fee = math::bps_of(total_sol, exit_fee_bps)?;
+        let sol_to_swap = total_sol - exit_fee;
+
+        // STEP 4: Swap into the output token; basket assets and a USDC
+        // vault's USDC also get the oracle bound
+        let (output_asset, min_amount_out) = match output_asset {
+            Some(output_asset) => {
+                let oracle_min = swaps.min_out(&sol_asset, &output_asset, sol_to_swap)?;
+                (output_asset, min_out.max(oracle_min))
+            }
+            None => (SwapAsset::unpriced(output_mint), min_out),
+        };
+        let amount_out = swaps.execute_swap(&sol_asset, &output_asset, sol_to_swap, min_amount_out)?;
+        msg!("🔄 Swapped {} lamports into {} of {}", sol_to_swap, amount_out, output_mint);
+
+        // STEP 5: Pay the user and burn their shares
+        let transfer_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.vault_output_account.to_account_info(),
+            to: ctx.accounts.user_output_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token::transfer(transfer_ctx, amount_out)?;
+
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            from: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let burn_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_accounts);
+        token_interface::burn(burn_ctx, shares)?;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        let position = &mut ctx.accounts.user_position;
+        position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+        let realized_pnl_usd = position.record_withdrawal(shares, total_withdrawal_value_usd, clock);
+
+        // STEP 6: Value what is left
+        let new_total_shares = total_shares - shares;
+        let new_tvl = vault_tvl_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &withdraw_prices,
+        )?;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+        msg!("✅ Zap withdrawal complete: {} of {} for {} shares", amount_out, output_mint, shares);
+
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+
+        if exit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user: ctx.accounts.user.key(),
+                fee_bps: exit_fee_bps,
+                fee_lamports: exit_fee,
+            });
+        }
+        emit_cpi!(WithdrawEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            shares_burned: shares,
+            withdraw_mint: output_mint,
+            amount_withdrawn: amount_out,
+            exit_fee,
+            tvl_usd: new_tvl,
+            realized_pnl_usd,
+        });
+
+        Ok(())
+    }
+
+    /// Create a recurring deposit into the vault
+    ///
+    /// Pass `funding_mint` to fund the schedule in a token (e.g. USDC), which
+    /// is swapped into SOL at each execution; omit it to fund in SOL. The
+    /// schedule holds no funds until fund_dca_schedule. Executions run at
+    /// most every `interval_secs`, starting immediately.
+    pub fn create_dca_schedule(
+        ctx: Context<CreateDcaSchedule>,
+        _name: String,
+        amount_per_period: u64,
+        interval_secs: i64,
+        min_sol_out: u64,
+    ) -> Result<()> {
+        require!(amount_per_period > 0, VaultError::InvalidAmount);
+        require!(interval_secs >= MIN_DCA_INTERVAL_SECS, VaultError::InvalidDcaSchedule);
+        ctx.accounts.vault.load()?.require_deposits_open()?;
+
+        let funding_mint = ctx.accounts.funding_mint.as_ref().map(|mint| mint.key());
+        require!(
+            funding_mint != Some(anchor_spl::token::spl_token::native_mint::ID),
+            VaultError::InvalidDcaSchedule
+        );
+
+        let vault_key = ctx.accounts.vault.key();
+        let user = ctx.accounts.user.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        // Shares are minted into the user's ATA and position by the crank
+        ctx.accounts.user_position.init_if_new(vault_key, user, ctx.bumps.user_position);
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.vault = vault_key;
+        schedule.user = user;
+        schedule.funding_mint = funding_mint;
+        schedule.amount_per_period = amount_per_period;
+        schedule.min_sol_out = if funding_mint.is_some() { min_sol_out } else { 0 };
+        schedule.interval_secs = interval_secs;
+        schedule.next_execution_ts = now;
+        schedule.remaining = 0;
+        schedule.executions = 0;
+        schedule.bump = ctx.bumps.schedule;
+
+        msg!(
+            "📅 DCA schedule created: {} every {}s",
+            amount_per_period,
+            interval_secs
+        );
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(DcaScheduleCreatedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            schedule: ctx.accounts.schedule.key(),
+            user,
+            funding_mint,
+            amount_per_period,
+            interval_secs,
+        });
+
+        Ok(())
+    }
+
+    /// Add `amount` to a DCA schedule's balance
+    /// SOL schedules take lamports from the user; token schedules need the
+    /// user's token account and the schedule's ATA for the funding mint
+    pub fn fund_dca_schedule(ctx: Context<FundDcaSchedule>, _name: String, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let schedule = &ctx.accounts.schedule;
+        match schedule.funding_mint {
+            None => transfer_lamports_in(
+                &ctx.accounts.user,
+                &schedule.to_account_info(),
+                &ctx.accounts.system_program,
+                amount,
+            )?,
+            Some(mint) => {
+                let (Some(from), Some(to)) = (
+                    ctx.accounts.user_funding_account.as_ref(),
+                    ctx.accounts.schedule_funding_account.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidDcaSchedule);
+                };
+                require!(
+                    from.mint == mint && to.mint == mint && to.owner == schedule.key(),
+                    VaultError::InvalidDcaSchedule
+                );
+                let cpi_accounts = anchor_spl::token::Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+                anchor_spl::token::transfer(cpi_ctx, amount)?;
+            }
+        }
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.remaining = schedule.remaining.saturating_add(amount);
+        msg!("💰 DCA schedule funded with {} ({} remaining)", amount, schedule.remaining);
+
+        Ok(())
+    }
+
+    /// Execute a due DCA period: deposit into the vault on the user's behalf
+    /// Permissionless - anyone may crank a schedule once it is due
+    ///
+    /// Follows deposit_zap for token schedules; the SOL share of the deposit
+    /// stays in the vault rather than going to the Marinade strategy.
+    /// remaining_accounts: [mint, vault ATA] per asset, price accounts, then
+    /// swap backend accounts (funding token -> SOL first for token schedules,
+    /// then the allocation swaps).
+    pub fn execute_dca<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteDca<'info>>,
+        _name: String,
+    ) -> Result<()> {
+        let clock = &ctx.accounts.clock;
+        let now = clock.unix_timestamp;
+        let schedule = &ctx.accounts.schedule;
+        let amount = schedule.due_amount(now).ok_or(VaultError::DcaNotDue)?;
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let expected_accounts = vault.assets().len() * 2 + price_slots;
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        let restricted = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices).restricted;
+        require!(!restricted, VaultError::VaultRestricted);
+
+        // STEP 1: Value the vault before any of the deposit arrives
+        let current_tvl = vault_tvl_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &feed_prices,
+        )?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
+        );
+        let lockup_secs = vault.lockup_secs;
+        // The funds, the swaps and the share mint all take the vault account
+        drop(vault);
+
+        // STEP 2: Move this period's funds into the vault as SOL
+        let schedule = &ctx.accounts.schedule;
+        let sol_received = match schedule.funding_mint {
+            None => {
+                **schedule.to_account_info().try_borrow_mut_lamports()? -= amount;
+                **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? += amount;
+                amount
+            }
+            Some(mint) => {
+                let (Some(from), Some(to)) = (
+                    ctx.accounts.schedule_funding_account.as_ref(),
+                    ctx.accounts.vault_input_account.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidDcaSchedule);
+                };
+                require!(
+                    from.mint == mint && to.mint == mint && to.owner == vault_key,
+                    VaultError::InvalidDcaSchedule
+                );
+
+                let schedule_seeds = &[
+                    b"dca".as_ref(),
+                    schedule.vault.as_ref(),
+                    schedule.user.as_ref(),
+                    &[schedule.bump],
+                ];
+                let schedule_signer = &[&schedule_seeds[..]];
+                let cpi_accounts = anchor_spl::token::Transfer {
+                    from: from.to_account_info(),
+                    to: to.to_account_info(),
+                    authority: schedule.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    schedule_signer,
+                );
+                anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+                let min_sol_out = schedule.min_sol_out_for(amount)?;
+                let sol_out = swap_to_sol(
+                    &mut swaps,
+                    &ctx.accounts.vault,
+                    None,
+                    mint,
+                    amount,
+                    min_sol_out,
+                    &feed_prices,
+                )?;
+                msg!("🔄 Swapped {} of {} into {} lamports", amount, mint, sol_out);
+                sol_out
+            }
+        };
+
+        // STEP 3: Allocate it like a SOL deposit and mint the user's shares
+        let ReceivedDeposit {
+            deposit_fee,
+            deposit_usd,
+            shares,
+            sol_leg,
+            ..
+        } = deposit_received_sol(
+            &mut swaps,
+            &ctx.accounts.vault,
+            sol_received,
+            current_tvl,
+            total_shares,
+            &feed_prices,
+        )?;
+        wrap_sol_leg(
+            &*ctx.accounts.vault.load()?,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program,
+            sol_leg,
+        )?;
+
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.share_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares)?;
+
+        let position = &mut ctx.accounts.user_position;
+        position.record_deposit(shares, deposit_usd, clock);
+        position.lock_shares(shares, lockup_secs, now);
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.record_execution(amount, now);
+        msg!(
+            "📅 DCA execution {}: {} shares minted, {} remaining",
+            schedule.executions,
+            shares,
+            schedule.remaining
+        );
+
+        let new_tvl = current_tvl + deposit_usd;
+        let new_total_shares = total_shares + shares;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, now);
+        vault.pending_fees = vault.pending_fees.saturating_add(deposit_fee);
+        vault.end_operation();
+
+        let user = ctx.accounts.user.key();
+        if deposit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user,
+                fee_bps: vault.deposit_fee_bps,
+                fee_lamports: deposit_fee,
+            });
+        }
+        emit_cpi!(DepositEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user,
+            deposit_mint: schedule
+                .funding_mint
+                .unwrap_or(anchor_lang::solana_program::system_program::ID),
+            amount_deposited: amount,
+            shares_minted: shares,
+            tvl_usd: new_tvl,
+        });
+        emit_cpi!(DcaExecutedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            schedule: schedule.key(),
+            user,
+            amount,
+            shares_minted: shares,
+            remaining: schedule.remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a DCA schedule, refunding whatever has not been deposited
+    /// Token schedules also close the schedule's token account
+    pub fn cancel_dca_schedule(ctx: Context<CancelDcaSchedule>, _name: String) -> Result<()> {
+        let schedule = &ctx.accounts.schedule;
+
+        // SOL funds leave with the schedule's lamports when it closes
+        let refunded = match schedule.funding_mint {
+            None => schedule.remaining,
+            Some(mint) => {
+                let (Some(from), Some(to)) = (
+                    ctx.accounts.schedule_funding_account.as_ref(),
+                    ctx.accounts.user_funding_account.as_ref(),
+                ) else {
+                    return err!(VaultError::InvalidDcaSchedule);
+                };
+                require!(
+                    from.mint == mint && from.owner == schedule.key() && to.mint == mint,
+                    VaultError::InvalidDcaSchedule
+                );
+
+                let schedule_seeds = &[
+                    b"dca".as_ref(),
+                    schedule.vault.as_ref(),
+                    schedule.user.as_ref(),
+                    &[schedule.bump],
+                ];
+                let signer_seeds = &[&schedule_seeds[..]];
+                let refunded = from.amount;
+                if refunded > 0 {
+                    let cpi_accounts = anchor_spl::token::Transfer {
+                        from: from.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: schedule.to_account_info(),
+                    };
+                    let cpi_ctx = CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        cpi_accounts,
+                        signer_seeds,
+                    );
+                    anchor_spl::token::transfer(cpi_ctx, refunded)?;
+                }
+
+                let cpi_accounts = anchor_spl::token::CloseAccount {
+                    account: from.to_account_info(),
+                    destination: ctx.accounts.user.to_account_info(),
+                    authority: schedule.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    cpi_accounts,
+                    signer_seeds,
+                );
+                anchor_spl::token::close_account(cpi_ctx)?;
+                refunded
+            }
+        };
+        msg!("🛑 DCA schedule cancelled, {} refunded", refunded);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(DcaScheduleCancelledEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            schedule: ctx.accounts.schedule.key(),
+            user: ctx.accounts.user.key(),
+            refunded,
+        });
+
+        Ok(())
+    }
+
+    /// Queue `amount` lamports in the current epoch's deposit batch
+    ///
+    /// Batched deposits skip the per-deposit swaps: the lamports wait in the
+    /// epoch's DepositBatch until process_deposit_batch deposits the whole
+    /// batch at one NAV after the epoch ends, and claim_batch_shares pays each
+    /// depositor their shares. cancel_batched_deposit refunds a deposit until
+    /// its batch is processed. `epoch` must be the current epoch.
+    pub fn queue_batched_deposit(
+        ctx: Context<QueueBatchedDeposit>,
+        _name: String,
+        epoch: u64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        ctx.accounts.vault.load()?.require_deposits_open()?;
+        require!(epoch == Clock::get()?.epoch, VaultError::InvalidBatchEpoch);
+
+        let vault_key = ctx.accounts.vault.key();
+        let user = ctx.accounts.user.key();
+        transfer_lamports_in(
+            &ctx.accounts.user,
+            &ctx.accounts.batch.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+
+        // Shares are paid into the user's ATA and position by the crank
+        ctx.accounts.user_position.init_if_new(vault_key, user, ctx.bumps.user_position);
+
+        let batch_key = ctx.accounts.batch.key();
+        let batch = &mut ctx.accounts.batch;
+        if batch.vault == Pubkey::default() {
+            batch.vault = vault_key;
+            batch.epoch = epoch;
+            batch.bump = ctx.bumps.batch;
+        }
+        let deposit = &mut ctx.accounts.batch_deposit;
+        if deposit.batch == Pubkey::default() {
+            deposit.batch = batch_key;
+            deposit.user = user;
+            deposit.bump = ctx.bumps.batch_deposit;
+            batch.open_deposits += 1;
+        }
+        deposit.lamports = deposit.lamports.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+        batch.lamports = batch.lamports.checked_add(amount).ok_or(VaultError::MathOverflow)?;
+        msg!("⏳ Queued {} lamports for epoch {} ({} in the batch)", amount, epoch, batch.lamports);
+
+        let batch_lamports = batch.lamports;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(BatchedDepositQueuedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            epoch,
+            user,
+            amount,
+            batch_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit an ended epoch's batch at one NAV
+    /// Permissionless - the keeper cranks each batch once its epoch is over
+    ///
+    /// Values the vault before the batch arrives, then charges the deposit fee
+    /// and allocates the batch like one SOL deposit, with one swap per asset
+    /// for every depositor. All the batch's shares are minted into its share
+    /// ATA, so the supply matches TVL right away; claim_batch_shares hands
+    /// them out. remaining_accounts: [mint, vault ATA] per asset, price
+    /// accounts, then swap backend accounts.
+    pub fn process_deposit_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProcessDepositBatch<'info>>,
+        _name: String,
+        epoch: u64,
+    ) -> Result<()> {
+        let clock = &ctx.accounts.clock;
+        let now = clock.unix_timestamp;
+        require!(epoch < clock.epoch, VaultError::BatchNotReady);
+        let batch = &ctx.accounts.batch;
+        require!(!batch.processed && batch.lamports > 0, VaultError::BatchNotReady);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let expected_accounts = vault.assets().len() * 2 + price_slots;
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        let restricted = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices).restricted;
+        require!(!restricted, VaultError::VaultRestricted);
+
+        // STEP 1: Value the vault before the batch arrives
+        let current_tvl = vault_tvl_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &feed_prices,
+        )?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
+        );
+        // The swaps and the share mint take the vault account
+        drop(vault);
+
+        // STEP 2: Move the queued lamports into the vault and allocate them
+        let batch = &ctx.accounts.batch;
+        let lamports = batch.lamports;
+        **batch.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? += lamports;
+
+        let ReceivedDeposit {
+            deposit_fee,
+            deposit_usd,
+            shares,
+            sol_leg,
+            ..
+        } = deposit_received_sol(&mut swaps, &ctx.accounts.vault, lamports, current_tvl, total_shares, &feed_prices)?;
+        wrap_sol_leg(
+            &*ctx.accounts.vault.load()?,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program,
+            sol_leg,
+        )?;
+
+        // STEP 3: Mint the batch's shares for its depositors to claim
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.batch_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.share_token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, shares)?;
+
+        let batch = &mut ctx.accounts.batch;
+        batch.processed = true;
+        batch.shares = shares;
+        batch.deposit_usd = deposit_usd;
+        msg!(
+            "📦 Epoch {} batch: {} lamports from {} deposits bought {} shares",
+            epoch,
+            lamports,
+            batch.open_deposits,
+            shares
+        );
+
+        let new_tvl = current_tvl + deposit_usd;
+        let new_total_shares = total_shares + shares;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+
+        let deposits = batch.open_deposits;
+        let batch_key = batch.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, now);
+        vault.pending_fees = vault.pending_fees.saturating_add(deposit_fee);
+        vault.end_operation();
+        if deposit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user: batch_key,
+                fee_bps: vault.deposit_fee_bps,
+                fee_lamports: deposit_fee,
+            });
+        }
+        emit_cpi!(DepositBatchProcessedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            epoch,
+            lamports,
+            deposits,
+            shares_minted: shares,
+            fee_lamports: deposit_fee,
+            tvl_usd: new_tvl,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a processed batch's depositor their cut of its shares and close
+    /// their BatchDeposit
+    /// Permissionless - the keeper claims for every depositor after processing
+    pub fn claim_batch_shares(ctx: Context<ClaimBatchShares>, _name: String, epoch: u64) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        require!(batch.processed, VaultError::BatchNotReady);
+
+        let lamports = ctx.accounts.batch_deposit.lamports;
+        let (shares, deposit_usd) = batch.cut(lamports, ctx.accounts.batch_shares_ata.amount)?;
+
+        let vault_key = ctx.accounts.vault.key();
+        let (signer, lockup_secs) = {
+            let vault = ctx.accounts.vault.load()?;
+            (vault.signer(), vault.lockup_secs)
+        };
+        let vault_seeds = signer.seeds();
+        let epoch_bytes = epoch.to_le_bytes();
+        let batch_seeds = &[
+            b"deposit_batch".as_ref(),
+            vault_key.as_ref(),
+            epoch_bytes.as_ref(),
+            &[batch.bump],
+        ];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.batch_shares_ata.to_account_info(),
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: batch.to_account_info(),
+        };
+        let batch_signer = [&batch_seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.share_token_program.to_account_info(),
+            cpi_accounts,
+            &batch_signer,
+        );
+        soulbound::transfer(
+            cpi_ctx,
+            shares,
+            &ctx.accounts.vault.to_account_info(),
+            &[&vault_seeds[..]],
+        )?;
+
+        let clock = Clock::get()?;
+        let position = &mut ctx.accounts.user_position;
+        position.record_deposit(shares, deposit_usd, &clock);
+        position.lock_shares(shares, lockup_secs, clock.unix_timestamp);
+
+        let batch = &mut ctx.accounts.batch;
+        batch.open_deposits -= 1;
+        msg!("🪙 Paid {} batch shares for {} lamports ({} deposits left)", shares, lamports, batch.open_deposits);
+
+        let batch_key = batch.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(BatchSharesClaimedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            user: ctx.accounts.user.key(),
+            lamports,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    /// Take a queued deposit back out of a batch that hasn't been processed
+    pub fn cancel_batched_deposit(ctx: Context<CancelBatchedDeposit>, _name: String, _epoch: u64) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        require!(!batch.processed, VaultError::BatchAlreadyProcessed);
+
+        let refunded = ctx.accounts.batch_deposit.lamports;
+        **batch.to_account_info().try_borrow_mut_lamports()? -= refunded;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += refunded;
+
+        let batch = &mut ctx.accounts.batch;
+        batch.lamports -= refunded;
+        batch.open_deposits -= 1;
+        msg!("🛑 Batched deposit cancelled, {} refunded", refunded);
+
+        let batch_key = batch.key();
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(BatchedDepositCancelledEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            user: ctx.accounts.user.key(),
+            refunded,
+        });
+
+        Ok(())
+    }
+
+    /// Queue `shares` for redemption in the current epoch's withdrawal batch
+    ///
+    /// The shares move into the epoch's WithdrawalBatch share ATA, where they
+    /// wait for process_withdrawal_batch to redeem the whole batch at one NAV
+    /// after the epoch ends; claim_batch_withdrawal then pays each user their
+    /// SOL. cancel_batched_withdrawal returns the shares until the batch is
+    /// processed. Locked shares can't be queued; `epoch` must be the current
+    /// epoch.
+    pub fn queue_batched_withdrawal(
+        ctx: Context<QueueBatchedWithdrawal>,
+        _name: String,
+        epoch: u64,
+        shares: u64,
+    ) -> Result<()> {
+        require!(shares > 0, VaultError::InvalidAmount);
+        let clock = Clock::get()?;
+        require!(epoch == clock.epoch, VaultError::InvalidBatchEpoch);
+
+        let vault_key = ctx.accounts.vault.key();
+        let user = ctx.accounts.user.key();
+        ctx.accounts.user_position.init_if_new(vault_key, user, ctx.bumps.user_position);
+        let balance = ctx.accounts.user_shares_ata.amount;
+        require!(balance >= shares, VaultError::InsufficientShares);
+        let locked_shares = ctx.accounts.user_position.locked_at(clock.unix_timestamp);
+        require!(balance - shares >= locked_shares, VaultError::SharesLocked);
+
+        let signer = ctx.accounts.vault.load()?.signer();
+        let vault_seeds = signer.seeds();
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.user_shares_ata.to_account_info(),
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.batch_shares_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), cpi_accounts);
+        soulbound::transfer(
+            cpi_ctx,
+            shares,
+            &ctx.accounts.vault.to_account_info(),
+            &[&vault_seeds[..]],
+        )?;
+
+        let batch_key = ctx.accounts.batch.key();
+        let batch = &mut ctx.accounts.batch;
+        if batch.vault == Pubkey::default() {
+            batch.vault = vault_key;
+            batch.epoch = epoch;
+            batch.bump = ctx.bumps.batch;
+        }
+        let withdrawal = &mut ctx.accounts.batch_withdrawal;
+        if withdrawal.batch == Pubkey::default() {
+            withdrawal.batch = batch_key;
+            withdrawal.user = user;
+            withdrawal.bump = ctx.bumps.batch_withdrawal;
+            batch.open_withdrawals += 1;
+        }
+        withdrawal.shares = withdrawal.shares.checked_add(shares).ok_or(VaultError::MathOverflow)?;
+        batch.shares = batch.shares.checked_add(shares).ok_or(VaultError::MathOverflow)?;
+        msg!("⏳ Queued {} shares for epoch {} ({} in the batch)", shares, epoch, batch.shares);
+
+        let batch_shares = batch.shares;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(BatchedWithdrawalQueuedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            epoch,
+            user,
+            shares,
+            batch_shares,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem an ended epoch's withdrawal batch at one NAV
+    /// Permissionless - the keeper cranks each batch once its epoch is over
+    ///
+    /// Follows withdraw_zap for the whole batch at once: the batch's slice of
+    /// the vault's native SOL, plus its slice of BTC and ETH sold into SOL
+    /// with one swap per asset, moves onto the WithdrawalBatch, and its shares
+    /// are burned. Exit fees are charged per user by claim_batch_withdrawal.
+    /// Needs a backend that moves tokens (not Mock) and a vault without a
+    /// strategy or wrapped SOL. remaining_accounts: [mint, vault ATA] per
+    /// asset, price accounts, then swap backend accounts for BTC -> SOL and
+    /// ETH -> SOL.
+    pub fn process_withdrawal_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProcessWithdrawalBatch<'info>>,
+        _name: String,
+        epoch: u64,
+    ) -> Result<()> {
+        let clock = &ctx.accounts.clock;
+        require!(epoch < clock.epoch, VaultError::BatchNotReady);
+        let batch = &ctx.accounts.batch;
+        require!(!batch.processed && batch.shares > 0, VaultError::BatchNotReady);
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Withdraw)?;
+        let shares = ctx.accounts.batch.shares;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        require!(vault.marinade_strategy().is_none(), VaultError::StrategyActive);
+        require!(vault.swap_backend() != SwapBackend::Mock, VaultError::UnsupportedSwapPair);
+        require!(vault.wrapped_sol_ata().is_none(), VaultError::WrappedSolUnsupported);
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        let expected_accounts = vault.assets().len() * 2 + price_slots;
+        let swap_accounts =
+            swap_engine::backend_accounts(vault.swap_backend(), ctx.remaining_accounts, expected_accounts)?;
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+        }
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+        // Circuit breaker: withdrawals stay open, but at conservative prices
+        let breaker = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices);
+        if let Some(event) = breaker.tripped {
+            emit_cpi!(event);
+            if let Some(event) = pause_change_event(&mut vault, vault_key, false) {
+                emit_cpi!(event);
+            }
+        }
+        let withdraw_prices = if breaker.restricted {
+            msg!("⚠️  Vault restricted - withdrawing at conservative prices");
+            prices::conservative_prices(&vault, feed_prices)
+        } else {
+            feed_prices
+        };
+        let [btc_normalized, eth_normalized, sol_normalized] = withdraw_prices;
+
+        // STEP 1: The batch's share of native SOL, measured before swaps credit the vault
+        let rent_exempt_minimum = ctx.accounts.rent.minimum_balance(ctx.accounts.vault.to_account_info().data_len());
+        let native_sol_balance = ctx.accounts.vault.to_account_info().lamports()
+            .saturating_sub(rent_exempt_minimum)
+            .saturating_sub(vault.fee_reserve());
+        let mut total_sol = math::pro_rata(native_sol_balance, shares, total_shares)?;
+        let mut withdrawal_usd = sol_normalized.tokens_to_usd(total_sol, 9)?;
+
+        // STEP 2: Swap the batch's share of BTC and ETH into SOL
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            swap_accounts,
+        );
+        let sol_asset = SwapAsset {
+            mint: anchor_spl::token::spl_token::native_mint::ID,
+            decimals: 9,
+            price: sol_normalized.original_price,
+            expo: sol_normalized.expo,
+        };
+
+        let assets: Vec<(Pubkey, u8)> = vault
+            .assets()
+            .iter()
+            .enumerate()
+            .map(|(i, asset)| (asset.mint, vault.asset_decimals(i)))
+            .collect();
+        // The swaps and the burn take the vault account
+        drop(vault);
+
+        for (i, &(mint, decimals)) in assets.iter().enumerate() {
+            let price = match i {
+                0 => &btc_normalized,
+                1 => &eth_normalized,
+                _ => continue,
+            };
+            let balance = token_io::token_amount(&ctx.remaining_accounts[i * 2 + 1])?;
+
+            let amount_to_withdraw = math::pro_rata(balance, shares, total_shares)?;
+            if amount_to_withdraw == 0 {
+                continue;
+            }
+            withdrawal_usd += price.tokens_to_usd(amount_to_withdraw, decimals)?;
+
+            let source = SwapAsset {
+                mint,
+                decimals,
+                price: price.original_price,
+                expo: price.expo,
+            };
+            let swap_min_out = swaps.min_out(&source, &sol_asset, amount_to_withdraw)?;
+            let sol_out = swaps.execute_swap(&source, &sol_asset, amount_to_withdraw, swap_min_out)?;
+            msg!("  • Swapped {} of {} to {} SOL", amount_to_withdraw, mint, sol_out);
+            total_sol += sol_out;
+        }
+
+        // STEP 3: Move the proceeds onto the batch and burn its shares
+        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= total_sol;
+        **ctx.accounts.batch.to_account_info().try_borrow_mut_lamports()? += total_sol;
+
+        let batch = &ctx.accounts.batch;
+        let epoch_bytes = epoch.to_le_bytes();
+        let batch_seeds = &[
+            b"withdrawal_batch".as_ref(),
+            vault_key.as_ref(),
+            epoch_bytes.as_ref(),
+            &[batch.bump],
+        ];
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            from: ctx.accounts.batch_shares_ata.to_account_info(),
+            authority: batch.to_account_info(),
+        };
+        let batch_signer = [&batch_seeds[..]];
+        let burn_ctx = CpiContext::new_with_signer(
+            ctx.accounts.share_token_program.to_account_info(),
+            burn_accounts,
+            &batch_signer,
+        );
+        token_interface::burn(burn_ctx, shares)?;
+
+        // STEP 4: Value what is left
+        let new_total_shares = total_shares - shares;
+        let new_tvl = vault_tvl_usd(
+            &*ctx.accounts.vault.load()?,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+            &withdraw_prices,
+        )?;
+        let new_share_price = Vault::calculate_share_price(new_tvl, new_total_shares)?;
+
+        let batch = &mut ctx.accounts.batch;
+        batch.processed = true;
+        batch.lamports = total_sol;
+        batch.withdrawal_usd = withdrawal_usd;
+        msg!(
+            "📦 Epoch {} batch: {} shares from {} withdrawals redeemed for {} lamports",
+            epoch,
+            shares,
+            batch.open_withdrawals,
+            total_sol
+        );
+
+        let withdrawals = batch.open_withdrawals;
+        let batch_key = batch.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.cache_nav(new_tvl, new_share_price, new_total_shares, clock.unix_timestamp);
+        vault.end_operation();
+        emit_cpi!(WithdrawalBatchProcessedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            epoch,
+            shares_burned: shares,
+            withdrawals,
+            lamports: total_sol,
+            tvl_usd: new_tvl,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a processed batch's shareholder their cut of its SOL and close
+    /// their BatchWithdrawal
+    /// Permissionless - the keeper claims for every user after processing
+    ///
+    /// The user's exit fee is taken from their cut and stays in the vault,
+    /// as with withdraw_multi_asset.
+    pub fn claim_batch_withdrawal(ctx: Context<ClaimBatchWithdrawal>, _name: String, _epoch: u64) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        require!(batch.processed, VaultError::BatchNotReady);
+
+        let shares = ctx.accounts.batch_withdrawal.shares;
+        let (lamports, withdrawal_usd) = batch.cut(shares)?;
+
+        let clock = Clock::get()?;
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let exit_fee_bps = ctx.accounts.user_position.exit_fee_bps(
+            vault.exit_fee_bps,
+            vault.exit_fee_window_secs,
+            clock.unix_timestamp,
+        );
+        let exit_fee = math::bps_of(lamports, exit_fee_bps)?;
+        **batch.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += lamports - exit_fee;
+        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? += exit_fee;
+
+        let position = &mut ctx.accounts.user_position;
+        let realized_pnl_usd = position.record_withdrawal(shares, withdrawal_usd, &clock);
+
+        let batch = &mut ctx.accounts.batch;
+        batch.shares -= shares;
+        batch.lamports -= lamports;
+        batch.withdrawal_usd -= withdrawal_usd;
+        batch.open_withdrawals -= 1;
+        msg!(
+            "💸 Paid {} lamports for {} batch shares ({} withdrawals left)",
+            lamports - exit_fee,
+            shares,
+            batch.open_withdrawals
+        );
+
+        let batch_key = batch.key();
+        let user = ctx.accounts.user.key();
+        if exit_fee > 0 {
+            emit_cpi!(FeeCollectedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                user,
+                fee_bps: exit_fee_bps,
+                fee_lamports: exit_fee,
+            });
+        }
+        emit_cpi!(BatchWithdrawalClaimedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            user,
+            shares,
+            lamports: lamports - exit_fee,
+            exit_fee,
+            realized_pnl_usd,
+        });
+
+        Ok(())
+    }
+
+    /// Take queued shares back out of a withdrawal batch that hasn't been processed
+    pub fn cancel_batched_withdrawal(ctx: Context<CancelBatchedWithdrawal>, _name: String, epoch: u64) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        require!(!batch.processed, VaultError::BatchAlreadyProcessed);
+
+        let shares = ctx.accounts.batch_withdrawal.shares;
+        let signer = ctx.accounts.vault.load()?.signer();
+        let vault_seeds = signer.seeds();
+        let vault_key = ctx.accounts.vault.key();
+        let epoch_bytes = epoch.to_le_bytes();
+        let batch_seeds = &[
+            b"withdrawal_batch".as_ref(),
+            vault_key.as_ref(),
+            epoch_bytes.as_ref(),
+            &[batch.bump],
+        ];
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.batch_shares_ata.to_account_info(),
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: batch.to_account_info(),
+        };
+        let batch_signer = [&batch_seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.share_token_program.to_account_info(),
+            cpi_accounts,
+            &batch_signer,
+        );
+        soulbound::transfer(
+            cpi_ctx,
+            shares,
+            &ctx.accounts.vault.to_account_info(),
+            &[&vault_seeds[..]],
+        )?;
+
+        let batch = &mut ctx.accounts.batch;
+        batch.shares -= shares;
+        batch.open_withdrawals -= 1;
+        msg!("🛑 Batched withdrawal cancelled, {} shares returned", shares);
+
+        let batch_key = batch.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(BatchedWithdrawalCancelledEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            batch: batch_key,
+            user: ctx.accounts.user.key(),
+            shares,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize mock price oracle for devnet testing
+    /// This allows testing with real-time market prices on devnet
+    pub fn initialize_mock_oracle(ctx: Context<InitializeMockOracle>) -> Result<()> {
+        let oracle = &mut ctx.accounts.mock_oracle;
+        
+        oracle.authority = ctx.accounts.authority.key();
+        oracle.btc_price = 0;
+        oracle.eth_price = 0;
+        oracle.sol_price = 0;
+        oracle.last_update = Clock::get()?.unix_timestamp;
+        oracle.bump = ctx.bumps.mock_oracle;
+        oracle.history = [PriceSample::default(); TWAP_SAMPLES];
+        oracle.history_index = 0;
+        oracle.history_len = 0;
+        oracle.commit_frequency_ms = 0;
+        oracle.last_commit_at = 0;
+        oracle.delegated_at = 0;
+        oracle.max_session_secs = 0;
+
+        msg!("Mock oracle initialized: {}", oracle.key());
+        
+        Ok(())
+    }
+
+    /// Replace the keys besides the authority that may update the mock oracle
+    ///
+    /// An empty list revokes every feeder. Only callable by oracle authority,
+    /// who pays for the feeders account on the first call.
+    pub fn set_oracle_feeders(ctx: Context<SetOracleFeeders>, feeders: Vec<Pubkey>) -> Result<()> {
+        require!(feeders.len() <= MAX_ORACLE_FEEDERS, VaultError::InvalidOracleFeeders);
+        for (i, feeder) in feeders.iter().enumerate() {
+            require!(!feeders[..i].contains(feeder), VaultError::InvalidOracleFeeders);
+        }
+
+        let oracle_feeders = &mut ctx.accounts.oracle_feeders;
+        oracle_feeders.oracle = ctx.accounts.mock_oracle.key();
+        oracle_feeders.feeders = feeders;
+        oracle_feeders.bump = ctx.bumps.oracle_feeders;
+
+        msg!("Mock oracle feeders set: {:?}", oracle_feeders.feeders);
+
+        Ok(())
+    }
+
+    /// Update mock oracle prices
+    /// Fetches real-time prices and updates the mock oracle
+    /// Only callable by oracle authority or one of its OracleFeeders, which
+    /// must then be passed as `oracle_feeders`
+    pub fn update_mock_oracle(
+        ctx: Context<UpdateMockOracle>,
+        btc_price: i64,
+        eth_price: i64,
+        sol_price: i64,
+    ) -> Result<()> {
+        let oracle = &mut ctx.accounts.mock_oracle;
+        let authority = ctx.accounts.authority.key();
+        
+        require!(
+            may_update_mock_oracle(oracle, ctx.accounts.oracle_feeders.as_deref(), &authority),
+            VaultError::Unauthorized
+        );
+
+        write_mock_prices(oracle, [btc_price, eth_price, sol_price], Clock::get()?.unix_timestamp)
+    }
+
+    /// Update several of the mock oracle's markets in one instruction
+    ///
+    /// Meant for a price pusher driving a delegated oracle on the ER at
+    /// sub-second cadence: the whole basket moves atomically, as one TWAP
+    /// sample, instead of one instruction per asset. Each pair names a mint of
+    /// `vault` (which must price from this oracle); markets not listed keep
+    /// their current price. Same signers as update_mock_oracle.
+    pub fn update_prices_batch(ctx: Context<UpdatePricesBatch>, prices: Vec<MintPrice>) -> Result<()> {
+        let oracle = &mut ctx.accounts.mock_oracle;
+        let authority = ctx.accounts.authority.key();
+        require!(
+            may_update_mock_oracle(oracle, ctx.accounts.oracle_feeders.as_deref(), &authority),
+            VaultError::Unauthorized
+        );
+
+        let vault = ctx.accounts.vault.load()?;
+        require!(vault.mock_oracle() == Some(oracle.key()), VaultError::InvalidQuote);
+        require!(
+            !prices.is_empty() && prices.len() <= ORACLE_MARKETS,
+            VaultError::InvalidPrice
+        );
+
+        let mut market_prices = [oracle.btc_price, oracle.eth_price, oracle.sol_price];
+        let mut updated = [false; ORACLE_MARKETS];
+        for update in &prices {
+            let market = vault.oracle_market(&update.mint).ok_or(VaultError::InvalidMint)?;
+            require!(!updated[market], VaultError::InvalidMint);
+            updated[market] = true;
+            market_prices[market] = update.price;
+        }
+
+        write_mock_prices(oracle, market_prices, Clock::get()?.unix_timestamp)
+    }
+
+    /// Update mock oracle prices from a payload signed off-chain
+    ///
+    /// Permissionless: the transaction carries, right before this
+    /// instruction, an Ed25519 program instruction verifying the oracle
+    /// authority's (or a feeder's) signature over `signed_prices::message`.
+    /// The signing time becomes the oracle's last_update; it must be newer
+    /// than the current one, so a payload lands once, and no older than
+    /// MAX_SIGNED_PRICE_AGE_SECS.
+    pub fn update_mock_oracle_signed(
+        ctx: Context<UpdateMockOracleSigned>,
+        btc_price: i64,
+        eth_price: i64,
+        sol_price: i64,
+        signed_at: i64,
+    ) -> Result<()> {
+        let oracle = &mut ctx.accounts.mock_oracle;
+        let instructions = ctx.accounts.instructions.to_account_info();
+
+        let current_index = load_current_index_checked(&instructions)? as usize;
+        require!(current_index > 0, VaultError::InvalidPriceSignature);
+        let ed25519_ix = load_instruction_at_checked(current_index - 1, &instructions)?;
+        let (signer, message) =
+            signed_prices::verified_message(&ed25519_ix).ok_or(VaultError::InvalidPriceSignature)?;
+        require!(
+            message == signed_prices::message(&oracle.key(), btc_price, eth_price, sol_price, signed_at),
+            VaultError::InvalidPriceSignature
+        );
+        require!(
+            may_update_mock_oracle(oracle, ctx.accounts.oracle_feeders.as_deref(), &signer),
+            VaultError::Unauthorized
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            signed_at > oracle.last_update
+                && signed_at <= now
+                && now - signed_at <= signed_prices::MAX_SIGNED_PRICE_AGE_SECS,
+            VaultError::StaleQuote
+        );
+
+        msg!("Prices signed by {} at {}", signer, signed_at);
+        write_mock_prices(oracle, [btc_price, eth_price, sol_price], signed_at)
+    }
+
+    /// Refresh the vault's PriceCache from its configured oracles
+    ///
+    /// Permissionless crank: anyone may pay to create or refresh the cache.
+    /// Deposits and withdrawals that pass the cache instead of the oracle
+    /// accounts accept it for MAX_PRICE_CACHE_AGE_SECS.
+    ///
+    /// **remaining_accounts layout:**
+    /// - [0]: MockOracle account (only if price_source = MockOracle)
+    pub fn refresh_prices<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshPrices<'info>>,
+        _vault_name: String,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let clock = Clock::get()?;
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices(&vault, &price_accounts, &clock, 300)?;
+
+        // Cranking also persists circuit breaker trips that deposits can only reject
+        let breaker = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices);
+        if let Some(event) = breaker.tripped {
+            emit_cpi!(event);
+            if let Some(event) = pause_change_event(&mut vault, vault_key, false) {
+                emit_cpi!(event);
+            }
+        }
+        let [btc, eth, sol] = feed_prices;
+
+        let cache = &mut ctx.accounts.price_cache;
+        cache.vault = vault_key;
+        cache.btc_price = btc.price_usd;
+        cache.eth_price = eth.price_usd;
+        cache.sol_price = sol.price_usd;
+        cache.last_update = clock.unix_timestamp;
+        cache.bump = ctx.bumps.price_cache;
+
+        msg!("Price cache refreshed - BTC: ${}, ETH: ${}, SOL: ${}",
+             btc.price_usd / 1_000_000, eth.price_usd / 1_000_000, sol.price_usd / 1_000_000);
+
+        Ok(())
+    }
+
+    /// Record a NAV checkpoint in the vault's NavHistory ring buffer
+    ///
+    /// Permissionless crank, accepted at most once per NAV_SNAPSHOT_INTERVAL_SECS.
+    /// TVL is valued the same way deposit values it. Also refreshes the vault's
+    /// trailing 7d/30d share price changes from the history and samples its
+    /// deviation from target weights into the rolling TrackingError.
+    ///
+    /// **remaining_accounts layout:**
+    /// - [0..n*2]: Pairs of (asset_mint, vault_ata) for each asset in vault.assets
+    /// - [n*2]: MockOracle account or PriceCache (if used)
+    pub fn snapshot_nav<'info>(
+        ctx: Context<'_, '_, '_, 'info, SnapshotNav<'info>>,
+        _vault_name: String,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let clock = Clock::get()?;
+
+        let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+        require!(
+            ctx.remaining_accounts.len() == vault.assets().len() * 2 + price_slots,
+            VaultError::InvalidRemainingAccounts
+        );
+        for (i, asset) in vault.assets().iter().enumerate() {
+            require!(
+                ctx.remaining_accounts[i * 2 + 1].key() == asset.ata,
+                VaultError::InvalidATA
+            );
+        }
+
+        let history = &ctx.accounts.nav_history;
+        if let Some(latest) = history.latest() {
+            require!(
+                clock.unix_timestamp - latest.timestamp >= NAV_SNAPSHOT_INTERVAL_SECS,
+                VaultError::SnapshotTooSoon
+            );
+        }
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, &clock, 300)?;
+
+        let holdings_usd = vault_holdings_usd(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &Rent::get()?,
+            &feed_prices,
+        )?;
+        let tvl_usd = holdings_usd
+            .iter()
+            .try_fold(0i64, |tvl, usd| tvl.checked_add(*usd))
+            .ok_or(VaultError::MathOverflow)?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let share_price = Vault::calculate_share_price(tvl_usd, total_shares)?;
+
+        // Strategy SOL counts toward the SOL leg, as in the TVL; hidden
+        // targets can't be tracked against
+        let weights: Vec<u8> = vault.assets().iter().map(|a| a.weight).collect();
+        let deviation = match vault.weights_hidden() {
+            true => None,
+            false => tracking::deviation_bps(&weights, &holdings_usd[..weights.len().min(3)], tvl_usd),
+        };
+
+        let snapshot = NavSnapshot {
+            timestamp: clock.unix_timestamp,
+            tvl_usd,
+            total_shares,
+            share_price,
+        };
+        let history = &mut ctx.accounts.nav_history;
+        history.vault = vault_key;
+        history.bump = ctx.bumps.nav_history;
+        history.push(snapshot);
+
+        let now = clock.unix_timestamp;
+        let tracking_error = &mut ctx.accounts.tracking_error;
+        tracking_error.vault = vault_key;
+        tracking_error.bump = ctx.bumps.tracking_error;
+        if let Some(deviation_bps) = deviation {
+            tracking_error.record(deviation_bps, now);
+        }
+
+        // Rolling performance, kept until the history reaches back far enough
+        let change_7d = history.share_price_change_bps(share_price, now, 7 * SECONDS_PER_DAY);
+        let change_30d = history.share_price_change_bps(share_price, now, 30 * SECONDS_PER_DAY);
+        if let Some(bps) = change_7d {
+            vault.share_price_change_7d_bps = bps;
+        }
+        if let Some(bps) = change_30d {
+            vault.share_price_change_30d_bps = bps;
+        }
+        vault.performance_updated_at = now;
+        vault.cache_nav(tvl_usd, share_price, total_shares, now);
+
+        emit_cpi!(NavSnapshotEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            timestamp: snapshot.timestamp,
+            tvl_usd,
+            total_shares,
+            share_price,
+            tracking_error_bps: tracking_error.tracking_error_bps,
+        });
+
+        msg!("📸 NAV snapshot - TVL: ${}, shares: {}, share price: ${}, tracking error: {} bps",
+             tvl_usd, total_shares, share_price, tracking_error.tracking_error_bps);
+
+        Ok(())
+    }
+
+    /// Current TVL and share price, written to return data
+    ///
+    /// Read-only: simulate it instead of re-implementing the pricing math
+    /// client-side. Takes the same remaining_accounts as snapshot_nav.
+    pub fn get_vault_stats<'info>(
+        ctx: Context<'_, '_, '_, 'info, VaultView<'info>>,
+        _vault_name: String,
+    ) -> Result<VaultStats> {
+        let vault = ctx.accounts.vault.load()?;
+        let nav = view_nav(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            &ctx.accounts.vault_token_mint,
+            [&ctx.accounts.btc_quote, &ctx.accounts.eth_quote, &ctx.accounts.sol_quote],
+            ctx.remaining_accounts,
+        )?;
+
+        // USDC base units are micro-USD at the peg; SOL figures are lamports
+        let quote_currency = ctx.accounts.quote_config.as_ref().map_or(QuoteCurrency::Sol, |quote| quote.currency);
+        let (tvl_quote, share_price_quote) = match quote_currency {
+            QuoteCurrency::Usdc => (nav.tvl_usd, nav.share_price),
+            QuoteCurrency::Sol => (
+                nav.prices[2].usd_to_tokens(nav.tvl_usd, 9)?,
+                nav.prices[2].usd_to_tokens(nav.share_price, 9)?,
+            ),
+        };
+
+        // Same NAV in the vault's denomination, in micro-units of it
+        let fx_feed = ctx.accounts.quote_config.as_ref().and_then(|quote| quote.fx_feed());
+        let (nav_currency, tvl_nav, share_price_nav) = match fx_feed {
+            None => (USD_CURRENCY, nav.tvl_usd, nav.share_price),
+            Some(feed) => {
+                let fx_account = ctx.accounts.fx_feed.as_ref().ok_or(VaultError::FxFeedRequired)?;
+                require_keys_eq!(fx_account.key(), feed, VaultError::FxFeedRequired);
+                let fx = prices::verify_oracle_quote(ctx.remaining_accounts, fx_account)?;
+                (
+                    ctx.accounts.quote_config.as_ref().map_or(USD_CURRENCY, |quote| quote.nav_currency),
+                    fx.usd_to_tokens(nav.tvl_usd, 6)?,
+                    fx.usd_to_tokens(nav.share_price, 6)?,
+                )
+            }
+        };
+
+        Ok(VaultStats {
+            tvl_usd: nav.tvl_usd,
+            total_shares: nav.total_shares,
+            share_price: nav.share_price,
+            prices: nav.prices.map(|p| p.price_usd),
+            deposits_paused: vault.soft_closed() || vault.guardian_deposits_paused() || nav.restricted,
+            share_price_change_7d_bps: vault.share_price_change_7d_bps,
+            share_price_change_30d_bps: vault.share_price_change_30d_bps,
+            quote_currency,
+            tvl_quote,
+            share_price_quote,
+            nav_currency,
+            tvl_nav,
+            share_price_nav,
+        })
+    }
+
+    /// Shares a deposit of `amount` lamports would mint right now
+    ///
+    /// Read-only; same accounts as get_vault_stats. Fails like the deposit
+    /// would while deposits are paused.
+    pub fn preview_deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, VaultView<'info>>,
+        _vault_name: String,
+        amount: u64,
+    ) -> Result<DepositPreview> {
+        require!(amount > 0, VaultError::InvalidAmount);
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_deposits_open()?;
+
+        let nav = view_nav(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            &ctx.accounts.vault_token_mint,
+            [&ctx.accounts.btc_quote, &ctx.accounts.eth_quote, &ctx.accounts.sol_quote],
+            ctx.remaining_accounts,
+        )?;
+        require!(!nav.restricted, VaultError::VaultRestricted);
+
+        // Same steps as deposit_multi_asset
+        let deposit_fee = math::bps_of(amount, vault.deposit_fee_bps)?;
+        let deposit_usd = nav.prices[2].tokens_to_usd(amount - deposit_fee, 9)?;
+        let shares_out = Vault::calculate_shares_for_deposit(deposit_usd, nav.tvl_usd, nav.total_shares)?;
+        vault.require_min_shares(shares_out)?;
+
+        Ok(DepositPreview {
+            shares_out,
+            deposit_fee,
+            deposit_usd,
+            share_price: nav.share_price,
+            tvl_usd: nav.tvl_usd,
+        })
+    }
+
+    /// SOL that redeeming `shares` would pay out right now
+    ///
+    /// Read-only; same remaining_accounts as get_vault_stats. Pass the
+    /// holder's UserPosition to include their exit fee.
+    pub fn preview_withdraw<'info>(
+        ctx: Context<'_, '_, '_, 'info, PreviewWithdraw<'info>>,
+        _vault_name: String,
+        shares: u64,
+    ) -> Result<WithdrawPreview> {
+        require!(shares > 0, VaultError::InvalidAmount);
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        let nav = view_nav(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            &ctx.accounts.vault_token_mint,
+            [&ctx.accounts.btc_quote, &ctx.accounts.eth_quote, &ctx.accounts.sol_quote],
+            ctx.remaining_accounts,
+        )?;
+        require!(shares <= nav.total_shares, VaultError::InsufficientShares);
+
+        // Same steps as withdraw_multi_asset, including conservative prices while restricted
+        let [btc, eth, sol] = if nav.restricted {
+            prices::conservative_prices(&vault, nav.prices)
+        } else {
+            nav.prices
+        };
+        let mut withdraw_usd = 0i64;
+        for (i, price) in [(0, &btc), (1, &eth), (2, &sol)] {
+            if i >= vault.assets().len() {
+                break;
+            }
+            let decimals = vault.asset_decimals(i);
+            let ata_info = &ctx.remaining_accounts[i * 2 + 1];
+            let balance = if vault.sol_slot() == Some(i) {
+                sol_leg_balance(&vault, ctx.accounts.vault.as_ref(), ata_info, &Rent::get()?)?
+            } else {
+                token_io::token_amount(ata_info)?
+            };
+            let amount = math::pro_rata(balance, shares, nav.total_shares)?;
+            withdraw_usd += price.tokens_to_usd(amount, decimals)?;
+        }
+        let basket_lamports = u64::try_from(sol.usd_to_tokens(withdraw_usd, 9)?).map_err(|_| VaultError::MathOverflow)?;
+        let staked_share = math::pro_rata(
+            strategy_value::staked_lamports(&vault, &vault_key, ctx.remaining_accounts)?,
+            shares,
+            nav.total_shares,
+        )?;
+        withdraw_usd += sol.tokens_to_usd(staked_share, 9)?;
+
+        let exit_fee_bps = match &ctx.accounts.user_position {
+            Some(position) => position.exit_fee_bps(
+                vault.exit_fee_bps,
+                vault.exit_fee_window_secs,
+                Clock::get()?.unix_timestamp,
+            ),
+            None => 0,
+        };
+        let payout = math::split_withdraw_payout(basket_lamports, staked_share, exit_fee_bps)?;
+        let exit_fee = payout.exit_fee;
+
+        Ok(WithdrawPreview {
+            sol_out: payout.from_vault + payout.from_strategy,
+            exit_fee,
+            exit_fee_bps,
+            withdraw_usd,
+            share_price: nav.share_price,
+            restricted: nav.restricted,
+        })
+    }
+
+    /// Drifts and the swap legs rebalance would execute right now
+    ///
+    /// Read-only dry run for keepers and UIs: same remaining_accounts as
+    /// rebalance (price accounts, then the vault ATAs) without the swap
+    /// backend accounts. Legs carry the oracle quote and the least the real
+    /// swap may return under the vault's max_slippage_bps.
+    pub fn simulate_rebalance<'info>(
+        ctx: Context<'_, '_, '_, 'info, SimulateRebalance<'info>>,
+        _vault_name: String,
+    ) -> Result<RebalancePreview> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        require!(!vault.weights_hidden(), VaultError::WeightsHidden);
+        let clock = Clock::get()?;
+        let RebalanceHoldings { prices, current_usds, total_usd } = rebalance_holdings(
+            &vault,
+            vault_key,
+            [&ctx.accounts.btc_quote, &ctx.accounts.eth_quote, &ctx.accounts.sol_quote],
+            ctx.remaining_accounts,
+            &clock,
+        )?;
+
+        let current_weights_bps = calculate_weights_bps(&current_usds, total_usd);
+        if total_usd == 0 {
+            return Ok(RebalancePreview {
+                tvl_usd: 0,
+                current_weights_bps,
+                drifts_pct: vec![0; vault.assets().len()],
+                needs_rebalance: false,
+                legs: Vec::new(),
+                dust_legs_skipped: 0,
+                dust_usd_skipped: 0,
+            });
+        }
+
+        let weights: Vec<u8> = vault.assets().iter().map(|asset| asset.weight).collect();
+        let drifts = rebalance_plan::drifts(&weights, &current_usds, total_usd);
+        let needs_rebalance = rebalance_plan::needs_rebalance(&drifts);
+        let (legs, dust) = if needs_rebalance {
+            plan_rebalance_legs(&vault, ctx.accounts.rebalance_config.as_ref(), &prices, &drifts, total_usd)?
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let legs = legs
+            .iter()
+            .map(|leg| {
+                let from = rebalance_swap_asset(&vault, &prices, leg.from)?;
+                let to = rebalance_swap_asset(&vault, &prices, leg.to)?;
+                let (expected_out, min_out) =
+                    swap_engine::quote(&from, &to, leg.amount_in, vault.max_slippage_bps)?;
+                Ok(PlannedSwap {
+                    from_mint: from.mint,
+                    to_mint: to.mint,
+                    swap_usd: leg.swap_usd,
+                    amount_in: leg.amount_in,
+                    expected_out,
+                    min_out,
+                })
+            })
+            .collect::<Result<Vec<PlannedSwap>>>()?;
+        msg!("Simulated rebalance: {} legs, needed: {}", legs.len(), needs_rebalance);
+
+        Ok(RebalancePreview {
+            tvl_usd: total_usd,
+            current_weights_bps,
+            drifts_pct: drifts.iter().map(|drift| drift.drift_pct).collect(),
+            needs_rebalance,
+            legs,
+            dust_legs_skipped: dust.len() as u8,
+            dust_usd_skipped: dust.iter().map(|leg| leg.swap_usd).sum(),
+        })
+    }
+
+    // ============================================================================
+    // EPHEMERAL ROLLUPS INTEGRATION
+    // ============================================================================
+    // Only built with the `ephemeral-rollups` feature (default); see er.rs
+
+    /// Delegate mock oracle to Ephemeral Rollup for high-frequency price updates
+    /// This transfers ownership of the oracle PDA to the ER delegation program
+    /// Allows sub-second price updates without L1 transaction fees
+    /// Only callable by oracle authority
+    ///
+    /// The ER commits the oracle to L1 every `commit_frequency_ms`; the
+    /// interval is also kept on the oracle, so crank_mock_oracle_commit can
+    /// force a commit whenever the last one is older than it. After
+    /// `max_session_secs` anyone may end the session with
+    /// force_undelegate_mock_oracle.
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn delegate_mock_oracle(
+        ctx: Context<DelegateMockOracleCtx>,
+        commit_frequency_ms: u32,
+        max_session_secs: u32,
+    ) -> Result<()> {
+        require!(
+            commit_frequency_ms >= MIN_ER_COMMIT_FREQUENCY_MS,
+            VaultError::InvalidCommitFrequency
+        );
+        require!(
+            max_session_secs > 0 && max_session_secs <= MAX_ER_SESSION_SECS,
+            VaultError::InvalidSessionDuration
+        );
+
+        // Record the interval while the oracle is still ours to write
+        let oracle_info = &ctx.accounts.pda;
+        require_keys_eq!(*oracle_info.owner, crate::ID, VaultError::InvalidPrice);
+        let mut oracle = MockPriceOracle::try_deserialize(&mut &oracle_info.try_borrow_data()?[..])?;
+        require_keys_eq!(oracle.authority, ctx.accounts.payer.key(), VaultError::Unauthorized);
+        let now = Clock::get()?.unix_timestamp;
+        oracle.commit_frequency_ms = commit_frequency_ms;
+        oracle.last_commit_at = now;
+        oracle.delegated_at = now;
+        oracle.max_session_secs = max_session_secs;
+        oracle.try_serialize(&mut &mut oracle_info.try_borrow_mut_data()?[..])?;
+
+        // Delegate the oracle PDA to Ephemeral Rollup
+        // The #[delegate] macro on DelegateMockOracle provides the delegate_pda method
+        ctx.accounts.delegate_pda(
+            &ctx.accounts.payer,
+            &[b"mock_oracle", ctx.accounts.payer.key().as_ref()],
+            DelegateConfig {
+                validator: None, // Will use default ER validator
+                commit_frequency_ms,
+            },
+        )?;
+
+        msg!("Mock oracle delegated to Ephemeral Rollup: {}", ctx.accounts.pda.key());
+        msg!("High-frequency price updates now enabled, committing every {}ms", commit_frequency_ms);
+        msg!("Anyone may undelegate the oracle after {}s", max_session_secs);
+
+        Ok(())
+    }
+
+    /// Commit mock oracle state to L1 without undelegating
+    /// This creates a checkpoint on L1 while keeping the oracle on ER
+    /// Useful for periodic state synchronization
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn commit_mock_oracle(ctx: Context<CommitMockOracle>) -> Result<()> {
+        let authority = &ctx.accounts.authority;
+        let mock_oracle = &mut ctx.accounts.mock_oracle;
+        mock_oracle.last_commit_at = Clock::get()?.unix_timestamp;
+        mock_oracle.exit(&crate::ID)?;
+
+        // Commit the oracle account state to L1
+        commit_accounts(
+            authority,
+            vec![&mock_oracle.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        msg!("Mock oracle state committed to L1: {}", mock_oracle.key());
+        msg!("Oracle remains on ER for continued high-frequency updates");
+        
+        Ok(())
+    }
+
+    /// Commit the mock oracle to L1 if its last commit is overdue
+    ///
+    /// Permissionless crank run on the ER: succeeds only once the
+    /// commit_frequency_ms set by delegate_mock_oracle has passed since the
+    /// last commit, so L1 readers never fall further behind than that even
+    /// when the ER's own scheduled commits lag.
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn crank_mock_oracle_commit(ctx: Context<CrankMockOracleCommit>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mock_oracle = &mut ctx.accounts.mock_oracle;
+        require!(mock_oracle.commit_due(now), VaultError::OracleCommitNotDue);
+        let since = now - mock_oracle.last_commit_at;
+        mock_oracle.last_commit_at = now;
+        mock_oracle.exit(&crate::ID)?;
+
+        commit_accounts(
+            &ctx.accounts.payer,
+            vec![&mock_oracle.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        msg!("Mock oracle committed to L1 by crank, {}s after the last commit", since);
+
+        Ok(())
+    }
+
+    /// Undelegate mock oracle from Ephemeral Rollup back to L1
+    /// This commits final state and returns ownership to the vault program
+    /// Callable by oracle authority to end ER session
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn undelegate_mock_oracle(ctx: Context<UndelegateMockOracle>) -> Result<()> {
+        let authority = &ctx.accounts.authority;
+        let mock_oracle = &mut ctx.accounts.mock_oracle;
+        
+        // Verify authority
+        require!(
+            authority.key() == mock_oracle.authority,
+            VaultError::Unauthorized
+        );
+        mock_oracle.delegated_at = 0;
+        mock_oracle.last_commit_at = Clock::get()?.unix_timestamp;
+        mock_oracle.exit(&crate::ID)?;
+
+        // Commit and undelegate the oracle back to L1
+        commit_and_undelegate_accounts(
+            authority,
+            vec![&mock_oracle.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        msg!("Mock oracle undelegated from Ephemeral Rollup: {}", mock_oracle.key());
+        msg!("Oracle returned to L1, final state committed");
+        
+        Ok(())
+    }
+
+    /// Undelegate a mock oracle whose ER session has outlived max_session_secs
+    ///
+    /// Permissionless, so an oracle whose authority (or ER operator) went
+    /// away is not left off L1 indefinitely: its last ER state is committed
+    /// and ownership returns to the vault program, as undelegate_mock_oracle
+    /// does.
+    #[cfg(feature = "ephemeral-rollups")]
+    pub fn force_undelegate_mock_oracle(ctx: Context<ForceUndelegateMockOracle>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mock_oracle = &mut ctx.accounts.mock_oracle;
+        require!(mock_oracle.session_expired(now), VaultError::ErSessionActive);
+        let session_secs = now - mock_oracle.delegated_at;
+        mock_oracle.delegated_at = 0;
+        mock_oracle.last_commit_at = now;
+        mock_oracle.exit(&crate::ID)?;
+
+        commit_and_undelegate_accounts(
+            &ctx.accounts.payer,
+            vec![&mock_oracle.to_account_info()],
+            &ctx.accounts.magic_context,
+            &ctx.accounts.magic_program,
+        )?;
+
+        msg!(
+            "Mock oracle {} force-undelegated after a {}s ER session",
+            mock_oracle.key(),
+            session_secs
+        );
+
+        Ok(())
+    }
+    
+
+    /// Set price source for vault (Switchboard or MockOracle)
+    /// Allows switching between real Switchboard feeds and mock oracle
+    pub fn set_price_source(
+        ctx: Context<SetPriceSource>,
+        _name: String,
+        price_source: PriceSource,
+        mock_oracle: Option<Pubkey>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        // Cross-checking needs its threshold, set through set_cross_checked_pricing
+        require!(price_source != PriceSource::CrossChecked, VaultError::InvalidPrice);
+        // If setting to MockOracle, require mock_oracle address
+        if price_source == PriceSource::MockOracle {
+            require!(mock_oracle.is_some(), VaultError::InvalidPrice);
+        }
+        // Aggregated pricing needs feeds for BTC, ETH and SOL first
+        if price_source == PriceSource::Aggregated {
+            for feed_index in 0..3u8 {
+                require!(
+                    vault.price_feeds().iter().any(|f| f.feed_index == feed_index),
+                    VaultError::InvalidPrice
+                );
+            }
+        }
+
+        vault.set_price_source(price_source);
+        vault.set_mock_oracle(mock_oracle);
+
+        msg!("Price source set to: {:?}", price_source);
+        emit_cpi!(price_source_changed_event(&mut vault, vault_key));
+        
+        Ok(())
+    }
+
+    /// Price from Switchboard, cross-checked against `mock_oracle`
+    ///
+    /// Every pricing-sensitive instruction then reads both sources and fails
+    /// with PriceDivergence when BTC, ETH or SOL differ by more than
+    /// `max_divergence_bps` of the Switchboard price, so a single compromised
+    /// feed can't value deposits, withdrawals or rebalances on its own. The
+    /// mock oracle account is passed where a MockOracle vault passes it.
+    pub fn set_cross_checked_pricing(
+        ctx: Context<SetPriceSource>,
+        _name: String,
+        mock_oracle: Pubkey,
+        max_divergence_bps: u16,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+        require!(
+            max_divergence_bps > 0 && max_divergence_bps <= 10_000,
+            VaultError::InvalidPriceFeeds
+        );
+
+        vault.set_price_source(PriceSource::CrossChecked);
+        vault.set_mock_oracle(Some(mock_oracle));
+        vault.max_divergence_bps = max_divergence_bps;
+
+        msg!("Price source set to Switchboard cross-checked against {} ({} bps)", mock_oracle, max_divergence_bps);
+        emit_cpi!(price_source_changed_event(&mut vault, vault_key));
+
+        Ok(())
+    }
+
+    /// Configure the feeds used when price_source = Aggregated
+    ///
+    /// Up to MAX_FEEDS_PER_ASSET feeds per oracle market (BTC, ETH, SOL). The
+    /// vault prices each market at the median of its feeds and rejects the
+    /// price when the feeds spread more than `max_divergence_bps` apart.
+    pub fn configure_price_feeds(
+        ctx: Context<SetPriceSource>,
+        _name: String,
+        price_feeds: Vec<PriceFeed>,
+        max_divergence_bps: u16,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+        validate_price_feeds(&price_feeds, max_divergence_bps)?;
+
+        vault.set_price_feeds(&price_feeds)?;
+        vault.max_divergence_bps = max_divergence_bps;
+
+        msg!(
+            "Configured {} price feeds (max divergence {} bps)",
+            vault.price_feeds().len(),
+            max_divergence_bps
+        );
+        emit_cpi!(price_source_changed_event(&mut vault, vault_key));
+
+        Ok(())
+    }
+
+    /// Set the price feed of each asset of a per-asset priced vault (one
+    /// whose basket is not BTC/ETH/SOL; see Vault::per_asset_pricing)
+    ///
+    /// `feeds` are in vault.assets order; `sol_feed` prices the SOL that
+    /// deposits and withdrawals settle in. A MockOracle feed reads the oracle
+    /// market at its feed_index. Deposits and withdrawals pass the feed
+    /// accounts after the AssetOracles, in the same order.
+    pub fn configure_asset_oracles(
+        ctx: Context<ConfigureAssetOracles>,
+        _name: String,
+        feeds: Vec<PriceFeed>,
+        sol_feed: PriceFeed,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(vault.per_asset_pricing(), VaultError::InvalidPriceFeeds);
+        require!(feeds.len() == vault.assets().len(), VaultError::InvalidPriceFeeds);
+        // MockOracle feeds must name one of the oracle's markets
+        let valid_market = |feed: &PriceFeed| {
+            feed.kind != PriceFeedKind::MockOracle || (feed.feed_index as usize) < ORACLE_MARKETS
+        };
+        require!(
+            feeds.iter().chain([&sol_feed]).all(valid_market),
+            VaultError::InvalidPriceFeeds
+        );
+
+        let oracles = &mut ctx.accounts.asset_oracles;
+        oracles.vault = vault_key;
+        oracles.feeds = feeds.clone();
+        oracles.sol_feed = sol_feed;
+        oracles.bump = ctx.bumps.asset_oracles;
+
+        msg!("Configured {} asset feeds", feeds.len());
+
+        emit_cpi!(AssetOraclesConfiguredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            feeds,
+            sol_feed,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the price circuit breaker
+    ///
+    /// When any BTC/ETH/SOL price moves more than `max_move_bps` away from the
+    /// vault's checkpoint, the vault enters restricted mode: deposits are
+    /// rejected and withdrawals are priced conservatively until the admin
+    /// calls clear_circuit_breaker. `max_move_bps` = 0 disables the breaker.
+    pub fn set_circuit_breaker(
+        ctx: Context<SetPriceSource>,
+        _name: String,
+        max_move_bps: u16,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        vault.circuit_breaker_bps = max_move_bps;
+        vault.price_checkpoint = [0; 3];
+
+        msg!("Circuit breaker set to: {} bps", max_move_bps);
+
+        Ok(())
+    }
+
+    /// Leave restricted mode after a circuit breaker trip
+    /// The checkpoint is re-seeded from the next prices the vault reads
+    pub fn clear_circuit_breaker(ctx: Context<SetPriceSource>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        let was_paused = vault.deposits_paused();
+        vault.set_restricted(false);
+        vault.price_checkpoint = [0; 3];
+
+        emit_cpi!(CircuitBreakerEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            restricted: false,
+            checkpoint: [0; 3],
+            prices: [0; 3],
+        });
+
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
+
+        msg!("Circuit breaker cleared");
+
+        Ok(())
+    }
+
+    /// Set (or with None, remove) the vault's guardian
+    ///
+    /// The guardian may only pause: guardian_pause stops deposits and, with
+    /// `may_pause_rebalancing`, also rebalancing. It can't touch withdrawals,
+    /// fees, strategies or composition, and can't undo a pause, so a
+    /// monitoring bot can hold the key hot. Governed instruction.
+    pub fn set_guardian(
+        ctx: Context<SetGuardian>,
+        _name: String,
+        guardian: Option<Pubkey>,
+        may_pause_rebalancing: bool,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let vault_guardian = &mut ctx.accounts.vault_guardian;
+        vault_guardian.vault = vault_key;
+        vault_guardian.guardian = guardian;
+        vault_guardian.may_pause_rebalancing = may_pause_rebalancing;
+        vault_guardian.bump = ctx.bumps.vault_guardian;
+
+        msg!("🛡️  Guardian set to {:?} (may pause rebalancing: {})", guardian, may_pause_rebalancing);
+
+        emit_cpi!(GuardianSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            guardian,
+            may_pause_rebalancing,
+        });
+
+        Ok(())
+    }
+
+    /// Pause the vault as its guardian
+    ///
+    /// Deposits are refused until the governed authority calls
+    /// resume_deposits. Withdrawals keep working at normal prices: unlike a
+    /// circuit breaker trip, the vault isn't restricted. With
+    /// `pause_rebalancing` (if the guardian may) rebalance, the confidential
+    /// rebalance flow and swap_via_orca are refused until resume_rebalancing.
+    pub fn guardian_pause(ctx: Context<GuardianPause>, _name: String, pause_rebalancing: bool) -> Result<()> {
+        let vault_guardian = &ctx.accounts.vault_guardian;
+        require!(
+            vault_guardian.guardian == Some(ctx.accounts.guardian.key()),
+            VaultError::Unauthorized
+        );
+        require!(
+            !pause_rebalancing || vault_guardian.may_pause_rebalancing,
+            VaultError::GuardianCannotPauseRebalancing
+        );
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let was_paused = vault.deposits_paused();
+        let was_rebalancing_paused = vault.rebalancing_paused();
+        vault.set_guardian_deposits_paused(true);
+        if pause_rebalancing {
+            vault.set_rebalancing_paused(true);
+        }
+
+        msg!(
+            "🛡️  Guardian {} paused deposits (rebalancing paused: {})",
+            ctx.accounts.guardian.key(),
+            vault.rebalancing_paused()
+        );
+
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
+        if vault.rebalancing_paused() != was_rebalancing_paused {
+            emit_cpi!(RebalancingPauseEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                paused: true,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Let the vault take deposits again after guardian_pause stopped them
+    ///
+    /// A circuit breaker trip is separate and still needs
+    /// clear_circuit_breaker. Governed instruction.
+    pub fn resume_deposits(ctx: Context<GuardianResume>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let was_paused = vault.deposits_paused();
+        vault.set_guardian_deposits_paused(false);
+
+        msg!("Deposits resumed");
+
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Let the vault rebalance again after guardian_pause stopped it;
+    /// governed instruction
+    pub fn resume_rebalancing(ctx: Context<GuardianResume>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let was_rebalancing_paused = vault.rebalancing_paused();
+        vault.set_rebalancing_paused(false);
+
+        msg!("Rebalancing resumed");
+
+        if was_rebalancing_paused {
+            emit_cpi!(RebalancingPauseEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                paused: false,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Price MockOracle vaults from a TWAP instead of the latest print
+    ///
+    /// `window_secs` = 0 restores spot pricing. Applies to deposit, withdraw,
+    /// rebalance and the price cache.
+    pub fn set_twap_window(
+        ctx: Context<SetPriceSource>,
+        _name: String,
+        window_secs: u32,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        vault.twap_window_secs = window_secs;
+
+        msg!("TWAP window set to: {}s", window_secs);
+        emit_cpi!(price_source_changed_event(&mut vault, vault_key));
+
+        Ok(())
+    }
+
+    /// Set a strategy for the vault (only callable by vault authority)
+    /// This allows the vault to delegate asset management to a strategy
+    pub fn set_strategy(ctx: Context<SetStrategy>, _name: String, strategy: Pubkey) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        // Only vault admin (or governance) can set strategy
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(vault.wind_down_deadline == 0, VaultError::VaultWindingDown);
+
+        // The strategy must be a live StrategyAccount of an approved strategy
+        // program, bound to this vault at its canonical PDA
+        let strategy_info = &ctx.accounts.strategy_account;
+        require!(
+            strategy_info.key() == strategy && APPROVED_STRATEGY_PROGRAMS.contains(strategy_info.owner),
+            VaultError::InvalidStrategyAccount
+        );
+        let strategy_account =
+            marinade_strategy::StrategyAccount::try_deserialize(&mut &strategy_info.try_borrow_data()?[..])
+                .map_err(|_| VaultError::InvalidStrategyAccount)?;
+        let expected = Pubkey::create_program_address(
+            &[b"marinade_strategy", vault_key.as_ref(), &[strategy_account.bump]],
+            strategy_info.owner,
+        )
+        .map_err(|_| VaultError::InvalidStrategyAccount)?;
+        require!(
+            strategy_account.vault == vault_key && expected == strategy,
+            VaultError::InvalidStrategyAccount
+        );
+
+        vault.set_marinade_strategy(Some(strategy));
+
+        msg!("Strategy set for vault: {}", strategy);
+
+        emit_cpi!(StrategySetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            strategy,
+        });
+
+        Ok(())
+    }
+
+    /// Point holders of this vault at a successor product
+    ///
+    /// With soft_closed = true the vault stops accepting deposits while
+    /// withdrawals and migrate_position stay available, so a deprecated
+    /// product can funnel its holders into the replacement.
+    pub fn suggest_alternative(
+        ctx: Context<SuggestAlternative>,
+        _name: String,
+        soft_closed: bool,
+    ) -> Result<()> {
+        let successor = ctx.accounts.successor_vault.key();
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+        require!(successor != vault_key, VaultError::InvalidSuccessor);
+        require!(vault.wind_down_deadline == 0, VaultError::VaultWindingDown);
+        // A vault still being built by add_vault_asset is opened by finalize_vault
+        vault.validate_weights()?;
+
+        let was_paused = vault.deposits_paused();
+        vault.set_successor(Some(successor));
+        vault.set_soft_closed(soft_closed);
+
+        msg!("Successor for vault '{}' set to {} (soft_closed: {})", vault.name(), successor, soft_closed);
+
+        emit_cpi!(SuccessorSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            successor: vault.successor(),
+            soft_closed,
+        });
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Clear the successor mapping and reopen the vault for deposits
+    pub fn clear_alternative(ctx: Context<ClearAlternative>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        require!(vault.wind_down_deadline == 0, VaultError::VaultWindingDown);
+        vault.validate_weights()?;
+
+        let was_paused = vault.deposits_paused();
+        vault.set_successor(None);
+        vault.set_soft_closed(false);
+
+        msg!("Successor cleared for vault '{}'", vault.name());
+
+        emit_cpi!(SuccessorSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            successor: None,
+            soft_closed: false,
+        });
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Move a position from a vault into its suggested successor in one step
+    ///
+    /// Priced as a withdrawal followed by a deposit: both vaults are valued
+    /// at fresh prices, the burned shares are worth their slice of the old
+    /// vault's NAV, and that value moves as idle SOL into the successor PDA,
+    /// where it buys shares at the successor's NAV net of its deposit fee
+    /// (held to the successor's minimum deposit). Neither side's holders
+    /// are diluted; both baskets are left off target by the SOL that moved
+    /// until their next rebalance. The old vault's idle SOL must cover the
+    /// position, the staked leg must be unwound (remove_strategy) first,
+    /// neither vault may hold wrapped SOL and neither may be restricted.
+    ///
+    /// **remaining_accounts layout:**
+    /// - The vault's snapshot_nav accounts: [mint, ATA] per asset, then its price accounts
+    /// - The successor's snapshot_nav accounts, in the same layout
+    pub fn migrate_position<'info>(
+        ctx: Context<'_, '_, '_, 'info, MigratePosition<'info>>,
+        _name: String,
+        _successor_name: String,
+        shares: u64,
+    ) -> Result<()> {
+        require!(shares > 0, VaultError::InvalidAmount);
+
+        let vault_key = ctx.accounts.vault.key();
+        let successor_key = ctx.accounts.successor_vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let mut successor = ctx.accounts.successor_vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Withdraw)?;
+        successor.begin_operation(VaultOperation::Deposit)?;
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+
+        require!(
+            vault.successor() == Some(successor_key),
+            VaultError::InvalidSuccessor
+        );
+        successor.require_deposits_open()?;
+        require!(
+            vault.marinade_strategy().is_none(),
+            VaultError::StrategyStillActive
+        );
+        require!(
+            vault.wrapped_sol_ata().is_none() && successor.wrapped_sol_ata().is_none(),
+            VaultError::WrappedSolUnsupported
+        );
+        require!(
+            ctx.accounts.user_shares_ata.amount >= shares && shares <= total_shares,
+            VaultError::InsufficientShares
+        );
+
+        // STEP 1: Value both vaults at fresh prices
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let successor_info = ctx.accounts.successor_vault.to_account_info();
+        let quotes = [&ctx.accounts.btc_quote, &ctx.accounts.eth_quote, &ctx.accounts.sol_quote];
+        let vault_accounts = vault.assets().len() * 2 + prices::price_account_count(&vault, ctx.remaining_accounts);
+        require!(
+            ctx.remaining_accounts.len() > vault_accounts,
+            VaultError::InvalidRemainingAccounts
+        );
+        let (vault_remaining, successor_remaining) = ctx.remaining_accounts.split_at(vault_accounts);
+        let nav = view_nav(&vault, &vault_info, &ctx.accounts.vault_token_mint, quotes, vault_remaining)?;
+        let successor_nav = view_nav(
+            &successor,
+            &successor_info,
+            &ctx.accounts.successor_token_mint,
+            quotes,
+            successor_remaining,
+        )?;
+        require!(!nav.restricted && !successor_nav.restricted, VaultError::VaultRestricted);
+
+        // STEP 2: Withdraw - the shares' slice of the vault's NAV, paid in idle SOL
+        let position_usd = math::to_i64(math::mul_div_floor(
+            math::to_u128(nav.tvl_usd)?,
+            shares as u128,
+            total_shares as u128,
+        )?)?;
+        let lamports_to_move = math::to_u64(math::to_u128(nav.prices[2].usd_to_tokens(position_usd, 9)?)?)?;
+        let vault_rent = ctx.accounts.rent.minimum_balance(vault_info.data_len());
+        let vault_native_sol = vault_info
+            .lamports()
+            .saturating_sub(vault_rent)
+            .saturating_sub(vault.fee_reserve());
+        require!(
+            lamports_to_move > 0 && lamports_to_move <= vault_native_sol,
+            VaultError::InsufficientBalance
+        );
+
+        // STEP 3: Deposit - price the SOL against the successor before it lands
+        let deposit_fee = math::bps_of(lamports_to_move, successor.deposit_fee_bps)?;
+        let deposit_usd = successor_nav.prices[2].tokens_to_usd(lamports_to_move - deposit_fee, 9)?;
+        let shares_to_mint =
+            Vault::calculate_shares_for_deposit(deposit_usd, successor_nav.tvl_usd, successor_nav.total_shares)?;
+        successor.require_min_shares(shares_to_mint)?;
+
+        // STEP 4: Burn old shares
+        let successor_signer = successor.signer();
+        let vault_name = vault.name().to_string();
+        let successor_name = successor.name().to_string();
+        // The burn and the mint take the vault accounts
+        drop(vault);
+        drop(successor);
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            from: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let burn_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_accounts);
+        token_interface::burn(burn_ctx, shares)?;
+
+        // STEP 5: Move SOL between the two program-owned vault PDAs
+        **vault_info.try_borrow_mut_lamports()? -= lamports_to_move;
+        **successor_info.try_borrow_mut_lamports()? += lamports_to_move;
+
+        // STEP 6: Mint successor shares (successor PDA signs)
+        let successor_seeds = successor_signer.seeds();
+        let signer_seeds = &[&successor_seeds[..]];
+
+        let mint_accounts = token_interface::MintTo {
+            mint: ctx.accounts.successor_token_mint.to_account_info(),
+            to: ctx.accounts.user_successor_shares_ata.to_account_info(),
+            authority: successor_info.clone(),
+        };
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.successor_share_token_program.to_account_info(),
+            mint_accounts,
+            signer_seeds,
+        );
+        token_interface::mint_to(mint_ctx, shares_to_mint)?;
+
+        msg!(
+            "Migrated {} shares of '{}' into {} shares of '{}' ({} lamports)",
+            shares,
+            vault_name,
+            shares_to_mint,
+            successor_name,
+            lamports_to_move
+        );
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(PositionMigratedEvent {
+            from_vault: vault_key,
+            seq: vault.next_event_seq(),
+            to_vault: successor_key,
+            user: ctx.accounts.user.key(),
+            shares_burned: shares,
+            lamports_moved: lamports_to_move,
+            shares_minted: shares_to_mint,
+        });
+
+        let now = Clock::get()?.unix_timestamp;
+        vault.cache_nav(nav.tvl_usd - position_usd, nav.share_price, total_shares - shares, now);
+        vault.end_operation();
+        let mut successor = ctx.accounts.successor_vault.load_mut()?;
+        let successor_tvl = successor_nav.tvl_usd + deposit_usd;
+        let successor_shares = successor_nav.total_shares + shares_to_mint;
+        let successor_share_price = Vault::calculate_share_price(successor_tvl, successor_shares)?;
+        successor.cache_nav(successor_tvl, successor_share_price, successor_shares, now);
+        successor.pending_fees = successor.pending_fees.saturating_add(deposit_fee);
+        successor.end_operation();
+
+        Ok(())
+    }
+
+    /// Set the lockup applied to newly minted shares (only callable by vault authority)
+    /// Protects long-term holders from rapid in/out arbitrage around oracle updates
+    pub fn set_lockup(ctx: Context<SetLockup>, _name: String, lockup_secs: u32) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        vault.lockup_secs = lockup_secs;
+
+        msg!("Share lockup set to: {}s", lockup_secs);
+
+        Ok(())
+    }
+
+    /// Hand governed instructions to a governance authority
+    ///
+    /// `governance` is typically an SPL Governance (Realms) PDA; proposals then
+    /// execute update_composition, the fee setters and the strategy setters
+    /// with it as signer. With `governance_mode` on, the admin can no longer
+    /// execute those directly.
+    pub fn set_governance(
+        ctx: Context<SetGovernance>,
+        _name: String,
+        governance: Option<Pubkey>,
+        governance_mode: bool,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(
+            !governance_mode || governance.is_some(),
+            VaultError::GovernanceOnly
+        );
+
+        vault.set_governance(governance);
+        vault.set_governance_mode(governance_mode);
+
+        msg!("Governance set to {:?} (governance_mode: {})", governance, governance_mode);
+
+        emit_cpi!(GovernanceSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            governance,
+            governance_mode,
+        });
+
+        Ok(())
+    }
+
+    /// Re-weight the vault's basket (admin or governance)
+    /// Mints and ATAs are fixed at creation; `weights` follows vault.assets order
+    pub fn update_composition(
+        ctx: Context<UpdateComposition>,
+        _name: String,
+        weights: Vec<u8>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(!vault.market_cap_weighted(), VaultError::MarketCapWeighted);
+        require!(!vault.weights_hidden(), VaultError::WeightsHidden);
+        require!(weights.len() == vault.assets().len(), VaultError::InvalidAssetCount);
+        require!(weights.iter().all(|w| *w > 0), VaultError::InvalidWeights);
+
+        for (asset, weight) in vault.assets_mut().iter_mut().zip(weights.iter()) {
+            asset.weight = *weight;
+        }
+        vault.validate_weights()?;
+
+        msg!("Composition updated: {:?}", weights);
+
+        emit_cpi!(CompositionChangedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mints: vault.assets().iter().map(|a| a.mint).collect(),
+            weights,
+        });
+
+        Ok(())
+    }
+
+    /// Switch market-cap index mode on or off (only callable by vault authority)
+    ///
+    /// While enabled, update_composition is rejected and recompute_weights
+    /// sets the weights from posted supplies × oracle prices, at most once
+    /// per `recompute_interval_secs` and by at most `max_weight_change`
+    /// points per asset per run. `supply_authority` may post supplies
+    /// alongside the vault authority.
+    pub fn configure_market_cap_index(
+        ctx: Context<ConfigureMarketCapIndex>,
+        _name: String,
+        enabled: bool,
+        supply_authority: Option<Pubkey>,
+        recompute_interval_secs: i64,
+        max_weight_change: u8,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(
+            recompute_interval_secs >= MIN_RECOMPUTE_INTERVAL_SECS && (1..=100).contains(&max_weight_change),
+            VaultError::InvalidIndexConfig
+        );
+        require!(!enabled || !vault.weights_hidden(), VaultError::WeightsHidden);
+
+        let index = &mut ctx.accounts.market_cap_index;
+        index.vault = vault_key;
+        index.supply_authority = supply_authority.unwrap_or_default();
+        index.recompute_interval_secs = recompute_interval_secs;
+        index.max_weight_change = max_weight_change;
+        index.bump = ctx.bumps.market_cap_index;
+        vault.set_market_cap_weighted(enabled);
+
+        msg!(
+            "Market-cap index {} (every {}s, at most {} points per run)",
+            if enabled { "enabled" } else { "disabled" },
+            recompute_interval_secs,
+            max_weight_change
+        );
+
+        emit_cpi!(MarketCapIndexConfiguredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            enabled,
+            supply_authority,
+            recompute_interval_secs,
+            max_weight_change,
+        });
+
+        Ok(())
+    }
+
+    /// Post each asset's circulating supply, in whole tokens and vault.assets
+    /// order (vault authority or the index's supply authority)
+    pub fn set_asset_supplies(ctx: Context<SetAssetSupplies>, _name: String, supplies: Vec<u64>) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let index = &mut ctx.accounts.market_cap_index;
+
+        require!(
+            index.can_post_supplies(&vault, &ctx.accounts.authority.key()),
+            VaultError::Unauthorized
+        );
+        require!(supplies.len() == vault.assets().len(), VaultError::InvalidAssetCount);
+        require!(supplies.iter().all(|s| *s > 0), VaultError::InvalidIndexConfig);
+
+        index.supplies = supplies.clone();
+        index.supplies_updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(AssetSuppliesSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            supplies,
+        });
+
+        Ok(())
+    }
+
+    /// Recompute a market-cap index vault's weights from supplies × prices
+    ///
+    /// Permissionless crank, accepted once per recompute interval. Each
+    /// weight moves towards its market-cap share by at most the index's
+    /// max_weight_change; rebalance then trades towards the new weights.
+    ///
+    /// **remaining_accounts layout:**
+    /// - MockOracle account or PriceCache (if used), or the Aggregated feed accounts
+    pub fn recompute_weights<'info>(
+        ctx: Context<'_, '_, '_, 'info, RecomputeWeights<'info>>,
+        _name: String,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let index = &ctx.accounts.market_cap_index;
+        let clock = Clock::get()?;
+
+        require!(vault.market_cap_weighted(), VaultError::InvalidIndexConfig);
+        require!(
+            index.supplies_updated_at > 0 && index.supplies.len() == vault.assets().len(),
+            VaultError::SuppliesNotPosted
+        );
+        require!(
+            clock.unix_timestamp - index.last_recompute >= index.recompute_interval_secs,
+            VaultError::RecomputeTooSoon
+        );
+
+        let price_accounts = PriceAccounts {
+            vault: vault_key,
+            btc_quote: ctx.accounts.btc_quote.as_ref(),
+            eth_quote: ctx.accounts.eth_quote.as_ref(),
+            sol_quote: ctx.accounts.sol_quote.as_ref(),
+            remaining_accounts: ctx.remaining_accounts,
+        };
+        let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, &clock, 300)?;
+        require!(vault.assets().len() <= feed_prices.len(), VaultError::InvalidPrice);
+
+        let caps = index.market_caps(&feed_prices);
+        let target = index_weights::market_cap_weights(&caps).ok_or(VaultError::InvalidPrice)?;
+        let current: Vec<u8> = vault.assets().iter().map(|a| a.weight).collect();
+        let weights = index_weights::step_weights(&current, &target, index.max_weight_change);
+
+        for (asset, weight) in vault.assets_mut().iter_mut().zip(weights.iter()) {
+            asset.weight = *weight;
+        }
+        vault.validate_weights()?;
+        ctx.accounts.market_cap_index.last_recompute = clock.unix_timestamp;
+
+        msg!("Weights recomputed: {:?} (market-cap target {:?})", weights, target);
+
+        emit_cpi!(WeightsRecomputedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            target_weights: target,
+            weights,
+        });
+
+        Ok(())
+    }
+
+    /// Register the vault's glide path (only callable by vault authority)
+    ///
+    /// Replaces any previous schedule; `steps` must be in ascending time order
+    /// and each a full composition. An empty list clears the schedule.
+    pub fn set_composition_schedule(
+        ctx: Context<SetCompositionSchedule>,
+        _name: String,
+        steps: Vec<ScheduleStep>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(
+            steps.is_empty() || !vault.market_cap_weighted(),
+            VaultError::MarketCapWeighted
+        );
+        require!(steps.is_empty() || !vault.weights_hidden(), VaultError::WeightsHidden);
+        VaultSchedule::validate(&steps, vault.assets().len())?;
+
+        let step_count = steps.len() as u8;
+        let final_at = steps.last().map(|step| step.at).unwrap_or_default();
+        let schedule = &mut ctx.accounts.vault_schedule;
+        schedule.vault = vault_key;
+        schedule.steps = steps;
+        schedule.next_step = 0;
+        schedule.bump = ctx.bumps.vault_schedule;
+
+        msg!("Composition schedule set: {} steps, last at {}", step_count, final_at);
+
+        emit_cpi!(CompositionScheduleSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            steps: step_count,
+            final_at,
+        });
+
+        Ok(())
+    }
+
+    /// Apply the latest due step of the vault's glide path
+    ///
+    /// Permissionless crank. Steps that came due while nobody cranked are
+    /// skipped in favour of the latest one; rebalance then trades towards
+    /// the new weights.
+    pub fn apply_schedule_step(ctx: Context<ApplyScheduleStep>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let schedule = &mut ctx.accounts.vault_schedule;
+
+        require!(!vault.market_cap_weighted(), VaultError::MarketCapWeighted);
+        require!(!vault.weights_hidden(), VaultError::WeightsHidden);
+        let index = schedule
+            .due_step(Clock::get()?.unix_timestamp)
+            .ok_or(VaultError::ScheduleStepNotDue)?;
+        let weights = schedule.steps[index].weights.clone();
+        require!(weights.len() == vault.assets().len(), VaultError::InvalidAssetCount);
+
+        for (asset, weight) in vault.assets_mut().iter_mut().zip(weights.iter()) {
+            asset.weight = *weight;
+        }
+        vault.validate_weights()?;
+        schedule.next_step = index as u8 + 1;
+
+        msg!("Schedule step {} applied: {:?}", index, weights);
+
+        emit_cpi!(CompositionChangedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mints: vault.assets().iter().map(|a| a.mint).collect(),
+            weights,
+        });
+
+        Ok(())
+    }
+
+    /// Make the vault an LST index, or turn LST pricing off (only callable
+    /// by vault authority)
+    ///
+    /// `pools` names the rate source of each asset in vault.assets order; the
+    /// SOL slot must be LstKind::Sol. Each asset's pool is read once here to
+    /// check it backs the asset's mint and to seed the rates. An empty list
+    /// turns LST pricing off.
+    ///
+    /// **remaining_accounts layout:**
+    /// - [0..n]: pool account of each asset (any account for the SOL slot)
+    pub fn configure_lst_basket<'info>(
+        ctx: Context<'_, '_, '_, 'info, ConfigureLstBasket<'info>>,
+        _name: String,
+        pools: Vec<LstPool>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        let enabled = !pools.is_empty();
+        let rates = if enabled {
+            require!(
+                pools.get(2).is_some_and(|pool| pool.kind == LstKind::Sol),
+                VaultError::InvalidLstPool
+            );
+            lst::read_rates(&vault, &pools, ctx.remaining_accounts)?
+        } else {
+            Vec::new()
+        };
+
+        let basket = &mut ctx.accounts.lst_basket;
+        basket.vault = vault_key;
+        basket.pools = pools.clone();
+        basket.rates = rates.clone();
+        basket.rates_updated_at = Clock::get()?.unix_timestamp;
+        basket.bump = ctx.bumps.lst_basket;
+        vault.set_lst_index(enabled);
+
+        msg!("LST index {}: rates {:?}", if enabled { "enabled" } else { "disabled" }, rates);
+
+        emit_cpi!(LstBasketConfiguredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            pools,
+            rates,
+        });
+
+        Ok(())
+    }
+
+    /// Re-read an LST index's exchange rates from its pools
+    ///
+    /// Permissionless crank; prices accept the rates for MAX_LST_RATE_AGE_SECS.
+    ///
+    /// **remaining_accounts layout:**
+    /// - [0..n]: pool account of each asset, as for configure_lst_basket
+    pub fn refresh_lst_rates<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefreshLstRates<'info>>,
+        _name: String,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let basket = &mut ctx.accounts.lst_basket;
+
+        require!(vault.lst_index(), VaultError::LstBasketRequired);
+        basket.rates = lst::read_rates(&vault, &basket.pools, ctx.remaining_accounts)?;
+        basket.rates_updated_at = Clock::get()?.unix_timestamp;
+
+        msg!("LST rates refreshed: {:?}", basket.rates);
+
+        emit_cpi!(LstRatesRefreshedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            rates: basket.rates.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Configure the time-decaying exit fee (only callable by vault authority)
+    /// `max_fee_bps` applies right after a deposit and decays to zero over `window_secs`
+    pub fn set_exit_fee(
+        ctx: Context<SetExitFee>,
+        _name: String,
+        max_fee_bps: u16,
+        window_secs: u32,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(max_fee_bps <= MAX_EXIT_FEE_BPS, VaultError::InvalidFee);
+
+        vault.exit_fee_bps = max_fee_bps;
+        vault.exit_fee_window_secs = window_secs;
+
+        msg!("Exit fee set to: {} bps decaying over {}s", max_fee_bps, window_secs);
+
+        Ok(())
+    }
+
+    /// Set the name, symbol and URI wallets show for the share token (only callable by vault authority)
+    /// Creates the share mint's Metaplex metadata account on first use, updates it afterwards
+    pub fn set_share_metadata(
+        ctx: Context<SetShareMetadata>,
+        _name: String,
+        share_name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        let signer = {
+            let vault = ctx.accounts.vault.load()?;
+            vault.require_governed_authority(&ctx.accounts.authority.key())?;
+            vault.signer()
+        };
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let vault_info = ctx.accounts.vault.to_account_info();
+        share_metadata::create_or_update(
+            &share_metadata::ShareMetadataAccounts {
+                metadata_program: &ctx.accounts.token_metadata_program.to_account_info(),
+                metadata: &ctx.accounts.metadata.to_account_info(),
+                mint: &ctx.accounts.vault_token_mint.to_account_info(),
+                authority: &vault_info,
+                payer: &ctx.accounts.authority.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+                rent: &ctx.accounts.rent.to_account_info(),
+            },
+            &share_metadata::ShareMetadata {
+                name: &share_name,
+                symbol: &symbol,
+                uri: &uri,
+            },
+            signer_seeds,
+        )?;
+
+        msg!("Share metadata set: {} ({})", share_name, symbol);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(ShareMetadataSetEvent {
+            vault: vault_info.key(),
+            seq: vault.next_event_seq(),
+            name: share_name,
+            symbol,
+            uri,
+        });
+
+        Ok(())
+    }
+
+    /// Convert a vault stored in an older layout to the current one
+    ///
+    /// The account grows to Vault::LEN (the payer funds the extra rent). A
+    /// version 2 (zero-copy) vault gains the zeroed tail and
+    /// Vault::upgrade_v2 moves its packed flags into their fields; a Borsh
+    /// vault from before version 2 is rebuilt by LegacyVault::upgrade, with
+    /// fields a version 0 vault lacked decoding as zero and getting their
+    /// defaults. Permissionless: the conversion is deterministic.
+    pub fn migrate_vault(ctx: Context<MigrateVault>, _name: String) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        require_keys_eq!(*vault_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+
+        let old_len = vault_info.data_len();
+        require!(old_len != Vault::LEN, VaultError::VaultUpToDate);
+        let (v2_vault, (admin, name, bump)) = {
+            let data = vault_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == *Vault::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            if old_len == Vault::V2_LEN {
+                let mut vault = Box::<Vault>::default();
+                bytemuck::bytes_of_mut(&mut *vault)[..old_len - 8].copy_from_slice(&data[8..]);
+                let seeds = (vault.admin, vault.name().to_string(), vault.bump);
+                (Some(vault), seeds)
+            } else {
+                let prefix = VaultPrefix::deserialize(&mut &data[8..])?;
+                (None, (prefix.admin, prefix.name, prefix.bump))
+            }
+        };
+        let expected = Pubkey::create_program_address(
+            &[b"vault", admin.as_ref(), name.as_bytes(), &[bump]],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::ConstraintSeeds)?;
+        require_keys_eq!(expected, vault_info.key(), ErrorCode::ConstraintSeeds);
+
+        let rent = Rent::get()?.minimum_balance(Vault::LEN);
+        let top_up = rent.saturating_sub(vault_info.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: vault_info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        vault_info.resize(Vault::LEN)?;
+
+        let (from_version, mut vault) = match v2_vault {
+            Some(mut vault) => {
+                let from_version = vault.version;
+                vault.upgrade_v2();
+                (from_version, vault)
+            }
+            None => {
+                let legacy = LegacyVault::deserialize(&mut &vault_info.try_borrow_data()?[8..])?;
+                (legacy.version, Box::new(legacy.upgrade()?))
+            }
+        };
+
+        msg!(
+            "Vault '{}' migrated from v{} to v{} ({} -> {} bytes)",
+            vault.name(),
+            from_version,
+            VAULT_VERSION,
+            old_len,
+            Vault::LEN
+        );
+
+        let event = VaultMigratedEvent {
+            vault: vault_info.key(),
+            seq: vault.next_event_seq(),
+            from_version,
+            to_version: VAULT_VERSION,
+        };
+        vault.try_serialize(&mut &mut vault_info.try_borrow_mut_data()?[..])?;
+        emit_cpi!(event);
+
+        Ok(())
+    }
+
+    /// Add a vault created before the registry existed (permissionless; the
+    /// payer covers rent for the registration account)
+    pub fn register_vault(ctx: Context<RegisterVault>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let registry = &mut ctx.accounts.vault_registry;
+        registry.bump = ctx.bumps.vault_registry;
+        let registration = &mut ctx.accounts.vault_registration;
+        registration.register(registry, vault_key, &vault, ctx.bumps.vault_registration)?;
+
+        msg!("Vault '{}' registered at index {}", vault.name(), registration.index);
+
+        emit_cpi!(VaultRegisteredEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            index: registration.index,
+        });
+
+        Ok(())
+    }
+
+    /// Set the description, icon, category and risk level frontends show for the vault
+    /// (only callable by vault authority); creates the VaultMetadata account on first use
+    pub fn set_vault_metadata(
+        ctx: Context<SetVaultMetadata>,
+        _name: String,
+        description: String,
+        icon_uri: String,
+        category: String,
+        risk_level: u8,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        VaultMetadata::validate(&description, &icon_uri, &category, risk_level)?;
+
+        let metadata = &mut ctx.accounts.vault_metadata;
+        metadata.vault = vault_key;
+        metadata.description = description;
+        metadata.icon_uri = icon_uri;
+        metadata.category = category.clone();
+        metadata.risk_level = risk_level;
+        metadata.updated_at = Clock::get()?.unix_timestamp;
+        metadata.bump = ctx.bumps.vault_metadata;
+
+        msg!("Vault metadata set: {} (risk {})", category, risk_level);
+
+        emit_cpi!(VaultMetadataSetEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            category,
+            risk_level,
+        });
+
+        Ok(())
+    }
+
+    /// Create the vault's address lookup table (only callable by vault authority)
+    /// The table is derived from the vault PDA and `recent_slot`; the vault PDA
+    /// is its authority, so only extend_lookup_table can change it
+    pub fn create_lookup_table(ctx: Context<CreateLookupTable>, _name: String, recent_slot: u64) -> Result<()> {
+        let signer = {
+            let vault = ctx.accounts.vault.load()?;
+            vault.require_governed_authority(&ctx.accounts.authority.key())?;
+            vault.signer()
+        };
+        let vault_seeds = signer.seeds();
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let accounts = lookup_table::LookupTableAccounts {
+            lookup_table_program: &ctx.accounts.address_lookup_table_program.to_account_info(),
+            lookup_table: &ctx.accounts.lookup_table.to_account_info(),
+            authority: &vault_info,
+            payer: &ctx.accounts.authority.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        };
+        lookup_table::create(&accounts, recent_slot, &[&vault_seeds[..]])?;
+
+        let record = &mut ctx.accounts.vault_lookup_table;
+        record.vault = vault_info.key();
+        record.lookup_table = ctx.accounts.lookup_table.key();
+        record.address_count = 0;
+        record.updated_at = Clock::get()?.unix_timestamp;
+        record.bump = ctx.bumps.vault_lookup_table;
+
+        msg!("Lookup table created: {}", record.lookup_table);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(LookupTableCreatedEvent {
+            vault: vault_info.key(),
+            seq: vault.next_event_seq(),
+            lookup_table: record.lookup_table,
+        });
+
+        Ok(())
+    }
+
+    /// Add addresses to the vault's lookup table (only callable by vault authority)
+    /// Addresses already in the table are skipped; at most
+    /// MAX_ADDRESSES_PER_EXTEND per call
+    pub fn extend_lookup_table(ctx: Context<ExtendLookupTable>, _name: String, addresses: Vec<Pubkey>) -> Result<()> {
+        let signer = {
+            let vault = ctx.accounts.vault.load()?;
+            vault.require_governed_authority(&ctx.accounts.authority.key())?;
+            vault.signer()
+        };
+        let vault_seeds = signer.seeds();
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let accounts = lookup_table::LookupTableAccounts {
+            lookup_table_program: &ctx.accounts.address_lookup_table_program.to_account_info(),
+            lookup_table: &ctx.accounts.lookup_table.to_account_info(),
+            authority: &vault_info,
+            payer: &ctx.accounts.authority.to_account_info(),
+            system_program: &ctx.accounts.system_program.to_account_info(),
+        };
+        let (added, total) = lookup_table::extend(&accounts, &addresses, &[&vault_seeds[..]])?;
+
+        let record = &mut ctx.accounts.vault_lookup_table;
+        record.address_count = total as u16;
+        record.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!("Lookup table extended: {} added, {} total", added, total);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(LookupTableExtendedEvent {
+            vault: vault_info.key(),
+            seq: vault.next_event_seq(),
+            added: added as u16,
+            total: total as u16,
+        });
+
+        Ok(())
+    }
+
+    /// Set the vault's swap slippage tolerance (only callable by vault authority)
+    /// Every deposit, withdrawal and rebalance swap must return at least its
+    /// oracle quote less `max_slippage_bps`
+    pub fn set_max_slippage(
+        ctx: Context<SetMaxSlippage>,
+        _name: String,
+        max_slippage_bps: u16,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(max_slippage_bps <= MAX_SLIPPAGE_BPS, VaultError::InvalidSlippage);
+
+        vault.max_slippage_bps = max_slippage_bps;
+
+        msg!("Max slippage set to: {} bps", max_slippage_bps);
+
+        Ok(())
+    }
+
+    /// Set the fewest shares a deposit may mint (only callable by vault authority)
+    /// Deposits that would mint fewer (or none) abort with MintedZeroShares,
+    /// so their SOL is never taken without shares in return
+    pub fn set_min_deposit_shares(
+        ctx: Context<SetMinDepositShares>,
+        _name: String,
+        min_shares: u32,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        vault.min_deposit_shares = min_shares;
+
+        msg!("Minimum deposit set to: {} shares", min_shares);
+
+        Ok(())
+    }
+
+    /// Release a vault's operation lock (only callable by vault authority)
+    /// For a confidential rebalance whose result never arrives: the pending
+    /// computation is dropped, so its result can no longer be applied
+    pub fn clear_operation_lock(ctx: Context<ClearOperationLock>, _name: String) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        msg!("Operation lock cleared (was {:?})", vault.operation());
+        vault.end_operation();
+        vault.set_pending_computation_offset(None);
+
+        Ok(())
+    }
+
+    /// Release the lock of a confidential rebalance queued more than
+    /// CONFIDENTIAL_REBALANCE_TIMEOUT_SECS ago
+    /// Permissionless - holders need not wait on the authority to withdraw
+    /// when a result never arrives or is never applied
+    ///
+    /// The queue time comes from the MXE's RebalanceResult of the pending
+    /// computation; the computation is dropped like clear_operation_lock does.
+    pub fn release_stale_rebalance(
+        ctx: Context<ReleaseStaleRebalance>,
+        _name: String,
+        computation_offset: u64,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            vault.operation() == VaultOperation::Rebalance
+                && vault.pending_computation_offset() == Some(computation_offset),
+            VaultError::InvalidConfidentialResult
+        );
+        let result = mxe::queued_result(&ctx.accounts.rebalance_result, &vault_key, computation_offset)?;
+        let stale_at = result
+            .queued_at
+            .checked_add(CONFIDENTIAL_REBALANCE_TIMEOUT_SECS)
+            .ok_or(VaultError::MathOverflow)?;
+        require!(Clock::get()?.unix_timestamp >= stale_at, VaultError::LockNotStale);
+
+        msg!("Stale confidential rebalance {} released", computation_offset);
+        vault.end_operation();
+        vault.set_pending_computation_offset(None);
+
+        Ok(())
+    }
+
+    /// Set the creation unit size for authorized participants (only callable by vault authority)
+    /// 0 disables create_units and redeem_units
+    pub fn set_creation_unit(ctx: Context<SetCreationUnit>, _name: String, unit_shares: u64) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        vault.creation_unit_shares = unit_shares;
+
+        msg!("Creation unit set to: {} shares", unit_shares);
+
+        Ok(())
+    }
+
+    /// Designate `participant` as an authorized participant (only callable by vault authority)
+    pub fn add_authorized_participant(
+        ctx: Context<AddAuthorizedParticipant>,
+        _name: String,
+        participant: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.vault.load()?.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let record = &mut ctx.accounts.authorized_participant;
+        record.vault = ctx.accounts.vault.key();
+        record.participant = participant;
+        record.units_created = 0;
+        record.units_redeemed = 0;
+        record.bump = ctx.bumps.authorized_participant;
+
+        msg!("Authorized participant added: {}", participant);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(AuthorizedParticipantEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            participant,
+            authorized: true,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke an authorized participant (only callable by vault authority)
+    /// Shares the participant already holds stay redeemable through the regular withdrawal
+    pub fn remove_authorized_participant(ctx: Context<RemoveAuthorizedParticipant>, _name: String) -> Result<()> {
+        ctx.accounts.vault.load()?.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let participant = ctx.accounts.authorized_participant.participant;
+        msg!("Authorized participant removed: {}", participant);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(AuthorizedParticipantEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            participant,
+            authorized: false,
+        });
+
+        Ok(())
+    }
+
+    /// Create `units` creation units: the participant delivers the basket in kind
+    /// and receives `units * creation_unit_shares` shares at NAV, with no fees
+    ///
+    /// Each asset is taken in proportion to the vault's holdings (rounded up),
+    /// so the share price is unchanged and no oracle is read. The SOL leg is
+    /// native lamports unless the vault holds it as wrapped SOL. Token-2022
+    /// assets with a transfer fee are grossed up so the vault receives its
+    /// full amount.
+    /// remaining_accounts: [participant token account, vault ATA, mint] per asset
+    pub fn create_units<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateUnits<'info>>,
+        _name: String,
+        units: u64,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        require!(units > 0, VaultError::InvalidAmount);
+        vault.begin_operation(VaultOperation::Deposit)?;
+        vault.require_deposits_open()?;
+
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        require!(
+            vault.creation_unit_shares > 0 && total_shares > 0,
+            VaultError::CreationUnitUnavailable
+        );
+        let shares = units
+            .checked_mul(vault.creation_unit_shares)
+            .ok_or(VaultError::MathOverflow)?;
+
+        let holdings = in_kind_holdings(
+            &vault,
+            ctx.accounts.vault.as_ref(),
+            ctx.remaining_accounts,
+            &ctx.accounts.rent,
+        )?;
+        let mints: Vec<Pubkey> = vault.assets().iter().map(|asset| asset.mint).collect();
+        let signer = vault.signer();
+        // The contributions and the share mint take the vault account
+        drop(vault);
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        let mut amounts = Vec::with_capacity(holdings.len());
+        for (i, holding) in holdings.iter().enumerate() {
+            // Rounded up so a creation never dilutes existing holders
+            let amount = math::pro_rata_ceil(holding.amount, shares, total_shares)?;
+            amounts.push(amount);
+            if amount == 0 {
+                continue;
+            }
+
+            if holding.native {
+                let cpi_accounts = Transfer {
+                    from: ctx.accounts.participant.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+                transfer(cpi_ctx, amount)?;
+            } else {
+                let accounts = &ctx.remaining_accounts[i * IN_KIND_ACCOUNTS_PER_ASSET..];
+                token_io::transfer(
+                    &ctx.accounts.token_program.to_account_info(),
+                    token_2022_program.as_ref(),
+                    &accounts[0],
+                    &accounts[2],
+                    &accounts[1],
+                    &ctx.accounts.participant.to_account_info(),
+                    token_io::amount_before_fee(&accounts[2], amount)?,
+                    &[],
+                )?;
+            }
+            msg!("  • Received {} of {}", amount, mints[i]);
+        }
+
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            to: ctx.accounts.participant_shares_ata.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.share_token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token_interface::mint_to(cpi_ctx, shares)?;
+
+        msg!("🏭 Created {} units = {} shares", units, shares);
+
+        let record = &mut ctx.accounts.authorized_participant;
+        record.units_created = record.units_created.saturating_add(units);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        rescale_nav_cache(&mut vault, total_shares, total_shares + shares)?;
+        vault.end_operation();
+        emit_cpi!(CreationUnitsEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            participant: ctx.accounts.participant.key(),
+            created: true,
+            units,
+            shares,
+            amounts,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem `units` creation units: burn the participant's shares and pay out
+    /// the basket in kind at NAV, with no fees
+    ///
+    /// Each asset is paid in proportion to the vault's holdings (rounded down).
+    /// remaining_accounts: [participant token account, vault ATA, mint] per asset
+    pub fn redeem_units<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemUnits<'info>>,
+        _name: String,
+        units: u64,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let creation_unit_shares = {
+            let mut vault = ctx.accounts.vault.load_mut()?;
+            vault.begin_operation(VaultOperation::Withdraw)?;
+            vault.creation_unit_shares
+        };
+        require!(units > 0, VaultError::InvalidAmount);
+        require!(creation_unit_shares > 0, VaultError::CreationUnitUnavailable);
+
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let shares = units
+            .checked_mul(creation_unit_shares)
+            .ok_or(VaultError::MathOverflow)?;
+        require!(
+            ctx.accounts.participant_shares_ata.amount >= shares,
+            VaultError::InsufficientShares
+        );
+
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        let amounts = pay_in_kind(
+            &ctx.accounts.vault,
+            &ctx.accounts.participant.to_account_info(),
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            token_2022_program.as_ref(),
+            &ctx.accounts.rent,
+            shares,
+            total_shares,
+        )?;
+
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            from: ctx.accounts.participant_shares_ata.to_account_info(),
+            authority: ctx.accounts.participant.to_account_info(),
+        };
+        let burn_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_accounts);
+        token_interface::burn(burn_ctx, shares)?;
+
+        msg!("🏭 Redeemed {} units = {} shares", units, shares);
+
+        let record = &mut ctx.accounts.authorized_participant;
+        record.units_redeemed = record.units_redeemed.saturating_add(units);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        rescale_nav_cache(&mut vault, total_shares, total_shares - shares)?;
+        vault.end_operation();
+        emit_cpi!(CreationUnitsEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            participant: ctx.accounts.participant.key(),
+            created: false,
+            units,
+            shares,
+            amounts,
+        });
+
+        Ok(())
+    }
+
+    /// Start winding the vault down (only callable by vault authority)
+    ///
+    /// Deposits freeze for good. Holders exit through withdraw_multi_asset
+    /// (paid in SOL) or redeem_wind_down (paid in kind, no fees or prices);
+    /// once `grace_period_secs` have passed and every share is redeemed,
+    /// close_vault reclaims the rent. The strategy must be removed first.
+    pub fn begin_wind_down(ctx: Context<BeginWindDown>, _name: String, grace_period_secs: i64) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(vault.wind_down_deadline == 0, VaultError::VaultWindingDown);
+        require!(vault.marinade_strategy().is_none(), VaultError::StrategyActive);
+        require!(
+            grace_period_secs >= MIN_WIND_DOWN_GRACE_SECS,
+            VaultError::InvalidGracePeriod
+        );
+
+        let was_paused = vault.deposits_paused();
+        vault.set_soft_closed(true);
+        vault.wind_down_deadline = Clock::get()?
+            .unix_timestamp
+            .checked_add(grace_period_secs)
+            .ok_or(VaultError::MathOverflow)?;
+
+        msg!("Vault '{}' winding down until {}", vault.name(), vault.wind_down_deadline);
+
+        emit_cpi!(WindDownStartedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            deadline: vault.wind_down_deadline,
+        });
+        if let Some(event) = pause_change_event(&mut vault, vault_key, was_paused) {
+            emit_cpi!(event);
+        }
+
+        Ok(())
+    }
+
+    /// Redeem `shares` of a winding-down vault for their slice of every asset
+    ///
+    /// Fee-free and oracle-free, and lockups no longer apply.
+    /// remaining_accounts: [user token account, vault ATA, mint] per asset (the SOL
+    /// slot pays native lamports to the user while the vault holds native SOL).
+    pub fn redeem_wind_down<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemWindDown<'info>>,
+        _name: String,
+        shares: u64,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let wind_down_deadline = {
+            let mut vault = ctx.accounts.vault.load_mut()?;
+            vault.begin_operation(VaultOperation::Withdraw)?;
+            vault.wind_down_deadline
+        };
+        require!(wind_down_deadline != 0, VaultError::NotWindingDown);
+        require!(shares > 0, VaultError::InvalidAmount);
+        require!(
+            ctx.accounts.user_shares_ata.amount >= shares,
+            VaultError::InsufficientShares
+        );
+
+        let total_shares = ctx.accounts.vault_token_mint.supply;
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        let amounts = pay_in_kind(
+            &ctx.accounts.vault,
+            &ctx.accounts.user.to_account_info(),
+            ctx.remaining_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            token_2022_program.as_ref(),
+            &ctx.accounts.rent,
+            shares,
+            total_shares,
+        )?;
+
+        let burn_accounts = token_interface::Burn {
+            mint: ctx.accounts.vault_token_mint.to_account_info(),
+            from: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let burn_ctx = CpiContext::new(ctx.accounts.share_token_program.to_account_info(), burn_accounts);
+        token_interface::burn(burn_ctx, shares)?;
+
+        msg!("🏁 Redeemed {} shares in kind", shares);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        rescale_nav_cache(&mut vault, total_shares, total_shares - shares)?;
+        vault.end_operation();
+        emit_cpi!(WindDownRedeemedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            user: ctx.accounts.user.key(),
+            shares,
+            amounts,
+        });
+
+        Ok(())
+    }
+
+    /// Close a wound-down vault (only callable by vault authority)
+    ///
+    /// Needs the grace period over, no shares outstanding and no fees owed.
+    /// Rounding dust in each vault ATA goes to the paired receiver, the ATAs
+    /// and the Vault (plus its registry entry) are closed to the admin, and
+    /// the share mint loses its mint authority.
+    /// remaining_accounts: [vault ATA, dust receiver token account, mint] per asset.
+    pub fn close_vault<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseVault<'info>>,
+        _name: String,
+    ) -> Result<()> {
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(vault.wind_down_deadline != 0, VaultError::NotWindingDown);
+        require!(vault.operation() == VaultOperation::Idle, VaultError::VaultBusy);
+        require!(
+            Clock::get()?.unix_timestamp >= vault.wind_down_deadline
+                && ctx.accounts.vault_token_mint.supply == 0
+                && vault.fee_reserve() == 0,
+            VaultError::WindDownIncomplete
+        );
+        require!(
+            ctx.remaining_accounts.len() == vault.assets().len() * IN_KIND_ACCOUNTS_PER_ASSET,
+            VaultError::InvalidRemainingAccounts
+        );
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let assets: Vec<(Pubkey, Pubkey)> = vault.assets().iter().map(|asset| (asset.mint, asset.ata)).collect();
+        let (vault_name, admin) = (vault.name().to_string(), vault.admin);
+        // The vault signs the closes below
+        drop(vault);
+
+        for (i, &(asset_mint, asset_ata)) in assets.iter().enumerate() {
+            let accounts = &ctx.remaining_accounts[i * IN_KIND_ACCOUNTS_PER_ASSET..];
+            let (ata_info, receiver, mint) = (&accounts[0], &accounts[1], &accounts[2]);
+            require!(ata_info.key() == asset_ata, VaultError::InvalidATA);
+            require!(mint.key() == asset_mint, VaultError::InvalidMint);
+            if ata_info.data_is_empty() {
+                continue;
+            }
+
+            let dust = token_io::token_amount(ata_info)?;
+            if dust > 0 {
+                token_io::transfer(
+                    &token_program,
+                    token_2022_program.as_ref(),
+                    ata_info,
+                    mint,
+                    receiver,
+                    &vault_info,
+                    dust,
+                    signer_seeds,
+                )?;
+            }
+
+            let cpi_accounts = anchor_spl::token_interface::CloseAccount {
+                account: ata_info.clone(),
+                destination: ctx.accounts.admin.to_account_info(),
+                authority: vault_info.clone(),
+            };
+            let program = token_io::program_for(ata_info, &token_program, token_2022_program.as_ref())?;
+            anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+                program.clone(),
+                cpi_accounts,
+                signer_seeds,
+            ))?;
+            msg!("  • Closed ATA for {} ({} dust)", asset_mint, dust);
+        }
+
+        let cpi_accounts = token_interface::SetAuthority {
+            current_authority: vault_info.clone(),
+            account_or_mint: ctx.accounts.vault_token_mint.to_account_info(),
+        };
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.share_token_program.to_account_info(),
+                cpi_accounts,
+                signer_seeds,
+            ),
+            anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType::MintTokens,
+            None,
+        )?;
+
+        msg!("🔒 Vault '{}' closed", vault_name);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(VaultClosedEvent {
+            vault: vault_info.key(),
+            seq: vault.next_event_seq(),
+            admin,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the simulated pool behind the Mock swap backend (only callable by vault authority)
+    /// `depth_usd` is the liquidity on each side in micro-USD; 0 disables price impact
+    pub fn set_mock_pool(
+        ctx: Context<SetMockPool>,
+        _name: String,
+        fee_bps: u16,
+        depth_usd: u64,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(fee_bps <= MAX_MOCK_POOL_FEE_BPS, VaultError::InvalidFee);
+
+        vault.set_mock_pool(MockPoolConfig { fee_bps, depth_usd });
+
+        msg!("Mock pool set to: {} bps fee, ${} depth", fee_bps, depth_usd / 1_000_000);
+
+        Ok(())
+    }
+
+    /// Configure the deposit fee and the referrer's share of it (only callable by vault authority)
+    /// `referral_share_bps` is in bps of the fee, not of the deposit
+    pub fn set_deposit_fee(
+        ctx: Context<SetDepositFee>,
+        _name: String,
+        fee_bps: u16,
+        referral_share_bps: u16,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(fee_bps <= MAX_DEPOSIT_FEE_BPS, VaultError::InvalidFee);
+        require!(referral_share_bps <= 10_000, VaultError::InvalidFee);
+
+        vault.deposit_fee_bps = fee_bps;
+        vault.referral_share_bps = referral_share_bps;
+
+        msg!("Deposit fee set to: {} bps ({} bps of it to referrers)", fee_bps, referral_share_bps);
+
+        Ok(())
+    }
+
+    /// Create the protocol-wide fee config; the caller becomes its authority
+    pub fn initialize_protocol_config(
+        ctx: Context<InitializeProtocolConfig>,
+        treasury: Pubkey,
+        creator_fee_share_bps: u16,
+    ) -> Result<()> {
+        require!(creator_fee_share_bps <= 10_000, VaultError::InvalidFee);
+
+        let config = &mut ctx.accounts.protocol_config;
+        config.authority = ctx.accounts.authority.key();
+        config.treasury = treasury;
+        config.creator_fee_share_bps = creator_fee_share_bps;
+        config.bump = ctx.bumps.protocol_config;
+
+        msg!("Protocol config initialized - treasury: {}, creator share: {} bps", treasury, creator_fee_share_bps);
+
+        Ok(())
+    }
+
+    /// Update the treasury and the creator's fee share (only callable by config authority)
+    /// The new share applies to fees not yet settled by a claim
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        treasury: Pubkey,
+        creator_fee_share_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+
+        require!(
+            ctx.accounts.authority.key() == config.authority,
+            VaultError::Unauthorized
+        );
+        require!(creator_fee_share_bps <= 10_000, VaultError::InvalidFee);
+
+        config.treasury = treasury;
+        config.creator_fee_share_bps = creator_fee_share_bps;
+
+        msg!("Protocol config updated - treasury: {}, creator share: {} bps", treasury, creator_fee_share_bps);
+
+        Ok(())
+    }
+
+    /// Replace the protocol fee tiers (only callable by config authority)
+    ///
+    /// Each tier sets the protocol's share of fees for vaults whose cached
+    /// TVL is at or above its threshold, so larger vaults can pay less; fees
+    /// are split at the tier the vault is in when they are next settled.
+    /// Tiers must be strictly ascending by TVL; an empty list turns them off.
+    pub fn set_protocol_fee_tiers(ctx: Context<UpdateProtocolConfig>, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+
+        require!(
+            ctx.accounts.authority.key() == config.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            fee_tiers.len() <= MAX_FEE_TIERS
+                && fee_tiers.iter().all(|tier| tier.protocol_fee_share_bps <= 10_000)
+                && fee_tiers.windows(2).all(|pair| pair[0].min_tvl_usd < pair[1].min_tvl_usd),
+            VaultError::InvalidFeeTiers
+        );
+
+        config.fee_tiers = fee_tiers;
+
+        for tier in &config.fee_tiers {
+            msg!("Fee tier: from ${} TVL, protocol share {} bps", tier.min_tvl_usd / 1_000_000, tier.protocol_fee_share_bps);
+        }
+
+        Ok(())
+    }
+
+    /// Replace the approved alternates to Portal wBTC/wETH (only callable by
+    /// config authority)
+    ///
+    /// The first call creates the whitelist, from which point create_vault
+    /// rejects any other mint in the BTC and ETH slots.
+    pub fn set_asset_whitelist(
+        ctx: Context<SetAssetWhitelist>,
+        btc_alternates: Vec<Pubkey>,
+        eth_alternates: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            btc_alternates.len() <= MAX_APPROVED_ALTERNATES && eth_alternates.len() <= MAX_APPROVED_ALTERNATES,
+            VaultError::InvalidAssetCount
+        );
+
+        let whitelist = &mut ctx.accounts.asset_whitelist;
+        whitelist.btc_alternates = btc_alternates;
+        whitelist.eth_alternates = eth_alternates;
+        whitelist.bump = ctx.bumps.asset_whitelist;
+
+        msg!(
+            "Asset whitelist set - BTC alternates: {:?}, ETH alternates: {:?}",
+            whitelist.btc_alternates,
+            whitelist.eth_alternates
+        );
+
+        Ok(())
+    }
+
+    /// Set the Switchboard feed hashes Switchboard and CrossChecked vaults
+    /// accept for BTC, ETH and SOL (only callable by config authority)
+    ///
+    /// The quote accounts are passed unchecked, so a pull feed only prices a
+    /// market when its feed hash (what its oracle jobs fetch) matches.
+    pub fn set_switchboard_config(
+        ctx: Context<SetSwitchboardConfig>,
+        feed_hashes: [[u8; 32]; 3],
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            feed_hashes.iter().all(|hash| *hash != [0; 32]),
+            VaultError::InvalidPriceFeeds
+        );
+
+        let config = &mut ctx.accounts.switchboard_config;
+        config.feed_hashes = feed_hashes;
+        config.bump = ctx.bumps.switchboard_config;
+
+        msg!("Switchboard feed hashes set for BTC/ETH/SOL");
+
+        Ok(())
+    }
+
+    /// Set the Chainlink Data Streams feed ids Chainlink vaults price BTC, ETH
+    /// and SOL from (only callable by config authority)
+    ///
+    /// Only v3 (crypto) feeds are supported. A market whose feed id changes
+    /// drops its last price until a report of the new feed is posted.
+    pub fn set_chainlink_config(
+        ctx: Context<SetChainlinkConfig>,
+        feed_ids: [[u8; 32]; 3],
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            feed_ids.iter().all(|id| id[..2] == [0, 3]),
+            VaultError::InvalidPriceFeeds
+        );
+
+        let config = &mut ctx.accounts.chainlink_config;
+        for (market, feed_id) in feed_ids.iter().enumerate() {
+            if config.feed_ids[market] != *feed_id {
+                config.prices[market] = 0;
+                config.observed_at[market] = 0;
+            }
+        }
+        config.feed_ids = feed_ids;
+        config.bump = ctx.bumps.chainlink_config;
+
+        msg!("Chainlink Data Streams feed ids set for BTC/ETH/SOL");
+
+        Ok(())
+    }
+
+    /// Verify a Chainlink Data Streams report and keep its price for
+    /// Chainlink vaults (permissionless)
+    ///
+    /// `signed_report` goes to the Chainlink verifier as is (the
+    /// snappy-compressed full report). The verifier checks the DON's
+    /// signatures and that its access controller allows the relayer, then
+    /// returns the report; its benchmark price replaces the stored one of the
+    /// market with its feed id if observed later and not yet expired.
+    pub fn post_chainlink_report(ctx: Context<PostChainlinkReport>, signed_report: Vec<u8>) -> Result<()> {
+        use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+        use anchor_lang::solana_program::program::{get_return_data, invoke};
+
+        let accounts = &ctx.accounts;
+        let ix = Instruction {
+            program_id: chainlink::VERIFIER_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(accounts.verifier_account.key(), false),
+                AccountMeta::new_readonly(accounts.access_controller.key(), false),
+                AccountMeta::new_readonly(accounts.relayer.key(), true),
+                AccountMeta::new_readonly(accounts.report_config.key(), false),
+            ],
+            data: chainlink::verify_instruction_data(&signed_report),
+        };
+        invoke(
+            &ix,
+            &[
+                accounts.verifier_account.to_account_info(),
+                accounts.access_controller.to_account_info(),
+                accounts.relayer.to_account_info(),
+                accounts.report_config.to_account_info(),
+                accounts.verifier_program.to_account_info(),
+            ],
+        )?;
+
+        let (program_id, report_data) = get_return_data().ok_or(VaultError::InvalidQuote)?;
+        require_keys_eq!(program_id, chainlink::VERIFIER_PROGRAM_ID, VaultError::InvalidQuote);
+        let report = chainlink::parse_report_v3(&report_data).ok_or(VaultError::InvalidQuote)?;
+        require!(
+            report.expires_at as i64 >= Clock::get()?.unix_timestamp,
+            VaultError::StaleQuote
+        );
+        let price = report.micro_usd().ok_or(VaultError::InvalidPrice)?;
+
+        let config = &mut ctx.accounts.chainlink_config;
+        let market = config
+            .feed_ids
+            .iter()
+            .position(|feed_id| *feed_id == report.feed_id)
+            .ok_or(VaultError::InvalidQuote)?;
+        require!(
+            report.observations_timestamp as i64 > config.observed_at[market],
+            VaultError::StaleQuote
+        );
+        config.prices[market] = price;
+        config.observed_at[market] = report.observations_timestamp as i64;
+
+        msg!(
+            "Chainlink market {} priced at {} micro-USD (observed {})",
+            market,
+            price,
+            report.observations_timestamp
+        );
+
+        Ok(())
+    }
+
+    /// Set the Pyth Lazer signers and feed ids PythLazer vaults price from
+    /// (only callable by config authority)
+    ///
+    /// `feed_ids` are the Lazer ids of BTC, ETH and SOL. Replacing the
+    /// signers takes effect for every PythLazer vault at once.
+    pub fn set_pyth_lazer_config(
+        ctx: Context<SetPythLazerConfig>,
+        trusted_signers: Vec<Pubkey>,
+        feed_ids: [u32; 3],
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            !trusted_signers.is_empty() && trusted_signers.len() <= MAX_PYTH_LAZER_SIGNERS,
+            VaultError::InvalidPriceFeeds
+        );
+        require!(feed_ids.iter().all(|id| *id > 0), VaultError::InvalidPriceFeeds);
+
+        let config = &mut ctx.accounts.pyth_lazer_config;
+        config.trusted_signers = trusted_signers;
+        config.feed_ids = feed_ids;
+        config.bump = ctx.bumps.pyth_lazer_config;
+
+        msg!(
+            "Pyth Lazer config set - signers: {:?}, BTC/ETH/SOL feeds: {:?}",
+            config.trusted_signers,
+            config.feed_ids
+        );
+
+        Ok(())
+    }
+
+    /// Create or replace a vault template (only callable by config authority)
+    ///
+    /// Templates are audited presets, e.g. "Blue Chip 40/30/30" or "LST
+    /// Basket": a composition of at most MAX_TEMPLATE_ASSETS assets plus the
+    /// pricing and swap settings create_vault_from_template gives new vaults.
+    /// Replacing a template does not change vaults already created from it.
+    pub fn set_vault_template(
+        ctx: Context<SetVaultTemplate>,
+        template_name: String,
+        preset: VaultPreset,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            !template_name.is_empty() && template_name.len() <= MAX_NAME_LEN,
+            VaultError::InvalidName
+        );
+        validate_preset(&preset)?;
+
+        let template = &mut ctx.accounts.vault_template;
+        template.name = template_name;
+        template.preset = preset;
+        template.bump = ctx.bumps.vault_template;
+
+        msg!(
+            "Vault template '{}' set - {} assets, {:?} pricing, {:?} swaps",
+            template.name,
+            template.preset.assets.len(),
+            template.preset.price_source,
+            template.preset.swap_backend
+        );
+
+        Ok(())
+    }
+
+    /// Delete a vault template, returning its rent to the config authority
+    pub fn remove_vault_template(ctx: Context<RemoveVaultTemplate>, _template_name: String) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+            VaultError::Unauthorized
+        );
+
+        msg!("Vault template '{}' removed", ctx.accounts.vault_template.name);
+
+        Ok(())
+    }
+
+    /// Pay the protocol's share of the vault's fees to the treasury (permissionless)
+    pub fn claim_protocol_fees(ctx: Context<ClaimProtocolFees>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let creator_fee_share_bps = ctx.accounts.protocol_config.creator_fee_share_bps_at(vault.last_tvl_usd);
+        vault.settle_fees(creator_fee_share_bps)?;
+
+        let lamports = vault.protocol_fees_owed;
+        require!(lamports > 0, VaultError::InvalidAmount);
+        vault.protocol_fees_owed = 0;
+
+        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += lamports;
+
+        msg!("💸 Paid {} lamports of protocol fees to treasury", lamports);
+
+        emit_cpi!(FeesClaimedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            recipient: ctx.accounts.treasury.key(),
+            to_creator: false,
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the creator's share of the vault's fees to the vault admin
+    pub fn claim_creator_fees(ctx: Context<ClaimCreatorFees>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        let creator_fee_share_bps = ctx.accounts.protocol_config.creator_fee_share_bps_at(vault.last_tvl_usd);
+        vault.settle_fees(creator_fee_share_bps)?;
+
+        let lamports = vault.creator_fees_owed;
+        require!(lamports > 0, VaultError::InvalidAmount);
+        vault.creator_fees_owed = 0;
+
+        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += lamports;
+
+        msg!("💸 Paid {} lamports of creator fees to vault admin", lamports);
+
+        emit_cpi!(FeesClaimedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            recipient: ctx.accounts.authority.key(),
+            to_creator: true,
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Send the whole balance of a vault-owned token account whose mint is
+    /// not part of the composition to the treasury's account for that mint
+    /// (only callable by vault authority)
+    ///
+    /// Recovers airdrops and tokens sent to the vault by mistake, which TVL
+    /// never counts. Composition assets and wrapped SOL can't be swept, so
+    /// this never moves holders' funds. Works for SPL Token and Token-2022
+    /// mints; pass token_2022_program for the latter.
+    pub fn sweep_unknown_token(ctx: Context<SweepUnknownToken>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let mint = ctx.accounts.mint.key();
+        require!(
+            vault.get_asset_by_mint(&mint).is_none() && mint != anchor_spl::token::spl_token::native_mint::ID,
+            VaultError::CompositionAssetSweep
+        );
+
+        let source = token_io::load_token_account(&ctx.accounts.vault_token_account)?;
+        require!(source.owner == vault_key && source.mint == mint, VaultError::InvalidATA);
+        let destination = token_io::load_token_account(&ctx.accounts.treasury_token_account)?;
+        require!(
+            destination.owner == ctx.accounts.protocol_config.treasury && destination.mint == mint,
+            VaultError::InvalidATA
+        );
+        let amount = source.amount;
+        require!(amount > 0, VaultError::InvalidAmount);
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        // The vault signs the transfer below
+        drop(vault);
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        token_io::transfer(
+            &ctx.accounts.token_program.to_account_info(),
+            token_2022_program.as_ref(),
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.vault.to_account_info(),
+            amount,
+            &[&vault_seeds[..]],
+        )?;
+        msg!("🧹 Swept {} of {} to the treasury", amount, mint);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(StrayTokenSweptEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mint,
+            from: ctx.accounts.vault_token_account.key(),
+            to: ctx.accounts.treasury_token_account.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Close an empty vault-owned token account whose mint is not part of the
+    /// composition and return its rent to the vault admin (only callable by
+    /// vault authority)
+    ///
+    /// For ATAs left behind by assets no longer in the basket, or by tokens
+    /// swept with sweep_unknown_token. Composition assets' ATAs and wrapped
+    /// SOL accounts stay open; close_vault closes those when a vault winds down.
+    pub fn close_asset_ata(ctx: Context<CloseAssetAta>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let account_info = &ctx.accounts.vault_token_account;
+        let account = token_io::load_token_account(account_info)?;
+        require!(account.owner == vault_key, VaultError::InvalidATA);
+        require!(
+            account.amount == 0
+                && vault.get_asset_by_mint(&account.mint).is_none()
+                && vault.assets().iter().all(|asset| asset.ata != account_info.key())
+                && account.mint != anchor_spl::token::spl_token::native_mint::ID,
+            VaultError::AssetAccountInUse
+        );
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        // The vault signs the close below
+        drop(vault);
+        let lamports = account_info.lamports();
+        let token_program = ctx.accounts.token_program.to_account_info();
+        let token_2022_program = ctx.accounts.token_2022_program.as_ref().map(|p| p.to_account_info());
+        let program = token_io::program_for(account_info, &token_program, token_2022_program.as_ref())?;
+        let cpi_accounts = anchor_spl::token_interface::CloseAccount {
+            account: account_info.to_account_info(),
+            destination: ctx.accounts.admin.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        anchor_spl::token_interface::close_account(CpiContext::new_with_signer(
+            program.clone(),
+            cpi_accounts,
+            &[&vault_seeds[..]],
+        ))?;
+        msg!("🗑️  Closed unused token account for {}, {} lamports of rent returned", account.mint, lamports);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(AssetAtaClosedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            account: ctx.accounts.vault_token_account.key(),
+            mint: account.mint,
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Register the caller as a referrer for this vault
+    /// Creates the ReferralBalance that referred deposits credit
+    pub fn register_referrer(ctx: Context<RegisterReferrer>, _name: String) -> Result<()> {
+        let referral = &mut ctx.accounts.referral_balance;
+        referral.vault = ctx.accounts.vault.key();
+        referral.referrer = ctx.accounts.referrer.key();
+        referral.accrued_lamports = 0;
+        referral.total_earned = 0;
+        referral.bump = ctx.bumps.referral_balance;
+
+        msg!("Referrer {} registered for vault '{}'", referral.referrer, ctx.accounts.vault.load()?.name());
+
+        Ok(())
+    }
+
+    /// Pay out the referrer's accrued referral fees
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>, _name: String) -> Result<()> {
+        let referral = &mut ctx.accounts.referral_balance;
+        let lamports = referral.accrued_lamports;
+        require!(lamports > 0, VaultError::InvalidAmount);
+
+        **referral.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.referrer.to_account_info().try_borrow_mut_lamports()? += lamports;
+        referral.accrued_lamports = 0;
+
+        msg!("💸 Claimed {} lamports of referral fees", lamports);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(ReferralFeesClaimedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            referrer: ctx.accounts.referrer.key(),
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of deposit fees paid to share stakers (only callable by vault authority)
+    /// Like a protocol config change, the new share applies to fees not yet settled
+    pub fn set_staker_fee_share(
+        ctx: Context<SetStakerFeeShare>,
+        _name: String,
+        staker_fee_share_bps: u16,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(staker_fee_share_bps <= 10_000, VaultError::InvalidFee);
+
+        vault.staker_fee_share_bps = staker_fee_share_bps;
+
+        msg!("Staker fee share set to: {} bps", staker_fee_share_bps);
+
+        Ok(())
+    }
+
+    /// Create the vault's share stake pool and the ATA that holds staked shares
+    pub fn initialize_stake_pool(ctx: Context<InitializeStakePool>, _name: String) -> Result<()> {
+        let pool = &mut ctx.accounts.stake_pool;
+        pool.vault = ctx.accounts.vault.key();
+        pool.total_staked = 0;
+        pool.reward_per_share = 0;
+        pool.total_distributed = 0;
+        pool.bump = ctx.bumps.stake_pool;
+
+        msg!("Stake pool initialized for vault '{}'", ctx.accounts.vault.load()?.name());
+
+        Ok(())
+    }
+
+    /// Stake vault shares to earn the stakers' share of the vault's fees
+    pub fn stake_shares(ctx: Context<StakeShares>, _name: String, shares: u64) -> Result<()> {
+        require!(shares > 0, VaultError::InvalidAmount);
+        require!(
+            !soulbound::is_soulbound(&ctx.accounts.vault_token_mint.to_account_info()),
+            VaultError::SoulboundShares
+        );
+
+        let transfer_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.user_shares_ata.to_account_info(),
+            to: ctx.accounts.pool_shares_ata.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let transfer_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_accounts);
+        anchor_spl::token::transfer(transfer_ctx, shares)?;
+
+        let pool = &mut ctx.accounts.stake_pool;
+        let position = &mut ctx.accounts.stake_position;
+        position.init_if_new(pool.key(), ctx.accounts.owner.key(), ctx.bumps.stake_position);
+        position.accrue(pool.reward_per_share);
+        let staked = position.shares + shares;
+        position.set_shares(staked, pool.reward_per_share);
+        pool.total_staked += shares;
+
+        msg!("🔒 Staked {} shares ({} staked in pool)", shares, pool.total_staked);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(SharesStakedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            owner: ctx.accounts.owner.key(),
+            shares,
+            total_staked: pool.total_staked,
+        });
+
+        Ok(())
+    }
+
+    /// Unstake vault shares; rewards earned so far stay claimable
+    pub fn unstake_shares(ctx: Context<UnstakeShares>, _name: String, shares: u64) -> Result<()> {
+        require!(shares > 0, VaultError::InvalidAmount);
+
+        let pool = &mut ctx.accounts.stake_pool;
+        let position = &mut ctx.accounts.stake_position;
+        require!(shares <= position.shares, VaultError::InsufficientShares);
+
+        position.accrue(pool.reward_per_share);
+        let staked = position.shares - shares;
+        position.set_shares(staked, pool.reward_per_share);
+        pool.total_staked -= shares;
+
+        let vault_key = ctx.accounts.vault.key();
+        let pool_seeds = &[b"stake_pool".as_ref(), vault_key.as_ref(), &[pool.bump]];
+        let signer_seeds = &[&pool_seeds[..]];
+
+        let transfer_accounts = anchor_spl::token::Transfer {
+            from: ctx.accounts.pool_shares_ata.to_account_info(),
+            to: ctx.accounts.user_shares_ata.to_account_info(),
+            authority: pool.to_account_info(),
+        };
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        anchor_spl::token::transfer(transfer_ctx, shares)?;
+
+        msg!("🔓 Unstaked {} shares ({} staked in pool)", shares, pool.total_staked);
+
+        let total_staked = pool.total_staked;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(SharesUnstakedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            owner: ctx.accounts.owner.key(),
+            shares,
+            total_staked,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out the staker's accrued fee rewards
+    pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>, _name: String) -> Result<()> {
+        let pool = &mut ctx.accounts.stake_pool;
+        let position = &mut ctx.accounts.stake_position;
+        position.accrue(pool.reward_per_share);
+
+        let lamports = position.unclaimed;
+        require!(lamports > 0, VaultError::InvalidAmount);
+        position.unclaimed = 0;
+
+        **pool.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **ctx.accounts.owner.to_account_info().try_borrow_mut_lamports()? += lamports;
+
+        msg!("💸 Claimed {} lamports of staking rewards", lamports);
+
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(StakingRewardsClaimedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            owner: ctx.accounts.owner.key(),
+            lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Move the stakers' share of the vault's fees into the stake pool (permissionless)
+    /// Rewards are split pro rata over the shares staked at this moment
+    pub fn distribute_staking_rewards(ctx: Context<DistributeStakingRewards>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let pool = &mut ctx.accounts.stake_pool;
+        require!(pool.total_staked > 0, VaultError::NoStakers);
+
+        let creator_fee_share_bps = ctx.accounts.protocol_config.creator_fee_share_bps_at(vault.last_tvl_usd);
+        vault.settle_fees(creator_fee_share_bps)?;
+
+        let lamports = vault.staker_fees_owed;
+        require!(lamports > 0, VaultError::InvalidAmount);
+        vault.staker_fees_owed = 0;
+
+        **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? -= lamports;
+        **pool.to_account_info().try_borrow_mut_lamports()? += lamports;
+        pool.distribute(lamports);
+
+        msg!("💸 Distributed {} lamports over {} staked shares", lamports, pool.total_staked);
+
+        emit_cpi!(StakingRewardsDistributedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            lamports,
+            total_staked: pool.total_staked,
+        });
+
+        Ok(())
+    }
+
+    /// Remove strategy from vault (only callable by vault authority)
+    /// This makes the vault work standalone without delegation
+    pub fn remove_strategy(ctx: Context<RemoveStrategy>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        // Only vault admin (or governance) can remove strategy
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let strategy = vault.marinade_strategy();
+        vault.set_marinade_strategy(None);
+
+        msg!("Strategy removed from vault");
+
+        emit_cpi!(StrategyRemovedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            strategy,
+        });
+
+        Ok(())
+    }
+
+    /// Pull `msol_amount` of the strategy's mSOL back into the vault as idle SOL
+    ///
+    /// Liquid-unstakes through Marinade into the vault's system-owned SOL
+    /// buffer PDA (Marinade only pays out to system accounts), then forwards
+    /// the lamports to the vault PDA, where withdrawals draw native SOL from.
+    /// Lets the authority pre-fund expected withdrawals or cut staking
+    /// exposure outside the withdrawal flow.
+    pub fn divest_from_strategy(ctx: Context<DivestFromStrategy>, _name: String, msol_amount: u64) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(msol_amount > 0, VaultError::InvalidAmount);
+        vault.begin_operation(VaultOperation::StrategyExit)?;
+
+        let strategy_key = ctx.accounts.strategy_account.key();
+        require!(vault.marinade_strategy() == Some(strategy_key), VaultError::MarinadeError);
+
+        verify_unstake_accounts(
+            &ctx.accounts.marinade_program,
+            &ctx.accounts.marinade_state,
+            &ctx.accounts.msol_mint,
+            &ctx.accounts.liq_pool_msol_leg,
+            &ctx.accounts.liq_pool_sol_leg_pda,
+            &ctx.accounts.treasury_msol_account,
+        )?;
+
+        let strategy_data = ctx.accounts.strategy_account.try_borrow_data()?;
+        let strategy = marinade_strategy::StrategyAccount::try_deserialize(&mut &strategy_data[..])?;
+        drop(strategy_data);
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        // The strategy CPI and the forward below take the vault account
+        drop(vault);
+        let signer_seeds = &[&vault_seeds[..]];
+        let cpi_accounts = marinade_strategy::cpi::accounts::Unstake {
+            strategy_account: ctx.accounts.strategy_account.to_account_info(),
+            vault: ctx.accounts.vault.to_account_info(),
+            sol_receiver: ctx.accounts.sol_buffer.to_account_info(),
+            marinade_state: ctx.accounts.marinade_state.to_account_info(),
+            msol_mint: ctx.accounts.msol_mint.to_account_info(),
+            liq_pool_msol_leg: ctx.accounts.liq_pool_msol_leg.to_account_info(),
+            liq_pool_sol_leg_pda: ctx.accounts.liq_pool_sol_leg_pda.to_account_info(),
+            msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
+            treasury_msol_account: ctx.accounts.treasury_msol_account.to_account_info(),
+            marinade_program: ctx.accounts.marinade_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marinade_strategy_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        let buffer_before = ctx.accounts.sol_buffer.lamports();
+        marinade_strategy::cpi::unstake(cpi_ctx, msol_amount)?;
+        let lamports_received = ctx.accounts.sol_buffer.lamports().saturating_sub(buffer_before);
+        require!(lamports_received > 0, VaultError::MarinadeError);
+
+        // Forward everything in the buffer (it never keeps a balance) to the vault PDA
+        let buffer_seeds = &[b"sol_buffer".as_ref(), vault_key.as_ref(), &[ctx.bumps.sol_buffer]];
+        let buffer_signer = &[&buffer_seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.sol_buffer.to_account_info(),
+            to: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            cpi_accounts,
+            buffer_signer,
+        );
+        transfer(cpi_ctx, ctx.accounts.sol_buffer.lamports())?;
+
+        let initial_staked =
+            math::pro_rata(strategy.total_staked, msol_amount, strategy.msol_balance.max(msol_amount))?;
+        let yield_lamports = lamports_received.saturating_sub(initial_staked);
+        msg!("🌊 Divested {} mSOL into {} lamports of idle SOL", msol_amount, lamports_received);
+
+        // The SOL is idle now, so it leaves the cached strategy value
+        if let Some(strategy_value) = ctx.accounts.strategy_value.as_mut() {
+            strategy_value.value_lamports = strategy_value.value_lamports.saturating_sub(lamports_received);
+        }
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.end_operation();
+        emit_cpi!(StrategyUnstakedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            strategy: strategy_key,
+            msol_amount,
+            lamports_received,
+            yield_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Sync the vault's StrategyValue from its strategy's report_value
+    ///
+    /// Permissionless crank: anyone may pay to create or refresh the account.
+    /// Deposits and withdrawals of a vault with a strategy value it from this
+    /// account, so it must have been synced within its max age (see
+    /// set_strategy_value_max_age); staking yield enters TVL here.
+    pub fn sync_strategy_value(ctx: Context<SyncStrategyValue>, _name: String) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let strategy_key = ctx.accounts.strategy_account.key();
+        require!(
+            ctx.accounts.vault.load()?.marinade_strategy() == Some(strategy_key),
+            VaultError::MarinadeError
+        );
+        marinade::verify(
+            ctx.accounts.marinade_state.owner,
+            &[
+                (MarinadeAccount::State, ctx.accounts.marinade_state.key()),
+                (MarinadeAccount::MsolMint, ctx.accounts.msol_mint.key()),
+            ],
+        )?;
+
+        let cpi_accounts = marinade_strategy::cpi::accounts::ReportValue {
+            strategy_account: ctx.accounts.strategy_account.to_account_info(),
+            vault: ctx.accounts.vault.to_account_info(),
+            marinade_state: ctx.accounts.marinade_state.to_account_info(),
+            msol_ata: ctx.accounts.strategy_msol_ata.to_account_info(),
+            msol_mint: ctx.accounts.msol_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.marinade_strategy_program.to_account_info(), cpi_accounts);
+        let value_lamports = marinade_strategy::cpi::report_value(cpi_ctx)?.get();
+
+        let now = Clock::get()?.unix_timestamp;
+        let strategy_value = &mut ctx.accounts.strategy_value;
+        strategy_value.vault = vault_key;
+        strategy_value.strategy = strategy_key;
+        strategy_value.value_lamports = value_lamports;
+        strategy_value.updated_at = now;
+        strategy_value.bump = ctx.bumps.strategy_value;
+        msg!("🌊 Strategy value synced: {} lamports", value_lamports);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        emit_cpi!(StrategyValueSyncedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            strategy: strategy_key,
+            value_lamports,
+            updated_at: now,
+        });
+
+        Ok(())
+    }
+
+    /// Set how old a strategy value sync deposits and withdrawals accept
+    /// (0 = DEFAULT_MAX_STRATEGY_VALUE_AGE_SECS)
+    pub fn set_strategy_value_max_age(
+        ctx: Context<SetStrategyValueMaxAge>,
+        _name: String,
+        max_age_secs: u32,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let strategy_value = &mut ctx.accounts.strategy_value;
+        strategy_value.vault = vault_key;
+        strategy_value.max_age_secs = max_age_secs;
+        strategy_value.bump = ctx.bumps.strategy_value;
+        msg!("Strategy value max age set to {}s", strategy_value::max_age_secs(strategy_value));
+
+        Ok(())
+    }
+
+    /// Cap how much of TVL one rebalance may trade, in bps (0 = no cap;
+    /// only callable by vault authority)
+    /// Legs are scaled down together to fit and the remaining drift is left
+    /// to later rebalances, so a bad price print cannot churn the whole basket
+    pub fn set_max_turnover(
+        ctx: Context<SetRebalanceConfig>,
+        _name: String,
+        max_turnover_bps_per_rebalance: u16,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(max_turnover_bps_per_rebalance <= 10_000, VaultError::InvalidTurnoverCap);
+
+        let config = &mut ctx.accounts.rebalance_config;
+        config.vault = vault_key;
+        config.max_turnover_bps_per_rebalance = max_turnover_bps_per_rebalance;
+        config.bump = ctx.bumps.rebalance_config;
+        msg!("Rebalance turnover capped at {} bps of TVL", max_turnover_bps_per_rebalance);
+
+        Ok(())
+    }
+
+    /// Set the smallest leg a rebalance swaps, in micro-USD (0 = the $1
+    /// default), and per-asset dust thresholds in base units, in
+    /// vault.assets order (missing entries are 0). Smaller legs are skipped
+    /// and counted in RebalanceCompletedEvent.
+    pub fn set_dust_thresholds(
+        ctx: Context<SetRebalanceConfig>,
+        _name: String,
+        min_swap_usd: u64,
+        dust_thresholds: Vec<u64>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(
+            dust_thresholds.len() <= vault.assets().len(),
+            VaultError::InvalidDustThresholds
+        );
+
+        let config = &mut ctx.accounts.rebalance_config;
+        config.vault = vault_key;
+        config.min_swap_usd = min_swap_usd;
+        config.dust_thresholds = [0; MAX_ASSETS];
+        config.dust_thresholds[..dust_thresholds.len()].copy_from_slice(&dust_thresholds);
+        config.bump = ctx.bumps.rebalance_config;
+        msg!("Rebalance dust: minimum swap {} micro-USD, thresholds {:?}", min_swap_usd, dust_thresholds);
+
+        Ok(())
+    }
+
+    /// Quote the vault in SOL or USDC (see QuoteCurrency)
+    ///
+    /// `quote_mint` must be a 6-decimal mint for USDC (the canonical USDC
+    /// mint on mainnet builds) and is ignored for SOL.
+    pub fn set_quote_currency(ctx: Context<SetQuoteCurrency>, _name: String, currency: QuoteCurrency) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+
+        let quote_mint = match currency {
+            QuoteCurrency::Sol => Pubkey::default(),
+            QuoteCurrency::Usdc => {
+                let mint = &ctx.accounts.quote_mint;
+                require!(mint.decimals == USDC_DECIMALS, VaultError::InvalidMint);
+                #[cfg(feature = "mainnet")]
+                require_keys_eq!(mint.key(), orca::USDC_MINT, VaultError::InvalidMint);
+                mint.key()
+            }
+        };
+
+        let config = &mut ctx.accounts.quote_config;
+        config.vault = vault_key;
+        config.currency = currency;
+        config.quote_mint = quote_mint;
+        config.bump = ctx.bumps.quote_config;
+        msg!("Vault quoted in {:?} (mint {})", currency, quote_mint);
+
+        Ok(())
+    }
+
+    /// Denominate the vault's NAV in another currency, e.g. EUR or TRY
+    ///
+    /// `fx_feed` is a Switchboard feed pricing one unit of `currency` in USD,
+    /// read through the oracle adapter program passed in remaining_accounts;
+    /// it must read now. get_vault_stats then reports TVL and share price in
+    /// micro-units of `currency` as well as USD. Share minting and burning
+    /// stay in USD, which converts both sides of every ratio by the same rate.
+    /// "USD" clears the denomination and ignores the feed account.
+    pub fn set_nav_currency<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetNavCurrency<'info>>,
+        _name: String,
+        currency: [u8; 3],
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(currency.iter().all(u8::is_ascii_uppercase), VaultError::InvalidNavCurrency);
+
+        let fx_feed = if currency == USD_CURRENCY {
+            Pubkey::default()
+        } else {
+            let feed = &ctx.accounts.fx_feed;
+            let fx = prices::verify_oracle_quote(ctx.remaining_accounts, feed)?;
+            msg!("{} at {} micro-USD", String::from_utf8_lossy(&currency), fx.price_usd);
+            feed.key()
+        };
+
+        let config = &mut ctx.accounts.quote_config;
+        config.vault = vault_key;
+        config.nav_currency = currency;
+        config.fx_feed = fx_feed;
+        config.bump = ctx.bumps.quote_config;
+        msg!("Vault NAV denominated in {} (feed {})", String::from_utf8_lossy(&currency), fx_feed);
+
+        Ok(())
+    }
+
+    /// Rebalance vault when asset drifts exceed threshold
+    /// 
+    /// This function detects when asset allocations drift from target weights
+    /// and executes swaps to restore the target portfolio composition.
+    /// 
+    /// **Process:**
+    /// 1. Authorization check (only admin)
+    /// 2. Fetch current prices from the vault's price source
+    /// 3. Calculate current USD values for each asset
+    /// 4. Detect drifts > threshold (5%)
+    /// 5. Execute MockSwap operations to rebalance
+    /// 
+    /// **remaining_accounts layout:**
+    /// - [0]: MockOracle account (only if price_source = MockOracle)
+    /// - [..n]: Vault's ATAs for each asset (mut)
+    pub fn rebalance<'info>(
+        ctx: Context<'_, '_, '_, 'info, Rebalance<'info>>,
+        _vault_name: String,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        
+        // STEP 1: Authorization check
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+        // Hidden targets are only known to the confidential rebalance
+        require!(!vault.weights_hidden(), VaultError::WeightsHidden);
+        require!(!vault.rebalancing_paused(), VaultError::RebalancingPaused);
+        vault.begin_operation(VaultOperation::Rebalance)?;
+
+        msg!("🔄 Starting rebalancing for vault: {}", vault.name());
+
+        // MockOracle/feed accounts (and an LST index's basket) are passed
+        // ahead of the ATAs; Switchboard quotes are named accounts
+        let ata_offset = rebalance_ata_offset(&vault);
+        let swap_accounts = swap_engine::backend_accounts(
+            vault.swap_backend(),
+            ctx.remaining_accounts,
+            vault.assets().len() + ata_offset,
+        )?;
+
+        // STEP 2-3: Fetch prices (2 min max staleness for mock prices) and
+        // value each asset
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+        let RebalanceHoldings { prices, current_usds, total_usd } = rebalance_holdings(
+            &vault,
+            vault_key,
+            [&ctx.accounts.btc_quote, &ctx.accounts.eth_quote, &ctx.accounts.sol_quote],
+            ctx.remaining_accounts,
+            &clock,
+        )?;
+        
+        let current_weights_bps = calculate_weights_bps(&current_usds, total_usd);
+
+        emit_cpi!(RebalanceStartedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            authority: ctx.accounts.authority.key(),
+            tvl_usd: total_usd,
+            current_weights_bps: current_weights_bps.clone(),
+            timestamp: current_time,
+        });
+
+        if total_usd == 0 {
+            msg!("⚠️  Empty vault - no rebalancing needed");
+            emit_cpi!(RebalanceCompletedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                swap_count: 0,
+                tvl_usd: total_usd,
+                new_weights_bps: current_weights_bps,
+                dust_legs_skipped: 0,
+                dust_usd_skipped: 0,
+                timestamp: current_time,
+            });
+            vault.end_operation();
+            return Ok(());
+        }
+
+        msg!("💰 Total TVL: ${}", total_usd / 1_000_000);
+        
+        // STEP 4: Check for drifts > threshold (5%)
+        let weights: Vec<u8> = vault.assets().iter().map(|asset| asset.weight).collect();
+        let drifts = rebalance_plan::drifts(&weights, &current_usds, total_usd);
+        for drift in &drifts {
+            msg!("   Asset {} (weight={}%): drift={}%",
+                drift.index, weights[drift.index], drift.drift_pct);
+        }
+        
+        if !rebalance_plan::needs_rebalance(&drifts) {
+            msg!("✅ No rebalancing needed - all assets within threshold");
+            emit_cpi!(RebalanceCompletedEvent {
+                vault: vault_key,
+                seq: vault.next_event_seq(),
+                swap_count: 0,
+                tvl_usd: total_usd,
+                new_weights_bps: current_weights_bps,
+                dust_legs_skipped: 0,
+                dust_usd_skipped: 0,
+                timestamp: current_time,
+            });
+            vault.end_operation();
+            return Ok(());
+        }
+        
+        msg!("🔨 Rebalancing required!");
+
+        // STEP 5: Execute swaps through the vault's swap backend
+        let (legs, dust) = plan_rebalance_legs(&vault, ctx.accounts.rebalance_config.as_ref(), &prices, &drifts, total_usd)?;
+        // The swaps credit the vault account
+        drop(vault);
+        let (swap_events, post_swap_usds) =
+            execute_rebalance_swaps(&ctx.accounts.vault, swap_accounts, &prices, &legs, &current_usds)?;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let swap_count = swap_events.len() as u8;
+        for event in swap_events {
+            emit_cpi!(event);
+        }
+        
+        msg!("✅ Rebalancing complete!");
+
+        emit_cpi!(RebalanceCompletedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            swap_count,
+            tvl_usd: total_usd,
+            new_weights_bps: calculate_weights_bps(&post_swap_usds, total_usd),
+            dust_legs_skipped: dust.len() as u8,
+            dust_usd_skipped: dust.iter().map(|leg| leg.swap_usd).sum(),
+            timestamp: current_time,
+        });
+        vault.end_operation();
+        
+        Ok(())
+    }
+
+    /// Select the DEX backend for the vault's swaps (only callable by vault authority)
+    pub fn set_swap_backend(
+        ctx: Context<SetSwapBackend>,
+        _name: String,
+        backend: SwapBackend,
+    ) -> Result<()> {
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        vault.require_governed_authority(&ctx.accounts.authority.key())?;
+        require!(backend != SwapBackend::Jupiter, VaultError::SwapBackendUnavailable);
+        require!(
+            backend != SwapBackend::Sanctum || vault.wrapped_sol_ata().is_none(),
+            VaultError::WrappedSolUnsupported
+        );
+
+        vault.set_swap_backend(backend);
+
+        msg!("Swap backend set to: {:?}", backend);
+
+        Ok(())
+    }
+
+    /// Swap vault tokens directly through an Orca Whirlpool (only callable by vault authority)
+    ///
+    /// Needs only the pool's fixed accounts, for when an aggregator route is too
+    /// account-heavy for one transaction. Requires swap_backend = Orca and a pool
+    /// trading one of the supported pairs (SOL/USDC, SOL/wBTC).
+    /// `a_to_b` sells the pool's token A for token B.
+    pub fn swap_via_orca(
+        ctx: Context<SwapViaOrca>,
+        _name: String,
+        amount_in: u64,
+        min_amount_out: u64,
+        a_to_b: bool,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let mut vault = ctx.accounts.vault.load_mut()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+        vault.begin_operation(VaultOperation::Rebalance)?;
+        require!(vault.swap_backend() == SwapBackend::Orca, VaultError::SwapBackendDisabled);
+        require!(!vault.rebalancing_paused(), VaultError::RebalancingPaused);
+        require!(amount_in > 0, VaultError::InvalidAmount);
+
+        let pool = orca::WhirlpoolInfo::load(&ctx.accounts.whirlpool)?;
+        let (from_mint, to_mint) = if a_to_b {
+            (pool.token_mint_a, pool.token_mint_b)
+        } else {
+            (pool.token_mint_b, pool.token_mint_a)
+        };
+
+        msg!("🐋 Swapping {} of {} via Orca (min out {})", amount_in, from_mint, min_amount_out);
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        let signer_seeds = &[&vault_seeds[..]];
+
+        // Same account group the swap engine takes per Orca swap
+        let swap_accounts = [
+            ctx.accounts.whirlpool_program.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.whirlpool.to_account_info(),
+            ctx.accounts.vault_token_a.to_account_info(),
+            ctx.accounts.token_vault_a.to_account_info(),
+            ctx.accounts.vault_token_b.to_account_info(),
+            ctx.accounts.token_vault_b.to_account_info(),
+            ctx.accounts.tick_array_0.to_account_info(),
+            ctx.accounts.tick_array_1.to_account_info(),
+            ctx.accounts.tick_array_2.to_account_info(),
+            ctx.accounts.oracle.to_account_info(),
+        ];
+        let mut swaps = SwapEngine::new(
+            &vault,
+            ctx.accounts.vault.to_account_info(),
+            signer_seeds,
+            &swap_accounts,
+        );
+        // The vault signs the swap below
+        drop(vault);
+        let amount_out = swaps.execute_swap(
+            &SwapAsset::unpriced(from_mint),
+            &SwapAsset::unpriced(to_mint),
+            amount_in,
+            min_amount_out,
+        )?;
+
+        msg!("   Received {} of {}", amount_out, to_mint);
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.end_operation();
+        emit_cpi!(SwapExecutedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            from_mint,
+            to_mint,
+            amount_in,
+            amount_out,
+        });
+
+        Ok(())
+    }
+
+    /// Hide the vault's target weights (vault admin)
+    ///
+    /// Queues seal_target_weights on the rebalancing MXE with the BTC, ETH
+    /// and SOL weights encrypted under `pub_key` and computation_nonce(vault,
+    /// computation_offset). The cluster re-encrypts them to the MXE key and
+    /// its callback stores them in the vault's SealedTargets, together with
+    /// whether they sum to 100; the vault PDA signs the request, so no one
+    /// else can replace them. The plaintext weights are zeroed and the vault
+    /// flagged: from then on it rebalances only through
+    /// rebalance_confidential, rebalance and every weight change are
+    /// rejected, and deposits go to the SOL slot until the next confidential
+    /// rebalance spreads them. rebalance_confidential refuses to run until a
+    /// seal has completed with weights summing to 100.
+    ///
+    /// Holdings are public once trades land, so hiding only keeps the
+    /// targets out of view ahead of the trades; each rebalance reveals its
+    /// trades, capped at HIDDEN_MAX_TRADE_BPS of TVL per asset. Call again
+    /// to move the hidden targets.
+    pub fn set_hidden_weights(
+        ctx: Context<SetHiddenWeights>,
+        _name: String,
+        computation_offset: u64,
+        pub_key: [u8; 32],
+        encrypted_weights: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+
+        require!(ctx.accounts.authority.key() == vault.admin, VaultError::Unauthorized);
+        // Same vaults rebalance_confidential serves: a mock-oracle BTC/ETH/SOL basket
+        require!(
+            vault.price_source() == PriceSource::MockOracle && vault.mock_oracle().is_some(),
+            VaultError::InvalidPrice
+        );
+        require!(vault.assets().len() == ORACLE_MARKETS, VaultError::InvalidAssetCount);
+        require!(!vault.market_cap_weighted(), VaultError::MarketCapWeighted);
+        require!(vault.pending_computation_offset().is_none(), VaultError::VaultBusy);
+        let ciphertexts: [[u8; CIPHERTEXT_LEN]; ORACLE_MARKETS] = encrypted_weights
+            .try_into()
+            .map_err(|_| VaultError::InvalidEncryptedInput)?;
+
+        let nonce = mxe::computation_nonce(&vault_key, computation_offset);
+        let instruction_data = build_seal_target_weights_data(computation_offset, &pub_key, nonce, &ciphertexts);
+
+        use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+        use anchor_lang::solana_program::program::invoke_signed;
+
+        let ix = Instruction {
+            program_id: ctx.accounts.arcium_mxe_program.key(),
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.authority.key(), true),             // payer (signer, mut)
+                AccountMeta::new_readonly(vault_key, true),                       // vault (PDA signer)
+                AccountMeta::new(ctx.accounts.sign_pda_account.key(), false),     // sign_pda_account (mut)
+                AccountMeta::new_readonly(ctx.accounts.mxe_account.key(), false), // mxe_account
+                AccountMeta::new(ctx.accounts.mempool_account.key(), false),      // mempool_account (mut)
+                AccountMeta::new(ctx.accounts.executing_pool.key(), false),       // executing_pool (mut)
+                AccountMeta::new(ctx.accounts.computation_account.key(), false),  // computation_account (mut)
+                AccountMeta::new_readonly(ctx.accounts.comp_def_account.key(), false), // comp_def_account
+                AccountMeta::new(ctx.accounts.cluster_account.key(), false),      // cluster_account (mut)
+                AccountMeta::new(ctx.accounts.pool_account.key(), false),         // pool_account (mut)
+                AccountMeta::new_readonly(ctx.accounts.clock_account.key(), false), // clock_account
+                AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // system_program
+                AccountMeta::new_readonly(ctx.accounts.arcium_program.key(), false), // arcium_program
+                AccountMeta::new(ctx.accounts.sealed_targets.key(), false),       // sealed_targets (mut)
+            ],
+            data: instruction_data,
+        };
+
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        // The vault account signs the CPI
+        drop(vault);
+        invoke_signed(
+            &ix,
+            &[
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.sign_pda_account.to_account_info(),
+                ctx.accounts.mxe_account.to_account_info(),
+                ctx.accounts.mempool_account.to_account_info(),
+                ctx.accounts.executing_pool.to_account_info(),
+                ctx.accounts.computation_account.to_account_info(),
+                ctx.accounts.comp_def_account.to_account_info(),
+                ctx.accounts.cluster_account.to_account_info(),
+                ctx.accounts.pool_account.to_account_info(),
+                ctx.accounts.clock_account.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.arcium_program.to_account_info(),
+                ctx.accounts.sealed_targets.to_account_info(),
+            ],
+            &[&vault_seeds[..]],
+        )?;
+
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        for asset in vault.assets_mut().iter_mut() {
+            asset.weight = 0;
+        }
+        vault.set_weights_hidden(true);
+
+        msg!("🔐 Target weights sealed for vault: {} (computation {})", vault.name(), computation_offset);
+
+        emit_cpi!(CompositionChangedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            mints: vault.assets().iter().map(|a| a.mint).collect(),
+            weights: vec![0; ORACLE_MARKETS],
+        });
+
+        Ok(())
+    }
+
+    /// Rebalance vault using Arcium MXE for confidential computation
+    /// 
+    /// This instruction prevents MEV attacks by encrypting the rebalancing
+    /// computation inputs and outputs using Arcium's Multi-Party Computation.
+    /// 
+    /// **Flow:**
+    /// 1. Client: Fetch vault state and prices, prepare encrypted portfolio
+    /// 2. On-chain: Call Arcium MXE to queue encrypted rebalancing computation
+    /// 3. Arcium MXE: Compute rebalancing in encrypted form
+    /// 4. Callback: the MXE stores the revealed drifts in its RebalanceResult
+    /// 5. apply_confidential_rebalance: execute the swaps
+    /// 
+    /// **encrypted_portfolio:** one ciphertext per RebalancingInput field, in
+    /// circuit order (see REBALANCING_INPUT_FIELDS), all encrypted under the
+    /// pub_key shared secret and mxe::computation_nonce(vault,
+    /// computation_offset), so every computation gets a fresh nonce.
+    ///
+    /// A hidden-weights vault passes an empty portfolio instead: it queues
+    /// compute_hidden_rebalancing, signed by the vault PDA, with its
+    /// SealedTargets and the USD values priced here from the oracle and its
+    /// ATAs. The result carries the trades toward the sealed targets, each
+    /// capped at HIDDEN_MAX_TRADE_BPS of TVL.
+    ///
+    /// **remaining_accounts layout (hidden-weights vaults):**
+    /// - [0]: MockOracle account
+    /// - [1..n]: Vault's ATAs for each asset
+    pub fn rebalance_confidential(
+        ctx: Context<RebalanceConfidential>,
+        _vault_name: String,
+        computation_offset: u64,
+        pub_key: [u8; 32],
+        encrypted_portfolio: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+        
+        // STEP 1: Authorization check
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+
+        require!(!vault.rebalancing_paused(), VaultError::RebalancingPaused);
+
+        msg!("🔐 Starting confidential rebalancing for vault: {}", vault.name());
+
+        // Verify we're using MockOracle
+        require!(
+            vault.price_source() == PriceSource::MockOracle,
+            VaultError::InvalidPrice
+        );
+        require!(
+            vault.mock_oracle().is_some(),
+            VaultError::InvalidPrice
+        );
+
+        msg!("📡 Queuing encrypted computation to Arcium MXE...");
+
+        use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+        use anchor_lang::solana_program::program::invoke_signed;
+
+        // STEP 2: Build instruction data and account metas for the CPI
+        let mut account_metas = vec![
+            AccountMeta::new(ctx.accounts.authority.key(), true),             // payer (signer, mut)
+        ];
+        let mut account_infos = vec![ctx.accounts.authority.to_account_info()];
+        let instruction_data = if vault.weights_hidden() {
+            require!(encrypted_portfolio.is_empty(), VaultError::InvalidEncryptedInput);
+            let sealed_targets = ctx
+                .accounts
+                .sealed_targets
+                .as_ref()
+                .ok_or(VaultError::TargetsNotSealed)?;
+            let sealed_by = mxe::require_sealed_targets(sealed_targets, &vault_key)?;
+            msg!("   Sealed targets from computation {}", sealed_by);
+
+            let (_, current_usds, total_usd) = confidential_holdings(&vault, ctx.remaining_accounts)?;
+            let max_trade_usd = rebalance_plan::max_turnover_usd(total_usd, HIDDEN_MAX_TRADE_BPS)?
+                .ok_or(VaultError::MathOverflow)?;
+            let usd_values = current_usds
+                .iter()
+                .map(|usd| math::to_u64(math::to_u128(*usd)?))
+                .collect::<Result<Vec<u64>>>()?;
+
+            account_metas.push(AccountMeta::new_readonly(vault_key, true)); // vault (PDA signer)
+            account_infos.push(ctx.accounts.vault.to_account_info());
+            build_compute_hidden_rebalancing_data(
+                computation_offset,
+                &usd_values,
+                math::to_u64(math::to_u128(max_trade_usd)?)?,
+            )?
+        } else {
+            // Layout: discriminator + offset + vault + pub_key + nonce + 13 ciphertexts
+            build_compute_rebalancing_data(
+                computation_offset,
+                &vault_key,
+                &pub_key,
+                mxe::computation_nonce(&vault_key, computation_offset),
+                &encrypted_portfolio,
+            )?
+        };
+
+        msg!("   Instruction data size: {} bytes", instruction_data.len());
+        msg!(
+            "   Encrypted portfolio: {} fields x {} bytes",
+            encrypted_portfolio.len(),
+            CIPHERTEXT_LEN
+        );
+
+        account_metas.extend([
+            AccountMeta::new(ctx.accounts.sign_pda_account.key(), false),     // sign_pda_account (mut)
+            AccountMeta::new_readonly(ctx.accounts.mxe_account.key(), false), // mxe_account
+            AccountMeta::new(ctx.accounts.mempool_account.key(), false),      // mempool_account (mut)
+            AccountMeta::new(ctx.accounts.executing_pool.key(), false),       // executing_pool (mut)
+            AccountMeta::new(ctx.accounts.computation_account.key(), false),  // computation_account (mut)
+            AccountMeta::new_readonly(ctx.accounts.comp_def_account.key(), false), // comp_def_account
+            AccountMeta::new(ctx.accounts.cluster_account.key(), false),      // cluster_account (mut)
+            AccountMeta::new(ctx.accounts.pool_account.key(), false),         // pool_account (mut)
+            AccountMeta::new_readonly(ctx.accounts.clock_account.key(), false), // clock_account
+            AccountMeta::new_readonly(ctx.accounts.system_program.key(), false), // system_program
+            AccountMeta::new_readonly(ctx.accounts.arcium_program.key(), false), // arcium_program
+        ]);
+        account_infos.extend([
+            ctx.accounts.sign_pda_account.to_account_info(),
+            ctx.accounts.mxe_account.to_account_info(),
+            ctx.accounts.mempool_account.to_account_info(),
+            ctx.accounts.executing_pool.to_account_info(),
+            ctx.accounts.computation_account.to_account_info(),
+            ctx.accounts.comp_def_account.to_account_info(),
+            ctx.accounts.cluster_account.to_account_info(),
+            ctx.accounts.pool_account.to_account_info(),
+            ctx.accounts.clock_account.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            ctx.accounts.arcium_program.to_account_info(),
+        ]);
+        if let Some(sealed_targets) = ctx.accounts.sealed_targets.as_ref().filter(|_| vault.weights_hidden()) {
+            account_metas.push(AccountMeta::new_readonly(sealed_targets.key(), false)); // sealed_targets
+            account_infos.push(sealed_targets.to_account_info());
+        }
+        account_metas.push(AccountMeta::new(ctx.accounts.rebalance_result.key(), false)); // rebalance_result (mut)
+        account_infos.push(ctx.accounts.rebalance_result.to_account_info());
+
+        let ix = Instruction {
+            program_id: ctx.accounts.arcium_mxe_program.key(),
+            accounts: account_metas,
+            data: instruction_data,
+        };
+
+        // STEP 3: Invoke the Arcium MXE program; a hidden-weights vault signs
+        let signer = vault.signer();
+        let vault_seeds = signer.seeds();
+        drop(vault);
+        invoke_signed(&ix, &account_infos, &[&vault_seeds[..]])?;
+
+        // Only the result of this computation may be applied later, and the
+        // vault stays locked for the rebalance until it is
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        vault.begin_operation(VaultOperation::Rebalance)?;
+        vault.set_pending_computation_offset(Some(computation_offset));
+
+        msg!("✅ Encrypted computation queued successfully!");
+        msg!("   Computation offset: {}", computation_offset);
+        msg!("   Portfolio data: [ENCRYPTED - {} fields]", REBALANCING_INPUT_FIELDS);
+        msg!("   MEV protection: ACTIVE");
+        msg!("   Awaiting MXE callback with encrypted results...");
+        
+        Ok(())
+    }
+
+    /// Execute the swaps of a completed confidential rebalance computation
+    ///
+    /// Closes the Arcium loop: the drifts are read from the RebalanceResult
+    /// the MXE callback stored for the pending computation (see mxe), never
+    /// from the caller. They are re-derived from on-chain balances and oracle
+    /// prices and must match within one percentage point, which catches a
+    /// computation fed stale or wrong inputs, before the corresponding swaps
+    /// are executed. A hidden-weights vault priced the computation's inputs
+    /// itself, so there is nothing to re-derive: it executes the capped
+    /// trades the result carries (see rebalance_plan::drifts_from_trades).
+    ///
+    /// **remaining_accounts layout:**
+    /// - [0]: MockOracle account
+    /// - [1..n]: Vault's ATAs for each asset (mut)
+    pub fn apply_confidential_rebalance<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApplyConfidentialRebalance<'info>>,
+        _vault_name: String,
+        computation_offset: u64,
+    ) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let vault = ctx.accounts.vault.load()?;
+
+        require!(
+            ctx.accounts.authority.key() == vault.admin,
+            VaultError::Unauthorized
+        );
+        require!(!vault.rebalancing_paused(), VaultError::RebalancingPaused);
+        require!(
+            vault.pending_computation_offset() == Some(computation_offset),
+            VaultError::InvalidConfidentialResult
+        );
+        // Still holding the lock rebalance_confidential took
+        require!(vault.operation() == VaultOperation::Rebalance, VaultError::VaultBusy);
+        let result = mxe::completed_result(&ctx.accounts.rebalance_result, &vault_key, computation_offset)?;
+        require!(
+            vault.assets().len() == result.drifts_pct.len(),
+            VaultError::InvalidConfidentialResult
+        );
+        let swap_accounts = swap_engine::backend_accounts(
+            vault.swap_backend(),
+            ctx.remaining_accounts,
+            vault.assets().len() + 1,
+        )?;
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let (prices, current_usds, total_usd) = confidential_holdings(&vault, ctx.remaining_accounts)?;
+
+        let drifts = if vault.weights_hidden() {
+            rebalance_plan::drifts_from_trades(&result.trades_usd)
+        } else {
+            // Verify the decrypted drifts against the plaintext state
+            let weights: Vec<u8> = vault.assets().iter().map(|asset| asset.weight).collect();
+            let drifts = rebalance_plan::drifts(&weights, &current_usds, total_usd);
+            for (drift, decrypted) in drifts.iter().zip(&result.drifts_pct) {
+                require!(
+                    (drift.drift_pct - *decrypted as i64).abs() <= 1,
+                    VaultError::InvalidConfidentialResult
+                );
+            }
+            drifts
+        };
+
+        let (legs, dust) = plan_rebalance_legs(&vault, ctx.accounts.rebalance_config.as_ref(), &prices, &drifts, total_usd)?;
+        // The swaps credit the vault account
+        drop(vault);
+        let (swap_events, post_swap_usds) =
+            execute_rebalance_swaps(&ctx.accounts.vault, swap_accounts, &prices, &legs, &current_usds)?;
+        let mut vault = ctx.accounts.vault.load_mut()?;
+        let swap_count = swap_events.len() as u8;
+        for event in swap_events {
+            emit_cpi!(event);
+        }
+
+        msg!(
+            "🔐 Applied confidential rebalance {}: {} swaps",
+            computation_offset,
+            swap_count
+        );
+
+        emit_cpi!(RebalanceCompletedEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            swap_count,
+            tvl_usd: total_usd,
+            new_weights_bps: calculate_weights_bps(&post_swap_usds, total_usd),
+            dust_legs_skipped: dust.len() as u8,
+            dust_usd_skipped: dust.iter().map(|leg| leg.swap_usd).sum(),
+            timestamp: current_time,
+        });
+
+        vault.set_pending_computation_offset(None);
+        vault.end_operation();
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Deposit Return Data
+// ============================================================================
+
+/// Slice of a deposit assigned to one basket asset
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AssetAllocation {
+    pub mint: Pubkey,
+    /// USD value allocated to this asset (micro-dollars)
+    pub usd_value: i64,
+    /// Token amount the allocation buys, in the asset's native decimals
+    pub token_amount: u64,
+}
+
+/// Result of get_vault_stats, written to return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct VaultStats {
+    /// TVL in USD micro-dollars
+    pub tvl_usd: i64,
+    pub total_shares: u64,
+    /// Share price in micro-dollars
+    pub share_price: i64,
+    /// BTC/ETH/SOL prices used, in micro-dollars
+    pub prices: [i64; 3],
+    /// Soft-closed, restricted, or would be restricted at these prices
+    pub deposits_paused: bool,
+    pub share_price_change_7d_bps: i32,
+    pub share_price_change_30d_bps: i32,
+    /// Vault's quote currency (Sol unless its QuoteConfig was passed)
+    pub quote_currency: QuoteCurrency,
+    /// TVL and share price in the quote currency: lamports, or USDC base units
+    pub tvl_quote: i64,
+    pub share_price_quote: i64,
+    /// Vault's NAV denomination (QuoteConfig::nav_currency, "USD" by default)
+    pub nav_currency: [u8; 3],
+    /// TVL and share price in micro-units of nav_currency
+    pub tvl_nav: i64,
+    pub share_price_nav: i64,
+}
+
+/// Result of preview_deposit, written to return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DepositPreview {
+    pub shares_out: u64,
+    /// Lamports of the deposit that would be kept as fee
+    pub deposit_fee: u64,
+    /// Value of the deposit net of the fee, in USD micro-dollars
+    pub deposit_usd: i64,
+    pub share_price: i64,
+    pub tvl_usd: i64,
+}
+
+/// Result of preview_withdraw, written to return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct WithdrawPreview {
+    /// Lamports paid out, net of the exit fee
+    pub sol_out: u64,
+    pub exit_fee: u64,
+    pub exit_fee_bps: u16,
+    /// Value of the redeemed basket in USD micro-dollars
+    pub withdraw_usd: i64,
+    pub share_price: i64,
+    /// Priced at conservative prices because the vault is (or would be) restricted
+    pub restricted: bool,
+}
+
+/// Swap leg planned by simulate_rebalance
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PlannedSwap {
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    /// Value moved, in USD micro-dollars
+    pub swap_usd: i64,
+    pub amount_in: u64,
+    /// Oracle quote for amount_in
+    pub expected_out: u64,
+    /// Least the swap may return (quote less max_slippage_bps)
+    pub min_out: u64,
+}
+
+/// Result of simulate_rebalance, written to return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RebalancePreview {
+    pub tvl_usd: i64,
+    /// Current weight of each asset, in bps of TVL
+    pub current_weights_bps: Vec<u16>,
+    /// Current weight less target weight per asset, in percentage points
+    pub drifts_pct: Vec<i64>,
+    /// Some asset drifted past the threshold; legs is empty otherwise
+    pub needs_rebalance: bool,
+    /// Swaps in the order rebalance would execute them
+    pub legs: Vec<PlannedSwap>,
+    /// Legs rebalance would skip as dust and their combined value in micro-USD
+    pub dust_legs_skipped: u8,
+    pub dust_usd_skipped: i64,
+}
+
+/// Result of deposit_multi_asset, written to return data
+/// SDKs decode this from a simulated transaction to preview a deposit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct DepositReceipt {
+    pub shares_minted: u64,
+    /// Lamports of the deposit kept as fee (not converted into shares);
+    /// deposit_single_asset and deposit_msol keep it in the deposited
+    /// token's base units
+    pub deposit_fee: u64,
+    /// Deposit value in USD micro-dollars
+    pub deposit_usd: i64,
+    /// Share price used for minting (micro-dollars)
+    pub share_price: i64,
+    /// Lamports delegated to the Marinade strategy
+    pub staked_lamports: u64,
+    pub allocations: Vec<AssetAllocation>,
+}
+
+// ============================================================================
+// Arcium MXE Data Structures
+// ============================================================================
+
+/// Encrypted swap instruction from Arcium MXE
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EncryptedSwapInstruction {
+    pub from_asset: u8,
+    pub to_asset: u8,
+    pub amount: u64,        // Encrypted in production
+    pub min_output: u64,    // Encrypted in production
+}
+
+/// Encrypted rebalancing result from Arcium MXE
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct RebalancingResultEncrypted {
+    pub swap_count: u8,
+    pub encrypted_swaps: Vec<EncryptedSwapInstruction>,
+    pub total_tvl_encrypted: Vec<u8>,  // Encrypted TVL
+    pub drifts_encrypted: Vec<u8>,     // Encrypted drift values
+}
+
+/// Check a new vault's composition: 1 to MAX_ASSETS assets with positive
+/// weights summing to exactly 100
+fn validate_composition(assets: &[AssetConfig]) -> Result<()> {
+    require!(
+        !assets.is_empty() && assets.len() <= MAX_ASSETS,
+        VaultError::InvalidAssetCount
+    );
+    let total_weight: u64 = assets.iter().map(|a| a.weight as u64).sum();
+    require!(total_weight == 100, VaultError::InvalidWeights);
+    require!(
+        assets.iter().all(|a| a.weight > 0),
+        VaultError::InvalidWeights
+    );
+
+    // Each mint appears once, so weights and ATAs match one slot each; this
+    // also keeps the native (SOL) mint to a single slot
+    require!(
+        assets
+            .iter()
+            .enumerate()
+            .all(|(i, a)| assets[..i].iter().all(|b| b.mint != a.mint)),
+        VaultError::DuplicateAsset
+    );
+    Ok(())
+}
+
+/// Check feeds for price_source = Aggregated: at most MAX_FEEDS_PER_ASSET per
+/// BTC/ETH/SOL market and a divergence limit of at most 100%
+fn validate_price_feeds(price_feeds: &[PriceFeed], max_divergence_bps: u16) -> Result<()> {
+    require!(price_feeds.len() <= MAX_PRICE_FEEDS, VaultError::InvalidPriceFeeds);
+    require!(max_divergence_bps <= 10_000, VaultError::InvalidPriceFeeds);
+
+    for feed_index in 0..3u8 {
+        let count = price_feeds.iter().filter(|f| f.feed_index == feed_index).count();
+        require!(count <= MAX_FEEDS_PER_ASSET, VaultError::InvalidPriceFeeds);
+    }
+    require!(
+        price_feeds.iter().all(|f| f.feed_index < 3),
+        VaultError::InvalidPriceFeeds
+    );
+    Ok(())
+}
+
+/// Check a template's preset as set_vault_template stores it
+fn validate_preset(preset: &VaultPreset) -> Result<()> {
+    require!(preset.assets.len() <= MAX_TEMPLATE_ASSETS, VaultError::InvalidAssetCount);
+    validate_composition(&preset.asset_configs())?;
+
+    require!(
+        !matches!(preset.price_source, PriceSource::MockOracle | PriceSource::CrossChecked)
+            || preset.mock_oracle.is_some(),
+        VaultError::InvalidPrice
+    );
+    require!(
+        preset.price_source != PriceSource::CrossChecked || preset.max_divergence_bps > 0,
+        VaultError::InvalidPriceFeeds
+    );
+    validate_price_feeds(&preset.price_feeds, preset.max_divergence_bps)?;
+    if preset.price_source == PriceSource::Aggregated {
+        for feed_index in 0..3u8 {
+            require!(
+                preset.price_feeds.iter().any(|f| f.feed_index == feed_index),
+                VaultError::InvalidPrice
+            );
+        }
+    }
+
+    require!(preset.swap_backend != SwapBackend::Jupiter, VaultError::SwapBackendUnavailable);
+    require!(preset.max_slippage_bps <= MAX_SLIPPAGE_BPS, VaultError::InvalidSlippage);
+    Ok(())
+}
+
+/// Protocol whitelist of wrapped BTC/ETH mints, None while it isn't created
+fn load_asset_whitelist(account: &AccountInfo) -> Result<Option<AssetWhitelist>> {
+    if account.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(AssetWhitelist::try_deserialize(&mut &account.try_borrow_data()?[..])?))
+}
+
+/// Defaults of a new vault with an empty composition
+fn init_vault_state(vault: &mut Vault, bump: u8, admin: Pubkey, name: &str, vault_token_mint: Pubkey) -> Result<()> {
+    vault.bump = bump;
+    vault.admin = admin;
+    vault.set_name(name)?;
+    vault.vault_token_mint = vault_token_mint;
+    vault.set_assets(&[])?;
+    vault.set_marinade_strategy(None);
+    // Default to Switchboard for mainnet compatibility
+    vault.set_price_source(PriceSource::Switchboard);
+    vault.set_mock_oracle(None);
+    vault.set_successor(None);
+    vault.set_soft_closed(false);
+    vault.set_pending_computation_offset(None);
+    vault.twap_window_secs = 0;
+    vault.set_price_feeds(&[])?;
+    vault.max_divergence_bps = 0;
+    vault.circuit_breaker_bps = 0;
+    vault.price_checkpoint = [0; 3];
+    vault.set_restricted(false);
+    vault.lockup_secs = 0;
+    vault.exit_fee_bps = 0;
+    vault.exit_fee_window_secs = 0;
+    vault.share_price_change_7d_bps = 0;
+    vault.share_price_change_30d_bps = 0;
+    vault.performance_updated_at = 0;
+    vault.last_tvl_usd = 0;
+    vault.last_share_price = 1_000_000; // $1.00 until the first deposit
+    vault.total_shares = 0;
+    vault.last_nav_update = 0;
+    vault.event_seq = 0;
+    vault.deposit_fee_bps = 0;
+    vault.referral_share_bps = 0;
+    vault.pending_fees = 0;
+    vault.protocol_fees_owed = 0;
+    vault.creator_fees_owed = 0;
+    vault.set_governance(None);
+    vault.set_governance_mode(false);
+    vault.staker_fee_share_bps = 0;
+    vault.staker_fees_owed = 0;
+    vault.set_swap_backend(SwapBackend::Mock);
+    vault.max_slippage_bps = DEFAULT_MAX_SLIPPAGE_BPS;
+    vault.set_mock_pool(MockPoolConfig::DEFAULT);
+    vault.creation_unit_shares = 0;
+    vault.wind_down_deadline = 0;
+    vault.min_deposit_shares = 0;
+    vault.version = VAULT_VERSION;
+    Ok(())
+}
+
+/// Programs open_asset_ata may CPI into
+struct AssetAtaPrograms<'a, 'info> {
+    token_program: &'a AccountInfo<'info>,
+    /// Required when the asset is a Token-2022 mint
+    token_2022_program: Option<&'a AccountInfo<'info>>,
+    associated_token_program: &'a AccountInfo<'info>,
+    system_program: &'a AccountInfo<'info>,
+}
+
+/// Check a basket asset's mint for slot `index` and create the vault's ATA
+/// for it under the mint's token program if it doesn't exist yet; returns
+/// the asset as stored in the composition
+#[allow(clippy::too_many_arguments)]
+fn open_asset_ata<'info>(
+    index: usize,
+    asset_config: &AssetConfig,
+    mint_account: &AccountInfo<'info>,
+    ata_account: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    whitelist: Option<&AssetWhitelist>,
+    programs: &AssetAtaPrograms<'_, 'info>,
+) -> Result<AssetConfig> {
+    // Validate account types and ownership
+    require!(
+        token_io::is_token_program(mint_account.owner),
+        VaultError::InvalidMint
+    );
+    let mint_token_program = token_io::program_for(
+        mint_account,
+        programs.token_program,
+        programs.token_2022_program,
+    )?;
+
+    // Validate mint matches expected mint from AssetConfig
+    require!(
+        mint_account.key() == asset_config.mint,
+        VaultError::InvalidMint
+    );
+    require!(
+        whitelist.is_none_or(|w| w.allows(index, &asset_config.mint)),
+        VaultError::UnapprovedMint
+    );
+
+    // Derive expected ATA address for security (prevent fake ATAs)
+    let expected_ata = anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        &vault.key(),
+        &asset_config.mint,
+        mint_account.owner,
+    );
+    require!(ata_account.key() == expected_ata, VaultError::InvalidATA);
+
+    // Check ATA ownership only if account already exists
+    // Uninitialized accounts are owned by System Program, not ATA Program
+    if !ata_account.data_is_empty() {
+        require!(
+            ata_account.owner == mint_account.owner,
+            VaultError::InvalidATA
+        );
+    }
+
+    // Initialize ATA if it doesn't exist
+    // This is a CPI (Cross-Program Invocation) to the Associated Token Program
+    // We check if the account is empty (uninitialized) and create it if needed
+    if ata_account.data_is_empty() {
+        msg!("Initializing ATA for asset {}", asset_config.mint);
+
+        // Create the ATA using CPI to the Associated Token Program
+        // This is the idiomatic way to create ATAs on Solana
+        let cpi_accounts = anchor_spl::associated_token::Create {
+            payer: payer.clone(),
+            associated_token: ata_account.clone(),
+            authority: vault.clone(),
+            mint: mint_account.clone(),
+            system_program: programs.system_program.clone(),
+            token_program: mint_token_program.clone(),
+        };
+
+        let cpi_program = programs.associated_token_program.clone();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        anchor_spl::associated_token::create(cpi_ctx)?;
+
+        msg!("ATA created successfully: {}", expected_ata);
+    } else {
+        msg!("ATA already exists: {}", expected_ata);
+    }
+
+    msg!(
+        "Asset {}: mint={}, weight={}%, ata={}",
+        index,
+        asset_config.mint,
+        asset_config.weight,
+        expected_ata
+    );
+    Ok(AssetConfig {
+        mint: asset_config.mint,
+        weight: asset_config.weight,
+        ata: expected_ata,
+    })
+}
+
+/// Whether `key` is the mock oracle's authority or one of its feeders
+fn may_update_mock_oracle(oracle: &MockPriceOracle, feeders: Option<&OracleFeeders>, key: &Pubkey) -> bool {
+    *key == oracle.authority || feeders.is_some_and(|feeders| feeders.allows(key))
+}
+
+/// Validate and store BTC/ETH/SOL micro-USD prices observed at `timestamp`
+fn write_mock_prices(oracle: &mut MockPriceOracle, prices: [i64; 3], timestamp: i64) -> Result<()> {
+    let [btc_price, eth_price, sol_price] = prices;
+
+    // Validate prices are reasonable (positive and within bounds)
+    require!(btc_price > 0 && btc_price < 10_000_000_000_000, VaultError::InvalidPrice);
+    require!(eth_price > 0 && eth_price < 10_000_000_000_000, VaultError::InvalidPrice);
+    require!(sol_price > 0 && sol_price < 10_000_000_000_000, VaultError::InvalidPrice);
+
+    oracle.btc_price = btc_price;
+    oracle.eth_price = eth_price;
+    oracle.sol_price = sol_price;
+    oracle.last_update = timestamp;
+    oracle.record_sample();
+
+    msg!("Mock oracle updated - BTC: ${}, ETH: ${}, SOL: ${}", 
+         btc_price / 1_000_000, eth_price / 1_000_000, sol_price / 1_000_000);
+
+    Ok(())
+}
+
+/// A vault from init_vault that finalize_vault hasn't opened yet
+fn require_unfinalized(vault: &Vault) -> Result<()> {
+    require!(!vault.finalized(), VaultError::VaultFinalized);
+    Ok(())
+}
+
+// ============================================================================
+// Helper Functions for Rebalancing
+// ============================================================================
+
+/// Value the vault's holdings in micro-USD from its ATAs and native SOL
+///
+/// `remaining_accounts` starts with the deposit layout: [mint, ATA] pairs in
+/// `vault.assets` order. Native SOL above the vault's rent-exempt minimum counts
+/// as the SOL balance while the SOL ATA is empty.
+/// Prices and NAV shared by the view instructions
+struct ViewNav {
+    prices: [NormalizedPrice; 3],
+    /// The circuit breaker is tripped, or would trip at these prices
+    restricted: bool,
+    tvl_usd: i64,
+    total_shares: u64,
+    share_price: i64,
+}
+
+/// Price and value the vault without writing to it
+///
+/// `remaining_accounts` follow the snapshot_nav layout: [mint, ATA] per
+/// asset, then the price accounts.
+fn view_nav<'info>(
+    vault: &Vault,
+    vault_info: &AccountInfo<'info>,
+    vault_token_mint: &InterfaceAccount<'info, ShareMint>,
+    quotes: [&UncheckedAccount<'info>; 3],
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<ViewNav> {
+    let price_slots = prices::price_account_count(vault, remaining_accounts);
+    require!(
+        remaining_accounts.len() == vault.assets().len() * 2 + price_slots,
+        VaultError::InvalidRemainingAccounts
+    );
+    for (i, asset) in vault.assets().iter().enumerate() {
+        require!(remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+    }
+
+    let clock = Clock::get()?;
+    let [btc_quote, eth_quote, sol_quote] = quotes;
+    let price_accounts = PriceAccounts {
+        vault: vault_info.key(),
+        btc_quote: btc_quote.as_ref(),
+        eth_quote: eth_quote.as_ref(),
+        sol_quote: sol_quote.as_ref(),
+        remaining_accounts,
+    };
+    let feed_prices = prices::fetch_feed_prices_cached(vault, &price_accounts, &clock, 300)?;
+    let tvl_usd = vault_tvl_usd(vault, vault_info, remaining_accounts, &Rent::get()?, &feed_prices)?;
+    let total_shares = vault_token_mint.supply;
+
+    Ok(ViewNav {
+        prices: feed_prices,
+        restricted: prices::would_restrict(vault, &feed_prices),
+        tvl_usd,
+        total_shares,
+        share_price: Vault::calculate_share_price(tvl_usd, total_shares)?,
+    })
+}
+
+/// Split a net SOL deposit across the basket by weight (see
+/// Vault::deposit_weight)
+///
+/// Assets are bought through the swap backend; the SOL slot's share (if the
+/// basket has one) is not swapped and is returned so the caller can stake it
+/// or leave it in the vault.
+/// Value `amount` lamports against the vault and escrow them in a new
+/// DepositTicket (deposit_prepare and reveal_deposit)
+fn prepare_deposit_ticket<'info>(
+    ctx: &mut Context<'_, '_, '_, 'info, DepositPrepare<'info>>,
+    amount: u64,
+) -> Result<DepositPreparedEvent> {
+    require!(amount > 0, VaultError::InvalidAmount);
+
+    let vault_key = ctx.accounts.vault.key();
+    let mut vault = ctx.accounts.vault.load_mut()?;
+    vault.require_deposits_open()?;
+
+    let price_slots = prices::price_account_count(&vault, ctx.remaining_accounts);
+    require!(
+        ctx.remaining_accounts.len() == vault.assets().len() * 2 + price_slots,
+        VaultError::InvalidRemainingAccounts
+    );
+    for (i, asset) in vault.assets().iter().enumerate() {
+        require!(ctx.remaining_accounts[i * 2 + 1].key() == asset.ata, VaultError::InvalidATA);
+    }
+
+    let clock = &ctx.accounts.clock;
+    let price_accounts = PriceAccounts {
+        vault: vault_key,
+        btc_quote: ctx.accounts.btc_quote.as_ref(),
+        eth_quote: ctx.accounts.eth_quote.as_ref(),
+        sol_quote: ctx.accounts.sol_quote.as_ref(),
+        remaining_accounts: ctx.remaining_accounts,
+    };
+    let feed_prices = prices::fetch_feed_prices_cached(&vault, &price_accounts, clock, 300)?;
+
+    let restricted = prices::check_circuit_breaker(&mut vault, vault_key, &feed_prices).restricted;
+    require!(!restricted, VaultError::VaultRestricted);
+
+    // Value the deposit against the vault before any of it arrives
+    let current_tvl = vault_tvl_usd(
+        &vault,
+        ctx.accounts.vault.as_ref(),
+        ctx.remaining_accounts,
+        &ctx.accounts.rent,
+        &feed_prices,
+    )?;
+    let total_shares = ctx.accounts.vault_token_mint.supply;
+    let share_price = Vault::calculate_share_price(current_tvl, total_shares)?;
+    let deposit_fee = math::bps_of(amount, vault.deposit_fee_bps)?;
+    let deposit_usd = feed_prices[2].tokens_to_usd(amount - deposit_fee, 9)?;
+    let shares = Vault::calculate_shares_for_deposit(deposit_usd, current_tvl, total_shares)?;
+    vault.require_min_shares(shares)?;
+    require!(shares > 0, VaultError::InvalidAmount);
+
+    // Escrow the lamports in the ticket
+    transfer_lamports_in(
+        &ctx.accounts.user,
+        &ctx.accounts.ticket.to_account_info(),
+        &ctx.accounts.system_program,
+        amount,
+    )?;
+
+    let expires_at = clock.unix_timestamp + DEPOSIT_TICKET_TTL_SECS;
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.vault = vault_key;
+    ticket.user = ctx.accounts.user.key();
+    ticket.amount = amount;
+    ticket.deposit_fee = deposit_fee;
+    ticket.deposit_usd = deposit_usd;
+    ticket.shares = shares;
+    ticket.prices = feed_prices;
+    ticket.next_asset = 0;
+    ticket.allocated_weight = 0;
+    ticket.expires_at = expires_at;
+    ticket.bump = ctx.bumps.ticket;
+
+    let position = &mut ctx.accounts.user_position;
+    position.init_if_new(vault_key, ctx.accounts.user.key(), ctx.bumps.user_position);
+
+    msg!(
+        "🎟️  Deposit of {} lamports prepared: {} shares at ${} per share, expires at {}",
+        amount,
+        shares,
+        share_price,
+        expires_at
+    );
+
+    vault.cache_nav(current_tvl, share_price, total_shares, clock.unix_timestamp);
+    Ok(DepositPreparedEvent {
+        vault: vault_key,
+        seq: vault.next_event_seq(),
+        user: ctx.accounts.user.key(),
+        amount,
+        shares,
+        expires_at,
+    })
+}
+
+fn allocate_deposit(
+    swaps: &mut SwapEngine,
+    vault: &AccountLoader<Vault>,
+    net_amount: u64,
+    deposit_usd_micro: i64,
+    prices: &BasketPrices,
+) -> Result<(Vec<AssetAllocation>, Option<u64>)> {
+    let num_assets = vault.load()?.assets().len();
+    let mut sol_to_stake: Option<u64> = None;
+    let mut allocations: Vec<AssetAllocation> = Vec::with_capacity(num_assets);
+
+    for i in 0..num_assets {
+        let (weight, sol_slot) = {
+            let vault = vault.load()?;
+            (vault.deposit_weight(i), vault.sol_slot())
+        };
+        let usd_allocation = (deposit_usd_micro * weight as i64) / 100;
+        let sol_amount_for_asset = math::to_u64(math::mul_div_floor(net_amount as u128, weight as u128, 100)?)?;
+
+        if sol_slot == Some(i) {
+            // Store SOL amount for Marinade staking
+            sol_to_stake = Some(sol_amount_for_asset);
+        }
+        allocations.push(allocate_asset(swaps, vault, i, sol_amount_for_asset, usd_allocation, prices)?);
+    }
+
+    Ok((allocations, sol_to_stake))
+}
+
+/// Put `sol_amount` lamports of a deposit into basket asset `index`
+///
+/// Assets are bought through the swap backend; the SOL slot keeps the SOL
+/// itself. The vault is only borrowed to read the asset: the swap can
+/// credit the vault PDA, so the caller must not hold a borrow either.
+fn allocate_asset(
+    swaps: &mut SwapEngine,
+    vault: &AccountLoader<Vault>,
+    index: usize,
+    sol_amount: u64,
+    usd_allocation: i64,
+    prices: &BasketPrices,
+) -> Result<AssetAllocation> {
+    let (asset, weight, sol_slot) = {
+        let vault = vault.load()?;
+        (vault.assets()[index], vault.deposit_weight(index), vault.sol_slot())
+    };
+
+    let token_amount = if sol_slot == Some(index) {
+        // For SOL, no swap needed - amount will be staked via Marinade
+        sol_amount
+    } else if sol_amount == 0 {
+        // Nothing to buy (a hidden-weights vault's deposit skips the asset)
+        0
+    } else {
+        // Swap SOL into the asset (BTC/ETH, an LST, or any per-asset priced mint)
+        let price = &prices.assets[index];
+        let sol_asset = SwapAsset {
+            mint: anchor_spl::token::spl_token::native_mint::ID,
+            decimals: 9,
+            price: prices.sol.original_price,
+            expo: prices.sol.expo,
+        };
+        let target = SwapAsset {
+            mint: asset.mint,
+            decimals: prices.decimals[index],
+            price: price.original_price,
+            expo: price.expo,
+        };
+        let min_out = swaps.min_out(&sol_asset, &target, sol_amount)?;
+        swaps.execute_swap(&sol_asset, &target, sol_amount, min_out)?
+    };
+
+    msg!(
+        "  ✓ Asset {} ({}%): ${} USD = {} tokens (from {} SOL)",
+        asset.mint,
+        weight,
+        usd_allocation,
+        token_amount,
+        sol_amount
+    );
+
+    // NOTE: The Mock backend only calculates amounts; Orca swaps
+    // execute here. SOL is delegated to the Marinade strategy by the caller.
+    Ok(AssetAllocation {
+        mint: asset.mint,
+        usd_value: usd_allocation,
+        token_amount,
+    })
+}
+
+/// Swap asset with oracle pricing for a BTC or ETH basket mint
+///
+/// Other mints (including the SOL slot, which is native) return None and
+/// can only be priced by the swap backend itself.
+fn basket_swap_asset(vault: &Vault, mint: Pubkey, prices: &[NormalizedPrice; 3]) -> Option<SwapAsset> {
+    let index = vault.assets().iter().position(|asset| asset.mint == mint)?;
+    if index > 1 {
+        return None;
+    }
+    let price = &prices[index];
+    Some(SwapAsset {
+        mint,
+        decimals: vault.asset_decimals(index),
+        price: price.original_price,
+        expo: price.expo,
+    })
+}
+
+/// A USDC vault's USDC as a swap side, priced at its $1 peg
+fn quote_swap_asset(quote: Option<&QuoteConfig>, mint: Pubkey) -> Option<SwapAsset> {
+    quote.filter(|quote| quote.is_quote_mint(&mint)).map(|_| SwapAsset {
+        mint,
+        decimals: USDC_DECIMALS,
+        price: 1_000_000,
+        expo: -6,
+    })
+}
+
+/// Swap `amount` of a token the vault holds into SOL
+///
+/// Fails below `min_sol_out`; basket assets and a USDC vault's USDC are also
+/// held to the oracle quote less max_slippage_bps. Mock vaults can only swap
+/// those.
+fn swap_to_sol(
+    swaps: &mut SwapEngine,
+    vault: &AccountLoader<Vault>,
+    quote: Option<&QuoteConfig>,
+    mint: Pubkey,
+    amount: u64,
+    min_sol_out: u64,
+    prices: &[NormalizedPrice; 3],
+) -> Result<u64> {
+    let sol_asset = SwapAsset {
+        mint: anchor_spl::token::spl_token::native_mint::ID,
+        decimals: 9,
+        price: prices[2].original_price,
+        expo: prices[2].expo,
+    };
+    let (input_asset, backend) = {
+        let vault = vault.load()?;
+        (basket_swap_asset(&vault, mint, prices), vault.swap_backend())
+    };
+    let input_asset = input_asset.or_else(|| quote_swap_asset(quote, mint));
+    let (input_asset, min_out) = match input_asset {
+        Some(input_asset) => {
+            let oracle_min = swaps.min_out(&input_asset, &sol_asset, amount)?;
+            (input_asset, min_sol_out.max(oracle_min))
+        }
+        None => {
+            require!(backend != SwapBackend::Mock, VaultError::UnsupportedSwapPair);
+            (SwapAsset::unpriced(mint), min_sol_out)
+        }
+    };
+    swaps.execute_swap(&input_asset, &sol_asset, amount, min_out)
+}
+
+/// Shares and allocation for SOL that already reached the vault
+struct ReceivedDeposit {
+    deposit_fee: u64,
+    deposit_usd: i64,
+    shares: u64,
+    allocations: Vec<AssetAllocation>,
+    /// Lamports of the SOL slot's allocation
+    sol_leg: u64,
+}
+
+/// Charge the deposit fee on `sol_received`, price the rest against the TVL
+/// and share supply taken before the SOL arrived, and allocate it across the
+/// basket
+///
+/// The SOL share stays in the vault (wrapped by the caller where the vault
+/// holds wrapped SOL); it is not delegated to a strategy.
+fn deposit_received_sol(
+    swaps: &mut SwapEngine,
+    vault: &AccountLoader<Vault>,
+    sol_received: u64,
+    current_tvl: i64,
+    total_shares: u64,
+    prices: &[NormalizedPrice; 3],
+) -> Result<ReceivedDeposit> {
+    let (deposit_fee, net_amount, deposit_usd, shares, basket_prices) = {
+        let vault = vault.load()?;
+        let deposit_fee = math::bps_of(sol_received, vault.deposit_fee_bps)?;
+        let net_amount = sol_received - deposit_fee;
+        let deposit_usd = prices[2].tokens_to_usd(net_amount, 9)?;
+        let shares = Vault::calculate_shares_for_deposit(deposit_usd, current_tvl, total_shares)?;
+        vault.require_min_shares(shares)?;
+        (deposit_fee, net_amount, deposit_usd, shares, BasketPrices::from_markets(&vault, prices))
+    };
+    msg!("Deposit: {} SOL = ${} USD (fee: {} lamports)", net_amount, deposit_usd, deposit_fee);
+
+    let (allocations, sol_leg) = allocate_deposit(swaps, vault, net_amount, deposit_usd, &basket_prices)?;
+
+    Ok(ReceivedDeposit {
+        deposit_fee,
+        deposit_usd,
+        shares,
+        allocations,
+        sol_leg: sol_leg.unwrap_or(0),
+    })
+}
+
+/// Wrap the SOL leg of a deposit into the vault's wSOL ATA
+///
+/// Only for vaults whose SOL slot is the native mint; others keep the SOL
+/// leg as the vault PDA's lamports. `remaining_accounts` start with
+/// [mint, vault ATA] per asset.
+fn wrap_sol_leg<'info>(
+    vault: &Vault,
+    vault_info: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    token_program: &Program<'info, Token>,
+    lamports: u64,
+) -> Result<()> {
+    let (Some(wsol_ata), Some(slot)) = (vault.wrapped_sol_ata(), vault.sol_slot()) else {
+        return Ok(());
+    };
+    if lamports == 0 {
+        return Ok(());
+    }
+    let ata_info = &remaining_accounts[slot * 2 + 1];
+    require!(ata_info.key() == wsol_ata, VaultError::InvalidATA);
+    token_io::wrap_sol(vault_info, ata_info, &token_program.to_account_info(), lamports)?;
+    msg!("🎁 Wrapped {} lamports into the vault's wSOL account", lamports);
+    Ok(())
+}
+
+/// One asset's holdings as counted by vault_tvl_usd
+struct InKindHolding {
+    amount: u64,
+    /// Held as native lamports on the vault PDA rather than in the ATA
+    native: bool,
+}
+
+/// Holdings of each asset for in-kind creation and redemption
+///
+/// `remaining_accounts` hold [participant token account, vault ATA, mint]
+/// per asset. Like vault_tvl_usd, the SOL slot counts the vault's native
+/// lamports (less rent and fees owed) unless it holds wrapped SOL.
+fn in_kind_holdings(
+    vault: &Vault,
+    vault_info: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+    rent: &Rent,
+) -> Result<Vec<InKindHolding>> {
+    require!(
+        remaining_accounts.len() == vault.assets().len() * IN_KIND_ACCOUNTS_PER_ASSET,
+        VaultError::InvalidRemainingAccounts
+    );
+
+    let native_sol_slot = vault.sol_slot().filter(|_| vault.wrapped_sol_ata().is_none());
+
+    let mut holdings = Vec::with_capacity(vault.assets().len());
+    for (i, asset) in vault.assets().iter().enumerate() {
+        let accounts = &remaining_accounts[i * IN_KIND_ACCOUNTS_PER_ASSET..];
+        let (participant_account, ata_info) = (&accounts[0], &accounts[1]);
+        require!(ata_info.key() == asset.ata, VaultError::InvalidATA);
+        require!(accounts[2].key() == asset.mint, VaultError::InvalidMint);
+
+        let holding = if native_sol_slot == Some(i) {
+            InKindHolding {
+                amount: sol_leg_balance(vault, vault_info, ata_info, rent)?,
+                native: true,
+            }
+        } else {
+            let amount = if ata_info.data_is_empty() {
+                0
+            } else {
+                token_io::token_amount(ata_info)?
+            };
+            let account = token_io::load_token_account(participant_account)?;
+            require!(account.mint == asset.mint, VaultError::InvalidMint);
+            InKindHolding { amount, native: false }
+        };
+        holdings.push(holding);
+    }
+    Ok(holdings)
+}
+
+/// Pay `recipient` its `shares / total_shares` slice of every holding
+/// (rounded down) and return the amounts paid, in vault.assets order
+///
+/// `remaining_accounts` are laid out as for in_kind_holdings; tokens go to
+/// the holder account of each asset (less any Token-2022 transfer fee),
+/// native SOL straight to `recipient`.
+#[allow(clippy::too_many_arguments)]
+fn pay_in_kind<'info>(
+    vault: &AccountLoader<'info, Vault>,
+    recipient: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    token_program: &AccountInfo<'info>,
+    token_2022_program: Option<&AccountInfo<'info>>,
+    rent: &Rent,
+    shares: u64,
+    total_shares: u64,
+) -> Result<Vec<u64>> {
+    let vault_info = vault.to_account_info();
+    let (holdings, signer) = {
+        let vault = vault.load()?;
+        (in_kind_holdings(&vault, &vault_info, remaining_accounts, rent)?, vault.signer())
+    };
+    let vault_seeds = signer.seeds();
+    let signer_seeds = &[&vault_seeds[..]];
+
+    let mut amounts = Vec::with_capacity(holdings.len());
+    for (i, holding) in holdings.iter().enumerate() {
+        let amount = math::pro_rata(holding.amount, shares, total_shares)?;
+        amounts.push(amount);
+        if amount == 0 {
+            continue;
+        }
+
+        let accounts = &remaining_accounts[i * IN_KIND_ACCOUNTS_PER_ASSET..];
+        if holding.native {
+            **vault_info.try_borrow_mut_lamports()? -= amount;
+            **recipient.try_borrow_mut_lamports()? += amount;
+        } else {
+            token_io::transfer(
+                token_program,
+                token_2022_program,
+                &accounts[1],
+                &accounts[2],
+                &accounts[0],
+                &vault_info,
+                amount,
+                signer_seeds,
+            )?;
+        }
+        msg!("  • Paid {} of {}", amount, accounts[2].key());
+    }
+    Ok(amounts)
+}
+
+/// Check the Marinade accounts a marinade_strategy unstake passes through
+fn verify_unstake_accounts(
+    marinade_program: &AccountInfo,
+    marinade_state: &AccountInfo,
+    msol_mint: &AccountInfo,
+    liq_pool_msol_leg: &AccountInfo,
+    liq_pool_sol_leg_pda: &AccountInfo,
+    treasury_msol_account: &AccountInfo,
+) -> Result<()> {
+    marinade::verify(
+        marinade_program.key,
+        &[
+            (MarinadeAccount::State, marinade_state.key()),
+            (MarinadeAccount::MsolMint, msol_mint.key()),
+            (MarinadeAccount::LiqPoolMsolLeg, liq_pool_msol_leg.key()),
+            (MarinadeAccount::LiqPoolSolLeg, liq_pool_sol_leg_pda.key()),
+            (MarinadeAccount::TreasuryMsolAccount, treasury_msol_account.key()),
+        ],
+    )
+}
+
+/// Move `amount` lamports from a signing user into a program account
+///
+/// The one place user SOL enters the vault program, so each deposit path
+/// debits the user exactly once.
+fn transfer_lamports_in<'info>(
+    from: &Signer<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from: from.to_account_info(),
+        to: to.clone(),
+    };
+    let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+    transfer(cpi_ctx, amount)
+}
+
+/// Scale the cached TVL to a new share supply at an unchanged share price
+/// (in-kind creation and redemption happen exactly at NAV)
+fn rescale_nav_cache(vault: &mut Vault, old_shares: u64, new_shares: u64) -> Result<()> {
+    let tvl = if old_shares == 0 {
+        0
+    } else {
+        math::to_i64(math::mul_div_floor(
+            math::to_u128(vault.last_tvl_usd)?,
+            new_shares as u128,
+            old_shares as u128,
+        )?)?
+    };
+    let (share_price, updated) = (vault.last_share_price, vault.last_nav_update);
+    vault.cache_nav(tvl, share_price, new_shares, updated);
+    Ok(())
+}
+
+/// TVL of a per-asset priced vault: each asset's vault ATA at its own price
+/// and mint decimals, plus SOL staked through the strategy
+///
+/// A native-mint slot is held as wrapped SOL, so its ATA holds the whole
+/// unstaked SOL leg.
+fn basket_tvl_usd(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+    prices: &BasketPrices,
+) -> Result<i64> {
+    let mut tvl = 0i64;
+    for (i, asset) in vault.assets().iter().enumerate() {
+        let ata_account_info = &remaining_accounts[i * 2 + 1];
+        require!(ata_account_info.key() == asset.ata, VaultError::InvalidATA);
+        let balance = if ata_account_info.data_is_empty() {
+            0
+        } else {
+            token_io::token_amount(ata_account_info)?
+        };
+        let usd_value = prices.assets[i].tokens_to_usd(balance, prices.decimals[i])?;
+        msg!("Asset {} (weight {}%): {} tokens = ${}", asset.mint, asset.weight, balance, usd_value);
+        tvl = tvl.checked_add(usd_value).ok_or(VaultError::MathOverflow)?;
+    }
+
+    let staked_lamports = strategy_value::staked_lamports(vault, vault_key, remaining_accounts)?;
+    let staked_usd = prices.sol.tokens_to_usd(staked_lamports, 9)?;
+    if staked_lamports > 0 {
+        msg!("  Strategy value: {} lamports = ${}", staked_lamports, staked_usd);
+    }
+    Ok(tvl.checked_add(staked_usd).ok_or(VaultError::MathOverflow)?)
+}
+
+fn vault_tvl_usd(
+    vault: &Vault,
+    vault_info: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+    rent: &Rent,
+    prices: &[NormalizedPrice; 3],
+) -> Result<i64> {
+    let holdings_usd = vault_holdings_usd(vault, vault_info, remaining_accounts, rent, prices)?;
+    let tvl = holdings_usd
+        .iter()
+        .try_fold(0i64, |tvl, usd| tvl.checked_add(*usd))
+        .ok_or(VaultError::MathOverflow)?;
+    msg!(
+        "TVL Calculation: BTC=${}, ETH=${}, SOL=${}, Total=${}",
+        holdings_usd[0],
+        holdings_usd[1],
+        holdings_usd[2],
+        tvl
+    );
+    Ok(tvl)
+}
+
+/// USD value of the vault's BTC, ETH and SOL legs, SOL staked through the
+/// strategy included in the SOL leg; vault_tvl_usd is their sum
+fn vault_holdings_usd(
+    vault: &Vault,
+    vault_info: &AccountInfo,
+    remaining_accounts: &[AccountInfo],
+    rent: &Rent,
+    prices: &[NormalizedPrice; 3],
+) -> Result<[i64; 3]> {
+    // Get asset balances from remaining_accounts (vault ATAs)
+    let mut btc_balance = 0u64;
+    let mut eth_balance = 0u64;
+    let mut sol_balance = 0u64;
+
+    for (i, asset) in vault.assets().iter().enumerate() {
+        let ata_account_info = &remaining_accounts[i * 2 + 1];
+
+        // The SOL leg is wrapped SOL or the PDA's own lamports, never both
+        if vault.sol_slot() == Some(i) {
+            sol_balance = sol_leg_balance(vault, vault_info, ata_account_info, rent)?;
+            msg!("  → Using SOL balance: {}", sol_balance);
+            continue;
+        }
+        
+        // Parse the ATA to get balance
+        if ata_account_info.data_is_empty() {
+            msg!("  Asset {} ATA is empty (balance = 0)", asset.mint);
+            continue;
+        }
+
+        // Deserialize token account (SPL Token or Token-2022) to get amount
+        let ata = token_io::load_token_account(ata_account_info)?;
+        
+        msg!("Asset {} (weight {}%): {} tokens in ATA", asset.mint, asset.weight, ata.amount);
+
+        // Map balance to correct asset based on position (BTC, ETH, SOL), so
+        // governance can re-weight the basket without breaking the mapping
+        // This is a simplified approach - in production you'd match by mint address
+        match i {
+            0 => btc_balance = ata.amount,
+            1 => eth_balance = ata.amount,
+            _ => {}
+        }
+    }
+
+    // SOL staked through the strategy, valued at the last sync plus yield
+    let staked_lamports = strategy_value::staked_lamports(vault, vault_info.key, remaining_accounts)?;
+    let staked_usd = prices[2].tokens_to_usd(staked_lamports, 9)?;
+    if staked_lamports > 0 {
+        msg!("  Strategy value: {} lamports = ${}", staked_lamports, staked_usd);
+    }
+
+    let [btc_price, eth_price, sol_price] = prices;
+    // LSTs all have 9 decimals and are priced at SOL × their exchange rate
+    let decimals = if vault.lst_index() {
+        [lst::LST_DECIMALS; 3]
+    } else {
+        [8, 18, 9]
+    };
+    let sol_usd = sol_price.tokens_to_usd(sol_balance, decimals[2])?;
+    Ok([
+        btc_price.tokens_to_usd(btc_balance, decimals[0])?,
+        eth_price.tokens_to_usd(eth_balance, decimals[1])?,
+        sol_usd.checked_add(staked_usd).ok_or(VaultError::MathOverflow)?,
+    ])
+}
+
+/// Balance of the SOL leg: the wSOL ATA's for a native-mint SOL slot, the
+/// vault PDA's lamports above rent and the fee reserve otherwise
+///
+/// TVL, previews, withdrawals and in-kind transfers all read the SOL leg
+/// here, so the vault only counts SOL that a withdrawal pays out.
+fn sol_leg_balance(vault: &Vault, vault_info: &AccountInfo, ata_info: &AccountInfo, rent: &Rent) -> Result<u64> {
+    if vault.wrapped_sol_ata().is_some() {
+        return if ata_info.data_is_empty() {
+            Ok(0)
+        } else {
+            token_io::token_amount(ata_info)
+        };
+    }
+    Ok(vault_info
+        .lamports()
+        .saturating_sub(rent.minimum_balance(vault_info.data_len()))
+        .saturating_sub(vault.fee_reserve()))
+}
+
+/// PriceSourceChangedEvent describing the vault's current pricing setup
+fn price_source_changed_event(vault: &mut Vault, vault_key: Pubkey) -> PriceSourceChangedEvent {
+    PriceSourceChangedEvent {
+        vault: vault_key,
+        seq: vault.next_event_seq(),
+        price_source: vault.price_source(),
+        mock_oracle: vault.mock_oracle(),
+        feed_count: vault.price_feeds().len() as u8,
+        twap_window_secs: vault.twap_window_secs,
+    }
+}
+
+/// VaultPauseEvent if deposits paused or resumed since `was_paused`
+fn pause_change_event(vault: &mut Vault, vault_key: Pubkey, was_paused: bool) -> Option<VaultPauseEvent> {
+    let paused = vault.deposits_paused();
+    (paused != was_paused).then(|| VaultPauseEvent {
+        vault: vault_key,
+        seq: vault.next_event_seq(),
+        paused,
+        soft_closed: vault.soft_closed(),
+        restricted: vault.restricted(),
+        guardian_paused: vault.guardian_deposits_paused(),
+    })
+}
+
+/// Calculate USD value of an asset balance
+fn calculate_asset_usd_value(balance: u64, price: i64, mint: Pubkey) -> Result<i64> {
+    // Determine token decimals based on mint
+    let decimals = get_token_decimals(mint)?;
+    
+    // Calculate: (balance * price) / 10^decimals
+    // Both sides are in micro-USD (6 decimals)
+    let usd_value = math::mul_div_floor(balance as u128, math::to_u128(price)?, math::pow10(decimals)?)?;
+
+    math::to_i64(usd_value)
+}
+
+/// Execute the planned swap legs that move over-allocated assets into
+/// under-allocated ones
+///
+/// Returns a RebalanceSwapEvent per leg for the caller to emit and the
+/// per-asset USD values after the swaps.
+fn execute_rebalance_swaps<'info>(
+    vault: &AccountLoader<'info, Vault>,
+    swap_accounts: &[AccountInfo<'info>],
+    prices: &[i64],
+    legs: &[rebalance_plan::Leg],
+    current_usds: &[i64],
+) -> Result<(Vec<RebalanceSwapEvent>, Vec<i64>)> {
+    // USD value per asset after the swaps below, used for the completion event
+    let mut post_swap_usds = current_usds.to_vec();
+    let mut swapped = Vec::with_capacity(legs.len());
+
+    // Swap sides and the engine's settings are read up front: the swaps can
+    // credit the vault PDA, so no borrow of its data may be held across them
+    let vault_data = vault.load()?;
+    let signer = vault_data.signer();
+    let sides = legs
+        .iter()
+        .map(|leg| {
+            Ok((
+                rebalance_swap_asset(&vault_data, prices, leg.from)?,
+                rebalance_swap_asset(&vault_data, prices, leg.to)?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let vault_seeds = signer.seeds();
+    let signer_seeds = &[&vault_seeds[..]];
+    let mut swaps = SwapEngine::new(
+        &vault_data,
+        vault.to_account_info(),
+        signer_seeds,
+        swap_accounts,
+    );
+    drop(vault_data);
+
+    for (leg, (from, to)) in legs.iter().zip(sides) {
+        msg!("     Swapping ${} from asset {} to asset {}",
+            leg.swap_usd / 1_000_000, leg.from, leg.to);
+
+        let min_out = swaps.min_out(&from, &to, leg.amount_in)?;
+        let amount_out = swaps.execute_swap(&from, &to, leg.amount_in, min_out)?;
+
+        msg!("       Input: {} (asset {}), Output: {} (asset {})",
+            leg.amount_in, leg.from, amount_out, leg.to);
+
+        post_swap_usds[leg.from] -= leg.swap_usd;
+        post_swap_usds[leg.to] += leg.swap_usd;
+        swapped.push((leg, from.mint, to.mint, amount_out));
+    }
+
+    let mut vault_data = vault.load_mut()?;
+    let swap_events = swapped
+        .into_iter()
+        .map(|(leg, from_mint, to_mint, amount_out)| RebalanceSwapEvent {
+            vault: vault.key(),
+            seq: vault_data.next_event_seq(),
+            from_mint,
+            to_mint,
+            amount_in: leg.amount_in,
+            amount_out,
+            from_price: prices[leg.from],
+            to_price: prices[leg.to],
+        })
+        .collect();
+
+    Ok((swap_events, post_swap_usds))
+}
+
+/// Asset in slot `index` as a swap side, at its micro-USD (expo -6) price
+fn rebalance_swap_asset(vault: &Vault, prices: &[i64], index: usize) -> Result<SwapAsset> {
+    let mint = vault.assets()[index].mint;
+    Ok(SwapAsset {
+        mint,
+        decimals: get_token_decimals(mint)?,
+        price: prices[index],
+        expo: -6,
+    })
+}
+
+/// Price accounts rebalance takes ahead of the vault ATAs in remaining_accounts
+fn rebalance_ata_offset(vault: &Vault) -> usize {
+    vault.lst_index() as usize + prices::oracle_account_count(vault)
+}
+
+/// Prices and per-asset USD values a rebalance works from
+struct RebalanceHoldings {
+    /// Micro-USD per whole token, in asset order
+    prices: Vec<i64>,
+    current_usds: Vec<i64>,
+    total_usd: i64,
+}
+
+/// Price every asset (2 min max staleness for mock prices) and value the
+/// vault's ATAs, which follow the price accounts in `remaining_accounts`
+fn rebalance_holdings<'info>(
+    vault: &Vault,
+    vault_key: Pubkey,
+    quotes: [&UncheckedAccount<'info>; 3],
+    remaining_accounts: &[AccountInfo<'info>],
+    clock: &Clock,
+) -> Result<RebalanceHoldings> {
+    let ata_offset = rebalance_ata_offset(vault);
+    require!(
+        remaining_accounts.len() >= vault.assets().len() + ata_offset,
+        VaultError::InvalidRemainingAccounts
+    );
+    let [btc_quote, eth_quote, sol_quote] = quotes;
+    let price_accounts = PriceAccounts {
+        vault: vault_key,
+        btc_quote: btc_quote.as_ref(),
+        eth_quote: eth_quote.as_ref(),
+        sol_quote: sol_quote.as_ref(),
+        remaining_accounts,
+    };
+    let asset_prices = prices::fetch_prices(vault, &price_accounts, clock, 120)?;
+    let prices = vault
+        .assets()
+        .iter()
+        .map(|asset| prices::price_for_mint(&asset_prices, &asset.mint).map(|p| p.price_usd))
+        .collect::<Result<Vec<i64>>>()?;
+
+    let mut total_usd: i64 = 0;
+    let mut current_usds = Vec::with_capacity(vault.assets().len());
+    for (i, asset) in vault.assets().iter().enumerate() {
+        let ata_account = &remaining_accounts[i + ata_offset];
+        require!(ata_account.key() == asset.ata, VaultError::InvalidATA);
+
+        // Parse token account and check it is the vault's holding of this asset
+        let token_account = token_io::load_token_account(ata_account)?;
+        require!(
+            token_account.owner == vault_key && token_account.mint == asset.mint,
+            VaultError::InvalidATA
+        );
+
+        // balance is in native token decimals, price is in micro-USD
+        let usd_value = calculate_asset_usd_value(token_account.amount, prices[i], asset.mint)?;
+        current_usds.push(usd_value);
+        total_usd = total_usd.checked_add(usd_value).ok_or(VaultError::MathOverflow)?;
+
+        msg!(
+            "   Asset {}: Price=${}, Balance={}, USD=${}",
+            i,
+            prices[i] / 1_000_000,
+            token_account.amount,
+            usd_value / 1_000_000
+        );
+    }
+
+    Ok(RebalanceHoldings {
+        prices,
+        current_usds,
+        total_usd,
+    })
+}
+
+/// Planned legs for drifted assets at `prices` (rebalance's token decimals),
+/// scaled down to the turnover cap in the vault's RebalanceConfig
+fn plan_rebalance_legs(
+    vault: &Vault,
+    config: Option<&Account<RebalanceConfig>>,
+    prices: &[i64],
+    drifts: &[rebalance_plan::Drift],
+    total_usd: i64,
+) -> Result<(Vec<rebalance_plan::Leg>, Vec<rebalance_plan::Leg>)> {
+    let decimals = vault
+        .assets()
+        .iter()
+        .map(|asset| get_token_decimals(asset.mint))
+        .collect::<Result<Vec<u8>>>()?;
+    let legs = rebalance_plan::legs(drifts, prices, &decimals)?;
+
+    let max_turnover_bps = config.map_or(0, |config| config.max_turnover_bps_per_rebalance);
+    let legs = match rebalance_plan::max_turnover_usd(total_usd, max_turnover_bps)? {
+        Some(max_turnover_usd) => {
+            msg!("   Turnover capped at ${} ({} bps of TVL)", max_turnover_usd / 1_000_000, max_turnover_bps);
+            rebalance_plan::cap_turnover(legs, max_turnover_usd)?
+        }
+        None => legs,
+    };
+
+    let limits = match config {
+        Some(config) => rebalance_plan::DustLimits {
+            min_swap_usd: match config.min_swap_usd {
+                0 => rebalance_plan::MIN_SWAP_USD,
+                min_swap_usd => math::to_i64(min_swap_usd as u128)?,
+            },
+            min_amount_in: &config.dust_thresholds[..vault.assets().len()],
+        },
+        None => rebalance_plan::DustLimits::default(),
+    };
+    let (legs, dust) = rebalance_plan::split_dust(legs, &limits);
+    for leg in &dust {
+        msg!("   Skipping dust: ${} ({} units) from asset {} to asset {}",
+            leg.swap_usd / 1_000_000, leg.amount_in, leg.from, leg.to);
+    }
+    Ok((legs, dust))
+}
+
+/// Mock-oracle prices and per-asset USD values of a confidential-rebalance
+/// vault, with their total (which must be positive)
+///
+/// remaining_accounts: [0] the vault's MockOracle, [1..=n] its asset ATAs.
+fn confidential_holdings(vault: &Vault, remaining_accounts: &[AccountInfo]) -> Result<([i64; ORACLE_MARKETS], Vec<i64>, i64)> {
+    require!(remaining_accounts.len() > vault.assets().len(), VaultError::InvalidRemainingAccounts);
+
+    // Prices from the vault's own mock oracle
+    let oracle_key = vault.mock_oracle().ok_or(VaultError::InvalidPrice)?;
+    let oracle_account = &remaining_accounts[0];
+    require!(oracle_account.key() == oracle_key, VaultError::InvalidPrice);
+    let oracle_data = oracle_account.try_borrow_data()?;
+    let oracle = MockPriceOracle::try_deserialize(&mut &oracle_data[..])?;
+    drop(oracle_data);
+
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(current_time - oracle.last_update < 120, VaultError::StaleQuote);
+
+    let prices = [oracle.btc_price, oracle.eth_price, oracle.sol_price];
+
+    let mut total_usd: i64 = 0;
+    let mut current_usds = Vec::with_capacity(vault.assets().len());
+    for (i, asset) in vault.assets().iter().enumerate() {
+        let ata_account = &remaining_accounts[i + 1];
+        require!(ata_account.key() == asset.ata, VaultError::InvalidATA);
+
+        let usd_value = calculate_asset_usd_value(token_io::token_amount(ata_account)?, prices[i], asset.mint)?;
+
+        current_usds.push(usd_value);
+        total_usd = total_usd.checked_add(usd_value).ok_or(VaultError::MathOverflow)?;
+    }
+    require!(total_usd > 0, VaultError::InsufficientBalance);
+
+    Ok((prices, current_usds, total_usd))
+}
+
+/// Serialize seal_target_weights instruction data for the Arcium MXE program
+///
+/// Layout (Borsh, little-endian): discriminator, computation_offset (u64),
+/// pub_key, nonce (u128), then the BTC/ETH/SOL weight ciphertexts
+fn build_seal_target_weights_data(
+    computation_offset: u64,
+    pub_key: &[u8; 32],
+    nonce: u128,
+    ciphertexts: &[[u8; CIPHERTEXT_LEN]; ORACLE_MARKETS],
+) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + 8 + 32 + 16 + ORACLE_MARKETS * CIPHERTEXT_LEN);
+    data.extend_from_slice(&mxe::SEAL_TARGET_WEIGHTS_DISCRIMINATOR);
+    data.extend_from_slice(&computation_offset.to_le_bytes());
+    data.extend_from_slice(pub_key);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    for ciphertext in ciphertexts {
+        data.extend_from_slice(ciphertext);
+    }
+    data
+}
+
+/// Serialize compute_hidden_rebalancing instruction data for the Arcium MXE
+/// program
+///
+/// Layout (Borsh, little-endian): discriminator, computation_offset (u64),
+/// the BTC/ETH/SOL USD values and max_trade_usd (u64 micro-USD each)
+fn build_compute_hidden_rebalancing_data(
+    computation_offset: u64,
+    usd_values: &[u64],
+    max_trade_usd: u64,
+) -> Result<Vec<u8>> {
+    require!(usd_values.len() == ORACLE_MARKETS, VaultError::InvalidAssetCount);
+
+    let mut data = Vec::with_capacity(8 + 8 + (ORACLE_MARKETS + 1) * 8);
+    data.extend_from_slice(&mxe::COMPUTE_HIDDEN_REBALANCING_DISCRIMINATOR);
+    data.extend_from_slice(&computation_offset.to_le_bytes());
+    for usd_value in usd_values {
+        data.extend_from_slice(&usd_value.to_le_bytes());
+    }
+    data.extend_from_slice(&max_trade_usd.to_le_bytes());
+    Ok(data)
+}
+
+/// Serialize compute_rebalancing instruction data for the Arcium MXE program
+///
+/// Layout (Borsh, little-endian):
+/// - 8 bytes: discriminator
+/// - 8 bytes: computation_offset (u64)
+/// - 32 bytes: vault the result is for
+/// - 32 bytes: pub_key
+/// - 16 bytes: nonce (u128)
+/// - 13 * 32 bytes: encrypted RebalancingInput fields ([[u8; 32]; 13], no length prefix)
+fn build_compute_rebalancing_data(
+    computation_offset: u64,
+    vault: &Pubkey,
+    pub_key: &[u8; 32],
+    nonce: u128,
+    encrypted_portfolio: &[[u8; CIPHERTEXT_LEN]],
+) -> Result<Vec<u8>> {
+    require!(
+        encrypted_portfolio.len() == REBALANCING_INPUT_FIELDS,
+        VaultError::InvalidEncryptedInput
+    );
+
+    let mut data =
+        Vec::with_capacity(8 + 8 + 32 + 32 + 16 + REBALANCING_INPUT_FIELDS * CIPHERTEXT_LEN);
+    data.extend_from_slice(&COMPUTE_REBALANCING_DISCRIMINATOR);
+    data.extend_from_slice(&computation_offset.to_le_bytes());
+    data.extend_from_slice(vault.as_ref());
+    data.extend_from_slice(pub_key);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    for ciphertext in encrypted_portfolio {
+        data.extend_from_slice(ciphertext);
+    }
+
+    Ok(data)
+}
+
+/// Convert per-asset USD values into weights in basis points (10_000 = 100%)
+/// Returns all zeros for an empty vault
+fn calculate_weights_bps(usd_values: &[i64], total_usd: i64) -> Vec<u16> {
+    usd_values
+        .iter()
+        .map(|value| {
+            if total_usd <= 0 {
+                0
+            } else {
+                ((*value as i128 * 10_000) / total_usd as i128).clamp(0, 10_000) as u16
+            }
+        })
+        .collect()
+}
+
+/// Get token decimals based on mint address
+fn get_token_decimals(_mint: Pubkey) -> Result<u8> {
+    // In production, this would query the mint account
+    // For now, we use standard decimals for devnet testing
+    // BTC: 8, ETH: 9 (simplified from 18), SOL: 9
+    
+    // Default to SOL decimals (9) for all tokens in testing
+    // TODO: Read actual decimals from mint account in production
+    Ok(9)
+}
+
+// ============================================================================
+// Account Validation Structs
+// ============================================================================
+
+/// Accounts for creating a new multi-asset vault
+///
+/// **Architecture Notes:**
+/// - Vault PDA: Derived from [b"vault", admin, name] for multi-vault support
+/// - Space calculation: Dynamic based on name length and asset count
+/// - Share mint: Also a PDA [b"vault_mint", admin, name] for determinism
+/// - Remaining accounts: Used for variable asset list (mints + ATAs)
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, assets: Vec<AssetConfig>)]
+pub struct CreateVault<'info> {
+    /// The vault account - stores all composition and state
+    /// Uses dynamic space allocation based on name and asset count
+    #[account(
+        init,
+        payer = admin,
+        space = Vault::LEN,
+        seeds = [b"vault", admin.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Admin who creates and manages the vault
+    /// Pays for account rent and has rebalance permissions
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Mint for vault shares, created by the instruction: SPL Token, or
+    /// Token-2022 with the NonTransferable extension for soulbound vaults
+    /// Vault PDA is mint authority (secure share minting)
+    /// 9 decimals for high precision in share calculations
+    /// CHECK: Address fixed by the seeds; created in the instruction
+    #[account(mut, seeds = [b"vault_mint", admin.key().as_ref(), name.as_bytes()], bump)]
+    pub vault_token_mint: UncheckedAccount<'info>,
+
+    /// Program-wide vault counter (created by the first vault)
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = VaultRegistry::LEN,
+        seeds = [b"vault_registry"],
+        bump
+    )]
+    pub vault_registry: Account<'info, VaultRegistry>,
+
+    /// This vault's registry entry
+    #[account(
+        init,
+        payer = admin,
+        space = VaultRegistration::LEN,
+        seeds = [b"vault_registration", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_registration: Account<'info, VaultRegistration>,
+
+    /// Protocol whitelist of wrapped BTC/ETH mints (not created = unrestricted)
+    /// CHECK: Address fixed by the seeds; deserialized only when it has data
+    #[account(seeds = [b"asset_whitelist"], bump)]
+    pub asset_whitelist: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when any basket asset is a Token-2022
+    /// mint or the shares are soulbound
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    // remaining_accounts layout (per asset):
+    // [0]: mint (UncheckedAccount) - SPL Token or Token-2022, validated in instruction
+    // [1]: ata (mut, UncheckedAccount) - vault's ATA under the mint's token program, validated and created
+    // For N assets: 2*N accounts total
+}
+
+/// Accounts for create_vault_from_template: create_vault's plus the template
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, template_name: String)]
+pub struct CreateVaultFromTemplate<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Vault::LEN,
+        seeds = [b"vault", admin.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Share mint, set up as in create_vault
+    /// CHECK: Address fixed by the seeds; created in the instruction
+    #[account(mut, seeds = [b"vault_mint", admin.key().as_ref(), name.as_bytes()], bump)]
+    pub vault_token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_template", template_name.as_bytes()],
+        bump = vault_template.bump
+    )]
+    pub vault_template: Box<Account<'info, VaultTemplate>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = VaultRegistry::LEN,
+        seeds = [b"vault_registry"],
+        bump
+    )]
+    pub vault_registry: Account<'info, VaultRegistry>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = VaultRegistration::LEN,
+        seeds = [b"vault_registration", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_registration: Account<'info, VaultRegistration>,
+
+    /// Protocol whitelist of wrapped BTC/ETH mints (not created = unrestricted)
+    /// CHECK: Address fixed by the seeds; deserialized only when it has data
+    #[account(seeds = [b"asset_whitelist"], bump)]
+    pub asset_whitelist: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when any template asset is a Token-2022
+    /// mint or the shares are soulbound
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    // remaining_accounts: [mint, ata] per template asset, as for create_vault
+}
+
+/// Accounts for init_vault: create_vault without the composition
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct InitVault<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Vault::LEN,
+        seeds = [b"vault", admin.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Share mint, set up as in create_vault
+    /// CHECK: Address fixed by the seeds; created in the instruction
+    #[account(mut, seeds = [b"vault_mint", admin.key().as_ref(), name.as_bytes()], bump)]
+    pub vault_token_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = VaultRegistry::LEN,
+        seeds = [b"vault_registry"],
+        bump
+    )]
+    pub vault_registry: Account<'info, VaultRegistry>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = VaultRegistration::LEN,
+        seeds = [b"vault_registration", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_registration: Account<'info, VaultRegistration>,
+
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when the shares are soulbound
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct AddVaultAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Vault authority; pays for the ATA
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: The asset's SPL Token or Token-2022 mint, validated in instruction
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Vault's ATA for `mint` under the mint's token program, validated and created
+    #[account(mut)]
+    pub vault_ata: UncheckedAccount<'info>,
+
+    /// Protocol whitelist of wrapped BTC/ETH mints (not created = unrestricted)
+    /// CHECK: Address fixed by the seeds; deserialized only when it has data
+    #[account(seeds = [b"asset_whitelist"], bump)]
+    pub asset_whitelist: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when the asset is a Token-2022 mint
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct FinalizeVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DepositZap<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Token being deposited
+    pub input_mint: Box<Account<'info, Mint>>,
+
+    /// User's account holding the input token
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::authority = user
+    )]
+    pub user_input_account: Box<Account<'info, TokenAccount>>,
+
+    /// Vault's ATA for the input token (the swap's source account)
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = input_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_input_account: Box<Account<'info, TokenAccount>>,
+
+    /// User's ATA to receive vault shares
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// User's position in this vault (created on first deposit)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    /// Vault's share token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    /// Vault's QuoteConfig; pass it to hold a USDC vault's USDC to its peg
+    #[account(
+        seeds = [b"quote_config", vault.key().as_ref()],
+        bump = quote_config.bump
+    )]
+    pub quote_config: Option<Account<'info, QuoteConfig>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DepositSingleAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Debited under the user's signature; the token program checks
+    /// its owner and that its mint matches the deposited asset
+    #[account(mut)]
+    pub user_token_account: UncheckedAccount<'info>,
+
+    /// User's ATA to receive vault shares
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// User's position in this vault (created on first deposit)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    /// Vault's share token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when the deposited asset is a Token-2022 mint
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DepositMsol<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's mSOL account
+    #[account(
+        mut,
+        token::mint = msol_mint,
+        token::authority = user
+    )]
+    pub user_msol_account: Box<Account<'info, TokenAccount>>,
+
+    /// mSOL mint; Marinade's state must record it as its mSOL mint
+    pub msol_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Owner and mSOL mint are checked when reading its exchange rate
+    pub marinade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the vault's marinade_strategy
+    #[account(mut)]
+    pub strategy_account: UncheckedAccount<'info>,
+
+    /// Strategy's mSOL ATA (receives the deposit)
+    /// CHECK: Validated by marinade_strategy during CPI
+    #[account(mut)]
+    pub strategy_msol_ata: UncheckedAccount<'info>,
+
+    /// CHECK: The marinade_strategy program
+    #[account(address = marinade_strategy::ID)]
+    pub marinade_strategy_program: UncheckedAccount<'info>,
+
+    /// User's ATA to receive vault shares
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// User's position in this vault (created on first deposit)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    /// Vault's share token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DepositPrepare<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// One open ticket per user and vault
+    #[account(
+        init,
+        payer = user,
+        space = DepositTicket::LEN,
+        seeds = [b"deposit_ticket", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub ticket: Box<Account<'info, DepositTicket>>,
+
+    /// User's ATA to receive vault shares
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// User's position in this vault (created on first deposit)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    /// The commitment reveal_deposit opens, closed to the user; none for
+    /// deposit_prepare
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"deposit_commitment", vault.key().as_ref(), user.key().as_ref()],
+        bump = deposit_commitment.bump
+    )]
+    pub deposit_commitment: Option<Box<Account<'info, DepositCommitment>>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CommitDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// One open commitment per user and vault
+    #[account(
+        init,
+        payer = user,
+        space = DepositCommitment::LEN,
+        seeds = [b"deposit_commitment", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub deposit_commitment: Account<'info, DepositCommitment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CancelDepositCommitment<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"deposit_commitment", vault.key().as_ref(), user.key().as_ref()],
+        bump = deposit_commitment.bump
+    )]
+    pub deposit_commitment: Account<'info, DepositCommitment>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DepositExecute<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_ticket", vault.key().as_ref(), user.key().as_ref()],
+        bump = ticket.bump
+    )]
+    pub ticket: Box<Account<'info, DepositTicket>>,
+
+    /// Depositor, receives the shares and the ticket's rent
+    /// CHECK: Bound to the ticket by its seeds
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// Anyone may execute an unexpired ticket
+    pub executor: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CloseDepositTicket<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"deposit_ticket", vault.key().as_ref(), user.key().as_ref()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, DepositTicket>,
+
+    /// Depositor, receives the refund
+    /// CHECK: Bound to the ticket by its seeds
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// The user, or anyone once the ticket has expired
+    pub closer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DepositMultiAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's ATA to receive vault shares
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    /// User's position in this vault (created on first deposit)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    /// Referrer's balance in this vault (required when a referrer is passed)
+    #[account(mut)]
+    pub referral_balance: Option<Box<Account<'info, ReferralBalance>>>,
+
+    /// Vault's share token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    // ========== Marinade Strategy Accounts (Optional - only if vault.marinade_strategy is set) ==========
+    
+    /// Marinade Strategy program (for CPI)
+    /// CHECK: This is the marinade_strategy program that wraps Marinade Finance
+    #[account(address = marinade_strategy::ID)]
+    pub marinade_strategy_program: UncheckedAccount<'info>,
+    
+    /// Marinade Finance program (passed through to strategy)
+    /// CHECK: Checked against known Marinade deployments when marinade_strategy is configured
+    pub marinade_program: UncheckedAccount<'info>,
+    
+    /// Marinade state account
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub marinade_state: UncheckedAccount<'info>,
+    
+    /// Marinade reserve PDA
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub reserve_pda: UncheckedAccount<'info>,
+    
+    /// mSOL token mint
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub msol_mint: UncheckedAccount<'info>,
+    
+    /// Strategy's mSOL ATA (receives mSOL from staking)
+    /// CHECK: Validated by Marinade program during CPI
+    #[account(mut)]
+    pub strategy_msol_ata: UncheckedAccount<'info>,
+    
+    /// mSOL mint authority
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub msol_mint_authority: UncheckedAccount<'info>,
+    
+    /// Liquidity pool SOL leg PDA
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+    
+    /// Liquidity pool mSOL leg
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+    
+    /// Liquidity pool mSOL leg authority
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_msol_leg_authority: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    
+    // remaining_accounts layout:
+    // [0-5]: Asset mints and ATAs (3 assets × 2 accounts each)
+    //   [0]: BTC mint, [1]: BTC vault ATA
+    //   [2]: ETH mint, [3]: ETH vault ATA  
+    //   [4]: SOL mint, [5]: SOL vault ATA
+    // [6]: MockOracle account (if using MockOracle price source)
+    // [7]: Marinade strategy account (if marinade_strategy is configured)
+}
+
+/// Helper account to pass Marinade strategy account via remaining_accounts
+/// CHECK: This is validated against vault.marinade_strategy
+pub struct MarinadeStrategyAccount;
+
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct WithdrawZap<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's ATA holding vault shares (will be burned)
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// User's position in this vault (created here for holders who predate positions)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    /// Vault's share token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// Token the user is paid in
+    pub output_mint: Box<Account<'info, Mint>>,
+
+    /// Vault's ATA for the output token (the final swap's destination)
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = output_mint,
+        associated_token::authority = vault
+    )]
+    pub vault_output_account: Box<Account<'info, TokenAccount>>,
+
+    /// User's ATA receiving the output token
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = output_mint,
+        associated_token::authority = user
+    )]
+    pub user_output_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    /// Vault's QuoteConfig; pass it to hold a USDC vault's USDC to its peg
+    #[account(
+        seeds = [b"quote_config", vault.key().as_ref()],
+        bump = quote_config.bump
+    )]
+    pub quote_config: Option<Account<'info, QuoteConfig>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateDcaSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init,
+        payer = user,
+        space = DcaSchedule::LEN,
+        seeds = [b"dca", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Token the schedule is funded in (omit for SOL)
+    pub funding_mint: Option<Account<'info, Mint>>,
+
+    /// User's ATA to receive vault shares (created here so the crank can mint)
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    /// User's position in this vault (created here so the crank can update it)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct FundDcaSchedule<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"dca", vault.key().as_ref(), user.key().as_ref()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's funding token account (token schedules only)
+    #[account(mut)]
+    pub user_funding_account: Option<Account<'info, TokenAccount>>,
+
+    /// Schedule's ATA for the funding mint (token schedules only)
+    #[account(mut)]
+    pub schedule_funding_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ExecuteDca<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"dca", vault.key().as_ref(), user.key().as_ref()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    /// Schedule owner, receives the shares
+    /// CHECK: Bound to the schedule by its seeds
+    pub user: UncheckedAccount<'info>,
+
+    /// Anyone may crank a due schedule
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// Schedule's ATA for the funding mint (token schedules only)
+    #[account(mut)]
+    pub schedule_funding_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Vault's ATA for the funding mint, the swap's source (token schedules only)
+    #[account(mut)]
+    pub vault_input_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CancelDcaSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"dca", vault.key().as_ref(), user.key().as_ref()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's token account receiving the refund (token schedules only)
+    #[account(mut)]
+    pub user_funding_account: Option<Account<'info, TokenAccount>>,
+
+    /// Schedule's ATA for the funding mint, closed here (token schedules only)
+    #[account(mut)]
+    pub schedule_funding_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct QueueBatchedDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = DepositBatch::LEN,
+        seeds = [b"deposit_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub batch: Box<Account<'info, DepositBatch>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = BatchDeposit::LEN,
+        seeds = [b"batch_deposit", batch.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub batch_deposit: Account<'info, BatchDeposit>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's ATA to receive vault shares (created here so the crank can pay them)
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// User's position in this vault (created here so the crank can update it)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct ProcessDepositBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, DepositBatch>>,
+
+    /// Holds the batch's shares until claimed
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = share_token_program,
+    )]
+    pub batch_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// Anyone may crank an ended epoch's batch
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct ClaimBatchShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, DepositBatch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"batch_deposit", batch.key().as_ref(), user.key().as_ref()],
+        bump = batch_deposit.bump
+    )]
+    pub batch_deposit: Account<'info, BatchDeposit>,
+
+    /// Depositor, receives the shares and the BatchDeposit's rent
+    /// CHECK: Bound to the deposit by its seeds
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// Anyone may claim for a depositor
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = share_token_program,
+    )]
+    pub batch_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct CancelBatchedDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, DepositBatch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"batch_deposit", batch.key().as_ref(), user.key().as_ref()],
+        bump = batch_deposit.bump
+    )]
+    pub batch_deposit: Account<'info, BatchDeposit>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct QueueBatchedWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = WithdrawalBatch::LEN,
+        seeds = [b"withdrawal_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub batch: Box<Account<'info, WithdrawalBatch>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = BatchWithdrawal::LEN,
+        seeds = [b"batch_withdrawal", batch.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub batch_withdrawal: Account<'info, BatchWithdrawal>,
+
+    /// Holds the batch's shares until it is processed
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = share_token_program,
+    )]
+    pub batch_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct ProcessWithdrawalBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, WithdrawalBatch>>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = share_token_program,
+    )]
+    pub batch_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    /// Anyone may crank an ended epoch's batch
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct ClaimBatchWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, WithdrawalBatch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"batch_withdrawal", batch.key().as_ref(), user.key().as_ref()],
+        bump = batch_withdrawal.bump
+    )]
+    pub batch_withdrawal: Account<'info, BatchWithdrawal>,
+
+    /// Shareholder, receives the SOL and the BatchWithdrawal's rent
+    /// CHECK: Bound to the withdrawal by its seeds
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// Anyone may claim for a shareholder
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, epoch: u64)]
+pub struct CancelBatchedWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal_batch", vault.key().as_ref(), epoch.to_le_bytes().as_ref()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, WithdrawalBatch>>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"batch_withdrawal", batch.key().as_ref(), user.key().as_ref()],
+        bump = batch_withdrawal.bump
+    )]
+    pub batch_withdrawal: Account<'info, BatchWithdrawal>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = share_token_program,
+    )]
+    pub batch_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: Box<InterfaceAccount<'info, ShareAccount>>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Box<InterfaceAccount<'info, ShareMint>>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct WithdrawMultiAsset<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// System-owned account to receive SOL from Marinade (required by Marinade)
+    /// Unstake proceeds are part of the user's payout, so it must be the user
+    /// CHECK: Must be system-owned for Marinade liquid_unstake
+    #[account(mut, address = user.key() @ VaultError::InvalidSolReceiver)]
+    pub sol_receiver: UncheckedAccount<'info>,
+
+    /// User's ATA holding vault shares (will be burned)
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    /// User's position in this vault (created here for holders who predate positions)
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UserPosition::LEN,
+        seeds = [b"user_position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+
+    /// Vault's share token mint
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    // ========== Marinade Strategy Accounts (Optional - only if vault.marinade_strategy is set) ==========
+    
+    /// Marinade Strategy program (for CPI)
+    /// CHECK: This is the marinade_strategy program that wraps Marinade Finance
+    #[account(address = marinade_strategy::ID)]
+    pub marinade_strategy_program: UncheckedAccount<'info>,
+    
+    /// Marinade Finance program (passed through to strategy)
+    /// CHECK: Checked against known Marinade deployments when marinade_strategy is configured
+    pub marinade_program: UncheckedAccount<'info>,
+    
+    /// Marinade state account
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub marinade_state: UncheckedAccount<'info>,
+    
+    /// mSOL token mint
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub msol_mint: UncheckedAccount<'info>,
+    
+    /// Liquidity pool mSOL leg
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+    
+    /// Liquidity pool SOL leg PDA
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+    
+    /// Strategy's mSOL ATA
+    /// CHECK: Validated by strategy program
+    #[account(mut)]
+    pub strategy_msol_ata: UncheckedAccount<'info>,
+    
+    /// Treasury mSOL account
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub treasury_msol_account: UncheckedAccount<'info>,
+
+    /// User's wSOL account, closed to the user to unwrap their share of the
+    /// vault's wrapped SOL (required when the SOL slot is the native mint)
+    #[account(
+        mut,
+        token::mint = anchor_spl::token::spl_token::native_mint::ID,
+        token::authority = user
+    )]
+    pub user_wsol_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    
+    // remaining_accounts layout:
+    // For each asset in vault.assets:
+    //   [i*2]: Asset mint account (UncheckedAccount)
+    //   [i*2+1]: Vault's ATA for that asset (mut, UncheckedAccount)
+    // After assets: MockOracle (if using MockOracle price source)
+    // After oracle: Marinade strategy account (if marinade_strategy is configured)
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Owner, contents and PDA are validated in set_strategy
+    pub strategy_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetLockup<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetExitFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetMockPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetCreationUnit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, participant: Pubkey)]
+pub struct AddAuthorizedParticipant<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuthorizedParticipant::LEN,
+        seeds = [b"authorized_participant", vault.key().as_ref(), participant.as_ref()],
+        bump
+    )]
+    pub authorized_participant: Account<'info, AuthorizedParticipant>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RemoveAuthorizedParticipant<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            b"authorized_participant",
+            vault.key().as_ref(),
+            authorized_participant.participant.as_ref()
+        ],
+        bump = authorized_participant.bump
+    )]
+    pub authorized_participant: Account<'info, AuthorizedParticipant>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateUnits<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"authorized_participant", vault.key().as_ref(), participant.key().as_ref()],
+        bump = authorized_participant.bump
+    )]
+    pub authorized_participant: Account<'info, AuthorizedParticipant>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    /// Participant's ATA to receive vault shares
+    #[account(
+        init_if_needed,
+        payer = participant,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = participant,
+        associated_token::token_program = share_token_program,
+    )]
+    pub participant_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when any basket asset is a Token-2022 mint
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RedeemUnits<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"authorized_participant", vault.key().as_ref(), participant.key().as_ref()],
+        bump = authorized_participant.bump
+    )]
+    pub authorized_participant: Account<'info, AuthorizedParticipant>,
+
+    #[account(mut)]
+    pub participant: Signer<'info>,
+
+    /// Participant's ATA holding the shares to burn
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = participant,
+        associated_token::token_program = share_token_program,
+    )]
+    pub participant_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when any basket asset is a Token-2022 mint
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct BeginWindDown<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RedeemWindDown<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// User's ATA holding the shares to burn
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when any basket asset is a Token-2022 mint
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Vault admin; receives the rent of every closed account
+    #[account(mut, address = vault.load()?.admin)]
+    pub admin: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Registry entry (absent for vaults created before the registry that
+    /// were never registered)
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"vault_registration", vault.key().as_ref()],
+        bump = vault_registration.bump
+    )]
+    pub vault_registration: Option<Account<'info, VaultRegistration>>,
+
+    pub authority: Signer<'info>,
+
+    /// Token program of the share mint (Token-2022 for soulbound vaults)
+    pub share_token_program: Interface<'info, TokenInterface>,
+    pub token_program: Program<'info, Token>,
+    /// Token-2022 program, required when any basket asset is a Token-2022 mint
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetShareMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// Share mint's metadata account (created on first use)
+    /// CHECK: Address checked here; contents owned by the Token Metadata program
+    #[account(mut, address = share_metadata::metadata_address(&vault_token_mint.key()))]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Metaplex Token Metadata program
+    #[account(address = TOKEN_METADATA_PROGRAM_ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    /// Vault authority; pays for the metadata account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct MigrateVault<'info> {
+    /// Vault in any layout version; owner, discriminator and seeds are
+    /// checked in the instruction because it may not decode as a Vault yet
+    /// CHECK: Validated in migrate_vault
+    #[account(mut)]
+    pub vault: UncheckedAccount<'info>,
+
+    /// Pays the rent for the grown account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = VaultRegistry::LEN,
+        seeds = [b"vault_registry"],
+        bump
+    )]
+    pub vault_registry: Account<'info, VaultRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = VaultRegistration::LEN,
+        seeds = [b"vault_registration", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_registration: Account<'info, VaultRegistration>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetVaultMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = VaultMetadata::LEN,
+        seeds = [b"vault_metadata", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_metadata: Account<'info, VaultMetadata>,
+
+    /// Vault authority; pays for the metadata account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateLookupTable<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = VaultLookupTable::LEN,
+        seeds = [b"vault_lookup_table", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_lookup_table: Account<'info, VaultLookupTable>,
+
+    /// Table derived from the vault PDA and recent slot
+    /// CHECK: Address checked against the derivation in create_lookup_table
+    #[account(mut)]
+    pub lookup_table: UncheckedAccount<'info>,
+
+    /// Vault authority; pays for the record and the table
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Address lookup table program
+    #[account(address = LOOKUP_TABLE_PROGRAM_ID)]
+    pub address_lookup_table_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ExtendLookupTable<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_lookup_table", vault.key().as_ref()],
+        bump = vault_lookup_table.bump,
+        has_one = lookup_table @ VaultError::InvalidLookupTable
+    )]
+    pub vault_lookup_table: Account<'info, VaultLookupTable>,
+
+    /// CHECK: Matched against the vault's lookup table record
+    #[account(mut)]
+    pub lookup_table: UncheckedAccount<'info>,
+
+    /// Vault authority; pays rent for the grown table
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Address lookup table program
+    #[account(address = LOOKUP_TABLE_PROGRAM_ID)]
+    pub address_lookup_table_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetMaxSlippage<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetMinDepositShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClearOperationLock<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, computation_offset: u64)]
+pub struct ReleaseStaleRebalance<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Result PDA of the pending computation (rebalancing MXE program)
+    /// CHECK: Seeds constraint; parsed by mxe::queued_result
+    #[account(
+        seeds = [REBALANCE_RESULT_SEED, &computation_offset.to_le_bytes()],
+        bump,
+        seeds::program = REBALANCING_MXE_PROGRAM_ID
+    )]
+    pub rebalance_result: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetGovernance<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Vault admin, or the governance once one is set
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct UpdateComposition<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Vault admin, or the governance once one is set
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ConfigureMarketCapIndex<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = MarketCapIndex::LEN,
+        seeds = [b"market_cap_index", vault.key().as_ref()],
+        bump
+    )]
+    pub market_cap_index: Account<'info, MarketCapIndex>,
+
+    /// Vault admin, or the governance once one is set; pays for the index account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetAssetSupplies<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"market_cap_index", vault.key().as_ref()],
+        bump = market_cap_index.bump
+    )]
+    pub market_cap_index: Account<'info, MarketCapIndex>,
+
+    /// Vault authority or the index's supply authority
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RecomputeWeights<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"market_cap_index", vault.key().as_ref()],
+        bump = market_cap_index.bump
+    )]
+    pub market_cap_index: Account<'info, MarketCapIndex>,
+
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetCompositionSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = VaultSchedule::LEN,
+        seeds = [b"vault_schedule", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_schedule: Box<Account<'info, VaultSchedule>>,
+
+    /// Vault admin, or the governance once one is set; pays for the schedule account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ApplyScheduleStep<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_schedule", vault.key().as_ref()],
+        bump = vault_schedule.bump
+    )]
+    pub vault_schedule: Box<Account<'info, VaultSchedule>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ConfigureAssetOracles<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AssetOracles::LEN,
+        seeds = [b"asset_oracles", vault.key().as_ref()],
+        bump
+    )]
+    pub asset_oracles: Box<Account<'info, AssetOracles>>,
+
+    /// Vault admin, or the governance once one is set; pays for the oracles account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ConfigureLstBasket<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = LstBasket::LEN,
+        seeds = [b"lst_basket", vault.key().as_ref()],
+        bump
+    )]
+    pub lst_basket: Box<Account<'info, LstBasket>>,
+
+    /// Vault admin, or the governance once one is set; pays for the basket account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RefreshLstRates<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"lst_basket", vault.key().as_ref()],
+        bump = lst_basket.bump
+    )]
+    pub lst_basket: Box<Account<'info, LstBasket>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetDepositFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProtocolConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ProtocolConfig::LEN,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAssetWhitelist<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AssetWhitelist::LEN,
+        seeds = [b"asset_whitelist"],
+        bump
+    )]
+    pub asset_whitelist: Account<'info, AssetWhitelist>,
+
+    /// Config authority; pays for the whitelist account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetSwitchboardConfig<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SwitchboardConfig::LEN,
+        seeds = [b"switchboard_config"],
+        bump
+    )]
+    pub switchboard_config: Account<'info, SwitchboardConfig>,
+
+    /// Config authority; pays for the config account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetChainlinkConfig<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ChainlinkConfig::LEN,
+        seeds = [b"chainlink_config"],
+        bump
+    )]
+    pub chainlink_config: Account<'info, ChainlinkConfig>,
+
+    /// Config authority; pays for the config account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostChainlinkReport<'info> {
+    #[account(
+        mut,
+        seeds = [b"chainlink_config"],
+        bump = chainlink_config.bump
+    )]
+    pub chainlink_config: Account<'info, ChainlinkConfig>,
+
+    /// CHECK: Verifier state account, checked by the verifier
+    pub verifier_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verifier's access controller, checked by the verifier
+    pub access_controller: UncheckedAccount<'info>,
+
+    /// CHECK: Verifier config of the DON that signed the report, checked by the verifier
+    pub report_config: UncheckedAccount<'info>,
+
+    /// CHECK: Chainlink Data Streams verifier program
+    #[account(address = chainlink::VERIFIER_PROGRAM_ID)]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Relays the report; must be allowed by the access controller
+    pub relayer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPythLazerConfig<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = PythLazerConfig::LEN,
+        seeds = [b"pyth_lazer_config"],
+        bump
+    )]
+    pub pyth_lazer_config: Account<'info, PythLazerConfig>,
+
+    /// Config authority; pays for the config account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_name: String)]
+pub struct SetVaultTemplate<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = VaultTemplate::LEN,
+        seeds = [b"vault_template", template_name.as_bytes()],
+        bump
+    )]
+    pub vault_template: Box<Account<'info, VaultTemplate>>,
+
+    /// Config authority; pays for the template account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_name: String)]
+pub struct RemoveVaultTemplate<'info> {
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"vault_template", template_name.as_bytes()],
+        bump = vault_template.bump
+    )]
+    pub vault_template: Box<Account<'info, VaultTemplate>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClaimProtocolFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Must be the treasury recorded in protocol_config
+    #[account(mut, address = protocol_config.treasury)]
+    pub treasury: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClaimCreatorFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SweepUnknownToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Mint of the stray tokens; must not be a composition asset
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Vault-owned token account of `mint`, checked in the instruction
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury-owned token account of `mint`, checked in the instruction
+    #[account(mut)]
+    pub treasury_token_account: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CloseAssetAta<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// CHECK: Empty vault-owned token account, checked in the instruction
+    #[account(mut)]
+    pub vault_token_account: UncheckedAccount<'info>,
+
+    /// Vault admin; receives the rent
+    #[account(mut, address = vault.load()?.admin)]
+    pub admin: SystemAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init,
+        payer = referrer,
+        space = ReferralBalance::LEN,
+        seeds = [b"referral", vault.key().as_ref(), referrer.key().as_ref()],
+        bump
+    )]
+    pub referral_balance: Account<'info, ReferralBalance>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClaimReferralFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"referral", vault.key().as_ref(), referrer.key().as_ref()],
+        bump = referral_balance.bump
+    )]
+    pub referral_balance: Account<'info, ReferralBalance>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetStakerFeeShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct InitializeStakePool<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = StakePool::LEN,
+        seeds = [b"stake_pool", vault.key().as_ref()],
+        bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Account<'info, Mint>,
+
+    /// Pool's ATA holding the staked shares
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = stake_pool
+    )]
+    pub pool_shares_ata: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct StakeShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_pool", vault.key().as_ref()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    /// Owner's stake position (created on first stake)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = StakePosition::LEN,
+        seeds = [b"stake_position", stake_pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = owner
+    )]
+    pub user_shares_ata: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = stake_pool
+    )]
+    pub pool_shares_ata: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct UnstakeShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_pool", vault.key().as_ref()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_position", stake_pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_position.bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+
+    #[account(address = vault.load()?.vault_token_mint)]
+    pub vault_token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = owner
+    )]
+    pub user_shares_ata: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = stake_pool
+    )]
+    pub pool_shares_ata: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClaimStakingRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_pool", vault.key().as_ref()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_position", stake_pool.key().as_ref(), owner.key().as_ref()],
+        bump = stake_position.bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DistributeStakingRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_pool", vault.key().as_ref()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Account<'info, StakePool>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RemoveStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct DivestFromStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+
+    /// System-owned pass-through for Marinade's payout (empty between calls)
+    #[account(
+        mut,
+        seeds = [b"sol_buffer", vault.key().as_ref()],
+        bump
+    )]
+    pub sol_buffer: SystemAccount<'info>,
+
+    /// Cached strategy value, reduced by the SOL divested (if synced yet)
+    #[account(
+        mut,
+        seeds = [b"strategy_value", vault.key().as_ref()],
+        bump = strategy_value.bump
+    )]
+    pub strategy_value: Option<Account<'info, StrategyValue>>,
+
+    /// CHECK: Must be the vault's marinade_strategy
+    #[account(mut)]
+    pub strategy_account: UncheckedAccount<'info>,
+
+    /// CHECK: The marinade_strategy program
+    #[account(address = marinade_strategy::ID)]
+    pub marinade_strategy_program: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    pub marinade_program: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub marinade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub msol_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_msol_leg: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
+
+    /// Strategy's mSOL ATA
+    /// CHECK: Validated by marinade_strategy during CPI
+    #[account(mut)]
+    pub strategy_msol_ata: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    #[account(mut)]
+    pub treasury_msol_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SyncStrategyValue<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StrategyValue::LEN,
+        seeds = [b"strategy_value", vault.key().as_ref()],
+        bump
+    )]
+    pub strategy_value: Account<'info, StrategyValue>,
+
+    /// CHECK: Must be the vault's marinade_strategy
+    pub strategy_account: UncheckedAccount<'info>,
+
+    /// CHECK: The marinade_strategy program
+    #[account(address = marinade_strategy::ID)]
+    pub marinade_strategy_program: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    pub marinade_state: UncheckedAccount<'info>,
+
+    /// CHECK: Matched to a known Marinade deployment (marinade::verify) before any CPI
+    pub msol_mint: UncheckedAccount<'info>,
+
+    /// Strategy's mSOL ATA
+    /// CHECK: Validated by marinade_strategy during CPI
+    pub strategy_msol_ata: UncheckedAccount<'info>,
+
+    /// Anyone can crank the value
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetStrategyValueMaxAge<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = StrategyValue::LEN,
+        seeds = [b"strategy_value", vault.key().as_ref()],
+        bump
+    )]
+    pub strategy_value: Account<'info, StrategyValue>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetQuoteCurrency<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = QuoteConfig::LEN,
+        seeds = [b"quote_config", vault.key().as_ref()],
+        bump
+    )]
+    pub quote_config: Account<'info, QuoteConfig>,
+
+    /// USDC mint for QuoteCurrency::Usdc (any mint for Sol)
+    pub quote_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetNavCurrency<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = QuoteConfig::LEN,
+        seeds = [b"quote_config", vault.key().as_ref()],
+        bump
+    )]
+    pub quote_config: Account<'info, QuoteConfig>,
+
+    /// Switchboard feed for the currency's USD price (any account for USD)
+    /// CHECK: Priced through the oracle adapter (in remaining_accounts)
+    /// unless the currency is USD
+    pub fx_feed: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetRebalanceConfig<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RebalanceConfig::LEN,
+        seeds = [b"rebalance_config", vault.key().as_ref()],
+        bump
+    )]
+    pub rebalance_config: Account<'info, RebalanceConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SuggestAlternative<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Successor product holders are pointed at (must be a live vault)
+    pub successor_vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ClearAlternative<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String, successor_name: String)]
+pub struct MigratePosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// User's shares in the vault being left (burned)
+    #[account(
+        mut,
+        associated_token::mint = vault_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = share_token_program,
+    )]
+    pub user_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", successor_vault.load()?.admin.as_ref(), successor_name.as_bytes()],
+        bump = successor_vault.load()?.bump
+    )]
+    pub successor_vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_mint", successor_vault.load()?.admin.as_ref(), successor_name.as_bytes()],
+        bump
+    )]
+    pub successor_token_mint: InterfaceAccount<'info, ShareMint>,
+
+    /// User's ATA receiving successor shares
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = successor_token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = successor_share_token_program,
+    )]
+    pub user_successor_shares_ata: InterfaceAccount<'info, ShareAccount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// CHECK: Only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    /// Token program of the vault's share mint
+    pub share_token_program: Interface<'info, TokenInterface>,
+    /// Token program of the successor's share mint
+    pub successor_share_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(vault_name: String)]
+pub struct Rebalance<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+    
+    /// Admin or authorized rebalancer
+    pub authority: Signer<'info>,
+
+    /// Turnover cap (only if the vault has set one)
+    #[account(
+        seeds = [b"rebalance_config", vault.key().as_ref()],
+        bump = rebalance_config.bump
+    )]
+    pub rebalance_config: Option<Account<'info, RebalanceConfig>>,
+
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts:
+    // [0]: MockOracle account (if price_source = MockOracle)
+    // [..n]: Vault ATAs for each asset (mut)
+}
+
+/// Read-only accounts for simulate_rebalance
+#[derive(Accounts)]
+#[instruction(vault_name: String)]
+pub struct SimulateRebalance<'info> {
+    #[account(
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    /// Turnover cap (only if the vault has set one)
+    #[account(
+        seeds = [b"rebalance_config", vault.key().as_ref()],
+        bump = rebalance_config.bump
+    )]
+    pub rebalance_config: Option<Account<'info, RebalanceConfig>>,
+
+    // remaining_accounts: as for rebalance, without swap backend accounts
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetSwapBackend<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SwapViaOrca<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    pub authority: Signer<'info>,
+
+    /// Vault's account for the pool's token A
+    #[account(mut, token::authority = vault)]
+    pub vault_token_a: Box<Account<'info, TokenAccount>>,
+
+    /// Vault's account for the pool's token B
+    #[account(mut, token::authority = vault)]
+    pub vault_token_b: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Owner and pair checked by the swap engine
+    #[account(mut)]
+    pub whirlpool: UncheckedAccount<'info>,
+
+    /// CHECK: Must match the Whirlpool's token_vault_a
+    #[account(mut)]
+    pub token_vault_a: UncheckedAccount<'info>,
+
+    /// CHECK: Must match the Whirlpool's token_vault_b
+    #[account(mut)]
+    pub token_vault_b: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the Whirlpool program during CPI
+    #[account(mut)]
+    pub tick_array_0: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the Whirlpool program during CPI
+    #[account(mut)]
+    pub tick_array_1: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the Whirlpool program during CPI
+    #[account(mut)]
+    pub tick_array_2: UncheckedAccount<'info>,
+
+    /// CHECK: Whirlpool oracle PDA, validated by the Whirlpool program during CPI
+    #[account(mut)]
+    pub oracle: UncheckedAccount<'info>,
+
+    /// CHECK: Orca Whirlpool program
+    #[account(address = orca::WHIRLPOOL_PROGRAM_ID)]
+    pub whirlpool_program: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetHiddenWeights<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Vault admin (holds the shared key the weights are encrypted under;
+    /// also pays for the computation)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    // ============ Arcium MXE Accounts ============
+
+    /// Arcium MXE rebalancing program
+    /// CHECK: Address constraint
+    #[account(address = REBALANCING_MXE_PROGRAM_ID)]
+    pub arcium_mxe_program: UncheckedAccount<'info>,
+
+    /// Sign PDA account for Arcium
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub sign_pda_account: UncheckedAccount<'info>,
+
+    /// MXE account (Multi-party eXecution Environment)
+    /// CHECK: Derived by Arcium program
+    pub mxe_account: UncheckedAccount<'info>,
+
+    /// Mempool account for queued computations
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub mempool_account: UncheckedAccount<'info>,
+
+    /// Executing pool for active computations
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub executing_pool: UncheckedAccount<'info>,
+
+    /// Computation account (unique per computation offset)
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub computation_account: UncheckedAccount<'info>,
+
+    /// Computation definition account (seal_target_weights circuit)
+    /// CHECK: Derived by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+
+    /// Cluster account (Arcium compute cluster)
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub cluster_account: UncheckedAccount<'info>,
+
+    /// Fee pool account for Arcium fees
+    /// CHECK: Arcium fee pool address
+    #[account(mut)]
+    pub pool_account: UncheckedAccount<'info>,
+
+    /// Clock account for timestamp validation
+    /// CHECK: Arcium clock account
+    pub clock_account: UncheckedAccount<'info>,
+
+    /// Arcium base program
+    /// CHECK: Arcium program ID
+    pub arcium_program: UncheckedAccount<'info>,
+
+    /// Vault's sealed targets, created by the MXE program and filled by its callback
+    /// CHECK: Seeds constraint; the MXE program initializes it
+    #[account(
+        mut,
+        seeds = [SEALED_TARGETS_SEED, vault.key().as_ref()],
+        bump,
+        seeds::program = REBALANCING_MXE_PROGRAM_ID
+    )]
+    pub sealed_targets: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for confidential rebalancing via Arcium MXE
+#[derive(Accounts)]
+#[instruction(vault_name: String, computation_offset: u64)]
+pub struct RebalanceConfidential<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+    
+    /// Admin or authorized rebalancer (also pays for computation)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    // ============ Arcium MXE Accounts ============
+    
+    /// Arcium MXE rebalancing program
+    /// CHECK: Address constraint
+    #[account(address = REBALANCING_MXE_PROGRAM_ID)]
+    pub arcium_mxe_program: UncheckedAccount<'info>,
+    
+    /// Sign PDA account for Arcium
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub sign_pda_account: UncheckedAccount<'info>,
+    
+    /// MXE account (Multi-party eXecution Environment)
+    /// CHECK: Derived by Arcium program
+    pub mxe_account: UncheckedAccount<'info>,
+    
+    /// Mempool account for queued computations
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    /// Executing pool for active computations
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    /// Computation account (unique per computation offset)
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub computation_account: UncheckedAccount<'info>,
+    
+    /// Computation definition account (defines the circuit)
+    /// CHECK: Derived by Arcium program
+    pub comp_def_account: UncheckedAccount<'info>,
+    
+    /// Cluster account (Arcium compute cluster)
+    /// CHECK: Derived by Arcium program
+    #[account(mut)]
+    pub cluster_account: UncheckedAccount<'info>,
+    
+    /// Fee pool account for Arcium fees
+    /// CHECK: Arcium fee pool address
+    #[account(mut)]
+    pub pool_account: UncheckedAccount<'info>,
+    
+    /// Clock account for timestamp validation
+    /// CHECK: Arcium clock account
+    pub clock_account: UncheckedAccount<'info>,
+    
+    /// Arcium base program
+    /// CHECK: Arcium program ID
+    pub arcium_program: UncheckedAccount<'info>,
+
+    /// Result PDA, created by the MXE program and filled by its callback
+    /// CHECK: Seeds constraint; the MXE program initializes it
+    #[account(
+        mut,
+        seeds = [REBALANCE_RESULT_SEED, &computation_offset.to_le_bytes()],
+        bump,
+        seeds::program = REBALANCING_MXE_PROGRAM_ID
+    )]
+    pub rebalance_result: UncheckedAccount<'info>,
+
+    /// Targets sealed to the MXE (only for a hidden-weights vault)
+    /// CHECK: Seeds constraint; parsed by mxe::require_sealed_targets
+    #[account(
+        seeds = [SEALED_TARGETS_SEED, vault.key().as_ref()],
+        bump,
+        seeds::program = REBALANCING_MXE_PROGRAM_ID
+    )]
+    pub sealed_targets: Option<UncheckedAccount<'info>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    
+    // remaining_accounts (hidden-weights vaults only):
+    // [0]: MockOracle account
+    // [1..n]: Vault's ATAs for each asset
 }
 
-// ============================================================================
-// Arcium MXE Data Structures
-// ============================================================================
+/// Accounts for applying a completed Arcium MXE rebalancing result
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(vault_name: String, computation_offset: u64)]
+pub struct ApplyConfidentialRebalance<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
 
-/// Encrypted swap instruction from Arcium MXE
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct EncryptedSwapInstruction {
-    pub from_asset: u8,
-    pub to_asset: u8,
-    pub amount: u64,        // Encrypted in production
-    pub min_output: u64,    // Encrypted in production
-}
+    /// Vault admin
+    pub authority: Signer<'info>,
 
-/// Encrypted rebalancing result from Arcium MXE
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct RebalancingResultEncrypted {
-    pub swap_count: u8,
-    pub encrypted_swaps: Vec<EncryptedSwapInstruction>,
-    pub total_tvl_encrypted: Vec<u8>,  // Encrypted TVL
-    pub drifts_encrypted: Vec<u8>,     // Encrypted drift values
-}
+    /// Result the MXE callback stored for the pending computation
+    /// CHECK: Seeds constraint; owner and contents checked by mxe::completed_result
+    #[account(
+        seeds = [REBALANCE_RESULT_SEED, &computation_offset.to_le_bytes()],
+        bump,
+        seeds::program = REBALANCING_MXE_PROGRAM_ID
+    )]
+    pub rebalance_result: UncheckedAccount<'info>,
 
-// ============================================================================
-// Helper Functions for Rebalancing
-// ============================================================================
+    /// Turnover cap (only if the vault has set one)
+    #[account(
+        seeds = [b"rebalance_config", vault.key().as_ref()],
+        bump = rebalance_config.bump
+    )]
+    pub rebalance_config: Option<Account<'info, RebalanceConfig>>,
 
-/// Calculate USD value of an asset balance
-fn calculate_asset_usd_value(balance: u64, price: i64, mint: Pubkey) -> Result<i64> {
-    // Determine token decimals based on mint
-    let decimals = get_token_decimals(mint)?;
-    
-    // Calculate: (balance * price) / 10^decimals
-    // Both sides are in micro-USD (6 decimals)
-    let balance_i64 = balance as i64;
-    let usd_value = (balance_i64 * price) / 10i64.pow(decimals as u32);
-    
-    Ok(usd_value)
-}
+    pub token_program: Program<'info, Token>,
 
-/// Get token decimals based on mint address
-fn get_token_decimals(_mint: Pubkey) -> Result<u8> {
-    // In production, this would query the mint account
-    // For now, we use standard decimals for devnet testing
-    // BTC: 8, ETH: 9 (simplified from 18), SOL: 9
-    
-    // Default to SOL decimals (9) for all tokens in testing
-    // TODO: Read actual decimals from mint account in production
-    Ok(9)
+    // remaining_accounts:
+    // [0]: MockOracle account
+    // [1..n]: Vault ATAs for each asset (mut)
 }
 
-// ============================================================================
-// Account Validation Structs
-// ============================================================================
-
-/// Accounts for creating a new multi-asset vault
-///
-/// **Architecture Notes:**
-/// - Vault PDA: Derived from [b"vault", admin, name] for multi-vault support
-/// - Space calculation: Dynamic based on name length and asset count
-/// - Share mint: Also a PDA [b"vault_mint", admin, name] for determinism
-/// - Remaining accounts: Used for variable asset list (mints + ATAs)
 #[derive(Accounts)]
-#[instruction(name: String, assets: Vec<AssetConfig>)]
-pub struct CreateVault<'info> {
-    /// The vault account - stores all composition and state
-    /// Uses dynamic space allocation based on name and asset count
+pub struct InitializeMockOracle<'info> {
     #[account(
         init,
-        payer = admin,
-        space = Vault::space(name.len(), assets.len()),
-        seeds = [b"vault", admin.key().as_ref(), name.as_bytes()],
+        payer = authority,
+        space = MockPriceOracle::LEN,
+        seeds = [b"mock_oracle", authority.key().as_ref()],
         bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub mock_oracle: Account<'info, MockPriceOracle>,
 
-    /// Admin who creates and manages the vault
-    /// Pays for account rent and has rebalance permissions
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub authority: Signer<'info>,
 
-    /// SPL token mint for vault shares
-    /// Vault PDA is mint authority (secure share minting)
-    /// 9 decimals for high precision in share calculations
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMockOracle<'info> {
     #[account(
-        init,
-        payer = admin,
-        mint::decimals = 9,
-        mint::authority = vault,
-        seeds = [b"vault_mint", admin.key().as_ref(), name.as_bytes()],
-        bump
+        mut,
+        seeds = [b"mock_oracle", mock_oracle.authority.as_ref()],
+        bump = mock_oracle.bump
     )]
-    pub vault_token_mint: Account<'info, Mint>,
+    pub mock_oracle: Account<'info, MockPriceOracle>,
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-    // remaining_accounts layout (per asset):
-    // [0]: mint (UncheckedAccount) - validated in instruction
-    // [1]: ata (mut, UncheckedAccount) - vault's ATA, validated and created
-    // For N assets: 2*N accounts total
+    /// Needed when `authority` signs as a feeder rather than the owner
+    #[account(
+        seeds = [b"oracle_feeders", mock_oracle.key().as_ref()],
+        bump = oracle_feeders.bump
+    )]
+    pub oracle_feeders: Option<Account<'info, OracleFeeders>>,
+
+    /// Oracle authority or one of its feeders
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(name: String)]
-pub struct DepositMultiAsset<'info> {
+pub struct UpdatePricesBatch<'info> {
     #[account(
         mut,
-        seeds = [b"vault", vault.admin.as_ref(), name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"mock_oracle", mock_oracle.authority.as_ref()],
+        bump = mock_oracle.bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub mock_oracle: Account<'info, MockPriceOracle>,
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Needed when `authority` signs as a feeder rather than the owner
+    #[account(
+        seeds = [b"oracle_feeders", mock_oracle.key().as_ref()],
+        bump = oracle_feeders.bump
+    )]
+    pub oracle_feeders: Option<Account<'info, OracleFeeders>>,
+
+    /// Vault whose basket mints name the markets; read only
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Oracle authority or one of its feeders
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMockOracleSigned<'info> {
+    #[account(
+        mut,
+        seeds = [b"mock_oracle", mock_oracle.authority.as_ref()],
+        bump = mock_oracle.bump
+    )]
+    pub mock_oracle: Account<'info, MockPriceOracle>,
+
+    /// Needed when a feeder rather than the owner signed the prices
+    #[account(
+        seeds = [b"oracle_feeders", mock_oracle.key().as_ref()],
+        bump = oracle_feeders.bump
+    )]
+    pub oracle_feeders: Option<Account<'info, OracleFeeders>>,
+
+    /// CHECK: Instructions sysvar, read for the Ed25519 verification
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOracleFeeders<'info> {
+    #[account(
+        seeds = [b"mock_oracle", authority.key().as_ref()],
+        bump = mock_oracle.bump
+    )]
+    pub mock_oracle: Account<'info, MockPriceOracle>,
 
-    /// User's ATA to receive vault shares
     #[account(
         init_if_needed,
-        payer = user,
-        associated_token::mint = vault_token_mint,
-        associated_token::authority = user
+        payer = authority,
+        space = OracleFeeders::LEN,
+        seeds = [b"oracle_feeders", mock_oracle.key().as_ref()],
+        bump
     )]
-    pub user_shares_ata: Account<'info, TokenAccount>,
+    pub oracle_feeders: Account<'info, OracleFeeders>,
 
-    /// Vault's share token mint
+    /// Oracle authority; pays for the feeders account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(vault_name: String)]
+pub struct RefreshPrices<'info> {
     #[account(
         mut,
-        seeds = [b"vault_mint", vault.admin.as_ref(), name.as_bytes()],
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = PriceCache::LEN,
+        seeds = [b"price_cache", vault.key().as_ref()],
         bump
     )]
-    pub vault_token_mint: Account<'info, Mint>,
+    pub price_cache: Account<'info, PriceCache>,
 
     /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
     /// CHECK: Optional account - only validated when price_source is Switchboard
@@ -1969,109 +14035,51 @@ pub struct DepositMultiAsset<'info> {
     /// CHECK: Optional account - only validated when price_source is Switchboard
     pub sol_quote: UncheckedAccount<'info>,
 
-    // ========== Marinade Strategy Accounts (Optional - only if vault.marinade_strategy is set) ==========
-    
-    /// Marinade Strategy program (for CPI)
-    /// CHECK: This is the marinade_strategy program that wraps Marinade Finance
-    pub marinade_strategy_program: UncheckedAccount<'info>,
-    
-    /// Marinade Finance program (passed through to strategy)
-    /// CHECK: Validated as Marinade program ID when marinade_strategy is configured
-    pub marinade_program: UncheckedAccount<'info>,
-    
-    /// Marinade state account
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub marinade_state: UncheckedAccount<'info>,
-    
-    /// Marinade reserve PDA
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub reserve_pda: UncheckedAccount<'info>,
-    
-    /// mSOL token mint
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub msol_mint: UncheckedAccount<'info>,
-    
-    /// Strategy's mSOL ATA (receives mSOL from staking)
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub strategy_msol_ata: UncheckedAccount<'info>,
-    
-    /// mSOL mint authority
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub msol_mint_authority: UncheckedAccount<'info>,
-    
-    /// Liquidity pool SOL leg PDA
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
-    
-    /// Liquidity pool mSOL leg
-    /// CHECK: Validated by Marinade program during CPI
+    /// Anyone can crank the cache
     #[account(mut)]
-    pub liq_pool_msol_leg: UncheckedAccount<'info>,
-    
-    /// Liquidity pool mSOL leg authority
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub liq_pool_msol_leg_authority: UncheckedAccount<'info>,
+    pub payer: Signer<'info>,
 
-    pub clock: Sysvar<'info, Clock>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-    
-    // remaining_accounts layout:
-    // [0-5]: Asset mints and ATAs (3 assets × 2 accounts each)
-    //   [0]: BTC mint, [1]: BTC vault ATA
-    //   [2]: ETH mint, [3]: ETH vault ATA  
-    //   [4]: SOL mint, [5]: SOL vault ATA
-    // [6]: MockOracle account (if using MockOracle price source)
-    // [7]: Marinade strategy account (if marinade_strategy is configured)
-}
-
-/// Helper account to pass Marinade strategy account via remaining_accounts
-/// CHECK: This is validated against vault.marinade_strategy
-pub struct MarinadeStrategyAccount;
 
+    // remaining_accounts:
+    // [0]: MockOracle account (if price_source = MockOracle)
+}
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(name: String)]
-pub struct WithdrawMultiAsset<'info> {
+#[instruction(vault_name: String)]
+pub struct SnapshotNav<'info> {
     #[account(
         mut,
-        seeds = [b"vault", vault.admin.as_ref(), name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
     )]
-    pub vault: Account<'info, Vault>,
-
-    #[account(mut)]
-    pub user: Signer<'info>,
+    pub vault: AccountLoader<'info, Vault>,
 
-    /// System-owned account to receive SOL from Marinade (required by Marinade)
-    /// CHECK: Must be system-owned for Marinade liquid_unstake
-    #[account(mut)]
-    pub sol_receiver: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = NavHistory::LEN,
+        seeds = [b"nav_history", vault.key().as_ref()],
+        bump
+    )]
+    pub nav_history: Box<Account<'info, NavHistory>>,
 
-    /// User's ATA holding vault shares (will be burned)
     #[account(
-        mut,
-        associated_token::mint = vault_token_mint,
-        associated_token::authority = user
+        init_if_needed,
+        payer = payer,
+        space = TrackingError::LEN,
+        seeds = [b"tracking_error", vault.key().as_ref()],
+        bump
     )]
-    pub user_shares_ata: Account<'info, TokenAccount>,
+    pub tracking_error: Box<Account<'info, TrackingError>>,
 
-    /// Vault's share token mint
+    /// Vault share mint (supply is the total share count)
     #[account(
-        mut,
-        seeds = [b"vault_mint", vault.admin.as_ref(), name.as_bytes()],
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
         bump
     )]
-    pub vault_token_mint: Account<'info, Mint>,
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
 
     /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
     /// CHECK: Optional account - only validated when price_source is Switchboard
@@ -2085,221 +14093,96 @@ pub struct WithdrawMultiAsset<'info> {
     /// CHECK: Optional account - only validated when price_source is Switchboard
     pub sol_quote: UncheckedAccount<'info>,
 
-    // ========== Marinade Strategy Accounts (Optional - only if vault.marinade_strategy is set) ==========
-    
-    /// Marinade Strategy program (for CPI)
-    /// CHECK: This is the marinade_strategy program that wraps Marinade Finance
-    pub marinade_strategy_program: UncheckedAccount<'info>,
-    
-    /// Marinade Finance program (passed through to strategy)
-    /// CHECK: Validated as Marinade program ID when marinade_strategy is configured
-    pub marinade_program: UncheckedAccount<'info>,
-    
-    /// Marinade state account
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub marinade_state: UncheckedAccount<'info>,
-    
-    /// mSOL token mint
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub msol_mint: UncheckedAccount<'info>,
-    
-    /// Liquidity pool mSOL leg
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub liq_pool_msol_leg: UncheckedAccount<'info>,
-    
-    /// Liquidity pool SOL leg PDA
-    /// CHECK: Validated by Marinade program during CPI
-    #[account(mut)]
-    pub liq_pool_sol_leg_pda: UncheckedAccount<'info>,
-    
-    /// Strategy's mSOL ATA
-    /// CHECK: Validated by strategy program
-    #[account(mut)]
-    pub strategy_msol_ata: UncheckedAccount<'info>,
-    
-    /// Treasury mSOL account
-    /// CHECK: Validated by Marinade program during CPI
+    /// Anyone can crank a snapshot
     #[account(mut)]
-    pub treasury_msol_account: UncheckedAccount<'info>,
+    pub payer: Signer<'info>,
 
-    pub clock: Sysvar<'info, Clock>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-    
-    // remaining_accounts layout:
-    // For each asset in vault.assets:
-    //   [i*2]: Asset mint account (UncheckedAccount)
-    //   [i*2+1]: Vault's ATA for that asset (mut, UncheckedAccount)
-    // After assets: MockOracle (if using MockOracle price source)
-    // After oracle: Marinade strategy account (if marinade_strategy is configured)
 }
 
+/// Read-only accounts for get_vault_stats and preview_deposit
 #[derive(Accounts)]
-#[instruction(name: String)]
-pub struct SetStrategy<'info> {
+#[instruction(vault_name: String)]
+pub struct VaultView<'info> {
     #[account(
-        mut,
-        seeds = [b"vault", vault.admin.as_ref(), name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub vault: AccountLoader<'info, Vault>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
-}
-
-#[derive(Accounts)]
-#[instruction(name: String)]
-pub struct RemoveStrategy<'info> {
+    /// Vault share mint (supply is the total share count)
     #[account(
-        mut,
-        seeds = [b"vault", vault.admin.as_ref(), name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
-}
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
 
-#[derive(Accounts)]
-#[instruction(vault_name: String)]
-pub struct Rebalance<'info> {
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
+
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
+
+    /// Vault's QuoteConfig, for stats in its quote currency
     #[account(
-        mut,
-        seeds = [b"vault", vault.admin.as_ref(), vault_name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"quote_config", vault.key().as_ref()],
+        bump = quote_config.bump
     )]
-    pub vault: Account<'info, Vault>,
-    
-    /// Admin or authorized rebalancer
-    pub authority: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    
-    // remaining_accounts:
-    // [0]: MockOracle account (if price_source = MockOracle)
-    // [1..n]: Vault ATAs for each asset (mut)
+    pub quote_config: Option<Account<'info, QuoteConfig>>,
+
+    /// FX feed of the QuoteConfig's NAV currency, for stats in it
+    /// CHECK: Must be quote_config.fx_feed; checked in get_vault_stats
+    pub fx_feed: Option<UncheckedAccount<'info>>,
 }
 
-/// Accounts for confidential rebalancing via Arcium MXE
+/// Read-only accounts for preview_withdraw
 #[derive(Accounts)]
 #[instruction(vault_name: String)]
-pub struct RebalanceConfidential<'info> {
+pub struct PreviewWithdraw<'info> {
     #[account(
-        mut,
-        seeds = [b"vault", vault.admin.as_ref(), vault_name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"vault", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
+        bump = vault.load()?.bump
     )]
-    pub vault: Account<'info, Vault>,
-    
-    /// Admin or authorized rebalancer (also pays for computation)
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    // ============ Arcium MXE Accounts ============
-    
-    /// Arcium MXE rebalancing program
-    /// CHECK: Program ID verified at call site
-    pub arcium_mxe_program: UncheckedAccount<'info>,
-    
-    /// Sign PDA account for Arcium
-    /// CHECK: Derived by Arcium program
-    #[account(mut)]
-    pub sign_pda_account: UncheckedAccount<'info>,
-    
-    /// MXE account (Multi-party eXecution Environment)
-    /// CHECK: Derived by Arcium program
-    pub mxe_account: UncheckedAccount<'info>,
-    
-    /// Mempool account for queued computations
-    /// CHECK: Derived by Arcium program
-    #[account(mut)]
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    /// Executing pool for active computations
-    /// CHECK: Derived by Arcium program
-    #[account(mut)]
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    /// Computation account (unique per computation offset)
-    /// CHECK: Derived by Arcium program
-    #[account(mut)]
-    pub computation_account: UncheckedAccount<'info>,
-    
-    /// Computation definition account (defines the circuit)
-    /// CHECK: Derived by Arcium program
-    pub comp_def_account: UncheckedAccount<'info>,
-    
-    /// Cluster account (Arcium compute cluster)
-    /// CHECK: Derived by Arcium program
-    #[account(mut)]
-    pub cluster_account: UncheckedAccount<'info>,
-    
-    /// Fee pool account for Arcium fees
-    /// CHECK: Arcium fee pool address
-    #[account(mut)]
-    pub pool_account: UncheckedAccount<'info>,
-    
-    /// Clock account for timestamp validation
-    /// CHECK: Arcium clock account
-    pub clock_account: UncheckedAccount<'info>,
-    
-    /// Arcium base program
-    /// CHECK: Arcium program ID
-    pub arcium_program: UncheckedAccount<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    
-    // remaining_accounts:
-    // [0]: MockOracle account
-    // [1]: Arcium cluster account (for verification)
-    // [2..n]: Vault's ATAs for each asset (mut)
-}
+    pub vault: AccountLoader<'info, Vault>,
 
-#[derive(Accounts)]
-pub struct InitializeMockOracle<'info> {
+    /// Vault share mint (supply is the total share count)
     #[account(
-        init,
-        payer = authority,
-        space = MockPriceOracle::LEN,
-        seeds = [b"mock_oracle", authority.key().as_ref()],
+        seeds = [b"vault_mint", vault.load()?.admin.as_ref(), vault_name.as_bytes()],
         bump
     )]
-    pub mock_oracle: Account<'info, MockPriceOracle>,
+    pub vault_token_mint: InterfaceAccount<'info, ShareMint>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    /// Switchboard Oracle Quote for BTC/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub btc_quote: UncheckedAccount<'info>,
 
-    pub system_program: Program<'info, System>,
-}
+    /// Switchboard Oracle Quote for ETH/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub eth_quote: UncheckedAccount<'info>,
 
-#[derive(Accounts)]
-pub struct UpdateMockOracle<'info> {
-    #[account(
-        mut,
-        seeds = [b"mock_oracle", mock_oracle.authority.as_ref()],
-        bump = mock_oracle.bump
-    )]
-    pub mock_oracle: Account<'info, MockPriceOracle>,
+    /// Switchboard Oracle Quote for SOL/USD (only used when price_source = Switchboard)
+    /// CHECK: Optional account - only validated when price_source is Switchboard
+    pub sol_quote: UncheckedAccount<'info>,
 
-    pub authority: Signer<'info>,
+    /// Redeeming holder's position, for the exit fee (omit to preview without it)
+    #[account(constraint = user_position.vault == vault.key() @ VaultError::Unauthorized)]
+    pub user_position: Option<Account<'info, UserPosition>>,
 }
 
 // ============================================================================
-// EPHEMERAL ROLLUPS CONTEXTS (TEMPORARILY DISABLED)
+// EPHEMERAL ROLLUPS CONTEXTS
 // ============================================================================
-// Disabled pending ER SDK compatibility with Anchor 0.31.1+
+// Only built with the `ephemeral-rollups` feature (default); see er.rs
 
 /// Context for delegating mock oracle to Ephemeral Rollup
 /// Uses #[delegate] macro from ephemeral-rollups-sdk
+#[cfg(feature = "ephemeral-rollups")]
 #[delegate]
 #[derive(Accounts)]
 pub struct DelegateMockOracleCtx<'info> {
@@ -2309,8 +14192,11 @@ pub struct DelegateMockOracleCtx<'info> {
     /// CHECK: The pda to delegate
     #[account(mut, del)]
     pub pda: AccountInfo<'info>,
-}/// Context for committing mock oracle state to L1 (without undelegating)
+}
+
+/// Context for committing mock oracle state to L1 (without undelegating)
 /// Uses #[commit] macro from ephemeral-rollups-sdk
+#[cfg(feature = "ephemeral-rollups")]
 #[commit]
 #[derive(Accounts)]
 pub struct CommitMockOracle<'info> {
@@ -2326,8 +14212,43 @@ pub struct CommitMockOracle<'info> {
     pub mock_oracle: Account<'info, MockPriceOracle>,
 }
 
+/// Context for the permissionless ER commit crank
+#[cfg(feature = "ephemeral-rollups")]
+#[commit]
+#[derive(Accounts)]
+pub struct CrankMockOracleCommit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The mock oracle account to commit
+    #[account(
+        mut,
+        seeds = [b"mock_oracle", mock_oracle.authority.as_ref()],
+        bump = mock_oracle.bump
+    )]
+    pub mock_oracle: Account<'info, MockPriceOracle>,
+}
+
+/// Context for undelegating an expired ER session; anyone may pay
+#[cfg(feature = "ephemeral-rollups")]
+#[commit]
+#[derive(Accounts)]
+pub struct ForceUndelegateMockOracle<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The mock oracle account to undelegate
+    #[account(
+        mut,
+        seeds = [b"mock_oracle", mock_oracle.authority.as_ref()],
+        bump = mock_oracle.bump
+    )]
+    pub mock_oracle: Account<'info, MockPriceOracle>,
+}
+
 /// Context for undelegating mock oracle from Ephemeral Rollup back to L1
 /// Uses #[commit] macro to handle commit + undelegate operation
+#[cfg(feature = "ephemeral-rollups")]
 #[commit]
 #[derive(Accounts)]
 pub struct UndelegateMockOracle<'info> {
@@ -2343,15 +14264,78 @@ pub struct UndelegateMockOracle<'info> {
     pub mock_oracle: Account<'info, MockPriceOracle>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct SetGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = VaultGuardian::LEN,
+        seeds = [b"vault_guardian", vault.key().as_ref()],
+        bump
+    )]
+    pub vault_guardian: Account<'info, VaultGuardian>,
+
+    /// Admin, or governance (see require_governed_authority)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct GuardianPause<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    #[account(
+        seeds = [b"vault_guardian", vault.key().as_ref()],
+        bump = vault_guardian.bump
+    )]
+    pub vault_guardian: Account<'info, VaultGuardian>,
+
+    pub guardian: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct GuardianResume<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
+    )]
+    pub vault: AccountLoader<'info, Vault>,
+
+    /// Admin, or governance (see require_governed_authority)
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(name: String)]
 pub struct SetPriceSource<'info> {
     #[account(
         mut,
-        seeds = [b"vault", vault.admin.as_ref(), name.as_bytes()],
-        bump = vault.bump
+        seeds = [b"vault", vault.load()?.admin.as_ref(), name.as_bytes()],
+        bump = vault.load()?.bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub vault: AccountLoader<'info, Vault>,
 
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -2401,4 +14385,174 @@ pub enum VaultError {
     AssetNotFound,
     #[msg("Insufficient balance for rebalance")]
     InsufficientBalance,
+    #[msg("Vault is soft-closed and no longer accepts deposits")]
+    VaultSoftClosed,
+    #[msg("Successor vault does not match the vault's suggested alternative")]
+    InvalidSuccessor,
+    #[msg("Strategy must be removed before positions can migrate")]
+    StrategyStillActive,
+    #[msg("Confidential rebalance result does not match the pending computation or vault state")]
+    InvalidConfidentialResult,
+    #[msg("Encrypted portfolio must contain exactly 13 ciphertexts")]
+    InvalidEncryptedInput,
+    #[msg("Invalid price feed configuration")]
+    InvalidPriceFeeds,
+    #[msg("Price feeds diverge more than the allowed threshold")]
+    PriceDivergence,
+    #[msg("Vault is restricted after an extreme price move")]
+    VaultRestricted,
+    #[msg("Shares are still in their lockup period")]
+    SharesLocked,
+    #[msg("Fee exceeds the allowed maximum")]
+    InvalidFee,
+    #[msg("A NAV snapshot was already taken this interval")]
+    SnapshotTooSoon,
+    #[msg("Referrer is not registered for this vault")]
+    InvalidReferral,
+    #[msg("Vault is in governance mode: only the governance can execute this")]
+    GovernanceOnly,
+    #[msg("No shares are staked in this pool")]
+    NoStakers,
+    #[msg("Vault is not configured for this swap backend")]
+    SwapBackendDisabled,
+    #[msg("Pair is not supported by this swap backend")]
+    UnsupportedSwapPair,
+    #[msg("Whirlpool accounts do not match the pool")]
+    InvalidWhirlpool,
+    #[msg("Swap output is below the minimum amount out")]
+    SlippageExceeded,
+    #[msg("Swap backend is not available on-chain yet")]
+    SwapBackendUnavailable,
+    #[msg("Slippage tolerance exceeds the allowed maximum")]
+    InvalidSlippage,
+    #[msg("Not available while the vault has a strategy set")]
+    StrategyActive,
+    #[msg("Invalid DCA schedule configuration or funding accounts")]
+    InvalidDcaSchedule,
+    #[msg("DCA schedule is not due yet or has no funds left")]
+    DcaNotDue,
+    #[msg("Batched deposits and withdrawals go into the current epoch's batch")]
+    InvalidBatchEpoch,
+    #[msg("Batch is empty, already processed, or its epoch hasn't ended")]
+    BatchNotReady,
+    #[msg("Batch was already processed; claim from it instead")]
+    BatchAlreadyProcessed,
+    #[msg("Composition assets and wrapped SOL can't be swept")]
+    CompositionAssetSweep,
+    #[msg("Token account still holds tokens or belongs to a composition asset")]
+    AssetAccountInUse,
+    #[msg("Creation units need a unit size and an existing basket")]
+    CreationUnitUnavailable,
+    #[msg("Share name must be 1-32 bytes, symbol 1-10 bytes and URI at most 200 bytes")]
+    InvalidShareMetadata,
+    #[msg("Description, icon URI or category too long, or risk level outside 1-5")]
+    InvalidVaultMetadata,
+    #[msg("Vault is winding down")]
+    VaultWindingDown,
+    #[msg("Vault is not winding down")]
+    NotWindingDown,
+    #[msg("Wind-down grace period is shorter than the minimum")]
+    InvalidGracePeriod,
+    #[msg("Grace period has not ended, or shares or fees are still outstanding")]
+    WindDownIncomplete,
+    #[msg("Vault is already stored in the current layout")]
+    VaultUpToDate,
+    #[msg("User wSOL account is required to unwrap the vault's wrapped SOL")]
+    WsolAccountRequired,
+    #[msg("Not available while the vault holds its SOL leg as wrapped SOL")]
+    WrappedSolUnsupported,
+    #[msg("Deposit ticket has expired")]
+    DepositTicketExpired,
+    #[msg("Only the depositor can close a deposit ticket before it expires")]
+    DepositTicketActive,
+    #[msg("Lookup table does not match the vault's, or too many addresses in one extension")]
+    InvalidLookupTable,
+    #[msg("Lookup table cannot hold more addresses")]
+    LookupTableFull,
+    #[msg("Weights are set by the vault's market-cap index")]
+    MarketCapWeighted,
+    #[msg("Market-cap index is disabled, or its interval, change bound or supplies are out of range")]
+    InvalidIndexConfig,
+    #[msg("Asset supplies have not been posted for the current composition")]
+    SuppliesNotPosted,
+    #[msg("Weights were recomputed too recently")]
+    RecomputeTooSoon,
+    #[msg("Schedule has too many steps, steps out of order, or a step is not a valid composition")]
+    InvalidSchedule,
+    #[msg("No schedule step is due")]
+    ScheduleStepNotDue,
+    #[msg("Pool account does not back the asset's mint, or the SOL slot is not LstKind::Sol")]
+    InvalidLstPool,
+    #[msg("LST index prices need the vault's LstBasket")]
+    LstBasketRequired,
+    #[msg("LST exchange rates are stale; run refresh_lst_rates")]
+    StaleLstRates,
+    #[msg("BTC/ETH mint is neither the canonical Portal mint nor an approved alternate")]
+    UnapprovedMint,
+    #[msg("Vault has a strategy but its StrategyValue account was not passed")]
+    StrategyValueRequired,
+    #[msg("Strategy value is older than its max age; crank sync_strategy_value")]
+    StaleStrategyValue,
+    #[msg("Marinade unstake proceeds must go to the withdrawing user")]
+    InvalidSolReceiver,
+    #[msg("Account does not belong to a known Marinade deployment")]
+    InvalidMarinadeAccount,
+    #[msg("Strategy is not an approved strategy account bound to this vault")]
+    InvalidStrategyAccount,
+    #[msg("Asset mint appears more than once")]
+    DuplicateAsset,
+    #[msg("Vault prices per asset: pass its AssetOracles and feed accounts")]
+    AssetOraclesRequired,
+    #[msg("Deposit mints no shares or fewer than the vault minimum")]
+    MintedZeroShares,
+    #[msg("Vault is locked by another operation in progress")]
+    VaultBusy,
+    #[msg("Turnover cap must be at most 10000 bps of TVL")]
+    InvalidTurnoverCap,
+    #[msg("More dust thresholds than vault assets")]
+    InvalidDustThresholds,
+    #[msg("Fee tiers must be at most 4, ascending by TVL, with shares of at most 10000 bps")]
+    InvalidFeeTiers,
+    #[msg("NAV currency must be a three-letter uppercase ISO 4217 code")]
+    InvalidNavCurrency,
+    #[msg("Vault's NAV currency needs its FX feed account")]
+    FxFeedRequired,
+    #[msg("Vault shares are soulbound and can't leave the holder's account")]
+    SoulboundShares,
+    #[msg("Vault is already finalized; assets are added between init_vault and finalize_vault")]
+    VaultFinalized,
+    #[msg("Oracle feeders must be distinct and at most 8")]
+    InvalidOracleFeeders,
+    #[msg("Expected an Ed25519 instruction verifying one signature over this price update, just before it")]
+    InvalidPriceSignature,
+    #[msg("Price source needs the oracle adapter program in remaining accounts")]
+    OracleAdapterRequired,
+    #[msg("Vault's target weights are hidden; it rebalances through rebalance_confidential only")]
+    WeightsHidden,
+    #[msg("Hidden-weights vault has no completed seal of target weights summing to 100")]
+    TargetsNotSealed,
+    #[msg("Deposit commitment missing, unexpected, or not opened by this amount and salt")]
+    InvalidDepositCommitment,
+    #[msg("A committed deposit can only be revealed in a later slot")]
+    DepositRevealTooEarly,
+    #[msg("ER commit frequency is below MIN_ER_COMMIT_FREQUENCY_MS")]
+    InvalidCommitFrequency,
+    #[msg("Mock oracle was committed to L1 less than its commit frequency ago")]
+    OracleCommitNotDue,
+    #[msg("ER session duration must be between 1 second and MAX_ER_SESSION_SECS")]
+    InvalidSessionDuration,
+    #[msg("Mock oracle's ER session has not reached its max session duration")]
+    ErSessionActive,
+    #[msg("Rebalancing is paused by the vault's guardian")]
+    RebalancingPaused,
+    #[msg("This guardian may only pause deposits")]
+    GuardianCannotPauseRebalancing,
+    #[msg("Deposits are paused by the vault's guardian")]
+    DepositsPaused,
+    #[msg("The confidential rebalance has not timed out yet")]
+    LockNotStale,
+    #[msg("Deposit commitment is past DEPOSIT_COMMITMENT_TTL_SLOTS and can only be cancelled")]
+    DepositCommitmentExpired,
+    #[msg("Deposit buys fewer shares than the minimum requested")]
+    SharesBelowMinimum,
 }
@@ -0,0 +1,105 @@
+//! Per-vault address lookup tables
+//!
+//! A deposit or withdrawal passes every asset's mint, ATA, price feeds and
+//! swap accounts, which overflows a legacy transaction past a few assets.
+//! Each vault can own one lookup table (the vault PDA is its authority) that
+//! clients reference from v0 transactions. The table's addresses start after
+//! a fixed-size header and are read directly here.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::address_lookup_table::instruction as alt_instruction;
+use anchor_lang::solana_program::address_lookup_table::state::{
+    LOOKUP_TABLE_MAX_ADDRESSES, LOOKUP_TABLE_META_SIZE,
+};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::VaultError;
+
+pub use anchor_lang::solana_program::address_lookup_table::program::ID as LOOKUP_TABLE_PROGRAM_ID;
+
+/// Most addresses added by one extend_lookup_table call, so the
+/// instruction fits in a transaction
+pub const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+
+/// Addresses currently stored in a lookup table account
+pub fn table_addresses(table: &AccountInfo) -> Result<Vec<Pubkey>> {
+    require_keys_eq!(*table.owner, LOOKUP_TABLE_PROGRAM_ID, VaultError::InvalidLookupTable);
+    let data = table.try_borrow_data()?;
+    let keys = data.get(LOOKUP_TABLE_META_SIZE..).ok_or(VaultError::InvalidLookupTable)?;
+    require!(keys.len() % 32 == 0, VaultError::InvalidLookupTable);
+    Ok(keys
+        .chunks_exact(32)
+        .map(|key| Pubkey::try_from(key).expect("32-byte chunk"))
+        .collect())
+}
+
+/// Accounts shared by the create and extend instructions
+pub struct LookupTableAccounts<'a, 'info> {
+    pub lookup_table_program: &'a AccountInfo<'info>,
+    pub lookup_table: &'a AccountInfo<'info>,
+    /// Vault PDA: table authority
+    pub authority: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+}
+
+impl<'info> LookupTableAccounts<'_, 'info> {
+    fn infos(&self) -> [AccountInfo<'info>; 5] {
+        [
+            self.lookup_table.clone(),
+            self.authority.clone(),
+            self.payer.clone(),
+            self.system_program.clone(),
+            self.lookup_table_program.clone(),
+        ]
+    }
+}
+
+/// Create the table derived from the vault PDA and `recent_slot`
+pub fn create(accounts: &LookupTableAccounts, recent_slot: u64, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+    // The signed variant works whether or not the runtime still requires the
+    // authority to sign; the vault PDA signs through its seeds either way
+    #[allow(deprecated)]
+    let (ix, address) =
+        alt_instruction::create_lookup_table_signed(accounts.authority.key(), accounts.payer.key(), recent_slot);
+    require_keys_eq!(address, accounts.lookup_table.key(), VaultError::InvalidLookupTable);
+
+    invoke_signed(&ix, &accounts.infos(), signer_seeds)?;
+    Ok(())
+}
+
+/// Append the `addresses` not already in the table; returns how many were
+/// added and the table's new length
+pub fn extend(
+    accounts: &LookupTableAccounts,
+    addresses: &[Pubkey],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<(usize, usize)> {
+    require!(addresses.len() <= MAX_ADDRESSES_PER_EXTEND, VaultError::InvalidLookupTable);
+
+    let existing = table_addresses(accounts.lookup_table)?;
+    let mut new_addresses: Vec<Pubkey> = Vec::with_capacity(addresses.len());
+    for address in addresses {
+        if !existing.contains(address) && !new_addresses.contains(address) {
+            new_addresses.push(*address);
+        }
+    }
+    if new_addresses.is_empty() {
+        return Ok((0, existing.len()));
+    }
+    require!(
+        existing.len() + new_addresses.len() <= LOOKUP_TABLE_MAX_ADDRESSES,
+        VaultError::LookupTableFull
+    );
+
+    let added = new_addresses.len();
+    let total = existing.len() + added;
+    let ix = alt_instruction::extend_lookup_table(
+        accounts.lookup_table.key(),
+        accounts.authority.key(),
+        Some(accounts.payer.key()),
+        new_addresses,
+    );
+    invoke_signed(&ix, &accounts.infos(), signer_seeds)?;
+    Ok((added, total))
+}
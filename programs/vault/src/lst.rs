@@ -0,0 +1,228 @@
+//! Liquid staking token rates
+//!
+//! An LST index (mSOL, jitoSOL, bSOL, ...) is valued by exchange rate rather
+//! than by a USD feed per token: each LST is worth its pool's lamports per
+//! token × the SOL price. refresh_lst_rates reads the rates off the pools'
+//! own state into the vault's LstBasket, and `apply_rates` turns the SOL
+//! feed into per-asset prices wherever the vault reads prices.
+//!
+//! The SOL slot (index 2) stays native SOL at rate 1:1.
+
+use anchor_lang::prelude::*;
+
+use crate::prices::{NormalizedPrice, PriceAccounts};
+use crate::state::{LstBasket, LstKind, LstPool, Vault};
+use crate::VaultError;
+
+/// Marinade liquid staking program
+pub const MARINADE_PROGRAM_ID: Pubkey = pubkey!("MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD");
+
+/// SPL stake pool program and Sanctum's deployments of it
+pub const STAKE_POOL_PROGRAM_IDS: [Pubkey; 3] = [
+    pubkey!("SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy"),
+    pubkey!("SP12tWFxD9oJsVWNavTTBZvMbA6gkAmxtVgxdqvyvhY"),
+    pubkey!("SPMBzsVUuoHA4Jm6KunbsotaahvVikZs1JyTW6iJvbn"),
+];
+
+/// Rates are lamports per LST token scaled by RATE_SCALE (1:1 = RATE_SCALE)
+pub const RATE_SCALE: u64 = 1_000_000_000;
+
+/// Decimals of every LST (the same as SOL)
+pub const LST_DECIMALS: u8 = 9;
+
+/// Maximum age of LstBasket rates accepted for pricing
+/// Pool rates only move at epoch boundaries (~2 days).
+pub const MAX_LST_RATE_AGE_SECS: i64 = 86_400;
+
+// Marinade State layout (after the 8-byte discriminator); msol_price is
+// lamports per mSOL scaled by 2^32
+const MARINADE_MSOL_MINT_OFFSET: usize = 8;
+const MARINADE_MSOL_PRICE_OFFSET: usize = 512;
+const MARINADE_PRICE_DENOMINATOR: u128 = 1 << 32;
+
+// SPL StakePool layout (no discriminator; account_type 1 = StakePool)
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+const STAKE_POOL_MINT_OFFSET: usize = 162;
+const STAKE_POOL_TOTAL_LAMPORTS_OFFSET: usize = 258;
+const STAKE_POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+
+/// Exchange rate of `mint` read from its pool account
+pub fn read_rate(kind: LstKind, pool: &AccountInfo, mint: &Pubkey) -> Result<u64> {
+    match kind {
+        LstKind::Sol => Ok(RATE_SCALE),
+        LstKind::Marinade => marinade_rate(pool, mint),
+        LstKind::SplStakePool => stake_pool_rate(pool, mint),
+    }
+}
+
+/// Exchange rate of each asset of `vault`, read from the pool accounts in
+/// `pool_accounts` (one per asset; any account for a Sol slot)
+pub fn read_rates(vault: &Vault, pools: &[LstPool], pool_accounts: &[AccountInfo]) -> Result<Vec<u64>> {
+    require!(
+        pools.len() == vault.assets().len() && pool_accounts.len() == pools.len(),
+        VaultError::InvalidRemainingAccounts
+    );
+    pools
+        .iter()
+        .zip(pool_accounts)
+        .zip(vault.assets().iter())
+        .map(|((pool, account), asset)| {
+            require!(
+                pool.kind == LstKind::Sol || account.key() == pool.pool,
+                VaultError::InvalidLstPool
+            );
+            read_rate(pool.kind, account, &asset.mint)
+        })
+        .collect()
+}
+
+/// Rate of a stake pool holding `total_lamports` against `token_supply`
+/// pool tokens; an empty pool trades 1:1
+pub fn pool_rate(total_lamports: u64, token_supply: u64) -> Option<u64> {
+    if token_supply == 0 {
+        return Some(RATE_SCALE);
+    }
+    u64::try_from(total_lamports as u128 * RATE_SCALE as u128 / token_supply as u128).ok()
+}
+
+/// USD price of an LST worth `rate` lamports per token at `sol_price`
+pub fn lst_price(sol_price: &NormalizedPrice, rate: u64) -> Result<NormalizedPrice> {
+    let price_usd = i64::try_from(sol_price.price_usd as i128 * rate as i128 / RATE_SCALE as i128)
+        .map_err(|_| VaultError::MathOverflow)?;
+    Ok(NormalizedPrice::from_micro_usd(price_usd))
+}
+
+/// Whether `account` is an LstBasket owned by this program
+pub fn is_lst_basket(account: &AccountInfo) -> bool {
+    account.owner == &crate::ID
+        && account
+            .try_borrow_data()
+            .map(|data| data.starts_with(LstBasket::DISCRIMINATOR))
+            .unwrap_or(false)
+}
+
+/// Replace the BTC/ETH slots of freshly read feed prices with LST prices
+///
+/// Vaults that are not LST indexes get `prices` back unchanged. LST indexes
+/// need their LstBasket among the price accounts, with rates no older than
+/// MAX_LST_RATE_AGE_SECS.
+pub fn apply_rates(
+    vault: &Vault,
+    accounts: &PriceAccounts,
+    clock: &Clock,
+    prices: [NormalizedPrice; 3],
+) -> Result<[NormalizedPrice; 3]> {
+    if !vault.lst_index() {
+        return Ok(prices);
+    }
+
+    let basket_account = accounts
+        .remaining_accounts
+        .iter()
+        .find(|acc| is_lst_basket(acc))
+        .ok_or(VaultError::LstBasketRequired)?;
+    let basket_data = basket_account.try_borrow_data()?;
+    let basket = LstBasket::try_deserialize(&mut &basket_data[..])?;
+    require!(basket.vault == accounts.vault, VaultError::LstBasketRequired);
+    require!(
+        clock.unix_timestamp - basket.rates_updated_at <= MAX_LST_RATE_AGE_SECS,
+        VaultError::StaleLstRates
+    );
+
+    let sol_price = prices[2];
+    let mut lst_prices = prices;
+    for (price, rate) in lst_prices.iter_mut().zip(&basket.rates) {
+        *price = lst_price(&sol_price, *rate)?;
+    }
+    Ok(lst_prices)
+}
+
+/// mSOL rate from the Marinade State (or the localnet mock_marinade state)
+fn marinade_rate(state: &AccountInfo, mint: &Pubkey) -> Result<u64> {
+    let data = state.try_borrow_data()?;
+
+    if state.owner == &mock_marinade::ID {
+        let mock = mock_marinade::MarinadeState::try_deserialize(&mut &data[..])?;
+        require!(mock.msol_mint == *mint, VaultError::InvalidLstPool);
+        // The mock scales msol_price by 10^9, the same as RATE_SCALE
+        return Ok(mock.msol_price);
+    }
+
+    require!(state.owner == &MARINADE_PROGRAM_ID, VaultError::InvalidLstPool);
+    require!(
+        data.len() >= MARINADE_MSOL_PRICE_OFFSET + 8,
+        VaultError::InvalidLstPool
+    );
+    require!(
+        data[MARINADE_MSOL_MINT_OFFSET..MARINADE_MSOL_MINT_OFFSET + 32] == mint.to_bytes(),
+        VaultError::InvalidLstPool
+    );
+    let msol_price = read_u64(&data, MARINADE_MSOL_PRICE_OFFSET);
+    u64::try_from(msol_price as u128 * RATE_SCALE as u128 / MARINADE_PRICE_DENOMINATOR)
+        .map_err(|_| VaultError::MathOverflow.into())
+}
+
+/// Pool token rate from an SPL StakePool account
+fn stake_pool_rate(pool: &AccountInfo, mint: &Pubkey) -> Result<u64> {
+    require!(
+        STAKE_POOL_PROGRAM_IDS.contains(pool.owner),
+        VaultError::InvalidLstPool
+    );
+    let data = pool.try_borrow_data()?;
+    require!(
+        data.len() >= STAKE_POOL_TOKEN_SUPPLY_OFFSET + 8
+            && data[0] == STAKE_POOL_ACCOUNT_TYPE
+            && data[STAKE_POOL_MINT_OFFSET..STAKE_POOL_MINT_OFFSET + 32] == mint.to_bytes(),
+        VaultError::InvalidLstPool
+    );
+    pool_rate(
+        read_u64(&data, STAKE_POOL_TOTAL_LAMPORTS_OFFSET),
+        read_u64(&data, STAKE_POOL_TOKEN_SUPPLY_OFFSET),
+    )
+    .ok_or(VaultError::MathOverflow.into())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_rate() {
+        // 1.1 SOL backing each token
+        assert_eq!(pool_rate(1_100, 1_000), Some(1_100_000_000));
+        assert_eq!(pool_rate(0, 0), Some(RATE_SCALE));
+        assert_eq!(pool_rate(u64::MAX, 1), None);
+    }
+
+    #[test]
+    fn test_lst_price() {
+        // SOL at $150 and an LST at 1.25 SOL -> $187.50
+        let sol = NormalizedPrice::from_micro_usd(150_000_000);
+        assert_eq!(lst_price(&sol, 1_250_000_000).unwrap().price_usd, 187_500_000);
+        assert_eq!(lst_price(&sol, RATE_SCALE).unwrap().price_usd, sol.price_usd);
+    }
+
+    #[test]
+    fn test_stake_pool_rate() {
+        let mint = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let owner = STAKE_POOL_PROGRAM_IDS[0];
+        let mut lamports = 0;
+        let mut data = vec![0u8; 300];
+        data[0] = STAKE_POOL_ACCOUNT_TYPE;
+        data[STAKE_POOL_MINT_OFFSET..STAKE_POOL_MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+        data[STAKE_POOL_TOTAL_LAMPORTS_OFFSET..STAKE_POOL_TOTAL_LAMPORTS_OFFSET + 8]
+            .copy_from_slice(&3_300u64.to_le_bytes());
+        data[STAKE_POOL_TOKEN_SUPPLY_OFFSET..STAKE_POOL_TOKEN_SUPPLY_OFFSET + 8]
+            .copy_from_slice(&3_000u64.to_le_bytes());
+        let pool = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+
+        assert_eq!(read_rate(LstKind::SplStakePool, &pool, &mint).unwrap(), 1_100_000_000);
+        // Another LST's pool is rejected
+        assert!(read_rate(LstKind::SplStakePool, &pool, &Pubkey::new_unique()).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Known Marinade deployments
+//!
+//! Deposit, withdraw and the strategy instructions pass Marinade's accounts
+//! through to marinade_strategy unchecked by Anchor. Before any CPI they are
+//! matched here against the deployment they claim to belong to: mainnet
+//! Marinade (fixed addresses and PDAs of its state) or the localnet
+//! mock_marinade program.
+
+use anchor_lang::prelude::*;
+
+use crate::lst::MARINADE_PROGRAM_ID;
+use crate::VaultError;
+
+/// Marinade state account
+pub const MARINADE_STATE: Pubkey = pubkey!("8szGkuLTAux9XMgZ2vtY39jVSowEcpBfFfD8hXSEqdGC");
+/// mSOL mint
+pub const MSOL_MINT: Pubkey = pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So");
+/// Liquidity pool mSOL leg, as recorded in the Marinade state
+pub const LIQ_POOL_MSOL_LEG: Pubkey = pubkey!("7GgPYjS5Dza89wV6FpZ23kUJRG5vbQ1GM25ezspYFSoE");
+/// Treasury mSOL account, as recorded in the Marinade state
+pub const TREASURY_MSOL_ACCOUNT: Pubkey = pubkey!("B1aLzaNMeFVAyQ6f3XbbUyKcH2YPHu2fqiEagmiF23VR");
+
+/// Role of an account in a Marinade CPI
+#[derive(Clone, Copy, Debug)]
+pub enum MarinadeAccount {
+    State,
+    MsolMint,
+    ReservePda,
+    MsolMintAuthority,
+    LiqPoolSolLeg,
+    LiqPoolMsolLeg,
+    LiqPoolMsolLegAuthority,
+    TreasuryMsolAccount,
+}
+
+/// Address `role` must have in the deployment run by `program`
+fn expected(program: &Pubkey, role: MarinadeAccount) -> Result<Pubkey> {
+    use MarinadeAccount::*;
+
+    if *program == MARINADE_PROGRAM_ID {
+        let pda = |seed: &[u8]| Pubkey::find_program_address(&[MARINADE_STATE.as_ref(), seed], program).0;
+        Ok(match role {
+            State => MARINADE_STATE,
+            MsolMint => MSOL_MINT,
+            ReservePda => pda(b"reserve"),
+            MsolMintAuthority => pda(b"st_mint"),
+            LiqPoolSolLeg => pda(b"liq_sol"),
+            LiqPoolMsolLeg => LIQ_POOL_MSOL_LEG,
+            LiqPoolMsolLegAuthority => pda(b"liq_st_sol_authority"),
+            TreasuryMsolAccount => TREASURY_MSOL_ACCOUNT,
+        })
+    } else if *program == mock_marinade::ID {
+        // The mock has no reserve or treasury; its legs stand in for them
+        let pda = |seed: &[u8]| Pubkey::find_program_address(&[seed], program).0;
+        Ok(match role {
+            State | LiqPoolMsolLegAuthority => pda(b"state"),
+            MsolMint => pda(b"msol_mint"),
+            ReservePda | LiqPoolSolLeg => pda(b"liq_sol"),
+            MsolMintAuthority => pda(b"msol_mint_authority"),
+            LiqPoolMsolLeg | TreasuryMsolAccount => pda(b"liq_msol"),
+        })
+    } else {
+        err!(VaultError::InvalidMarinadeAccount)
+    }
+}
+
+/// Check each `(role, key)` against the Marinade deployment `program`
+///
+/// Instructions that do not take the Marinade program pass the state
+/// account's owner, which `MarinadeAccount::State` then pins down.
+pub fn verify(program: &Pubkey, accounts: &[(MarinadeAccount, Pubkey)]) -> Result<()> {
+    require!(
+        *program == MARINADE_PROGRAM_ID || *program == mock_marinade::ID,
+        VaultError::InvalidMarinadeAccount
+    );
+    for (role, key) in accounts {
+        let expected = expected(program, *role)?;
+        if *key != expected {
+            msg!("Marinade {:?} account {} is not {}", role, key, expected);
+            return err!(VaultError::InvalidMarinadeAccount);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_deployments() {
+        let mint = (MarinadeAccount::MsolMint, MSOL_MINT);
+        assert!(verify(&MARINADE_PROGRAM_ID, &[mint, (MarinadeAccount::State, MARINADE_STATE)]).is_ok());
+        // Mainnet accounts under the mock program (and vice versa) are rejected
+        assert!(verify(&mock_marinade::ID, &[mint]).is_err());
+        let mock_mint = Pubkey::find_program_address(&[b"msol_mint"], &mock_marinade::ID).0;
+        assert!(verify(&mock_marinade::ID, &[(MarinadeAccount::MsolMint, mock_mint)]).is_ok());
+        assert!(verify(&MARINADE_PROGRAM_ID, &[(MarinadeAccount::MsolMint, mock_mint)]).is_err());
+        // Unknown programs are never a Marinade deployment
+        assert!(verify(&Pubkey::new_unique(), &[]).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//! Fixed-point helpers for share and TVL math
+//!
+//! Every product of two on-chain quantities (token amount × price, USD ×
+//! share supply, balance × shares) is taken in u128 and divided once, so no
+//! intermediate step can overflow or lose precision to an early descale.
+//! Results that leave the vault (shares minted, tokens paid out) round down;
+//! amounts owed to the vault (shares burned, tokens paid in) round up.
+
+use anchor_lang::prelude::*;
+
+use crate::VaultError;
+
+/// USD values and prices are in micro-dollars
+pub const USD_UNIT: u128 = 1_000_000;
+
+/// `a * b / c`, rounded down
+pub fn mul_div_floor(a: u128, b: u128, c: u128) -> Result<u128> {
+    require!(c > 0, VaultError::MathOverflow);
+    let product = a.checked_mul(b).ok_or(VaultError::MathOverflow)?;
+    Ok(product / c)
+}
+
+/// `a * b / c`, rounded up
+pub fn mul_div_ceil(a: u128, b: u128, c: u128) -> Result<u128> {
+    require!(c > 0, VaultError::MathOverflow);
+    let product = a.checked_mul(b).ok_or(VaultError::MathOverflow)?;
+    Ok(product.div_ceil(c))
+}
+
+/// 10^decimals
+pub fn pow10(decimals: u8) -> Result<u128> {
+    10u128
+        .checked_pow(decimals as u32)
+        .ok_or(VaultError::MathOverflow.into())
+}
+
+/// Narrow to u64, failing instead of truncating
+pub fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| VaultError::MathOverflow.into())
+}
+
+/// Narrow to i64, failing instead of wrapping
+pub fn to_i64(value: u128) -> Result<i64> {
+    i64::try_from(value).map_err(|_| VaultError::MathOverflow.into())
+}
+
+/// Widen a signed micro-USD value, rejecting negatives
+pub fn to_u128(value: i64) -> Result<u128> {
+    u128::try_from(value).map_err(|_| VaultError::MathOverflow.into())
+}
+
+/// `amount`'s `shares / total_shares` slice, rounded down (payouts)
+pub fn pro_rata(amount: u64, shares: u64, total_shares: u64) -> Result<u64> {
+    to_u64(mul_div_floor(amount as u128, shares as u128, total_shares as u128)?)
+}
+
+/// `amount`'s `shares / total_shares` slice, rounded up (amounts paid in)
+pub fn pro_rata_ceil(amount: u64, shares: u64, total_shares: u64) -> Result<u64> {
+    to_u64(mul_div_ceil(amount as u128, shares as u128, total_shares as u128)?)
+}
+
+/// `bps` basis points of `amount`, rounded down (fees and fee splits)
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    to_u64(mul_div_floor(amount as u128, bps as u128, 10_000)?)
+}
+
+/// Where a withdrawal's SOL comes from, in lamports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WithdrawPayout {
+    /// Exit fee kept by the vault
+    pub exit_fee: u64,
+    /// Paid from the vault's own SOL (native and wrapped)
+    pub from_vault: u64,
+    /// The user's slice of the staked position, paid by Marinade straight
+    /// to the user when their mSOL is unstaked
+    pub from_strategy: u64,
+}
+
+/// Split a withdrawal between the vault and its staking strategy
+///
+/// `basket_lamports` is the user's slice of the vault's own assets in SOL and
+/// `staked_lamports` their slice of the strategy value (yield included). The
+/// exit fee is taken on both legs but can only be withheld from the vault's,
+/// so it is capped there. The vault's payout never depends on what Marinade
+/// actually returns, so yield cannot make it underflow or pay twice.
+pub fn split_withdraw_payout(basket_lamports: u64, staked_lamports: u64, exit_fee_bps: u16) -> Result<WithdrawPayout> {
+    let total = basket_lamports as u128 + staked_lamports as u128;
+    let exit_fee = to_u64(mul_div_floor(total, exit_fee_bps as u128, 10_000)?)?.min(basket_lamports);
+    Ok(WithdrawPayout {
+        exit_fee,
+        from_vault: basket_lamports - exit_fee,
+        from_strategy: staked_lamports,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NormalizedPrice, Vault};
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div_floor(10, 1, 3).unwrap(), 3);
+        assert_eq!(mul_div_ceil(10, 1, 3).unwrap(), 4);
+        assert_eq!(mul_div_ceil(9, 1, 3).unwrap(), 3);
+        assert!(mul_div_floor(1, 1, 0).is_err());
+        assert!(mul_div_ceil(u128::MAX, 2, 1).is_err());
+    }
+
+    #[test]
+    fn test_pro_rata() {
+        // The full supply takes everything, u64::MAX balances included
+        assert_eq!(pro_rata(u64::MAX, 7, 7).unwrap(), u64::MAX);
+        assert_eq!(pro_rata(u64::MAX, 1, 2).unwrap(), u64::MAX / 2);
+        // One share of a huge supply rounds down to nothing
+        assert_eq!(pro_rata(999, 1, 1_000).unwrap(), 0);
+        assert!(pro_rata(1, 1, 0).is_err());
+        // Paid-in slices round up instead
+        assert_eq!(pro_rata_ceil(999, 1, 1_000).unwrap(), 1);
+        assert_eq!(pro_rata_ceil(u64::MAX, 7, 7).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_bps_of() {
+        assert_eq!(bps_of(10_000, 30).unwrap(), 30);
+        // Rounded down, never more than the amount
+        assert_eq!(bps_of(333, 30).unwrap(), 0);
+        assert_eq!(bps_of(u64::MAX, 10_000).unwrap(), u64::MAX);
+        // Misconfigured rates above 100% fail instead of truncating
+        assert!(bps_of(u64::MAX, u16::MAX).is_err());
+    }
+
+    #[test]
+    fn test_narrowing() {
+        assert_eq!(to_u64(u64::MAX as u128).unwrap(), u64::MAX);
+        assert!(to_u64(u64::MAX as u128 + 1).is_err());
+        assert!(to_i64(i64::MAX as u128 + 1).is_err());
+        assert!(to_u128(-1).is_err());
+        assert!(pow10(39).is_err());
+    }
+
+    #[test]
+    fn test_tokens_to_usd_extremes() {
+        // 21M BTC (8 decimals) at $100k: the product overflows i64 but not u128
+        let btc = NormalizedPrice::from_micro_usd(100_000_000_000);
+        let all_btc = 21_000_000 * 100_000_000;
+        assert_eq!(btc.tokens_to_usd(all_btc, 8).unwrap(), 2_100_000_000_000_000_000);
+        // u64::MAX wei (~18.4 ETH, 18 decimals) at $1M
+        let eth = NormalizedPrice::from_micro_usd(1_000_000_000_000);
+        assert_eq!(eth.tokens_to_usd(u64::MAX, 18).unwrap(), 18_446_744_073_709);
+        // Values past i64 fail instead of wrapping
+        assert!(btc.tokens_to_usd(u64::MAX, 0).is_err());
+        // Dust rounds down
+        assert_eq!(btc.tokens_to_usd(1, 8).unwrap(), 1_000);
+        assert_eq!(eth.tokens_to_usd(999_999, 18).unwrap(), 0);
+
+        // 10^18 micro-USD at $1 per 1 token of 18 decimals
+        let usd = NormalizedPrice::from_micro_usd(1_000_000);
+        assert_eq!(usd.usd_to_tokens(1_000_000, 18).unwrap(), 1_000_000_000_000_000_000);
+        assert!(usd.usd_to_tokens(-1, 9).is_err());
+    }
+
+    #[test]
+    fn test_share_price_extremes() {
+        assert_eq!(Vault::calculate_share_price(0, 0).unwrap(), 1_000_000);
+        // A trillion-dollar vault with a single share unit
+        assert!(Vault::calculate_share_price(i64::MAX, 1).is_err());
+        assert_eq!(
+            Vault::calculate_share_price(1_000_000_000_000_000_000, 1_000_000_000_000_000_000).unwrap(),
+            1_000_000
+        );
+        // Rounded down: $1 over 3 units
+        assert_eq!(Vault::calculate_share_price(1_000_000, 3).unwrap(), 333_333_333_333);
+    }
+
+    #[test]
+    fn test_mint_rounds_down_and_burn_rounds_up() {
+        // First deposit: $1.00 buys 10^6 share units
+        assert_eq!(Vault::calculate_shares_for_deposit(1_000_000, 0, 0).unwrap(), 1_000_000);
+        // $1 of a $3 vault with 10 units buys 3.33 units -> 3
+        assert_eq!(Vault::calculate_shares_for_deposit(1_000_000, 3_000_000, 10).unwrap(), 3);
+        // ...and redeeming $1 of it burns 3.33 units -> 4
+        assert_eq!(Vault::calculate_shares_to_burn(1_000_000, 3_000_000, 10).unwrap(), 4);
+        // Huge supply against a huge TVL stays exact
+        let shares = Vault::calculate_shares_for_deposit(i64::MAX, i64::MAX, u64::MAX).unwrap();
+        assert_eq!(shares, u64::MAX);
+        assert!(Vault::calculate_shares_to_burn(1, 0, 1).is_err());
+
+        // Minting then redeeming at the same TVL never returns more than deposited
+        let (tvl, supply) = (7_777_777_777i64, 3_333_333u64);
+        for deposit in [1i64, 999, 1_000_001, 123_456_789] {
+            let minted = Vault::calculate_shares_for_deposit(deposit, tvl, supply).unwrap();
+            let redeemed = mul_div_floor(minted as u128, (tvl + deposit) as u128, (supply + minted) as u128).unwrap();
+            assert!(redeemed <= deposit as u128);
+        }
+    }
+
+    #[test]
+    fn test_withdraw_payout_with_yield() {
+        // 10 SOL of basket and 5 SOL staked that grew to 5.5 SOL: the vault
+        // pays its 10 SOL and Marinade the 5.5 SOL, with no fee
+        let payout = split_withdraw_payout(10_000_000_000, 5_500_000_000, 0).unwrap();
+        assert_eq!(payout.from_vault, 10_000_000_000);
+        assert_eq!(payout.from_strategy, 5_500_000_000);
+        assert_eq!(payout.exit_fee, 0);
+
+        // A 1% exit fee covers both legs and comes out of the vault's
+        let payout = split_withdraw_payout(10_000_000_000, 5_500_000_000, 100).unwrap();
+        assert_eq!(payout.exit_fee, 155_000_000);
+        assert_eq!(payout.from_vault, 9_845_000_000);
+        assert_eq!(payout.from_strategy, 5_500_000_000);
+
+        // Yield larger than the whole basket never underflows the vault leg
+        let payout = split_withdraw_payout(1_000, 50_000_000_000, 500).unwrap();
+        assert_eq!(payout.exit_fee, 1_000);
+        assert_eq!(payout.from_vault, 0);
+        assert_eq!(payout.from_strategy, 50_000_000_000);
+
+        // Without a strategy everything comes from the vault
+        let payout = split_withdraw_payout(u64::MAX, 0, 10_000).unwrap();
+        assert_eq!(payout, WithdrawPayout { exit_fee: u64::MAX, from_vault: 0, from_strategy: 0 });
+    }
+}
@@ -0,0 +1,145 @@
+//! Arcium rebalancing MXE results
+//!
+//! rebalance_confidential queues compute_rebalancing on the rebalancing-mxe
+//! program, which creates a RebalanceResult PDA for the computation offset.
+//! Only the MXE's Arcium callback fills it in, and Arcium runs the callback
+//! only after verifying the cluster's signature over the computation output,
+//! so a completed result at the expected address is what the circuit
+//! revealed for the computation the vault queued. apply_confidential_rebalance
+//! reads it from there instead of trusting drifts passed by the caller.
+//!
+//! A hidden-weights vault seals its target weights to the MXE instead
+//! (seal_target_weights, signed by the vault PDA): the cluster re-encrypts
+//! them under the MXE key and the callback stores them in the vault's
+//! SealedTargets. Its rebalances feed those ciphertexts and on-chain USD
+//! values to compute_hidden_rebalancing, whose result carries capped trades
+//! rather than drifts, so neither the manager nor the keeper supplies what
+//! the vault trades toward.
+//!
+//! The MXE program pulls in the Arcium SDK, so its accounts are parsed here
+//! from the Anchor layout rather than through a crate dependency.
+
+use anchor_lang::prelude::*;
+
+use crate::VaultError;
+
+/// rebalancing-mxe program (devnet)
+pub const REBALANCING_MXE_PROGRAM_ID: Pubkey = pubkey!("FwbzbjGyBmb5n7VAPfMnYKZthycScuA6ktGE7rtZ2Z9x");
+
+/// Seed of the per-computation result PDA: [REBALANCE_RESULT_SEED, computation_offset (le)]
+pub const REBALANCE_RESULT_SEED: &[u8] = b"rebalance_result";
+
+/// Seed of a vault's sealed targets: [SEALED_TARGETS_SEED, vault]
+pub const SEALED_TARGETS_SEED: &[u8] = b"sealed_targets";
+
+// First 8 bytes of SHA256("account:RebalanceResult")
+const REBALANCE_RESULT_DISCRIMINATOR: [u8; 8] = [223, 81, 17, 62, 165, 131, 211, 97];
+// First 8 bytes of SHA256("account:SealedTargets")
+const SEALED_TARGETS_DISCRIMINATOR: [u8; 8] = [26, 132, 132, 166, 238, 59, 72, 110];
+
+/// Instruction discriminator: first 8 bytes of SHA256("global:seal_target_weights")
+pub const SEAL_TARGET_WEIGHTS_DISCRIMINATOR: [u8; 8] = [51, 41, 60, 147, 170, 245, 145, 238];
+/// Instruction discriminator: first 8 bytes of SHA256("global:compute_hidden_rebalancing")
+pub const COMPUTE_HIDDEN_REBALANCING_DISCRIMINATOR: [u8; 8] = [83, 4, 16, 35, 149, 43, 253, 112];
+
+/// Nonce the client encrypts a computation's inputs under
+///
+/// Derived from the vault and the computation offset, which Arcium accepts
+/// once (it creates the computation account), so no two computations share
+/// a key/nonce pair and the caller cannot pick one.
+pub fn computation_nonce(vault: &Pubkey, computation_offset: u64) -> u128 {
+    let hash = anchor_lang::solana_program::hash::hashv(&[
+        b"mxe_nonce",
+        vault.as_ref(),
+        &computation_offset.to_le_bytes(),
+    ]);
+    let mut nonce = [0u8; 16];
+    nonce.copy_from_slice(&hash.to_bytes()[..16]);
+    u128::from_le_bytes(nonce)
+}
+
+/// Revealed RebalancingResult of one computation, as the MXE stores it
+/// (the trailing bump is left unread)
+#[derive(AnchorDeserialize, Clone, Debug)]
+pub struct RebalanceResult {
+    pub computation_offset: u64,
+    /// Payer of the computation (the vault admin)
+    pub requester: Pubkey,
+    /// Vault the computation was queued for
+    pub vault: Pubkey,
+    /// Set by the callback once the cluster's output is in
+    pub completed: bool,
+    pub needs_rebalance: bool,
+    /// BTC, ETH and SOL drift from target, in percentage points
+    pub drifts_pct: [i16; 3],
+    /// Total value the circuit computed, in micro-USD
+    pub total_tvl: u64,
+    /// Hidden-weights computations only: capped BTC, ETH and SOL trades in
+    /// micro-USD, positive to buy
+    pub trades_usd: [i64; 3],
+    /// Unix timestamp the computation was queued
+    pub queued_at: i64,
+}
+
+/// The result of `computation_offset`, queued for `vault`, completed or not
+///
+/// `account` must already be checked to be the result PDA of that offset
+/// under REBALANCING_MXE_PROGRAM_ID.
+pub fn queued_result(account: &AccountInfo, vault: &Pubkey, computation_offset: u64) -> Result<RebalanceResult> {
+    require_keys_eq!(*account.owner, REBALANCING_MXE_PROGRAM_ID, VaultError::InvalidConfidentialResult);
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == REBALANCE_RESULT_DISCRIMINATOR,
+        VaultError::InvalidConfidentialResult
+    );
+    let result = RebalanceResult::deserialize(&mut &data[8..])?;
+    require!(
+        result.computation_offset == computation_offset && result.vault == *vault,
+        VaultError::InvalidConfidentialResult
+    );
+    Ok(result)
+}
+
+/// The completed result of `computation_offset`, queued for `vault`
+///
+/// `account` must already be checked to be the result PDA of that offset
+/// under REBALANCING_MXE_PROGRAM_ID.
+pub fn completed_result(account: &AccountInfo, vault: &Pubkey, computation_offset: u64) -> Result<RebalanceResult> {
+    let result = queued_result(account, vault, computation_offset)?;
+    require!(result.completed, VaultError::InvalidConfidentialResult);
+    msg!(
+        "MXE result {}: requested by {}, needs rebalance: {}, TVL {}",
+        computation_offset,
+        result.requester,
+        result.needs_rebalance,
+        result.total_tvl
+    );
+    Ok(result)
+}
+
+/// Require `vault`'s targets to be sealed, by a completed seal of weights
+/// summing to 100; returns the computation that sealed them
+///
+/// `account` must already be checked to be the SealedTargets PDA of `vault`
+/// under REBALANCING_MXE_PROGRAM_ID.
+pub fn require_sealed_targets(account: &AccountInfo, vault: &Pubkey) -> Result<u64> {
+    require_keys_eq!(*account.owner, REBALANCING_MXE_PROGRAM_ID, VaultError::TargetsNotSealed);
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == SEALED_TARGETS_DISCRIMINATOR,
+        VaultError::TargetsNotSealed
+    );
+    // vault, nonce (u128), three 32-byte ciphertexts, then the plain fields
+    let mut rest = &data[8..];
+    let sealed_vault = Pubkey::deserialize(&mut rest)?;
+    let _nonce = u128::deserialize(&mut rest)?;
+    let _ciphertexts = <[[u8; 32]; 3]>::deserialize(&mut rest)?;
+    let computation_offset = u64::deserialize(&mut rest)?;
+    let completed = bool::deserialize(&mut rest)?;
+    let valid = bool::deserialize(&mut rest)?;
+    require!(
+        sealed_vault == *vault && completed && valid,
+        VaultError::TargetsNotSealed
+    );
+    Ok(computation_offset)
+}
@@ -0,0 +1,156 @@
+//! Orca Whirlpool swap backend
+//!
+//! Swaps the vault's tokens directly against a single Whirlpool instead of
+//! routing through an aggregator. One pool needs only a fixed set of 11
+//! accounts, which keeps a swap inside a single transaction when an
+//! aggregator route would not fit. Only the top pairs are allowed.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::VaultError;
+
+/// Orca Whirlpool program (same ID on mainnet and devnet)
+pub const WHIRLPOOL_PROGRAM_ID: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+
+/// Wrapped SOL
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+/// USDC
+pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+/// Wrapped BTC (Portal)
+pub const WBTC_MINT: Pubkey = pubkey!("3NZ9JMVBmGAqocybic2c7LQCJScmgsAZ6vQqTDzcqmJh");
+
+/// Pairs the vault may swap through a Whirlpool, in either direction
+pub const SUPPORTED_PAIRS: [(Pubkey, Pubkey); 2] = [(WSOL_MINT, USDC_MINT), (WSOL_MINT, WBTC_MINT)];
+
+// Instruction discriminator: first 8 bytes of SHA256("global:swap")
+const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+// Price limits accepted by the Whirlpool program (no limit in either direction)
+const MIN_SQRT_PRICE_X64: u128 = 4_295_048_016;
+const MAX_SQRT_PRICE_X64: u128 = 79_226_673_515_401_279_992_447_579_055;
+
+// Whirlpool account layout offsets (after the 8-byte discriminator)
+const TOKEN_MINT_A_OFFSET: usize = 101;
+const TOKEN_VAULT_A_OFFSET: usize = 133;
+const TOKEN_MINT_B_OFFSET: usize = 181;
+const TOKEN_VAULT_B_OFFSET: usize = 213;
+
+/// Mints and token vaults of a Whirlpool, read from its account data
+#[derive(Clone, Copy, Debug)]
+pub struct WhirlpoolInfo {
+    pub token_mint_a: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_vault_b: Pubkey,
+}
+
+impl WhirlpoolInfo {
+    /// Parse a Whirlpool account owned by the Whirlpool program
+    pub fn load(whirlpool: &AccountInfo) -> Result<Self> {
+        require!(
+            whirlpool.owner == &WHIRLPOOL_PROGRAM_ID,
+            VaultError::InvalidWhirlpool
+        );
+        let data = whirlpool.try_borrow_data()?;
+        require!(
+            data.len() >= TOKEN_VAULT_B_OFFSET + 32,
+            VaultError::InvalidWhirlpool
+        );
+
+        let key_at = |offset: usize| Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap());
+        Ok(Self {
+            token_mint_a: key_at(TOKEN_MINT_A_OFFSET),
+            token_vault_a: key_at(TOKEN_VAULT_A_OFFSET),
+            token_mint_b: key_at(TOKEN_MINT_B_OFFSET),
+            token_vault_b: key_at(TOKEN_VAULT_B_OFFSET),
+        })
+    }
+
+    /// Whether this pool trades one of SUPPORTED_PAIRS
+    pub fn is_supported(&self) -> bool {
+        SUPPORTED_PAIRS.iter().any(|(x, y)| {
+            (self.token_mint_a == *x && self.token_mint_b == *y)
+                || (self.token_mint_a == *y && self.token_mint_b == *x)
+        })
+    }
+}
+
+/// Accounts of a Whirlpool `swap` instruction
+pub struct WhirlpoolSwap<'a, 'info> {
+    pub whirlpool_program: &'a AccountInfo<'info>,
+    pub token_program: &'a AccountInfo<'info>,
+    /// Owner of both token_owner_accounts (the vault PDA)
+    pub token_authority: &'a AccountInfo<'info>,
+    pub whirlpool: &'a AccountInfo<'info>,
+    pub token_owner_account_a: &'a AccountInfo<'info>,
+    pub token_vault_a: &'a AccountInfo<'info>,
+    pub token_owner_account_b: &'a AccountInfo<'info>,
+    pub token_vault_b: &'a AccountInfo<'info>,
+    pub tick_arrays: [&'a AccountInfo<'info>; 3],
+    pub oracle: &'a AccountInfo<'info>,
+}
+
+/// Swap exactly `amount_in` through the Whirlpool, failing inside the pool
+/// if less than `min_amount_out` comes back
+/// `a_to_b` sells token A for token B
+pub fn swap(
+    accounts: &WhirlpoolSwap,
+    amount_in: u64,
+    min_amount_out: u64,
+    a_to_b: bool,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let sqrt_price_limit = if a_to_b { MIN_SQRT_PRICE_X64 } else { MAX_SQRT_PRICE_X64 };
+
+    // Args: amount, other_amount_threshold, sqrt_price_limit, amount_specified_is_input, a_to_b
+    let mut data = Vec::with_capacity(8 + 8 + 8 + 16 + 1 + 1);
+    data.extend_from_slice(&SWAP_DISCRIMINATOR);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+    data.extend_from_slice(&sqrt_price_limit.to_le_bytes());
+    data.push(1);
+    data.push(a_to_b as u8);
+
+    let account_metas = vec![
+        AccountMeta::new_readonly(accounts.token_program.key(), false),
+        AccountMeta::new_readonly(accounts.token_authority.key(), true),
+        AccountMeta::new(accounts.whirlpool.key(), false),
+        AccountMeta::new(accounts.token_owner_account_a.key(), false),
+        AccountMeta::new(accounts.token_vault_a.key(), false),
+        AccountMeta::new(accounts.token_owner_account_b.key(), false),
+        AccountMeta::new(accounts.token_vault_b.key(), false),
+        AccountMeta::new(accounts.tick_arrays[0].key(), false),
+        AccountMeta::new(accounts.tick_arrays[1].key(), false),
+        AccountMeta::new(accounts.tick_arrays[2].key(), false),
+        AccountMeta::new(accounts.oracle.key(), false),
+    ];
+
+    let ix = Instruction {
+        program_id: WHIRLPOOL_PROGRAM_ID,
+        accounts: account_metas,
+        data,
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            accounts.token_program.clone(),
+            accounts.token_authority.clone(),
+            accounts.whirlpool.clone(),
+            accounts.token_owner_account_a.clone(),
+            accounts.token_vault_a.clone(),
+            accounts.token_owner_account_b.clone(),
+            accounts.token_vault_b.clone(),
+            accounts.tick_arrays[0].clone(),
+            accounts.tick_arrays[1].clone(),
+            accounts.tick_arrays[2].clone(),
+            accounts.oracle.clone(),
+            accounts.whirlpool_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
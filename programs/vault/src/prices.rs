@@ -0,0 +1,695 @@
+//! Price module
+//!
+//! Single place where the vault reads prices. Every instruction that needs
+//! BTC/ETH/SOL prices goes through `fetch_prices` (or `fetch_feed_prices`),
+//! so a new price source only has to be wired in here. Vaults whose basket
+//! is not BTC/ETH/SOL price each asset through `fetch_basket_prices`.
+//!
+//! Oracle vendor formats (Switchboard pull feeds, Pyth Lazer updates, the
+//! mock oracle) are parsed by the oracle_adapter program, called by CPI, so
+//! they can be upgraded without redeploying the vault. Sources it parses
+//! take the adapter program in `remaining_accounts`, found by key.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+
+use crate::chainlink;
+use crate::lst;
+use crate::math;
+use crate::state::{
+    AssetOracles, PriceCache, PriceFeed, PriceFeedKind, PythLazerConfig, SwitchboardConfig, ChainlinkConfig, Vault, MAX_FEEDS_PER_ASSET,
+};
+use crate::{PriceSource, VaultError};
+
+/// Maximum age of PriceCache prices accepted by deposit/withdraw
+pub const MAX_PRICE_CACHE_AGE_SECS: i64 = 60;
+
+// Price feed precision helper
+// Switchboard Oracle Quotes use i64 with negative exponents (e.g., -8 for BTC)
+// This helper normalizes prices to a common USD value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct NormalizedPrice {
+    pub price_usd: i64, // Price in USD with 6 decimals (micro-dollars)
+    pub original_price: i64,
+    pub expo: i32,
+}
+
+/// Prices the oracle adapter returns carry the same fields
+impl From<oracle_adapter::NormalizedPrice> for NormalizedPrice {
+    fn from(price: oracle_adapter::NormalizedPrice) -> Self {
+        Self {
+            price_usd: price.price_usd,
+            original_price: price.original_price,
+            expo: price.expo,
+        }
+    }
+}
+
+impl NormalizedPrice {
+    /// Wrap a price that is already in micro-dollars (MockOracle, PriceCache)
+    pub fn from_micro_usd(price_usd: i64) -> Self {
+        Self {
+            price_usd,
+            original_price: price_usd / 1_000_000,
+            expo: -6,
+        }
+    }
+
+    /// Calculate token amount from USD value (in micro-dollars)
+    /// Returns amount in token's native decimals, rounded down
+    pub fn usd_to_tokens(&self, usd_micro: i64, token_decimals: u8) -> Result<i64> {
+        // usd_micro and price_usd both have 6 decimals, so
+        // tokens = usd_micro × 10^decimals / price_usd
+        let base_amount = math::mul_div_floor(
+            math::to_u128(usd_micro)?,
+            math::pow10(token_decimals)?,
+            math::to_u128(self.price_usd)?,
+        )?;
+        math::to_i64(base_amount)
+    }
+
+    /// Calculate USD value from token amount, rounded down
+    pub fn tokens_to_usd(&self, amount: u64, token_decimals: u8) -> Result<i64> {
+        let usd = math::mul_div_floor(
+            amount as u128,
+            math::to_u128(self.price_usd)?,
+            math::pow10(token_decimals)?,
+        )?;
+        math::to_i64(usd)
+    }
+}
+
+/// Price of a single vault asset
+#[derive(Clone, Copy, Debug)]
+pub struct AssetPrice {
+    pub mint: Pubkey,
+    pub price: NormalizedPrice,
+}
+
+/// Price and mint decimals of every basket asset, plus the price of SOL
+pub struct BasketPrices {
+    /// Price of each asset, in vault.assets order
+    pub assets: Vec<NormalizedPrice>,
+    /// Decimals of each asset's mint, in vault.assets order
+    pub decimals: Vec<u8>,
+    /// Price of SOL, the currency deposits and withdrawals settle in
+    pub sol: NormalizedPrice,
+}
+
+impl BasketPrices {
+    /// Prices of a BTC/ETH/SOL basket from the three oracle markets
+    pub fn from_markets(vault: &Vault, markets: &[NormalizedPrice; 3]) -> Self {
+        Self {
+            assets: markets.to_vec(),
+            decimals: (0..markets.len()).map(|i| vault.asset_decimals(i)).collect(),
+            sol: markets[2],
+        }
+    }
+}
+
+/// Accounts a price source may read from
+pub struct PriceAccounts<'a, 'info> {
+    /// Vault being priced (used to match its PriceCache)
+    pub vault: Pubkey,
+    /// Switchboard pull feeds (only read when price_source = Switchboard or
+    /// CrossChecked)
+    pub btc_quote: &'a AccountInfo<'info>,
+    pub eth_quote: &'a AccountInfo<'info>,
+    pub sol_quote: &'a AccountInfo<'info>,
+    /// Searched for the vault's MockOracle, the protocol's price configs and
+    /// feed accounts, depending on the price source
+    pub remaining_accounts: &'a [AccountInfo<'info>],
+}
+
+/// The oracle adapter program, found in `remaining_accounts`
+fn find_oracle_adapter<'a, 'info>(remaining_accounts: &'a [AccountInfo<'info>]) -> Result<&'a AccountInfo<'info>> {
+    remaining_accounts
+        .iter()
+        .find(|acc| acc.key == &oracle_adapter::ID)
+        .ok_or(VaultError::OracleAdapterRequired.into())
+}
+
+/// BTC, ETH and SOL from prices the oracle adapter returned
+fn market_prices(prices: Vec<oracle_adapter::NormalizedPrice>) -> Result<[NormalizedPrice; 3]> {
+    let prices: [oracle_adapter::NormalizedPrice; 3] =
+        prices.try_into().map_err(|_| VaultError::InvalidQuote)?;
+    Ok(prices.map(NormalizedPrice::from))
+}
+
+/// Price Switchboard On-Demand pull feeds through the oracle adapter
+///
+/// Each feed must be owned by the Switchboard program, aggregate at least
+/// its min_sample_size samples and have updated within the adapter's
+/// MAX_QUOTE_AGE_SECS. An expected feed hash pins what the feed prices: the
+/// market quote accounts are passed unchecked, so only the hash stops a
+/// caller from substituting another feed. Callers that match the account by
+/// key pass None.
+fn read_pull_feeds<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    feeds: &[&AccountInfo<'info>],
+    expected_feed_hashes: Vec<Option<[u8; 32]>>,
+) -> Result<Vec<NormalizedPrice>> {
+    let adapter = find_oracle_adapter(remaining_accounts)?;
+    let (first, rest) = feeds.split_first().ok_or(VaultError::InvalidQuote)?;
+    let cpi_ctx = CpiContext::new(
+        adapter.clone(),
+        oracle_adapter::cpi::accounts::ReadSwitchboard {
+            feed: (*first).clone(),
+        },
+    )
+    .with_remaining_accounts(rest.iter().map(|feed| (*feed).clone()).collect());
+    let prices = oracle_adapter::cpi::read_switchboard(cpi_ctx, expected_feed_hashes)?.get();
+    require!(prices.len() == feeds.len(), VaultError::InvalidQuote);
+    Ok(prices.into_iter().map(NormalizedPrice::from).collect())
+}
+
+/// Price one Switchboard pull feed matched by key (e.g. a NAV currency's FX
+/// feed); the oracle adapter must be in `remaining_accounts`
+pub fn verify_oracle_quote<'info>(
+    remaining_accounts: &[AccountInfo<'info>],
+    account: &AccountInfo<'info>,
+) -> Result<NormalizedPrice> {
+    let prices = read_pull_feeds(remaining_accounts, &[account], vec![None])?;
+    prices.first().copied().ok_or(VaultError::InvalidQuote.into())
+}
+
+/// BTC, ETH and SOL from the three quote accounts, each checked against the
+/// feed hash the protocol's SwitchboardConfig sets for its market
+fn read_switchboard_quotes(accounts: &PriceAccounts) -> Result<[NormalizedPrice; 3]> {
+    let config: SwitchboardConfig = read_protocol_config(accounts.remaining_accounts)?;
+    let quotes = [accounts.btc_quote, accounts.eth_quote, accounts.sol_quote];
+    let feed_hashes = config.feed_hashes.iter().map(|hash| Some(*hash)).collect();
+
+    let prices = read_pull_feeds(accounts.remaining_accounts, &quotes, feed_hashes)?;
+    Ok([prices[0], prices[1], prices[2]])
+}
+
+/// Fetch BTC, ETH and SOL prices (in that order) from the vault's price source
+///
+/// - Switchboard: the three quote accounts, each checked against the
+///   SwitchboardConfig (in `remaining_accounts`) feed hash of its market
+/// - MockOracle: reads the vault's oracle from `remaining_accounts` and
+///   rejects it if older than `max_mock_age` seconds
+/// - Aggregated: median of the vault's configured feeds per asset
+/// - CrossChecked: the Switchboard quotes, rejected if any strays more than
+///   max_divergence_bps from the vault's mock oracle
+/// - PythLazer: the signed Lazer update verified just before the instruction
+///   (see `oracle_adapter::pyth_lazer`), with the PythLazerConfig and
+///   instructions sysvar in `remaining_accounts`
+/// - Chainlink: the prices post_chainlink_report kept in the ChainlinkConfig
+///   (in `remaining_accounts`), each observed within MAX_CHAINLINK_AGE_SECS
+///
+/// LST indexes get LST prices in the BTC/ETH slots (see `lst::apply_rates`).
+pub fn fetch_feed_prices(
+    vault: &Vault,
+    accounts: &PriceAccounts,
+    clock: &Clock,
+    max_mock_age: i64,
+) -> Result<[NormalizedPrice; 3]> {
+    let prices = read_feed_prices(vault, accounts, clock, max_mock_age)?;
+    lst::apply_rates(vault, accounts, clock, prices)
+}
+
+/// Prices straight from the vault's price source, before LST rates
+fn read_feed_prices(
+    vault: &Vault,
+    accounts: &PriceAccounts,
+    clock: &Clock,
+    max_mock_age: i64,
+) -> Result<[NormalizedPrice; 3]> {
+    let current_time = clock.unix_timestamp;
+
+    msg!("🔍 Fetching prices from {:?}...", vault.price_source());
+
+    match vault.price_source() {
+        PriceSource::Switchboard => {
+            msg!("📊 Reading Switchboard Oracle Quotes...");
+
+            read_switchboard_quotes(accounts)
+        },
+        PriceSource::MockOracle => {
+            msg!("🎭 Reading Mock Oracle prices...");
+
+            let oracle_key = vault.mock_oracle().ok_or(VaultError::InvalidPrice)?;
+            let mock_oracle_account = find_account(accounts.remaining_accounts, &oracle_key)?;
+            let prices = read_mock_oracle(vault, accounts.remaining_accounts, mock_oracle_account, max_mock_age)?;
+
+            Ok(prices.map(NormalizedPrice::from_micro_usd))
+        },
+        PriceSource::Aggregated => {
+            msg!("🧮 Aggregating {} price feeds...", vault.price_feeds().len());
+
+            let mut prices = [NormalizedPrice::from_micro_usd(0); 3];
+            for (index, price) in prices.iter_mut().enumerate() {
+                *price = aggregate_feed_prices(vault, index as u8, accounts, max_mock_age)?;
+            }
+
+            Ok(prices)
+        },
+        PriceSource::CrossChecked => {
+            msg!("⚖️  Cross-checking Switchboard against the Mock Oracle...");
+
+            let prices = read_switchboard_quotes(accounts)?;
+            let oracle_key = vault.mock_oracle().ok_or(VaultError::InvalidPrice)?;
+            let mock_oracle_account = find_account(accounts.remaining_accounts, &oracle_key)?;
+            let reference = read_mock_oracle(vault, accounts.remaining_accounts, mock_oracle_account, max_mock_age)?;
+
+            for (index, (price, reference)) in prices.iter().zip(reference).enumerate() {
+                let divergence_bps = divergence_bps(price.price_usd, reference).ok_or(VaultError::InvalidPrice)?;
+                msg!("   Feed {}: {} vs {} ({} bps apart)", index, price.price_usd, reference, divergence_bps);
+                require!(
+                    divergence_bps <= vault.max_divergence_bps as u64,
+                    VaultError::PriceDivergence
+                );
+            }
+
+            Ok(prices)
+        },
+        PriceSource::PythLazer => {
+            msg!("⚡ Reading Pyth Lazer update...");
+
+            let config: PythLazerConfig = read_protocol_config(accounts.remaining_accounts)?;
+            let instructions = find_account(accounts.remaining_accounts, &sysvar_instructions::ID)?;
+            let adapter = find_oracle_adapter(accounts.remaining_accounts)?;
+            let cpi_accounts = oracle_adapter::cpi::accounts::ReadPythLazer {
+                instructions: instructions.clone(),
+            };
+            let cpi_ctx = CpiContext::new(adapter.clone(), cpi_accounts);
+            let prices = oracle_adapter::cpi::read_pyth_lazer(cpi_ctx, config.trusted_signers, config.feed_ids.to_vec())?;
+
+            market_prices(prices.get())
+        },
+        PriceSource::Chainlink => {
+            msg!("🔗 Reading Chainlink Data Streams prices...");
+
+            let config: ChainlinkConfig = read_protocol_config(accounts.remaining_accounts)?;
+
+            let mut prices = [NormalizedPrice::from_micro_usd(0); 3];
+            for (index, price) in prices.iter_mut().enumerate() {
+                require!(config.prices[index] > 0, VaultError::InvalidPrice);
+                require!(
+                    current_time - config.observed_at[index] <= chainlink::MAX_CHAINLINK_AGE_SECS,
+                    VaultError::StaleQuote
+                );
+                *price = NormalizedPrice::from_micro_usd(config.prices[index]);
+            }
+
+            Ok(prices)
+        },
+    }
+}
+
+/// A protocol-wide config account (PythLazerConfig, SwitchboardConfig,
+/// ChainlinkConfig) found in `remaining_accounts` by owner and discriminator;
+/// each is a single PDA, so no other account can match
+fn read_protocol_config<T: AccountDeserialize + Discriminator>(remaining_accounts: &[AccountInfo]) -> Result<T> {
+    let account = remaining_accounts
+        .iter()
+        .find(|acc| {
+            acc.owner == &crate::ID
+                && acc
+                    .try_borrow_data()
+                    .map(|data| data.starts_with(T::DISCRIMINATOR))
+                    .unwrap_or(false)
+        })
+        .ok_or(VaultError::InvalidPrice)?;
+    let data = account.try_borrow_data()?;
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Distance between two micro-USD prices in bps of `price`; None unless
+/// both are positive
+fn divergence_bps(price: i64, reference: i64) -> Option<u64> {
+    if price <= 0 || reference <= 0 {
+        return None;
+    }
+    let gap = (price as i128 - reference as i128).unsigned_abs();
+    u64::try_from(gap * 10_000 / price as u128).ok()
+}
+
+/// Find `key` in `remaining_accounts`
+fn find_account<'a, 'info>(
+    remaining_accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<&'a AccountInfo<'info>> {
+    remaining_accounts
+        .iter()
+        .find(|acc| acc.key == key)
+        .ok_or(VaultError::InvalidPrice.into())
+}
+
+/// Read BTC/ETH/SOL micro-USD prices from a MockOracle account through the
+/// oracle adapter, applying the vault's TWAP window when one is configured
+fn read_mock_oracle<'info>(
+    vault: &Vault,
+    remaining_accounts: &[AccountInfo<'info>],
+    account: &AccountInfo<'info>,
+    max_mock_age: i64,
+) -> Result<[i64; 3]> {
+    let adapter = find_oracle_adapter(remaining_accounts)?;
+    let cpi_accounts = oracle_adapter::cpi::accounts::ReadMockOracle {
+        oracle: account.clone(),
+    };
+    let cpi_ctx = CpiContext::new(adapter.clone(), cpi_accounts);
+    let prices = oracle_adapter::cpi::read_mock_oracle(cpi_ctx, crate::ID, max_mock_age, vault.twap_window_secs)?;
+
+    Ok(market_prices(prices.get())?.map(|price| price.price_usd))
+}
+
+/// Median of every feed configured for `feed_index` (0 = BTC, 1 = ETH, 2 = SOL)
+///
+/// Rejects the price if the feeds spread further apart than the vault's
+/// max_divergence_bps relative to the median.
+fn aggregate_feed_prices(
+    vault: &Vault,
+    feed_index: u8,
+    accounts: &PriceAccounts,
+    max_mock_age: i64,
+) -> Result<NormalizedPrice> {
+    let mut samples: Vec<i64> = Vec::with_capacity(MAX_FEEDS_PER_ASSET);
+
+    for feed in vault.price_feeds().iter().filter(|f| f.feed_index == feed_index) {
+        let account = find_account(accounts.remaining_accounts, &feed.account)?;
+        samples.push(read_feed(vault, feed, accounts.remaining_accounts, account, max_mock_age)?);
+    }
+
+    require!(!samples.is_empty(), VaultError::InvalidPrice);
+    samples.sort_unstable();
+
+    let mid = samples.len() / 2;
+    let median = if samples.len() % 2 == 1 {
+        samples[mid]
+    } else {
+        (samples[mid - 1] + samples[mid]) / 2
+    };
+    require!(median > 0, VaultError::InvalidPrice);
+
+    // Spread between the extreme feeds, in basis points of the median
+    let spread = (samples[samples.len() - 1] - samples[0]) as i128;
+    let spread_bps = spread * 10_000 / median as i128;
+    msg!(
+        "   Feed {}: median={} from {} sources (spread {} bps)",
+        feed_index,
+        median,
+        samples.len(),
+        spread_bps
+    );
+    require!(
+        spread_bps <= vault.max_divergence_bps as i128,
+        VaultError::PriceDivergence
+    );
+
+    Ok(NormalizedPrice::from_micro_usd(median))
+}
+
+/// Micro-USD price from a single feed account
+fn read_feed<'info>(
+    vault: &Vault,
+    feed: &PriceFeed,
+    remaining_accounts: &[AccountInfo<'info>],
+    account: &AccountInfo<'info>,
+    max_mock_age: i64,
+) -> Result<i64> {
+    match feed.kind {
+        PriceFeedKind::Switchboard => Ok(verify_oracle_quote(remaining_accounts, account)?.price_usd),
+        PriceFeedKind::MockOracle => {
+            let markets = read_mock_oracle(vault, remaining_accounts, account, max_mock_age)?;
+            markets
+                .get(feed.feed_index as usize)
+                .copied()
+                .ok_or(VaultError::InvalidPriceFeeds.into())
+        },
+    }
+}
+
+/// Fetch the price of every asset of a per-asset priced vault
+///
+/// The price accounts follow the [mint, vault ATA] pairs in this order,
+/// each checked by key:
+/// - the vault's AssetOracles
+/// - the feed account of each asset, in vault.assets order
+/// - the SOL feed account
+/// - the oracle adapter program
+///
+/// Decimals are read from the mint accounts of the pairs.
+pub fn fetch_basket_prices(vault: &Vault, accounts: &PriceAccounts, max_mock_age: i64) -> Result<BasketPrices> {
+    let assets = vault.assets();
+    let remaining = accounts.remaining_accounts;
+    let base = assets.len() * 2;
+    require!(
+        remaining.len() >= base + assets.len() + 3,
+        VaultError::InvalidRemainingAccounts
+    );
+
+    let oracles_account = &remaining[base];
+    require!(oracles_account.owner == &crate::ID, VaultError::AssetOraclesRequired);
+    let oracles = AssetOracles::try_deserialize(&mut &oracles_account.try_borrow_data()?[..])
+        .map_err(|_| VaultError::AssetOraclesRequired)?;
+    require!(
+        oracles.vault == accounts.vault && oracles.feeds.len() == assets.len(),
+        VaultError::AssetOraclesRequired
+    );
+
+    msg!("🔍 Fetching {} asset prices from AssetOracles...", assets.len());
+
+    let mut prices = BasketPrices {
+        assets: Vec::with_capacity(assets.len()),
+        decimals: Vec::with_capacity(assets.len()),
+        sol: NormalizedPrice::from_micro_usd(0),
+    };
+    for (i, (asset, feed)) in assets.iter().zip(&oracles.feeds).enumerate() {
+        let mint = &remaining[i * 2];
+        let account = &remaining[base + 1 + i];
+        require!(mint.key() == asset.mint, VaultError::InvalidMint);
+        require!(account.key() == feed.account, VaultError::InvalidPrice);
+
+        let price = read_feed(vault, feed, remaining, account, max_mock_age)?;
+        require!(price > 0, VaultError::InvalidPrice);
+        prices.assets.push(NormalizedPrice::from_micro_usd(price));
+        prices.decimals.push(crate::token_io::mint_decimals(mint)?);
+    }
+
+    let sol_account = &remaining[base + 1 + assets.len()];
+    require!(sol_account.key() == oracles.sol_feed.account, VaultError::InvalidPrice);
+    let sol_price = read_feed(vault, &oracles.sol_feed, remaining, sol_account, max_mock_age)?;
+    require!(sol_price > 0, VaultError::InvalidPrice);
+    prices.sol = NormalizedPrice::from_micro_usd(sol_price);
+
+    Ok(prices)
+}
+
+/// Outcome of check_circuit_breaker
+pub struct BreakerCheck {
+    /// The vault is restricted (tripped now or earlier)
+    pub restricted: bool,
+    /// Event for the caller to emit when the breaker tripped on this read
+    pub tripped: Option<crate::CircuitBreakerEvent>,
+}
+
+/// Run the circuit breaker against freshly fetched BTC/ETH/SOL prices
+///
+/// While unrestricted, prices within `circuit_breaker_bps` of the checkpoint
+/// become the new checkpoint; a larger move trips the breaker and keeps the
+/// old checkpoint.
+pub fn check_circuit_breaker(
+    vault: &mut Vault,
+    vault_key: Pubkey,
+    prices: &[NormalizedPrice; 3],
+) -> BreakerCheck {
+    let unrestricted = BreakerCheck { restricted: false, tripped: None };
+    if vault.restricted() {
+        return BreakerCheck { restricted: true, tripped: None };
+    }
+    if vault.circuit_breaker_bps == 0 {
+        return unrestricted;
+    }
+
+    let current = prices.map(|p| p.price_usd);
+    let checkpoint = vault.price_checkpoint;
+
+    // First read after enabling or clearing seeds the checkpoint
+    if checkpoint.iter().any(|price| *price <= 0) {
+        vault.price_checkpoint = current;
+        return unrestricted;
+    }
+
+    if !moved_past_breaker(vault, &current) {
+        vault.price_checkpoint = current;
+        return unrestricted;
+    }
+
+    msg!("🚨 Circuit breaker tripped: {:?} -> {:?}", checkpoint, current);
+    vault.set_restricted(true);
+    BreakerCheck {
+        restricted: true,
+        tripped: Some(crate::CircuitBreakerEvent {
+            vault: vault_key,
+            seq: vault.next_event_seq(),
+            restricted: true,
+            checkpoint,
+            prices: current,
+        }),
+    }
+}
+
+/// Whether the vault would be restricted at `prices`, without updating it
+///
+/// Read-only counterpart of check_circuit_breaker for the preview instructions.
+pub fn would_restrict(vault: &Vault, prices: &[NormalizedPrice; 3]) -> bool {
+    if vault.restricted() {
+        return true;
+    }
+    if vault.circuit_breaker_bps == 0 || vault.price_checkpoint.iter().any(|price| *price <= 0) {
+        return false;
+    }
+    moved_past_breaker(vault, &prices.map(|p| p.price_usd))
+}
+
+/// Any price moved more than circuit_breaker_bps from the (seeded) checkpoint
+fn moved_past_breaker(vault: &Vault, current: &[i64; 3]) -> bool {
+    current.iter().zip(vault.price_checkpoint.iter()).any(|(now, then)| {
+        let move_bps = (*now as i128 - *then as i128).abs() * 10_000 / *then as i128;
+        move_bps > vault.circuit_breaker_bps as i128
+    })
+}
+
+/// Prices used for withdrawals while the vault is restricted
+///
+/// BTC and ETH are valued at the lower of checkpoint and current price, SOL
+/// (the payout currency) at the higher, so a withdrawing user never profits
+/// from the move that tripped the breaker.
+pub fn conservative_prices(vault: &Vault, prices: [NormalizedPrice; 3]) -> [NormalizedPrice; 3] {
+    // An unseeded checkpoint (0) falls back to the current price
+    let checkpoint = |i: usize| match vault.price_checkpoint[i] {
+        price if price > 0 => price,
+        _ => prices[i].price_usd,
+    };
+
+    [
+        NormalizedPrice::from_micro_usd(prices[0].price_usd.min(checkpoint(0))),
+        NormalizedPrice::from_micro_usd(prices[1].price_usd.min(checkpoint(1))),
+        NormalizedPrice::from_micro_usd(prices[2].price_usd.max(checkpoint(2))),
+    ]
+}
+
+/// Distinct accounts an Aggregated vault reads prices from
+pub fn aggregated_feed_accounts(vault: &Vault) -> Vec<Pubkey> {
+    let mut keys: Vec<Pubkey> = Vec::with_capacity(vault.price_feeds().len());
+    for feed in &vault.price_feeds() {
+        if !keys.contains(&feed.account) {
+            keys.push(feed.account);
+        }
+    }
+    keys
+}
+
+/// Whether `account` is a PriceCache owned by this program
+pub fn is_price_cache(account: &AccountInfo) -> bool {
+    account.owner == &crate::ID
+        && account
+            .try_borrow_data()
+            .map(|data| data.starts_with(PriceCache::DISCRIMINATOR))
+            .unwrap_or(false)
+}
+
+/// Number of price accounts passed after the mint/ATA pairs in deposit/withdraw
+///
+/// Either the PriceCache or the vault's `oracle_account_count` accounts.
+/// LST indexes read without the PriceCache also take their LstBasket, and
+/// vaults with a staking strategy take their StrategyValue. Per-asset priced
+/// vaults take their AssetOracles, a feed per asset, the SOL feed and the
+/// oracle adapter.
+pub fn price_account_count(vault: &Vault, remaining_accounts: &[AccountInfo]) -> usize {
+    let strategy_value = vault.marinade_strategy().is_some() as usize;
+    if vault.per_asset_pricing() {
+        return vault.assets().len() + 3 + strategy_value;
+    }
+
+    let cache_supplied = remaining_accounts
+        .get(vault.assets().len() * 2)
+        .is_some_and(is_price_cache);
+    let lst_basket = (vault.lst_index() && !cache_supplied) as usize;
+
+    let price_accounts = if cache_supplied { 1 } else { oracle_account_count(vault) };
+    lst_basket + strategy_value + price_accounts
+}
+
+/// Accounts the vault's price source reads, the oracle adapter included
+/// when it parses the source
+///
+/// - MockOracle: the mock oracle and the adapter
+/// - Switchboard: the SwitchboardConfig and the adapter
+/// - CrossChecked: the mock oracle, the SwitchboardConfig and the adapter
+/// - PythLazer: the PythLazerConfig, the instructions sysvar and the adapter
+/// - Aggregated: each distinct feed account and the adapter
+/// - Chainlink: the ChainlinkConfig (its prices were verified when posted)
+pub fn oracle_account_count(vault: &Vault) -> usize {
+    match vault.price_source() {
+        PriceSource::Chainlink => 1,
+        PriceSource::MockOracle | PriceSource::Switchboard => 2,
+        PriceSource::CrossChecked | PriceSource::PythLazer => 3,
+        PriceSource::Aggregated => aggregated_feed_accounts(vault).len() + 1,
+    }
+}
+
+/// Like `fetch_feed_prices`, but prefers the vault's PriceCache when one is
+/// passed in `remaining_accounts`. Cached prices older than
+/// MAX_PRICE_CACHE_AGE_SECS are rejected rather than silently bypassed.
+pub fn fetch_feed_prices_cached(
+    vault: &Vault,
+    accounts: &PriceAccounts,
+    clock: &Clock,
+    max_mock_age: i64,
+) -> Result<[NormalizedPrice; 3]> {
+    let Some(cache_account) = accounts
+        .remaining_accounts
+        .iter()
+        .find(|acc| is_price_cache(acc))
+    else {
+        return fetch_feed_prices(vault, accounts, clock, max_mock_age);
+    };
+
+    let cache_data = cache_account.try_borrow_data()?;
+    let cache = PriceCache::try_deserialize(&mut &cache_data[..])?;
+    require!(cache.vault == accounts.vault, VaultError::InvalidPrice);
+
+    let cache_age = clock.unix_timestamp - cache.last_update;
+    require!(cache_age < MAX_PRICE_CACHE_AGE_SECS, VaultError::StaleQuote);
+
+    msg!("🗄️  Using cached prices ({}s old)", cache_age);
+
+    Ok([cache.btc_price, cache.eth_price, cache.sol_price].map(NormalizedPrice::from_micro_usd))
+}
+
+/// Fetch the price of every vault asset, keyed by mint
+///
+/// Assets map onto the oracle feeds by position (BTC, ETH, SOL), so vaults
+/// with more than three assets cannot be priced yet.
+pub fn fetch_prices(
+    vault: &Vault,
+    accounts: &PriceAccounts,
+    clock: &Clock,
+    max_mock_age: i64,
+) -> Result<Vec<AssetPrice>> {
+    let feeds = fetch_feed_prices(vault, accounts, clock, max_mock_age)?;
+    require!(vault.assets().len() <= feeds.len(), VaultError::InvalidPrice);
+
+    Ok(vault
+        .assets()
+        .iter()
+        .zip(feeds)
+        .map(|(asset, price)| AssetPrice {
+            mint: asset.mint,
+            price,
+        })
+        .collect())
+}
+
+/// Look up the price for `mint` in the result of `fetch_prices`
+pub fn price_for_mint(prices: &[AssetPrice], mint: &Pubkey) -> Result<NormalizedPrice> {
+    prices
+        .iter()
+        .find(|p| p.mint == *mint)
+        .map(|p| p.price)
+        .ok_or(VaultError::InvalidPrice.into())
+}
@@ -0,0 +1,269 @@
+//! Rebalance planning
+//!
+//! Turns the vault's per-asset USD values into drifts from the target
+//! weights and the swap legs that correct them. rebalance and
+//! apply_confidential_rebalance execute the legs; simulate_rebalance returns
+//! the same plan without moving anything, so what a keeper previews is what
+//! the real instruction would trade at the same prices and balances.
+//!
+//! A vault's RebalanceConfig can cap turnover: the legs are scaled down
+//! together so one rebalance trades at most a set share of TVL, and drift
+//! left over is corrected by the next rebalances. A bad price print can then
+//! only churn that much of the portfolio at a time. The same config sets the
+//! dust limits: legs worth less than the minimum swap notional, or selling
+//! less than the source asset's dust threshold, are skipped and reported.
+
+use anchor_lang::prelude::*;
+
+use crate::math;
+
+/// Drift from target, in percentage points, above which an asset triggers a rebalance
+pub const REBALANCE_THRESHOLD_PCT: i64 = 5;
+
+/// Smallest leg worth swapping when the vault sets no minimum, in micro-USD ($1)
+pub const MIN_SWAP_USD: i64 = 1_000_000;
+
+/// Legs too small to be worth a swap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DustLimits<'a> {
+    /// Legs worth this many micro-USD or less are skipped
+    pub min_swap_usd: i64,
+    /// Per asset (vault.assets order): legs selling this many base units or
+    /// less of it are skipped; missing entries mean 0
+    pub min_amount_in: &'a [u64],
+}
+
+impl Default for DustLimits<'_> {
+    fn default() -> Self {
+        DustLimits {
+            min_swap_usd: MIN_SWAP_USD,
+            min_amount_in: &[],
+        }
+    }
+}
+
+/// One asset's distance from its target weight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Drift {
+    pub index: usize,
+    /// Current weight less target weight, in whole percentage points
+    pub drift_pct: i64,
+    /// Current USD value less target USD value (positive = over-allocated)
+    pub excess_usd: i64,
+}
+
+/// One planned swap from an over-allocated asset into an under-allocated one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Leg {
+    pub from: usize,
+    pub to: usize,
+    /// Value moved, in micro-USD
+    pub swap_usd: i64,
+    /// Amount of the `from` asset sold, in its base units
+    pub amount_in: u64,
+}
+
+/// Drift of every asset, given target weights (whole percent) and current
+/// USD values summing to `total_usd` (which must be positive)
+pub fn drifts(weights: &[u8], current_usds: &[i64], total_usd: i64) -> Vec<Drift> {
+    weights
+        .iter()
+        .zip(current_usds)
+        .enumerate()
+        .map(|(index, (weight, current_usd))| {
+            let target_usd = (total_usd * *weight as i64) / 100;
+            let current_pct = (current_usd * 100) / total_usd;
+            Drift {
+                index,
+                drift_pct: current_pct - *weight as i64,
+                excess_usd: current_usd - target_usd,
+            }
+        })
+        .collect()
+}
+
+/// Drifts of a hidden-weights rebalance: the capped trades the MXE revealed
+/// (micro-USD, positive to buy) as excess USD
+///
+/// The targets stay sealed, so drift_pct is unknown and left 0.
+pub fn drifts_from_trades(trades_usd: &[i64]) -> Vec<Drift> {
+    trades_usd
+        .iter()
+        .enumerate()
+        .map(|(index, trade_usd)| Drift {
+            index,
+            drift_pct: 0,
+            excess_usd: trade_usd.saturating_neg(),
+        })
+        .collect()
+}
+
+/// Whether any asset drifted past REBALANCE_THRESHOLD_PCT
+pub fn needs_rebalance(drifts: &[Drift]) -> bool {
+    drifts.iter().any(|drift| drift.drift_pct.abs() > REBALANCE_THRESHOLD_PCT)
+}
+
+/// Swap legs correcting `drifts`, in execution order
+///
+/// Every over-allocated asset sells into every under-allocated one the
+/// smaller of its excess and the other's deficit; `prices` are micro-USD per
+/// whole token. Dust is left in; see `split_dust`.
+pub fn legs(drifts: &[Drift], prices: &[i64], decimals: &[u8]) -> Result<Vec<Leg>> {
+    let mut legs = Vec::new();
+    for from in drifts.iter().filter(|drift| drift.excess_usd > 0) {
+        for to in drifts.iter().filter(|drift| drift.excess_usd < 0 && drift.index != from.index) {
+            let swap_usd = from.excess_usd.min(to.excess_usd.abs());
+            // swap_usd / from_price * 10^from_decimals
+            let amount_in = math::to_u64(math::mul_div_floor(
+                math::to_u128(swap_usd)?,
+                math::pow10(decimals[from.index])?,
+                math::to_u128(prices[from.index])?,
+            )?)?;
+            legs.push(Leg {
+                from: from.index,
+                to: to.index,
+                swap_usd,
+                amount_in,
+            });
+        }
+    }
+    Ok(legs)
+}
+
+/// Most USD one rebalance may trade: `max_turnover_bps` of `total_usd`
+/// (None when the cap is 0, i.e. off)
+pub fn max_turnover_usd(total_usd: i64, max_turnover_bps: u16) -> Result<Option<i64>> {
+    if max_turnover_bps == 0 {
+        return Ok(None);
+    }
+    let cap = math::mul_div_floor(math::to_u128(total_usd)?, max_turnover_bps as u128, 10_000)?;
+    Ok(Some(math::to_i64(cap)?))
+}
+
+/// Scale `legs` down together so they trade at most `max_turnover_usd`
+///
+/// Every leg keeps its share of the total, so some may shrink to dust.
+pub fn cap_turnover(legs: Vec<Leg>, max_turnover_usd: i64) -> Result<Vec<Leg>> {
+    let turnover: i64 = legs.iter().map(|leg| leg.swap_usd).sum();
+    if turnover <= max_turnover_usd {
+        return Ok(legs);
+    }
+    let mut capped = Vec::with_capacity(legs.len());
+    for leg in legs {
+        let swap_usd = math::to_i64(math::mul_div_floor(
+            math::to_u128(leg.swap_usd)?,
+            math::to_u128(max_turnover_usd)?,
+            math::to_u128(turnover)?,
+        )?)?;
+        let amount_in = math::to_u64(math::mul_div_floor(
+            leg.amount_in as u128,
+            math::to_u128(swap_usd)?,
+            math::to_u128(leg.swap_usd)?,
+        )?)?;
+        capped.push(Leg {
+            swap_usd,
+            amount_in,
+            ..leg
+        });
+    }
+    Ok(capped)
+}
+
+/// Split `legs` into those worth swapping and the dust below `limits`,
+/// both in execution order
+pub fn split_dust(legs: Vec<Leg>, limits: &DustLimits) -> (Vec<Leg>, Vec<Leg>) {
+    legs.into_iter().partition(|leg| {
+        let min_amount_in = limits.min_amount_in.get(leg.from).copied().unwrap_or(0);
+        leg.swap_usd > limits.min_swap_usd && leg.amount_in > min_amount_in
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drifts_and_threshold() {
+        // $60 / $30 / $10 against 40 / 30 / 30
+        let drifts = drifts(&[40, 30, 30], &[60_000_000, 30_000_000, 10_000_000], 100_000_000);
+        assert_eq!(
+            drifts.iter().map(|d| (d.drift_pct, d.excess_usd)).collect::<Vec<_>>(),
+            vec![(20, 20_000_000), (0, 0), (-20, -20_000_000)]
+        );
+        assert!(needs_rebalance(&drifts));
+
+        let within = super::drifts(&[50, 50], &[52_000_000, 48_000_000], 100_000_000);
+        assert!(!needs_rebalance(&within));
+    }
+
+    #[test]
+    fn test_drifts_from_trades() {
+        // Sell $10 of BTC, hold ETH, buy $10 of SOL: the same legs as the plaintext drifts
+        let drifts = drifts_from_trades(&[-10_000_000, 0, 10_000_000]);
+        assert_eq!(
+            drifts.iter().map(|d| (d.drift_pct, d.excess_usd)).collect::<Vec<_>>(),
+            vec![(0, 10_000_000), (0, 0), (0, -10_000_000)]
+        );
+        let planned = legs(&drifts, &[60_000_000_000, 3_000_000_000, 200_000_000], &[9, 9, 9]).unwrap();
+        assert_eq!(
+            planned.iter().map(|leg| (leg.from, leg.to, leg.swap_usd)).collect::<Vec<_>>(),
+            vec![(0, 2, 10_000_000)]
+        );
+    }
+
+    #[test]
+    fn test_legs() {
+        // BTC $60k over by $20, SOL $200 under by $20: sell $20 of BTC
+        let drifts = drifts(&[40, 30, 30], &[60_000_000, 30_000_000, 10_000_000], 100_000_000);
+        let planned = legs(&drifts, &[60_000_000_000, 3_000_000_000, 200_000_000], &[9, 9, 9]).unwrap();
+        assert_eq!(
+            planned,
+            vec![Leg {
+                from: 0,
+                to: 2,
+                swap_usd: 20_000_000,
+                amount_in: 333_333,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_split_dust() {
+        // $0.90 and $20 legs against the default $1 minimum
+        let small = Leg { from: 0, to: 1, swap_usd: 900_000, amount_in: 900 };
+        let large = Leg { from: 1, to: 2, swap_usd: 20_000_000, amount_in: 20_000 };
+        let (kept, dust) = split_dust(vec![small, large], &DustLimits::default());
+        assert_eq!(kept, vec![large]);
+        assert_eq!(dust, vec![small]);
+
+        // A lower minimum keeps the small leg; a dust threshold on asset 1 drops the large one
+        let limits = DustLimits { min_swap_usd: 500_000, min_amount_in: &[0, 20_000] };
+        let (kept, dust) = split_dust(vec![small, large], &limits);
+        assert_eq!(kept, vec![small]);
+        assert_eq!(dust, vec![large]);
+    }
+
+    #[test]
+    fn test_turnover_cap() {
+        // $60 over-allocated into two $30 deficits of a $300 vault
+        let legs = vec![
+            Leg { from: 0, to: 1, swap_usd: 30_000_000, amount_in: 300 },
+            Leg { from: 0, to: 2, swap_usd: 30_000_000, amount_in: 300 },
+        ];
+        assert_eq!(max_turnover_usd(300_000_000, 0).unwrap(), None);
+        assert_eq!(cap_turnover(legs.clone(), 60_000_000).unwrap(), legs);
+
+        // 10% of TVL: each leg trades $15 of its $30
+        let cap = max_turnover_usd(300_000_000, 1_000).unwrap().unwrap();
+        assert_eq!(cap, 30_000_000);
+        let capped = cap_turnover(legs.clone(), cap).unwrap();
+        assert_eq!(
+            capped.iter().map(|leg| (leg.swap_usd, leg.amount_in)).collect::<Vec<_>>(),
+            vec![(15_000_000, 150), (15_000_000, 150)]
+        );
+
+        // A cap that leaves each leg at $1 turns it all into dust
+        let capped = cap_turnover(legs, 2_000_000).unwrap();
+        assert!(split_dust(capped, &DustLimits::default()).0.is_empty());
+    }
+}
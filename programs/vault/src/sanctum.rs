@@ -0,0 +1,104 @@
+//! Sanctum router swap backend
+//!
+//! Converts between SOL and liquid staking tokens, and between LSTs, through
+//! Sanctum's router. The router goes through the stake pools' own deposit
+//! and withdraw instructions instead of an AMM, so conversions happen at the
+//! pools' exchange rates less their fees:
+//! - SOL -> LST: StakeWrappedSol (deposits wrapped SOL into the LST's pool)
+//! - LST -> LST: SwapViaStake (withdraws a stake account from one pool and
+//!   deposits it into the other)
+//! - LST -> SOL: WithdrawWrappedSol
+//!
+//! The pool-specific accounts that follow the router's own accounts depend
+//! on the pools involved, so routes are built off-chain and passed through
+//! as-is. In every route the user (the vault PDA) comes first, the source
+//! token account second and the destination token account third.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Sanctum router program (same ID on mainnet and devnet)
+pub const SANCTUM_ROUTER_PROGRAM_ID: Pubkey = pubkey!("stkitrT1Uoy18Dk1fTrgPw8W6MVzoCfYoAFT4MLsmhq");
+
+/// Leading accounts of every route: [user, source token account, destination token account]
+pub const ROUTE_PREFIX_ACCOUNTS: usize = 3;
+
+// Router instruction discriminators (one byte)
+const STAKE_WRAPPED_SOL_DISCRIMINATOR: u8 = 0;
+const SWAP_VIA_STAKE_DISCRIMINATOR: u8 = 1;
+const WITHDRAW_WRAPPED_SOL_DISCRIMINATOR: u8 = 8;
+
+// Seed of SwapViaStake's bridge stake account. The destination pool absorbs
+// the bridge stake within the same instruction, so one seed can be reused.
+const BRIDGE_STAKE_SEED: u32 = 0;
+
+/// Router instruction used for a conversion
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Route {
+    StakeWrappedSol,
+    SwapViaStake,
+    WithdrawWrappedSol,
+}
+
+impl Route {
+    /// Route for a conversion; SOL to SOL is not a conversion (None)
+    pub fn between(from_sol: bool, to_sol: bool) -> Option<Self> {
+        match (from_sol, to_sol) {
+            (true, false) => Some(Self::StakeWrappedSol),
+            (false, false) => Some(Self::SwapViaStake),
+            (false, true) => Some(Self::WithdrawWrappedSol),
+            (true, true) => None,
+        }
+    }
+
+    /// Router instruction data converting `amount` of the source token
+    pub fn instruction_data(&self, amount: u64) -> Vec<u8> {
+        let mut data = Vec::with_capacity(1 + 8 + 4);
+        match self {
+            Self::StakeWrappedSol => data.push(STAKE_WRAPPED_SOL_DISCRIMINATOR),
+            Self::SwapViaStake => data.push(SWAP_VIA_STAKE_DISCRIMINATOR),
+            Self::WithdrawWrappedSol => data.push(WITHDRAW_WRAPPED_SOL_DISCRIMINATOR),
+        }
+        data.extend_from_slice(&amount.to_le_bytes());
+        if *self == Self::SwapViaStake {
+            data.extend_from_slice(&BRIDGE_STAKE_SEED.to_le_bytes());
+        }
+        data
+    }
+}
+
+/// Convert exactly `amount` through the router
+///
+/// `accounts` is the route after the router program. Accounts keep the
+/// writability they were passed with; only `user` signs. Slippage is checked
+/// by the caller on the destination balance.
+pub fn swap<'info>(
+    route: Route,
+    router_program: &AccountInfo<'info>,
+    user: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let account_metas = accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: account.key(),
+            is_signer: account.key() == user.key(),
+            is_writable: account.is_writable,
+        })
+        .collect();
+
+    let ix = Instruction {
+        program_id: SANCTUM_ROUTER_PROGRAM_ID,
+        accounts: account_metas,
+        data: route.instruction_data(amount),
+    };
+
+    let mut account_infos = accounts.to_vec();
+    account_infos.push(router_program.clone());
+    invoke_signed(&ix, &account_infos, signer_seeds)?;
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! Metaplex Token Metadata for share mints
+//!
+//! Wallets and explorers read a token's name, symbol and logo URI from its
+//! Metaplex metadata account. The mpl-token-metadata crate pins its own
+//! Solana SDK, so the two instructions the vault needs are encoded here.
+//! The vault PDA is both mint authority and update authority of its shares.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::VaultError;
+
+/// Metaplex Token Metadata program (same ID on mainnet and devnet)
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Limits enforced by the Token Metadata program
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+
+// Instruction discriminators (single byte, Shank-style)
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+const UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+
+/// Metadata PDA of `mint`: ["metadata", program, mint]
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Name, symbol and URI shown for a share token
+pub struct ShareMetadata<'a> {
+    pub name: &'a str,
+    pub symbol: &'a str,
+    pub uri: &'a str,
+}
+
+impl ShareMetadata<'_> {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            !self.name.is_empty()
+                && self.name.len() <= MAX_NAME_LENGTH
+                && !self.symbol.is_empty()
+                && self.symbol.len() <= MAX_SYMBOL_LENGTH
+                && self.uri.len() <= MAX_URI_LENGTH,
+            VaultError::InvalidShareMetadata
+        );
+        Ok(())
+    }
+
+    /// Borsh DataV2: name, symbol, uri, no royalties, creators, collection or uses
+    fn data_v2(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(4 * 3 + self.name.len() + self.symbol.len() + self.uri.len() + 5);
+        for field in [self.name, self.symbol, self.uri] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data.extend_from_slice(&0u16.to_le_bytes()); // seller_fee_basis_points
+        data.push(0); // creators: None
+        data.push(0); // collection: None
+        data.push(0); // uses: None
+        data
+    }
+}
+
+/// Accounts shared by the create and update instructions
+pub struct ShareMetadataAccounts<'a, 'info> {
+    pub metadata_program: &'a AccountInfo<'info>,
+    pub metadata: &'a AccountInfo<'info>,
+    pub mint: &'a AccountInfo<'info>,
+    /// Vault PDA: mint authority and update authority
+    pub authority: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub rent: &'a AccountInfo<'info>,
+}
+
+/// Create the metadata account, or update it if the mint already has one
+pub fn create_or_update(
+    accounts: &ShareMetadataAccounts,
+    metadata: &ShareMetadata,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    metadata.validate()?;
+
+    let ix = if accounts.metadata.data_is_empty() {
+        // Args: data, is_mutable, collection_details
+        let mut data = vec![CREATE_METADATA_ACCOUNT_V3];
+        data.extend(metadata.data_v2());
+        data.push(1); // is_mutable
+        data.push(0); // collection_details: None
+
+        Instruction {
+            program_id: TOKEN_METADATA_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.metadata.key(), false),
+                AccountMeta::new_readonly(accounts.mint.key(), false),
+                AccountMeta::new_readonly(accounts.authority.key(), true),
+                AccountMeta::new(accounts.payer.key(), true),
+                AccountMeta::new_readonly(accounts.authority.key(), true),
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+                AccountMeta::new_readonly(accounts.rent.key(), false),
+            ],
+            data,
+        }
+    } else {
+        // Args: Option<data>, new_update_authority, primary_sale_happened, is_mutable
+        let mut data = vec![UPDATE_METADATA_ACCOUNT_V2, 1];
+        data.extend(metadata.data_v2());
+        data.extend_from_slice(&[0, 0, 0]);
+
+        Instruction {
+            program_id: TOKEN_METADATA_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.metadata.key(), false),
+                AccountMeta::new_readonly(accounts.authority.key(), true),
+            ],
+            data,
+        }
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            accounts.metadata.clone(),
+            accounts.mint.clone(),
+            accounts.authority.clone(),
+            accounts.payer.clone(),
+            accounts.system_program.clone(),
+            accounts.rent.clone(),
+            accounts.metadata_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_v2_layout() {
+        let metadata = ShareMetadata {
+            name: "DeFi Blue Chip Index",
+            symbol: "DBCI",
+            uri: "",
+        };
+        let data = metadata.data_v2();
+        assert_eq!(&data[..4], &20u32.to_le_bytes());
+        assert_eq!(&data[4..24], b"DeFi Blue Chip Index");
+        assert_eq!(&data[24..28], &4u32.to_le_bytes());
+        assert_eq!(&data[28..32], b"DBCI");
+        // Empty URI, zero royalties, no creators/collection/uses
+        assert_eq!(&data[32..], &[0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(metadata.validate().is_ok());
+
+        let too_long = ShareMetadata { symbol: "TOOLONGSYMBOL", ..metadata };
+        assert!(too_long.validate().is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//! Ed25519-signed mock oracle updates
+//!
+//! update_mock_oracle_signed lets anyone land prices the oracle authority (or
+//! one of its feeders) signed off-chain, so a relaying bot never holds the
+//! key. The transaction carries an Ed25519 program instruction right before
+//! it, verifying one signature over `message`; the vault program reads that
+//! instruction back through the instructions sysvar and checks it signed this
+//! exact payload (see `oracle_adapter::ed25519`, re-exported here).
+//!
+//! The message names the oracle, so a signature can't be replayed against
+//! another one, and carries the signing time, which must be newer than the
+//! oracle's last update and at most MAX_SIGNED_PRICE_AGE_SECS old.
+
+use anchor_lang::prelude::*;
+
+pub use oracle_adapter::ed25519::{ed25519_instruction_data, verified_message};
+
+/// Oldest signed payload update_mock_oracle_signed accepts
+pub const MAX_SIGNED_PRICE_AGE_SECS: i64 = 60;
+
+/// Prefix of every signed price message
+pub const MESSAGE_DOMAIN: &[u8] = b"etf-defi:mock-oracle:v1";
+
+/// Length of `message`: domain, oracle, three prices and the signing time
+pub const MESSAGE_LEN: usize = MESSAGE_DOMAIN.len() + 32 + 4 * 8;
+
+/// Bytes the oracle authority signs for a price update (micro-USD prices)
+pub fn message(oracle: &Pubkey, btc_price: i64, eth_price: i64, sol_price: i64, signed_at: i64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(MESSAGE_LEN);
+    message.extend_from_slice(MESSAGE_DOMAIN);
+    message.extend_from_slice(oracle.as_ref());
+    for value in [btc_price, eth_price, sol_price, signed_at] {
+        message.extend_from_slice(&value.to_le_bytes());
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_layout() {
+        let oracle = Pubkey::new_unique();
+        let message = message(&oracle, 65_000_000_000, 3_000_000_000, 150_000_000, 1_700_000_000);
+        assert_eq!(message.len(), MESSAGE_LEN);
+        assert!(message.starts_with(MESSAGE_DOMAIN));
+        assert_eq!(&message[MESSAGE_DOMAIN.len()..MESSAGE_DOMAIN.len() + 32], oracle.as_ref());
+        assert_eq!(message[MESSAGE_LEN - 8..], 1_700_000_000i64.to_le_bytes());
+    }
+}
@@ -0,0 +1,140 @@
+//! Soulbound (non-transferable) vault shares
+//!
+//! A vault created with `soulbound` issues its shares from a Token-2022 mint
+//! with the NonTransferable extension; every other vault's share mint is a
+//! plain SPL Token mint. Token-2022 rejects any transfer of a
+//! non-transferable mint's tokens, so holders can't move soulbound shares to
+//! another wallet, while the vault, as mint authority, still mints and burns
+//! them. Where the vault itself moves shares between accounts (batch
+//! custody), `transfer` burns them from one and mints them to the other.
+//! Share mints have no freeze authority either way.
+//!
+//! Instructions that touch shares take the mint's program as
+//! `share_token_program`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount};
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::state::Mint as Token2022Mint;
+use anchor_spl::token_interface::{
+    self, Burn, InitializeMint2, MintTo, NonTransferableMintInitialize, TransferChecked,
+};
+
+use crate::VaultError;
+
+/// Share decimals, high for precision in share calculations
+pub const SHARE_DECIMALS: u8 = 9;
+
+/// Whether `mint` is the share mint of a soulbound vault
+pub fn is_soulbound(mint: &AccountInfo) -> bool {
+    *mint.owner == anchor_spl::token_2022::ID
+}
+
+/// Create and initialize the share mint PDA at `mint`, signed with
+/// `mint_seeds`: a non-transferable Token-2022 mint when `soulbound`, an SPL
+/// Token mint otherwise, with `vault` as mint authority
+#[allow(clippy::too_many_arguments)]
+pub fn create_share_mint<'info>(
+    soulbound: bool,
+    mint: &AccountInfo<'info>,
+    mint_seeds: &[&[u8]],
+    vault: &Pubkey,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_2022_program: Option<&AccountInfo<'info>>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let (program, space) = if soulbound {
+        let program = token_2022_program.ok_or(error!(VaultError::InvalidRemainingAccounts))?;
+        let space = ExtensionType::try_calculate_account_len::<Token2022Mint>(&[ExtensionType::NonTransferable])?;
+        (program, space)
+    } else {
+        (token_program, anchor_spl::token::Mint::LEN)
+    };
+    create_pda(mint, mint_seeds, space, program.key, payer, system_program)?;
+
+    if soulbound {
+        let accounts = NonTransferableMintInitialize {
+            token_program_id: program.clone(),
+            mint: mint.clone(),
+        };
+        token_interface::non_transferable_mint_initialize(CpiContext::new(program.clone(), accounts))?;
+    }
+    let accounts = InitializeMint2 { mint: mint.clone() };
+    token_interface::initialize_mint2(
+        CpiContext::new(program.clone(), accounts),
+        SHARE_DECIMALS,
+        vault,
+        None,
+    )
+}
+
+/// transfer_checked of shares, done as a burn from `from` (signed as in
+/// `ctx`) and a mint to `to` (signed by `vault` with `vault_seeds`) when
+/// the shares are soulbound
+pub fn transfer<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    vault: &AccountInfo<'info>,
+    vault_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if !is_soulbound(&ctx.accounts.mint) {
+        return token_interface::transfer_checked(ctx, amount, SHARE_DECIMALS);
+    }
+    let (program, seeds) = (ctx.program.clone(), ctx.signer_seeds);
+    let TransferChecked { from, mint, to, authority } = ctx.accounts;
+    let burn = Burn {
+        mint: mint.clone(),
+        from,
+        authority,
+    };
+    token_interface::burn(CpiContext::new_with_signer(program.clone(), burn, seeds), amount)?;
+    let mint_to = MintTo {
+        mint,
+        to,
+        authority: vault.clone(),
+    };
+    token_interface::mint_to(CpiContext::new_with_signer(program, mint_to, vault_seeds), amount)
+}
+
+/// Create a `space`-byte account owned by `owner` at the PDA `account`,
+/// including one that already holds lamports
+fn create_pda<'info>(
+    account: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+    owner: &Pubkey,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let signer = [seeds];
+    let rent = Rent::get()?.minimum_balance(space);
+    let current = account.lamports();
+    if current == 0 {
+        let accounts = CreateAccount {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        return system_program::create_account(
+            CpiContext::new_with_signer(system_program.clone(), accounts, &signer),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+    if current < rent {
+        let accounts = system_program::Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        system_program::transfer(CpiContext::new(system_program.clone(), accounts), rent - current)?;
+    }
+    let accounts = Allocate {
+        account_to_allocate: account.clone(),
+    };
+    system_program::allocate(CpiContext::new_with_signer(system_program.clone(), accounts, &signer), space as u64)?;
+    let accounts = Assign {
+        account_to_assign: account.clone(),
+    };
+    system_program::assign(CpiContext::new_with_signer(system_program.clone(), accounts, &signer), owner)
+}
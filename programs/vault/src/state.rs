@@ -1,36 +1,1716 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use bytemuck::{Pod, Zeroable};
 
-use crate::PriceSource;
+use crate::math;
+use crate::swap::MockPoolConfig;
+use crate::{NormalizedPrice, PriceSource, QuoteCurrency, SwapBackend, VaultOperation};
+
+/// Most assets a vault's composition can hold
+pub const MAX_ASSETS: usize = 32;
+/// Longest vault name, in bytes
+pub const MAX_NAME_LEN: usize = 32;
+/// Assets of a basket priced from the BTC/ETH/SOL oracle markets; vaults
+/// with any other count price each asset through their AssetOracles
+pub const ORACLE_MARKETS: usize = 3;
 
 /// Multi-asset vault account that stores composition, shares, and asset allocations
 /// This is the core PDA for each unique vault instance
+///
+/// Zero-copy layout: fixed-capacity arrays instead of Vecs and Strings, raw
+/// keys and flags instead of Options, enums and bools (see the accessors).
+/// Fields are grouped by alignment so the struct has no padding. Instructions
+/// take `AccountLoader<Vault>` and read it in place through load()/load_mut().
+/// The runtime borrow-checks every account passed to a CPI, so a handler must
+/// drop its Ref/RefMut before any CPI the vault takes part in and load again
+/// afterwards; vault-signed CPIs take their seeds from signer(), which owns
+/// them.
+///
+/// The layout ends in `reserved`: a new field takes its bytes from there
+/// (they are zero in every existing vault), so it needs no realloc. Once
+/// they run out, a new field has to grow the account, which means a
+/// VAULT_VERSION bump and a migrate_vault step that reallocs existing vaults
+/// (paying the extra rent) before the program reads them at the new size.
+/// Each flag gets its own field rather than a spare bit of another.
+#[account(zero_copy)]
+pub struct Vault {
+    /// TVL in micro-dollars as of the last deposit, withdrawal or NAV snapshot
+    pub last_tvl_usd: i64,
+    /// Share price in micro-dollars as of the last deposit, withdrawal or NAV snapshot
+    pub last_share_price: i64,
+    /// Share mint supply after the last instruction that minted or burned shares
+    pub total_shares: u64,
+    /// Unix timestamp last_tvl_usd and last_share_price were computed
+    pub last_nav_update: i64,
+    /// Sequence number of the last event emitted for this vault
+    pub event_seq: u64,
+    /// Deposit fee lamports not yet split between protocol and creator
+    pub pending_fees: u64,
+    /// Fee lamports owed to the protocol treasury
+    pub protocol_fees_owed: u64,
+    /// Fee lamports owed to the vault creator (admin)
+    pub creator_fees_owed: u64,
+    /// Fee lamports owed to share stakers, moved to the stake pool by distribute_staking_rewards
+    pub staker_fees_owed: u64,
+    /// Shares in one creation unit for authorized participants (0 = AP creation disabled)
+    pub creation_unit_shares: u64,
+    /// End of the wind-down grace period (0 = not winding down); once set,
+    /// deposits stay frozen and close_vault is allowed after this time
+    pub wind_down_deadline: i64,
+    /// Unix timestamp the share price changes were last updated
+    pub performance_updated_at: i64,
+    /// Arcium computation queued by rebalance_confidential and not yet applied
+    /// (valid when has_pending_computation is set)
+    pub pending_computation_offset: u64,
+    /// Mock swap pool liquidity on each side in micro-USD (0 = no price impact)
+    pub mock_pool_depth_usd: u64,
+    /// Last BTC/ETH/SOL prices that passed the circuit breaker, in micro-USD
+    pub price_checkpoint: [i64; 3],
+    /// TWAP window in seconds for MockOracle prices (0 = use the latest price)
+    pub twap_window_secs: u32,
+    /// Seconds newly minted shares stay locked before they can be redeemed (0 = no lockup)
+    pub lockup_secs: u32,
+    /// Seconds over which the exit fee decays linearly to zero
+    pub exit_fee_window_secs: u32,
+    /// Share price change over the trailing 7 days, in bps (updated by snapshot_nav)
+    pub share_price_change_7d_bps: i32,
+    /// Share price change over the trailing 30 days, in bps (updated by snapshot_nav)
+    pub share_price_change_30d_bps: i32,
+    /// Maximum spread between feeds of one asset, in bps of their median
+    pub max_divergence_bps: u16,
+    /// Maximum BTC/ETH/SOL move from price_checkpoint before the vault restricts itself (0 = off)
+    pub circuit_breaker_bps: u16,
+    /// Exit fee charged right after a deposit, in bps; kept in the vault for remaining holders
+    pub exit_fee_bps: u16,
+    /// Fee taken from each deposit, in bps; kept in the vault minus the referral share
+    pub deposit_fee_bps: u16,
+    /// Share of the deposit fee credited to the depositor's referrer, in bps of the fee
+    pub referral_share_bps: u16,
+    /// Share of deposit fees paid to share stakers, in bps (taken before the protocol/creator split)
+    pub staker_fee_share_bps: u16,
+    /// Maximum shortfall of a swap's output from its oracle quote, in bps
+    pub max_slippage_bps: u16,
+    /// Mock swap pool fee taken from the input, in bps
+    pub mock_pool_fee_bps: u16,
+    /// Bump seed for the vault PDA
+    pub bump: u8,
+    /// Bytes of `name` in use
+    pub name_len: u8,
+    /// Slots of `assets` in use
+    pub num_assets: u8,
+    /// Slots of `price_feeds` in use
+    pub num_price_feeds: u8,
+    /// PriceSource, as its discriminant
+    pub price_source: u8,
+    /// SwapBackend, as its discriminant
+    pub swap_backend: u8,
+    /// Soft-closed vaults reject new deposits; withdrawals and migration
+    /// stay open. See soft_closed()
+    pub soft_closed: u8,
+    /// Set when the circuit breaker trips; deposits blocked and withdrawals
+    /// priced conservatively until the admin clears it
+    pub restricted: u8,
+    /// When set, governed instructions can only be executed by the governance
+    pub governance_mode: u8,
+    /// Whether pending_computation_offset holds a queued computation
+    pub has_pending_computation: u8,
+    /// Layout version (VAULT_VERSION once created or migrated)
+    pub version: u8,
+    /// Admin/authority that can manage this vault (rebalance, update composition, etc.)
+    pub admin: Pubkey,
+    /// SPL mint for vault shares (each vault has unique shares)
+    pub vault_token_mint: Pubkey,
+    /// Marinade strategy PDA for SOL staking (default key = none)
+    pub marinade_strategy: Pubkey,
+    /// Mock oracle account used when price_source = MockOracle (default key = none)
+    pub mock_oracle: Pubkey,
+    /// Successor vault suggested to holders when this product is deprecated (default key = none)
+    pub successor: Pubkey,
+    /// Governance authority (e.g. an SPL Governance PDA) for governed instructions (default key = none)
+    pub governance: Pubkey,
+    /// Unique vault identifier/name (e.g., "MVPVault", "AggressiveGrowth"), UTF-8
+    pub name: [u8; MAX_NAME_LEN],
+    /// Basket composition: the first num_assets slots are in use
+    pub assets: [AssetConfig; MAX_ASSETS],
+    /// Feeds read when price_source = Aggregated (up to 3 per asset); the
+    /// first num_price_feeds slots are in use
+    pub price_feeds: [PriceFeedSlot; MAX_PRICE_FEEDS],
+    /// Set when a MarketCapIndex drives the target weights; update_composition
+    /// is rejected and recompute_weights is the only way they change
+    pub market_cap_weighted: u8,
+    /// Set while the vault is an LST index: assets are priced from the SOL
+    /// feed and the exchange rates in its LstBasket
+    pub lst_index: u8,
+    /// Where version 2 kept min_deposit_shares (little-endian); zero since
+    /// migrate_vault moved it, and free for a future field
+    pub v2_min_deposit_shares: [u8; 4],
+    /// VaultOperation holding the vault, as its discriminant (0 = idle);
+    /// takes the last padding byte, so the account size stays a multiple of 8
+    pub operation: u8,
+    /// Fewest shares a deposit may mint (0 = any nonzero amount)
+    pub min_deposit_shares: u32,
+    /// Set by init_vault until finalize_vault checks the composition; see
+    /// finalized()
+    pub unfinalized: u8,
+    /// Shares come from a non-transferable Token-2022 mint, fixed at creation
+    pub soulbound: u8,
+    /// Set by guardian_pause until resume_rebalancing
+    pub rebalancing_paused: u8,
+    /// Set by guardian_pause until resume_rebalancing; unlike restricted,
+    /// withdrawal pricing is unaffected
+    pub guardian_deposits_paused: u8,
+    /// set_hidden_weights sealed the target weights to the rebalancing MXE
+    pub weights_hidden: u8,
+    /// Zero bytes for future fields
+    pub reserved: [u8; 31],
+}
+
+/// Current Vault layout version, written by create_vault and migrate_vault
+/// (2: zero-copy layout, 3: a field per flag and the reserved tail)
+pub const VAULT_VERSION: u8 = 3;
+
+/// Owned copy of the vault PDA's seeds; see Vault::signer()
+pub struct VaultSigner {
+    admin: Pubkey,
+    name: [u8; MAX_NAME_LEN],
+    name_len: usize,
+    bump: [u8; 1],
+}
+
+impl VaultSigner {
+    pub fn seeds(&self) -> [&[u8]; 4] {
+        [
+            b"vault",
+            self.admin.as_ref(),
+            &self.name[..self.name_len],
+            &self.bump,
+        ]
+    }
+}
+
+/// Leading fields of the Borsh layout vaults used before version 2
+///
+/// migrate_vault reads these to check the vault's seeds before converting it.
+#[derive(AnchorDeserialize)]
+pub struct VaultPrefix {
+    pub bump: u8,
+    pub admin: Pubkey,
+    pub name: String,
+    pub vault_token_mint: Pubkey,
+    pub assets: Vec<AssetConfig>,
+}
+
+/// Borsh layout of Vault before version 2, decoded only by migrate_vault
+///
+/// Fields were only ever appended, so a version 0 vault decodes once its data
+/// is zero-extended; fields it lacked read as zero.
+#[derive(AnchorDeserialize)]
+pub struct LegacyVault {
+    pub bump: u8,
+    pub admin: Pubkey,
+    pub name: String,
+    pub vault_token_mint: Pubkey,
+    pub assets: Vec<AssetConfig>,
+    pub marinade_strategy: Option<Pubkey>,
+    pub price_source: PriceSource,
+    pub mock_oracle: Option<Pubkey>,
+    pub successor: Option<Pubkey>,
+    pub soft_closed: bool,
+    pub pending_computation_offset: Option<u64>,
+    pub twap_window_secs: u32,
+    pub price_feeds: Vec<PriceFeed>,
+    pub max_divergence_bps: u16,
+    pub circuit_breaker_bps: u16,
+    pub price_checkpoint: [i64; 3],
+    pub restricted: bool,
+    pub lockup_secs: u32,
+    pub exit_fee_bps: u16,
+    pub exit_fee_window_secs: u32,
+    pub share_price_change_7d_bps: i32,
+    pub share_price_change_30d_bps: i32,
+    pub performance_updated_at: i64,
+    pub last_tvl_usd: i64,
+    pub last_share_price: i64,
+    pub total_shares: u64,
+    pub last_nav_update: i64,
+    pub event_seq: u64,
+    pub deposit_fee_bps: u16,
+    pub referral_share_bps: u16,
+    pub pending_fees: u64,
+    pub protocol_fees_owed: u64,
+    pub creator_fees_owed: u64,
+    pub governance: Option<Pubkey>,
+    pub governance_mode: bool,
+    pub staker_fee_share_bps: u16,
+    pub staker_fees_owed: u64,
+    pub swap_backend: SwapBackend,
+    pub max_slippage_bps: u16,
+    pub mock_pool: MockPoolConfig,
+    pub creation_unit_shares: u64,
+    pub wind_down_deadline: i64,
+    pub version: u8,
+}
+
+impl LegacyVault {
+    /// The vault in the current layout; fields a version 0 vault lacked and
+    /// whose zero is not a valid setting get their create_vault defaults
+    pub fn upgrade(&self) -> Result<Vault> {
+        let mut vault = Vault::zeroed();
+        vault.bump = self.bump;
+        vault.admin = self.admin;
+        vault.set_name(&self.name)?;
+        vault.vault_token_mint = self.vault_token_mint;
+        vault.set_assets(&self.assets)?;
+        vault.set_marinade_strategy(self.marinade_strategy);
+        vault.set_price_source(self.price_source);
+        vault.set_mock_oracle(self.mock_oracle);
+        vault.set_successor(self.successor);
+        vault.set_soft_closed(self.soft_closed);
+        vault.set_pending_computation_offset(self.pending_computation_offset);
+        vault.twap_window_secs = self.twap_window_secs;
+        vault.set_price_feeds(&self.price_feeds)?;
+        vault.max_divergence_bps = self.max_divergence_bps;
+        vault.circuit_breaker_bps = self.circuit_breaker_bps;
+        vault.price_checkpoint = self.price_checkpoint;
+        vault.set_restricted(self.restricted);
+        vault.lockup_secs = self.lockup_secs;
+        vault.exit_fee_bps = self.exit_fee_bps;
+        vault.exit_fee_window_secs = self.exit_fee_window_secs;
+        vault.share_price_change_7d_bps = self.share_price_change_7d_bps;
+        vault.share_price_change_30d_bps = self.share_price_change_30d_bps;
+        vault.performance_updated_at = self.performance_updated_at;
+        vault.last_tvl_usd = self.last_tvl_usd;
+        vault.last_share_price = self.last_share_price;
+        vault.total_shares = self.total_shares;
+        vault.last_nav_update = self.last_nav_update;
+        vault.event_seq = self.event_seq;
+        vault.deposit_fee_bps = self.deposit_fee_bps;
+        vault.referral_share_bps = self.referral_share_bps;
+        vault.pending_fees = self.pending_fees;
+        vault.protocol_fees_owed = self.protocol_fees_owed;
+        vault.creator_fees_owed = self.creator_fees_owed;
+        vault.set_governance(self.governance);
+        vault.set_governance_mode(self.governance_mode);
+        vault.staker_fee_share_bps = self.staker_fee_share_bps;
+        vault.staker_fees_owed = self.staker_fees_owed;
+        vault.set_swap_backend(self.swap_backend);
+        vault.max_slippage_bps = self.max_slippage_bps;
+        vault.set_mock_pool(self.mock_pool);
+        vault.creation_unit_shares = self.creation_unit_shares;
+        vault.wind_down_deadline = self.wind_down_deadline;
+
+        if self.version < 1 {
+            if vault.last_share_price == 0 {
+                vault.last_share_price = 1_000_000;
+            }
+            if vault.max_slippage_bps == 0 {
+                vault.max_slippage_bps = crate::DEFAULT_MAX_SLIPPAGE_BPS;
+            }
+            if self.mock_pool == (MockPoolConfig { fee_bps: 0, depth_usd: 0 }) {
+                vault.set_mock_pool(MockPoolConfig::DEFAULT);
+            }
+        }
+        vault.version = VAULT_VERSION;
+        Ok(vault)
+    }
+}
+
+/// Per-vault cache of oracle prices, refreshed by the permissionless
+/// refresh_prices crank so deposits and withdrawals can skip the oracle accounts
+/// PDA seeds: [b"price_cache", vault]
+#[account]
+pub struct PriceCache {
+    /// Vault these prices belong to
+    pub vault: Pubkey,
+    /// BTC/USD price in micro-dollars (6 decimals)
+    pub btc_price: i64,
+    /// ETH/USD price in micro-dollars (6 decimals)
+    pub eth_price: i64,
+    /// SOL/USD price in micro-dollars (6 decimals)
+    pub sol_price: i64,
+    /// Unix timestamp of the last refresh
+    pub last_update: i64,
+    /// Bump seed for the cache PDA
+    pub bump: u8,
+}
+
+impl PriceCache {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1; // discriminator + vault + 3 prices + timestamp + bump
+}
+
+/// SOL value of the vault's strategy position, synced from the strategy's
+/// report_value by the permissionless sync_strategy_value crank
+/// PDA seeds: [b"strategy_value", vault]
+#[account]
+pub struct StrategyValue {
+    /// Vault this value belongs to
+    pub vault: Pubkey,
+    /// Strategy that reported the value
+    pub strategy: Pubkey,
+    /// Lamports the position is worth, staking yield included; adjusted in
+    /// place as deposits and withdrawals move SOL in and out of the strategy
+    pub value_lamports: u64,
+    /// Unix timestamp of the last sync
+    pub updated_at: i64,
+    /// Oldest sync accepted for valuation, in seconds (0 = the default)
+    pub max_age_secs: u32,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl StrategyValue {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 4 + 1; // discriminator + 2 pubkeys + value + timestamp + max age + bump
+}
+
+/// Per-vault limits on what a rebalance may trade, set by the vault authority
+/// Vaults without one rebalance uncapped with the default $1 minimum swap
+/// PDA seeds: [b"rebalance_config", vault]
+#[account]
+pub struct RebalanceConfig {
+    /// Vault these limits belong to
+    pub vault: Pubkey,
+    /// Most one rebalance may trade, in bps of TVL (0 = no cap); excess
+    /// drift is corrected over the following rebalances
+    pub max_turnover_bps_per_rebalance: u16,
+    /// Smallest leg worth swapping, in micro-USD (0 = the $1 default)
+    pub min_swap_usd: u64,
+    /// Per asset, in vault.assets order: legs selling this many base units
+    /// or less of it are skipped as dust (0 = no threshold)
+    pub dust_thresholds: [u64; MAX_ASSETS],
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl RebalanceConfig {
+    // discriminator + vault + turnover cap + min swap + dust thresholds + bump
+    pub const LEN: usize = 8 + 32 + 2 + 8 + 8 * MAX_ASSETS + 1;
+}
+
+/// Hot key that may pause a vault but change nothing else, set by the
+/// vault's governed authority; typically held by a monitoring bot
+/// PDA seeds: [b"vault_guardian", vault]
+///
+/// guardian_pause stops deposits and, when allowed, rebalancing; it never
+/// touches withdrawals or their pricing. Only the governed authority resumes
+/// either, so a leaked guardian key can at worst halt new inflows.
+#[account]
+pub struct VaultGuardian {
+    /// Vault this guardian watches
+    pub vault: Pubkey,
+    /// Key allowed to call guardian_pause (None = no guardian)
+    pub guardian: Option<Pubkey>,
+    /// Whether the guardian may also pause rebalancing
+    pub may_pause_rebalancing: bool,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl VaultGuardian {
+    // discriminator + vault + optional guardian + flag + bump
+    pub const LEN: usize = 8 + 32 + (1 + 32) + 1 + 1;
+}
+
+/// Currency a vault takes deposits in and reports its NAV in, set by the
+/// vault authority; vaults without one are SOL-quoted
+/// PDA seeds: [b"quote_config", vault]
+#[account]
+#[derive(Debug)]
+pub struct QuoteConfig {
+    /// Vault this setting belongs to
+    pub vault: Pubkey,
+    pub currency: QuoteCurrency,
+    /// USDC mint of a USDC-quoted vault (default key for SOL)
+    pub quote_mint: Pubkey,
+    /// Bump seed for the PDA
+    pub bump: u8,
+    /// ISO 4217 code NAV is reported in, e.g. b"EUR" (b"USD" by default)
+    pub nav_currency: [u8; 3],
+    /// Switchboard feed pricing one unit of nav_currency in USD
+    /// (default key for USD)
+    pub fx_feed: Pubkey,
+}
+
+impl QuoteConfig {
+    pub const LEN: usize = 8 + 32 + 1 + 32 + 1 + 3 + 32; // discriminator + vault + currency + mint + bump + nav currency + fx feed
+
+    /// Whether `mint` is this vault's USDC
+    pub fn is_quote_mint(&self, mint: &Pubkey) -> bool {
+        self.currency == QuoteCurrency::Usdc && self.quote_mint == *mint
+    }
+
+    /// FX feed to convert NAV with, or None when NAV is reported in USD
+    pub fn fx_feed(&self) -> Option<Pubkey> {
+        (self.fx_feed != Pubkey::default()).then_some(self.fx_feed)
+    }
+}
+
+/// Protocol-wide fee settings
+/// PDA seeds: [b"protocol_config"]
+#[account]
+pub struct ProtocolConfig {
+    /// Can update the config
+    pub authority: Pubkey,
+    /// Receives the protocol's share of vault fees
+    pub treasury: Pubkey,
+    /// Share of vault fees owed to the vault creator, in bps (rest goes to the treasury)
+    pub creator_fee_share_bps: u16,
+    /// Bump seed for the config PDA
+    pub bump: u8,
+    /// Lower protocol shares for larger vaults, ascending by min_tvl_usd;
+    /// vaults below every tier use creator_fee_share_bps
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+impl ProtocolConfig {
+    // discriminator + 2 pubkeys + share + bump + fee tiers at MAX_FEE_TIERS
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 1 + 4 + FeeTier::LEN * MAX_FEE_TIERS;
+
+    /// Creator's share of fees for a vault worth `tvl_usd` (micro-USD): the
+    /// complement of the highest tier the vault reaches, else creator_fee_share_bps
+    pub fn creator_fee_share_bps_at(&self, tvl_usd: i64) -> u16 {
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| tvl_usd >= 0 && tvl_usd as u64 >= tier.min_tvl_usd)
+            .map_or(self.creator_fee_share_bps, |tier| 10_000 - tier.protocol_fee_share_bps)
+    }
+}
+
+/// Most TVL tiers in a protocol fee schedule
+pub const MAX_FEE_TIERS: usize = 4;
+
+/// Protocol share of fees for vaults at or above a TVL
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeTier {
+    /// Smallest TVL the tier applies to, in micro-USD
+    pub min_tvl_usd: u64,
+    /// Protocol's share of vault fees (after stakers), in bps; the creator gets the rest
+    pub protocol_fee_share_bps: u16,
+}
+
+impl FeeTier {
+    pub const LEN: usize = 8 + 2;
+}
+
+/// Most approved alternates per wrapped asset in the AssetWhitelist
+pub const MAX_APPROVED_ALTERNATES: usize = 8;
+
+/// Canonical Portal (Wormhole) wrapped BTC and ETH
+pub const PORTAL_WBTC_MINT: Pubkey = crate::orca::WBTC_MINT;
+pub const PORTAL_WETH_MINT: Pubkey = pubkey!("7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs");
+
+/// Mints create_vault accepts in the BTC and ETH slots, kept by the
+/// ProtocolConfig authority. Once it exists a vault's BTC must be Portal
+/// wBTC or an approved alternate (likewise ETH), so an admin cannot list a
+/// worthless look-alike mint. Deployments without one (localnet test mints)
+/// are unrestricted.
+/// PDA seeds: [b"asset_whitelist"]
+#[account]
+pub struct AssetWhitelist {
+    /// Accepted in the BTC slot besides Portal wBTC
+    pub btc_alternates: Vec<Pubkey>,
+    /// Accepted in the ETH slot besides Portal wETH
+    pub eth_alternates: Vec<Pubkey>,
+    /// Bump seed for the whitelist PDA
+    pub bump: u8,
+}
+
+impl AssetWhitelist {
+    // discriminator + 2 alternate lists at MAX_APPROVED_ALTERNATES + bump
+    pub const LEN: usize = 8 + 2 * (4 + 32 * MAX_APPROVED_ALTERNATES) + 1;
+
+    /// Whether `mint` may fill basket slot `index` (slots past ETH are unchecked)
+    pub fn allows(&self, index: usize, mint: &Pubkey) -> bool {
+        match index {
+            0 => *mint == PORTAL_WBTC_MINT || self.btc_alternates.contains(mint),
+            1 => *mint == PORTAL_WETH_MINT || self.eth_alternates.contains(mint),
+            _ => true,
+        }
+    }
+}
+
+/// Switchboard On-Demand feed hashes of the BTC, ETH and SOL markets, kept by
+/// the ProtocolConfig authority. Switchboard and CrossChecked vaults take
+/// their quote accounts unchecked, and only accept a pull feed whose hash
+/// matches its market's.
+/// PDA seeds: [b"switchboard_config"]
+#[account]
+pub struct SwitchboardConfig {
+    /// Feed hashes of BTC, ETH and SOL, in that order
+    pub feed_hashes: [[u8; 32]; ORACLE_MARKETS],
+    /// Bump seed for the config PDA
+    pub bump: u8,
+}
+
+impl SwitchboardConfig {
+    // discriminator + feed hashes + bump
+    pub const LEN: usize = 8 + 32 * ORACLE_MARKETS + 1;
+}
+
+/// Chainlink Data Streams feed ids of the BTC, ETH and SOL markets, kept by
+/// the ProtocolConfig authority, with the latest price post_chainlink_report
+/// verified for each. Chainlink vaults price from these.
+/// PDA seeds: [b"chainlink_config"]
+#[account]
+pub struct ChainlinkConfig {
+    /// Feed ids of BTC, ETH and SOL, in that order
+    pub feed_ids: [[u8; 32]; ORACLE_MARKETS],
+    /// Latest verified price of each market, in micro-USD (0 = none yet)
+    pub prices: [i64; ORACLE_MARKETS],
+    /// Observation time of each price
+    pub observed_at: [i64; ORACLE_MARKETS],
+    /// Bump seed for the config PDA
+    pub bump: u8,
+}
+
+impl ChainlinkConfig {
+    // discriminator + feed ids + prices + observation times + bump
+    pub const LEN: usize = 8 + 32 * ORACLE_MARKETS + 8 * ORACLE_MARKETS + 8 * ORACLE_MARKETS + 1;
+}
+
+/// Most trusted signers a PythLazerConfig can hold
+pub const MAX_PYTH_LAZER_SIGNERS: usize = 4;
+
+/// Pyth Lazer signers and feed ids shared by every PythLazer vault, kept by
+/// the ProtocolConfig authority. Lazer rotates its signing keys, so the
+/// authority lists the current ones (and the next, ahead of a rotation).
+/// PDA seeds: [b"pyth_lazer_config"]
+#[account]
+pub struct PythLazerConfig {
+    /// Keys whose Ed25519 signature over an update payload is trusted
+    pub trusted_signers: Vec<Pubkey>,
+    /// Lazer feed ids of BTC, ETH and SOL, in that order
+    pub feed_ids: [u32; ORACLE_MARKETS],
+    /// Bump seed for the config PDA
+    pub bump: u8,
+}
+
+impl PythLazerConfig {
+    // discriminator + signer list at MAX_PYTH_LAZER_SIGNERS + feed ids + bump
+    pub const LEN: usize = 8 + 4 + 32 * MAX_PYTH_LAZER_SIGNERS + 4 * ORACLE_MARKETS + 1;
+}
+
+/// Most keys an OracleFeeders set can hold
+pub const MAX_ORACLE_FEEDERS: usize = 8;
+
+/// Keys besides its authority that may push prices to a MockPriceOracle
+///
+/// Lets a price-pusher bot sign with a hot key while the oracle PDA stays
+/// derived from, and administered by, the authority's key. Any listed key
+/// may update on its own; set_oracle_feeders replaces the whole set, so a
+/// leaked key is rotated out without redeploying the oracle.
+/// PDA seeds: [b"oracle_feeders", mock_oracle]
+#[account]
+pub struct OracleFeeders {
+    /// MockPriceOracle the feeders may update
+    pub oracle: Pubkey,
+    /// Authorized updater keys
+    pub feeders: Vec<Pubkey>,
+    /// Bump seed for the feeders PDA
+    pub bump: u8,
+}
+
+impl OracleFeeders {
+    // discriminator + oracle + feeder list at MAX_ORACLE_FEEDERS + bump
+    pub const LEN: usize = 8 + 32 + 4 + 32 * MAX_ORACLE_FEEDERS + 1;
+
+    /// Whether `key` may push prices
+    pub fn allows(&self, key: &Pubkey) -> bool {
+        self.feeders.contains(key)
+    }
+}
+
+/// Most assets a VaultTemplate can hold; create_vault_from_template opens
+/// every ATA in one instruction, so templates stay below what fits
+pub const MAX_TEMPLATE_ASSETS: usize = 10;
+
+/// One asset of a VaultTemplate
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TemplateAsset {
+    pub mint: Pubkey,
+    /// Target weight in whole percent
+    pub weight: u8,
+}
+
+/// Settings a vault created from a template starts with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct VaultPreset {
+    /// Composition; weights sum to 100
+    pub assets: Vec<TemplateAsset>,
+    pub price_source: PriceSource,
+    /// Required when price_source = MockOracle
+    pub mock_oracle: Option<Pubkey>,
+    pub twap_window_secs: u32,
+    /// Feeds read when price_source = Aggregated
+    pub price_feeds: Vec<PriceFeed>,
+    pub max_divergence_bps: u16,
+    pub swap_backend: SwapBackend,
+    pub max_slippage_bps: u16,
+}
+
+impl VaultPreset {
+    // asset list at MAX_TEMPLATE_ASSETS + price source + mock oracle option + TWAP window
+    // + feed list at MAX_PRICE_FEEDS + divergence + swap backend + slippage
+    pub const LEN: usize = (4 + 33 * MAX_TEMPLATE_ASSETS) + 1 + 33 + 4 + (4 + 34 * MAX_PRICE_FEEDS) + 2 + 1 + 2;
+
+    /// Composition as create_vault takes it (ATAs are derived on creation)
+    pub fn asset_configs(&self) -> Vec<AssetConfig> {
+        self.assets
+            .iter()
+            .map(|asset| AssetConfig {
+                mint: asset.mint,
+                weight: asset.weight,
+                ata: Pubkey::default(),
+            })
+            .collect()
+    }
+}
+
+/// Composition, oracle and swap preset curated by the protocol authority,
+/// from which create_vault_from_template creates vaults in one call
+/// PDA seeds: [b"vault_template", name]
+#[account]
+pub struct VaultTemplate {
+    /// Template name, e.g. "Blue Chip 40/30/30"
+    pub name: String,
+    pub preset: VaultPreset,
+    /// Vaults created from the template
+    pub vaults_created: u64,
+    /// Bump seed for the template PDA
+    pub bump: u8,
+}
+
+impl VaultTemplate {
+    // discriminator + name at MAX_NAME_LEN + preset + counter + bump
+    pub const LEN: usize = 8 + (4 + MAX_NAME_LEN) + VaultPreset::LEN + 8 + 1;
+}
+
+/// Referral fees a referrer earned in one vault, held as lamports in the PDA
+/// itself until claim_referral_fees pays them out
+/// PDA seeds: [b"referral", vault, referrer]
+#[account]
+pub struct ReferralBalance {
+    /// Vault the referred deposits went into
+    pub vault: Pubkey,
+    /// Referrer credited with the fees
+    pub referrer: Pubkey,
+    /// Lamports earned and not yet claimed
+    pub accrued_lamports: u64,
+    /// Lamports earned over the account's lifetime
+    pub total_earned: u64,
+    /// Bump seed for the referral PDA
+    pub bump: u8,
+}
+
+impl ReferralBalance {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1; // discriminator + 2 pubkeys + 2 * u64 + bump
+}
+
+/// Market maker the vault admin allows to create and redeem creation units
+/// PDA seeds: [b"authorized_participant", vault, participant]
+#[account]
+pub struct AuthorizedParticipant {
+    /// Vault the participant may create and redeem units of
+    pub vault: Pubkey,
+    /// Participant wallet
+    pub participant: Pubkey,
+    /// Creation units created over the account's lifetime
+    pub units_created: u64,
+    /// Creation units redeemed over the account's lifetime
+    pub units_redeemed: u64,
+    /// Bump seed for the participant PDA
+    pub bump: u8,
+}
+
+impl AuthorizedParticipant {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1; // discriminator + 2 pubkeys + 2 * u64 + bump
+}
+
+/// Shortest grace period between begin_wind_down and close_vault
+pub const MIN_WIND_DOWN_GRACE_SECS: i64 = 7 * SECONDS_PER_DAY;
+
+/// Program-wide vault counter
+/// PDA seeds: [b"vault_registry"]
+#[account]
+pub struct VaultRegistry {
+    /// Vaults registered so far; the next registration's index
+    pub vault_count: u64,
+    /// Bump seed for the registry PDA
+    pub bump: u8,
+}
+
+impl VaultRegistry {
+    pub const LEN: usize = 8 + 8 + 1; // discriminator + count + bump
+}
+
+/// One vault's entry in the registry, written by create_vault (or
+/// register_vault for vaults created before the registry existed)
+///
+/// Frontends enumerate vaults with a getProgramAccounts call filtered on
+/// this account's discriminator; TVL and share price live in the Vault.
+/// PDA seeds: [b"vault_registration", vault]
+#[account]
+pub struct VaultRegistration {
+    /// Registered vault
+    pub vault: Pubkey,
+    /// Vault admin
+    pub admin: Pubkey,
+    /// Vault share mint
+    pub share_mint: Pubkey,
+    /// Position in registration order, starting at 0
+    pub index: u64,
+    /// Unix timestamp of registration
+    pub registered_at: i64,
+    /// Bump seed for the registration PDA
+    pub bump: u8,
+}
+
+impl VaultRegistration {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 1; // discriminator + 3 pubkeys + index + timestamp + bump
+
+    /// Record `vault` as the registry's next entry
+    pub fn register(
+        &mut self,
+        registry: &mut VaultRegistry,
+        vault_key: Pubkey,
+        vault: &Vault,
+        bump: u8,
+    ) -> Result<()> {
+        self.vault = vault_key;
+        self.admin = vault.admin;
+        self.share_mint = vault.vault_token_mint;
+        self.index = registry.vault_count;
+        self.registered_at = Clock::get()?.unix_timestamp;
+        self.bump = bump;
+        registry.vault_count = registry
+            .vault_count
+            .checked_add(1)
+            .ok_or(crate::VaultError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+/// Longest VaultMetadata::description, in bytes
+pub const MAX_DESCRIPTION_LEN: usize = 280;
+/// Longest VaultMetadata::icon_uri, in bytes
+pub const MAX_ICON_URI_LEN: usize = 200;
+/// Longest VaultMetadata::category, in bytes
+pub const MAX_CATEGORY_LEN: usize = 32;
+/// Highest VaultMetadata::risk_level (1 = lowest risk)
+pub const MAX_RISK_LEVEL: u8 = 5;
+
+/// Display information for a vault, read by frontends
+/// PDA seeds: [b"vault_metadata", vault]
+#[account]
+pub struct VaultMetadata {
+    /// Vault this metadata describes
+    pub vault: Pubkey,
+    /// Free-form description of the strategy
+    pub description: String,
+    /// Icon or logo URI
+    pub icon_uri: String,
+    /// Category label, e.g. "Index" or "Yield"
+    pub category: String,
+    /// Risk level, 1 (lowest) to MAX_RISK_LEVEL
+    pub risk_level: u8,
+    /// Unix timestamp of the last update
+    pub updated_at: i64,
+    /// Bump seed for the metadata PDA
+    pub bump: u8,
+}
+
+impl VaultMetadata {
+    // discriminator + vault + 3 strings at their max length + risk level + timestamp + bump
+    pub const LEN: usize =
+        8 + 32 + (4 + MAX_DESCRIPTION_LEN) + (4 + MAX_ICON_URI_LEN) + (4 + MAX_CATEGORY_LEN) + 1 + 8 + 1;
+
+    /// Check field lengths and the risk level range
+    pub fn validate(description: &str, icon_uri: &str, category: &str, risk_level: u8) -> Result<()> {
+        require!(
+            description.len() <= MAX_DESCRIPTION_LEN
+                && icon_uri.len() <= MAX_ICON_URI_LEN
+                && !category.is_empty()
+                && category.len() <= MAX_CATEGORY_LEN
+                && (1..=MAX_RISK_LEVEL).contains(&risk_level),
+            crate::VaultError::InvalidVaultMetadata
+        );
+        Ok(())
+    }
+}
+
+/// Shortest interval between recompute_weights runs
+pub const MIN_RECOMPUTE_INTERVAL_SECS: i64 = 60 * 60;
+
+/// Market-cap index mode: recompute_weights sets the vault's weights from
+/// each asset's supply × price, moving each weight by at most
+/// max_weight_change points per run
+/// PDA seeds: [b"market_cap_index", vault]
+#[account]
+pub struct MarketCapIndex {
+    /// Vault whose weights this index drives
+    pub vault: Pubkey,
+    /// Besides the vault authority, may post supplies (e.g. a supply oracle;
+    /// default key = authority only)
+    pub supply_authority: Pubkey,
+    /// Circulating supply of each asset in whole tokens, in vault.assets order
+    pub supplies: Vec<u64>,
+    /// Unix timestamp supplies were last posted (0 = never)
+    pub supplies_updated_at: i64,
+    /// Minimum seconds between recompute_weights runs
+    pub recompute_interval_secs: i64,
+    /// Largest change of any one weight per run, in percentage points
+    pub max_weight_change: u8,
+    /// Unix timestamp of the last recompute_weights run
+    pub last_recompute: i64,
+    /// Bump seed for the index PDA
+    pub bump: u8,
+}
+
+impl MarketCapIndex {
+    // discriminator + vault + supply authority + supplies at MAX_ASSETS + 2 timestamps + interval + max change + bump
+    pub const LEN: usize = 8 + 32 + 32 + (4 + 8 * MAX_ASSETS) + 8 + 8 + 1 + 8 + 1;
+
+    /// Whether `signer` may post supplies for a vault governed by `vault`
+    pub fn can_post_supplies(&self, vault: &Vault, signer: &Pubkey) -> bool {
+        (self.supply_authority != Pubkey::default() && self.supply_authority == *signer)
+            || vault.require_governed_authority(signer).is_ok()
+    }
+
+    /// Market cap of each asset in micro-USD, from `prices` in vault.assets order
+    pub fn market_caps(&self, prices: &[NormalizedPrice]) -> Vec<u128> {
+        self.supplies
+            .iter()
+            .zip(prices)
+            .map(|(supply, price)| *supply as u128 * price.price_usd.max(0) as u128)
+            .collect()
+    }
+}
+
+/// Most steps one composition schedule can hold
+pub const MAX_SCHEDULE_STEPS: usize = 12;
+
+/// One future composition of a glide path
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ScheduleStep {
+    /// Unix timestamp from which the step may be applied
+    pub at: i64,
+    /// Target weights, in vault.assets order (must sum to 100)
+    pub weights: Vec<u8>,
+}
+
+/// Glide path: target compositions apply_schedule_step moves the vault to
+/// as each step comes due, e.g. 80% SOL at launch to 80% stables at maturity
+/// PDA seeds: [b"vault_schedule", vault]
+#[account]
+pub struct VaultSchedule {
+    /// Vault whose weights the schedule drives
+    pub vault: Pubkey,
+    /// Steps in ascending `at` order
+    pub steps: Vec<ScheduleStep>,
+    /// Index of the first step not yet applied
+    pub next_step: u8,
+    /// Bump seed for the schedule PDA
+    pub bump: u8,
+}
+
+impl VaultSchedule {
+    // discriminator + vault + steps at MAX_SCHEDULE_STEPS x (timestamp + weights at MAX_ASSETS) + next step + bump
+    pub const LEN: usize = 8 + 32 + 4 + MAX_SCHEDULE_STEPS * (8 + 4 + MAX_ASSETS) + 1 + 1;
+
+    /// Check step count, ordering and that each step is a valid composition
+    /// of `asset_count` assets
+    pub fn validate(steps: &[ScheduleStep], asset_count: usize) -> Result<()> {
+        require!(steps.len() <= MAX_SCHEDULE_STEPS, crate::VaultError::InvalidSchedule);
+        for (i, step) in steps.iter().enumerate() {
+            require!(
+                step.weights.len() == asset_count
+                    && step.weights.iter().all(|w| *w > 0)
+                    && step.weights.iter().map(|w| *w as u32).sum::<u32>() == 100,
+                crate::VaultError::InvalidSchedule
+            );
+            require!(
+                i == 0 || steps[i - 1].at < step.at,
+                crate::VaultError::InvalidSchedule
+            );
+        }
+        Ok(())
+    }
+
+    /// Index of the latest step due at `now` that has not been applied;
+    /// earlier due steps are skipped over
+    pub fn due_step(&self, now: i64) -> Option<usize> {
+        self.steps
+            .iter()
+            .enumerate()
+            .skip(self.next_step as usize)
+            .take_while(|(_, step)| step.at <= now)
+            .last()
+            .map(|(i, _)| i)
+    }
+}
+
+/// How an LstBasket slot's exchange rate is read
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LstKind {
+    Sol,          // The vault's native SOL slot (rate 1:1)
+    Marinade,     // mSOL, rate from the Marinade State
+    SplStakePool, // SPL stake pool LSTs (jitoSOL, bSOL, Sanctum pools)
+}
+
+/// Rate source of one LST index asset
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LstPool {
+    pub kind: LstKind,
+    /// Marinade State or stake pool account (default key for Sol)
+    pub pool: Pubkey,
+}
+
+/// LST index: the pool behind each asset and its cached exchange rate
+/// refresh_lst_rates re-reads the rates; prices use them for
+/// MAX_LST_RATE_AGE_SECS.
+/// PDA seeds: [b"lst_basket", vault]
+#[account]
+pub struct LstBasket {
+    /// Vault priced by this basket
+    pub vault: Pubkey,
+    /// Rate source of each asset, in vault.assets order
+    pub pools: Vec<LstPool>,
+    /// Lamports per token of each asset, scaled by lst::RATE_SCALE
+    pub rates: Vec<u64>,
+    /// Unix timestamp rates were last read
+    pub rates_updated_at: i64,
+    /// Bump seed for the basket PDA
+    pub bump: u8,
+}
+
+impl LstBasket {
+    // discriminator + vault + pools at MAX_ASSETS + rates at MAX_ASSETS + timestamp + bump
+    pub const LEN: usize = 8 + 32 + (4 + 33 * MAX_ASSETS) + (4 + 8 * MAX_ASSETS) + 8 + 1;
+}
+
+/// Address lookup table holding a vault's mints, ATAs, feeds and strategy
+/// accounts, owned by the vault PDA
+/// PDA seeds: [b"vault_lookup_table", vault]
+#[account]
+pub struct VaultLookupTable {
+    /// Vault the table belongs to
+    pub vault: Pubkey,
+    /// Lookup table account
+    pub lookup_table: Pubkey,
+    /// Addresses stored in the table
+    pub address_count: u16,
+    /// Unix timestamp of the last extension
+    pub updated_at: i64,
+    /// Bump seed for the record PDA
+    pub bump: u8,
+}
+
+impl VaultLookupTable {
+    // discriminator + vault + table + count + timestamp + bump
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 8 + 1;
+}
+
+/// Fixed-point scale of StakePool::reward_per_share
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Vault shares staked to earn the stakers' cut of the vault's fees
+/// Staked shares sit in the pool's share ATA, rewards as lamports in the PDA itself
+/// PDA seeds: [b"stake_pool", vault]
+#[account]
+pub struct StakePool {
+    /// Vault whose shares are staked
+    pub vault: Pubkey,
+    /// Shares currently staked across all positions
+    pub total_staked: u64,
+    /// Lamports distributed per staked share since creation, scaled by REWARD_PRECISION
+    pub reward_per_share: u128,
+    /// Lamports distributed to stakers over the pool's lifetime
+    pub total_distributed: u64,
+    /// Bump seed for the pool PDA
+    pub bump: u8,
+}
+
+impl StakePool {
+    pub const LEN: usize = 8 + 32 + 8 + 16 + 8 + 1; // discriminator + vault + staked + acc + distributed + bump
+
+    /// Spread `lamports` over the currently staked shares
+    pub fn distribute(&mut self, lamports: u64) {
+        self.reward_per_share += lamports as u128 * REWARD_PRECISION / self.total_staked as u128;
+        self.total_distributed = self.total_distributed.saturating_add(lamports);
+    }
+}
+
+/// One staker's position in a StakePool
+/// PDA seeds: [b"stake_position", stake_pool, owner]
+#[account]
+pub struct StakePosition {
+    /// Pool the shares are staked in
+    pub pool: Pubkey,
+    /// Wallet that staked and may unstake and claim
+    pub owner: Pubkey,
+    /// Shares staked
+    pub shares: u64,
+    /// reward_per_share already accounted for in `unclaimed`, times `shares`
+    pub reward_debt: u128,
+    /// Lamports earned and not yet claimed
+    pub unclaimed: u64,
+    /// Bump seed for the position PDA
+    pub bump: u8,
+}
+
+impl StakePosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 16 + 8 + 1; // discriminator + 2 pubkeys + shares + debt + unclaimed + bump
+
+    pub fn init_if_new(&mut self, pool: Pubkey, owner: Pubkey, bump: u8) {
+        if self.pool == Pubkey::default() {
+            self.pool = pool;
+            self.owner = owner;
+            self.bump = bump;
+        }
+    }
+
+    /// Credit rewards earned since the last update; call before changing `shares`
+    pub fn accrue(&mut self, reward_per_share: u128) {
+        // Dust below one lamport stays in the debt gap and is credited later
+        let earned = (self.shares as u128 * reward_per_share - self.reward_debt) / REWARD_PRECISION;
+        self.unclaimed = self.unclaimed.saturating_add(earned as u64);
+        self.reward_debt += earned * REWARD_PRECISION;
+    }
+
+    /// Change the staked amount, keeping already-earned rewards out of future accruals
+    pub fn set_shares(&mut self, shares: u64, reward_per_share: u128) {
+        self.shares = shares;
+        self.reward_debt = shares as u128 * reward_per_share;
+    }
+}
+
+/// Per-user position in a vault, kept up to date by deposit and withdraw
+/// PDA seeds: [b"user_position", vault, user]
+#[account]
+pub struct UserPosition {
+    /// Vault this position belongs to
+    pub vault: Pubkey,
+    /// Position owner
+    pub user: Pubkey,
+    /// Cumulative USD deposited, in micro-dollars (cost basis)
+    pub deposited_usd: i64,
+    /// Cumulative USD withdrawn, in micro-dollars
+    pub withdrawn_usd: i64,
+    /// Shares minted to the user minus shares they burned through this vault
+    pub shares: u64,
+    /// Slot of the last deposit or withdrawal
+    pub last_action_slot: u64,
+    /// Unix timestamp of the last deposit or withdrawal
+    pub last_action_ts: i64,
+    /// Shares still inside the vault's lockup period
+    pub locked_shares: u64,
+    /// Unix timestamp when locked_shares become redeemable
+    pub locked_until: i64,
+    /// Unix timestamp of the last deposit (start of the exit fee decay)
+    pub last_deposit_ts: i64,
+    /// Average USD paid per share still held, in micro-dollars per whole
+    /// share (the unit of the share price)
+    pub avg_cost_per_share: i64,
+    /// Cumulative realized PnL of withdrawals, in micro-dollars
+    pub realized_pnl_usd: i64,
+    /// Bump seed for the position PDA
+    pub bump: u8,
+}
+
+/// Base units per whole vault share (shares have 9 decimals)
+const SHARE_UNIT: i128 = 1_000_000_000;
+
+impl UserPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1; // discriminator + 2 pubkeys + 10 * 8 + bump
+
+    /// Fill in the identity of a freshly created (init_if_needed) position
+    pub fn init_if_new(&mut self, vault: Pubkey, user: Pubkey, bump: u8) {
+        if self.vault == Pubkey::default() {
+            self.vault = vault;
+            self.user = user;
+            self.bump = bump;
+        }
+    }
+
+    /// Shares that cannot be redeemed at `now`
+    pub fn locked_at(&self, now: i64) -> u64 {
+        if now < self.locked_until {
+            self.locked_shares
+        } else {
+            0
+        }
+    }
+
+    /// Lock freshly minted shares for `lockup_secs`
+    /// A new deposit restarts the lock for shares that are still locked
+    pub fn lock_shares(&mut self, shares: u64, lockup_secs: u32, now: i64) {
+        if lockup_secs == 0 {
+            return;
+        }
+        self.locked_shares = self.locked_at(now).saturating_add(shares);
+        self.locked_until = now + lockup_secs as i64;
+    }
+
+    pub fn record_deposit(&mut self, shares: u64, usd_value: i64, clock: &Clock) {
+        // Weighted average of the held shares' cost and this deposit's
+        let total_shares = self.shares as i128 + shares as i128;
+        if total_shares > 0 {
+            let held_cost = self.avg_cost_per_share as i128 * self.shares as i128;
+            let avg = (held_cost + usd_value as i128 * SHARE_UNIT) / total_shares;
+            self.avg_cost_per_share = avg.clamp(0, i64::MAX as i128) as i64;
+        }
+        self.shares = self.shares.saturating_add(shares);
+        self.deposited_usd = self.deposited_usd.saturating_add(usd_value);
+        self.last_action_slot = clock.slot;
+        self.last_action_ts = clock.unix_timestamp;
+        self.last_deposit_ts = clock.unix_timestamp;
+    }
+
+    /// Exit fee in bps at `now`: `max_fee_bps` right after the last deposit,
+    /// decaying linearly to zero over `window_secs`
+    pub fn exit_fee_bps(&self, max_fee_bps: u16, window_secs: u32, now: i64) -> u16 {
+        let elapsed = now.saturating_sub(self.last_deposit_ts);
+        if window_secs == 0 || elapsed >= window_secs as i64 {
+            return 0;
+        }
+        let remaining = (window_secs as i64 - elapsed.max(0)) as u64;
+        (max_fee_bps as u64 * remaining / window_secs as u64) as u16
+    }
+
+    /// Shares may exceed the tracked balance for holders who received shares
+    /// by transfer or deposited before positions existed, hence saturating
+    ///
+    /// Returns the realized PnL: proceeds of the tracked shares less their
+    /// average cost. Untracked shares have no known cost and realize nothing.
+    pub fn record_withdrawal(&mut self, shares: u64, usd_value: i64, clock: &Clock) -> i64 {
+        let realized_pnl = self.realized_pnl(shares, usd_value);
+        self.shares = self.shares.saturating_sub(shares);
+        if self.shares == 0 {
+            self.avg_cost_per_share = 0;
+        }
+        self.withdrawn_usd = self.withdrawn_usd.saturating_add(usd_value);
+        self.realized_pnl_usd = self.realized_pnl_usd.saturating_add(realized_pnl);
+        self.last_action_slot = clock.slot;
+        self.last_action_ts = clock.unix_timestamp;
+        realized_pnl
+    }
+
+    /// PnL of redeeming `shares` for `usd_value` at the average cost
+    pub fn realized_pnl(&self, shares: u64, usd_value: i64) -> i64 {
+        let tracked = shares.min(self.shares);
+        if tracked == 0 {
+            return 0;
+        }
+        let proceeds = usd_value as i128 * tracked as i128 / shares as i128;
+        let cost = self.avg_cost_per_share as i128 * tracked as i128 / SHARE_UNIT;
+        (proceeds - cost).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+}
+
+/// Shortest interval between DCA executions
+pub const MIN_DCA_INTERVAL_SECS: i64 = 60;
+
+/// Recurring deposit pre-funded by a user and executed by a permissionless crank
+/// PDA seeds: [b"dca", vault, user]
+///
+/// SOL schedules hold their funds as lamports on this account; token
+/// schedules hold them in this account's ATA for `funding_mint`.
+#[account]
+pub struct DcaSchedule {
+    /// Vault the deposits go into
+    pub vault: Pubkey,
+    /// Wallet that funds the schedule and receives the shares
+    pub user: Pubkey,
+    /// Token the schedule is funded in, None for SOL
+    pub funding_mint: Option<Pubkey>,
+    /// Amount deposited per execution, in funding units
+    pub amount_per_period: u64,
+    /// Minimum lamports a full period must swap into (token schedules only)
+    pub min_sol_out: u64,
+    /// Seconds between executions
+    pub interval_secs: i64,
+    /// Unix timestamp from which the next execution may run
+    pub next_execution_ts: i64,
+    /// Funded amount not yet deposited
+    pub remaining: u64,
+    /// Executions so far
+    pub executions: u32,
+    /// Bump seed for the schedule PDA
+    pub bump: u8,
+}
+
+impl DcaSchedule {
+    pub const LEN: usize = 8 + 32 + 32 + 33 + 8 + 8 + 8 + 8 + 8 + 4 + 1; // discriminator + 2 pubkeys + mint option + 5 * 8 + executions + bump
+
+    /// Amount to deposit at `now`, or None if not due or out of funds
+    /// The last execution deposits whatever is left
+    pub fn due_amount(&self, now: i64) -> Option<u64> {
+        if now < self.next_execution_ts || self.remaining == 0 {
+            return None;
+        }
+        Some(self.amount_per_period.min(self.remaining))
+    }
+
+    /// Minimum lamports for swapping `amount`, scaled from the per-period bound
+    pub fn min_sol_out_for(&self, amount: u64) -> Result<u64> {
+        math::pro_rata(self.min_sol_out, amount, self.amount_per_period)
+    }
+
+    /// Record an execution of `amount` at `now`
+    /// Missed periods are skipped rather than executed back to back
+    pub fn record_execution(&mut self, amount: u64, now: i64) {
+        self.remaining -= amount;
+        self.executions += 1;
+        let missed = (now - self.next_execution_ts) / self.interval_secs;
+        self.next_execution_ts += (missed + 1) * self.interval_secs;
+    }
+}
+
+/// Seconds a prepared deposit may take to execute before it can only be closed
+pub const DEPOSIT_TICKET_TTL_SECS: i64 = 5 * 60;
+
+/// Deposit split across transactions: deposit_prepare values it and locks
+/// the prices, deposit_execute allocates it one basket asset at a time
+/// PDA seeds: [b"deposit_ticket", vault, user]
+///
+/// The deposit's lamports wait on this account until their asset's slice is
+/// allocated; each slice mints its share of the shares.
+#[account]
+pub struct DepositTicket {
+    /// Vault the deposit goes into
+    pub vault: Pubkey,
+    /// Depositor, receives the shares and any refund
+    pub user: Pubkey,
+    /// Lamports deposited, including the fee
+    pub amount: u64,
+    /// Deposit fee on `amount`, kept by the vault
+    pub deposit_fee: u64,
+    /// Micro-USD value of `amount` net of the fee
+    pub deposit_usd: i64,
+    /// Shares the whole deposit buys at the share price when prepared
+    pub shares: u64,
+    /// BTC, ETH and SOL prices when prepared, used for every slice
+    pub prices: [NormalizedPrice; 3],
+    /// Next basket asset to allocate
+    pub next_asset: u8,
+    /// Total weight of the assets allocated so far
+    pub allocated_weight: u8,
+    /// Unix timestamp after which the ticket can no longer execute
+    pub expires_at: i64,
+    /// Bump seed for the ticket PDA
+    pub bump: u8,
+}
+
+/// One asset's cut of a DepositTicket
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TicketSlice {
+    pub lamports: u64,
+    pub fee: u64,
+    pub usd: i64,
+    pub shares: u64,
+}
+
+impl DepositTicket {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 3 * (8 + 8 + 4) + 1 + 1 + 8 + 1; // discriminator + 2 pubkeys + 4 * 8 + 3 prices + progress + expiry + bump
+
+    /// The next asset's cut for an asset of `weight`; `last` takes the rest
+    /// Slices are cut at cumulative weights so rounding never strands lamports
+    pub fn next_slice(&self, weight: u8, last: bool) -> Result<TicketSlice> {
+        let from = self.allocated_weight as u128;
+        let to = if last { 100 } else { (from + weight as u128).min(100) };
+        let cut = |total: u128| -> Result<u128> {
+            Ok(math::mul_div_floor(total, to, 100)? - math::mul_div_floor(total, from, 100)?)
+        };
+        Ok(TicketSlice {
+            lamports: math::to_u64(cut(self.amount as u128)?)?,
+            fee: math::to_u64(cut(self.deposit_fee as u128)?)?,
+            usd: math::to_i64(cut(math::to_u128(self.deposit_usd.max(0))?)?)?,
+            shares: math::to_u64(cut(self.shares as u128)?)?,
+        })
+    }
+
+    /// Mark the next asset allocated
+    pub fn record_slice(&mut self, weight: u8, last: bool) {
+        self.allocated_weight = if last { 100 } else { self.allocated_weight.saturating_add(weight).min(100) };
+        self.next_asset += 1;
+    }
+
+    /// Lamports not yet moved into the vault
+    pub fn unallocated_lamports(&self) -> Result<u64> {
+        Ok(self.amount - math::to_u64(math::mul_div_floor(self.amount as u128, self.allocated_weight as u128, 100)?)?)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.allocated_weight == 100
+    }
+}
+
+/// Prefix of every deposit commitment preimage
+pub const DEPOSIT_COMMITMENT_DOMAIN: &[u8] = b"etf-defi:deposit-commitment:v1";
+
+/// Slots after its commit a deposit can still be revealed (about as long as
+/// a blockhash stays valid); later, it can only be cancelled
+pub const DEPOSIT_COMMITMENT_TTL_SLOTS: u64 = 150;
+
+/// Hidden deposit amount, revealed by reveal_deposit in a later slot
+/// PDA seeds: [b"deposit_commitment", vault, user]
+///
+/// commit_deposit only records the hash, so nothing on-chain tells searchers
+/// how large the deposit is (and how much the vault will swap) until it is
+/// revealed; the reveal is priced at the oracle prices of its own slot.
 #[account]
-pub struct Vault {
-    /// Bump seed for the vault PDA
+pub struct DepositCommitment {
+    /// Vault the deposit goes into
+    pub vault: Pubkey,
+    /// Depositor, the only one who can reveal or cancel
+    pub user: Pubkey,
+    /// `DepositCommitment::hash` of the amount and the user's salt
+    pub commitment: [u8; 32],
+    /// Slot the commitment landed in; the reveal must come later
+    pub committed_slot: u64,
+    /// Bump seed for the commitment PDA
     pub bump: u8,
-    /// Admin/authority that can manage this vault (rebalance, update composition, etc.)
-    pub admin: Pubkey,
-    /// Unique vault identifier/name (e.g., "MVPVault", "AggressiveGrowth")
-    /// Max 32 bytes to keep space reasonable
-    pub name: String,
-    /// SPL mint for vault shares (each vault has unique shares)
-    pub vault_token_mint: Pubkey,
-    /// Basket composition: array of assets with weights and ATAs
-    /// Vec is dynamic but we need to account for max size in space calculation
-    pub assets: Vec<AssetConfig>,
-    /// Optional Marinade strategy PDA for SOL staking
-    /// Stored at vault level since each vault may have its own strategy state
-    pub marinade_strategy: Option<Pubkey>,
-    /// Price source configuration (Switchboard or MockOracle)
-    /// Allows testing on devnet with mock oracle, production with Switchboard
-    pub price_source: PriceSource,
-    /// Optional mock oracle account (used when price_source = MockOracle)
-    pub mock_oracle: Option<Pubkey>,
+}
+
+impl DepositCommitment {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1; // discriminator + vault + user + commitment + slot + bump
+
+    /// Commitment to depositing `amount` lamports into `vault`
+    /// `salt` is 32 random bytes the user keeps until the reveal
+    pub fn hash(vault: &Pubkey, user: &Pubkey, amount: u64, salt: &[u8; 32]) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hashv(&[
+            DEPOSIT_COMMITMENT_DOMAIN,
+            vault.as_ref(),
+            user.as_ref(),
+            &amount.to_le_bytes(),
+            salt,
+        ])
+        .to_bytes()
+    }
+
+    /// Whether `amount` and `salt` open this commitment
+    pub fn opens(&self, amount: u64, salt: &[u8; 32]) -> bool {
+        Self::hash(&self.vault, &self.user, amount, salt) == self.commitment
+    }
+
+    /// Require `slot` to be after the commit and within DEPOSIT_COMMITMENT_TTL_SLOTS
+    pub fn require_revealable(&self, slot: u64) -> Result<()> {
+        require!(slot > self.committed_slot, crate::VaultError::DepositRevealTooEarly);
+        require!(
+            slot <= self.committed_slot.saturating_add(DEPOSIT_COMMITMENT_TTL_SLOTS),
+            crate::VaultError::DepositCommitmentExpired
+        );
+        Ok(())
+    }
+}
+
+/// SOL deposits queued during one epoch, valued together at a single NAV
+/// PDA seeds: [b"deposit_batch", vault, epoch (u64 LE)]
+///
+/// The queued lamports wait on this account until process_deposit_batch
+/// runs after the epoch ends: it deposits them in one go, with one round of
+/// swaps, and mints the whole batch's shares into this account's share ATA.
+/// claim_batch_shares then pays each BatchDeposit its cut.
+#[account]
+pub struct DepositBatch {
+    /// Vault the deposits go into
+    pub vault: Pubkey,
+    /// Epoch the deposits were queued in
+    pub epoch: u64,
+    /// Lamports queued, including the deposit fee
+    pub lamports: u64,
+    /// BatchDeposits not yet claimed (or cancelled)
+    pub open_deposits: u32,
+    /// Set once process_deposit_batch has run
+    pub processed: bool,
+    /// Shares the batch bought (0 until processed)
+    pub shares: u64,
+    /// Micro-USD value of the batch net of the fee (0 until processed)
+    pub deposit_usd: i64,
+    /// Bump seed for the batch PDA
+    pub bump: u8,
+}
+
+impl DepositBatch {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 4 + 1 + 8 + 8 + 1; // discriminator + vault + epoch + lamports + open deposits + processed + shares + usd + bump
+
+    /// Shares and micro-USD owed for `lamports` of the batch; the last open
+    /// deposit takes `shares_left`, so rounding never strands shares
+    pub fn cut(&self, lamports: u64, shares_left: u64) -> Result<(u64, i64)> {
+        let shares = if self.open_deposits == 1 {
+            shares_left
+        } else {
+            math::pro_rata(self.shares, lamports, self.lamports)?.min(shares_left)
+        };
+        let usd = math::mul_div_floor(math::to_u128(self.deposit_usd.max(0))?, lamports as u128, self.lamports as u128)?;
+        Ok((shares, math::to_i64(usd)?))
+    }
+}
+
+/// One user's deposit in a DepositBatch
+/// PDA seeds: [b"batch_deposit", batch, user]
+#[account]
+pub struct BatchDeposit {
+    /// Batch the deposit is queued in
+    pub batch: Pubkey,
+    /// Depositor, receives the shares (or the refund)
+    pub user: Pubkey,
+    /// Lamports queued, including the deposit fee
+    pub lamports: u64,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl BatchDeposit {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1; // discriminator + batch + user + lamports + bump
+}
+
+/// Share withdrawals queued during one epoch, settled together at a single NAV
+/// PDA seeds: [b"withdrawal_batch", vault, epoch (u64 LE)]
+///
+/// Queued shares wait in this account's share ATA until
+/// process_withdrawal_batch runs after the epoch ends: it burns them all and
+/// sells the batch's slice of the basket in one round of swaps, leaving the
+/// SOL proceeds on this account. claim_batch_withdrawal then pays each
+/// BatchWithdrawal its cut, less its own exit fee.
+#[account]
+pub struct WithdrawalBatch {
+    /// Vault the shares are redeemed from
+    pub vault: Pubkey,
+    /// Epoch the withdrawals were queued in
+    pub epoch: u64,
+    /// Shares queued and not yet claimed (or cancelled)
+    pub shares: u64,
+    /// BatchWithdrawals not yet claimed (or cancelled)
+    pub open_withdrawals: u32,
+    /// Set once process_withdrawal_batch has run
+    pub processed: bool,
+    /// Proceeds not yet claimed, in lamports (0 until processed)
+    pub lamports: u64,
+    /// Micro-USD value of the unclaimed proceeds (0 until processed)
+    pub withdrawal_usd: i64,
+    /// Bump seed for the batch PDA
+    pub bump: u8,
+}
+
+impl WithdrawalBatch {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 4 + 1 + 8 + 8 + 1; // discriminator + vault + epoch + shares + open withdrawals + processed + lamports + usd + bump
+
+    /// Lamports and micro-USD owed for `shares` of the unclaimed batch; the
+    /// last withdrawal takes all that is left, so rounding never strands SOL
+    pub fn cut(&self, shares: u64) -> Result<(u64, i64)> {
+        if shares >= self.shares {
+            return Ok((self.lamports, self.withdrawal_usd));
+        }
+        let usd = math::mul_div_floor(math::to_u128(self.withdrawal_usd.max(0))?, shares as u128, self.shares as u128)?;
+        Ok((math::pro_rata(self.lamports, shares, self.shares)?, math::to_i64(usd)?))
+    }
+}
+
+/// One user's withdrawal in a WithdrawalBatch
+/// PDA seeds: [b"batch_withdrawal", batch, user]
+#[account]
+pub struct BatchWithdrawal {
+    /// Batch the withdrawal is queued in
+    pub batch: Pubkey,
+    /// Shareholder, receives the proceeds (or the shares back)
+    pub user: Pubkey,
+    /// Shares queued
+    pub shares: u64,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl BatchWithdrawal {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1; // discriminator + batch + user + shares + bump
+}
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// Minimum spacing between NAV snapshots (6 hours)
+pub const NAV_SNAPSHOT_INTERVAL_SECS: i64 = 6 * 60 * 60;
+/// NAV snapshots kept per vault (32 days at the snapshot interval)
+pub const NAV_HISTORY_LEN: usize = 128;
+
+/// One NAV checkpoint written by snapshot_nav
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct NavSnapshot {
+    /// Unix timestamp of the snapshot
+    pub timestamp: i64,
+    /// Vault TVL in micro-dollars (6 decimals)
+    pub tvl_usd: i64,
+    /// Share mint supply at the snapshot
+    pub total_shares: u64,
+    /// Share price in micro-dollars (6 decimals)
+    pub share_price: i64,
+}
+
+/// Ring buffer of NAV snapshots so historical performance can be read on-chain
+/// PDA seeds: [b"nav_history", vault]
+#[account]
+pub struct NavHistory {
+    /// Vault these snapshots belong to
+    pub vault: Pubkey,
+    /// Slot the next snapshot is written to once the buffer is full
+    pub head: u16,
+    /// Snapshots, oldest at `head` once the buffer has wrapped
+    pub snapshots: Vec<NavSnapshot>,
+    /// Bump seed for the history PDA
+    pub bump: u8,
+}
+
+impl NavHistory {
+    pub const LEN: usize = 8 + 32 + 2 + 4 + NAV_HISTORY_LEN * 32 + 1; // discriminator + vault + head + vec + bump
+
+    /// Most recent snapshot, if any
+    pub fn latest(&self) -> Option<&NavSnapshot> {
+        if self.snapshots.len() < NAV_HISTORY_LEN {
+            self.snapshots.last()
+        } else {
+            let idx = (self.head as usize + NAV_HISTORY_LEN - 1) % NAV_HISTORY_LEN;
+            self.snapshots.get(idx)
+        }
+    }
+
+    /// Share price change in bps between the latest snapshot taken at least
+    /// `window_secs` before `now` and `share_price`
+    ///
+    /// Returns None while the history does not reach back that far.
+    pub fn share_price_change_bps(&self, share_price: i64, now: i64, window_secs: i64) -> Option<i32> {
+        let base = self
+            .snapshots
+            .iter()
+            .filter(|s| s.timestamp <= now - window_secs && s.share_price > 0)
+            .max_by_key(|s| s.timestamp)?;
+        let change = (share_price as i128 - base.share_price as i128) * 10_000 / base.share_price as i128;
+        Some(change.clamp(i32::MIN as i128, i32::MAX as i128) as i32)
+    }
+
+    /// Append a snapshot, overwriting the oldest one once the buffer is full
+    pub fn push(&mut self, snapshot: NavSnapshot) {
+        if self.snapshots.len() < NAV_HISTORY_LEN {
+            self.snapshots.push(snapshot);
+        } else {
+            self.snapshots[self.head as usize] = snapshot;
+            self.head = ((self.head as usize + 1) % NAV_HISTORY_LEN) as u16;
+        }
+    }
+}
+
+/// Rolling tracking error of a vault, updated by each snapshot_nav (see tracking.rs)
+/// PDA seeds: [b"tracking_error", vault]
+#[account]
+pub struct TrackingError {
+    /// Vault whose holdings are sampled
+    pub vault: Pubkey,
+    /// Deviation from the target weights at the last snapshot, in bps of TVL
+    pub last_deviation_bps: u32,
+    /// Weighted mean of the squared deviations, in bps²
+    pub mean_square_bps: u64,
+    /// Root mean square deviation over the rolling window, in bps
+    pub tracking_error_bps: u32,
+    /// Snapshots sampled so far
+    pub samples: u32,
+    /// Unix timestamp of the last sample
+    pub updated_at: i64,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl TrackingError {
+    pub const LEN: usize = 8 + 32 + 4 + 8 + 4 + 4 + 8 + 1; // discriminator + vault + 3 u32 + mean square + timestamp + bump
+
+    /// Add the deviation sampled at `now`
+    pub fn record(&mut self, deviation_bps: u32, now: i64) {
+        self.mean_square_bps = crate::tracking::next_mean_square(self.mean_square_bps, self.samples, deviation_bps);
+        self.tracking_error_bps = crate::tracking::tracking_error_bps(self.mean_square_bps);
+        self.last_deviation_bps = deviation_bps;
+        self.samples = self.samples.saturating_add(1);
+        self.updated_at = now;
+    }
+}
+
+/// Maximum number of price feeds aggregated for a single asset
+pub const MAX_FEEDS_PER_ASSET: usize = 3;
+/// Maximum number of price feeds per vault (3 oracle markets: BTC, ETH, SOL)
+pub const MAX_PRICE_FEEDS: usize = MAX_FEEDS_PER_ASSET * 3;
+
+/// Kind of account a PriceFeed points at
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PriceFeedKind {
+    Switchboard, // Switchboard Pull Feed for a single market
+    MockOracle,  // MockPriceOracle (provides all three markets)
+}
+
+/// One price source for an asset in an Aggregated vault
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PriceFeed {
+    /// Account holding the price
+    pub kind: PriceFeedKind,
+    /// Oracle market this feed prices: 0 = BTC, 1 = ETH, 2 = SOL
+    pub feed_index: u8,
+    /// Switchboard feed or MockPriceOracle account
+    pub account: Pubkey,
+}
+
+/// Price feed of each asset of a vault whose basket is not priced from the
+/// BTC/ETH/SOL oracle markets (see Vault::per_asset_pricing)
+/// MockOracle feeds read the oracle market at their feed_index.
+/// PDA seeds: [b"asset_oracles", vault]
+#[account]
+#[derive(Debug)]
+pub struct AssetOracles {
+    /// Vault priced by these feeds
+    pub vault: Pubkey,
+    /// Feed of each asset, in vault.assets order
+    pub feeds: Vec<PriceFeed>,
+    /// Feed of SOL, the currency deposits and withdrawals settle in
+    pub sol_feed: PriceFeed,
+    /// Bump seed for the PDA
+    pub bump: u8,
+}
+
+impl AssetOracles {
+    // discriminator + vault + feeds at MAX_ASSETS + SOL feed + bump
+    pub const LEN: usize = 8 + 32 + (4 + 34 * MAX_ASSETS) + 34 + 1;
+}
+
+/// Stored form of a PriceFeed in the zero-copy Vault (kind as its discriminant)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct PriceFeedSlot {
+    pub kind: u8,
+    pub feed_index: u8,
+    pub account: Pubkey,
+}
+
+impl From<&PriceFeed> for PriceFeedSlot {
+    fn from(feed: &PriceFeed) -> Self {
+        Self {
+            kind: feed.kind as u8,
+            feed_index: feed.feed_index,
+            account: feed.account,
+        }
+    }
+}
+
+impl From<&PriceFeedSlot> for PriceFeed {
+    fn from(slot: &PriceFeedSlot) -> Self {
+        Self {
+            kind: if slot.kind == PriceFeedKind::MockOracle as u8 {
+                PriceFeedKind::MockOracle
+            } else {
+                PriceFeedKind::Switchboard
+            },
+            feed_index: slot.feed_index,
+            account: slot.account,
+        }
+    }
 }
 
 /// Asset configuration within a vault's composition
 /// Defines each asset in the basket with its weight and storage account
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[repr(C)]
 pub struct AssetConfig {
     /// Asset mint (e.g., wBTC, wETH, SOL wrapped mint, or native SOL placeholder)
     pub mint: Pubkey,
@@ -42,38 +1722,364 @@ pub struct AssetConfig {
     pub ata: Pubkey,
 }
 
+/// Stored form of an optional key: the default key stands for None
+fn stored_key(key: Option<Pubkey>) -> Pubkey {
+    key.unwrap_or_default()
+}
+
+fn optional_key(key: Pubkey) -> Option<Pubkey> {
+    (key != Pubkey::default()).then_some(key)
+}
+
+impl anchor_lang::AccountSerialize for Vault {
+    fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+        writer
+            .write_all(Vault::DISCRIMINATOR)
+            .and_then(|_| writer.write_all(bytemuck::bytes_of(self)))
+            .map_err(|_| error!(ErrorCode::AccountDidNotSerialize))
+    }
+}
+
+impl Default for Vault {
+    fn default() -> Self {
+        Zeroable::zeroed()
+    }
+}
+
 impl Vault {
-    /// Calculate space required for a Vault account
-    /// This is critical for Solana's rent-exemption model
-    /// 
-    /// Space breakdown:
-    /// - 8 bytes: Anchor account discriminator (identifies account type)
-    /// - 1 byte: bump seed
-    /// - 32 bytes: admin pubkey
-    /// - 4 bytes: String length prefix for name
-    /// - name.len() bytes: actual name string
-    /// - 32 bytes: vault_token_mint pubkey
-    /// - 4 bytes: Vec length prefix for assets
-    /// - assets.len() * 65 bytes: each AssetConfig (32 + 1 + 32)
-    /// - 1 + 32 bytes: Option<Pubkey> for marinade_strategy
-    /// - 1 byte: price_source enum
-    /// - 1 + 32 bytes: Option<Pubkey> for mock_oracle
-    pub fn space(name_len: usize, num_assets: usize) -> usize {
-        8 +  // discriminator
-        1 +  // bump
-        32 + // admin
-        4 + name_len + // name (String with length prefix)
-        32 + // vault_token_mint
-        4 + (num_assets * (32 + 1 + 32)) + // assets Vec (mint + weight + ata per asset)
-        1 + 32 + // marinade_strategy Option<Pubkey>
-        1 +      // price_source enum
-        1 + 32   // mock_oracle Option<Pubkey>
+    /// Space of a Vault account: discriminator + the fixed layout
+    pub const LEN: usize = 8 + std::mem::size_of::<Vault>();
+
+    /// Space of a version 2 Vault account, which ended at `operation`
+    pub const V2_LEN: usize = 8 + std::mem::offset_of!(Vault, min_deposit_shares);
+
+    /// Move a version 2 vault's packed state into the fields version 3 gave it
+    ///
+    /// Version 2 had no spare bytes: soft_closed also held UNFINALIZED (2)
+    /// and SOULBOUND (4), restricted held REBALANCING_PAUSED (2) and
+    /// DEPOSITS_PAUSED (4), market_cap_weighted held WEIGHTS_HIDDEN (2), and
+    /// min_deposit_shares sat in v2_min_deposit_shares.
+    pub fn upgrade_v2(&mut self) {
+        self.min_deposit_shares = u32::from_le_bytes(self.v2_min_deposit_shares);
+        self.v2_min_deposit_shares = [0; 4];
+        self.unfinalized = (self.soft_closed & 2 != 0) as u8;
+        self.soulbound = (self.soft_closed & 4 != 0) as u8;
+        self.soft_closed &= 1;
+        self.rebalancing_paused = (self.restricted & 2 != 0) as u8;
+        self.guardian_deposits_paused = (self.restricted & 4 != 0) as u8;
+        self.restricted &= 1;
+        self.weights_hidden = (self.market_cap_weighted & 2 != 0) as u8;
+        self.market_cap_weighted &= 1;
+        self.version = VAULT_VERSION;
+    }
+
+    /// Seeds the vault PDA signs with, copied out of the account so no
+    /// borrow of its data is held across the CPI
+    pub fn signer(&self) -> VaultSigner {
+        VaultSigner {
+            admin: self.admin,
+            name: self.name,
+            name_len: self.name_len as usize,
+            bump: [self.bump],
+        }
+    }
+
+    /// UTF-8 name the vault PDA is derived from
+    pub fn name(&self) -> &str {
+        std::str::from_utf8(&self.name[..self.name_len as usize]).unwrap_or_default()
+    }
+
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= MAX_NAME_LEN,
+            crate::VaultError::InvalidName
+        );
+        self.name = [0; MAX_NAME_LEN];
+        self.name[..name.len()].copy_from_slice(name.as_bytes());
+        self.name_len = name.len() as u8;
+        Ok(())
+    }
+
+    /// Assets in the composition, in slot order
+    pub fn assets(&self) -> &[AssetConfig] {
+        &self.assets[..self.num_assets as usize]
+    }
+
+    pub fn assets_mut(&mut self) -> &mut [AssetConfig] {
+        &mut self.assets[..self.num_assets as usize]
+    }
+
+    /// Replace the composition; at most MAX_ASSETS assets
+    pub fn set_assets(&mut self, assets: &[AssetConfig]) -> Result<()> {
+        require!(assets.len() <= MAX_ASSETS, crate::VaultError::InvalidAssetCount);
+        self.assets = [AssetConfig::default(); MAX_ASSETS];
+        self.assets[..assets.len()].copy_from_slice(assets);
+        self.num_assets = assets.len() as u8;
+        Ok(())
+    }
+
+    /// Feeds read when price_source = Aggregated
+    pub fn price_feeds(&self) -> Vec<PriceFeed> {
+        self.price_feeds[..self.num_price_feeds as usize]
+            .iter()
+            .map(PriceFeed::from)
+            .collect()
+    }
+
+    pub fn set_price_feeds(&mut self, feeds: &[PriceFeed]) -> Result<()> {
+        require!(feeds.len() <= MAX_PRICE_FEEDS, crate::VaultError::InvalidPriceFeeds);
+        self.price_feeds = [PriceFeedSlot::default(); MAX_PRICE_FEEDS];
+        for (slot, feed) in self.price_feeds.iter_mut().zip(feeds) {
+            *slot = feed.into();
+        }
+        self.num_price_feeds = feeds.len() as u8;
+        Ok(())
+    }
+
+    pub fn marinade_strategy(&self) -> Option<Pubkey> {
+        optional_key(self.marinade_strategy)
+    }
+
+    pub fn set_marinade_strategy(&mut self, strategy: Option<Pubkey>) {
+        self.marinade_strategy = stored_key(strategy);
+    }
+
+    pub fn mock_oracle(&self) -> Option<Pubkey> {
+        optional_key(self.mock_oracle)
+    }
+
+    pub fn set_mock_oracle(&mut self, oracle: Option<Pubkey>) {
+        self.mock_oracle = stored_key(oracle);
+    }
+
+    pub fn successor(&self) -> Option<Pubkey> {
+        optional_key(self.successor)
+    }
+
+    pub fn set_successor(&mut self, successor: Option<Pubkey>) {
+        self.successor = stored_key(successor);
+    }
+
+    pub fn governance(&self) -> Option<Pubkey> {
+        optional_key(self.governance)
+    }
+
+    pub fn set_governance(&mut self, governance: Option<Pubkey>) {
+        self.governance = stored_key(governance);
+    }
+
+    pub fn pending_computation_offset(&self) -> Option<u64> {
+        (self.has_pending_computation != 0).then_some(self.pending_computation_offset)
+    }
+
+    pub fn set_pending_computation_offset(&mut self, offset: Option<u64>) {
+        self.has_pending_computation = offset.is_some() as u8;
+        self.pending_computation_offset = offset.unwrap_or_default();
+    }
+
+    pub fn operation(&self) -> VaultOperation {
+        match self.operation {
+            1 => VaultOperation::Deposit,
+            2 => VaultOperation::Withdraw,
+            3 => VaultOperation::Rebalance,
+            4 => VaultOperation::StrategyExit,
+            _ => VaultOperation::Idle,
+        }
+    }
+
+    /// Take the operation lock for `operation`, failing while another
+    /// operation (e.g. a confidential rebalance awaiting its result) holds it
+    pub fn begin_operation(&mut self, operation: VaultOperation) -> Result<()> {
+        require!(
+            self.operation() == VaultOperation::Idle,
+            crate::VaultError::VaultBusy
+        );
+        self.operation = operation as u8;
+        Ok(())
+    }
+
+    /// Release the operation lock
+    pub fn end_operation(&mut self) {
+        self.operation = VaultOperation::Idle as u8;
+    }
+
+    pub fn price_source(&self) -> PriceSource {
+        match self.price_source {
+            1 => PriceSource::MockOracle,
+            2 => PriceSource::Aggregated,
+            3 => PriceSource::CrossChecked,
+            4 => PriceSource::PythLazer,
+            5 => PriceSource::Chainlink,
+            _ => PriceSource::Switchboard,
+        }
+    }
+
+    pub fn set_price_source(&mut self, source: PriceSource) {
+        self.price_source = source as u8;
+    }
+
+    pub fn swap_backend(&self) -> SwapBackend {
+        match self.swap_backend {
+            1 => SwapBackend::Orca,
+            2 => SwapBackend::Jupiter,
+            3 => SwapBackend::MockAmm,
+            4 => SwapBackend::Sanctum,
+            _ => SwapBackend::Mock,
+        }
+    }
+
+    pub fn set_swap_backend(&mut self, backend: SwapBackend) {
+        self.swap_backend = backend as u8;
+    }
+
+    pub fn mock_pool(&self) -> MockPoolConfig {
+        MockPoolConfig {
+            fee_bps: self.mock_pool_fee_bps,
+            depth_usd: self.mock_pool_depth_usd,
+        }
+    }
+
+    pub fn set_mock_pool(&mut self, pool: MockPoolConfig) {
+        self.mock_pool_fee_bps = pool.fee_bps;
+        self.mock_pool_depth_usd = pool.depth_usd;
+    }
+
+    /// Whether new deposits are rejected: the vault was soft-closed, or it
+    /// is still being built and finalize_vault hasn't opened it
+    pub fn soft_closed(&self) -> bool {
+        self.soft_closed != 0 || !self.finalized()
+    }
+
+    pub fn set_soft_closed(&mut self, soft_closed: bool) {
+        self.soft_closed = soft_closed as u8;
+    }
+
+    /// False from init_vault until finalize_vault; vaults from create_vault
+    /// are finalized from the start
+    pub fn finalized(&self) -> bool {
+        self.unfinalized == 0
+    }
+
+    pub fn set_finalized(&mut self, finalized: bool) {
+        self.unfinalized = !finalized as u8;
+    }
+
+    /// Whether the shares are non-transferable (see soulbound.rs)
+    pub fn soulbound(&self) -> bool {
+        self.soulbound != 0
+    }
+
+    pub fn set_soulbound(&mut self, soulbound: bool) {
+        self.soulbound = soulbound as u8;
+    }
+
+    /// Token program of the share mint: Token-2022 for soulbound vaults
+    pub fn share_token_program(&self) -> Pubkey {
+        if self.soulbound() {
+            anchor_spl::token_2022::ID
+        } else {
+            anchor_spl::token::ID
+        }
+    }
+
+    pub fn restricted(&self) -> bool {
+        self.restricted != 0
+    }
+
+    pub fn set_restricted(&mut self, restricted: bool) {
+        self.restricted = restricted as u8;
+    }
+
+    /// Whether the guardian has paused rebalancing (guardian_pause)
+    pub fn rebalancing_paused(&self) -> bool {
+        self.rebalancing_paused != 0
+    }
+
+    pub fn set_rebalancing_paused(&mut self, paused: bool) {
+        self.rebalancing_paused = paused as u8;
+    }
+
+    /// Whether the guardian has paused deposits (guardian_pause); unlike
+    /// restricted(), withdrawal pricing is unaffected
+    pub fn guardian_deposits_paused(&self) -> bool {
+        self.guardian_deposits_paused != 0
+    }
+
+    pub fn set_guardian_deposits_paused(&mut self, paused: bool) {
+        self.guardian_deposits_paused = paused as u8;
+    }
+
+    pub fn governance_mode(&self) -> bool {
+        self.governance_mode != 0
+    }
+
+    pub fn set_governance_mode(&mut self, governance_mode: bool) {
+        self.governance_mode = governance_mode as u8;
+    }
+
+    pub fn market_cap_weighted(&self) -> bool {
+        self.market_cap_weighted != 0
+    }
+
+    pub fn set_market_cap_weighted(&mut self, market_cap_weighted: bool) {
+        self.market_cap_weighted = market_cap_weighted as u8;
+    }
+
+    /// Whether the target weights are sealed to the rebalancing MXE
+    /// (set_hidden_weights); the plaintext weights are then all zero
+    pub fn weights_hidden(&self) -> bool {
+        self.weights_hidden != 0
+    }
+
+    pub fn set_weights_hidden(&mut self, hidden: bool) {
+        self.weights_hidden = hidden as u8;
+    }
+
+    /// Share of a deposit, in whole percent, that buys asset `index`
+    ///
+    /// Its target weight, except in a hidden-weights vault: there deposits
+    /// go entirely to the SOL slot and the next confidential rebalance
+    /// spreads them, so allocations reveal nothing about the targets.
+    pub fn deposit_weight(&self, index: usize) -> u8 {
+        if self.weights_hidden() {
+            return if self.sol_slot() == Some(index) { 100 } else { 0 };
+        }
+        self.assets()[index].weight
+    }
+
+    pub fn lst_index(&self) -> bool {
+        self.lst_index != 0
+    }
+
+    pub fn set_lst_index(&mut self, lst_index: bool) {
+        self.lst_index = lst_index as u8;
+    }
+
+    /// Check a deposit mints some shares and at least min_deposit_shares,
+    /// so dust deposits abort instead of donating their SOL to holders
+    pub fn require_min_shares(&self, shares: u64) -> Result<()> {
+        require!(
+            shares > 0 && shares >= self.min_deposit_shares as u64,
+            crate::VaultError::MintedZeroShares
+        );
+        Ok(())
+    }
+
+    /// Decimals of the asset in slot `index`: BTC (8), ETH (18) and SOL (9)
+    /// by position, or 9 for every slot of an LST index
+    pub fn asset_decimals(&self, index: usize) -> u8 {
+        match index {
+            _ if self.lst_index() => crate::lst::LST_DECIMALS,
+            0 => 8,
+            1 => 18,
+            _ => 9,
+        }
     }
 
     /// Validate that asset weights sum to 100%
     /// This is a core invariant for proper allocation
     pub fn validate_weights(&self) -> Result<()> {
-        let total_weight: u64 = self.assets.iter().map(|a| a.weight as u64).sum();
+        let total_weight: u64 = self.assets().iter().map(|a| a.weight as u64).sum();
         require!(total_weight == 100, crate::VaultError::InvalidWeights);
         Ok(())
     }
@@ -81,7 +2087,112 @@ impl Vault {
     /// Get asset config by mint pubkey
     /// Useful for lookups during deposit/withdraw/rebalance
     pub fn get_asset_by_mint(&self, mint: &Pubkey) -> Option<&AssetConfig> {
-        self.assets.iter().find(|a| &a.mint == mint)
+        self.assets().iter().find(|a| &a.mint == mint)
+    }
+
+    /// Cache the NAV figures a state-changing instruction just computed, so
+    /// frontends can read TVL and share price from the vault account alone
+    pub fn cache_nav(&mut self, tvl_usd: i64, share_price: i64, total_shares: u64, timestamp: i64) {
+        self.last_tvl_usd = tvl_usd;
+        self.last_share_price = share_price;
+        self.total_shares = total_shares;
+        self.last_nav_update = timestamp;
+    }
+
+    /// Next per-vault event sequence number (starts at 1, never reused)
+    pub fn next_event_seq(&mut self) -> u64 {
+        self.event_seq += 1;
+        self.event_seq
+    }
+
+    /// Deposits are paused while the vault is soft-closed, restricted or
+    /// paused by its guardian
+    pub fn deposits_paused(&self) -> bool {
+        self.soft_closed() || self.restricted() || self.guardian_deposits_paused()
+    }
+
+    /// Reject a deposit into a soft-closed or guardian-paused vault; the
+    /// circuit breaker is checked against fresh prices by the caller
+    pub fn require_deposits_open(&self) -> Result<()> {
+        require!(!self.soft_closed(), crate::VaultError::VaultSoftClosed);
+        require!(!self.guardian_deposits_paused(), crate::VaultError::DepositsPaused);
+        Ok(())
+    }
+
+    /// Check that `signer` may execute governed instructions
+    /// (composition, fees, strategy and the governance settings themselves)
+    ///
+    /// Admin and governance can both act until governance_mode is turned on;
+    /// from then on only the governance can, typically by executing a passed
+    /// SPL Governance proposal.
+    pub fn require_governed_authority(&self, signer: &Pubkey) -> Result<()> {
+        let is_governance = self.governance().as_ref() == Some(signer);
+        if self.governance_mode() {
+            require!(is_governance, crate::VaultError::GovernanceOnly);
+        } else {
+            require!(
+                is_governance || *signer == self.admin,
+                crate::VaultError::Unauthorized
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether assets are priced one by one through the vault's AssetOracles
+    /// rather than from the BTC/ETH/SOL oracle markets
+    pub fn per_asset_pricing(&self) -> bool {
+        self.assets().len() != ORACLE_MARKETS
+    }
+
+    /// Oracle market (0 = BTC, 1 = ETH, 2 = SOL) that prices `mint`; None
+    /// for mints outside the basket and for per-asset priced vaults
+    pub fn oracle_market(&self, mint: &Pubkey) -> Option<usize> {
+        if self.per_asset_pricing() {
+            return None;
+        }
+        self.assets().iter().position(|asset| asset.mint == *mint)
+    }
+
+    /// Slot that holds the basket's SOL leg: the third slot of a BTC/ETH/SOL
+    /// basket, or the native mint's slot (if any) of a per-asset priced vault
+    pub fn sol_slot(&self) -> Option<usize> {
+        if self.per_asset_pricing() {
+            self.assets()
+                .iter()
+                .position(|asset| asset.mint == anchor_spl::token::spl_token::native_mint::ID)
+        } else {
+            Some(2)
+        }
+    }
+
+    /// The SOL slot's ATA when its mint is the native mint, so the SOL leg is
+    /// held as wrapped SOL rather than as the vault PDA's own lamports
+    pub fn wrapped_sol_ata(&self) -> Option<Pubkey> {
+        self.sol_slot()
+            .and_then(|slot| self.assets().get(slot))
+            .filter(|asset| asset.mint == anchor_spl::token::spl_token::native_mint::ID)
+            .map(|asset| asset.ata)
+    }
+
+    /// Fee lamports held in the vault PDA that do not belong to shareholders
+    pub fn fee_reserve(&self) -> u64 {
+        self.pending_fees
+            .saturating_add(self.protocol_fees_owed)
+            .saturating_add(self.creator_fees_owed)
+            .saturating_add(self.staker_fees_owed)
+    }
+
+    /// Split pending fees between stakers, protocol and creator at the current ratios
+    /// Stakers take their cut first; the rest is split by `creator_fee_share_bps`
+    pub fn settle_fees(&mut self, creator_fee_share_bps: u16) -> Result<()> {
+        let stakers = math::bps_of(self.pending_fees, self.staker_fee_share_bps)?;
+        let remaining = self.pending_fees - stakers;
+        let creator = math::bps_of(remaining, creator_fee_share_bps)?;
+        self.staker_fees_owed = self.staker_fees_owed.saturating_add(stakers);
+        self.creator_fees_owed = self.creator_fees_owed.saturating_add(creator);
+        self.protocol_fees_owed = self.protocol_fees_owed.saturating_add(remaining - creator);
+        self.pending_fees = 0;
+        Ok(())
     }
 
     /// Update total assets (only callable by the vault authority)
@@ -107,3 +2218,48 @@ impl Vault {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_layout_is_a_prefix() {
+        assert_eq!(Vault::LEN % 8, 0);
+        assert_eq!(Vault::V2_LEN, 8 + std::mem::offset_of!(Vault, operation) + 1);
+        // min_deposit_shares, five flags and the reserved bytes
+        assert_eq!(Vault::LEN - Vault::V2_LEN, 40);
+    }
+
+    #[test]
+    fn test_upgrade_v2_unpacks_flags() {
+        let mut vault = Vault {
+            version: 2,
+            soft_closed: 1 | 4,
+            restricted: 2 | 4,
+            market_cap_weighted: 1 | 2,
+            v2_min_deposit_shares: 1_000u32.to_le_bytes(),
+            ..Default::default()
+        };
+        vault.upgrade_v2();
+
+        assert_eq!(vault.version, VAULT_VERSION);
+        assert!(vault.soft_closed() && vault.finalized() && vault.soulbound());
+        assert!(!vault.restricted());
+        assert!(vault.rebalancing_paused() && vault.guardian_deposits_paused());
+        assert!(vault.market_cap_weighted() && vault.weights_hidden());
+        assert_eq!(vault.min_deposit_shares, 1_000);
+        assert_eq!(vault.v2_min_deposit_shares, [0; 4]);
+        assert_eq!((vault.soft_closed, vault.restricted, vault.market_cap_weighted), (1, 0, 1));
+
+        // An unfinalized vault stays closed until finalize_vault
+        let mut vault = Vault {
+            soft_closed: 2,
+            restricted: 1,
+            ..Default::default()
+        };
+        vault.upgrade_v2();
+        assert!(!vault.finalized() && vault.soft_closed() && !vault.soulbound());
+        assert!(vault.restricted() && !vault.rebalancing_paused() && !vault.guardian_deposits_paused());
+    }
+}
@@ -0,0 +1,89 @@
+//! Cached value of the vault's staking strategy
+//!
+//! The SOL a vault stakes through its strategy sits outside its ATAs, so
+//! TVL only sees it through the StrategyValue account: sync_strategy_value
+//! records the strategy's report_value (mSOL at Marinade's exchange rate,
+//! yield included), and instructions that move SOL into or out of the
+//! strategy adjust it in place. Vaults with a strategy pass the account with
+//! their price accounts and are valued with it only while it is fresh.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{StrategyValue, Vault};
+use crate::VaultError;
+
+/// Oldest sync accepted when the vault has not configured max_age_secs
+pub const DEFAULT_MAX_STRATEGY_VALUE_AGE_SECS: i64 = 600;
+
+/// Whether `account` is a StrategyValue owned by this program
+pub fn is_strategy_value(account: &AccountInfo) -> bool {
+    account.owner == &crate::ID
+        && account
+            .try_borrow_data()
+            .map(|data| data.starts_with(StrategyValue::DISCRIMINATOR))
+            .unwrap_or(false)
+}
+
+/// Oldest sync of `value` accepted for valuation, in seconds
+pub fn max_age_secs(value: &StrategyValue) -> i64 {
+    match value.max_age_secs {
+        0 => DEFAULT_MAX_STRATEGY_VALUE_AGE_SECS,
+        secs => secs as i64,
+    }
+}
+
+/// The vault's StrategyValue among `remaining_accounts`, checked against its
+/// current strategy and freshness
+fn find<'a, 'info>(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    remaining_accounts: &'a [AccountInfo<'info>],
+    now: i64,
+) -> Result<Option<(&'a AccountInfo<'info>, StrategyValue)>> {
+    let Some(strategy) = vault.marinade_strategy() else {
+        return Ok(None);
+    };
+    let account = remaining_accounts
+        .iter()
+        .find(|acc| is_strategy_value(acc))
+        .ok_or(VaultError::StrategyValueRequired)?;
+    let value = StrategyValue::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    require!(
+        value.vault == *vault_key && value.strategy == strategy,
+        VaultError::StrategyValueRequired
+    );
+    require!(
+        now - value.updated_at <= max_age_secs(&value),
+        VaultError::StaleStrategyValue
+    );
+    Ok(Some((account, value)))
+}
+
+/// Lamports staked through the vault's strategy (0 without one)
+///
+/// Vaults with a strategy must pass a StrategyValue synced within its max age.
+pub fn staked_lamports(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    Ok(find(vault, vault_key, remaining_accounts, now)?.map_or(0, |(_, value)| value.value_lamports))
+}
+
+/// Move the cached value by `delta` lamports after SOL entered or left the
+/// strategy outside a sync
+pub fn adjust(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+    delta: i64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let Some((account, mut value)) = find(vault, vault_key, remaining_accounts, now)? else {
+        return Ok(());
+    };
+    require!(account.is_writable, VaultError::StrategyValueRequired);
+    value.value_lamports = value.value_lamports.saturating_add_signed(delta);
+    value.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
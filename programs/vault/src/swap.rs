@@ -7,10 +7,27 @@ use anchor_lang::prelude::*;
 /// 
 /// **Design Philosophy:**
 /// - Uses real Switchboard Oracle Quotes for realistic simulations
-/// - Simple atomic swaps without slippage/fees for devnet testing
+/// - Simulated pool fee and constant-product price impact (see MockPoolConfig)
 /// - Easy to replace with Jupiter CPI when moving to mainnet
 pub struct MockSwap;
 
+/// Simulated pool MockSwap trades against
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct MockPoolConfig {
+    /// Pool fee taken from the input, in bps
+    pub fee_bps: u16,
+    /// Liquidity on each side of the pool in micro-USD (0 = no price impact)
+    pub depth_usd: u64,
+}
+
+impl MockPoolConfig {
+    /// 30 bps fee, $1M on each side
+    pub const DEFAULT: Self = Self {
+        fee_bps: 30,
+        depth_usd: 1_000_000 * 1_000_000,
+    };
+}
+
 impl MockSwap {
 
     /// Calculate output amount for a swap using Switchboard Oracle Quotes
@@ -78,6 +95,55 @@ impl MockSwap {
         Ok(amount_out as u64)
     }
 
+    /// Simulate a swap against a constant-product pool holding `pool.depth_usd`
+    /// on each side at oracle prices
+    ///
+    /// The fee is taken from the input first. With reserves x (input) and
+    /// y (output) at the oracle price, x * y = k gives
+    /// amount_out = y * fair_out / (y + fair_out), where fair_out is the
+    /// oracle-priced output of the input after fees.
+    #[allow(clippy::too_many_arguments)]
+    pub fn simulate_swap(
+        amount_in: u64,
+        from_price: i64,
+        from_expo: i32,
+        to_price: i64,
+        to_expo: i32,
+        from_decimals: u8,
+        to_decimals: u8,
+        pool: &MockPoolConfig,
+    ) -> Result<u64> {
+        let amount_after_fee = (amount_in as u128 * (10_000 - pool.fee_bps as u128) / 10_000) as u64;
+        let fair_out = Self::calculate_swap_output(
+            amount_after_fee,
+            from_price,
+            from_expo,
+            to_price,
+            to_expo,
+            from_decimals,
+            to_decimals,
+        )?;
+        if pool.depth_usd == 0 {
+            return Ok(fair_out);
+        }
+
+        // Output-side reserve: depth_usd worth of the output token ($1, 6 decimals in)
+        let reserve_out = Self::calculate_swap_output(
+            pool.depth_usd,
+            1_000_000,
+            -6,
+            to_price,
+            to_expo,
+            6,
+            to_decimals,
+        )? as u128;
+
+        let amount_out = reserve_out * fair_out as u128 / (reserve_out + fair_out as u128);
+        require!(amount_out > 0, crate::VaultError::InvalidAmount);
+
+        Ok(amount_out as u64)
+    }
+
     /*
     /// Execute a swap from native SOL to an SPL token
     /// 
@@ -112,8 +178,8 @@ mod tests {
         // Expected: ~1.666 ETH = 1_666_666_666_666_666_666 (18 decimals)
 
         let amount_in = 10_000_000u64; // 0.1 BTC
-        let btc_price = 50_000_00000000i64; // $50k with 8 decimals
-        let eth_price = 3_000_00000000i64; // $3k with 8 decimals
+        let btc_price = 5_000_000_000_000i64; // $50k with 8 decimals
+        let eth_price = 300_000_000_000i64; // $3k with 8 decimals
         let btc_expo = -8i32;
         let eth_expo = -8i32;
         let btc_decimals = 8u8;
@@ -169,4 +235,21 @@ mod tests {
         // 1 SOL ($100) should give 100 USDC
         assert_eq!(amount_out, 100_000_000u64);
     }
+
+    #[test]
+    fn test_simulated_fee_and_price_impact() {
+        // 1 SOL ($100) into a pool without depth only pays the 30 bps fee
+        let no_impact = MockPoolConfig { fee_bps: 30, depth_usd: 0 };
+        let out = MockSwap::simulate_swap(1_000_000_000, 100_00000000, -8, 1_00000000, -8, 9, 6, &no_impact).unwrap();
+        assert_eq!(out, 99_700_000u64);
+
+        // $100 against $1,000 of depth: 99.7 * 1000 / (1000 + 99.7) ≈ 90.66 USDC
+        let shallow = MockPoolConfig { fee_bps: 30, depth_usd: 1_000_000_000 };
+        let out = MockSwap::simulate_swap(1_000_000_000, 100_00000000, -8, 1_00000000, -8, 9, 6, &shallow).unwrap();
+        assert_eq!(out, 90_661_089u64);
+
+        // Larger trades get worse prices
+        let out_10x = MockSwap::simulate_swap(10_000_000_000, 100_00000000, -8, 1_00000000, -8, 9, 6, &shallow).unwrap();
+        assert!(out_10x < out * 10);
+    }
 }
@@ -0,0 +1,397 @@
+//! Swap engine
+//!
+//! Single place where the vault swaps tokens. Deposit allocation, withdrawal
+//! liquidation and rebalancing all call `SwapEngine::execute_swap`, and the
+//! vault's `swap_backend` decides how the swap is carried out, so a backend
+//! can be switched per vault without touching instruction logic.
+//!
+//! Backend accounts are passed after an instruction's own remaining accounts,
+//! one group per swap, in the order the swaps execute:
+//! - Mock: none
+//! - Orca: 11 accounts per swap (see `ORCA_ACCOUNTS_PER_SWAP`)
+//! - MockAmm: 5 accounts per pool hop (see `MOCK_AMM_ACCOUNTS_PER_HOP`);
+//!   token-to-token swaps take two hops through SOL
+//! - Jupiter: not available on-chain yet
+//! - Sanctum: the router program followed by the route's accounts, up to the
+//!   next router program account (routes vary in length; see `sanctum`).
+//!   SOL legs go through a vault-owned wrapped SOL account: SOL inputs are
+//!   wrapped into it first and SOL outputs unwrapped by closing it, so it
+//!   must exist before each swap.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token::TokenAccount;
+
+use crate::math;
+use crate::orca;
+use crate::sanctum;
+use crate::state::Vault;
+use crate::swap::{MockPoolConfig, MockSwap};
+use crate::VaultError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SwapBackend {
+    Mock,     // Oracle-priced MockSwap with simulated fee/impact (for devnet testing)
+    Orca,     // Direct Orca Whirlpool CPI for the top pairs
+    Jupiter,  // Jupiter aggregator (routes are built off-chain; not wired yet)
+    MockAmm,  // Devnet mock-amm pools that move real test tokens
+    Sanctum,  // Sanctum router for SOL/LST and LST/LST conversions
+}
+
+/// Orca accounts per swap:
+/// [whirlpool_program, token_program, whirlpool, vault_token_a, token_vault_a,
+///  vault_token_b, token_vault_b, tick_array_0, tick_array_1, tick_array_2, oracle]
+pub const ORCA_ACCOUNTS_PER_SWAP: usize = 11;
+
+/// MockAmm accounts per pool hop:
+/// [mock_amm_program, pool, pool_token_vault, vault_token_account, token_program]
+pub const MOCK_AMM_ACCOUNTS_PER_HOP: usize = 5;
+
+/// One side of a swap
+/// `price` is only used by the Mock backend (same units as MockSwap)
+#[derive(Clone, Copy, Debug)]
+pub struct SwapAsset {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub price: i64,
+    pub expo: i32,
+}
+
+impl SwapAsset {
+    /// Asset for swaps that never reach the Mock backend (only the mint matters)
+    pub fn unpriced(mint: Pubkey) -> Self {
+        Self {
+            mint,
+            decimals: 0,
+            price: 0,
+            expo: 0,
+        }
+    }
+}
+
+/// Split `remaining_accounts` into the instruction's own accounts and the
+/// backend accounts that follow them
+///
+/// The Mock backend takes no accounts, so for Mock vaults this keeps the
+/// exact-length check the instructions always had.
+pub fn backend_accounts<'a, 'info>(
+    backend: SwapBackend,
+    remaining_accounts: &'a [AccountInfo<'info>],
+    expected_accounts: usize,
+) -> Result<&'a [AccountInfo<'info>]> {
+    require!(
+        remaining_accounts.len() >= expected_accounts,
+        VaultError::InvalidRemainingAccounts
+    );
+    let backend_accounts = &remaining_accounts[expected_accounts..];
+    if backend == SwapBackend::Mock {
+        require!(backend_accounts.is_empty(), VaultError::InvalidRemainingAccounts);
+    }
+    Ok(backend_accounts)
+}
+
+/// Oracle quote for swapping `amount_in` of `from` into `to`, and that
+/// quote less `max_slippage_bps` (the least a swap may return)
+pub fn quote(from: &SwapAsset, to: &SwapAsset, amount_in: u64, max_slippage_bps: u16) -> Result<(u64, u64)> {
+    let quote = MockSwap::calculate_swap_output(
+        amount_in,
+        from.price,
+        from.expo,
+        to.price,
+        to.expo,
+        from.decimals,
+        to.decimals,
+    )?;
+    let min_out = math::bps_of(quote, 10_000u16.saturating_sub(max_slippage_bps))?;
+    Ok((quote, min_out))
+}
+
+/// Executes swaps on behalf of the vault through its configured backend
+pub struct SwapEngine<'a, 'info> {
+    backend: SwapBackend,
+    max_slippage_bps: u16,
+    mock_pool: MockPoolConfig,
+    /// Vault PDA, owner of the vault's token accounts
+    authority: AccountInfo<'info>,
+    signer_seeds: &'a [&'a [&'a [u8]]],
+    accounts: &'a [AccountInfo<'info>],
+    next_account: usize,
+}
+
+impl<'a, 'info> SwapEngine<'a, 'info> {
+    /// Engine for `vault`'s backend, slippage tolerance and mock pool
+    pub fn new(
+        vault: &Vault,
+        authority: AccountInfo<'info>,
+        signer_seeds: &'a [&'a [&'a [u8]]],
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Self {
+        Self {
+            backend: vault.swap_backend(),
+            max_slippage_bps: vault.max_slippage_bps,
+            mock_pool: vault.mock_pool(),
+            authority,
+            signer_seeds,
+            accounts,
+            next_account: 0,
+        }
+    }
+
+    /// Swap `amount_in` of `from` into `to`, returning the amount received
+    ///
+    /// Fails with SlippageExceeded if less than `min_out` comes back.
+    /// The Mock backend only prices the swap against its simulated pool; no tokens move.
+    pub fn execute_swap(
+        &mut self,
+        from: &SwapAsset,
+        to: &SwapAsset,
+        amount_in: u64,
+        min_out: u64,
+    ) -> Result<u64> {
+        let amount_out = match self.backend {
+            SwapBackend::Mock => MockSwap::simulate_swap(
+                amount_in,
+                from.price,
+                from.expo,
+                to.price,
+                to.expo,
+                from.decimals,
+                to.decimals,
+                &self.mock_pool,
+            )?,
+            SwapBackend::Orca => self.swap_orca(from, to, amount_in, min_out)?,
+            SwapBackend::Jupiter => return err!(VaultError::SwapBackendUnavailable),
+            SwapBackend::MockAmm => self.swap_mock_amm(from, to, amount_in)?,
+            SwapBackend::Sanctum => self.swap_sanctum(from, to, amount_in)?,
+        };
+
+        require!(amount_out >= min_out, VaultError::SlippageExceeded);
+
+        Ok(amount_out)
+    }
+
+    /// Minimum output for swapping `amount_in` of `from` into `to`: the
+    /// oracle quote less the vault's max_slippage_bps
+    pub fn min_out(&self, from: &SwapAsset, to: &SwapAsset, amount_in: u64) -> Result<u64> {
+        Ok(quote(from, to, amount_in, self.max_slippage_bps)?.1)
+    }
+
+    /// Take the next `count` backend accounts
+    fn take_accounts(&mut self, count: usize) -> Result<&'a [AccountInfo<'info>]> {
+        let end = self.next_account + count;
+        require!(end <= self.accounts.len(), VaultError::InvalidRemainingAccounts);
+        let group = &self.accounts[self.next_account..end];
+        self.next_account = end;
+        Ok(group)
+    }
+
+    /// Take a variable-length route: `program` followed by every account up
+    /// to its next occurrence (or the end)
+    fn take_route(&mut self, program: &Pubkey) -> Result<&'a [AccountInfo<'info>]> {
+        let rest = &self.accounts[self.next_account.min(self.accounts.len())..];
+        require!(
+            rest.first().is_some_and(|account| account.key == program),
+            VaultError::InvalidRemainingAccounts
+        );
+        let count = rest[1..]
+            .iter()
+            .position(|account| account.key == program)
+            .map_or(rest.len(), |next| next + 1);
+        self.take_accounts(count)
+    }
+
+    fn swap_orca(&mut self, from: &SwapAsset, to: &SwapAsset, amount_in: u64, min_out: u64) -> Result<u64> {
+        let group = self.take_accounts(ORCA_ACCOUNTS_PER_SWAP)?;
+        let [
+            whirlpool_program,
+            token_program,
+            whirlpool,
+            vault_token_a,
+            token_vault_a,
+            vault_token_b,
+            token_vault_b,
+            tick_array_0,
+            tick_array_1,
+            tick_array_2,
+            oracle,
+        ] = group
+        else {
+            return err!(VaultError::InvalidRemainingAccounts);
+        };
+
+        require!(
+            whirlpool_program.key() == orca::WHIRLPOOL_PROGRAM_ID
+                && token_program.key() == anchor_spl::token::ID,
+            VaultError::InvalidWhirlpool
+        );
+
+        // Pool accounts must be the ones recorded in the Whirlpool itself
+        let pool = orca::WhirlpoolInfo::load(whirlpool)?;
+        require!(pool.is_supported(), VaultError::UnsupportedSwapPair);
+        let a_to_b = if from.mint == pool.token_mint_a && to.mint == pool.token_mint_b {
+            true
+        } else if from.mint == pool.token_mint_b && to.mint == pool.token_mint_a {
+            false
+        } else {
+            return err!(VaultError::UnsupportedSwapPair);
+        };
+
+        let account_a = self.load_vault_token_account(vault_token_a)?;
+        let account_b = self.load_vault_token_account(vault_token_b)?;
+        require!(
+            token_vault_a.key() == pool.token_vault_a
+                && token_vault_b.key() == pool.token_vault_b
+                && account_a.mint == pool.token_mint_a
+                && account_b.mint == pool.token_mint_b,
+            VaultError::InvalidWhirlpool
+        );
+
+        let destination = if a_to_b { vault_token_b } else { vault_token_a };
+        let balance_before = self.load_vault_token_account(destination)?.amount;
+
+        orca::swap(
+            &orca::WhirlpoolSwap {
+                whirlpool_program,
+                token_program,
+                token_authority: &self.authority,
+                whirlpool,
+                token_owner_account_a: vault_token_a,
+                token_vault_a,
+                token_owner_account_b: vault_token_b,
+                token_vault_b,
+                tick_arrays: [tick_array_0, tick_array_1, tick_array_2],
+                oracle,
+            },
+            amount_in,
+            min_out,
+            a_to_b,
+            self.signer_seeds,
+        )?;
+
+        // Realized output, measured on the vault's own token account
+        let balance_after = self.load_vault_token_account(destination)?.amount;
+        Ok(balance_after.saturating_sub(balance_before))
+    }
+
+    /// Pools pair native SOL with one token; token-to-token goes through SOL
+    fn swap_mock_amm(&mut self, from: &SwapAsset, to: &SwapAsset, amount_in: u64) -> Result<u64> {
+        if from.mint == native_mint::ID {
+            self.mock_amm_hop(&to.mint, amount_in, true)
+        } else if to.mint == native_mint::ID {
+            self.mock_amm_hop(&from.mint, amount_in, false)
+        } else {
+            let sol = self.mock_amm_hop(&from.mint, amount_in, false)?;
+            self.mock_amm_hop(&to.mint, sol, true)
+        }
+    }
+
+    /// One swap against the SOL/`token_mint` pool; the overall minimum is
+    /// checked by execute_swap
+    fn mock_amm_hop(&mut self, token_mint: &Pubkey, amount_in: u64, sol_to_token: bool) -> Result<u64> {
+        let group = self.take_accounts(MOCK_AMM_ACCOUNTS_PER_HOP)?;
+        let [program, pool, pool_token_vault, vault_token_account, token_program] = group else {
+            return err!(VaultError::InvalidRemainingAccounts);
+        };
+
+        let (expected_pool, _) = Pubkey::find_program_address(&[b"pool", token_mint.as_ref()], &mock_amm::ID);
+        require!(program.key() == mock_amm::ID, VaultError::InvalidRemainingAccounts);
+        require!(pool.key() == expected_pool, VaultError::UnsupportedSwapPair);
+        self.load_vault_token_account(vault_token_account)?;
+
+        // The vault PDA cannot be debited by the system program, so SOL
+        // inputs are credited to the pool directly before the swap
+        if sol_to_token {
+            **self.authority.try_borrow_mut_lamports()? -= amount_in;
+            **pool.try_borrow_mut_lamports()? += amount_in;
+        }
+
+        let cpi_accounts = mock_amm::cpi::accounts::Swap {
+            pool: pool.clone(),
+            token_vault: pool_token_vault.clone(),
+            token_account: vault_token_account.clone(),
+            authority: self.authority.clone(),
+            sol_recipient: self.authority.clone(),
+            token_program: token_program.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(program.clone(), cpi_accounts, self.signer_seeds);
+        let amount_out = mock_amm::cpi::swap(cpi_ctx, amount_in, 0, sol_to_token)?.get();
+
+        Ok(amount_out)
+    }
+
+    /// Convert through the Sanctum router; SOL legs are wrapped/unwrapped
+    /// around the router call
+    fn swap_sanctum(&mut self, from: &SwapAsset, to: &SwapAsset, amount_in: u64) -> Result<u64> {
+        let route = sanctum::Route::between(from.mint == native_mint::ID, to.mint == native_mint::ID)
+            .ok_or(VaultError::UnsupportedSwapPair)?;
+        let group = self.take_route(&sanctum::SANCTUM_ROUTER_PROGRAM_ID)?;
+        let (router_program, route_accounts) = group.split_first().ok_or(VaultError::InvalidRemainingAccounts)?;
+        require!(
+            route_accounts.len() >= sanctum::ROUTE_PREFIX_ACCOUNTS,
+            VaultError::InvalidRemainingAccounts
+        );
+        let [user, source, destination] = &route_accounts[..sanctum::ROUTE_PREFIX_ACCOUNTS] else {
+            return err!(VaultError::InvalidRemainingAccounts);
+        };
+
+        require!(user.key() == self.authority.key(), VaultError::InvalidRemainingAccounts);
+        let source_account = self.load_vault_token_account(source)?;
+        let destination_account = self.load_vault_token_account(destination)?;
+        require!(
+            source_account.mint == from.mint && destination_account.mint == to.mint,
+            VaultError::UnsupportedSwapPair
+        );
+        let balance_before = destination_account.amount;
+        let token_program = route_accounts
+            .iter()
+            .find(|account| account.key() == anchor_spl::token::ID);
+
+        if route == sanctum::Route::StakeWrappedSol {
+            let token_program = token_program.ok_or(VaultError::InvalidRemainingAccounts)?;
+            **self.authority.try_borrow_mut_lamports()? -= amount_in;
+            **source.try_borrow_mut_lamports()? += amount_in;
+            anchor_spl::token::sync_native(CpiContext::new(
+                token_program.clone(),
+                anchor_spl::token::SyncNative {
+                    account: source.clone(),
+                },
+            ))?;
+        }
+
+        sanctum::swap(route, router_program, &self.authority, route_accounts, amount_in, self.signer_seeds)?;
+
+        // Realized output, measured on the vault's own token account
+        let balance_after = self.load_vault_token_account(destination)?.amount;
+        let amount_out = balance_after.saturating_sub(balance_before);
+
+        if route == sanctum::Route::WithdrawWrappedSol {
+            let token_program = token_program.ok_or(VaultError::InvalidRemainingAccounts)?;
+            anchor_spl::token::close_account(CpiContext::new_with_signer(
+                token_program.clone(),
+                anchor_spl::token::CloseAccount {
+                    account: destination.clone(),
+                    destination: self.authority.clone(),
+                    authority: self.authority.clone(),
+                },
+                self.signer_seeds,
+            ))?;
+        }
+
+        Ok(amount_out)
+    }
+
+    /// Deserialize a token account and check the vault owns it
+    fn load_vault_token_account(&self, account: &AccountInfo) -> Result<TokenAccount> {
+        require!(
+            account.owner == &anchor_spl::token::ID,
+            VaultError::InvalidATA
+        );
+        let data = account.try_borrow_data()?;
+        let token_account = TokenAccount::try_deserialize(&mut &data[..])?;
+        require!(
+            token_account.owner == self.authority.key(),
+            VaultError::InvalidATA
+        );
+        Ok(token_account)
+    }
+}
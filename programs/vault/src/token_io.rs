@@ -0,0 +1,159 @@
+//! Token program helpers for SPL Token and Token-2022 assets
+//!
+//! A vault asset's ATA is owned by the token program of its mint, so the
+//! program for any transfer is read off the account itself. Token-2022 mints
+//! may carry a transfer fee: the receiver gets the amount less the fee, so
+//! transfers into the vault are grossed up until the vault receives what it
+//! asked for.
+//!
+//! A SOL slot on the native mint is held as wrapped SOL: deposits move the
+//! SOL leg from the vault PDA's lamports into its wSOL ATA (sync_native), and
+//! withdrawals pay it out through the user's wSOL account, which is closed
+//! to unwrap it.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::Mint as MintState;
+use anchor_spl::token_interface::{self, TokenAccount};
+
+use crate::VaultError;
+
+/// SPL Token or Token-2022
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == anchor_spl::token::ID || *program_id == anchor_spl::token_2022::ID
+}
+
+/// Deserialize a token account of either token program
+pub fn load_token_account(account: &AccountInfo) -> Result<TokenAccount> {
+    require!(is_token_program(account.owner), VaultError::InvalidATA);
+    let data = account.try_borrow_data()?;
+    TokenAccount::try_deserialize(&mut &data[..])
+}
+
+/// Balance of a token account of either token program
+pub fn token_amount(account: &AccountInfo) -> Result<u64> {
+    Ok(load_token_account(account)?.amount)
+}
+
+/// Decimals of a mint of either token program
+pub fn mint_decimals(mint: &AccountInfo) -> Result<u8> {
+    require!(is_token_program(mint.owner), VaultError::InvalidMint);
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data).map_err(|_| VaultError::InvalidMint)?;
+    Ok(state.base.decimals)
+}
+
+/// Transfer fee config of a Token-2022 mint, if it has one
+fn transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(None);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data).map_err(|_| VaultError::InvalidMint)?;
+    Ok(state.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+/// Amount to send so the receiver ends up with `net_amount` of `mint`
+pub fn amount_before_fee(mint: &AccountInfo, net_amount: u64) -> Result<u64> {
+    let Some(config) = transfer_fee_config(mint)? else {
+        return Ok(net_amount);
+    };
+    let fee = config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net_amount)
+        .ok_or(VaultError::MathOverflow)?;
+    net_amount.checked_add(fee).ok_or(VaultError::MathOverflow.into())
+}
+
+/// Token program (SPL Token or Token-2022) owning `account`, picked from
+/// the programs the instruction was given
+pub fn program_for<'a, 'info>(
+    account: &AccountInfo<'info>,
+    token_program: &'a AccountInfo<'info>,
+    token_2022_program: Option<&'a AccountInfo<'info>>,
+) -> Result<&'a AccountInfo<'info>> {
+    if *account.owner == anchor_spl::token_2022::ID {
+        token_2022_program.ok_or(error!(VaultError::InvalidRemainingAccounts))
+    } else {
+        require!(*account.owner == anchor_spl::token::ID, VaultError::InvalidATA);
+        Ok(token_program)
+    }
+}
+
+/// transfer_checked through whichever token program owns `from`
+#[allow(clippy::too_many_arguments)]
+pub fn transfer<'info>(
+    token_program: &AccountInfo<'info>,
+    token_2022_program: Option<&AccountInfo<'info>>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let program = program_for(from, token_program, token_2022_program)?;
+    let decimals = mint_decimals(mint)?;
+    let cpi_accounts = token_interface::TransferChecked {
+        from: from.clone(),
+        mint: mint.clone(),
+        to: to.clone(),
+        authority: authority.clone(),
+    };
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(program.clone(), cpi_accounts, signer_seeds),
+        amount,
+        decimals,
+    )
+}
+
+/// Move `lamports` from the vault PDA into its wSOL account and sync the
+/// token balance
+pub fn wrap_sol<'info>(
+    vault: &AccountInfo<'info>,
+    wsol_account: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    lamports: u64,
+) -> Result<()> {
+    **vault.try_borrow_mut_lamports()? -= lamports;
+    **wsol_account.try_borrow_mut_lamports()? += lamports;
+    anchor_spl::token::sync_native(CpiContext::new(
+        token_program.clone(),
+        anchor_spl::token::SyncNative {
+            account: wsol_account.clone(),
+        },
+    ))
+}
+
+/// Pay `lamports` of the vault's wrapped SOL to `user` as native SOL: move
+/// them into the user's wSOL account, then close it to the user
+pub fn unwrap_sol<'info>(
+    vault: &AccountInfo<'info>,
+    vault_wsol_account: &AccountInfo<'info>,
+    user_wsol_account: &AccountInfo<'info>,
+    user: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    lamports: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            anchor_spl::token::Transfer {
+                from: vault_wsol_account.clone(),
+                to: user_wsol_account.clone(),
+                authority: vault.clone(),
+            },
+            signer_seeds,
+        ),
+        lamports,
+    )?;
+    anchor_spl::token::close_account(CpiContext::new(
+        token_program.clone(),
+        anchor_spl::token::CloseAccount {
+            account: user_wsol_account.clone(),
+            destination: user.clone(),
+            authority: user.clone(),
+        },
+    ))
+}
@@ -0,0 +1,97 @@
+//! Tracking error
+//!
+//! How far a vault's holdings stray from its target weights, sampled by
+//! snapshot_nav. A sample's deviation is half the summed absolute gaps between
+//! current and target weights: the share of TVL a rebalance would have to
+//! trade to put the vault back on target. The tracking error is the root mean
+//! square of the samples over a rolling window of TRACKING_ERROR_WINDOW
+//! snapshots, kept as an exponentially weighted mean square so the account
+//! stays fixed-size. Drift between rebalances pushes it up and each rebalance
+//! pulls the following samples back toward zero, so a vault that rebalances
+//! late or trades poorly shows a higher figure than a tight one.
+
+/// Snapshots the rolling tracking error averages over
+pub const TRACKING_ERROR_WINDOW: u64 = 30;
+
+/// Deviation from target in bps of TVL, given target weights (whole percent)
+/// and current USD values summing to `total_usd`; None for an empty vault
+pub fn deviation_bps(weights: &[u8], current_usds: &[i64], total_usd: i64) -> Option<u32> {
+    if total_usd <= 0 {
+        return None;
+    }
+    let gaps: i128 = weights
+        .iter()
+        .zip(current_usds)
+        .map(|(weight, usd)| {
+            let current_bps = *usd as i128 * 10_000 / total_usd as i128;
+            (current_bps - *weight as i128 * 100).abs()
+        })
+        .sum();
+    Some((gaps / 2).min(10_000) as u32)
+}
+
+/// Mean square deviation (bps²) after one more sample: the plain average
+/// over the first TRACKING_ERROR_WINDOW samples, exponentially weighted after
+pub fn next_mean_square(mean_square: u64, samples: u32, deviation_bps: u32) -> u64 {
+    let n = (samples as u64 + 1).min(TRACKING_ERROR_WINDOW);
+    let square = deviation_bps as u64 * deviation_bps as u64;
+    if square >= mean_square {
+        mean_square + (square - mean_square) / n
+    } else {
+        mean_square - (mean_square - square) / n
+    }
+}
+
+/// Tracking error in bps: the square root of the mean square, rounded down
+pub fn tracking_error_bps(mean_square: u64) -> u32 {
+    if mean_square < 2 {
+        return mean_square as u32;
+    }
+    // Newton's method from an estimate above the root
+    let mut root = mean_square;
+    let mut next = (root + mean_square / root) / 2;
+    while next < root {
+        root = next;
+        next = (root + mean_square / root) / 2;
+    }
+    root as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deviation() {
+        // $60 / $30 / $10 against 40 / 30 / 30: 20% over and 20% under
+        assert_eq!(
+            deviation_bps(&[40, 30, 30], &[60_000_000, 30_000_000, 10_000_000], 100_000_000),
+            Some(2_000)
+        );
+        assert_eq!(deviation_bps(&[50, 50], &[50_000_000, 50_000_000], 100_000_000), Some(0));
+        assert_eq!(deviation_bps(&[50, 50], &[0, 0], 0), None);
+    }
+
+    #[test]
+    fn test_rolling_tracking_error() {
+        // Plain average while the window fills: 300 then 0 → (90_000 + 0) / 2
+        let ms = next_mean_square(0, 0, 300);
+        assert_eq!(ms, 90_000);
+        let ms = next_mean_square(ms, 1, 0);
+        assert_eq!(ms, 45_000);
+        assert_eq!(tracking_error_bps(ms), 212);
+
+        // Past the window each sample moves the mean by 1/30 of the gap
+        assert_eq!(next_mean_square(90_000, 100, 0), 87_000);
+        assert_eq!(next_mean_square(0, 100, 300), 3_000);
+    }
+
+    #[test]
+    fn test_tracking_error_sqrt() {
+        assert_eq!(tracking_error_bps(0), 0);
+        assert_eq!(tracking_error_bps(1), 1);
+        assert_eq!(tracking_error_bps(99), 9);
+        assert_eq!(tracking_error_bps(100), 10);
+        assert_eq!(tracking_error_bps(100_000_000), 10_000);
+    }
+}
@@ -32,7 +32,8 @@ mod circuits {
     }
 
     /// Output structure with rebalancing decision
-    /// Kept simple to avoid MPC compiler limitations
+    /// Kept simple to avoid MPC compiler limitations; revealed so the vault
+    /// can act on it without trusting whoever decrypts it
     pub struct RebalancingResult {
         pub needs_rebalance: bool,
         pub btc_drift: i16,      // Drift in percentage points
@@ -45,11 +46,11 @@ mod circuits {
     /// 
     /// This function analyzes encrypted portfolio data to determine if
     /// rebalancing is required, without revealing actual balances or prices.
-    /// Only the rebalancing decision and drifts are returned.
+    /// Only the rebalancing decision and drifts are revealed.
     #[instruction]
     pub fn compute_rebalancing(
         input_ctxt: Enc<Shared, RebalancingInput>,
-    ) -> Enc<Shared, RebalancingResult> {
+    ) -> RebalancingResult {
         let input = input_ctxt.to_arcis();
 
         // Calculate total portfolio value (in micro-dollars with 6 decimals)
@@ -72,14 +73,69 @@ mod circuits {
         
         let needs_rebalance = btc_exceeds || eth_exceeds || sol_exceeds;
 
-        let result = RebalancingResult {
-            needs_rebalance,
-            btc_drift,
-            eth_drift,
-            sol_drift,
-            total_tvl,
-        };
+        RebalancingResult {
+            needs_rebalance: needs_rebalance.reveal(),
+            btc_drift: btc_drift.reveal(),
+            eth_drift: eth_drift.reveal(),
+            sol_drift: sol_drift.reveal(),
+            total_tvl: total_tvl.reveal(),
+        }
+    }
+
+    /// Target weights of a hidden-weights vault (whole percent)
+    pub struct TargetWeights {
+        pub btc_weight: u8,
+        pub eth_weight: u8,
+        pub sol_weight: u8,
+    }
+
+    /// Trades that move a hidden-weights vault toward its targets
+    pub struct HiddenRebalancingPlan {
+        // Signed USD adjustment per asset in micro-dollars (positive = buy)
+        pub btc_trade: i64,
+        pub eth_trade: i64,
+        pub sol_trade: i64,
+    }
+
+    /// Re-encrypt the manager's target weights to the MXE
+    ///
+    /// The manager's ciphertexts are used once; from then on only the
+    /// cluster can read the weights, and every output it encrypts gets a
+    /// fresh nonce. Also reveals whether the weights sum to 100.
+    #[instruction]
+    pub fn seal_target_weights(
+        input_ctxt: Enc<Shared, TargetWeights>,
+    ) -> (Enc<Mxe, TargetWeights>, bool) {
+        let weights = input_ctxt.to_arcis();
+        let total = weights.btc_weight as u16 + weights.eth_weight as u16 + weights.sol_weight as u16;
+        (Mxe::get().from_arcis(weights), (total == 100).reveal())
+    }
+
+    /// Trades toward sealed targets, from the USD values the vault read on-chain
+    ///
+    /// Only the trades are revealed, each capped at `max_trade_usd`, so a
+    /// vault far from its targets shows the direction it moves in but not
+    /// where it stops.
+    #[instruction]
+    pub fn compute_hidden_rebalancing(
+        targets_ctxt: Enc<Mxe, TargetWeights>,
+        btc_usd: u64,
+        eth_usd: u64,
+        sol_usd: u64,
+        max_trade_usd: u64,
+    ) -> HiddenRebalancingPlan {
+        let targets = targets_ctxt.to_arcis();
+        let total = btc_usd + eth_usd + sol_usd;
+        let cap = max_trade_usd as i64;
+
+        let btc_trade = (total * targets.btc_weight as u64 / 100) as i64 - btc_usd as i64;
+        let eth_trade = (total * targets.eth_weight as u64 / 100) as i64 - eth_usd as i64;
+        let sol_trade = (total * targets.sol_weight as u64 / 100) as i64 - sol_usd as i64;
 
-        input_ctxt.owner.from_arcis(result)
+        HiddenRebalancingPlan {
+            btc_trade: btc_trade.max(-cap).min(cap).reveal(),
+            eth_trade: eth_trade.max(-cap).min(cap).reveal(),
+            sol_trade: sol_trade.max(-cap).min(cap).reveal(),
+        }
     }
 }
@@ -6,6 +6,18 @@ declare_id!("FwbzbjGyBmb5n7VAPfMnYKZthycScuA6ktGE7rtZ2Z9x");
 
 // Use the comp_def_offset macro to generate the correct offset
 const COMP_DEF_OFFSET_COMPUTE_REBALANCING: u32 = comp_def_offset("compute_rebalancing");
+const COMP_DEF_OFFSET_SEAL_TARGET_WEIGHTS: u32 = comp_def_offset("seal_target_weights");
+const COMP_DEF_OFFSET_COMPUTE_HIDDEN_REBALANCING: u32 = comp_def_offset("compute_hidden_rebalancing");
+
+// Seed for the per-computation result PDA: [REBALANCE_RESULT_SEED, computation_offset (le)]
+pub const REBALANCE_RESULT_SEED: &[u8] = b"rebalance_result";
+
+// Seed for a hidden-weights vault's sealed targets: [SEALED_TARGETS_SEED, vault]
+pub const SEALED_TARGETS_SEED: &[u8] = b"sealed_targets";
+
+// Byte offset of SealedTargets::ciphertexts (discriminator + vault + nonce),
+// where compute_hidden_rebalancing reads the sealed weights from
+const SEALED_CIPHERTEXTS_OFFSET: u32 = 8 + 32 + 16;
 
 #[arcium_program]
 pub mod rebalancing_mxe {
@@ -32,28 +44,61 @@ pub mod rebalancing_mxe {
         Ok(())
     }
 
+    pub fn init_seal_target_weights_comp_def(ctx: Context<InitSealTargetWeightsCompDef>) -> Result<()> {
+        // Small circuit: uploaded on-chain with the Arcium CLI
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_compute_hidden_rebalancing_comp_def(
+        ctx: Context<InitComputeHiddenRebalancingCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, true, 0, None, None)?;
+        Ok(())
+    }
+
     pub fn compute_rebalancing(
         ctx: Context<ComputeRebalancing>,
         computation_offset: u64,
+        vault: Pubkey,
         pub_key: [u8; 32],
         nonce: u128,
-        encrypted_user_funds: [u8; 32], 
+        encrypted_portfolio: [[u8; 32]; 13],
     ) -> Result<()> {
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Result slot the callback fills in once the cluster finishes
+        let rebalance_result = &mut ctx.accounts.rebalance_result;
+        rebalance_result.computation_offset = computation_offset;
+        rebalance_result.requester = ctx.accounts.payer.key();
+        rebalance_result.vault = vault;
+        rebalance_result.completed = false;
+        rebalance_result.queued_at = Clock::get()?.unix_timestamp;
+        rebalance_result.bump = ctx.bumps.rebalance_result;
         
-        // Only one encrypted argument (the user's funds)
-        let args = vec![
+        // Enc<Shared, RebalancingInput>: fields in circuit declaration order
+        let mut args = vec![
             Argument::ArcisPubkey(pub_key),
             Argument::PlaintextU128(nonce),
-            Argument::EncryptedU8(encrypted_user_funds),
         ];
+        // 3 balances + 3 prices (u64)
+        for ciphertext in &encrypted_portfolio[..6] {
+            args.push(Argument::EncryptedU64(*ciphertext));
+        }
+        // 3 target weights + 3 current weights + threshold (u8)
+        for ciphertext in &encrypted_portfolio[6..] {
+            args.push(Argument::EncryptedU8(*ciphertext));
+        }
 
         queue_computation(
             ctx.accounts,
             computation_offset,
             args,
             None,
-            vec![ComputeRebalancingCallback::callback_ix(&[])],
+            vec![ComputeRebalancingCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.rebalance_result.key(),
+                is_writable: true,
+            }])],
         )?;
 
         Ok(())
@@ -64,19 +109,164 @@ pub mod rebalancing_mxe {
         ctx: Context<ComputeRebalancingCallback>,
         output: ComputationOutputs<ComputeRebalancingOutput>,
     ) -> Result<()> {
+        // The callback macro has already checked, through the instructions
+        // sysvar, that Arcium invoked this with the cluster-signed output
         let o = match output {
             ComputationOutputs::Success(ComputeRebalancingOutput { field_0 }) => field_0,
             _ => return Err(ErrorCode::AbortedComputation.into()),
         };
 
-        let mut rebalanced_allocations = Vec::new();
-        for ciphertext in o.ciphertexts.iter() {
-            rebalanced_allocations.push(*ciphertext);
-        }
+        // Persist the revealed RebalancingResult for the vault to apply
+        let rebalance_result = &mut ctx.accounts.rebalance_result;
+        require!(!rebalance_result.completed, ErrorCode::ResultAlreadyWritten);
+        rebalance_result.needs_rebalance = o.field_0;
+        rebalance_result.drifts_pct = [o.field_1, o.field_2, o.field_3];
+        rebalance_result.total_tvl = o.field_4;
+        rebalance_result.completed = true;
 
         emit!(RebalancingEvent {
-            allocations: rebalanced_allocations.try_into().unwrap_or([[0u8; 32]; 30]),
-            nonce: o.nonce.to_le_bytes(),
+            vault: rebalance_result.vault,
+            computation_offset: rebalance_result.computation_offset,
+            needs_rebalance: rebalance_result.needs_rebalance,
+            drifts_pct: rebalance_result.drifts_pct,
+        });
+        Ok(())
+    }
+
+    /// Seal a hidden-weights vault's target weights to the MXE
+    ///
+    /// Only the vault program can sign for the vault PDA, so only the vault
+    /// (through set_hidden_weights) can replace its targets.
+    pub fn seal_target_weights(
+        ctx: Context<SealTargetWeights>,
+        computation_offset: u64,
+        pub_key: [u8; 32],
+        nonce: u128,
+        encrypted_weights: [[u8; 32]; 3],
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+
+        // Cleared until the callback stores the new ciphertexts
+        let sealed_targets = &mut ctx.accounts.sealed_targets;
+        sealed_targets.vault = ctx.accounts.vault.key();
+        sealed_targets.computation_offset = computation_offset;
+        sealed_targets.completed = false;
+        sealed_targets.valid = false;
+        sealed_targets.bump = ctx.bumps.sealed_targets;
+
+        // Enc<Shared, TargetWeights>
+        let mut args = vec![
+            Argument::ArcisPubkey(pub_key),
+            Argument::PlaintextU128(nonce),
+        ];
+        for ciphertext in &encrypted_weights {
+            args.push(Argument::EncryptedU8(*ciphertext));
+        }
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SealTargetWeightsCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.sealed_targets.key(),
+                is_writable: true,
+            }])],
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "seal_target_weights")]
+    pub fn seal_target_weights_callback(
+        ctx: Context<SealTargetWeightsCallback>,
+        output: ComputationOutputs<SealTargetWeightsOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(SealTargetWeightsOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let sealed_targets = &mut ctx.accounts.sealed_targets;
+        require!(!sealed_targets.completed, ErrorCode::ResultAlreadyWritten);
+        sealed_targets.nonce = o.field_0.nonce;
+        sealed_targets.ciphertexts = o.field_0.ciphertexts;
+        sealed_targets.valid = o.field_1;
+        sealed_targets.completed = true;
+        Ok(())
+    }
+
+    /// Queue a hidden-weights rebalance from the vault's on-chain USD values
+    ///
+    /// The sealed targets are read by the cluster straight from their
+    /// account; nothing the caller encrypts goes in, so no client nonce is
+    /// involved. The vault PDA signs, so the USD values are the ones the
+    /// vault program priced.
+    pub fn compute_hidden_rebalancing(
+        ctx: Context<ComputeHiddenRebalancing>,
+        computation_offset: u64,
+        btc_usd: u64,
+        eth_usd: u64,
+        sol_usd: u64,
+        max_trade_usd: u64,
+    ) -> Result<()> {
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let sealed_targets = &ctx.accounts.sealed_targets;
+        require!(sealed_targets.completed && sealed_targets.valid, ErrorCode::TargetsNotSealed);
+
+        let rebalance_result = &mut ctx.accounts.rebalance_result;
+        rebalance_result.computation_offset = computation_offset;
+        rebalance_result.requester = ctx.accounts.payer.key();
+        rebalance_result.vault = ctx.accounts.vault.key();
+        rebalance_result.completed = false;
+        rebalance_result.total_tvl = btc_usd.saturating_add(eth_usd).saturating_add(sol_usd);
+        rebalance_result.queued_at = Clock::get()?.unix_timestamp;
+        rebalance_result.bump = ctx.bumps.rebalance_result;
+
+        // Enc<Mxe, TargetWeights> read from SealedTargets, then the plaintext values
+        let args = vec![
+            Argument::PlaintextU128(sealed_targets.nonce),
+            Argument::Account(sealed_targets.key(), SEALED_CIPHERTEXTS_OFFSET, 3 * 32),
+            Argument::PlaintextU64(btc_usd),
+            Argument::PlaintextU64(eth_usd),
+            Argument::PlaintextU64(sol_usd),
+            Argument::PlaintextU64(max_trade_usd),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ComputeHiddenRebalancingCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.rebalance_result.key(),
+                is_writable: true,
+            }])],
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "compute_hidden_rebalancing")]
+    pub fn compute_hidden_rebalancing_callback(
+        ctx: Context<ComputeHiddenRebalancingCallback>,
+        output: ComputationOutputs<ComputeHiddenRebalancingOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ComputeHiddenRebalancingOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let rebalance_result = &mut ctx.accounts.rebalance_result;
+        require!(!rebalance_result.completed, ErrorCode::ResultAlreadyWritten);
+        rebalance_result.trades_usd = [o.field_0, o.field_1, o.field_2];
+        rebalance_result.needs_rebalance = rebalance_result.trades_usd.iter().any(|trade| *trade != 0);
+        rebalance_result.completed = true;
+
+        emit!(HiddenRebalancingEvent {
+            vault: rebalance_result.vault,
+            computation_offset: rebalance_result.computation_offset,
+            trades_usd: rebalance_result.trades_usd,
         });
         Ok(())
     }
@@ -139,6 +329,14 @@ pub struct ComputeRebalancing<'info> {
     pub clock_account: Account<'info, ClockAccount>,
     pub system_program: Program<'info, System>,
     pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        init,
+        payer = payer,
+        space = RebalanceResult::SPACE,
+        seeds = [REBALANCE_RESULT_SEED, &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub rebalance_result: Account<'info, RebalanceResult>,
 }
 
 #[callback_accounts("compute_rebalancing")]
@@ -152,6 +350,13 @@ pub struct ComputeRebalancingCallback<'info> {
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar is validated by comparing against the instructions sysvar ID
     pub instructions_sysvar: AccountInfo<'info>,
+    /// Result PDA registered as a callback account when the computation was queued
+    #[account(
+        mut,
+        seeds = [REBALANCE_RESULT_SEED, &rebalance_result.computation_offset.to_le_bytes()],
+        bump = rebalance_result.bump,
+    )]
+    pub rebalance_result: Account<'info, RebalanceResult>,
 }
 
 #[init_computation_definition_accounts("compute_rebalancing", payer)]
@@ -171,10 +376,309 @@ pub struct InitComputeRebalancingCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[queue_computation_accounts("seal_target_weights", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SealTargetWeights<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Vault PDA of the vault program; only that program can sign for it
+    pub vault: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account is validated by the Arcium program during computation queueing
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool is validated by the Arcium program during computation execution
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account is validated by the Arcium program for the specific computation
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SEAL_TARGET_WEIGHTS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = SealedTargets::SPACE,
+        seeds = [SEALED_TARGETS_SEED, vault.key().as_ref()],
+        bump,
+    )]
+    pub sealed_targets: Account<'info, SealedTargets>,
+}
+
+#[callback_accounts("seal_target_weights")]
+#[derive(Accounts)]
+pub struct SealTargetWeightsCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_SEAL_TARGET_WEIGHTS)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar is validated by comparing against the instructions sysvar ID
+    pub instructions_sysvar: AccountInfo<'info>,
+    /// Sealed targets registered as a callback account when the computation was queued
+    #[account(
+        mut,
+        seeds = [SEALED_TARGETS_SEED, sealed_targets.vault.as_ref()],
+        bump = sealed_targets.bump,
+    )]
+    pub sealed_targets: Account<'info, SealedTargets>,
+}
+
+#[init_computation_definition_accounts("seal_target_weights", payer)]
+#[derive(Accounts)]
+pub struct InitSealTargetWeightsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account will be initialized by the Arcium program during this instruction
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("compute_hidden_rebalancing", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ComputeHiddenRebalancing<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// Vault PDA of the vault program; only that program can sign for it
+    pub vault: Signer<'info>,
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    #[account(
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Account<'info, MXEAccount>,
+    #[account(
+        mut,
+        address = derive_mempool_pda!()
+    )]
+    /// CHECK: mempool_account is validated by the Arcium program during computation queueing
+    pub mempool_account: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_execpool_pda!()
+    )]
+    /// CHECK: executing_pool is validated by the Arcium program during computation execution
+    pub executing_pool: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = derive_comp_pda!(computation_offset)
+    )]
+    /// CHECK: computation_account is validated by the Arcium program for the specific computation
+    pub computation_account: UncheckedAccount<'info>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_HIDDEN_REBALANCING)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(
+        mut,
+        address = derive_cluster_pda!(mxe_account)
+    )]
+    pub cluster_account: Account<'info, Cluster>,
+    #[account(
+        mut,
+        address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS,
+    )]
+    pub pool_account: Account<'info, FeePool>,
+    #[account(
+        address = ARCIUM_CLOCK_ACCOUNT_ADDRESS
+    )]
+    pub clock_account: Account<'info, ClockAccount>,
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        seeds = [SEALED_TARGETS_SEED, vault.key().as_ref()],
+        bump = sealed_targets.bump,
+    )]
+    pub sealed_targets: Account<'info, SealedTargets>,
+    #[account(
+        init,
+        payer = payer,
+        space = RebalanceResult::SPACE,
+        seeds = [REBALANCE_RESULT_SEED, &computation_offset.to_le_bytes()],
+        bump,
+    )]
+    pub rebalance_result: Account<'info, RebalanceResult>,
+}
+
+#[callback_accounts("compute_hidden_rebalancing")]
+#[derive(Accounts)]
+pub struct ComputeHiddenRebalancingCallback<'info> {
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(
+        address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMPUTE_HIDDEN_REBALANCING)
+    )]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar is validated by comparing against the instructions sysvar ID
+    pub instructions_sysvar: AccountInfo<'info>,
+    /// Result PDA registered as a callback account when the computation was queued
+    #[account(
+        mut,
+        seeds = [REBALANCE_RESULT_SEED, &rebalance_result.computation_offset.to_le_bytes()],
+        bump = rebalance_result.bump,
+    )]
+    pub rebalance_result: Account<'info, RebalanceResult>,
+}
+
+#[init_computation_definition_accounts("compute_hidden_rebalancing", payer)]
+#[derive(Accounts)]
+pub struct InitComputeHiddenRebalancingCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        mut,
+        address = derive_mxe_pda!()
+    )]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account will be initialized by the Arcium program during this instruction
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+/// A hidden-weights vault's target weights, encrypted to the MXE
+///
+/// Written only by the seal_target_weights callback. The ciphertexts stay
+/// at SEALED_CIPHERTEXTS_OFFSET, where compute_hidden_rebalancing points the
+/// cluster at them; the vault reads this layout too (programs/vault/src/mxe.rs).
+#[account]
+pub struct SealedTargets {
+    pub vault: Pubkey,
+    /// Nonce of the MXE encryption, fresh for every seal
+    pub nonce: u128,
+    /// BTC, ETH and SOL target weights
+    pub ciphertexts: [[u8; 32]; 3],
+    /// Computation that last sealed the targets
+    pub computation_offset: u64,
+    /// Set by the callback once the cluster's output is in
+    pub completed: bool,
+    /// Whether the sealed weights sum to 100
+    pub valid: bool,
+    pub bump: u8,
+}
+
+impl SealedTargets {
+    pub const SPACE: usize = 8 + // discriminator
+        32 +      // vault
+        16 +      // nonce
+        3 * 32 +  // ciphertexts
+        8 +       // computation_offset
+        1 +       // completed
+        1 +       // valid
+        1;        // bump
+}
+
+/// Revealed RebalancingResult for a single computation
+///
+/// Only the callback sets `completed`, so a completed result is the
+/// cluster's output for the computation queued at `computation_offset`.
+/// The vault reads this layout directly (programs/vault/src/mxe.rs); keep
+/// the two in step.
+#[account]
+pub struct RebalanceResult {
+    pub computation_offset: u64,
+    pub requester: Pubkey,
+    /// Vault the computation was queued for
+    pub vault: Pubkey,
+    pub completed: bool,
+    pub needs_rebalance: bool,
+    /// BTC, ETH and SOL drift from target, in percentage points
+    pub drifts_pct: [i16; 3],
+    /// Total value locked in micro-dollars
+    pub total_tvl: u64,
+    /// Hidden-weights vaults only: capped BTC, ETH and SOL trades in
+    /// micro-USD, positive to buy
+    pub trades_usd: [i64; 3],
+    /// When the computation was queued; the vault lets anyone release its
+    /// lock once a result is this overdue
+    pub queued_at: i64,
+    pub bump: u8,
+}
+
+impl RebalanceResult {
+    pub const SPACE: usize = 8 + // discriminator
+        8 +       // computation_offset
+        32 +      // requester
+        32 +      // vault
+        1 +       // completed
+        1 +       // needs_rebalance
+        3 * 2 +   // drifts_pct
+        8 +       // total_tvl
+        3 * 8 +   // trades_usd
+        8 +       // queued_at
+        1;        // bump
+}
+
 #[event]
 pub struct RebalancingEvent {
-    pub allocations: [[u8; 32]; 30],
-    pub nonce: [u8; 16],
+    pub vault: Pubkey,
+    pub computation_offset: u64,
+    pub needs_rebalance: bool,
+    pub drifts_pct: [i16; 3],
+}
+
+#[event]
+pub struct HiddenRebalancingEvent {
+    pub vault: Pubkey,
+    pub computation_offset: u64,
+    pub trades_usd: [i64; 3],
 }
 
 #[error_code]
@@ -183,4 +687,8 @@ pub enum ErrorCode {
     AbortedComputation,
     #[msg("Cluster not set")]
     ClusterNotSet,
+    #[msg("Rebalance result already written")]
+    ResultAlreadyWritten,
+    #[msg("Target weights are not sealed or do not sum to 100")]
+    TargetsNotSealed,
 }
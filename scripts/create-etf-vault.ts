@@ -6,6 +6,7 @@ import { MarinadeStrategy } from "../target/types/marinade_strategy";
 import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
 import { getAssociatedTokenAddress, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import * as fs from "fs";
+import { storedName, vaultAssets } from "../tests/helpers/vault-account";
 
 // Configuration matching your constants
 const VAULT_NAME = "SOL-BTC-ETH-Index";
@@ -73,9 +74,9 @@ async function initializeVault() {
   try {
     const vaultAccount = await program.account.vault.fetch(vaultPda);
     console.log("✅ Vault already exists!");
-    console.log("   Name:", vaultAccount.name);
+    console.log("   Name:", storedName(vaultAccount));
     console.log("   Admin:", vaultAccount.admin.toString());
-    console.log("   Assets:", vaultAccount.assets.length);
+    console.log("   Assets:", vaultAssets(vaultAccount).length);
     return;
   } catch (e) {
     console.log("Vault doesn't exist, creating...");
@@ -122,7 +123,7 @@ async function initializeVault() {
   ];
 
   await program.methods
-    .createVault(VAULT_NAME, assets)
+    .createVault(VAULT_NAME, assets, false)
     .accounts({
       admin: adminKeypair.publicKey,
     })
@@ -15,12 +15,18 @@ import * as os from "os";
  * After delegation:
  * - Oracle can be updated every 100-500ms on ER (vs 30s on L1)
  * - Updates are FREE (no transaction fees while on ER)
- * - State commits to L1 automatically every 30 seconds
+ * - State commits to L1 automatically every ER_COMMIT_FREQUENCY_MS (default 30s);
+ *   anyone can crank an overdue commit with crankMockOracleCommit
+ * - After ER_MAX_SESSION_SECS (default 1 day) anyone can force the oracle back
+ *   to L1 with forceUndelegateMockOracle
  * 
  * Usage:
- *   yarn delegate-oracle
+ *   ER_COMMIT_FREQUENCY_MS=30000 ER_MAX_SESSION_SECS=86400 yarn delegate-oracle
  */
 
+const commitFrequencyMs = Number(process.env.ER_COMMIT_FREQUENCY_MS ?? 30_000);
+const maxSessionSecs = Number(process.env.ER_MAX_SESSION_SECS ?? 86_400);
+
 async function main() {
   console.log("\n" + "=".repeat(80));
   console.log("📤 DELEGATING MOCK ORACLE TO EPHEMERAL ROLLUP");
@@ -92,11 +98,12 @@ async function main() {
   console.log("\n🚀 Delegating oracle to Ephemeral Rollup...");
   console.log("   This will enable high-frequency price updates (100-500ms)");
   console.log("   Updates will be FREE while on ER");
-  console.log("   State will commit to L1 every 30 seconds");
+  console.log(`   State will commit to L1 every ${commitFrequencyMs}ms`);
+  console.log(`   Anyone may undelegate the oracle after ${maxSessionSecs}s`);
 
   try {
     const tx = await program.methods
-      .delegateMockOracle()
+      .delegateMockOracle(commitFrequencyMs, maxSessionSecs)
       .accounts({
         authority: admin.publicKey,
         mockOracle: mockOracle,
@@ -5,6 +5,7 @@ import { MarinadeStrategy } from "../target/types/marinade_strategy";
 import { PublicKey } from "@solana/web3.js";
 import { getAccount } from "@solana/spl-token";
 import { MSOL_MINT } from "../tests/helpers/marinade-accounts";
+import { optionalKey } from "../tests/helpers/vault-account";
 
 /**
  * Show Marinade Yield Information
@@ -46,13 +47,13 @@ async function showMarinadeYield(vaultAddress: PublicKey): Promise<void> {
   // Fetch vault data
   const vaultData = await vaultProgram.account.vault.fetch(vaultAddress);
   
-  if (!vaultData.marinadeStrategy) {
+  const strategyAddress = optionalKey(vaultData.marinadeStrategy);
+  if (!strategyAddress) {
     console.log("\n❌ This vault does not have a Marinade strategy configured");
     console.log("   No staked SOL to display\n");
     return;
   }
 
-  const strategyAddress = vaultData.marinadeStrategy;
   console.log(`Strategy Address: ${strategyAddress.toString()}\n`);
   
   // Fetch strategy data
@@ -17,7 +17,7 @@ import axios from "axios";
  * - Fetches BTC, ETH, SOL prices from CoinGecko
  * - Updates every 500ms on ER (vs 30s on L1)
  * - FREE updates (no transaction fees while on ER)
- * - Auto-commits to L1 every 30 seconds (configured in delegate)
+ * - Auto-commits to L1 at the interval passed to delegate-oracle
  * - Real-time monitoring and statistics
  * 
  * Prerequisites:
@@ -26,6 +26,7 @@ import axios from "axios";
  * 
  * Usage:
  *   yarn update-prices-er
+ *   VAULT_ADDRESS=<vault> yarn update-prices-er   (batch update keyed by the vault's mints)
  *   Press Ctrl+C to stop
  */
 
@@ -40,6 +41,12 @@ interface PriceData {
   sol: number;
 }
 
+// A vault's BTC/ETH/SOL mints, for update_prices_batch
+interface Basket {
+  vault: PublicKey;
+  mints: PublicKey[];
+}
+
 interface UpdateStats {
   totalUpdates: number;
   successfulUpdates: number;
@@ -94,13 +101,30 @@ async function updateOracleOnER(
   program: Program<Vault>,
   mockOracle: PublicKey,
   authority: Keypair,
-  prices: PriceData
+  prices: PriceData,
+  basket: Basket | null
 ): Promise<boolean> {
   try {
     const btcPrice = convertTomicroUsd(prices.btc);
     const ethPrice = convertTomicroUsd(prices.eth);
     const solPrice = convertTomicroUsd(prices.sol);
 
+    if (basket) {
+      // The whole basket in one instruction, one TWAP sample
+      const marketPrices = [btcPrice, ethPrice, solPrice];
+      await (program.methods as any)
+        .updatePricesBatch(basket.mints.map((mint, i) => ({ mint, price: marketPrices[i] })))
+        .accounts({
+          mockOracle: mockOracle,
+          oracleFeeders: null,
+          vault: basket.vault,
+          authority: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc({ skipPreflight: true, commitment: "processed" });
+      return true;
+    }
+
     await (program.methods as any)
       .updateMockOracle(btcPrice, ethPrice, solPrice)
       .accounts({
@@ -213,6 +237,20 @@ async function main() {
     process.exit(1);
   }
 
+  // Optional vault whose mints key a batch update
+  let basket: Basket | null = null;
+  if (process.env.VAULT_ADDRESS) {
+    const vault = new PublicKey(process.env.VAULT_ADDRESS);
+    const vaultData: any = await (program.account as any).vault.fetch(vault);
+    const mints = vaultData.assets.slice(0, vaultData.numAssets).map((asset: any) => asset.mint);
+    if (mints.length !== 3) {
+      console.error("❌ Batch updates need a BTC/ETH/SOL vault, got", mints.length, "assets");
+      process.exit(1);
+    }
+    basket = { vault, mints };
+    console.log("\n🧺 Batch updates keyed by vault:", vault.toString());
+  }
+
   // Fetch initial prices
   console.log("\n⚡ Fetching initial prices from CoinGecko...");
   const initialPrices = await fetchRealTimePrices();
@@ -241,7 +279,7 @@ async function main() {
   console.log("=".repeat(100));
   console.log("\n💡 Updates running at 500ms intervals (2x per second)");
   console.log("💰 All updates are FREE while on Ephemeral Rollup");
-  console.log("🔄 State auto-commits to L1 every 30 seconds");
+  console.log("🔄 State auto-commits to L1 at the interval set by delegate-oracle");
   console.log("⌨️  Press Ctrl+C to stop and view statistics\n");
   console.log("─".repeat(100));
 
@@ -265,7 +303,7 @@ async function main() {
       }
 
       // Update oracle on ER with current prices
-      const success = await updateOracleOnER(program, mockOracle, admin, cachedPrices);
+      const success = await updateOracleOnER(program, mockOracle, admin, cachedPrices, basket);
 
       if (success) {
         stats.successfulUpdates++;
@@ -328,7 +366,7 @@ async function main() {
     console.log("  SOL: " + formatCurrency(stats.priceChanges.sol.current));
 
     console.log("\n💡 Next Steps:");
-    console.log("  • Oracle state will auto-commit to L1 (configured at 30s intervals)");
+    console.log("  • Oracle state will auto-commit to L1 (at the interval set by delegate-oracle)");
     console.log("  • To manually undelegate and commit: yarn undelegate-oracle");
     console.log("  • To resume updates: yarn update-prices-er");
 
@@ -0,0 +1,164 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN, utils } from "@coral-xyz/anchor";
+import { Vault } from "../target/types/vault";
+import {
+  PublicKey,
+  TransactionMessage,
+  VersionedTransaction,
+  ParsedInnerInstruction,
+} from "@solana/web3.js";
+
+/**
+ * Deposit preview via full-transaction simulation
+ *
+ * Instead of re-implementing the vault's pricing math client-side, we build the
+ * exact deposit_multi_asset transaction (Marinade CPI, swaps and all), simulate
+ * it, and decode what the program itself reports:
+ * - the DepositReceipt written to return data
+ * - the events emitted during execution (self-CPI, see decodeCpiEvents)
+ * - the user's lamport delta (everything the deposit actually costs)
+ */
+
+export interface AllocationPreview {
+  mint: PublicKey;
+  usdValue: number; // micro-USD
+  tokenAmount: BN; // native decimals of the asset
+}
+
+export interface DepositPreview {
+  shares: BN;
+  /** Lamports of `amount` kept as deposit fee */
+  depositFee: BN;
+  depositUsd: number; // micro-USD
+  sharePrice: number; // micro-USD
+  stakedLamports: BN;
+  /** Lamports charged above `amount` (rent for new ATAs, network fee, ...) */
+  fees: BN;
+  allocations: AllocationPreview[];
+  events: { name: string; data: any }[];
+  /** Program log lines flagged as warnings (⚠️) plus client-side checks */
+  warnings: string[];
+}
+
+/** Anchor's EVENT_IX_TAG, prefixed to every emit_cpi! instruction */
+const EVENT_IX_TAG = Buffer.from([0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d]);
+
+/**
+ * Decode the events a vault instruction emitted with emit_cpi!.
+ *
+ * The program emits events as self-CPIs signed by its event authority, so
+ * they show up as inner instructions instead of (truncatable) log lines.
+ */
+export function decodeCpiEvents(
+  program: Program<Vault>,
+  innerInstructions: ParsedInnerInstruction[] | null | undefined
+): { name: string; data: any }[] {
+  const events: { name: string; data: any }[] = [];
+  for (const inner of innerInstructions ?? []) {
+    for (const ix of inner.instructions) {
+      if (!("data" in ix) || !ix.programId.equals(program.programId)) continue;
+      const data = Buffer.from(utils.bytes.bs58.decode(ix.data));
+      if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) continue;
+      const event = program.coder.events.decode(data.subarray(8).toString("base64"));
+      if (event) events.push(event);
+    }
+  }
+  return events;
+}
+
+export class DepositSimulationError extends Error {
+  constructor(message: string, public readonly logs: string[]) {
+    super(message);
+  }
+}
+
+/**
+ * Simulate a deposit and decode the result into a typed preview.
+ *
+ * @param program  Vault program client
+ * @param user     Depositing wallet (fee payer and signer)
+ * @param vaultName Vault name used for PDA derivation
+ * @param amount   Deposit amount in lamports
+ * @param referrer Optional referrer (pass their ReferralBalance as `referralBalance`)
+ * @param accounts Accounts for deposit_multi_asset, exactly as they would be sent
+ * @param remainingAccounts Asset mints/ATAs, oracle and strategy accounts
+ */
+export async function previewDeposit(
+  program: Program<Vault>,
+  user: PublicKey,
+  vaultName: string,
+  amount: BN,
+  accounts: Record<string, PublicKey>,
+  remainingAccounts: anchor.web3.AccountMeta[],
+  referrer: PublicKey | null = null
+): Promise<DepositPreview> {
+  const connection = program.provider.connection;
+
+  const ix = await (program.methods as any)
+    .depositMultiAsset(vaultName, amount, referrer)
+    .accounts({ user, ...accounts })
+    .remainingAccounts(remainingAccounts)
+    .instruction();
+
+  const message = new TransactionMessage({
+    payerKey: user,
+    recentBlockhash: (await connection.getLatestBlockhash()).blockhash,
+    instructions: [ix],
+  }).compileToV0Message();
+  const tx = new VersionedTransaction(message);
+
+  const balanceBefore = await connection.getBalance(user);
+  const sim = await connection.simulateTransaction(tx, {
+    sigVerify: false,
+    innerInstructions: true,
+    accounts: { encoding: "base64", addresses: [user.toBase58()] },
+  });
+  const logs = sim.value.logs ?? [];
+
+  if (sim.value.err) {
+    throw new DepositSimulationError(
+      `Deposit simulation failed: ${JSON.stringify(sim.value.err)}`,
+      logs
+    );
+  }
+
+  // Return data: "Program return: <program id> <base64>"
+  const returnLog = logs.find((line) =>
+    line.startsWith(`Program return: ${program.programId.toBase58()} `)
+  );
+  if (!returnLog) {
+    throw new DepositSimulationError("Deposit returned no DepositReceipt", logs);
+  }
+  const returnData = Buffer.from(returnLog.split(" ").pop()!, "base64");
+  const receipt: any = program.coder.types.decode("depositReceipt", returnData);
+
+  const events = decodeCpiEvents(program, sim.value.innerInstructions);
+
+  const postAccount = sim.value.accounts?.[0];
+  const balanceAfter = postAccount ? postAccount.lamports : balanceBefore;
+  const totalCost = new BN(balanceBefore - balanceAfter);
+  const fees = BN.max(totalCost.sub(amount), new BN(0));
+
+  const warnings = logs
+    .filter((line) => line.includes("⚠️"))
+    .map((line) => line.replace(/^Program log: /, "").trim());
+  if (receipt.sharesMinted.isZero()) {
+    warnings.push("Deposit is too small to mint any shares");
+  }
+
+  return {
+    shares: receipt.sharesMinted,
+    depositFee: receipt.depositFee,
+    depositUsd: receipt.depositUsd.toNumber(),
+    sharePrice: receipt.sharePrice.toNumber(),
+    stakedLamports: receipt.stakedLamports,
+    fees,
+    allocations: receipt.allocations.map((a: any) => ({
+      mint: a.mint,
+      usdValue: a.usdValue.toNumber(),
+      tokenAmount: a.tokenAmount,
+    })),
+    events,
+    warnings,
+  };
+}
@@ -0,0 +1,66 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { Vault } from "../target/types/vault";
+import { PublicKey } from "@solana/web3.js";
+
+/**
+ * Read-only vault views
+ *
+ * get_vault_stats, preview_deposit and preview_withdraw never write, so
+ * Anchor's `.view()` simulates them and decodes the returned struct. They take
+ * the snapshot_nav remaining accounts: [mint, vault ATA] per asset, then the
+ * price accounts (MockOracle, PriceCache or aggregated feeds).
+ */
+
+export interface ViewAccounts {
+  /** Switchboard quotes; PublicKey.default when the vault does not use them */
+  btcQuote: PublicKey;
+  ethQuote: PublicKey;
+  solQuote: PublicKey;
+}
+
+export async function getVaultStats(
+  program: Program<Vault>,
+  vaultName: string,
+  accounts: ViewAccounts & Record<string, PublicKey>,
+  remainingAccounts: anchor.web3.AccountMeta[]
+) {
+  return (program.methods as any)
+    .getVaultStats(vaultName)
+    .accounts(accounts)
+    .remainingAccounts(remainingAccounts)
+    .view();
+}
+
+/** Shares `amount` lamports would mint, without the swap/stake simulation of previewDeposit */
+export async function quoteDeposit(
+  program: Program<Vault>,
+  vaultName: string,
+  amount: BN,
+  accounts: ViewAccounts & Record<string, PublicKey>,
+  remainingAccounts: anchor.web3.AccountMeta[]
+) {
+  return (program.methods as any)
+    .previewDeposit(vaultName, amount)
+    .accounts(accounts)
+    .remainingAccounts(remainingAccounts)
+    .view();
+}
+
+/**
+ * SOL out for redeeming `shares`; pass the holder's UserPosition as
+ * `userPosition` to include their exit fee (null to leave it out)
+ */
+export async function quoteWithdraw(
+  program: Program<Vault>,
+  vaultName: string,
+  shares: BN,
+  accounts: ViewAccounts & { userPosition: PublicKey | null } & Record<string, PublicKey | null>,
+  remainingAccounts: anchor.web3.AccountMeta[]
+) {
+  return (program.methods as any)
+    .previewWithdraw(vaultName, shares)
+    .accounts(accounts)
+    .remainingAccounts(remainingAccounts)
+    .view();
+}
@@ -0,0 +1,230 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Vault } from "../target/types/vault";
+import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { expect } from "chai";
+import * as fs from "fs";
+import * as path from "path";
+import * as os from "os";
+
+/**
+ * Delegated mock oracle flow on MagicBlock Ephemeral Rollups
+ *
+ * Skipped when the program was built without the `ephemeral-rollups`
+ * feature. The L1 half (parameter checks, delegation) runs against the test
+ * validator, which clones the delegation program; the ER half (updates,
+ * commit crank, session timeout, undelegation) also needs an ER endpoint in
+ * EPHEMERAL_PROVIDER_ENDPOINT, e.g. https://devnet.magicblock.app/ when
+ * testing against devnet.
+ */
+
+const DELEGATION_PROGRAM_ID = new PublicKey("DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh");
+const MAGIC_PROGRAM_ID = new PublicKey("Magic11111111111111111111111111111111111111");
+const MAGIC_CONTEXT_ID = new PublicKey("MagicContext1111111111111111111111111111111");
+
+const COMMIT_FREQUENCY_MS = 1_000;
+const MAX_SESSION_SECS = 3_600;
+
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+describe("Ephemeral Rollups: delegated mock oracle", () => {
+  anchor.setProvider(anchor.AnchorProvider.env());
+
+  const program = anchor.workspace.Vault as Program<Vault>;
+  const provider = program.provider as anchor.AnchorProvider;
+  const erEndpoint = process.env.EPHEMERAL_PROVIDER_ENDPOINT;
+
+  // A fresh oracle, so delegating it leaves the shared test oracle on L1
+  const authority = Keypair.generate();
+  const [mockOracle] = PublicKey.findProgramAddressSync(
+    [Buffer.from("mock_oracle"), authority.publicKey.toBuffer()],
+    program.programId
+  );
+
+  const delegate = (commitFrequencyMs: number, maxSessionSecs: number) =>
+    (program.methods as any)
+      .delegateMockOracle(commitFrequencyMs, maxSessionSecs)
+      .accounts({
+        payer: authority.publicKey,
+        pda: mockOracle,
+        ownerProgram: program.programId,
+        bufferPda: PublicKey.findProgramAddressSync(
+          [Buffer.from("buffer"), mockOracle.toBuffer()],
+          program.programId
+        )[0],
+        delegationRecordPda: PublicKey.findProgramAddressSync(
+          [Buffer.from("delegation"), mockOracle.toBuffer()],
+          DELEGATION_PROGRAM_ID
+        )[0],
+        delegationMetadataPda: PublicKey.findProgramAddressSync(
+          [Buffer.from("delegation-metadata"), mockOracle.toBuffer()],
+          DELEGATION_PROGRAM_ID
+        )[0],
+        delegationProgram: DELEGATION_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async function () {
+    if (!program.idl.instructions.some((ix) => ix.name === "delegateMockOracle")) {
+      console.log("⏭️  Program built without the ephemeral-rollups feature");
+      this.skip();
+    }
+
+    // Fund the oracle authority from the test wallet
+    const keypairPath = path.join(os.homedir(), ".config", "solana", "id.json");
+    const admin = Keypair.fromSecretKey(
+      Uint8Array.from(JSON.parse(fs.readFileSync(keypairPath, "utf-8")))
+    );
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: admin.publicKey,
+          toPubkey: authority.publicKey,
+          lamports: LAMPORTS_PER_SOL / 10,
+        })
+      ),
+      [admin]
+    );
+
+    await (program.methods as any)
+      .initializeMockOracle()
+      .accounts({ mockOracle, authority: authority.publicKey, systemProgram: SystemProgram.programId })
+      .signers([authority])
+      .rpc();
+    await (program.methods as any)
+      .updateMockOracle(new anchor.BN(65_000_000_000), new anchor.BN(3_000_000_000), new anchor.BN(150_000_000))
+      .accounts({ mockOracle, oracleFeeders: null, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+  });
+
+  describe("Delegation on L1", () => {
+    it("Should reject a commit frequency below the minimum", async () => {
+      try {
+        await delegate(COMMIT_FREQUENCY_MS - 1, MAX_SESSION_SECS);
+        expect.fail("Sub-second commit frequency should be rejected");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidCommitFrequency");
+      }
+    });
+
+    it("Should reject a session without a timeout", async () => {
+      try {
+        await delegate(COMMIT_FREQUENCY_MS, 0);
+        expect.fail("Unbounded session should be rejected");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidSessionDuration");
+      }
+    });
+
+    it("Should record the ER parameters and hand the oracle to the delegation program", async () => {
+      await delegate(COMMIT_FREQUENCY_MS, MAX_SESSION_SECS);
+
+      const info = await provider.connection.getAccountInfo(mockOracle);
+      expect(info!.owner.toString()).to.equal(DELEGATION_PROGRAM_ID.toString());
+
+      const oracle: any = program.coder.accounts.decode("mockPriceOracle", info!.data);
+      expect(oracle.commitFrequencyMs).to.equal(COMMIT_FREQUENCY_MS);
+      expect(oracle.maxSessionSecs).to.equal(MAX_SESSION_SECS);
+      expect(oracle.delegatedAt.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("Should no longer accept L1 price updates", async () => {
+      try {
+        await (program.methods as any)
+          .updateMockOracle(new anchor.BN(66_000_000_000), new anchor.BN(3_100_000_000), new anchor.BN(155_000_000))
+          .accounts({ mockOracle, oracleFeeders: null, authority: authority.publicKey })
+          .signers([authority])
+          .rpc();
+        expect.fail("Delegated oracle should not be writable on L1");
+      } catch (error: any) {
+        expect(error.message).to.include("AccountOwnedByWrongProgram");
+      }
+    });
+  });
+
+  describe("Session on the ER", () => {
+    let erProgram: Program<Vault>;
+
+    before(function () {
+      if (!erEndpoint) {
+        console.log("⏭️  Set EPHEMERAL_PROVIDER_ENDPOINT to run the ER half");
+        this.skip();
+      }
+      const erProvider = new anchor.AnchorProvider(
+        new anchor.web3.Connection(erEndpoint!, "processed"),
+        new anchor.Wallet(authority),
+        { commitment: "processed", skipPreflight: true }
+      );
+      erProgram = new Program<Vault>(program.idl, erProvider);
+    });
+
+    const commitAccounts = () => ({
+      magicContext: MAGIC_CONTEXT_ID,
+      magicProgram: MAGIC_PROGRAM_ID,
+    });
+
+    it("Should update prices on the ER", async () => {
+      await (erProgram.methods as any)
+        .updateMockOracle(new anchor.BN(66_000_000_000), new anchor.BN(3_100_000_000), new anchor.BN(155_000_000))
+        .accounts({ mockOracle, oracleFeeders: null, authority: authority.publicKey })
+        .rpc();
+
+      const oracle: any = await (erProgram.account as any).mockPriceOracle.fetch(mockOracle);
+      expect(oracle.btcPrice.toNumber()).to.equal(66_000_000_000);
+    });
+
+    it("Should crank a commit only once the commit frequency has passed", async () => {
+      const crank = () =>
+        (erProgram.methods as any)
+          .crankMockOracleCommit()
+          .accounts({ payer: authority.publicKey, mockOracle, ...commitAccounts() })
+          .rpc();
+
+      await sleep(COMMIT_FREQUENCY_MS + 500);
+      await crank();
+
+      try {
+        await crank();
+        expect.fail("A second commit right away should not be due");
+      } catch (error: any) {
+        expect(error.message).to.include("OracleCommitNotDue");
+      }
+    });
+
+    it("Should not let anyone force an undelegation before the session expires", async () => {
+      const stranger = Keypair.generate();
+      try {
+        await (erProgram.methods as any)
+          .forceUndelegateMockOracle()
+          .accounts({ payer: stranger.publicKey, mockOracle, ...commitAccounts() })
+          .signers([stranger])
+          .rpc();
+        expect.fail("Session is still active");
+      } catch (error: any) {
+        expect(error.message).to.include("ErSessionActive");
+      }
+    });
+
+    it("Should return the oracle to L1 with the ER prices", async () => {
+      await (erProgram.methods as any)
+        .undelegateMockOracle()
+        .accounts({ authority: authority.publicKey, mockOracle, ...commitAccounts() })
+        .rpc();
+
+      // Undelegation lands on L1 asynchronously
+      let info = await provider.connection.getAccountInfo(mockOracle);
+      for (let attempt = 0; attempt < 30 && !info!.owner.equals(program.programId); attempt++) {
+        await sleep(1_000);
+        info = await provider.connection.getAccountInfo(mockOracle);
+      }
+      expect(info!.owner.toString()).to.equal(program.programId.toString());
+
+      const oracle: any = await (program.account as any).mockPriceOracle.fetch(mockOracle);
+      expect(oracle.btcPrice.toNumber()).to.equal(66_000_000_000);
+      expect(oracle.delegatedAt.toNumber()).to.equal(0);
+    });
+  });
+});
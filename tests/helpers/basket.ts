@@ -0,0 +1,334 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  AccountMeta,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  NATIVE_MINT,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getAccount,
+  getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { getMarinadeAccounts, MARINADE_PROGRAM_ID, MSOL_MINT } from "./marinade-accounts";
+
+// Shared plumbing for the behavior tests: a 40/30/30 BTC/ETH/SOL basket
+// priced from the admin's mock oracle, plus the long account lists that
+// deposit_multi_asset and withdraw_multi_asset need.
+
+export const ORACLE_ADAPTER_ID = new PublicKey("4pCSVvFkZLL4U3ydKv9zNwfg4tyBPeiw6AuKLZRHeEBK");
+export const MARINADE_STRATEGY_ID = new PublicKey("6U2DYMNfoY1G5toBdFgDZGjriWS2i9VZTZcMWE13dBLC");
+
+export interface Basket {
+  name: string;
+  admin: Keypair;
+  vault: PublicKey;
+  shareMint: PublicKey;
+  // Token-2022 for soulbound shares
+  shareTokenProgram: PublicKey;
+  mints: PublicKey[];
+  atas: PublicKey[];
+  mockOracle: PublicKey;
+}
+
+export function vaultPda(program: Program<any>, admin: PublicKey, name: string): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), admin.toBuffer(), Buffer.from(name)],
+    program.programId
+  )[0];
+}
+
+export function shareMintPda(program: Program<any>, admin: PublicKey, name: string): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("vault_mint"), admin.toBuffer(), Buffer.from(name)],
+    program.programId
+  )[0];
+}
+
+export function userPositionPda(program: Program<any>, vault: PublicKey, user: PublicKey): PublicKey {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from("user_position"), vault.toBuffer(), user.toBuffer()],
+    program.programId
+  )[0];
+}
+
+export function protocolConfigPda(program: Program<any>): PublicKey {
+  return PublicKey.findProgramAddressSync([Buffer.from("protocol_config")], program.programId)[0];
+}
+
+// The protocol config is a singleton; create it with `admin` as authority
+// and treasury unless an earlier test or run already has
+export async function ensureProtocolConfig(program: Program<any>, admin: Keypair): Promise<PublicKey> {
+  const protocolConfig = protocolConfigPda(program);
+  if (!(await program.account.protocolConfig.fetchNullable(protocolConfig))) {
+    await (program.methods as any)
+      .initializeProtocolConfig(admin.publicKey, 0)
+      .accounts({ protocolConfig, authority: admin.publicKey, systemProgram: SystemProgram.programId })
+      .signers([admin])
+      .rpc({ commitment: "confirmed" });
+  }
+  return protocolConfig;
+}
+
+// Mint/ATA pairs followed by the mock oracle and the oracle adapter
+export function basketAccounts(basket: Basket): AccountMeta[] {
+  const metas: AccountMeta[] = [];
+  basket.mints.forEach((mint, i) => {
+    metas.push({ pubkey: mint, isWritable: false, isSigner: false });
+    metas.push({ pubkey: basket.atas[i], isWritable: true, isSigner: false });
+  });
+  metas.push({ pubkey: basket.mockOracle, isWritable: false, isSigner: false });
+  metas.push({ pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false });
+  return metas;
+}
+
+// Create a mock-oracle priced basket over `mints` (weights 40/30/30)
+export async function createBasket(
+  program: Program<any>,
+  admin: Keypair,
+  name: string,
+  mints: PublicKey[],
+  mockOracle: PublicKey,
+  soulbound = false
+): Promise<Basket> {
+  const vault = vaultPda(program, admin.publicKey, name);
+  const atas = await Promise.all(mints.map((mint) => getAssociatedTokenAddress(mint, vault, true)));
+  const weights = [40, 30, 30];
+  const assets = mints.map((mint, i) => ({ mint, weight: weights[i], ata: atas[i] }));
+
+  await program.methods
+    .createVault(name, assets, soulbound)
+    .accounts({ admin: admin.publicKey, token2022Program: soulbound ? TOKEN_2022_PROGRAM_ID : null })
+    .remainingAccounts(
+      mints.flatMap((mint, i) => [
+        { pubkey: mint, isWritable: false, isSigner: false },
+        { pubkey: atas[i], isWritable: true, isSigner: false },
+      ])
+    )
+    .signers([admin])
+    .rpc({ commitment: "confirmed" });
+
+  await (program.methods as any)
+    .setPriceSource(name, { mockOracle: {} }, mockOracle)
+    .accounts({ vault, authority: admin.publicKey })
+    .signers([admin])
+    .rpc({ commitment: "confirmed" });
+
+  return {
+    name,
+    admin,
+    vault,
+    shareMint: shareMintPda(program, admin.publicKey, name),
+    shareTokenProgram: soulbound ? TOKEN_2022_PROGRAM_ID : TOKEN_PROGRAM_ID,
+    mints,
+    atas,
+    mockOracle,
+  };
+}
+
+export function shareAta(basket: Basket, owner: PublicKey): PublicKey {
+  return getAssociatedTokenAddressSync(basket.shareMint, owner, false, basket.shareTokenProgram);
+}
+
+// Refresh the mock oracle so deposits and withdrawals see fresh prices
+export async function setPrices(
+  program: Program<any>,
+  basket: Basket,
+  btcUsd: number,
+  ethUsd: number,
+  solUsd: number
+): Promise<void> {
+  await (program.methods as any)
+    .updateMockOracle(new BN(btcUsd * 1_000_000), new BN(ethUsd * 1_000_000), new BN(solUsd * 1_000_000))
+    .accounts({ mockOracle: basket.mockOracle, authority: basket.admin.publicKey })
+    .signers([basket.admin])
+    .rpc({ commitment: "confirmed" });
+}
+
+export async function depositSol(
+  program: Program<any>,
+  basket: Basket,
+  user: Keypair,
+  lamports: number
+): Promise<string> {
+  const marinade = await getMarinadeAccounts(program.provider.connection);
+  return program.methods
+    .depositMultiAsset(basket.name, new BN(lamports), null)
+    .accounts({
+      vault: basket.vault,
+      user: user.publicKey,
+      userSharesAta: shareAta(basket, user.publicKey),
+      referralBalance: null,
+      vaultTokenMint: basket.shareMint,
+      btcQuote: PublicKey.default,
+      ethQuote: PublicKey.default,
+      solQuote: PublicKey.default,
+      marinadeStrategyProgram: MARINADE_STRATEGY_ID,
+      marinadeProgram: MARINADE_PROGRAM_ID,
+      marinadeState: marinade.marinadeState,
+      reservePda: marinade.reservePda,
+      msolMint: MSOL_MINT,
+      strategyMsolAta: await getAssociatedTokenAddress(MSOL_MINT, user.publicKey, false),
+      msolMintAuthority: marinade.msolMintAuthority,
+      liqPoolSolLegPda: marinade.liqPoolSolLegPda,
+      liqPoolMsolLeg: marinade.liqPoolMsolLeg,
+      liqPoolMsolLegAuthority: marinade.liqPoolMsolLegAuthority,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      shareTokenProgram: basket.shareTokenProgram,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: SYSVAR_RENT_PUBKEY,
+    } as any)
+    .remainingAccounts(basketAccounts(basket))
+    .signers([user])
+    .rpc({ commitment: "confirmed" });
+}
+
+export async function withdrawShares(
+  program: Program<any>,
+  basket: Basket,
+  user: Keypair,
+  shares: number | bigint,
+  // The user's wSOL account, needed when the SOL slot holds wrapped SOL
+  userWsolAccount: PublicKey | null = null
+): Promise<string> {
+  const marinade = await getMarinadeAccounts(program.provider.connection);
+  return program.methods
+    .withdrawMultiAsset(basket.name, new BN(shares.toString()))
+    .accounts({
+      vault: basket.vault,
+      user: user.publicKey,
+      solReceiver: user.publicKey,
+      userSharesAta: shareAta(basket, user.publicKey),
+      vaultTokenMint: basket.shareMint,
+      btcQuote: PublicKey.default,
+      ethQuote: PublicKey.default,
+      solQuote: PublicKey.default,
+      marinadeStrategyProgram: MARINADE_STRATEGY_ID,
+      marinadeProgram: MARINADE_PROGRAM_ID,
+      marinadeState: marinade.marinadeState,
+      msolMint: MSOL_MINT,
+      liqPoolMsolLeg: marinade.liqPoolMsolLeg,
+      liqPoolSolLegPda: marinade.liqPoolSolLegPda,
+      strategyMsolAta: await getAssociatedTokenAddress(MSOL_MINT, user.publicKey, false),
+      treasuryMsolAccount: marinade.treasuryMsolAccount,
+      userWsolAccount,
+      clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+      shareTokenProgram: basket.shareTokenProgram,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: SYSVAR_RENT_PUBKEY,
+    } as any)
+    .remainingAccounts(basketAccounts(basket))
+    .signers([user])
+    .rpc({ commitment: "confirmed" });
+}
+
+// Move `shares` of `from` into its suggested successor `to`, valuing both
+// at their mock oracle prices
+export async function migratePosition(
+  program: Program<any>,
+  from: Basket,
+  to: Basket,
+  user: Keypair,
+  shares: number | bigint
+): Promise<string> {
+  return (program.methods as any)
+    .migratePosition(from.name, to.name, new BN(shares.toString()))
+    .accounts({
+      vault: from.vault,
+      vaultTokenMint: from.shareMint,
+      userSharesAta: shareAta(from, user.publicKey),
+      successorVault: to.vault,
+      successorTokenMint: to.shareMint,
+      userSuccessorSharesAta: shareAta(to, user.publicKey),
+      user: user.publicKey,
+      btcQuote: PublicKey.default,
+      ethQuote: PublicKey.default,
+      solQuote: PublicKey.default,
+      shareTokenProgram: from.shareTokenProgram,
+      successorShareTokenProgram: to.shareTokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: SYSVAR_RENT_PUBKEY,
+    })
+    .remainingAccounts([...basketAccounts(from), ...basketAccounts(to)])
+    .signers([user])
+    .rpc({ commitment: "confirmed" });
+}
+
+// preview_withdraw for `shares`, without an exit fee
+export async function previewWithdraw(program: Program<any>, basket: Basket, shares: number | bigint): Promise<any> {
+  return (program.methods as any)
+    .previewWithdraw(basket.name, new BN(shares.toString()))
+    .accounts({
+      vault: basket.vault,
+      vaultTokenMint: basket.shareMint,
+      btcQuote: PublicKey.default,
+      ethQuote: PublicKey.default,
+      solQuote: PublicKey.default,
+      userPosition: null,
+    })
+    .remainingAccounts(basketAccounts(basket))
+    .view();
+}
+
+// What the in-kind instructions see: each asset ATA's balance, with the SOL
+// slot read as native lamports above rent and the fee reserve unless its mint
+// is the native mint (`nativeSol`)
+export async function inKindHoldings(
+  program: Program<any>,
+  basket: Basket
+): Promise<{ amounts: bigint[]; nativeSol: boolean }> {
+  const connection = program.provider.connection;
+  const amounts: bigint[] = [];
+  for (const ata of basket.atas) {
+    const account = await getAccount(connection, ata, "confirmed").catch(() => null);
+    amounts.push(account ? account.amount : BigInt(0));
+  }
+  // The SOL leg is the wSOL ATA for a native-mint slot, the vault's lamports otherwise
+  const nativeSol = !basket.mints[2].equals(NATIVE_MINT);
+  if (nativeSol) {
+    const info = await connection.getAccountInfo(basket.vault, "confirmed");
+    const rent = await connection.getMinimumBalanceForRentExemption(info!.data.length);
+    const vault: any = await program.account.vault.fetch(basket.vault);
+    const feeReserve = [vault.pendingFees, vault.protocolFeesOwed, vault.creatorFeesOwed, vault.stakerFeesOwed].reduce(
+      (sum: bigint, fee: BN) => sum + BigInt(fee.toString()),
+      BigInt(0)
+    );
+    amounts[2] = BigInt(info!.lamports) - BigInt(rent) - feeReserve;
+  }
+  return { amounts, nativeSol };
+}
+
+export async function shareBalance(program: Program<any>, basket: Basket, user: PublicKey): Promise<bigint> {
+  const ata = shareAta(basket, user);
+  return (await getAccount(program.provider.connection, ata, "confirmed", basket.shareTokenProgram)).amount;
+}
+
+// Fresh test user funded with `sol` SOL from `funder`
+export async function fundedUser(program: Program<any>, funder: Keypair, sol: number): Promise<Keypair> {
+  const user = Keypair.generate();
+  const connection = program.provider.connection;
+  const sig = await connection.sendTransaction(
+    new Transaction().add(
+      SystemProgram.transfer({
+        fromPubkey: funder.publicKey,
+        toPubkey: user.publicKey,
+        lamports: sol * anchor.web3.LAMPORTS_PER_SOL,
+      })
+    ),
+    [funder]
+  );
+  await connection.confirmTransaction(sig, "confirmed");
+  return user;
+}
@@ -0,0 +1,20 @@
+import { PublicKey } from "@solana/web3.js";
+
+// Vault is a zero-copy account: fixed-size arrays with a length byte instead
+// of String/Vec, default keys instead of None, and u8 price source codes.
+
+export const PRICE_SOURCE_SWITCHBOARD = 0;
+export const PRICE_SOURCE_MOCK_ORACLE = 1;
+export const PRICE_SOURCE_AGGREGATED = 2;
+
+export function storedName(vault: any): string {
+  return Buffer.from(vault.name.slice(0, vault.nameLen)).toString("utf8");
+}
+
+export function vaultAssets(vault: any): any[] {
+  return vault.assets.slice(0, vault.numAssets);
+}
+
+export function optionalKey(key: PublicKey): PublicKey | null {
+  return key.equals(PublicKey.default) ? null : key;
+}
@@ -148,6 +148,92 @@ describe("Mock Oracle Tests", () => {
       const now = Math.floor(Date.now() / 1000);
       expect(now - updateTime).to.be.lessThan(10, "Update timestamp should be recent");
     });
+
+    it("Should accept updates from a feeder until it is rotated out", async () => {
+      const feeder = Keypair.generate();
+      const [oracleFeeders] = PublicKey.findProgramAddressSync(
+        [Buffer.from("oracle_feeders"), mockOracle.toBuffer()],
+        program.programId
+      );
+      const setFeeders = (feeders: PublicKey[]) =>
+        (program.methods as any)
+          .setOracleFeeders(feeders)
+          .accounts({
+            mockOracle: mockOracle,
+            oracleFeeders: oracleFeeders,
+            authority: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+      const current: any = await (program.account as any).mockPriceOracle.fetch(mockOracle);
+      const pushAsFeeder = () =>
+        (program.methods as any)
+          .updateMockOracle(current.btcPrice, current.ethPrice, current.solPrice)
+          .accounts({
+            mockOracle: mockOracle,
+            oracleFeeders: oracleFeeders,
+            authority: feeder.publicKey,
+          })
+          .signers([feeder])
+          .rpc();
+
+      await setFeeders([feeder.publicKey]);
+      await pushAsFeeder();
+      const updatedOracle: any = await (program.account as any).mockPriceOracle.fetch(mockOracle);
+      expect(updatedOracle.authority.toString()).to.equal(admin.publicKey.toString());
+      expect(updatedOracle.solPrice.toNumber()).to.equal(current.solPrice.toNumber());
+
+      await setFeeders([]);
+      try {
+        await pushAsFeeder();
+        expect.fail("Revoked feeder should not update the oracle");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+    });
+
+    it("Should accept signed price updates newer than the last one", async () => {
+      const current: any = await (program.account as any).mockPriceOracle.fetch(mockOracle);
+      const signedAt = current.lastUpdate.toNumber() + 1;
+      // Let the cluster clock pass the signing time
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      const i64 = (value: number) => new anchor.BN(value).toArrayLike(Buffer, "le", 8);
+      const relay = (at: number) =>
+        (program.methods as any)
+          .updateMockOracleSigned(current.btcPrice, current.ethPrice, current.solPrice, new anchor.BN(at))
+          .accounts({
+            mockOracle: mockOracle,
+            oracleFeeders: null,
+            instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+          })
+          .preInstructions([
+            anchor.web3.Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: admin.secretKey,
+              message: Buffer.concat([
+                Buffer.from("etf-defi:mock-oracle:v1"),
+                mockOracle.toBuffer(),
+                i64(current.btcPrice.toNumber()),
+                i64(current.ethPrice.toNumber()),
+                i64(current.solPrice.toNumber()),
+                i64(at),
+              ]),
+            }),
+          ])
+          .rpc();
+
+      await relay(signedAt);
+      const updatedOracle: any = await (program.account as any).mockPriceOracle.fetch(mockOracle);
+      expect(updatedOracle.lastUpdate.toNumber()).to.equal(signedAt);
+
+      // A payload signed before the last update is rejected
+      try {
+        await relay(signedAt - 1);
+        expect.fail("Older payload should be rejected");
+      } catch (error: any) {
+        expect(error.message).to.include("StaleQuote");
+      }
+    });
   });
 
   describe("Oracle Price Validation", () => {
@@ -12,17 +12,42 @@ import {
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  ExtensionType,
+  NATIVE_MINT,
   createMint,
   getAccount,
   getMint,
   getAssociatedTokenAddress,
+  getExtensionTypes,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  transferChecked,
 } from "@solana/spl-token";
 import { expect } from "chai";
 import * as fs from "fs";
 import * as path from "path";
 import * as os from "os";
+import * as crypto from "crypto";
 import { getMarinadeAccounts, MARINADE_PROGRAM_ID, MSOL_MINT } from "./helpers/marinade-accounts";
+import { optionalKey, PRICE_SOURCE_MOCK_ORACLE, storedName, vaultAssets } from "./helpers/vault-account";
+import {
+  Basket,
+  basketAccounts,
+  createBasket,
+  depositSol,
+  ensureProtocolConfig,
+  fundedUser,
+  inKindHoldings,
+  migratePosition,
+  previewWithdraw,
+  setPrices,
+  shareAta,
+  shareBalance,
+  userPositionPda,
+  withdrawShares,
+} from "./helpers/basket";
 import { 
   getMXEAccAddress, 
   getClusterAccAddress,
@@ -54,6 +79,9 @@ describe("Multi-Asset Vault Tests", () => {
   const BTC_USD_FEED = new PublicKey("DAXAq94Y5nX2dDp15SdeBzYRqTn8viFf9Dxq4ws7rHec");
   const ETH_USD_FEED = new PublicKey("DAXAq94Y5nX2dDp15SdeBzYRqTn8viFf9Dxq4ws7rHec");
 
+  // Oracle adapter program the vault reads price sources through
+  const ORACLE_ADAPTER_ID = new PublicKey("4pCSVvFkZLL4U3ydKv9zNwfg4tyBPeiw6AuKLZRHeEBK");
+
   let admin: Keypair;
   let user1: Keypair;
   let user2: Keypair;
@@ -177,7 +205,7 @@ describe("Multi-Asset Vault Tests", () => {
       const solAta = await getAssociatedTokenAddress(solMint, vaultPda, true);
 
       const tx = await program.methods
-        .createVault(vaultName, assets)
+        .createVault(vaultName, assets, false)
         .accounts({
           admin: admin.publicKey,
         })
@@ -199,11 +227,12 @@ describe("Multi-Asset Vault Tests", () => {
       expect(vaultAccount.admin.toString()).to.equal(
         admin.publicKey.toString()
       );
-      expect(vaultAccount.name).to.equal(vaultName);
-      expect(vaultAccount.assets.length).to.equal(3);
-      expect(vaultAccount.assets[0].weight).to.equal(40);
-      expect(vaultAccount.assets[1].weight).to.equal(30);
-      expect(vaultAccount.assets[2].weight).to.equal(30);
+      expect(storedName(vaultAccount)).to.equal(vaultName);
+      const assets = vaultAssets(vaultAccount);
+      expect(assets.length).to.equal(3);
+      expect(assets[0].weight).to.equal(40);
+      expect(assets[1].weight).to.equal(30);
+      expect(assets[2].weight).to.equal(30);
     });
 
     it("Fails with empty vault name", async () => {
@@ -223,7 +252,7 @@ describe("Multi-Asset Vault Tests", () => {
 
       try {
         await program.methods
-          .createVault(vaultName, assets)
+          .createVault(vaultName, assets, false)
           .accounts({
             admin: admin.publicKey,
           })
@@ -256,7 +285,7 @@ describe("Multi-Asset Vault Tests", () => {
 
     //   try {
     //     await program.methods
-    //       .createVault(vaultName, assets)
+    //       .createVault(vaultName, assets, false)
     //       .accounts({
     //         admin: admin.publicKey,
     //       })
@@ -297,7 +326,7 @@ describe("Multi-Asset Vault Tests", () => {
 
       try {
         await program.methods
-          .createVault(vaultName, assets)
+          .createVault(vaultName, assets, false)
           .accounts({
             admin: admin.publicKey,
           })
@@ -340,7 +369,7 @@ describe("Multi-Asset Vault Tests", () => {
 
       try {
         await program.methods
-          .createVault(vaultName, assets)
+          .createVault(vaultName, assets, false)
           .accounts({
             admin: admin.publicKey,
           })
@@ -358,13 +387,55 @@ describe("Multi-Asset Vault Tests", () => {
       }
     });
 
+    it("Fails with the same mint in two slots", async () => {
+      const vaultName = `DuplicateMint_${Date.now()}`;
+      const assets = [
+        {
+          mint: btcMint,
+          weight: 50,
+          ata: PublicKey.default,
+        },
+        {
+          mint: btcMint, // Same mint again
+          weight: 50,
+          ata: PublicKey.default,
+        },
+      ];
+
+      const vaultPda = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), admin.publicKey.toBuffer(), Buffer.from(vaultName)],
+        program.programId
+      )[0];
+
+      const btcAta = await getAssociatedTokenAddress(btcMint, vaultPda, true);
+
+      try {
+        await program.methods
+          .createVault(vaultName, assets, false)
+          .accounts({
+            admin: admin.publicKey,
+          })
+          .remainingAccounts([
+            { pubkey: btcMint, isWritable: false, isSigner: false },
+            { pubkey: btcAta, isWritable: true, isSigner: false },
+            { pubkey: btcMint, isWritable: false, isSigner: false },
+            { pubkey: btcAta, isWritable: true, isSigner: false },
+          ])
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown error for duplicate mint");
+      } catch (error: any) {
+        expect(error.message).to.include("DuplicateAsset");
+      }
+    });
+
     it("Fails with zero assets", async () => {
       const vaultName = `NoAssets_${Date.now()}`;
       const assets: any[] = []; // Empty assets array
 
       try {
         await program.methods
-          .createVault(vaultName, assets)
+          .createVault(vaultName, assets, false)
           .accounts({
             admin: admin.publicKey,
           })
@@ -377,12 +448,12 @@ describe("Multi-Asset Vault Tests", () => {
       }
     });
 
-    it("Fails with more than 10 assets", async () => {
+    it("Fails with more than 32 assets", async () => {
       const vaultName = `TooManyAssets_${Date.now()}`;
-      // Create 11 assets
-      const assets = Array.from({ length: 11 }, (_, i) => ({
+      // Create 33 assets
+      const assets = Array.from({ length: 33 }, (_, i) => ({
         mint: btcMint, // Reusing same mint for simplicity
-        weight: i === 0 ? 10 : 9, // Roughly 100% total (10 + 10*9 = 100, close enough)
+        weight: i < 4 ? 4 : 3, // 4*4 + 29*3 = 103; the count check fails first
         ata: PublicKey.default,
       }));
 
@@ -392,21 +463,21 @@ describe("Multi-Asset Vault Tests", () => {
       )[0];
 
       const btcAta = await getAssociatedTokenAddress(btcMint, vaultPda, true);
-      const remainingAccounts = Array.from({ length: 11 }, () => [
+      const remainingAccounts = Array.from({ length: 33 }, () => [
         { pubkey: btcMint, isWritable: false, isSigner: false },
         { pubkey: btcAta, isWritable: true, isSigner: false },
       ]).flat();
 
       try {
         await program.methods
-          .createVault(vaultName, assets)
+          .createVault(vaultName, assets, false)
           .accounts({
             admin: admin.publicKey,
           })
           .remainingAccounts(remainingAccounts)
           .signers([admin])
           .rpc();
-        expect.fail("Should have thrown error for >10 assets");
+        expect.fail("Should have thrown error for >32 assets");
       } catch (error: any) {
         // Either InvalidAssetCount or Transaction too large error is acceptable
         // Transaction becomes too large before validation can occur
@@ -464,7 +535,7 @@ describe("Multi-Asset Vault Tests", () => {
 
       // Create the vault
       await program.methods
-        .createVault(vaultName, assets)
+        .createVault(vaultName, assets, false)
         .accounts({
           admin: admin.publicKey,
         })
@@ -563,11 +634,12 @@ describe("Multi-Asset Vault Tests", () => {
       );
 
       const tx = await program.methods
-        .depositMultiAsset(vaultName, new anchor.BN(depositAmount))
+        .depositMultiAsset(vaultName, new anchor.BN(depositAmount), null)
         .accounts({
           vault: vaultPda,
           user: user1.publicKey,
           userSharesAta: userVaultTokenAccount,
+          referralBalance: null,
           vaultTokenMint: vaultTokenMintPda,
           btcQuote: PublicKey.default, // Not used with MockOracle
           ethQuote: PublicKey.default, // Not used with MockOracle
@@ -583,6 +655,7 @@ describe("Multi-Asset Vault Tests", () => {
           liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
           liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
           clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -596,6 +669,7 @@ describe("Multi-Asset Vault Tests", () => {
           { pubkey: solMint, isWritable: false, isSigner: false },
           { pubkey: solAta, isWritable: true, isSigner: false },
           { pubkey: mockOracle, isWritable: false, isSigner: false }, // Mock Oracle for price fetching
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
         ])
         .signers([user1])
         .rpc();
@@ -627,14 +701,79 @@ describe("Multi-Asset Vault Tests", () => {
       expect(Number(userVaultTokenAccountInfo.amount)).to.be.greaterThan(0);
       console.log("\n✅ User received shares:", userVaultTokenAccountInfo.amount.toString());
       
+      // Verify the user's position tracks shares and cost basis
+      const [userPosition] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), vaultPda.toBuffer(), user1.publicKey.toBuffer()],
+        program.programId
+      );
+      const position: any = await (program.account as any).userPosition.fetch(userPosition);
+      expect(position.user.toBase58()).to.equal(user1.publicKey.toBase58());
+      expect(position.shares.toString()).to.equal(userVaultTokenAccountInfo.amount.toString());
+      expect(position.depositedUsd.toNumber()).to.be.greaterThan(0);
+      
       // Verify vault state
       const vaultAccount: any = await program.account.vault.fetch(vaultPda);
       console.log("📊 Vault State After Deposit:");
-      console.log("  Total Shares:", vaultAccount.totalShares?.toString() || "N/A");
-      console.log("  TVL (micro-USD):", vaultAccount.tvlUsd?.toString() || "N/A");
-      if (vaultAccount.tvlUsd) {
-        console.log("  TVL (USD): $" + (vaultAccount.tvlUsd.toNumber() / 1_000_000).toFixed(2));
-      }
+      console.log("  Total Shares:", vaultAccount.totalShares.toString());
+      console.log("  TVL (micro-USD):", vaultAccount.lastTvlUsd.toString());
+      console.log("  TVL (USD): $" + (vaultAccount.lastTvlUsd.toNumber() / 1_000_000).toFixed(2));
+      const supply = (await getMint(provider.connection, vaultTokenMintPda)).supply;
+      expect(vaultAccount.totalShares.toString()).to.equal(supply.toString());
+      expect(vaultAccount.lastTvlUsd.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("Debits the user exactly the deposit amount", async () => {
+      // Regression: deposit_multi_asset used to transfer the lamports twice
+      const marinadeAccounts = await getMarinadeAccounts(provider.connection);
+      const dummyMsolAta = await getAssociatedTokenAddress(MSOL_MINT, admin.publicKey, false);
+      const depositAmount = 0.01 * anchor.web3.LAMPORTS_PER_SOL;
+
+      // The position and shares ATA exist from the previous deposit, and the
+      // provider wallet pays the fee, so user1 only pays the deposit itself
+      const balanceBefore = await provider.connection.getBalance(user1.publicKey);
+      await program.methods
+        .depositMultiAsset(vaultName, new anchor.BN(depositAmount), null)
+        .accounts({
+          vault: vaultPda,
+          user: user1.publicKey,
+          userSharesAta: userVaultTokenAccount,
+          referralBalance: null,
+          vaultTokenMint: vaultTokenMintPda,
+          btcQuote: PublicKey.default,
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
+          marinadeStrategyProgram: marinadeProgram.programId,
+          marinadeProgram: MARINADE_PROGRAM_ID,
+          marinadeState: marinadeAccounts.marinadeState,
+          reservePda: marinadeAccounts.reservePda,
+          msolMint: MSOL_MINT,
+          strategyMsolAta: dummyMsolAta,
+          msolMintAuthority: marinadeAccounts.msolMintAuthority,
+          liqPoolSolLegPda: marinadeAccounts.liqPoolSolLegPda,
+          liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
+          liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
+          clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        } as any)
+        .remainingAccounts([
+          { pubkey: btcMint, isWritable: false, isSigner: false },
+          { pubkey: btcAta, isWritable: true, isSigner: false },
+          { pubkey: ethMint, isWritable: false, isSigner: false },
+          { pubkey: ethAta, isWritable: true, isSigner: false },
+          { pubkey: solMint, isWritable: false, isSigner: false },
+          { pubkey: solAta, isWritable: true, isSigner: false },
+          { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
+        ])
+        .signers([user1])
+        .rpc({ commitment: "confirmed" });
+      const balanceAfter = await provider.connection.getBalance(user1.publicKey, "confirmed");
+
+      expect(balanceBefore - balanceAfter).to.equal(depositAmount);
     });
 
     it("Fails with zero deposit amount", async () => {
@@ -650,11 +789,12 @@ describe("Multi-Asset Vault Tests", () => {
 
       try {
         await program.methods
-          .depositMultiAsset(vaultName, new anchor.BN(0))
+          .depositMultiAsset(vaultName, new anchor.BN(0), null)
           .accounts({
             vault: vaultPda,
             user: user1.publicKey,
             userSharesAta: userVaultTokenAccount,
+            referralBalance: null,
             vaultTokenMint: vaultTokenMintPda,
             btcQuote: PublicKey.default,
             ethQuote: PublicKey.default,
@@ -670,6 +810,7 @@ describe("Multi-Asset Vault Tests", () => {
             liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
             liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
             clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
             tokenProgram: TOKEN_PROGRAM_ID,
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
@@ -683,6 +824,7 @@ describe("Multi-Asset Vault Tests", () => {
             { pubkey: solMint, isWritable: false, isSigner: false },
             { pubkey: solAta, isWritable: true, isSigner: false },
             { pubkey: mockOracle, isWritable: false, isSigner: false },
+            { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
           ])
           .signers([user1])
           .rpc();
@@ -691,6 +833,58 @@ describe("Multi-Asset Vault Tests", () => {
         expect(error.message).to.include("InvalidAmount");
       }
     });
+
+    it("Fails with a deposit too small to mint shares", async () => {
+      const marinadeAccounts = await getMarinadeAccounts(provider.connection);
+      const dummyMsolAta = await getAssociatedTokenAddress(MSOL_MINT, admin.publicKey, false);
+
+      // One lamport is worth far less than one share unit ($0.000001)
+      try {
+        await program.methods
+          .depositMultiAsset(vaultName, new anchor.BN(1), null)
+          .accounts({
+            vault: vaultPda,
+            user: user1.publicKey,
+            userSharesAta: userVaultTokenAccount,
+            referralBalance: null,
+            vaultTokenMint: vaultTokenMintPda,
+            btcQuote: PublicKey.default,
+            ethQuote: PublicKey.default,
+            solQuote: PublicKey.default,
+            marinadeStrategyProgram: marinadeProgram.programId,
+            marinadeProgram: MARINADE_PROGRAM_ID,
+            marinadeState: marinadeAccounts.marinadeState,
+            reservePda: marinadeAccounts.reservePda,
+            msolMint: MSOL_MINT,
+            strategyMsolAta: dummyMsolAta,
+            msolMintAuthority: marinadeAccounts.msolMintAuthority,
+            liqPoolSolLegPda: marinadeAccounts.liqPoolSolLegPda,
+            liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
+            liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+            shareTokenProgram: TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+          } as any)
+          .remainingAccounts([
+            { pubkey: btcMint, isWritable: false, isSigner: false },
+            { pubkey: btcAta, isWritable: true, isSigner: false },
+            { pubkey: ethMint, isWritable: false, isSigner: false },
+            { pubkey: ethAta, isWritable: true, isSigner: false },
+            { pubkey: solMint, isWritable: false, isSigner: false },
+            { pubkey: solAta, isWritable: true, isSigner: false },
+            { pubkey: mockOracle, isWritable: false, isSigner: false },
+            { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
+          ])
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown error for a zero-share deposit");
+      } catch (error: any) {
+        expect(error.message).to.include("MintedZeroShares");
+      }
+    });
   });
 
   // describe("withdraw_multi_asset", () => {
@@ -730,7 +924,7 @@ describe("Multi-Asset Vault Tests", () => {
 
   //     // Create the vault
   //     await program.methods
-  //       .createVault(vaultName, assets)
+  //       .createVault(vaultName, assets, false)
   //       .accounts({
   //         admin: admin.publicKey,
   //         tokenProgram: TOKEN_PROGRAM_ID,
@@ -834,7 +1028,7 @@ describe("Multi-Asset Vault Tests", () => {
 
   //     // Create the vault
   //     await program.methods
-  //       .createVault(vaultName, assets)
+  //       .createVault(vaultName, assets, false)
   //       .accounts({
   //         vault: vaultPda,
   //         admin: admin.publicKey,
@@ -985,7 +1179,7 @@ describe("Multi-Asset Vault Tests", () => {
       ];
       
       const tx = await program.methods
-        .createVault(MARINADE_VAULT_NAME, assets)
+        .createVault(MARINADE_VAULT_NAME, assets, false)
         .accounts({
           admin: admin.publicKey,
         })
@@ -1004,11 +1198,12 @@ describe("Multi-Asset Vault Tests", () => {
       
       // Verify vault state
       const vaultData = await program.account.vault.fetch(marinadeVault);
-      expect(vaultData.name).to.equal(MARINADE_VAULT_NAME);
-      expect(vaultData.assets.length).to.equal(3);
-      expect(vaultData.assets[0].weight).to.equal(40);
-      expect(vaultData.assets[1].weight).to.equal(30);
-      expect(vaultData.assets[2].weight).to.equal(30);
+      expect(storedName(vaultData)).to.equal(MARINADE_VAULT_NAME);
+      const assets = vaultAssets(vaultData);
+      expect(assets.length).to.equal(3);
+      expect(assets[0].weight).to.equal(40);
+      expect(assets[1].weight).to.equal(30);
+      expect(assets[2].weight).to.equal(30);
     });
 
     it("Step 2: Initialize Marinade Strategy", async () => {
@@ -1045,7 +1240,8 @@ describe("Multi-Asset Vault Tests", () => {
         .accounts({
           vault: marinadeVault,
           admin: admin.publicKey,
-        })
+          strategyAccount,
+        } as any)
         .signers([admin])
         .rpc({ commitment: "confirmed" });
       
@@ -1053,8 +1249,8 @@ describe("Multi-Asset Vault Tests", () => {
       
       // Verify vault now has strategy configured
       const vaultData = await program.account.vault.fetch(marinadeVault);
-      expect(vaultData.marinadeStrategy).to.not.be.null;
-      expect(vaultData.marinadeStrategy!.toString()).to.equal(strategyAccount.toString());
+      expect(optionalKey(vaultData.marinadeStrategy)).to.not.be.null;
+      expect(vaultData.marinadeStrategy.toString()).to.equal(strategyAccount.toString());
     });
 
     it("Step 4: Set Price Source to MockOracle", async () => {
@@ -1076,11 +1272,11 @@ describe("Multi-Asset Vault Tests", () => {
       
       // Refetch vault to ensure state is updated
       const vaultData = await program.account.vault.fetch(marinadeVault);
-      expect(vaultData.mockOracle).to.not.be.null;
-      expect(vaultData.mockOracle!.toString()).to.equal(mockOracle.toString());
+      expect(optionalKey(vaultData.mockOracle)).to.not.be.null;
+      expect(vaultData.mockOracle.toString()).to.equal(mockOracle.toString());
       
       console.log("  Vault price source:", vaultData.priceSource);
-      console.log("  Mock oracle:", vaultData.mockOracle!.toString());
+      console.log("  Mock oracle:", vaultData.mockOracle.toString());
     });
 
     it("Step 5: Deposit SOL and Verify 30% Delegated to Marinade", async () => {
@@ -1131,11 +1327,12 @@ describe("Multi-Asset Vault Tests", () => {
       
       // Perform deposit
       const tx = await program.methods
-        .depositMultiAsset(MARINADE_VAULT_NAME, DEPOSIT_AMOUNT)
+        .depositMultiAsset(MARINADE_VAULT_NAME, DEPOSIT_AMOUNT, null)
         .accounts({
           vault: marinadeVault,
           user: admin.publicKey,
           userSharesAta: userSharesAta,
+          referralBalance: null,
           vaultTokenMint: marinadeVaultTokenMint,
           btcQuote: PublicKey.default,
           ethQuote: PublicKey.default,
@@ -1151,6 +1348,7 @@ describe("Multi-Asset Vault Tests", () => {
           liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
           liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
           clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -1164,6 +1362,7 @@ describe("Multi-Asset Vault Tests", () => {
           { pubkey: solMint, isWritable: false, isSigner: false },
           { pubkey: solVaultAta, isWritable: true, isSigner: false },
           { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
           { pubkey: strategyAccount, isWritable: true, isSigner: false },
         ])
         .signers([admin])
@@ -1311,6 +1510,7 @@ describe("Multi-Asset Vault Tests", () => {
           strategyMsolAta: msolAta,
           treasuryMsolAccount: marinadeAccounts.treasuryMsolAccount,
           clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -1324,6 +1524,7 @@ describe("Multi-Asset Vault Tests", () => {
           { pubkey: solMint, isWritable: false, isSigner: false },
           { pubkey: solVaultAta, isWritable: true, isSigner: false },
           { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
           { pubkey: strategyAccount, isWritable: true, isSigner: false },
         ])
         .signers([admin])
@@ -1465,7 +1666,7 @@ describe("Multi-Asset Vault Tests", () => {
       ];
       
       const tx = await program.methods
-        .createVault(REBALANCE_VAULT_NAME, assets)
+        .createVault(REBALANCE_VAULT_NAME, assets, false)
         .accounts({
           admin: admin.publicKey,
         })
@@ -1484,8 +1685,8 @@ describe("Multi-Asset Vault Tests", () => {
       
       // Verify vault state
       const vaultData = await program.account.vault.fetch(rebalanceVault);
-      expect(vaultData.name).to.equal(REBALANCE_VAULT_NAME);
-      expect(vaultData.assets.length).to.equal(3);
+      expect(storedName(vaultData)).to.equal(REBALANCE_VAULT_NAME);
+      expect(vaultAssets(vaultData).length).to.equal(3);
     });
 
     it("Step 2: Configure Vault to Use Mock Oracle", async () => {
@@ -1504,8 +1705,8 @@ describe("Multi-Asset Vault Tests", () => {
       
       // Verify configuration
       const vaultData = await program.account.vault.fetch(rebalanceVault);
-      expect(vaultData.priceSource).to.deep.equal({ mockOracle: {} });
-      expect(vaultData.mockOracle).to.not.be.null;
+      expect(vaultData.priceSource).to.equal(PRICE_SOURCE_MOCK_ORACLE);
+      expect(optionalKey(vaultData.mockOracle)).to.not.be.null;
     });
 
     it("Step 3: Update Oracle Prices for Baseline", async () => {
@@ -1548,11 +1749,12 @@ describe("Multi-Asset Vault Tests", () => {
       const dummyMsolAta = await getAssociatedTokenAddress(MSOL_MINT, admin.publicKey, false);
       
       const tx = await program.methods
-        .depositMultiAsset(REBALANCE_VAULT_NAME, new anchor.BN(depositAmount))
+        .depositMultiAsset(REBALANCE_VAULT_NAME, new anchor.BN(depositAmount), null)
         .accounts({
           vault: rebalanceVault,
           user: admin.publicKey,
           userSharesAta: userSharesAta,
+          referralBalance: null,
           vaultTokenMint: rebalanceVaultTokenMint,
           btcQuote: PublicKey.default,
           ethQuote: PublicKey.default,
@@ -1568,6 +1770,7 @@ describe("Multi-Asset Vault Tests", () => {
           liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
           liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
           clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
@@ -1581,6 +1784,7 @@ describe("Multi-Asset Vault Tests", () => {
           { pubkey: solMint, isWritable: false, isSigner: false },
           { pubkey: solVaultAta, isWritable: true, isSigner: false },
           { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
         ])
         .signers([admin])
         .rpc({ commitment: "confirmed" });
@@ -1623,11 +1827,15 @@ describe("Multi-Asset Vault Tests", () => {
         .accounts({
           vault: rebalanceVault,
           authority: admin.publicKey,
+          btcQuote: PublicKey.default, // Not used with MockOracle
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .remainingAccounts([
           { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
           { pubkey: btcVaultAta, isWritable: true, isSigner: false },
           { pubkey: ethVaultAta, isWritable: true, isSigner: false },
           { pubkey: solVaultAta, isWritable: true, isSigner: false },
@@ -1669,11 +1877,15 @@ describe("Multi-Asset Vault Tests", () => {
         .accounts({
           vault: rebalanceVault,
           authority: admin.publicKey,
+          btcQuote: PublicKey.default, // Not used with MockOracle
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .remainingAccounts([
           { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
           { pubkey: btcVaultAta, isWritable: true, isSigner: false },
           { pubkey: ethVaultAta, isWritable: true, isSigner: false },
           { pubkey: solVaultAta, isWritable: true, isSigner: false },
@@ -1699,6 +1911,147 @@ describe("Multi-Asset Vault Tests", () => {
         }
       }
     });
+
+    it("Step 8: Refresh Price Cache and Deposit Without Oracle Account", async () => {
+      console.log("\n🗄️  Refreshing price cache...");
+      
+      const [priceCache] = PublicKey.findProgramAddressSync(
+        [Buffer.from("price_cache"), rebalanceVault.toBuffer()],
+        program.programId
+      );
+      
+      await (program.methods as any)
+        .refreshPrices(REBALANCE_VAULT_NAME)
+        .accounts({
+          vault: rebalanceVault,
+          priceCache,
+          btcQuote: PublicKey.default,
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: mockOracle, isWritable: false, isSigner: false },
+          { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
+        ])
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      
+      const cache = await (program.account as any).priceCache.fetch(priceCache);
+      const oracle = await (program.account as any).mockPriceOracle.fetch(mockOracle);
+      expect(cache.vault.toBase58()).to.equal(rebalanceVault.toBase58());
+      expect(cache.solPrice.toString()).to.equal(oracle.solPrice.toString());
+      
+      // Deposit passes the cache in the oracle's slot
+      const userSharesAta = getAssociatedTokenAddressSync(rebalanceVaultTokenMint, admin.publicKey);
+      const marinadeAccounts = await getMarinadeAccounts(provider.connection);
+      const dummyMsolAta = await getAssociatedTokenAddress(MSOL_MINT, admin.publicKey, false);
+      const sharesBefore = (await getAccount(provider.connection, userSharesAta)).amount;
+      
+      await program.methods
+        .depositMultiAsset(REBALANCE_VAULT_NAME, new anchor.BN(0.1 * anchor.web3.LAMPORTS_PER_SOL), null)
+        .accounts({
+          vault: rebalanceVault,
+          user: admin.publicKey,
+          userSharesAta: userSharesAta,
+          referralBalance: null,
+          vaultTokenMint: rebalanceVaultTokenMint,
+          btcQuote: PublicKey.default,
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
+          marinadeStrategyProgram: marinadeProgram.programId,
+          marinadeProgram: MARINADE_PROGRAM_ID,
+          marinadeState: marinadeAccounts.marinadeState,
+          reservePda: marinadeAccounts.reservePda,
+          msolMint: MSOL_MINT,
+          strategyMsolAta: dummyMsolAta,
+          msolMintAuthority: marinadeAccounts.msolMintAuthority,
+          liqPoolSolLegPda: marinadeAccounts.liqPoolSolLegPda,
+          liqPoolMsolLeg: marinadeAccounts.liqPoolMsolLeg,
+          liqPoolMsolLegAuthority: marinadeAccounts.liqPoolMsolLegAuthority,
+          clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        } as any)
+        .remainingAccounts([
+          { pubkey: btcMint, isWritable: false, isSigner: false },
+          { pubkey: btcVaultAta, isWritable: true, isSigner: false },
+          { pubkey: ethMint, isWritable: false, isSigner: false },
+          { pubkey: ethVaultAta, isWritable: true, isSigner: false },
+          { pubkey: solMint, isWritable: false, isSigner: false },
+          { pubkey: solVaultAta, isWritable: true, isSigner: false },
+          { pubkey: priceCache, isWritable: false, isSigner: false },
+        ])
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      
+      const sharesAfter = (await getAccount(provider.connection, userSharesAta)).amount;
+      expect(sharesAfter > sharesBefore).to.be.true;
+      console.log("✅ Deposit priced from cache");
+    });
+
+    it("Step 9: Snapshot NAV Once Per Interval", async () => {
+      console.log("\n📸 Taking NAV snapshot...");
+      
+      const [navHistory] = PublicKey.findProgramAddressSync(
+        [Buffer.from("nav_history"), rebalanceVault.toBuffer()],
+        program.programId
+      );
+      const [trackingError] = PublicKey.findProgramAddressSync(
+        [Buffer.from("tracking_error"), rebalanceVault.toBuffer()],
+        program.programId
+      );
+      const snapshotNav = () =>
+        (program.methods as any)
+          .snapshotNav(REBALANCE_VAULT_NAME)
+          .accounts({
+            vault: rebalanceVault,
+            navHistory,
+            trackingError,
+            vaultTokenMint: rebalanceVaultTokenMint,
+            btcQuote: PublicKey.default,
+            ethQuote: PublicKey.default,
+            solQuote: PublicKey.default,
+            payer: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([
+            { pubkey: btcMint, isWritable: false, isSigner: false },
+            { pubkey: btcVaultAta, isWritable: false, isSigner: false },
+            { pubkey: ethMint, isWritable: false, isSigner: false },
+            { pubkey: ethVaultAta, isWritable: false, isSigner: false },
+            { pubkey: solMint, isWritable: false, isSigner: false },
+            { pubkey: solVaultAta, isWritable: false, isSigner: false },
+            { pubkey: mockOracle, isWritable: false, isSigner: false },
+            { pubkey: ORACLE_ADAPTER_ID, isWritable: false, isSigner: false },
+          ])
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+      
+      await snapshotNav();
+      
+      const history = await (program.account as any).navHistory.fetch(navHistory);
+      const supply = (await getMint(provider.connection, rebalanceVaultTokenMint)).supply;
+      expect(history.snapshots.length).to.equal(1);
+      expect(history.snapshots[0].totalShares.toString()).to.equal(supply.toString());
+      expect(history.snapshots[0].tvlUsd.toNumber()).to.be.greaterThan(0);
+      const tracking = await (program.account as any).trackingError.fetch(trackingError);
+      expect(tracking.samples).to.equal(1);
+      expect(tracking.trackingErrorBps).to.equal(tracking.lastDeviationBps);
+      
+      // A second snapshot inside the interval is rejected
+      try {
+        await snapshotNav();
+        expect.fail("Second snapshot should be rejected");
+      } catch (err: any) {
+        expect(err.toString()).to.include("SnapshotTooSoon");
+      }
+      console.log("✅ NAV snapshot recorded");
+    });
   });
 
   /**
@@ -1720,18 +2073,35 @@ describe("Multi-Asset Vault Tests", () => {
   describe("Confidential Rebalancing Tests (Arcium MXE)", () => {
     const CONFIDENTIAL_VAULT_NAME = `ConfidentialVault_${Date.now()}`;
     const ARCIUM_MXE_PROGRAM_ID = new PublicKey("FwbzbjGyBmb5n7VAPfMnYKZthycScuA6ktGE7rtZ2Z9x");
+    // Fixed so the computation account address is deterministic
+    const COMPUTATION_OFFSET = new anchor.BN(1000000);
+
+    // vault::computation_nonce: the inputs of each computation are encrypted
+    // under a nonce the program derives from the vault and offset
+    const computationNonce = (vault: PublicKey, offset: anchor.BN): anchor.BN => {
+      const hash = crypto
+        .createHash("sha256")
+        .update(Buffer.concat([Buffer.from("mxe_nonce"), vault.toBuffer(), offset.toArrayLike(Buffer, "le", 8)]))
+        .digest();
+      return new anchor.BN(hash.subarray(0, 16), "le");
+    };
     
     let confidentialVault: PublicKey;
     let confidentialVaultTokenMint: PublicKey;
     let btcVaultAta: PublicKey;
     let ethVaultAta: PublicKey;
     let solVaultAta: PublicKey;
+
+    // Holds shares across the pending computation, to check the operation lock
+    let confidentialBasket: Basket;
+    let feederBasket: Basket;
+    let holder: Keypair;
     
     // Shared test data
     let encryptedData: {
       pub_key: number[];
       nonce: anchor.BN;
-      encryptedUserFunds: number[];  // Single encrypted value (32 bytes)
+      encryptedPortfolio: number[][];  // 13 ciphertexts (32 bytes each)
     };
     
     // Arcium MXE account PDAs
@@ -1822,7 +2192,7 @@ describe("Multi-Asset Vault Tests", () => {
       ];
       
       const tx = await program.methods
-        .createVault(CONFIDENTIAL_VAULT_NAME, assets)
+        .createVault(CONFIDENTIAL_VAULT_NAME, assets, false)
         .accounts({
           admin: admin.publicKey,
         })
@@ -1855,52 +2225,84 @@ describe("Multi-Asset Vault Tests", () => {
       console.log("✅ Price source set:", tx);
     });
 
-    it("Step 3: Prepare Encrypted User Funds Data", async () => {
-      console.log("\n🔐 Preparing encrypted user funds data...");
+    it("Step 2b: Seed a Holder and a Vault Migrating Into This One", async () => {
+      confidentialBasket = {
+        name: CONFIDENTIAL_VAULT_NAME,
+        admin,
+        vault: confidentialVault,
+        shareMint: confidentialVaultTokenMint,
+        mints: [btcMint, ethMint, solMint],
+        atas: [btcVaultAta, ethVaultAta, solVaultAta],
+        mockOracle,
+      };
+      await setPrices(program, confidentialBasket, 100_000, 3_500, 150);
+      holder = await fundedUser(program, admin, 0.3);
+      await depositSol(program, confidentialBasket, holder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      // A vault pointing its holders at this one, for migration into it
+      feederBasket = await createBasket(program, admin, `Feeder_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      await depositSol(program, feederBasket, holder, 0.05 * anchor.web3.LAMPORTS_PER_SOL);
+      await (program.methods as any)
+        .suggestAlternative(feederBasket.name, false)
+        .accounts({ vault: feederBasket.vault, successorVault: confidentialVault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("Step 3: Prepare Encrypted Portfolio Data", async () => {
+      console.log("\n🔐 Preparing encrypted portfolio data...");
       
-      // Simplified: Only encrypt the user's total funds value
-      // This is the most sensitive information that needs MEV protection
-      // In production, this would be actual encrypted data using Arcium's encryption
+      // RebalancingInput fields in circuit order:
+      // btc/eth/sol balance, btc/eth/sol price, btc/eth/sol weight,
+      // btc/eth/sol current weight, threshold
+      // In production, each field is encrypted with Arcium's RescueCipher
       const pub_key = new Uint8Array(32).fill(1); // Mock public key
-      const nonce = new anchor.BN(Date.now()); // Unique nonce for this computation (must be BN for u128)
-      
-      // Just ONE encrypted value: the user's total fund balance in USD (as u64)
-      // In production, this comes from Arcium encryption of the actual vault balance
-      const encryptedUserFunds = new Uint8Array(32);
-      // Fill with mock encrypted data (in production, this comes from Arcium encryption)
-      for (let j = 0; j < 32; j++) {
-        encryptedUserFunds[j] = j % 256; // Mock encrypted value
+      const nonce = computationNonce(confidentialVault, COMPUTATION_OFFSET); // Fresh for this computation (BN for u128)
+      
+      const encryptedPortfolio: number[][] = [];
+      for (let i = 0; i < 13; i++) {
+        // Mock ciphertext (in production, this comes from Arcium encryption)
+        const ciphertext = new Uint8Array(32);
+        for (let j = 0; j < 32; j++) {
+          ciphertext[j] = (i * 32 + j) % 256;
+        }
+        encryptedPortfolio.push(Array.from(ciphertext));
       }
       
-      console.log("✅ Encrypted user funds prepared:");
+      console.log("✅ Encrypted portfolio prepared:");
       console.log(`  Public Key: ${Buffer.from(pub_key).toString("hex").substring(0, 16)}...`);
       console.log(`  Nonce: ${nonce}`);
-      console.log(`  Encrypted Funds: ${Buffer.from(encryptedUserFunds).toString("hex").substring(0, 16)}...`);
+      console.log(`  Ciphertexts: ${encryptedPortfolio.length} x 32 bytes`);
       
       // Store for next tests
       encryptedData = {
         pub_key: Array.from(pub_key),
         nonce,
-        encryptedUserFunds: Array.from(encryptedUserFunds), // Single encrypted value
+        encryptedPortfolio,
       };
     });
 
     it("Step 4: Execute Confidential Rebalance with Arcium MXE", async () => {
       console.log("\n🔐 Executing confidential rebalance via Arcium MXE...");
       
-      const { pub_key, nonce, encryptedUserFunds } = encryptedData;
+      const { pub_key, encryptedPortfolio } = encryptedData;
       
       // Use a FIXED computation offset so the computation account address is deterministic
       // This allows us to hardcode the computation account that the MXE program expects
-      const computationOffset = new anchor.BN(1000000); // Fixed offset for testing
+      const computationOffset = COMPUTATION_OFFSET;
       
       // HARDCODED: Computation account for fixed offset 1000000 on cluster 1078779259
       // The Arcium MXE program expects this specific address (updated for new program ID)
       computationAccount = new PublicKey("5u19Tbi24U4WkZJzva2PtNtUm4JcY1zRvLUcicfsjvtH");
       
+      const [rebalanceResult] = PublicKey.findProgramAddressSync(
+        [Buffer.from("rebalance_result"), computationOffset.toArrayLike(Buffer, "le", 8)],
+        ARCIUM_MXE_PROGRAM_ID
+      );
+      
       console.log("  Computation Offset (FIXED):", computationOffset.toString());
       console.log("  Computation Account (HARDCODED):", computationAccount.toBase58());
-      console.log("  Encrypted Funds Size:", encryptedUserFunds.length, "bytes");
+      console.log("  Encrypted Portfolio Fields:", encryptedPortfolio.length);
       
       try {
         // Request more compute units and heap for the Arcium MXE computation queueing
@@ -1918,8 +2320,7 @@ describe("Multi-Asset Vault Tests", () => {
             CONFIDENTIAL_VAULT_NAME,
             computationOffset,
             pub_key,
-            nonce,
-            encryptedUserFunds  // Just one encrypted value now
+            encryptedPortfolio
           )
           .accounts({
             vault: confidentialVault,
@@ -1935,6 +2336,7 @@ describe("Multi-Asset Vault Tests", () => {
             poolAccount: poolAccount,
             clockAccount: clockAccount,
             arciumProgram: arciumProgram,
+            rebalanceResult: rebalanceResult,
             tokenProgram: TOKEN_PROGRAM_ID,
             systemProgram: SystemProgram.programId,
           })
@@ -1998,11 +2400,12 @@ describe("Multi-Asset Vault Tests", () => {
     it("Step 5: Verify Instruction Data Format", async () => {
       console.log("\n🔍 Verifying instruction data format...");
       
-      const { pub_key, nonce, encryptedUserFunds } = encryptedData;
+      const { pub_key, encryptedPortfolio } = encryptedData;
       
       // Manually construct instruction data to verify format
       const discriminator = [126, 197, 44, 141, 35, 123, 172, 126];
       const computationOffset = new anchor.BN(Date.now());
+      const nonce = computationNonce(confidentialVault, computationOffset);
       
       let instructionData: number[] = [];
       
@@ -2013,29 +2416,1726 @@ describe("Multi-Asset Vault Tests", () => {
       const offsetBytes = computationOffset.toArrayLike(Buffer, "le", 8);
       instructionData.push(...Array.from(offsetBytes));
       
-      // 3. pub_key: [u8; 32] (32 bytes)
+      // 3. vault: Pubkey (32 bytes), recorded in the RebalanceResult
+      instructionData.push(...confidentialVault.toBytes());
+      
+      // 4. pub_key: [u8; 32] (32 bytes)
       instructionData.push(...pub_key);
       
-      // 4. nonce: u128 (16 bytes, little-endian)
+      // 5. nonce: u128 (16 bytes, little-endian)
       const nonceBytes = nonce.toArrayLike(Buffer, "le", 16);
       instructionData.push(...Array.from(nonceBytes));
       
-      // 5. encrypted_user_funds: [u8; 32] (32 bytes)
-      instructionData.push(...encryptedUserFunds);
+      // 6. encrypted_portfolio: [[u8; 32]; 13] (416 bytes, no length prefix)
+      encryptedPortfolio.forEach((ciphertext) => instructionData.push(...ciphertext));
       
-      const expectedSize = 8 + 8 + 32 + 16 + 32; // discriminator + offset + pub_key + nonce + encrypted_funds
+      const expectedSize = 8 + 8 + 32 + 32 + 16 + 13 * 32; // discriminator + offset + vault + pub_key + nonce + encrypted_portfolio
       
       console.log("  Instruction Data Verification:");
       console.log(`    Discriminator: ${discriminator.length} bytes`);
       console.log(`    Computation Offset: 8 bytes`);
+      console.log(`    Vault: 32 bytes`);
       console.log(`    Public Key: 32 bytes`);
       console.log(`    Nonce: 16 bytes`);
-      console.log(`    Encrypted User Funds: 32 bytes (1 encrypted value)`);
+      console.log(`    Encrypted Portfolio: ${13 * 32} bytes (13 encrypted values)`);
       console.log(`    Total: ${instructionData.length} bytes`);
       console.log(`    Expected: ${expectedSize} bytes`);
       
       expect(instructionData.length).to.equal(expectedSize);
       console.log("  ✅ Instruction data format is correct!");
     });
+
+    it("Step 6: Refuses to Apply a Computation the Vault Did Not Queue", async () => {
+      // Nothing is pending at this offset, so there is no MXE result the vault may act on
+      const strayOffset = new anchor.BN(42);
+      const [strayResult] = PublicKey.findProgramAddressSync(
+        [Buffer.from("rebalance_result"), strayOffset.toArrayLike(Buffer, "le", 8)],
+        ARCIUM_MXE_PROGRAM_ID
+      );
+
+      try {
+        await (program.methods as any)
+          .applyConfidentialRebalance(CONFIDENTIAL_VAULT_NAME, strayOffset)
+          .accounts({
+            vault: confidentialVault,
+            authority: admin.publicKey,
+            rebalanceResult: strayResult,
+            rebalanceConfig: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([
+            { pubkey: mockOracle, isWritable: false, isSigner: false },
+            { pubkey: btcVaultAta, isWritable: true, isSigner: false },
+            { pubkey: ethVaultAta, isWritable: true, isSigner: false },
+            { pubkey: solVaultAta, isWritable: true, isSigner: false },
+          ])
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        expect.fail("apply_confidential_rebalance should need the pending computation's result");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidConfidentialResult");
+      }
+    });
+
+    const expectBusy = async (action: Promise<unknown>, what: string) => {
+      try {
+        await action;
+        expect.fail(`${what} should wait for the pending confidential rebalance`);
+      } catch (error: any) {
+        expect(error.message).to.include("VaultBusy");
+      }
+    };
+
+    it("Step 7: Holds the Operation Lock Until the Result Is Applied", async () => {
+      const vaultData: any = await program.account.vault.fetch(confidentialVault);
+      expect(vaultData.operation).to.equal(3); // VaultOperation::Rebalance
+      expect(vaultData.hasPendingComputation).to.equal(1);
+      expect(vaultData.pendingComputationOffset.toString()).to.equal(COMPUTATION_OFFSET.toString());
+    });
+
+    it("Step 8: Rejects Deposits While Locked", async () => {
+      await setPrices(program, confidentialBasket, 100_000, 3_500, 150);
+      await expectBusy(
+        depositSol(program, confidentialBasket, holder, 0.01 * anchor.web3.LAMPORTS_PER_SOL),
+        "deposit_multi_asset"
+      );
+    });
+
+    it("Step 9: Rejects Withdrawals While Locked", async () => {
+      const shares = await shareBalance(program, confidentialBasket, holder.publicKey);
+      await expectBusy(withdrawShares(program, confidentialBasket, holder, shares / BigInt(2)), "withdraw_multi_asset");
+      expect(await shareBalance(program, confidentialBasket, holder.publicKey)).to.equal(shares);
+    });
+
+    it("Step 10: Rejects a Plaintext Rebalance While Locked", async () => {
+      await expectBusy(
+        program.methods
+          .rebalance(CONFIDENTIAL_VAULT_NAME)
+          .accounts({
+            vault: confidentialVault,
+            authority: admin.publicKey,
+            btcQuote: PublicKey.default,
+            ethQuote: PublicKey.default,
+            solQuote: PublicKey.default,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          } as any)
+          .remainingAccounts(basketAccounts(confidentialBasket).slice(-2).concat(
+            confidentialBasket.atas.map((ata) => ({ pubkey: ata, isWritable: true, isSigner: false }))
+          ))
+          .signers([admin])
+          .rpc({ commitment: "confirmed" }),
+        "rebalance"
+      );
+    });
+
+    it("Step 11: Rejects Migrations Into the Locked Vault", async () => {
+      const shares = await shareBalance(program, feederBasket, holder.publicKey);
+      await expectBusy(migratePosition(program, feederBasket, confidentialBasket, holder, shares), "migrate_position");
+      expect(await shareBalance(program, feederBasket, holder.publicKey)).to.equal(shares);
+    });
+
+    it("Step 12: Does Not Let Anyone Release the Lock Before the Timeout", async () => {
+      const [rebalanceResult] = PublicKey.findProgramAddressSync(
+        [Buffer.from("rebalance_result"), COMPUTATION_OFFSET.toArrayLike(Buffer, "le", 8)],
+        ARCIUM_MXE_PROGRAM_ID
+      );
+      try {
+        await (program.methods as any)
+          .releaseStaleRebalance(CONFIDENTIAL_VAULT_NAME, COMPUTATION_OFFSET)
+          .accounts({ vault: confidentialVault, rebalanceResult })
+          .rpc({ commitment: "confirmed" });
+        expect.fail("release_stale_rebalance should wait out CONFIDENTIAL_REBALANCE_TIMEOUT_SECS");
+      } catch (error: any) {
+        expect(error.message).to.include("LockNotStale");
+      }
+    });
+
+    it("Step 13: Authority Clears the Lock and Deposits Resume", async () => {
+      await (program.methods as any)
+        .clearOperationLock(CONFIDENTIAL_VAULT_NAME)
+        .accounts({ vault: confidentialVault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const vaultData: any = await program.account.vault.fetch(confidentialVault);
+      expect(vaultData.operation).to.equal(0);
+      expect(vaultData.hasPendingComputation).to.equal(0);
+
+      const sharesBefore = await shareBalance(program, confidentialBasket, holder.publicKey);
+      await setPrices(program, confidentialBasket, 100_000, 3_500, 150);
+      await depositSol(program, confidentialBasket, holder, 0.01 * anchor.web3.LAMPORTS_PER_SOL);
+      expect(await shareBalance(program, confidentialBasket, holder.publicKey) > sharesBefore).to.equal(true);
+    });
+  });
+
+  describe("Guardian", () => {
+    const GUARDIAN_VAULT_NAME = `GuardianVault_${Date.now()}`;
+
+    let basket: Basket;
+    let guardian: Keypair;
+    let holder: Keypair;
+
+    before(async () => {
+      basket = await createBasket(program, admin, GUARDIAN_VAULT_NAME, [btcMint, ethMint, solMint], mockOracle);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+
+      guardian = Keypair.generate();
+      holder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, holder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      await (program.methods as any)
+        .setGuardian(GUARDIAN_VAULT_NAME, guardian.publicKey, true)
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("Pauses deposits and rebalancing without restricting the vault", async () => {
+      const shares = (await shareBalance(program, basket, holder.publicKey)) / BigInt(2);
+      const before = await previewWithdraw(program, basket, shares);
+
+      await (program.methods as any)
+        .guardianPause(GUARDIAN_VAULT_NAME, true)
+        .accounts({ vault: basket.vault, guardian: guardian.publicKey })
+        .signers([guardian])
+        .rpc({ commitment: "confirmed" });
+
+      const vaultData: any = await program.account.vault.fetch(basket.vault);
+      // The guardian pauses without tripping the circuit breaker
+      expect(vaultData.restricted).to.equal(0);
+      expect(vaultData.rebalancingPaused).to.equal(1);
+      expect(vaultData.guardianDepositsPaused).to.equal(1);
+
+      // Withdrawals are still priced at market, not conservatively
+      const during = await previewWithdraw(program, basket, shares);
+      expect(during.restricted).to.equal(false);
+      expect(during.solOut.toString()).to.equal(before.solOut.toString());
+    });
+
+    it("Rejects deposits while paused", async () => {
+      try {
+        await depositSol(program, basket, holder, 0.01 * anchor.web3.LAMPORTS_PER_SOL);
+        expect.fail("Deposit should be refused while the guardian pause is on");
+      } catch (error: any) {
+        expect(error.message).to.include("DepositsPaused");
+      }
+    });
+
+    it("Still lets holders withdraw while paused", async () => {
+      const sharesBefore = await shareBalance(program, basket, holder.publicKey);
+      const shares = sharesBefore / BigInt(2);
+      const preview = await previewWithdraw(program, basket, shares);
+      const solBefore = await provider.connection.getBalance(holder.publicKey);
+
+      await withdrawShares(program, basket, holder, shares);
+
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(sharesBefore - shares);
+      const solAfter = await provider.connection.getBalance(holder.publicKey);
+      // Holder pays the fee, so allow for it below the previewed payout
+      expect(solAfter - solBefore).to.be.greaterThan(preview.solOut.toNumber() - 10_000);
+    });
+
+    it("Does not let the guardian resume", async () => {
+      for (const method of ["resumeDeposits", "resumeRebalancing"]) {
+        try {
+          await (program.methods as any)
+            [method](GUARDIAN_VAULT_NAME)
+            .accounts({ vault: basket.vault, authority: guardian.publicKey })
+            .signers([guardian])
+            .rpc({ commitment: "confirmed" });
+          expect.fail(`${method} should need the governed authority`);
+        } catch (error: any) {
+          expect(error.message).to.include("Unauthorized");
+        }
+      }
+    });
+
+    it("Resumes deposits and rebalancing with the governed authority", async () => {
+      await (program.methods as any)
+        .resumeDeposits(GUARDIAN_VAULT_NAME)
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      await (program.methods as any)
+        .resumeRebalancing(GUARDIAN_VAULT_NAME)
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const vaultData: any = await program.account.vault.fetch(basket.vault);
+      expect(vaultData.restricted).to.equal(0);
+      expect(vaultData.rebalancingPaused).to.equal(0);
+      expect(vaultData.guardianDepositsPaused).to.equal(0);
+
+      const sharesBefore = await shareBalance(program, basket, holder.publicKey);
+      await depositSol(program, basket, holder, 0.01 * anchor.web3.LAMPORTS_PER_SOL);
+      expect(await shareBalance(program, basket, holder.publicKey) > sharesBefore).to.equal(true);
+    });
+  });
+
+  describe("Commit-Reveal Deposits", () => {
+    const REVEAL_VAULT_NAME = `RevealVault_${Date.now()}`;
+    // DEPOSIT_COMMITMENT_TTL_SLOTS
+    const COMMITMENT_TTL_SLOTS = 150;
+    const AMOUNT = new BN(0.05 * anchor.web3.LAMPORTS_PER_SOL);
+
+    let basket: Basket;
+    let depositor: Keypair;
+    // Salt of the depositor's open commitment
+    let openSalt: Buffer;
+
+    // DepositCommitment::hash
+    const commitmentHash = (user: PublicKey, amount: BN, salt: Buffer): number[] =>
+      Array.from(
+        crypto
+          .createHash("sha256")
+          .update(Buffer.from("etf-defi:deposit-commitment:v1"))
+          .update(basket.vault.toBuffer())
+          .update(user.toBuffer())
+          .update(amount.toArrayLike(Buffer, "le", 8))
+          .update(salt)
+          .digest()
+      );
+
+    const commitmentPda = (user: PublicKey): PublicKey =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("deposit_commitment"), basket.vault.toBuffer(), user.toBuffer()],
+        program.programId
+      )[0];
+
+    const ticketPda = (user: PublicKey): PublicKey =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("deposit_ticket"), basket.vault.toBuffer(), user.toBuffer()],
+        program.programId
+      )[0];
+
+    const commit = async (user: Keypair, amount: BN, salt: Buffer) =>
+      (program.methods as any)
+        .commitDeposit(REVEAL_VAULT_NAME, commitmentHash(user.publicKey, amount, salt))
+        .accounts({ vault: basket.vault, user: user.publicKey })
+        .signers([user])
+        .rpc({ commitment: "confirmed" });
+
+    const reveal = async (user: Keypair, amount: BN, salt: Buffer, minSharesOut: BN) =>
+      (program.methods as any)
+        .revealDeposit(REVEAL_VAULT_NAME, amount, Array.from(salt), minSharesOut)
+        .accounts({
+          vault: basket.vault,
+          user: user.publicKey,
+          ticket: ticketPda(user.publicKey),
+          userSharesAta: await getAssociatedTokenAddress(basket.shareMint, user.publicKey),
+          vaultTokenMint: basket.shareMint,
+          btcQuote: PublicKey.default,
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
+          depositCommitment: commitmentPda(user.publicKey),
+          clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .remainingAccounts(basketAccounts(basket))
+        .signers([user])
+        .rpc({ commitment: "confirmed" });
+
+    const expectRevealError = async (action: Promise<unknown>, code: string) => {
+      try {
+        await action;
+        expect.fail(`reveal_deposit should fail with ${code}`);
+      } catch (error: any) {
+        expect(error.message).to.include(code);
+      }
+    };
+
+    before(async () => {
+      basket = await createBasket(program, admin, REVEAL_VAULT_NAME, [btcMint, ethMint, solMint], mockOracle);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      // Seed the vault so reveals are priced against an existing NAV
+      const seeder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, seeder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+      depositor = await fundedUser(program, admin, 0.3);
+    });
+
+    it("Rejects a reveal that does not open the commitment", async () => {
+      const salt = crypto.randomBytes(32);
+      await commit(depositor, AMOUNT, salt);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+
+      await expectRevealError(reveal(depositor, AMOUNT.addn(1), salt, new BN(0)), "InvalidDepositCommitment");
+      await expectRevealError(reveal(depositor, AMOUNT, crypto.randomBytes(32), new BN(0)), "InvalidDepositCommitment");
+
+      // The commitment stays open and nothing was escrowed
+      const commitment: any = await (program.account as any).depositCommitment.fetch(commitmentPda(depositor.publicKey));
+      expect(commitment.commitment).to.deep.equal(commitmentHash(depositor.publicKey, AMOUNT, salt));
+      expect(await (program.account as any).depositTicket.fetchNullable(ticketPda(depositor.publicKey))).to.be.null;
+
+      await (program.methods as any)
+        .cancelDepositCommitment(REVEAL_VAULT_NAME)
+        .accounts({ vault: basket.vault, depositCommitment: commitmentPda(depositor.publicKey), user: depositor.publicKey })
+        .signers([depositor])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("Rejects a reveal that buys fewer shares than the minimum", async () => {
+      openSalt = crypto.randomBytes(32);
+      await commit(depositor, AMOUNT, openSalt);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+
+      await expectRevealError(
+        reveal(depositor, AMOUNT, openSalt, new BN("18446744073709551615")),
+        "SharesBelowMinimum"
+      );
+      expect(await (program.account as any).depositTicket.fetchNullable(ticketPda(depositor.publicKey))).to.be.null;
+    });
+
+    it("Reveals once, escrowing the amount at the reveal's prices", async () => {
+      const ticket = ticketPda(depositor.publicKey);
+      const balanceBefore = await provider.connection.getBalance(depositor.publicKey);
+
+      await reveal(depositor, AMOUNT, openSalt, new BN(1));
+
+      const ticketData: any = await (program.account as any).depositTicket.fetch(ticket);
+      expect(ticketData.amount.toString()).to.equal(AMOUNT.toString());
+      expect(ticketData.shares.toNumber()).to.be.greaterThan(0);
+      expect(ticketData.prices[2].priceUsd.toNumber()).to.equal(150 * 1_000_000);
+      // The amount left the depositor for the ticket, give or take account rent
+      const balanceAfter = await provider.connection.getBalance(depositor.publicKey);
+      expect(balanceBefore - balanceAfter).to.be.greaterThan(AMOUNT.toNumber() - 10_000_000);
+      expect(balanceBefore - balanceAfter).to.be.lessThan(AMOUNT.toNumber() + 10_000_000);
+      expect(await (program.account as any).depositCommitment.fetchNullable(commitmentPda(depositor.publicKey))).to.be.null;
+
+      // Refund the ticket, then replay the same reveal: the commitment is gone
+      await (program.methods as any)
+        .closeDepositTicket(REVEAL_VAULT_NAME)
+        .accounts({ vault: basket.vault, ticket, user: depositor.publicKey, closer: depositor.publicKey })
+        .signers([depositor])
+        .rpc({ commitment: "confirmed" });
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      await expectRevealError(reveal(depositor, AMOUNT, openSalt, new BN(1)), "AccountNotInitialized");
+      expect(await (program.account as any).depositTicket.fetchNullable(ticket)).to.be.null;
+    });
+
+    it("Rejects a reveal after DEPOSIT_COMMITMENT_TTL_SLOTS, leaving only cancel", async () => {
+      const salt = crypto.randomBytes(32);
+      await commit(depositor, AMOUNT, salt);
+      const commitment: any = await (program.account as any).depositCommitment.fetch(commitmentPda(depositor.publicKey));
+      const deadline = commitment.committedSlot.toNumber() + COMMITMENT_TTL_SLOTS;
+      while ((await provider.connection.getSlot("confirmed")) <= deadline) {
+        await new Promise((resolve) => setTimeout(resolve, 2_000));
+      }
+
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      await expectRevealError(reveal(depositor, AMOUNT, salt, new BN(1)), "DepositCommitmentExpired");
+      expect(await (program.account as any).depositTicket.fetchNullable(ticketPda(depositor.publicKey))).to.be.null;
+
+      await (program.methods as any)
+        .cancelDepositCommitment(REVEAL_VAULT_NAME)
+        .accounts({ vault: basket.vault, depositCommitment: commitmentPda(depositor.publicKey), user: depositor.publicKey })
+        .signers([depositor])
+        .rpc({ commitment: "confirmed" });
+      expect(await (program.account as any).depositCommitment.fetchNullable(commitmentPda(depositor.publicKey))).to.be.null;
+    });
+  });
+
+  describe("Migrating Positions to a Successor", () => {
+    let legacy: Basket;
+    let successor: Basket;
+    let migrant: Keypair;
+    let legacyHolder: Keypair;
+    let successorHolder: Keypair;
+
+    const refreshPrices = async () => {
+      await setPrices(program, legacy, 100_000, 3_500, 150);
+    };
+
+    before(async () => {
+      const stamp = Date.now();
+      legacy = await createBasket(program, admin, `Legacy_${stamp}`, [btcMint, ethMint, solMint], mockOracle);
+      successor = await createBasket(program, admin, `Successor_${stamp}`, [btcMint, ethMint, solMint], mockOracle);
+      await refreshPrices();
+
+      // The migrant holds a small slice of the legacy vault, so its idle SOL covers them
+      legacyHolder = await fundedUser(program, admin, 0.2);
+      migrant = await fundedUser(program, admin, 0.1);
+      successorHolder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, legacy, legacyHolder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+      await depositSol(program, legacy, migrant, 0.02 * anchor.web3.LAMPORTS_PER_SOL);
+      await depositSol(program, successor, successorHolder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      await (program.methods as any)
+        .suggestAlternative(legacy.name, true)
+        .accounts({ vault: legacy.vault, successorVault: successor.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("Moves the position at both NAVs without diluting either vault's holders", async () => {
+      const shares = await shareBalance(program, legacy, migrant.publicKey);
+      const legacyBefore = await previewWithdraw(program, legacy, BigInt(1_000_000));
+      const successorBefore = await previewWithdraw(program, successor, BigInt(1_000_000));
+      const migrantValue = await previewWithdraw(program, legacy, shares);
+      const legacySupply = (await getMint(provider.connection, legacy.shareMint)).supply;
+
+      await migratePosition(program, legacy, successor, migrant, shares);
+
+      // The shares are burned and successor shares minted
+      expect(await shareBalance(program, legacy, migrant.publicKey)).to.equal(BigInt(0));
+      expect((await getMint(provider.connection, legacy.shareMint)).supply).to.equal(legacySupply - shares);
+      const minted = await shareBalance(program, successor, migrant.publicKey);
+      expect(minted > BigInt(0)).to.equal(true);
+
+      // Neither vault's share price drops for the holders who stayed
+      await refreshPrices();
+      const legacyAfter = await previewWithdraw(program, legacy, BigInt(1_000_000));
+      const successorAfter = await previewWithdraw(program, successor, BigInt(1_000_000));
+      expect(legacyAfter.sharePrice.toNumber()).to.be.at.least(legacyBefore.sharePrice.toNumber());
+      expect(successorAfter.sharePrice.toNumber()).to.be.at.least(successorBefore.sharePrice.toNumber());
+      expect(legacyAfter.withdrawUsd.toNumber()).to.be.at.least(legacyBefore.withdrawUsd.toNumber());
+      expect(successorAfter.withdrawUsd.toNumber()).to.be.at.least(successorBefore.withdrawUsd.toNumber());
+
+      // The migrant keeps their value, less the successor's deposit fee and rounding
+      const successorVault: any = await program.account.vault.fetch(successor.vault);
+      const feeBps = successorVault.depositFeeBps;
+      const migrated = await previewWithdraw(program, successor, minted);
+      const expected = (migrantValue.withdrawUsd.toNumber() * (10_000 - feeBps)) / 10_000;
+      expect(migrated.withdrawUsd.toNumber()).to.be.within(expected * 0.99, migrantValue.withdrawUsd.toNumber());
+    });
+
+    it("Rejects a migration the legacy vault's idle SOL cannot cover", async () => {
+      await refreshPrices();
+      const shares = await shareBalance(program, legacy, legacyHolder.publicKey);
+      try {
+        await migratePosition(program, legacy, successor, legacyHolder, shares);
+        expect.fail("migrate_position should need the position's value in idle SOL");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientBalance");
+      }
+      expect(await shareBalance(program, legacy, legacyHolder.publicKey)).to.equal(shares);
+    });
+  });
+
+  describe("Soulbound Shares", () => {
+    let basket: Basket;
+    let holder: Keypair;
+
+    before(async () => {
+      basket = await createBasket(
+        program,
+        admin,
+        `Soulbound_${Date.now()}`,
+        [btcMint, ethMint, solMint],
+        mockOracle,
+        true
+      );
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      holder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, holder, 0.05 * anchor.web3.LAMPORTS_PER_SOL);
+    });
+
+    it("Issues shares from a non-transferable Token-2022 mint", async () => {
+      const mint = await getMint(provider.connection, basket.shareMint, "confirmed", TOKEN_2022_PROGRAM_ID);
+      expect(getExtensionTypes(mint.tlvData)).to.include(ExtensionType.NonTransferable);
+      expect(mint.mintAuthority!.toBase58()).to.equal(basket.vault.toBase58());
+      expect(mint.freezeAuthority).to.equal(null);
+      expect(mint.supply > BigInt(0)).to.equal(true);
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(mint.supply);
+
+      const vaultAccount: any = await program.account.vault.fetch(basket.vault);
+      expect(vaultAccount.soulbound).to.equal(1);
+    });
+
+    it("Rejects a transfer between holders", async () => {
+      const recipient = Keypair.generate();
+      const recipientAta = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        basket.shareMint,
+        recipient.publicKey,
+        false,
+        "confirmed",
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      try {
+        await transferChecked(
+          provider.connection,
+          holder,
+          shareAta(basket, holder.publicKey),
+          basket.shareMint,
+          recipientAta.address,
+          holder,
+          BigInt(1),
+          9,
+          [],
+          { commitment: "confirmed" },
+          TOKEN_2022_PROGRAM_ID
+        );
+        expect.fail("Token-2022 should reject moving non-transferable shares");
+      } catch (error: any) {
+        expect(error.message).to.not.include("Token-2022 should reject");
+      }
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(shares);
+      expect(await shareBalance(program, basket, recipient.publicKey)).to.equal(BigInt(0));
+    });
+
+    it("Still withdraws through the vault", async () => {
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      const supply = (await getMint(provider.connection, basket.shareMint, "confirmed", TOKEN_2022_PROGRAM_ID)).supply;
+      const lamportsBefore = await provider.connection.getBalance(holder.publicKey, "confirmed");
+
+      const burned = shares / BigInt(2);
+      await withdrawShares(program, basket, holder, burned);
+
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(shares - burned);
+      const after = await getMint(provider.connection, basket.shareMint, "confirmed", TOKEN_2022_PROGRAM_ID);
+      expect(after.supply).to.equal(supply - burned);
+      expect(await provider.connection.getBalance(holder.publicKey, "confirmed")).to.be.greaterThan(lamportsBefore);
+    });
+  });
+
+  describe("Share Staking", () => {
+    const STAKER_FEE_SHARE_BPS = 5_000;
+
+    let basket: Basket;
+    let staker: Keypair;
+    let stakePool: PublicKey;
+    let poolSharesAta: PublicKey;
+    let protocolConfig: PublicKey;
+
+    const stakePositionPda = (owner: PublicKey): PublicKey =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_position"), stakePool.toBuffer(), owner.toBuffer()],
+        program.programId
+      )[0];
+
+    const stake = async (owner: Keypair, shares: BN) =>
+      (program.methods as any)
+        .stakeShares(basket.name, shares)
+        .accounts({
+          vault: basket.vault,
+          stakePool,
+          stakePosition: stakePositionPda(owner.publicKey),
+          vaultTokenMint: basket.shareMint,
+          userSharesAta: shareAta(basket, owner.publicKey),
+          poolSharesAta,
+          owner: owner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+
+    const unstake = async (owner: Keypair, shares: BN) =>
+      (program.methods as any)
+        .unstakeShares(basket.name, shares)
+        .accounts({
+          vault: basket.vault,
+          stakePool,
+          stakePosition: stakePositionPda(owner.publicKey),
+          vaultTokenMint: basket.shareMint,
+          userSharesAta: shareAta(basket, owner.publicKey),
+          poolSharesAta,
+          owner: owner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+
+    const claim = async (owner: Keypair) =>
+      (program.methods as any)
+        .claimStakingRewards(basket.name)
+        .accounts({
+          vault: basket.vault,
+          stakePool,
+          stakePosition: stakePositionPda(owner.publicKey),
+          owner: owner.publicKey,
+        })
+        .signers([owner])
+        .rpc({ commitment: "confirmed" });
+
+    const distribute = async () =>
+      (program.methods as any)
+        .distributeStakingRewards(basket.name)
+        .accounts({ vault: basket.vault, protocolConfig, stakePool })
+        .rpc({ commitment: "confirmed" });
+
+    const shareBalanceOf = async (ata: PublicKey): Promise<bigint> =>
+      (await getAccount(provider.connection, ata, "confirmed")).amount;
+
+    before(async () => {
+      basket = await createBasket(program, admin, `Staking_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+
+      await (program.methods as any)
+        .setDepositFee(basket.name, 100, 0)
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      await (program.methods as any)
+        .setStakerFeeShare(basket.name, STAKER_FEE_SHARE_BPS)
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      protocolConfig = await ensureProtocolConfig(program, admin);
+
+      stakePool = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_pool"), basket.vault.toBuffer()],
+        program.programId
+      )[0];
+      poolSharesAta = await getAssociatedTokenAddress(basket.shareMint, stakePool, true);
+      await (program.methods as any)
+        .initializeStakePool(basket.name)
+        .accounts({
+          vault: basket.vault,
+          stakePool,
+          vaultTokenMint: basket.shareMint,
+          poolSharesAta,
+          payer: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      staker = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, staker, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+    });
+
+    it("Rejects distributing rewards before anyone stakes", async () => {
+      try {
+        await distribute();
+        expect.fail("distribute_staking_rewards should need staked shares");
+      } catch (error: any) {
+        expect(error.message).to.include("NoStakers");
+      }
+    });
+
+    it("Moves staked shares into the pool", async () => {
+      const shares = await shareBalance(program, basket, staker.publicKey);
+      const staked = shares / BigInt(2);
+
+      await stake(staker, new BN(staked.toString()));
+
+      expect(await shareBalance(program, basket, staker.publicKey)).to.equal(shares - staked);
+      expect(await shareBalanceOf(poolSharesAta)).to.equal(staked);
+      const pool: any = await program.account.stakePool.fetch(stakePool);
+      expect(pool.totalStaked.toString()).to.equal(staked.toString());
+      const position: any = await program.account.stakePosition.fetch(stakePositionPda(staker.publicKey));
+      expect(position.owner.toBase58()).to.equal(staker.publicKey.toBase58());
+      expect(position.shares.toString()).to.equal(staked.toString());
+    });
+
+    it("Rejects unstaking more than the position holds", async () => {
+      const position: any = await program.account.stakePosition.fetch(stakePositionPda(staker.publicKey));
+      try {
+        await unstake(staker, position.shares.addn(1));
+        expect.fail("unstake_shares should be capped at the staked shares");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientShares");
+      }
+      expect(await shareBalanceOf(poolSharesAta)).to.equal(BigInt(position.shares.toString()));
+    });
+
+    it("Rejects a claim with nothing accrued", async () => {
+      try {
+        await claim(staker);
+        expect.fail("claim_staking_rewards should need accrued rewards");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidAmount");
+      }
+    });
+
+    it("Pays the stakers' cut of new deposit fees to the staker", async () => {
+      // A second depositor's fee is what the stakers share
+      const depositor = await fundedUser(program, admin, 0.2);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      await depositSol(program, basket, depositor, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      const vaultBefore: any = await program.account.vault.fetch(basket.vault);
+      const expected = vaultBefore.stakerFeesOwed.add(
+        vaultBefore.pendingFees.muln(STAKER_FEE_SHARE_BPS).divn(10_000)
+      );
+      expect(expected.gtn(0)).to.equal(true);
+      const poolLamportsBefore = await provider.connection.getBalance(stakePool, "confirmed");
+
+      await distribute();
+
+      const distributed = (await provider.connection.getBalance(stakePool, "confirmed")) - poolLamportsBefore;
+      expect(distributed).to.equal(expected.toNumber());
+      const vaultAfter: any = await program.account.vault.fetch(basket.vault);
+      expect(vaultAfter.stakerFeesOwed.toNumber()).to.equal(0);
+      expect(vaultAfter.pendingFees.toNumber()).to.equal(0);
+      const pool: any = await program.account.stakePool.fetch(stakePool);
+      expect(pool.totalDistributed.toNumber()).to.equal(distributed);
+
+      // The only staker gets all of it, less fixed-point rounding
+      const stakerBefore = await provider.connection.getBalance(staker.publicKey, "confirmed");
+      await claim(staker);
+      const claimed = (await provider.connection.getBalance(staker.publicKey, "confirmed")) - stakerBefore;
+      expect(claimed).to.be.within(distributed - 2, distributed);
+      const position: any = await program.account.stakePosition.fetch(stakePositionPda(staker.publicKey));
+      expect(position.unclaimed.toNumber()).to.equal(0);
+    });
+
+    it("Returns the shares on unstake", async () => {
+      const position: any = await program.account.stakePosition.fetch(stakePositionPda(staker.publicKey));
+      const before = await shareBalance(program, basket, staker.publicKey);
+
+      await unstake(staker, position.shares);
+
+      expect(await shareBalance(program, basket, staker.publicKey)).to.equal(
+        before + BigInt(position.shares.toString())
+      );
+      expect(await shareBalanceOf(poolSharesAta)).to.equal(BigInt(0));
+      const pool: any = await program.account.stakePool.fetch(stakePool);
+      expect(pool.totalStaked.toNumber()).to.equal(0);
+      const after: any = await program.account.stakePosition.fetch(stakePositionPda(staker.publicKey));
+      expect(after.shares.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("DCA Schedules", () => {
+    const AMOUNT_PER_PERIOD = new BN(0.02 * anchor.web3.LAMPORTS_PER_SOL);
+    const FUNDED = new BN(0.05 * anchor.web3.LAMPORTS_PER_SOL);
+    // MIN_DCA_INTERVAL_SECS
+    const INTERVAL_SECS = 60;
+
+    let basket: Basket;
+    let user: Keypair;
+    let schedule: PublicKey;
+
+    const userPosition = (owner: PublicKey): PublicKey =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("user_position"), basket.vault.toBuffer(), owner.toBuffer()],
+        program.programId
+      )[0];
+
+    const createSchedule = async (intervalSecs: number) =>
+      (program.methods as any)
+        .createDcaSchedule(basket.name, AMOUNT_PER_PERIOD, new BN(intervalSecs), new BN(0))
+        .accounts({
+          vault: basket.vault,
+          schedule,
+          user: user.publicKey,
+          fundingMint: null,
+          userSharesAta: shareAta(basket, user.publicKey),
+          userPosition: userPosition(user.publicKey),
+          vaultTokenMint: basket.shareMint,
+          shareTokenProgram: basket.shareTokenProgram,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc({ commitment: "confirmed" });
+
+    const crank = async () =>
+      (program.methods as any)
+        .executeDca(basket.name)
+        .accounts({
+          vault: basket.vault,
+          schedule,
+          user: user.publicKey,
+          cranker: admin.publicKey,
+          userSharesAta: shareAta(basket, user.publicKey),
+          userPosition: userPosition(user.publicKey),
+          vaultTokenMint: basket.shareMint,
+          scheduleFundingAccount: null,
+          vaultInputAccount: null,
+          btcQuote: PublicKey.default,
+          ethQuote: PublicKey.default,
+          solQuote: PublicKey.default,
+          clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+          shareTokenProgram: basket.shareTokenProgram,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .remainingAccounts(basketAccounts(basket))
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      basket = await createBasket(program, admin, `Dca_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      // Seed the vault so executions are priced against an existing NAV
+      const seeder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, seeder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      user = await fundedUser(program, admin, 0.2);
+      schedule = PublicKey.findProgramAddressSync(
+        [Buffer.from("dca"), basket.vault.toBuffer(), user.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    });
+
+    it("Rejects an interval shorter than the minimum", async () => {
+      try {
+        await createSchedule(INTERVAL_SECS - 1);
+        expect.fail("create_dca_schedule should enforce MIN_DCA_INTERVAL_SECS");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidDcaSchedule");
+      }
+      expect(await provider.connection.getAccountInfo(schedule, "confirmed")).to.equal(null);
+    });
+
+    it("Creates and funds a SOL schedule", async () => {
+      await createSchedule(INTERVAL_SECS);
+
+      const created: any = await program.account.dcaSchedule.fetch(schedule);
+      expect(created.user.toBase58()).to.equal(user.publicKey.toBase58());
+      expect(created.fundingMint).to.equal(null);
+      expect(created.amountPerPeriod.toString()).to.equal(AMOUNT_PER_PERIOD.toString());
+      expect(created.remaining.toNumber()).to.equal(0);
+      expect(await shareBalance(program, basket, user.publicKey)).to.equal(BigInt(0));
+
+      const lamportsBefore = await provider.connection.getBalance(schedule, "confirmed");
+      await (program.methods as any)
+        .fundDcaSchedule(basket.name, FUNDED)
+        .accounts({
+          vault: basket.vault,
+          schedule,
+          user: user.publicKey,
+          userFundingAccount: null,
+          scheduleFundingAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc({ commitment: "confirmed" });
+
+      const funded: any = await program.account.dcaSchedule.fetch(schedule);
+      expect(funded.remaining.toString()).to.equal(FUNDED.toString());
+      expect((await provider.connection.getBalance(schedule, "confirmed")) - lamportsBefore).to.equal(
+        FUNDED.toNumber()
+      );
+    });
+
+    it("Deposits one period into the vault when cranked", async () => {
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      const before: any = await program.account.dcaSchedule.fetch(schedule);
+      const scheduleLamports = await provider.connection.getBalance(schedule, "confirmed");
+      const supply = (await getMint(provider.connection, basket.shareMint, "confirmed")).supply;
+
+      await crank();
+
+      const shares = await shareBalance(program, basket, user.publicKey);
+      expect(shares > BigInt(0)).to.equal(true);
+      expect((await getMint(provider.connection, basket.shareMint, "confirmed")).supply).to.equal(supply + shares);
+      const position: any = await program.account.userPosition.fetch(userPosition(user.publicKey));
+      expect(position.shares.toString()).to.equal(shares.toString());
+
+      const after: any = await program.account.dcaSchedule.fetch(schedule);
+      expect(after.remaining.toString()).to.equal(FUNDED.sub(AMOUNT_PER_PERIOD).toString());
+      expect(after.executions).to.equal(1);
+      expect(after.nextExecutionTs.toNumber()).to.be.at.least(before.nextExecutionTs.toNumber() + INTERVAL_SECS);
+      expect(scheduleLamports - (await provider.connection.getBalance(schedule, "confirmed"))).to.equal(
+        AMOUNT_PER_PERIOD.toNumber()
+      );
+    });
+
+    it("Rejects a crank before the next period is due", async () => {
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      const shares = await shareBalance(program, basket, user.publicKey);
+      try {
+        await crank();
+        expect.fail("execute_dca should wait for the next period");
+      } catch (error: any) {
+        expect(error.message).to.include("DcaNotDue");
+      }
+      expect(await shareBalance(program, basket, user.publicKey)).to.equal(shares);
+      const unchanged: any = await program.account.dcaSchedule.fetch(schedule);
+      expect(unchanged.executions).to.equal(1);
+    });
+
+    it("Refunds the undeposited balance on cancel", async () => {
+      const scheduleLamports = await provider.connection.getBalance(schedule, "confirmed");
+      const userBefore = await provider.connection.getBalance(user.publicKey, "confirmed");
+
+      await (program.methods as any)
+        .cancelDcaSchedule(basket.name)
+        .accounts({
+          vault: basket.vault,
+          schedule,
+          user: user.publicKey,
+          userFundingAccount: null,
+          scheduleFundingAccount: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc({ commitment: "confirmed" });
+
+      // The remaining funds and the schedule's rent both go back to the user
+      expect((await provider.connection.getBalance(user.publicKey, "confirmed")) - userBefore).to.equal(
+        scheduleLamports
+      );
+      expect(await provider.connection.getAccountInfo(schedule, "confirmed")).to.equal(null);
+    });
+  });
+
+  describe("Creation Units", () => {
+    let basket: Basket;
+    let participant: Keypair;
+    let participantAccounts: PublicKey[];
+    let authorizedParticipant: PublicKey;
+    let unitShares: bigint;
+    // Basket slots paid in tokens rather than native SOL
+    let tokenSlots: number[];
+
+    const holdings = async (): Promise<bigint[]> => {
+      const { amounts, nativeSol } = await inKindHoldings(program, basket);
+      tokenSlots = nativeSol ? [0, 1] : [0, 1, 2];
+      return amounts;
+    };
+
+    const participantBalances = async (): Promise<bigint[]> =>
+      Promise.all(
+        participantAccounts.map(async (account) => (await getAccount(provider.connection, account, "confirmed")).amount)
+      );
+
+    const supply = async (): Promise<bigint> =>
+      (await getMint(provider.connection, basket.shareMint, "confirmed")).supply;
+
+    const inKindAccounts = () =>
+      basket.mints.flatMap((mint, i) => [
+        { pubkey: participantAccounts[i], isWritable: true, isSigner: false },
+        { pubkey: basket.atas[i], isWritable: true, isSigner: false },
+        { pubkey: mint, isWritable: false, isSigner: false },
+      ]);
+
+    const unitsAccounts = () => ({
+      vault: basket.vault,
+      authorizedParticipant,
+      participant: participant.publicKey,
+      participantSharesAta: shareAta(basket, participant.publicKey),
+      vaultTokenMint: basket.shareMint,
+      shareTokenProgram: basket.shareTokenProgram,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      token2022Program: null,
+      rent: SYSVAR_RENT_PUBKEY,
+    });
+
+    const createUnits = async (units: number) =>
+      (program.methods as any)
+        .createUnits(basket.name, new BN(units))
+        .accounts({
+          ...unitsAccounts(),
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(inKindAccounts())
+        .signers([participant])
+        .rpc({ commitment: "confirmed" });
+
+    const redeemUnits = async (units: number) =>
+      (program.methods as any)
+        .redeemUnits(basket.name, new BN(units))
+        .accounts(unitsAccounts())
+        .remainingAccounts(inKindAccounts())
+        .signers([participant])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      basket = await createBasket(program, admin, `Units_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      const seeder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, seeder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      // One unit is a tenth of the supply, so the participant needs at most
+      // a tenth of each holding; give it the whole holding
+      unitShares = (await supply()) / BigInt(10);
+      await (program.methods as any)
+        .setCreationUnit(basket.name, new BN(unitShares.toString()))
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      participant = await fundedUser(program, admin, 0.3);
+      const held = await holdings();
+      participantAccounts = [];
+      for (const [i, mint] of basket.mints.entries()) {
+        const account = await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          admin,
+          mint,
+          participant.publicKey,
+          false,
+          "confirmed"
+        );
+        participantAccounts.push(account.address);
+        if (held[i] > BigInt(0)) {
+          await mintTo(provider.connection, admin, mint, account.address, admin, held[i], [], {
+            commitment: "confirmed",
+          });
+        }
+      }
+
+      authorizedParticipant = PublicKey.findProgramAddressSync(
+        [Buffer.from("authorized_participant"), basket.vault.toBuffer(), participant.publicKey.toBuffer()],
+        program.programId
+      )[0];
+    });
+
+    it("Rejects a participant the admin has not authorized", async () => {
+      const holdingsBefore = await holdings();
+      try {
+        await createUnits(1);
+        expect.fail("create_units should need an AuthorizedParticipant record");
+      } catch (error: any) {
+        expect(error.message).to.include("AccountNotInitialized");
+      }
+      expect(await holdings()).to.deep.equal(holdingsBefore);
+    });
+
+    it("Creates a unit from the basket delivered in kind", async () => {
+      await (program.methods as any)
+        .addAuthorizedParticipant(basket.name, participant.publicKey)
+        .accounts({
+          vault: basket.vault,
+          authorizedParticipant,
+          authority: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const supplyBefore = await supply();
+      const holdingsBefore = await holdings();
+      const balancesBefore = await participantBalances();
+      const priceBefore = await previewWithdraw(program, basket, BigInt(1_000_000));
+
+      await createUnits(1);
+
+      // Each asset is taken pro rata to the holdings, rounded up
+      const expected = holdingsBefore.map(
+        (held) => (held * unitShares + supplyBefore - BigInt(1)) / supplyBefore
+      );
+      const holdingsAfter = await holdings();
+      holdingsAfter.forEach((held, i) => expect(held - holdingsBefore[i]).to.equal(expected[i]));
+      const balancesAfter = await participantBalances();
+      tokenSlots.forEach((i) => expect(balancesBefore[i] - balancesAfter[i]).to.equal(expected[i]));
+
+      expect(await shareBalance(program, basket, participant.publicKey)).to.equal(unitShares);
+      expect(await supply()).to.equal(supplyBefore + unitShares);
+      const record: any = await program.account.authorizedParticipant.fetch(authorizedParticipant);
+      expect(record.unitsCreated.toNumber()).to.equal(1);
+
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      const priceAfter = await previewWithdraw(program, basket, BigInt(1_000_000));
+      expect(priceAfter.sharePrice.toNumber()).to.be.at.least(priceBefore.sharePrice.toNumber());
+    });
+
+    it("Rejects redeeming more units than the participant holds", async () => {
+      const shares = await shareBalance(program, basket, participant.publicKey);
+      try {
+        await redeemUnits(2);
+        expect.fail("redeem_units should need the units' shares");
+      } catch (error: any) {
+        expect(error.message).to.include("InsufficientShares");
+      }
+      expect(await shareBalance(program, basket, participant.publicKey)).to.equal(shares);
+    });
+
+    it("Redeems a unit for its slice of the basket in kind", async () => {
+      const supplyBefore = await supply();
+      const holdingsBefore = await holdings();
+      const balancesBefore = await participantBalances();
+
+      await redeemUnits(1);
+
+      // Each asset is paid pro rata to the holdings, rounded down
+      const expected = holdingsBefore.map((held) => (held * unitShares) / supplyBefore);
+      const holdingsAfter = await holdings();
+      holdingsAfter.forEach((held, i) => expect(holdingsBefore[i] - held).to.equal(expected[i]));
+      const balancesAfter = await participantBalances();
+      tokenSlots.forEach((i) => expect(balancesAfter[i] - balancesBefore[i]).to.equal(expected[i]));
+
+      expect(await shareBalance(program, basket, participant.publicKey)).to.equal(BigInt(0));
+      expect(await supply()).to.equal(supplyBefore - unitShares);
+      const record: any = await program.account.authorizedParticipant.fetch(authorizedParticipant);
+      expect(record.unitsRedeemed.toNumber()).to.equal(1);
+    });
+  });
+
+  describe("Winding Down a Vault", () => {
+    // MIN_WIND_DOWN_GRACE_SECS
+    const GRACE_SECS = 7 * 24 * 60 * 60;
+
+    let basket: Basket;
+    let holder: Keypair;
+    let holderAccounts: PublicKey[];
+    let vaultRegistration: PublicKey;
+
+    const beginWindDown = async (graceSecs: number) =>
+      (program.methods as any)
+        .beginWindDown(basket.name, new BN(graceSecs))
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const supply = async (): Promise<bigint> =>
+      (await getMint(provider.connection, basket.shareMint, "confirmed")).supply;
+
+    before(async () => {
+      basket = await createBasket(program, admin, `WindDown_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      holder = await fundedUser(program, admin, 0.2);
+      await depositSol(program, basket, holder, 0.1 * anchor.web3.LAMPORTS_PER_SOL);
+
+      holderAccounts = [];
+      for (const mint of basket.mints) {
+        const account = await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          admin,
+          mint,
+          holder.publicKey,
+          false,
+          "confirmed"
+        );
+        holderAccounts.push(account.address);
+      }
+      vaultRegistration = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_registration"), basket.vault.toBuffer()],
+        program.programId
+      )[0];
+    });
+
+    it("Rejects a grace period shorter than the minimum", async () => {
+      try {
+        await beginWindDown(GRACE_SECS - 1);
+        expect.fail("begin_wind_down should enforce MIN_WIND_DOWN_GRACE_SECS");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidGracePeriod");
+      }
+      const vaultAccount: any = await program.account.vault.fetch(basket.vault);
+      expect(vaultAccount.windDownDeadline.toNumber()).to.equal(0);
+    });
+
+    it("Freezes deposits until the deadline", async () => {
+      const started = Math.floor(Date.now() / 1000);
+      await beginWindDown(GRACE_SECS);
+
+      const vaultAccount: any = await program.account.vault.fetch(basket.vault);
+      expect(vaultAccount.softClosed).to.equal(1);
+      // Validator clocks drift from the host's; allow a few minutes either way
+      expect(vaultAccount.windDownDeadline.toNumber()).to.be.within(
+        started + GRACE_SECS - 300,
+        started + GRACE_SECS + 300
+      );
+
+      const depositor = await fundedUser(program, admin, 0.1);
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      try {
+        await depositSol(program, basket, depositor, 0.01 * anchor.web3.LAMPORTS_PER_SOL);
+        expect.fail("a winding-down vault should refuse deposits");
+      } catch (error: any) {
+        expect(error.message).to.include("VaultSoftClosed");
+      }
+
+      try {
+        await beginWindDown(GRACE_SECS);
+        expect.fail("begin_wind_down should only run once");
+      } catch (error: any) {
+        expect(error.message).to.include("VaultWindingDown");
+      }
+    });
+
+    it("Redeems shares for their slice of every holding", async () => {
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      const redeemed = shares / BigInt(2);
+      const supplyBefore = await supply();
+      const before = await inKindHoldings(program, basket);
+      const balancesBefore = await Promise.all(
+        holderAccounts.map(async (account) => (await getAccount(provider.connection, account, "confirmed")).amount)
+      );
+
+      await (program.methods as any)
+        .redeemWindDown(basket.name, new BN(redeemed.toString()))
+        .accounts({
+          vault: basket.vault,
+          user: holder.publicKey,
+          userSharesAta: shareAta(basket, holder.publicKey),
+          vaultTokenMint: basket.shareMint,
+          shareTokenProgram: basket.shareTokenProgram,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: null,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .remainingAccounts(
+          basket.mints.flatMap((mint, i) => [
+            { pubkey: holderAccounts[i], isWritable: true, isSigner: false },
+            { pubkey: basket.atas[i], isWritable: true, isSigner: false },
+            { pubkey: mint, isWritable: false, isSigner: false },
+          ])
+        )
+        .signers([holder])
+        .rpc({ commitment: "confirmed" });
+
+      // Each holding is paid pro rata to the shares, rounded down
+      const expected = before.amounts.map((held) => (held * redeemed) / supplyBefore);
+      const after = await inKindHoldings(program, basket);
+      after.amounts.forEach((held, i) => expect(before.amounts[i] - held).to.equal(expected[i]));
+      const balancesAfter = await Promise.all(
+        holderAccounts.map(async (account) => (await getAccount(provider.connection, account, "confirmed")).amount)
+      );
+      const tokenSlots = before.nativeSol ? [0, 1] : [0, 1, 2];
+      tokenSlots.forEach((i) => expect(balancesAfter[i] - balancesBefore[i]).to.equal(expected[i]));
+
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(shares - redeemed);
+      expect(await supply()).to.equal(supplyBefore - redeemed);
+    });
+
+    it("Keeps the vault open while the grace period runs and shares are outstanding", async () => {
+      try {
+        await (program.methods as any)
+          .closeVault(basket.name)
+          .accounts({
+            vault: basket.vault,
+            admin: admin.publicKey,
+            vaultTokenMint: basket.shareMint,
+            vaultRegistration,
+            authority: admin.publicKey,
+            shareTokenProgram: basket.shareTokenProgram,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            token2022Program: null,
+          })
+          .remainingAccounts(
+            basket.mints.flatMap((mint, i) => [
+              { pubkey: basket.atas[i], isWritable: true, isSigner: false },
+              { pubkey: holderAccounts[i], isWritable: true, isSigner: false },
+              { pubkey: mint, isWritable: false, isSigner: false },
+            ])
+          )
+          .signers([admin])
+          .rpc({ commitment: "confirmed" });
+        expect.fail("close_vault should wait for the deadline and an empty supply");
+      } catch (error: any) {
+        expect(error.message).to.include("WindDownIncomplete");
+      }
+      expect(await provider.connection.getAccountInfo(basket.vault, "confirmed")).to.not.equal(null);
+    });
+  });
+
+  describe("Sweeping Stray Tokens", () => {
+    const STRAY_AMOUNT = BigInt(1_000_000);
+
+    let basket: Basket;
+    let protocolConfig: PublicKey;
+    let strayMint: PublicKey;
+    let vaultStrayAccount: PublicKey;
+    let treasuryStrayAccount: PublicKey;
+
+    const sweep = async (mint: PublicKey, vaultTokenAccount: PublicKey, treasuryTokenAccount: PublicKey) =>
+      (program.methods as any)
+        .sweepUnknownToken(basket.name)
+        .accounts({
+          vault: basket.vault,
+          protocolConfig,
+          mint,
+          vaultTokenAccount,
+          treasuryTokenAccount,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const tokenBalance = async (account: PublicKey): Promise<bigint> =>
+      (await getAccount(provider.connection, account, "confirmed")).amount;
+
+    before(async () => {
+      basket = await createBasket(program, admin, `Sweep_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      protocolConfig = await ensureProtocolConfig(program, admin);
+      const { treasury } = await program.account.protocolConfig.fetch(protocolConfig);
+
+      // An airdrop of a token outside the composition
+      strayMint = await createMint(provider.connection, admin, admin.publicKey, null, 6);
+      vaultStrayAccount = (
+        await getOrCreateAssociatedTokenAccount(
+          provider.connection,
+          admin,
+          strayMint,
+          basket.vault,
+          true,
+          "confirmed"
+        )
+      ).address;
+      treasuryStrayAccount = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, admin, strayMint, treasury, true, "confirmed")
+      ).address;
+      await mintTo(provider.connection, admin, strayMint, vaultStrayAccount, admin, STRAY_AMOUNT, [], {
+        commitment: "confirmed",
+      });
+    });
+
+    it("Rejects sweeping a composition asset", async () => {
+      const { treasury } = await program.account.protocolConfig.fetch(protocolConfig);
+      const treasuryBtc = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        btcMint,
+        treasury,
+        true,
+        "confirmed"
+      );
+      try {
+        await sweep(btcMint, basket.atas[0], treasuryBtc.address);
+        expect.fail("sweep_unknown_token should never move basket assets");
+      } catch (error: any) {
+        expect(error.message).to.include("CompositionAssetSweep");
+      }
+    });
+
+    it("Sends the whole stray balance to the treasury", async () => {
+      const treasuryBefore = await tokenBalance(treasuryStrayAccount);
+
+      await sweep(strayMint, vaultStrayAccount, treasuryStrayAccount);
+
+      expect(await tokenBalance(vaultStrayAccount)).to.equal(BigInt(0));
+      expect((await tokenBalance(treasuryStrayAccount)) - treasuryBefore).to.equal(STRAY_AMOUNT);
+    });
+
+    it("Rejects sweeping an empty account", async () => {
+      try {
+        await sweep(strayMint, vaultStrayAccount, treasuryStrayAccount);
+        expect.fail("sweep_unknown_token should need a balance");
+      } catch (error: any) {
+        expect(error.message).to.include("InvalidAmount");
+      }
+    });
+  });
+
+  describe("Closing Unused Asset ATAs", () => {
+    let basket: Basket;
+    let emptyAccount: PublicKey;
+    let fundedAccount: PublicKey;
+
+    const closeAta = async (vaultTokenAccount: PublicKey) =>
+      (program.methods as any)
+        .closeAssetAta(basket.name)
+        .accounts({
+          vault: basket.vault,
+          vaultTokenAccount,
+          admin: admin.publicKey,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          token2022Program: null,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const expectInUse = async (vaultTokenAccount: PublicKey) => {
+      try {
+        await closeAta(vaultTokenAccount);
+        expect.fail("close_asset_ata should only close empty non-composition accounts");
+      } catch (error: any) {
+        expect(error.message).to.include("AssetAccountInUse");
+      }
+      expect(await provider.connection.getAccountInfo(vaultTokenAccount, "confirmed")).to.not.equal(null);
+    };
+
+    // Vault ATA for a fresh mint outside the composition
+    const strayAccount = async (): Promise<{ mint: PublicKey; account: PublicKey }> => {
+      const mint = await createMint(provider.connection, admin, admin.publicKey, null, 6);
+      const account = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        basket.vault,
+        true,
+        "confirmed"
+      );
+      return { mint, account: account.address };
+    };
+
+    before(async () => {
+      basket = await createBasket(program, admin, `CloseAta_${Date.now()}`, [btcMint, ethMint, solMint], mockOracle);
+      emptyAccount = (await strayAccount()).account;
+      const funded = await strayAccount();
+      fundedAccount = funded.account;
+      await mintTo(provider.connection, admin, funded.mint, fundedAccount, admin, BigInt(1), [], {
+        commitment: "confirmed",
+      });
+    });
+
+    it("Rejects closing a composition asset's ATA", async () => {
+      await expectInUse(basket.atas[0]);
+    });
+
+    it("Rejects closing an account that still holds tokens", async () => {
+      await expectInUse(fundedAccount);
+      expect((await getAccount(provider.connection, fundedAccount, "confirmed")).amount).to.equal(BigInt(1));
+    });
+
+    it("Closes an empty stray ATA and returns its rent to the admin", async () => {
+      const rent = await provider.connection.getBalance(emptyAccount, "confirmed");
+      const adminBefore = await provider.connection.getBalance(admin.publicKey, "confirmed");
+
+      const sig = await closeAta(emptyAccount);
+
+      // The admin may also be the wallet paying the transaction fee
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const fee = provider.publicKey!.equals(admin.publicKey) ? tx!.meta!.fee : 0;
+      const adminAfter = await provider.connection.getBalance(admin.publicKey, "confirmed");
+      expect(adminAfter - adminBefore).to.equal(rent - fee);
+      expect(await provider.connection.getAccountInfo(emptyAccount, "confirmed")).to.equal(null);
+    });
+  });
+
+  describe("Vault Templates", () => {
+    const stamp = Date.now();
+    const TEMPLATE_NAME = `BlueChip_${stamp}`;
+    const VAULT_NAME = `FromTemplate_${stamp}`;
+
+    let protocolConfig: PublicKey;
+    let vaultTemplate: PublicKey;
+    let preset: any;
+
+    const setTemplate = async (authority: Keypair) =>
+      (program.methods as any)
+        .setVaultTemplate(TEMPLATE_NAME, preset)
+        .accounts({
+          protocolConfig,
+          vaultTemplate,
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      protocolConfig = await ensureProtocolConfig(program, admin);
+      vaultTemplate = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_template"), Buffer.from(TEMPLATE_NAME)],
+        program.programId
+      )[0];
+      preset = {
+        assets: [
+          { mint: btcMint, weight: 40 },
+          { mint: ethMint, weight: 30 },
+          { mint: solMint, weight: 30 },
+        ],
+        priceSource: { mockOracle: {} },
+        mockOracle,
+        twapWindowSecs: 0,
+        priceFeeds: [],
+        maxDivergenceBps: 0,
+        swapBackend: { mock: {} },
+        maxSlippageBps: 300,
+      };
+    });
+
+    it("Rejects a template from anyone but the config authority", async () => {
+      const outsider = await fundedUser(program, admin, 0.05);
+      try {
+        await setTemplate(outsider);
+        expect.fail("set_vault_template should be limited to the config authority");
+      } catch (error: any) {
+        expect(error.message).to.include("Unauthorized");
+      }
+      expect(await provider.connection.getAccountInfo(vaultTemplate, "confirmed")).to.equal(null);
+    });
+
+    it("Stores the template", async () => {
+      await setTemplate(admin);
+
+      const template: any = await program.account.vaultTemplate.fetch(vaultTemplate);
+      expect(template.name).to.equal(TEMPLATE_NAME);
+      expect(template.preset.assets.map((a: any) => a.mint.toBase58())).to.deep.equal(
+        [btcMint, ethMint, solMint].map((mint) => mint.toBase58())
+      );
+      expect(template.preset.assets.map((a: any) => a.weight)).to.deep.equal([40, 30, 30]);
+      expect(template.preset.mockOracle.toBase58()).to.equal(mockOracle.toBase58());
+      expect(template.vaultsCreated.toNumber()).to.equal(0);
+    });
+
+    it("Creates a working vault with the template's settings", async () => {
+      const mints = [btcMint, ethMint, solMint];
+      const vault = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault"), admin.publicKey.toBuffer(), Buffer.from(VAULT_NAME)],
+        program.programId
+      )[0];
+      const atas = await Promise.all(mints.map((mint) => getAssociatedTokenAddress(mint, vault, true)));
+
+      await (program.methods as any)
+        .createVaultFromTemplate(VAULT_NAME, TEMPLATE_NAME, false)
+        .accounts({ admin: admin.publicKey, vaultTemplate, token2022Program: null })
+        .remainingAccounts(
+          mints.flatMap((mint, i) => [
+            { pubkey: mint, isWritable: false, isSigner: false },
+            { pubkey: atas[i], isWritable: true, isSigner: false },
+          ])
+        )
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const vaultAccount: any = await program.account.vault.fetch(vault);
+      expect(storedName(vaultAccount)).to.equal(VAULT_NAME);
+      const assets = vaultAssets(vaultAccount);
+      expect(assets.map((a: any) => a.mint.toBase58())).to.deep.equal(mints.map((mint) => mint.toBase58()));
+      expect(assets.map((a: any) => a.weight)).to.deep.equal([40, 30, 30]);
+      expect(assets.map((a: any) => a.ata.toBase58())).to.deep.equal(atas.map((ata) => ata.toBase58()));
+      expect(vaultAccount.priceSource).to.equal(PRICE_SOURCE_MOCK_ORACLE);
+      expect(optionalKey(vaultAccount.mockOracle)!.toBase58()).to.equal(mockOracle.toBase58());
+      expect(vaultAccount.maxSlippageBps).to.equal(300);
+
+      const template: any = await program.account.vaultTemplate.fetch(vaultTemplate);
+      expect(template.vaultsCreated.toNumber()).to.equal(1);
+
+      // The new vault takes deposits like one set up by hand
+      const basket: Basket = {
+        name: VAULT_NAME,
+        admin,
+        vault,
+        shareMint: vaultAccount.vaultTokenMint,
+        shareTokenProgram: TOKEN_PROGRAM_ID,
+        mints,
+        atas,
+        mockOracle,
+      };
+      const mint = await getMint(provider.connection, basket.shareMint, "confirmed");
+      expect(mint.mintAuthority!.toBase58()).to.equal(vault.toBase58());
+      expect(mint.supply).to.equal(BigInt(0));
+
+      await setPrices(program, basket, 100_000, 3_500, 150);
+      const depositor = await fundedUser(program, admin, 0.1);
+      await depositSol(program, basket, depositor, 0.02 * anchor.web3.LAMPORTS_PER_SOL);
+      const shares = await shareBalance(program, basket, depositor.publicKey);
+      expect(shares > BigInt(0)).to.equal(true);
+      expect((await getMint(provider.connection, basket.shareMint, "confirmed")).supply).to.equal(shares);
+    });
+  });
+
+  describe("Wrapped SOL Baskets", () => {
+    let basket: Basket;
+    let successor: Basket;
+    let holder: Keypair;
+    const SOL_WEIGHT = 30;
+
+    const refreshPrices = async () => {
+      await setPrices(program, basket, 100_000, 3_500, 150);
+    };
+
+    const vaultWsol = async () => (await getAccount(provider.connection, basket.atas[2], "confirmed")).amount;
+
+    before(async () => {
+      const stamp = Date.now();
+      basket = await createBasket(program, admin, `Wrapped_${stamp}`, [btcMint, ethMint, NATIVE_MINT], mockOracle);
+      successor = await createBasket(program, admin, `WrappedNext_${stamp}`, [btcMint, ethMint, solMint], mockOracle);
+      await refreshPrices();
+      holder = await fundedUser(program, admin, 0.2);
+
+      await (program.methods as any)
+        .suggestAlternative(basket.name, true)
+        .accounts({ vault: basket.vault, successorVault: successor.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+    });
+
+    it("Wraps the SOL leg of a deposit into the vault's wSOL ATA", async () => {
+      const lamports = 0.1 * anchor.web3.LAMPORTS_PER_SOL;
+      await depositSol(program, basket, holder, lamports);
+
+      // sync_native ran, so the token amount reflects the wrapped lamports
+      const wrapped = await vaultWsol();
+      expect(wrapped > BigInt(0)).to.equal(true);
+      expect(wrapped <= BigInt((lamports * SOL_WEIGHT) / 100)).to.equal(true);
+      expect((await inKindHoldings(program, basket)).nativeSol).to.equal(false);
+    });
+
+    it("Requires a wSOL account to withdraw", async () => {
+      await refreshPrices();
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      try {
+        await withdrawShares(program, basket, holder, shares / BigInt(2));
+        expect.fail("withdraw_multi_asset should need the user's wSOL account");
+      } catch (error: any) {
+        expect(error.message).to.include("WsolAccountRequired");
+      }
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(shares);
+    });
+
+    it("Refuses to migrate a position out of a wrapped SOL vault", async () => {
+      await refreshPrices();
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      try {
+        await migratePosition(program, basket, successor, holder, shares);
+        expect.fail("migrate_position should reject a wrapped SOL vault");
+      } catch (error: any) {
+        expect(error.message).to.include("WrappedSolUnsupported");
+      }
+    });
+
+    it("Unwraps the withdrawn SOL to the user and closes their wSOL account", async () => {
+      await refreshPrices();
+      const userWsol = (
+        await getOrCreateAssociatedTokenAccount(provider.connection, holder, NATIVE_MINT, holder.publicKey)
+      ).address;
+      const wsolRent = (await provider.connection.getAccountInfo(userWsol, "confirmed"))!.lamports;
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      const wrappedBefore = await vaultWsol();
+      const balanceBefore = await provider.connection.getBalance(holder.publicKey, "confirmed");
+
+      await withdrawShares(program, basket, holder, shares / BigInt(2), userWsol);
+
+      // The user is paid what left the vault's wSOL ATA plus their account's rent
+      const unwrapped = wrappedBefore - (await vaultWsol());
+      expect(unwrapped > BigInt(0)).to.equal(true);
+      const balanceAfter = await provider.connection.getBalance(holder.publicKey, "confirmed");
+      expect(balanceAfter - balanceBefore).to.equal(Number(unwrapped) + wsolRent);
+      expect(await provider.connection.getAccountInfo(userWsol, "confirmed")).to.equal(null);
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(shares - shares / BigInt(2));
+    });
+
+    it("Refuses to zap out of a wrapped SOL vault", async () => {
+      // withdraw_zap rejects the Mock backend first, so move to one that swaps
+      await (program.methods as any)
+        .setSwapBackend(basket.name, { mockAmm: {} })
+        .accounts({ vault: basket.vault, authority: admin.publicKey })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      await refreshPrices();
+
+      const shares = await shareBalance(program, basket, holder.publicKey);
+      try {
+        await (program.methods as any)
+          .withdrawZap(basket.name, new BN(shares.toString()), new BN(0))
+          .accounts({
+            vault: basket.vault,
+            user: holder.publicKey,
+            userSharesAta: shareAta(basket, holder.publicKey),
+            userPosition: userPositionPda(program, basket.vault, holder.publicKey),
+            vaultTokenMint: basket.shareMint,
+            outputMint: btcMint,
+            vaultOutputAccount: basket.atas[0],
+            userOutputAccount: await getAssociatedTokenAddress(btcMint, holder.publicKey),
+            btcQuote: PublicKey.default,
+            ethQuote: PublicKey.default,
+            solQuote: PublicKey.default,
+            quoteConfig: null,
+            clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+            shareTokenProgram: basket.shareTokenProgram,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(basketAccounts(basket))
+          .signers([holder])
+          .rpc({ commitment: "confirmed" });
+        expect.fail("withdraw_zap should reject a wrapped SOL vault");
+      } catch (error: any) {
+        expect(error.message).to.include("WrappedSolUnsupported");
+      }
+      expect(await shareBalance(program, basket, holder.publicKey)).to.equal(shares);
+    });
   });
 });